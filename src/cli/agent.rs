@@ -0,0 +1,188 @@
+// src/cli/agent.rs - Companion agent run on the Pi over ssh
+
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::core::image_utils::is_image_file;
+use crate::transfer::agent::{AgentMessage, AgentRequest, AGENT_PROTOCOL_VERSION};
+use crate::transfer::audit::sha256_file;
+
+/// Files between progress messages of long requests
+const PROGRESS_EVERY: usize = 20;
+
+/// Answer one request as JSON lines on stdout; returns the exit code
+///
+/// Failures of single files are reported and skipped, anything else ends
+/// the answer with an error and exit code 1.
+pub fn serve(request: &str) -> i32 {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let result = match serde_json::from_str::<AgentRequest>(request) {
+        Ok(request) => answer(&request, &mut out),
+        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Bad request: {}", e))),
+    };
+
+    match result {
+        Ok(()) => {
+            let _ = send(&mut out, &AgentMessage::Done);
+            0
+        },
+        Err(e) => {
+            let _ = send(&mut out, &AgentMessage::Error { message: e.to_string(), path: None });
+            1
+        },
+    }
+}
+
+fn answer(request: &AgentRequest, out: &mut dyn Write) -> io::Result<()> {
+    match request {
+        AgentRequest::Hello => send(out, &AgentMessage::Hello {
+            protocol: AGENT_PROTOCOL_VERSION,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }),
+        AgentRequest::List { path } => list(path, out),
+        AgentRequest::Checksums { root } => checksums(root, out),
+        AgentRequest::Thumbnails { dir, size, output_dir } => thumbnails(dir, *size, output_dir, out),
+        AgentRequest::ThumbnailFiles { files, size, output_dir } => thumbnail_files(files, *size, output_dir, out),
+    }
+}
+
+/// Write one message as a line, flushed so progress arrives as it happens
+fn send(out: &mut dyn Write, message: &AgentMessage) -> io::Result<()> {
+    let line = serde_json::to_string(message).map_err(io::Error::other)?;
+    writeln!(out, "{}", line)?;
+    out.flush()
+}
+
+fn list(dir: &Path, out: &mut dyn Write) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        // Like the find listing, links are reported as themselves, with their target
+        let Ok(metadata) = entry.metadata() else { continue };
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|age| age.as_secs() as i64);
+        let link_target = match metadata.file_type().is_symlink() {
+            true => fs::read_link(entry.path()).ok().map(|target| target.to_string_lossy().to_string()),
+            false => None,
+        };
+        // A link counts as a directory when what it points to is one
+        let is_dir = match link_target {
+            Some(_) => entry.path().is_dir(),
+            None => metadata.is_dir(),
+        };
+        let file_name = entry.file_name();
+        send(out, &AgentMessage::Entry {
+            name: file_name.to_string_lossy().to_string(),
+            is_dir,
+            size: metadata.len(),
+            modified,
+            link_target,
+            name_bytes: raw_name(&file_name),
+        })?;
+    }
+    Ok(())
+}
+
+// The name's bytes when it isn't UTF-8, so the app can still reach the file
+#[cfg(unix)]
+fn raw_name(name: &OsStr) -> Option<Vec<u8>> {
+    use std::os::unix::ffi::OsStrExt;
+    name.to_str().is_none().then(|| name.as_bytes().to_vec())
+}
+
+#[cfg(not(unix))]
+fn raw_name(_name: &OsStr) -> Option<Vec<u8>> {
+    None
+}
+
+fn checksums(root: &Path, out: &mut dyn Write) -> io::Result<()> {
+    let files = walk(root)?;
+    let total = files.len();
+    for (index, relative) in files.into_iter().enumerate() {
+        match sha256_file(&root.join(&relative)) {
+            Ok(sha256) => send(out, &AgentMessage::Checksum { path: relative, sha256 })?,
+            Err(e) => send(out, &AgentMessage::Error { message: e.to_string(), path: Some(relative) })?,
+        }
+        if (index + 1) % PROGRESS_EVERY == 0 || index + 1 == total {
+            send(out, &AgentMessage::Progress { done: index + 1, total })?;
+        }
+    }
+    Ok(())
+}
+
+fn thumbnails(dir: &Path, size: u32, output_dir: &Path, out: &mut dyn Write) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    let images: Vec<PathBuf> = fs::read_dir(dir)?
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+        .map(|entry| entry.path())
+        .filter(|path| is_image_file(path))
+        .collect();
+
+    // The output keeps the source's extension and so its format
+    let jobs = images
+        .into_iter()
+        .map(|source| {
+            let output = output_dir.join(source.file_name().unwrap_or_default());
+            (source, output)
+        })
+        .collect();
+    shrink_images(jobs, size, out)
+}
+
+fn thumbnail_files(files: &[PathBuf], size: u32, output_dir: &Path, out: &mut dyn Write) -> io::Result<()> {
+    fs::create_dir_all(output_dir)?;
+    // Numbered, as images from different folders may share a name; the
+    // extension is kept so the format is too
+    let jobs = files
+        .iter()
+        .enumerate()
+        .map(|(index, source)| {
+            let extension = source.extension().map(|e| e.to_string_lossy().to_string()).unwrap_or_default();
+            (source.clone(), output_dir.join(format!("{}.{}", index, extension)))
+        })
+        .collect();
+    shrink_images(jobs, size, out)
+}
+
+/// Write a thumbnail of each source to its output, reporting each one
+fn shrink_images(jobs: Vec<(PathBuf, PathBuf)>, size: u32, out: &mut dyn Write) -> io::Result<()> {
+    let total = jobs.len();
+    for (index, (source, output)) in jobs.into_iter().enumerate() {
+        let result = image::open(&source)
+            .map_err(|e| e.to_string())
+            .and_then(|image| image.thumbnail(size, size).save(&output).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => send(out, &AgentMessage::Thumbnail { source, output })?,
+            Err(message) => send(out, &AgentMessage::Error { message, path: Some(source) })?,
+        }
+        send(out, &AgentMessage::Progress { done: index + 1, total })?;
+    }
+    Ok(())
+}
+
+/// Files under `root`, relative to it; links are not followed, as with
+/// `find -type f`
+fn walk(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut folders = vec![PathBuf::new()];
+    while let Some(relative) = folders.pop() {
+        for entry in fs::read_dir(root.join(&relative))?.flatten() {
+            let Ok(kind) = entry.file_type() else { continue };
+            let path = relative.join(entry.file_name());
+            if kind.is_dir() {
+                folders.push(path);
+            } else if kind.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,352 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use crate::cli::{agent, CliCommand};
+use crate::config::{Config, Host, HostAddress, TransferTemplate};
+use crate::core::image::{ProcessingPreset, BUILTIN_PRESETS};
+use crate::core::image_utils::{find_images_in_dir, generate_output_filename, is_image_file, OutputNameContext};
+use crate::core::utils::{AppError, AppResult, log_error};
+use crate::transfer::audit::{audit_folders, AuditStatus};
+use crate::transfer::method::TransferMethod;
+use crate::transfer::{BroadcastJob, Mirror, PathFilter, factory_for_host, reachable_host, run_broadcast};
+use crate::transfer::wake::{send_magic_packet, wait_until_reachable, WAKE_TIMEOUT};
+
+/// Run a command and map the result to a process exit code
+pub fn run(command: CliCommand) -> i32 {
+    let result = match command {
+        CliCommand::Upload { files, host, remote_dir, password_env } =>
+            upload(&files, &host, &remote_dir, &password_env),
+        CliCommand::Download { remote_path, host, local_dir, password_env } =>
+            download(&remote_path, &host, &local_dir, &password_env),
+        CliCommand::Sync { local_dir, host, remote_dir, password_env } =>
+            sync(&local_dir, &host, &remote_dir, &password_env),
+        CliCommand::Audit { local_dir, host, remote_dir, csv, password_env } =>
+            audit(&local_dir, &host, &remote_dir, csv.as_deref(), &password_env),
+        CliCommand::Process { input, preset, output, name } =>
+            process(&input, &preset, output, name),
+        CliCommand::Run { template, delete, dry_run, password_env } =>
+            run_template(&template, delete, dry_run, &password_env),
+        CliCommand::Wake { host, no_wait } => wake(&host, no_wait),
+        CliCommand::Hosts => list_hosts(),
+        // The agent reports its own errors in its answer
+        CliCommand::Agent { request } => return agent::serve(&request),
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            log_error(&err);
+            if let Some(hint) = err.hint() {
+                eprintln!("{}", hint);
+            }
+            1
+        }
+    }
+}
+
+fn load_config() -> AppResult<Config> {
+    Config::load()
+}
+
+fn find_host(config: &Config, name: &str) -> AppResult<Host> {
+    config.hosts
+        .iter()
+        .find(|h| h.name == name || h.hostname == name)
+        .cloned()
+        .ok_or_else(|| AppError::ConfigError(format!(
+            "No saved host named '{}' (see `pi_remote_manager hosts`)",
+            name
+        )))
+}
+
+/// Create a connection to a saved host; passwords come from the environment
+/// so they never show up in shell history or cron files
+fn connect(host_name: &str, password_env: &str) -> AppResult<Box<dyn TransferMethod>> {
+    let config = load_config()?;
+    let host = find_host(&config, host_name)?;
+    connect_host(&host, password_env)
+}
+
+fn connect_host(host: &Host, password_env: &str) -> AppResult<Box<dyn TransferMethod>> {
+    let (address, endpoint) = reachable_host(host)?;
+    if endpoint.hostname != host.hostname {
+        eprintln!("{} is reachable through its {} address {}", host.name, address, endpoint.hostname);
+    }
+    let factory = factory_for_host(&endpoint);
+    let mut method = factory.create_method();
+
+    if !host.use_key_auth {
+        let password = env::var(password_env).map_err(|_| AppError::ConfigError(format!(
+            "Host '{}' uses password authentication; set {} or switch to key authentication",
+            host.name, password_env
+        )))?;
+        method.set_password(&password);
+    }
+
+    Ok(method)
+}
+
+fn upload(files: &[PathBuf], host: &str, remote_dir: &Path, password_env: &str) -> AppResult<()> {
+    let method = connect(host, password_env)?;
+
+    for file in files {
+        let file_name = file.file_name().ok_or_else(|| {
+            AppError::FileError(format!("Not a file: {}", file.display()))
+        })?;
+        let remote_path = remote_dir.join(file_name);
+
+        method.upload_file(file, &remote_path)?;
+        println!("{} -> {}:{}", file.display(), host, remote_path.display());
+    }
+
+    Ok(())
+}
+
+fn download(remote_path: &Path, host: &str, local_dir: &Path, password_env: &str) -> AppResult<()> {
+    let method = connect(host, password_env)?;
+
+    let file_name = remote_path.file_name().ok_or_else(|| {
+        AppError::FileError(format!("Not a file: {}", remote_path.display()))
+    })?;
+    fs::create_dir_all(local_dir)?;
+    let local_path = local_dir.join(file_name);
+
+    method.download_file(remote_path, &local_path)?;
+    println!("{}:{} -> {}", host, remote_path.display(), local_path.display());
+
+    Ok(())
+}
+
+fn sync(local_dir: &Path, host: &str, remote_dir: &Path, password_env: &str) -> AppResult<()> {
+    if !local_dir.is_dir() {
+        return Err(AppError::FileError(format!("Not a directory: {}", local_dir.display())));
+    }
+
+    let method = connect(host, password_env)?;
+    let job = BroadcastJob::UploadTree {
+        local_dir: local_dir.to_path_buf(),
+        remote_dir: remote_dir.to_path_buf(),
+    };
+
+    // A single-device broadcast reuses the recursive upload logic
+    for outcome in run_broadcast(vec![(host.to_string(), method)], &job) {
+        match outcome.result {
+            Ok(message) => println!("{}: {}", outcome.device, message),
+            Err(message) => return Err(AppError::NetworkError(message)),
+        }
+    }
+
+    Ok(())
+}
+
+fn audit(local_dir: &Path, host: &str, remote_dir: &Path, csv: Option<&Path>, password_env: &str) -> AppResult<()> {
+    if !local_dir.is_dir() {
+        return Err(AppError::FileError(format!("Not a directory: {}", local_dir.display())));
+    }
+
+    let method = connect(host, password_env)?;
+    let report = audit_folders(method.as_ref(), local_dir, remote_dir, &PathFilter::default(), &|_| {}, &AtomicBool::new(false))?;
+
+    for entry in report.entries.iter().filter(|entry| entry.status != AuditStatus::Matches) {
+        println!("{:<16} {}", entry.status.label(), entry.path.display());
+    }
+    println!("{} <-> {}:{}: {}", local_dir.display(), host, remote_dir.display(), report.summary());
+
+    if let Some(path) = csv {
+        report.write_csv(path)?;
+        println!("Report written to {}", path.display());
+    }
+
+    if report.is_clean() {
+        Ok(())
+    } else {
+        Err(AppError::FileError("The directories do not match".to_string()))
+    }
+}
+
+fn run_template(name: &str, delete: bool, dry_run: bool, password_env: &str) -> AppResult<()> {
+    let config = load_config()?;
+    let template = config.transfer_templates
+        .iter()
+        .find(|t| t.name == name)
+        .cloned()
+        .ok_or_else(|| AppError::ConfigError(format!(
+            "No transfer template named '{}' (see `pi_remote_manager hosts`)",
+            name
+        )))?;
+    let host = config.template_host(&template)?;
+    let method = connect_host(&host, password_env)?;
+
+    if config.template_walks(&template) {
+        let delete = template.mirror && (template.delete || delete);
+        return run_mirror(method.as_ref(), &template, delete, dry_run, config.remote_trash);
+    }
+
+    let source = PathBuf::from(&template.source);
+    let destination = PathBuf::from(&template.destination);
+    if template.upload {
+        method.upload_file(&source, &destination)?;
+        println!("{} -> {}:{}", source.display(), host.name, destination.display());
+    } else {
+        method.download_file(&source, &destination)?;
+        println!("{}:{} -> {}", host.name, source.display(), destination.display());
+    }
+
+    Ok(())
+}
+
+fn run_mirror(method: &dyn TransferMethod, template: &TransferTemplate, delete: bool, dry_run: bool, to_trash: bool) -> AppResult<()> {
+    let mirror = Mirror::from_template(template);
+    let mut plan = mirror.plan(method)?;
+
+    // Filtered copies leave the destination's other files alone
+    if !template.mirror {
+        plan.deletions.clear();
+    }
+
+    // The review list: what would go is always shown before anything does
+    for path in &plan.deletions {
+        let verb = if delete && !dry_run { "remove" } else { "would remove" };
+        println!("{}: {}", verb, mirror.target.join(path).display());
+    }
+    if dry_run {
+        for path in &plan.copies {
+            println!("would copy: {}", path.display());
+        }
+        println!(
+            "{} to copy, {} unchanged, {} not in the source",
+            plan.copies.len(), plan.unchanged, plan.deletions.len()
+        );
+        return Ok(());
+    }
+
+    let copied = mirror.copy(method, &plan.copies, &|done| {
+        println!("[{}/{}] {}", done, plan.copies.len(), plan.copies[done - 1].display());
+    }, &AtomicBool::new(false))?;
+    let removed = if delete { mirror.delete(method, &plan.deletions, to_trash)? } else { 0 };
+
+    println!(
+        "{} -> {}: {} copied, {} unchanged, {} removed",
+        mirror.source.display(), mirror.target.display(), copied, plan.unchanged, removed
+    );
+    if !delete && !plan.deletions.is_empty() {
+        println!("{} files not in the source were kept; pass --delete to remove them", plan.deletions.len());
+    }
+    Ok(())
+}
+
+fn process(input: &Path, preset_name: &str, output: Option<PathBuf>, name: Option<String>) -> AppResult<()> {
+    let preset = ProcessingPreset::find(preset_name).ok_or_else(|| {
+        let names: Vec<&str> = BUILTIN_PRESETS.iter().map(|p| p.name).collect();
+        AppError::ProcessingError(format!(
+            "Unknown preset '{}' (available: {})",
+            preset_name,
+            names.join(", ")
+        ))
+    })?;
+
+    let (images, default_output) = if input.is_dir() {
+        (find_images_in_dir(input), input.join("processed"))
+    } else if is_image_file(input) {
+        let parent = input.parent().unwrap_or_else(|| Path::new("."));
+        (vec![input.to_path_buf()], parent.join("processed"))
+    } else {
+        return Err(AppError::FileError(format!("No images found at {}", input.display())));
+    };
+
+    let output_dir = output.unwrap_or(default_output);
+    fs::create_dir_all(&output_dir)?;
+
+    let name_template = match name {
+        Some(name) => name,
+        None => load_config()?.output_name_template,
+    };
+    let service = preset.service();
+    let factory_index = preset.factory_index();
+
+    let mut failures = 0;
+    for (index, image) in images.iter().enumerate() {
+        let context = OutputNameContext::new(image, preset.format.clone(), preset.name, index + 1)
+            .with_size(preset.resize);
+        let file_name = generate_output_filename(image, &name_template, &context);
+        let output_path = output_dir.join(file_name.file_name().unwrap_or_default());
+
+        match service.process_image(image, &output_path, factory_index) {
+            Ok(()) => println!("{} -> {}", image.display(), output_path.display()),
+            Err(e) => {
+                eprintln!("{}: {}", image.display(), e);
+                failures += 1;
+            }
+        }
+    }
+
+    println!("Processed {} of {} images with preset '{}'", images.len() - failures, images.len(), preset.name);
+
+    if failures > 0 {
+        Err(AppError::ProcessingError(format!("{} images failed", failures)))
+    } else {
+        Ok(())
+    }
+}
+
+fn wake(host_name: &str, no_wait: bool) -> AppResult<()> {
+    let config = load_config()?;
+    let host = find_host(&config, host_name)?;
+
+    send_magic_packet(&host)?;
+    println!("Sent wake-on-LAN packet to {}", host.name);
+    if no_wait {
+        return Ok(());
+    }
+
+    let (_, endpoint) = wait_until_reachable(&host, WAKE_TIMEOUT)?;
+    println!("{} is up at {}:{}", host.name, endpoint.hostname, endpoint.port);
+    Ok(())
+}
+
+fn list_hosts() -> AppResult<()> {
+    let config = load_config()?;
+
+    println!("Hosts:");
+    for host in &config.hosts {
+        println!(
+            "  {:<20} {}@{}:{} ({})",
+            host.name,
+            host.username,
+            host.hostname,
+            host.port,
+            if host.use_key_auth { "key" } else { "password" }
+        );
+        if !host.fallback_addresses.is_empty() {
+            println!("  {:<20} falls back to {}", "", HostAddress::format_list(&host.fallback_addresses));
+        }
+    }
+
+    if !config.groups.is_empty() {
+        println!("Groups:");
+        for group in &config.groups {
+            println!("  {:<20} {}", group.name, group.members.join(", "));
+        }
+    }
+
+    if !config.transfer_templates.is_empty() {
+        println!("Templates:");
+        for template in &config.transfer_templates {
+            let (from, to) = if template.upload {
+                (template.source.clone(), format!("{}:{}", template.host, template.destination))
+            } else {
+                (format!("{}:{}", template.host, template.source), template.destination.clone())
+            };
+            println!("  {:<20} {} -> {}", template.name, from, to);
+        }
+    }
+
+    println!("Presets:");
+    for preset in BUILTIN_PRESETS {
+        println!("  {:<20} {}", preset.name, preset.description);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+// src/cli/mod.rs - Headless command line interface
+pub mod commands;
+pub mod agent;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+/// Environment variable read for password authentication when none is given
+pub const DEFAULT_PASSWORD_ENV: &str = "PI_REMOTE_MANAGER_PASSWORD";
+
+/// Transfer files and process images without starting the GUI
+#[derive(Parser, Debug)]
+#[command(name = "pi_remote_manager", version, about)]
+pub struct Cli {
+    /// Run headless; implied when a subcommand is given
+    #[arg(long)]
+    pub cli: bool,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CliCommand {
+    /// Upload one or more files to a saved host
+    Upload {
+        /// Local files to upload
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Name of the host in the config file
+        #[arg(long)]
+        host: String,
+        /// Destination directory on the host
+        #[arg(long, default_value = ".")]
+        remote_dir: PathBuf,
+        /// Environment variable holding the SSH password
+        #[arg(long, default_value = DEFAULT_PASSWORD_ENV)]
+        password_env: String,
+    },
+    /// Download a file from a saved host
+    Download {
+        /// Remote file to download
+        remote_path: PathBuf,
+        /// Name of the host in the config file
+        #[arg(long)]
+        host: String,
+        /// Local directory to save into
+        #[arg(long, default_value = ".")]
+        local_dir: PathBuf,
+        /// Environment variable holding the SSH password
+        #[arg(long, default_value = DEFAULT_PASSWORD_ENV)]
+        password_env: String,
+    },
+    /// Copy a local directory tree to a saved host
+    Sync {
+        /// Local directory to copy
+        local_dir: PathBuf,
+        /// Name of the host in the config file
+        #[arg(long)]
+        host: String,
+        /// Destination directory on the host
+        #[arg(long, default_value = ".")]
+        remote_dir: PathBuf,
+        /// Environment variable holding the SSH password
+        #[arg(long, default_value = DEFAULT_PASSWORD_ENV)]
+        password_env: String,
+    },
+    /// Compare checksums of a local directory and its copy on a saved host;
+    /// exits with 1 when any file differs or is missing
+    Audit {
+        /// Local directory to check
+        local_dir: PathBuf,
+        /// Name of the host in the config file
+        #[arg(long)]
+        host: String,
+        /// Directory on the host holding the copy
+        #[arg(long)]
+        remote_dir: PathBuf,
+        /// Also write every file's result to this CSV file
+        #[arg(long)]
+        csv: Option<PathBuf>,
+        /// Environment variable holding the SSH password
+        #[arg(long, default_value = DEFAULT_PASSWORD_ENV)]
+        password_env: String,
+    },
+    /// Process every image in a directory (or a single image) with a preset
+    Process {
+        /// Image file or directory of images
+        input: PathBuf,
+        /// Preset name (thumbs, web, archive)
+        #[arg(long, default_value = "thumbs")]
+        preset: String,
+        /// Output directory; defaults to <input>/processed
+        #[arg(long)]
+        output: Option<PathBuf>,
+        /// Output naming, e.g. "{stem}_{op}_{width}x{height}.{ext}";
+        /// defaults to the template set in the GUI
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Run a transfer template saved from the transfer panel
+    Run {
+        /// Template name
+        template: String,
+        /// Mirror templates: also remove destination files the source no
+        /// longer has, even if the template keeps them
+        #[arg(long)]
+        delete: bool,
+        /// Mirror templates: list what would be copied and removed without
+        /// changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Environment variable holding the SSH password
+        #[arg(long, default_value = DEFAULT_PASSWORD_ENV)]
+        password_env: String,
+    },
+    /// Wake a saved host with a wake-on-LAN packet and wait for ssh to answer
+    Wake {
+        /// Saved host name or hostname
+        host: String,
+        /// Send the packet and return without waiting for the Pi to boot
+        #[arg(long)]
+        no_wait: bool,
+    },
+    /// List saved hosts, groups, templates and presets
+    Hosts,
+    /// Answer one JSON request from the desktop app; run on the Pi over ssh
+    #[command(hide = true)]
+    Agent {
+        request: String,
+    },
+}
+
+/// Parse arguments and run a headless command if one was requested
+///
+/// Returns `None` when the GUI should start, otherwise the process exit code.
+pub fn run_from_args() -> Option<i32> {
+    // Plain launches (e.g. from a desktop icon) go straight to the GUI
+    if std::env::args_os().len() <= 1 {
+        return None;
+    }
+
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(command) => Some(commands::run(command)),
+        None if cli.cli => {
+            eprintln!("No command given; run with --help to see available commands");
+            Some(2)
+        },
+        None => None,
+    }
+}
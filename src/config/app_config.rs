@@ -2,11 +2,16 @@
 
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io;
-use std::error::Error;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
 use directories::ProjectDirs;
 
+use crate::core::utils::{AppError, AppResult, DEFAULT_OUTPUT_NAME_TEMPLATE};
+use crate::transfer::filter::PathFilter;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Host {
     pub name: String,
@@ -15,6 +20,25 @@ pub struct Host {
     pub port: u16,
     pub use_key_auth: bool,
     pub key_path: Option<String>,
+    /// Remote directory to open on connect instead of the home directory
+    #[serde(default)]
+    pub default_remote_dir: Option<String>,
+    /// Remote directory browsed most recently, reopened on the next connect
+    #[serde(default)]
+    pub last_remote_dir: Option<String>,
+    /// Jump host(s) to reach this host through, as for ssh's `-J`
+    #[serde(default)]
+    pub proxy_jump: Option<String>,
+    /// How files are copied to and from this host
+    #[serde(default)]
+    pub transfer: TransferOptions,
+    /// Other addresses of the same Pi, such as a VPN or DDNS name, tried in
+    /// order when `hostname` does not answer
+    #[serde(default)]
+    pub fallback_addresses: Vec<HostAddress>,
+    /// Network card address for waking the Pi with a wake-on-LAN packet
+    #[serde(default)]
+    pub mac_address: Option<String>,
 }
 
 impl Default for Host {
@@ -26,6 +50,512 @@ impl Default for Host {
             port: 22,
             use_key_auth: true,
             key_path: None,
+            default_remote_dir: None,
+            last_remote_dir: None,
+            proxy_jump: None,
+            transfer: TransferOptions::default(),
+            fallback_addresses: Vec::new(),
+            mac_address: None,
+        }
+    }
+}
+
+impl Host {
+    /// Remote directory to open on connect: where the host was last browsed,
+    /// else its configured start directory, else the user's home
+    pub fn start_directory(&self) -> PathBuf {
+        self.last_remote_dir
+            .as_ref()
+            .or(self.default_remote_dir.as_ref())
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(format!("/home/{}", self.username)))
+    }
+
+    /// This host at each of its addresses in the order they are tried:
+    /// `hostname` first, then the fallbacks
+    pub fn address_candidates(&self) -> Vec<(String, Host)> {
+        let mut candidates = vec![("primary".to_string(), self.clone())];
+        for address in &self.fallback_addresses {
+            let mut host = self.clone();
+            host.hostname = address.hostname.clone();
+            host.port = address.port.unwrap_or(self.port);
+            candidates.push((address.label(), host));
+        }
+        candidates
+    }
+}
+
+/// Another way to reach a host, e.g. its Tailscale address or public DDNS
+/// name when away from the home network
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HostAddress {
+    /// Shown when connecting through this address, e.g. "tailscale"
+    #[serde(default)]
+    pub name: String,
+    pub hostname: String,
+    /// Port to use instead of the host's, e.g. a forwarded router port
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+impl HostAddress {
+    pub fn label(&self) -> String {
+        if self.name.is_empty() { self.hostname.clone() } else { self.name.clone() }
+    }
+
+    /// Read a comma-separated list such as
+    /// "tailscale=100.64.0.5, ddns=mypi.example.org:2222"
+    pub fn parse_list(text: &str) -> Result<Vec<HostAddress>, String> {
+        text.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                let (name, address) = match item.split_once('=') {
+                    Some((name, address)) => (name.trim().to_string(), address.trim()),
+                    None => (String::new(), item),
+                };
+                let (hostname, port) = match address.rsplit_once(':') {
+                    // A bare IPv6 address has colons but no port
+                    Some((hostname, port)) if !hostname.contains(':') => {
+                        let port = port.parse::<u16>().map_err(|_| format!("Bad port in '{}'", item))?;
+                        (hostname.to_string(), Some(port))
+                    },
+                    _ => (address.to_string(), None),
+                };
+                if hostname.is_empty() {
+                    return Err(format!("No address in '{}'", item));
+                }
+                Ok(HostAddress { name, hostname, port })
+            })
+            .collect()
+    }
+
+    /// The inverse of [`HostAddress::parse_list`]
+    pub fn format_list(addresses: &[HostAddress]) -> String {
+        addresses
+            .iter()
+            .map(|address| {
+                let mut text = if address.name.is_empty() {
+                    address.hostname.clone()
+                } else {
+                    format!("{}={}", address.name, address.hostname)
+                };
+                if let Some(port) = address.port {
+                    text.push_str(&format!(":{}", port));
+                }
+                text
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Program used to copy files to and from a host
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferProtocol {
+    #[default]
+    Scp,
+    /// scp over the SFTP protocol (`scp -s`, OpenSSH 8.7 or newer)
+    Sftp,
+    Rsync,
+}
+
+impl TransferProtocol {
+    /// Every protocol, in the order they are offered
+    pub const ALL: [TransferProtocol; 3] = [Self::Scp, Self::Sftp, Self::Rsync];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Scp => "SCP",
+            Self::Sftp => "SFTP",
+            Self::Rsync => "Rsync",
+        }
+    }
+    
+    /// Position in [`TransferProtocol::ALL`], for choice widgets
+    pub fn index(&self) -> usize {
+        Self::ALL.iter().position(|p| p == self).unwrap_or(0)
+    }
+}
+
+/// Per-host tuning of file copies, so a Pi Zero on Wi-Fi and a Pi 5 on
+/// Ethernet can be treated differently
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct TransferOptions {
+    pub protocol: TransferProtocol,
+    /// Extra rsync arguments such as "--bwlimit=500"
+    pub rsync_args: Vec<String>,
+    /// Compress data in transit; None keeps each protocol's default
+    /// (rsync compresses, scp does not)
+    pub compression: Option<bool>,
+    /// Copies to or from this host that may run at the same time
+    pub concurrency: usize,
+    /// How copies and listings that fail on a flaky link are tried again
+    pub retry: RetryPolicy,
+    /// List, checksum and make thumbnails through the companion agent
+    /// when it is installed on the Pi
+    pub use_agent: bool,
+    /// Id of the transfer plugin copying files instead of `protocol`
+    pub plugin: Option<String>,
+    /// Offer to list and download again through `sudo` after the Pi
+    /// refused access, with the login password for `sudo -S`
+    pub sudo_fallback: bool,
+    /// Keep symbolic links in copied folders and archives as links instead
+    /// of copying what they point to; scp always copies the targets
+    pub preserve_symlinks: bool,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self {
+            protocol: TransferProtocol::Scp,
+            rsync_args: Vec::new(),
+            compression: None,
+            concurrency: 1,
+            retry: RetryPolicy::default(),
+            use_agent: true,
+            plugin: None,
+            sudo_fallback: false,
+            preserve_symlinks: true,
+        }
+    }
+}
+
+/// Automatic retries of copies and listings that failed for a reason that
+/// may go away, such as a timeout or a dropped connection
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Tries after the first one; 0 never retries
+    pub retries: u32,
+    /// Wait before the first retry, doubled for each one after it
+    pub initial_backoff_ms: u64,
+    /// Longest wait between two tries
+    pub max_backoff_ms: u64,
+    /// Wait a random 50-100% of the backoff so several copies that failed
+    /// together don't all retry at the same moment
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 2,
+            initial_backoff_ms: 1000,
+            max_backoff_ms: 30_000,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before retry number `retry` (1 for the first retry)
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let doubled = self.initial_backoff_ms.saturating_mul(1 << retry.saturating_sub(1).min(16));
+        let millis = doubled.min(self.max_backoff_ms.max(self.initial_backoff_ms));
+        if !self.jitter {
+            return Duration::from_millis(millis);
+        }
+        // Any varying bits will do; every RandomState hashes differently
+        let random = RandomState::new().build_hasher().finish() ^ retry as u64;
+        Duration::from_millis(millis / 2 + random % (millis / 2 + 1))
+    }
+}
+
+/// A named transfer that can be run again, e.g. "Pull last night's time-lapse"
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TransferTemplate {
+    pub name: String,
+    /// Name of the saved host the transfer goes to or comes from
+    pub host: String,
+    pub source: String,
+    pub destination: String,
+    /// True for local to Pi, false for Pi to local
+    pub upload: bool,
+    /// Transfer options to use instead of the host's own
+    #[serde(default)]
+    pub options: Option<TransferOptions>,
+    /// Make the destination folder match the source folder, copying only
+    /// new and changed files
+    #[serde(default)]
+    pub mirror: bool,
+    /// When mirroring, also remove destination files the source no longer
+    /// has, after they have been reviewed
+    #[serde(default)]
+    pub delete: bool,
+    /// Glob patterns, e.g. "*.jpg", of the only files transferred; empty
+    /// transfers every file
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Glob patterns, e.g. "*.tmp" or "lost+found/", of files and folders
+    /// never transferred or removed
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+/// Image processing run on every file downloaded from the Pi
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PostDownloadProcessing {
+    pub enabled: bool,
+    /// Built-in preset to run; None runs the operations set up in the
+    /// Image Processing tab
+    pub preset: Option<String>,
+    /// Folder the results go to; None writes them next to the download
+    pub output_dir: Option<String>,
+}
+
+/// Shrinking of images before the transfer panel uploads them
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct UploadOptimization {
+    pub enabled: bool,
+    /// Longest side, in pixels, of an uploaded image
+    pub max_dimension: u32,
+    /// Quality (1-100) JPEGs are re-encoded at
+    pub jpeg_quality: u8,
+}
+
+impl Default for UploadOptimization {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_dimension: 1920,
+            jpeg_quality: 85,
+        }
+    }
+}
+
+/// Camera stream shown in the Live View tab
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct LiveViewSettings {
+    pub url: String,
+    /// The URL returns one picture per request instead of an MJPEG stream
+    pub snapshots: bool,
+    /// Fetch the URL on the Pi over ssh rather than from this computer
+    pub through_ssh: bool,
+    /// Seconds between snapshots
+    pub snapshot_interval_secs: f64,
+}
+
+impl Default for LiveViewSettings {
+    fn default() -> Self {
+        Self {
+            // picamera2's mjpeg_server example
+            url: "http://localhost:8000/stream.mjpg".to_string(),
+            snapshots: false,
+            through_ssh: true,
+            snapshot_interval_secs: 1.0,
+        }
+    }
+}
+
+/// How the file lists react to the mouse
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct InteractionSettings {
+    /// A single click only selects; opening a file or folder takes a double
+    /// click, as in desktop file managers
+    pub double_click_to_open: bool,
+    /// Show a thumbnail of the image under the mouse
+    pub hover_preview: bool,
+    /// Milliseconds the mouse rests on an image before its thumbnail shows
+    pub hover_delay_ms: u64,
+}
+
+impl Default for InteractionSettings {
+    fn default() -> Self {
+        Self {
+            double_click_to_open: true,
+            hover_preview: false,
+            hover_delay_ms: 700,
+        }
+    }
+}
+
+/// Encoder settings of the Image Processing tab's processors, changed with
+/// their Settings button
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ProcessorSettings {
+    /// JPEG quality, 1-100
+    pub jpeg_quality: u8,
+    /// PNG compression, 0 (fastest) to 9 (smallest)
+    pub png_compression: u8,
+}
+
+impl Default for ProcessorSettings {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: 85,
+            png_compression: 6,
+        }
+    }
+}
+
+/// How much the caches and temporary files may grow; enforced in the
+/// background while the app runs
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Megabytes of the Pi's files kept downloaded for previews; the least
+    /// recently used are dropped beyond it
+    pub preview_cache_mb: u64,
+    /// Megabytes of prefetched thumbnails kept, oldest dropped first
+    pub thumbnail_cache_mb: u64,
+    /// Temporary files, such as files downloaded for opening, are deleted
+    /// after this many hours
+    pub temp_file_hours: u64,
+    /// Minutes between checks of the limits above
+    pub cleanup_minutes: u64,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            preview_cache_mb: 500,
+            thumbnail_cache_mb: 100,
+            temp_file_hours: 24,
+            cleanup_minutes: 30,
+        }
+    }
+}
+
+/// How large and in which colours the UI is drawn
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct Appearance {
+    /// Factor on top of the scaling FLTK picks for the screen, e.g. 1.5
+    /// for HiDPI screens it does not recognise
+    pub ui_scale: f32,
+    /// Size of ordinary text in points; smaller and larger text keeps its
+    /// proportion to it
+    pub font_size: i32,
+    /// White on black with yellow selections, for low-vision users
+    pub high_contrast: bool,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            font_size: 14,
+            high_contrast: false,
+        }
+    }
+}
+
+/// Embedded HTTP API through which home automation can start transfers,
+/// syncs and processing
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct ControlApiSettings {
+    pub enabled: bool,
+    /// Address to listen on; the default only accepts this computer
+    pub bind_address: String,
+    pub port: u16,
+    /// Clients must send `Authorization: Bearer <token>`; the API stays off
+    /// until one is set
+    pub token: String,
+}
+
+impl Default for ControlApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8765,
+            token: String::new(),
+        }
+    }
+}
+
+/// Where the results of a preset go besides its output folder
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OutputDestination {
+    /// Only the output folder on this computer
+    #[default]
+    Local,
+    /// Also copied into `remote_dir` on the saved host named `host`
+    Pi { host: String, remote_dir: String },
+    /// Also uploaded into `folder` of the linked Dropbox account
+    Dropbox { folder: String },
+}
+
+impl OutputDestination {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Local => "Output folder only".to_string(),
+            Self::Pi { host, remote_dir } => format!("{}:{}", host, remote_dir),
+            Self::Dropbox { folder } => format!("Dropbox {}", folder),
+        }
+    }
+}
+
+/// Destination chosen for the results of one preset
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PresetDestination {
+    pub preset: String,
+    pub destination: OutputDestination,
+}
+
+/// Dropbox account processed images can be uploaded to
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct DropboxSettings {
+    /// App key of a Dropbox app registered by the user; the app needs the
+    /// `files.content.write` permission
+    pub app_key: String,
+    /// Long-lived token from linking the account; None until linked
+    pub refresh_token: Option<String>,
+}
+
+/// A named set of hosts that jobs can be broadcast to
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct HostGroup {
+    pub name: String,
+    /// Names of the member hosts
+    pub members: Vec<String>,
+}
+
+/// A favourite directory offered in a browser pane's bookmark menu
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Bookmark {
+    /// Directory path; a leading `~` in local bookmarks means the home directory
+    pub path: String,
+    /// Hostname of the Pi the directory is on, None for a local directory
+    #[serde(default)]
+    pub host: Option<String>,
+}
+
+/// Keyboard shortcuts of the file browser actions, written like "F5",
+/// "Delete" or "Ctrl+Shift+K"
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub refresh: String,
+    /// Copy the selected entry to the other pane
+    pub transfer: String,
+    pub delete: String,
+    pub rename: String,
+    /// Move the keyboard focus between the local and remote panes
+    pub switch_pane: String,
+    pub quick_connect: String,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            refresh: "F5".to_string(),
+            transfer: "F6".to_string(),
+            delete: "Delete".to_string(),
+            rename: "F2".to_string(),
+            switch_pane: "Tab".to_string(),
+            quick_connect: "Ctrl+K".to_string(),
         }
     }
 }
@@ -38,6 +568,134 @@ pub struct Config {
     pub hosts: Vec<Host>,
     pub last_used_host_index: usize,
     pub image_formats: Vec<String>,
+    /// Seconds between automatic refreshes of the Device tab
+    #[serde(default = "default_dashboard_refresh_secs")]
+    pub dashboard_refresh_secs: u32,
+    /// Device groups for broadcast transfers
+    #[serde(default)]
+    pub groups: Vec<HostGroup>,
+    /// Seconds a remote directory listing may take before it is abandoned
+    #[serde(default = "default_listing_timeout_secs")]
+    pub listing_timeout_secs: u64,
+    /// Seconds a remote listing is reused when navigating; Refresh always lists afresh
+    #[serde(default = "default_listing_cache_ttl_secs")]
+    pub listing_cache_ttl_secs: u64,
+    /// Favourite local and remote directories
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// Also show toasts as desktop notifications (needs the
+    /// `desktop-notifications` build feature)
+    #[serde(default)]
+    pub desktop_notifications: bool,
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+    /// Window position at the last exit; None lets the window manager place it
+    #[serde(default)]
+    pub window_x: Option<i32>,
+    #[serde(default)]
+    pub window_y: Option<i32>,
+    /// Share of the window width taken by the local pane
+    #[serde(default = "default_browser_split")]
+    pub browser_split: f64,
+    /// Share of the File Browser tab taken by the panes above the transfer panel
+    #[serde(default = "default_transfer_split")]
+    pub transfer_split: f64,
+    /// Label of the tab that was open at the last exit
+    #[serde(default)]
+    pub last_tab: Option<String>,
+    /// Reopen the last directories, connection and preview on startup
+    #[serde(default)]
+    pub restore_session: bool,
+    /// Local pane directory at the last exit
+    #[serde(default)]
+    pub last_local_dir: Option<String>,
+    /// File previewed last, and whether it is on the Pi
+    #[serde(default)]
+    pub last_preview: Option<String>,
+    #[serde(default)]
+    pub last_preview_remote: bool,
+    /// Seconds between keepalive checks of the connected Pi; 0 turns them off
+    #[serde(default = "default_health_check_secs")]
+    pub health_check_secs: u64,
+    /// Saved transfer jobs
+    #[serde(default)]
+    pub transfer_templates: Vec<TransferTemplate>,
+    #[serde(default)]
+    pub post_download: PostDownloadProcessing,
+    #[serde(default)]
+    pub upload_optimization: UploadOptimization,
+    /// How processed images are named; see `OUTPUT_NAME_TOKENS`
+    #[serde(default = "default_output_name_template")]
+    pub output_name_template: String,
+    /// Leave out files the other pane already has with the same size and
+    /// an equal or newer modification time
+    #[serde(default = "default_skip_identical_transfers")]
+    pub skip_identical_transfers: bool,
+    /// Move files deleted on the Pi into its trash folder instead of
+    /// removing them
+    #[serde(default = "default_remote_trash")]
+    pub remote_trash: bool,
+    #[serde(default)]
+    pub live_view: LiveViewSettings,
+    #[serde(default)]
+    pub control_api: ControlApiSettings,
+    /// Rhai script whose hook functions run on connections, downloads and
+    /// finished processing jobs; None uses `hooks.rhai` next to the config
+    /// file when it exists
+    #[serde(default)]
+    pub hooks_script: Option<String>,
+    /// Where each preset's results are sent; presets not listed stay local
+    #[serde(default)]
+    pub preset_destinations: Vec<PresetDestination>,
+    #[serde(default)]
+    pub dropbox: DropboxSettings,
+    /// Language code of the UI, e.g. "de"; None follows the system locale
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub appearance: Appearance,
+    #[serde(default)]
+    pub interaction: InteractionSettings,
+    #[serde(default)]
+    pub storage: StorageSettings,
+    #[serde(default)]
+    pub processors: ProcessorSettings,
+}
+
+fn default_dashboard_refresh_secs() -> u32 {
+    10
+}
+
+fn default_listing_timeout_secs() -> u64 {
+    15
+}
+
+fn default_listing_cache_ttl_secs() -> u64 {
+    30
+}
+
+fn default_health_check_secs() -> u64 {
+    30
+}
+
+fn default_output_name_template() -> String {
+    DEFAULT_OUTPUT_NAME_TEMPLATE.to_string()
+}
+
+fn default_skip_identical_transfers() -> bool {
+    true
+}
+
+fn default_remote_trash() -> bool {
+    true
+}
+
+fn default_browser_split() -> f64 {
+    0.5
+}
+
+fn default_transfer_split() -> f64 {
+    0.8
 }
 
 impl Default for Config {
@@ -60,13 +718,46 @@ impl Default for Config {
                 "tiff".to_string(),
                 "webp".to_string(),
             ],
+            dashboard_refresh_secs: default_dashboard_refresh_secs(),
+            groups: Vec::new(),
+            listing_timeout_secs: default_listing_timeout_secs(),
+            listing_cache_ttl_secs: default_listing_cache_ttl_secs(),
+            bookmarks: Vec::new(),
+            desktop_notifications: false,
+            key_bindings: KeyBindings::default(),
+            window_x: None,
+            window_y: None,
+            browser_split: default_browser_split(),
+            transfer_split: default_transfer_split(),
+            last_tab: None,
+            restore_session: false,
+            last_local_dir: None,
+            last_preview: None,
+            last_preview_remote: false,
+            health_check_secs: default_health_check_secs(),
+            transfer_templates: Vec::new(),
+            post_download: PostDownloadProcessing::default(),
+            upload_optimization: UploadOptimization::default(),
+            output_name_template: default_output_name_template(),
+            skip_identical_transfers: default_skip_identical_transfers(),
+            remote_trash: default_remote_trash(),
+            live_view: LiveViewSettings::default(),
+            control_api: ControlApiSettings::default(),
+            hooks_script: None,
+            preset_destinations: Vec::new(),
+            dropbox: DropboxSettings::default(),
+            language: None,
+            appearance: Appearance::default(),
+            interaction: InteractionSettings::default(),
+            storage: StorageSettings::default(),
+            processors: ProcessorSettings::default(),
         }
     }
 }
 
 impl Config {
     /// Load configuration from file
-    pub fn load() -> Result<Self, Box<dyn Error>> {
+    pub fn load() -> AppResult<Self> {
         let config_path = Self::get_config_path()?;
         
         if !config_path.exists() {
@@ -74,13 +765,15 @@ impl Config {
         }
         
         let config_str = fs::read_to_string(&config_path)?;
-        let config = serde_json::from_str(&config_str)?;
+        let config = serde_json::from_str(&config_str).map_err(|e| AppError::ConfigError(
+            format!("{} is not valid: {}", config_path.display(), e)
+        ))?;
         
         Ok(config)
     }
     
     /// Save configuration to file
-    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+    pub fn save(&self) -> AppResult<()> {
         let config_path = Self::get_config_path()?;
         
         // Create parent directories if they don't exist
@@ -88,12 +781,205 @@ impl Config {
             fs::create_dir_all(parent)?;
         }
         
-        let config_str = serde_json::to_string_pretty(self)?;
+        let config_str = serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
         fs::write(&config_path, config_str)?;
         
         Ok(())
     }
     
+    /// Write the settings to `path` as JSON for use on another machine
+    ///
+    /// Unless `include_private` is set, key file paths and what belongs to
+    /// this machine only (window placement, last directories and preview)
+    /// are left out; hosts without a key path fall back to ssh's default keys.
+    /// The control API token and the Dropbox refresh token are never written.
+    pub fn export_to(&self, path: &Path, include_private: bool) -> AppResult<()> {
+        let mut exported = self.clone();
+        exported.control_api.token.clear();
+        exported.dropbox.refresh_token = None;
+        if !include_private {
+            exported.strip_private();
+        }
+        
+        let config_str = serde_json::to_string_pretty(&exported)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        fs::write(path, config_str)?;
+        
+        Ok(())
+    }
+    
+    /// Read settings written by [`Config::export_to`]
+    pub fn import_from(path: &Path) -> AppResult<Self> {
+        let config_str = fs::read_to_string(path)?;
+        serde_json::from_str(&config_str).map_err(|e| AppError::ConfigError(
+            format!("{} is not a settings file: {}", path.display(), e)
+        ))
+    }
+    
+    /// Add the hosts and groups of `other` whose names are not in use yet;
+    /// returns how many hosts were added
+    pub fn merge_hosts(&mut self, other: &Config) -> usize {
+        let before = self.hosts.len();
+        for host in &other.hosts {
+            if !self.hosts.iter().any(|h| h.name == host.name) {
+                self.hosts.push(host.clone());
+            }
+        }
+        for group in &other.groups {
+            if !self.groups.iter().any(|g| g.name == group.name) {
+                self.groups.push(group.clone());
+            }
+        }
+        self.hosts.len() - before
+    }
+    
+    /// Forget key paths and per-machine state
+    fn strip_private(&mut self) {
+        for host in &mut self.hosts {
+            host.key_path = None;
+            host.last_remote_dir = None;
+        }
+        self.window_x = None;
+        self.window_y = None;
+        self.last_tab = None;
+        self.last_local_dir = None;
+        self.last_preview = None;
+        self.last_preview_remote = false;
+        self.default_local_dir = Self::default().default_local_dir;
+        self.post_download.output_dir = None;
+    }
+    
+    /// Take on all of `imported`'s settings except the control API and the
+    /// hook script, which stay as they are here so a shared file can't open
+    /// a listening port or run a script on this machine
+    pub fn replace_with(&mut self, imported: Config) {
+        let control_api = std::mem::take(&mut self.control_api);
+        let hooks_script = self.hooks_script.take();
+        *self = imported;
+        self.control_api = control_api;
+        self.hooks_script = hooks_script;
+    }
+    
+    /// Hosts belonging to a group, in the group's member order
+    pub fn group_hosts(&self, group_name: &str) -> Vec<Host> {
+        self.groups
+            .iter()
+            .find(|g| g.name == group_name)
+            .map(|group| {
+                group.members
+                    .iter()
+                    .filter_map(|member| self.hosts.iter().find(|h| &h.name == member).cloned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    
+    /// Whether a template runs through the mirror walker: mirrors always
+    /// do, and filtered copies do on hosts whose protocol cannot filter
+    pub fn template_walks(&self, template: &TransferTemplate) -> bool {
+        template.mirror
+            || (!PathFilter::from_template(template).is_empty()
+                && self.template_host(template).is_ok_and(|host| host.transfer.protocol != TransferProtocol::Rsync))
+    }
+    
+    /// Save `template`, replacing a template of the same name
+    pub fn save_template(&mut self, template: TransferTemplate) {
+        match self.transfer_templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => self.transfer_templates.push(template),
+        }
+    }
+    
+    /// The host a template runs against, with the template's transfer
+    /// options applied
+    pub fn template_host(&self, template: &TransferTemplate) -> AppResult<Host> {
+        let mut host = self.hosts
+            .iter()
+            .find(|h| h.name == template.host)
+            .cloned()
+            .ok_or_else(|| AppError::ConfigError(format!(
+                "Template '{}' uses host '{}', which is no longer saved",
+                template.name, template.host
+            )))?;
+        
+        if let Some(options) = &template.options {
+            host.transfer = options.clone();
+        }
+        // rsync applies the template's filters itself
+        if host.transfer.protocol == TransferProtocol::Rsync {
+            host.transfer.rsync_args.extend(PathFilter::from_template(template).rsync_args());
+        }
+        Ok(host)
+    }
+    
+    /// Where the results of the preset named `preset` go
+    pub fn preset_destination(&self, preset: &str) -> OutputDestination {
+        self.preset_destinations
+            .iter()
+            .find(|entry| entry.preset.eq_ignore_ascii_case(preset))
+            .map(|entry| entry.destination.clone())
+            .unwrap_or_default()
+    }
+    
+    /// Send the results of `preset` to `destination` from now on
+    pub fn set_preset_destination(&mut self, preset: &str, destination: OutputDestination) {
+        self.preset_destinations.retain(|entry| !entry.preset.eq_ignore_ascii_case(preset));
+        if destination != OutputDestination::Local {
+            self.preset_destinations.push(PresetDestination { preset: preset.to_string(), destination });
+        }
+    }
+    
+    /// Remember `dir` as the last directory browsed on the host with this
+    /// hostname; returns whether anything changed
+    pub fn record_last_remote_dir(&mut self, hostname: &str, dir: &str) -> bool {
+        let mut changed = false;
+        for host in self.hosts.iter_mut().filter(|h| h.hostname == hostname) {
+            if host.last_remote_dir.as_deref() != Some(dir) {
+                host.last_remote_dir = Some(dir.to_string());
+                changed = true;
+            }
+        }
+        changed
+    }
+    
+    /// Bookmarks of one Pi, or the local ones for None, in the order they were added
+    pub fn bookmarks_for(&self, host: Option<&str>) -> Vec<Bookmark> {
+        self.bookmarks
+            .iter()
+            .filter(|b| b.host.as_deref() == host)
+            .cloned()
+            .collect()
+    }
+    
+    /// Bookmark `path`, or remove the bookmark if it exists; returns whether
+    /// the path is bookmarked afterwards
+    pub fn toggle_bookmark(&mut self, path: &str, host: Option<&str>) -> bool {
+        let before = self.bookmarks.len();
+        self.bookmarks.retain(|b| !(b.path == path && b.host.as_deref() == host));
+        
+        if self.bookmarks.len() == before {
+            self.bookmarks.push(Bookmark {
+                path: path.to_string(),
+                host: host.map(|h| h.to_string()),
+            });
+            true
+        } else {
+            false
+        }
+    }
+    
+    /// Hook script to run, if any
+    pub fn hooks_script_path(&self) -> Option<PathBuf> {
+        match &self.hooks_script {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Self::get_config_path()
+                .ok()
+                .and_then(|path| path.parent().map(|dir| dir.join("hooks.rhai")))
+                .filter(|path| path.is_file()),
+        }
+    }
+    
     /// Get the path to the configuration file
     fn get_config_path() -> Result<PathBuf, io::Error> {
         let proj_dirs = ProjectDirs::from("com", "PiImageProcessor", "piimgproc")
@@ -1,3 +1,4 @@
 mod app_config;
+pub mod ssh_import;
 
-pub use app_config::{Config, Host};
\ No newline at end of file
+pub use app_config::{Appearance, Bookmark, Config, ControlApiSettings, DropboxSettings, Host, HostAddress, HostGroup, InteractionSettings, KeyBindings, LiveViewSettings, OutputDestination, PostDownloadProcessing, PresetDestination, ProcessorSettings, RetryPolicy, StorageSettings, TransferOptions, TransferProtocol, TransferTemplate, UploadOptimization};
\ No newline at end of file
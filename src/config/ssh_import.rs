@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Host;
+use crate::core::utils::{glob_matches_ignore_case, AppError, AppResult};
+
+/// Options of one `Host` block in an OpenSSH config
+#[derive(Debug, Clone, Default)]
+struct HostBlock {
+    patterns: Vec<String>,
+    hostname: Option<String>,
+    user: Option<String>,
+    port: Option<u16>,
+    identity_file: Option<String>,
+    proxy_jump: Option<String>,
+}
+
+/// The user's OpenSSH client config, `~/.ssh/config`
+pub fn default_ssh_config_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("config"))
+}
+
+/// Read `path` and return a host for every alias it names
+///
+/// See [`parse_ssh_config`].
+pub fn read_ssh_config(path: &Path) -> AppResult<Vec<Host>> {
+    let text = fs::read_to_string(path).map_err(|e| {
+        AppError::ConfigError(format!("Could not read {}: {}", path.display(), e))
+    })?;
+    Ok(parse_ssh_config(&text))
+}
+
+/// Turn the concrete aliases of an OpenSSH config into hosts
+///
+/// Understands Host, HostName, User, Port, IdentityFile and ProxyJump.
+/// Like ssh, the first value found for an option wins, so wildcard blocks
+/// such as `Host *` fill in whatever the alias's own block leaves out.
+/// Wildcard and negated patterns are never imported themselves, and
+/// `Match` blocks are skipped.
+pub fn parse_ssh_config(text: &str) -> Vec<Host> {
+    let blocks = parse_blocks(text);
+
+    let mut aliases: Vec<&str> = Vec::new();
+    for block in &blocks {
+        for pattern in &block.patterns {
+            let concrete = !pattern.contains(['*', '?', '!']);
+            if concrete && !aliases.contains(&pattern.as_str()) {
+                aliases.push(pattern);
+            }
+        }
+    }
+
+    aliases
+        .into_iter()
+        .map(|alias| {
+            let mut merged = HostBlock::default();
+            for block in blocks.iter().filter(|block| block_matches(block, alias)) {
+                merged.hostname = merged.hostname.or_else(|| block.hostname.clone());
+                merged.user = merged.user.or_else(|| block.user.clone());
+                merged.port = merged.port.or(block.port);
+                merged.identity_file = merged.identity_file.or_else(|| block.identity_file.clone());
+                merged.proxy_jump = merged.proxy_jump.or_else(|| block.proxy_jump.clone());
+            }
+
+            Host {
+                name: alias.to_string(),
+                hostname: merged.hostname.map(|h| h.replace("%h", alias)).unwrap_or_else(|| alias.to_string()),
+                username: merged.user
+                    .or_else(|| std::env::var("USER").ok())
+                    .unwrap_or_else(|| Host::default().username),
+                port: merged.port.unwrap_or(22),
+                // Without an IdentityFile ssh still tries the default keys
+                use_key_auth: true,
+                key_path: merged.identity_file.map(|path| expand_home(&path)),
+                proxy_jump: merged.proxy_jump.filter(|jump| !jump.eq_ignore_ascii_case("none")),
+                ..Host::default()
+            }
+        })
+        .collect()
+}
+
+/// Hosts from `imported` not already saved under the same name or as the
+/// same user, address and port
+pub fn new_hosts(imported: Vec<Host>, existing: &[Host]) -> Vec<Host> {
+    imported
+        .into_iter()
+        .filter(|host| {
+            !existing.iter().any(|saved| {
+                saved.name == host.name
+                    || (saved.hostname == host.hostname && saved.username == host.username && saved.port == host.port)
+            })
+        })
+        .collect()
+}
+
+fn parse_blocks(text: &str) -> Vec<HostBlock> {
+    // Options before the first Host line apply to every host
+    let mut blocks = vec![HostBlock { patterns: vec!["*".to_string()], ..HostBlock::default() }];
+    let mut in_match = false;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Keywords are separated from values by whitespace or '='
+        let (keyword, value) = match line.find(|c: char| c.is_whitespace() || c == '=') {
+            Some(split) => (&line[..split], line[split..].trim_start_matches(|c: char| c.is_whitespace() || c == '=')),
+            None => (line, ""),
+        };
+        let value = value.trim().trim_matches('"').to_string();
+
+        match keyword.to_lowercase().as_str() {
+            "host" => {
+                in_match = false;
+                blocks.push(HostBlock {
+                    patterns: value.split_whitespace().map(str::to_string).collect(),
+                    ..HostBlock::default()
+                });
+                continue;
+            },
+            "match" => {
+                in_match = true;
+                continue;
+            },
+            _ if in_match => continue,
+            _ => {},
+        }
+
+        let block = blocks.last_mut().expect("blocks start with the global block");
+        match keyword.to_lowercase().as_str() {
+            "hostname" => block.hostname = block.hostname.take().or(Some(value)),
+            "user" => block.user = block.user.take().or(Some(value)),
+            "port" => block.port = block.port.or(value.parse().ok()),
+            "identityfile" => block.identity_file = block.identity_file.take().or(Some(value)),
+            "proxyjump" => block.proxy_jump = block.proxy_jump.take().or(Some(value)),
+            _ => {},
+        }
+    }
+
+    blocks
+}
+
+/// Whether `alias` matches the block: some pattern matches and no negated
+/// pattern does
+fn block_matches(block: &HostBlock, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in &block.patterns {
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob_matches_ignore_case(negated, alias) => return false,
+            Some(_) => {},
+            None => matched |= glob_matches_ignore_case(pattern, alias),
+        }
+    }
+    matched
+}
+
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::transfer::ssh::shell_quote;
+
+/// Common sensor resolutions offered in the capture panel
+pub const RESOLUTION_PRESETS: &[(u32, u32)] = &[
+    (640, 480),
+    (1280, 720),
+    (1920, 1080),
+    (2592, 1944),
+    (3280, 2464),
+    (4056, 3040),
+];
+
+/// Parameters for a still capture on the Pi camera
+#[derive(Debug, Clone)]
+pub struct CaptureSettings {
+    /// Image width in pixels
+    pub width: u32,
+    /// Image height in pixels
+    pub height: u32,
+    /// Shutter speed in microseconds; 0 leaves exposure on auto
+    pub shutter_us: u32,
+    /// Time before the capture is taken, in milliseconds
+    pub timeout_ms: u32,
+}
+
+impl Default for CaptureSettings {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            shutter_us: 0,
+            timeout_ms: 2000,
+        }
+    }
+}
+
+/// Build the shell command that captures a still image to `remote_path`
+///
+/// Prefers the libcamera tools (`rpicam-still` on Bookworm, `libcamera-still`
+/// on Bullseye) and falls back to the legacy `raspistill`.
+pub fn capture_command(settings: &CaptureSettings, remote_path: &str) -> String {
+    let output = shell_quote(remote_path);
+
+    let mut libcamera_args = format!(
+        "-n -o {} --width {} --height {} -t {}",
+        output, settings.width, settings.height, settings.timeout_ms.max(1)
+    );
+    let mut raspistill_args = format!(
+        "-n -o {} -w {} -h {} -t {}",
+        output, settings.width, settings.height, settings.timeout_ms.max(1)
+    );
+
+    if settings.shutter_us > 0 {
+        libcamera_args.push_str(&format!(" --shutter {}", settings.shutter_us));
+        raspistill_args.push_str(&format!(" -ss {}", settings.shutter_us));
+    }
+
+    format!(
+        "if command -v rpicam-still >/dev/null 2>&1; then rpicam-still {libcamera}; \
+elif command -v libcamera-still >/dev/null 2>&1; then libcamera-still {libcamera}; \
+elif command -v raspistill >/dev/null 2>&1; then raspistill {raspistill}; \
+else echo 'No camera tool found (install rpicam-apps or libcamera-apps)' >&2; exit 127; fi",
+        libcamera = libcamera_args,
+        raspistill = raspistill_args
+    )
+}
+
+/// Remote file name for a new capture, unique per call
+pub fn capture_file_name() -> String {
+    format!("capture_{}.jpg", chrono::Local::now().format("%Y%m%d_%H%M%S"))
+}
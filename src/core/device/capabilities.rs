@@ -0,0 +1,141 @@
+use std::env;
+use std::path::Path;
+
+/// An external program some feature relies on
+#[derive(Debug, Clone, Copy)]
+pub struct Tool {
+    /// Binary name looked up on PATH
+    pub binary: &'static str,
+    /// What the tool is needed for, shown in the report
+    pub purpose: &'static str,
+}
+
+/// Programs looked for on the Pi
+pub const REMOTE_TOOLS: &[Tool] = &[
+    Tool { binary: "rsync", purpose: "rsync transfers" },
+    Tool { binary: "convert", purpose: "ImageMagick processing on the Pi" },
+    Tool { binary: "rpicam-still", purpose: "camera capture (Bookworm)" },
+    Tool { binary: "libcamera-still", purpose: "camera capture (Bullseye)" },
+    Tool { binary: "raspistill", purpose: "camera capture (legacy)" },
+    Tool { binary: "vcgencmd", purpose: "temperature readings" },
+    Tool { binary: "systemctl", purpose: "service management" },
+];
+
+/// Programs looked for on this machine
+pub const LOCAL_TOOLS: &[Tool] = &[
+    Tool { binary: "ssh", purpose: "all remote access" },
+    Tool { binary: "scp", purpose: "SSH transfers" },
+    Tool { binary: "sshpass", purpose: "password authentication" },
+    Tool { binary: "rsync", purpose: "rsync transfers" },
+    Tool { binary: "convert", purpose: "ImageMagick processing" },
+];
+
+/// Availability of a single tool
+#[derive(Debug, Clone)]
+pub struct ToolStatus {
+    pub tool: Tool,
+    pub available: bool,
+}
+
+/// Which tools are installed locally and on the connected Pi
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    pub local: Vec<ToolStatus>,
+    pub remote: Vec<ToolStatus>,
+}
+
+impl Capabilities {
+    /// Whether a binary was found on the Pi
+    pub fn has_remote(&self, binary: &str) -> bool {
+        self.remote.iter().any(|s| s.available && s.tool.binary == binary)
+    }
+
+    /// Whether a binary was found on this machine
+    pub fn has_local(&self, binary: &str) -> bool {
+        self.local.iter().any(|s| s.available && s.tool.binary == binary)
+    }
+
+    /// Whether any of the camera tools is installed on the Pi
+    pub fn has_camera_tool(&self) -> bool {
+        ["rpicam-still", "libcamera-still", "raspistill"]
+            .iter()
+            .any(|binary| self.has_remote(binary))
+    }
+
+    /// rsync must exist on both ends
+    pub fn has_rsync(&self) -> bool {
+        self.has_local("rsync") && self.has_remote("rsync")
+    }
+
+    /// Human readable report listing every tool and what is affected if it is missing
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+
+        let mut section = |title: &str, statuses: &[ToolStatus]| {
+            if statuses.is_empty() {
+                return;
+            }
+            report.push_str(title);
+            report.push('\n');
+            for status in statuses {
+                report.push_str(&format!(
+                    "  [{}] {:<16} {}\n",
+                    if status.available { "ok" } else { "--" },
+                    status.tool.binary,
+                    status.tool.purpose
+                ));
+            }
+        };
+
+        section("This computer:", &self.local);
+        section("Raspberry Pi:", &self.remote);
+
+        report
+    }
+}
+
+/// Look up every local tool on PATH
+pub fn probe_local() -> Vec<ToolStatus> {
+    LOCAL_TOOLS
+        .iter()
+        .map(|tool| ToolStatus {
+            tool: *tool,
+            available: find_in_path(tool.binary),
+        })
+        .collect()
+}
+
+fn find_in_path(binary: &str) -> bool {
+    let paths = match env::var_os("PATH") {
+        Some(paths) => paths,
+        None => return false,
+    };
+
+    env::split_paths(&paths).any(|dir| {
+        let candidate = dir.join(binary);
+        candidate.is_file() || (cfg!(windows) && Path::new(&format!("{}.exe", candidate.display())).is_file())
+    })
+}
+
+/// Shell command that prints `<binary> yes|no` for every remote tool
+pub fn remote_probe_command() -> String {
+    let binaries: Vec<&str> = REMOTE_TOOLS.iter().map(|t| t.binary).collect();
+    format!(
+        "for t in {}; do if command -v $t >/dev/null 2>&1; then echo \"$t yes\"; else echo \"$t no\"; fi; done",
+        binaries.join(" ")
+    )
+}
+
+/// Parse the output of `remote_probe_command`; lines that don't belong to it are ignored
+pub fn parse_remote_probe(output: &str) -> Vec<ToolStatus> {
+    REMOTE_TOOLS
+        .iter()
+        .map(|tool| {
+            let available = output.lines().any(|line| {
+                let mut parts = line.split_whitespace();
+                parts.next() == Some(tool.binary) && parts.next() == Some("yes")
+            });
+            ToolStatus { tool: *tool, available }
+        })
+        .collect()
+}
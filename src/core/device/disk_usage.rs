@@ -0,0 +1,109 @@
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use crate::transfer::ssh::shell_quote_path;
+
+/// Size of one directory or file as reported by `du`
+#[derive(Debug, Clone)]
+pub struct DiskUsageEntry {
+    /// Full path on the device
+    pub path: PathBuf,
+    /// Size in KiB
+    pub size_kb: u64,
+}
+
+impl DiskUsageEntry {
+    /// Name shown in the breakdown
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.path.to_string_lossy().to_string())
+    }
+}
+
+/// Result of scanning one directory
+#[derive(Debug, Clone, Default)]
+pub struct DiskUsage {
+    /// Total size of the scanned directory in KiB
+    pub total_kb: u64,
+    /// Immediate children, largest first
+    pub entries: Vec<DiskUsageEntry>,
+}
+
+/// How the breakdown is ordered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskUsageSort {
+    SizeDescending,
+    SizeAscending,
+    NameAscending,
+    NameDescending,
+}
+
+/// Command listing the size of every immediate child of `dir`
+///
+/// `-x` keeps the scan on the SD card instead of wandering into mounted
+/// network shares, and unreadable directories are silently skipped.
+pub fn du_command(dir: &Path) -> String {
+    let dir = shell_quote_path(dir);
+    format!(
+        "du -x -k -s {dir} 2>/dev/null; find {dir} -mindepth 1 -maxdepth 1 -xdev -exec du -x -k -s {{}} + 2>/dev/null",
+        dir = dir
+    )
+}
+
+/// Parse the output of `du_command`; the first line is the directory total
+pub fn parse_du_output(output: &str, dir: &Path) -> DiskUsage {
+    let mut usage = DiskUsage::default();
+
+    for line in output.lines() {
+        let mut parts = line.splitn(2, '\t');
+        let size_kb = match parts.next().and_then(|s| s.trim().parse::<u64>().ok()) {
+            Some(size) => size,
+            None => continue,
+        };
+        let path = match parts.next() {
+            Some(path) => PathBuf::from(path),
+            None => continue,
+        };
+
+        if path == dir {
+            usage.total_kb = size_kb;
+        } else {
+            usage.entries.push(DiskUsageEntry { path, size_kb });
+        }
+    }
+
+    // Fall back to the sum of the children if the total was unreadable
+    if usage.total_kb == 0 {
+        usage.total_kb = usage.entries.iter().map(|e| e.size_kb).sum();
+    }
+
+    sort_entries(&mut usage.entries, DiskUsageSort::SizeDescending);
+    usage
+}
+
+/// Sort a breakdown in place
+pub fn sort_entries(entries: &mut [DiskUsageEntry], sort: DiskUsageSort) {
+    match sort {
+        DiskUsageSort::SizeDescending => entries.sort_by_key(|e| Reverse(e.size_kb)),
+        DiskUsageSort::SizeAscending => entries.sort_by_key(|e| e.size_kb),
+        DiskUsageSort::NameAscending => entries.sort_by_key(|e| e.name().to_lowercase()),
+        DiskUsageSort::NameDescending => {
+            entries.sort_by_key(|e| e.name().to_lowercase());
+            entries.reverse();
+        },
+    }
+}
+
+/// Format a size in KiB for display
+pub fn format_size_kb(size_kb: u64) -> String {
+    let size = size_kb as f64;
+    if size >= 1024.0 * 1024.0 {
+        format!("{:.1} GB", size / (1024.0 * 1024.0))
+    } else if size >= 1024.0 {
+        format!("{:.1} MB", size / 1024.0)
+    } else {
+        format!("{} KB", size_kb)
+    }
+}
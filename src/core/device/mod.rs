@@ -0,0 +1,25 @@
+pub mod status;
+pub mod camera;
+pub mod services;
+pub mod disk_usage;
+pub mod capabilities;
+pub mod power;
+pub mod search;
+
+// Re-export the types needed by other modules
+pub use status::{DeviceStatus, STATUS_COMMAND, parse_status, format_uptime};
+pub use camera::{CaptureSettings, RESOLUTION_PRESETS, capture_command, capture_file_name};
+pub use services::{
+    ServiceUnit, ServiceAction, LIST_SERVICES_COMMAND,
+    status_command, journal_command, parse_service_list,
+};
+pub use disk_usage::{
+    DiskUsage, DiskUsageSort,
+    du_command, parse_du_output, sort_entries, format_size_kb,
+};
+pub use capabilities::{
+    Capabilities,
+    probe_local, remote_probe_command, parse_remote_probe,
+};
+pub use power::PowerAction;
+pub use search::{SearchCriteria, SearchMatch, MAX_SEARCH_RESULTS, search_command, parse_search_output};
@@ -0,0 +1,49 @@
+use crate::transfer::method::SSH_FAILURE_EXIT;
+
+/// Power actions that can be sent to the Pi
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+    Reboot,
+    Shutdown,
+}
+
+impl PowerAction {
+    /// Lower-case verb used in prompts
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Self::Reboot => "reboot",
+            Self::Shutdown => "shut down",
+        }
+    }
+
+    /// Command performing the action
+    ///
+    /// It takes effect at once, so the Pi may drop the SSH session before
+    /// the command can report back; `accepted` allows for that.
+    pub fn command(&self) -> &'static str {
+        match self {
+            Self::Reboot => "sudo -n shutdown -r +0 2>&1 || sudo -n systemctl reboot",
+            Self::Shutdown => "sudo -n shutdown -h +0 2>&1 || sudo -n systemctl poweroff",
+        }
+    }
+
+    /// Whether the SSH exit status and stderr mean the command was accepted
+    ///
+    /// ssh exits with 255 when the Pi drops the connection while going down,
+    /// but also when it never connected, so 255 only counts when stderr
+    /// shows the Pi closed an established session.
+    pub fn accepted(&self, exit_code: Option<i32>, stderr: &str) -> bool {
+        match exit_code {
+            Some(0) | None => true,
+            Some(SSH_FAILURE_EXIT) => CONNECTION_CLOSED.iter().any(|message| stderr.contains(message)),
+            Some(_) => false,
+        }
+    }
+}
+
+/// What ssh prints when the far end goes away during a session
+const CONNECTION_CLOSED: &[&str] = &[
+    "closed by remote host",
+    "Connection reset by peer",
+    "Broken pipe",
+];
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+
+/// Most matches one search returns, so a broad pattern can't flood the results
+pub const MAX_SEARCH_RESULTS: usize = 2000;
+
+/// What to look for below a directory on the device
+#[derive(Debug, Clone, Default)]
+pub struct SearchCriteria {
+    /// Directory the search starts in
+    pub root: PathBuf,
+    /// Glob matched against names, ignoring case; empty matches everything
+    pub name_pattern: String,
+    /// Only files of at least this many KiB
+    pub min_size_kb: Option<u64>,
+    /// Only files of at most this many KiB
+    pub max_size_kb: Option<u64>,
+    /// Only entries modified within this many days
+    pub modified_within_days: Option<u32>,
+}
+
+/// A file or directory found by a search
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    /// Full path on the device
+    pub path: PathBuf,
+    pub is_dir: bool,
+    /// Size in bytes
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: Option<i64>,
+}
+
+/// `find` command for the criteria, printing one tab-separated match per line
+///
+/// Size limits only make sense for files, so they restrict the search to
+/// files. `-xdev` keeps it off mounted network shares and unreadable
+/// directories are silently skipped.
+pub fn search_command(criteria: &SearchCriteria) -> String {
+    let mut command = format!(
+        "find {} -xdev -mindepth 1",
+        shell_quote_path(&criteria.root)
+    );
+
+    if criteria.min_size_kb.is_some() || criteria.max_size_kb.is_some() {
+        command.push_str(" -type f");
+    }
+    if !criteria.name_pattern.is_empty() {
+        command.push_str(&format!(" -iname {}", shell_quote(&criteria.name_pattern)));
+    }
+    // find rounds sizes up to whole KiB and compares strictly
+    if let Some(min) = criteria.min_size_kb {
+        command.push_str(&format!(" -size +{}k", min.saturating_sub(1)));
+    }
+    if let Some(max) = criteria.max_size_kb {
+        command.push_str(&format!(" -size -{}k", max + 1));
+    }
+    if let Some(days) = criteria.modified_within_days {
+        command.push_str(&format!(" -mtime -{}", days));
+    }
+
+    command.push_str(&format!(
+        " -printf '%y\\t%s\\t%T@\\t%p\\n' 2>/dev/null | head -n {}",
+        MAX_SEARCH_RESULTS
+    ));
+    command
+}
+
+/// Parse the output of `search_command`
+pub fn parse_search_output(output: &str) -> Vec<SearchMatch> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(4, '\t');
+            let kind = parts.next()?;
+            let size = parts.next()?.parse().unwrap_or(0);
+            let modified = parts.next()?.parse::<f64>().ok().map(|secs| secs as i64);
+            let path = parts.next().filter(|p| !p.is_empty())?;
+
+            Some(SearchMatch {
+                path: PathBuf::from(path),
+                is_dir: kind == "d",
+                size,
+                modified,
+            })
+        })
+        .collect()
+}
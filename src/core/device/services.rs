@@ -0,0 +1,103 @@
+use crate::transfer::ssh::shell_quote;
+
+/// Lists every service unit known to systemd, one per line
+pub const LIST_SERVICES_COMMAND: &str =
+    "systemctl list-units --type=service --all --no-pager --no-legend --plain";
+
+/// Number of journal lines fetched for a unit
+pub const JOURNAL_LINES: u32 = 200;
+
+/// A systemd service as reported by `systemctl list-units`
+#[derive(Debug, Clone, Default)]
+pub struct ServiceUnit {
+    /// Unit name, e.g. `motion.service`
+    pub name: String,
+    /// Load state (`loaded`, `not-found`, ...)
+    pub load: String,
+    /// High-level activation state (`active`, `inactive`, `failed`, ...)
+    pub active: String,
+    /// Low-level state (`running`, `exited`, `dead`, ...)
+    pub sub: String,
+    /// Human readable description
+    pub description: String,
+}
+
+impl ServiceUnit {
+    pub fn is_active(&self) -> bool {
+        self.active == "active"
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.active == "failed"
+    }
+}
+
+/// Operations that change the state of a service
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl ServiceAction {
+    pub fn verb(&self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+        }
+    }
+
+    /// Whether the action interrupts a running service and should be confirmed
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Self::Stop | Self::Restart)
+    }
+
+    /// Command performing the action; `sudo -n` fails fast instead of
+    /// waiting for a password prompt that can never be answered
+    pub fn command(&self, unit: &str) -> String {
+        format!("sudo -n systemctl {} {}", self.verb(), shell_quote(unit))
+    }
+}
+
+/// Command printing the full status of a unit
+pub fn status_command(unit: &str) -> String {
+    format!("systemctl status --no-pager --full {}", shell_quote(unit))
+}
+
+/// Command printing the most recent journal entries of a unit
+pub fn journal_command(unit: &str) -> String {
+    format!(
+        "journalctl -u {} -n {} --no-pager 2>&1 || sudo -n journalctl -u {} -n {} --no-pager",
+        shell_quote(unit), JOURNAL_LINES, shell_quote(unit), JOURNAL_LINES
+    )
+}
+
+/// Parse the output of `LIST_SERVICES_COMMAND`
+pub fn parse_service_list(output: &str) -> Vec<ServiceUnit> {
+    let mut units: Vec<ServiceUnit> = output
+        .lines()
+        .filter_map(|line| {
+            // Failed units are prefixed with a marker even in --plain mode
+            let line = line.trim_start_matches(|c: char| c == '●' || c == '*' || c.is_whitespace());
+            let mut parts = line.split_whitespace();
+
+            let name = parts.next()?.to_string();
+            if !name.ends_with(".service") {
+                return None;
+            }
+
+            Some(ServiceUnit {
+                name,
+                load: parts.next().unwrap_or("").to_string(),
+                active: parts.next().unwrap_or("").to_string(),
+                sub: parts.next().unwrap_or("").to_string(),
+                description: parts.collect::<Vec<_>>().join(" "),
+            })
+        })
+        .collect();
+
+    units.sort_by(|a, b| a.name.cmp(&b.name));
+    units
+}
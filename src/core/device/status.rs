@@ -0,0 +1,147 @@
+/// Shell command that gathers everything the dashboard shows in one round trip
+///
+/// Each section is introduced by a `---name` marker line so the output can be
+/// split reliably even when a tool is missing on the device.
+pub const STATUS_COMMAND: &str = "echo ---loadavg; cat /proc/loadavg; \
+echo ---nproc; nproc; \
+echo ---temp; vcgencmd measure_temp 2>/dev/null || cat /sys/class/thermal/thermal_zone0/temp 2>/dev/null; \
+echo ---meminfo; cat /proc/meminfo; \
+echo ---df; df -Pk /; \
+echo ---uptime; cat /proc/uptime";
+
+/// Snapshot of a device's health
+#[derive(Debug, Clone, Default)]
+pub struct DeviceStatus {
+    /// 1, 5 and 15 minute load averages
+    pub load_average: [f32; 3],
+    /// Number of CPU cores
+    pub cpu_count: u32,
+    /// SoC temperature in degrees Celsius, if available
+    pub temperature_c: Option<f32>,
+    /// Total memory in KiB
+    pub mem_total_kb: u64,
+    /// Memory available to new processes in KiB
+    pub mem_available_kb: u64,
+    /// Size of the root filesystem in KiB
+    pub disk_total_kb: u64,
+    /// Used space on the root filesystem in KiB
+    pub disk_used_kb: u64,
+    /// Seconds since boot
+    pub uptime_secs: u64,
+}
+
+impl DeviceStatus {
+    /// One-minute load as a percentage of available cores
+    pub fn cpu_load_percent(&self) -> f32 {
+        let cores = self.cpu_count.max(1) as f32;
+        (self.load_average[0] / cores * 100.0).min(100.0)
+    }
+
+    /// Used memory as a percentage of total
+    pub fn memory_used_percent(&self) -> f32 {
+        if self.mem_total_kb == 0 {
+            return 0.0;
+        }
+        let used = self.mem_total_kb.saturating_sub(self.mem_available_kb);
+        used as f32 / self.mem_total_kb as f32 * 100.0
+    }
+
+    /// Used disk space as a percentage of total
+    pub fn disk_used_percent(&self) -> f32 {
+        if self.disk_total_kb == 0 {
+            return 0.0;
+        }
+        self.disk_used_kb as f32 / self.disk_total_kb as f32 * 100.0
+    }
+}
+
+/// Parse the output of `STATUS_COMMAND`
+pub fn parse_status(output: &str) -> DeviceStatus {
+    let mut status = DeviceStatus::default();
+    let mut section = "";
+
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("---") {
+            section = name;
+            continue;
+        }
+        if line.is_empty() {
+            continue;
+        }
+
+        match section {
+            "loadavg" => {
+                for (i, value) in line.split_whitespace().take(3).enumerate() {
+                    status.load_average[i] = value.parse().unwrap_or(0.0);
+                }
+            },
+            "nproc" => {
+                status.cpu_count = line.parse().unwrap_or(1);
+            },
+            "temp" => {
+                status.temperature_c = parse_temperature(line);
+            },
+            "meminfo" => {
+                let mut parts = line.split_whitespace();
+                let key = parts.next().unwrap_or("");
+                let value = parts.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+                match key {
+                    "MemTotal:" => status.mem_total_kb = value,
+                    "MemAvailable:" => status.mem_available_kb = value,
+                    _ => {}
+                }
+            },
+            "df" => {
+                // Skip the header; the data line is: fs, blocks, used, available, capacity, mount
+                if line.starts_with("Filesystem") {
+                    continue;
+                }
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                if parts.len() >= 4 {
+                    status.disk_total_kb = parts[1].parse().unwrap_or(0);
+                    status.disk_used_kb = parts[2].parse().unwrap_or(0);
+                }
+            },
+            "uptime" => {
+                status.uptime_secs = line
+                    .split_whitespace()
+                    .next()
+                    .and_then(|v| v.parse::<f64>().ok())
+                    .map(|v| v as u64)
+                    .unwrap_or(0);
+            },
+            _ => {}
+        }
+    }
+
+    status
+}
+
+/// Parse `temp=48.3'C` (vcgencmd) or millidegrees from sysfs
+fn parse_temperature(line: &str) -> Option<f32> {
+    if let Some(value) = line.strip_prefix("temp=") {
+        let number: String = value
+            .chars()
+            .take_while(|c| c.is_ascii_digit() || *c == '.')
+            .collect();
+        return number.parse().ok();
+    }
+
+    line.parse::<f32>().ok().map(|millidegrees| millidegrees / 1000.0)
+}
+
+/// Format an uptime in seconds as e.g. "3d 4h 12m"
+pub fn format_uptime(secs: u64) -> String {
+    let days = secs / 86_400;
+    let hours = (secs % 86_400) / 3_600;
+    let minutes = (secs % 3_600) / 60;
+
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
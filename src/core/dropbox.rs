@@ -0,0 +1,174 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::config::DropboxSettings;
+
+const AUTHORIZE_URL: &str = "https://www.dropbox.com/oauth2/authorize";
+const TOKEN_URL: &str = "https://api.dropboxapi.com/oauth2/token";
+const UPLOAD_URL: &str = "https://content.dropboxapi.com/2/files/upload";
+
+/// Largest file `files/upload` takes in one request
+const MAX_UPLOAD_BYTES: u64 = 150 * 1024 * 1024;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Linking of a Dropbox account with OAuth's PKCE flow, which needs no app
+/// secret: open `url`, let the user allow access and hand the code Dropbox
+/// shows to [`DropboxLink::finish`]
+pub struct DropboxLink {
+    pub url: String,
+    app_key: String,
+    verifier: String,
+}
+
+impl DropboxLink {
+    pub fn start(app_key: &str) -> Result<Self, String> {
+        let verifier = new_verifier()?;
+        // PKCE wants the URL-safe alphabet without padding
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        let url = format!(
+            "{}?client_id={}&response_type=code&token_access_type=offline&code_challenge={}&code_challenge_method=S256",
+            AUTHORIZE_URL, app_key, challenge
+        );
+        Ok(Self { url, app_key: app_key.to_string(), verifier })
+    }
+
+    /// Trade the code for a refresh token, to be kept in the settings
+    pub fn finish(&self, code: &str) -> Result<String, String> {
+        let answer = post_form(&[
+            ("code", code.trim()),
+            ("grant_type", "authorization_code"),
+            ("code_verifier", &self.verifier),
+            ("client_id", &self.app_key),
+        ])?;
+        answer["refresh_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Dropbox did not send a refresh token".to_string())
+    }
+}
+
+/// Uploads to a linked Dropbox account
+///
+/// Access tokens last a few hours; a new one is fetched with the refresh
+/// token when needed.
+pub struct DropboxClient {
+    app_key: String,
+    refresh_token: String,
+    access_token: Option<String>,
+}
+
+impl DropboxClient {
+    pub fn new(settings: &DropboxSettings) -> Result<Self, String> {
+        match &settings.refresh_token {
+            Some(refresh_token) if !settings.app_key.is_empty() => Ok(Self {
+                app_key: settings.app_key.clone(),
+                refresh_token: refresh_token.clone(),
+                access_token: None,
+            }),
+            _ => Err("No Dropbox account is linked; use Processing > Link Dropbox Account".to_string()),
+        }
+    }
+
+    /// Copy `local` to `dropbox_path`, e.g. "/Pi/thumbs/img.jpg", replacing
+    /// a file already there
+    pub fn upload(&mut self, local: &Path, dropbox_path: &str) -> Result<(), String> {
+        let size = fs::metadata(local).map_err(|e| format!("{}: {}", local.display(), e))?.len();
+        if size > MAX_UPLOAD_BYTES {
+            return Err(format!("{} is larger than Dropbox takes in one upload", local.display()));
+        }
+        let bytes = fs::read(local).map_err(|e| format!("{}: {}", local.display(), e))?;
+        let argument = ascii_json(&json!({ "path": dropbox_path, "mode": "overwrite", "mute": true }));
+
+        // A stored access token may have expired; fetch a new one once
+        for attempt in 0..2 {
+            let token = self.access_token()?;
+            let result = agent()
+                .post(UPLOAD_URL)
+                .set("Authorization", &format!("Bearer {}", token))
+                .set("Dropbox-API-Arg", &argument)
+                .set("Content-Type", "application/octet-stream")
+                .send_bytes(&bytes);
+            match result {
+                Ok(_) => return Ok(()),
+                Err(ureq::Error::Status(401, _)) if attempt == 0 => self.access_token = None,
+                Err(e) => return Err(describe(e)),
+            }
+        }
+        Err("Dropbox refused the access token".to_string())
+    }
+
+    fn access_token(&mut self) -> Result<String, String> {
+        if let Some(token) = &self.access_token {
+            return Ok(token.clone());
+        }
+        let answer = post_form(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &self.refresh_token),
+            ("client_id", &self.app_key),
+        ])?;
+        let token = answer["access_token"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| "Dropbox did not send an access token".to_string())?;
+        self.access_token = Some(token.clone());
+        Ok(token)
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build()
+}
+
+fn post_form(form: &[(&str, &str)]) -> Result<Value, String> {
+    let answer = agent().post(TOKEN_URL).send_form(form).map_err(describe)?;
+    let text = answer.into_string().map_err(|e| e.to_string())?;
+    serde_json::from_str(&text).map_err(|e| format!("Unexpected answer from Dropbox: {}", e))
+}
+
+/// Dropbox's own explanation of a failed request, when it gave one
+fn describe(error: ureq::Error) -> String {
+    match error {
+        ureq::Error::Status(status, response) => {
+            let text = response.into_string().unwrap_or_default();
+            let summary = serde_json::from_str::<Value>(&text).ok().and_then(|answer| {
+                answer["error_summary"]
+                    .as_str()
+                    .or_else(|| answer["error_description"].as_str())
+                    .map(str::to_string)
+            });
+            format!("Dropbox answered {}: {}", status, summary.unwrap_or(text))
+        },
+        e => format!("Could not reach Dropbox: {}", e),
+    }
+}
+
+/// JSON for an HTTP header, which must be ASCII
+fn ascii_json(value: &Value) -> String {
+    let mut text = String::new();
+    for c in value.to_string().chars() {
+        if c.is_ascii() {
+            text.push(c);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                text.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    text
+}
+
+/// 43 characters from 32 bytes of the operating system's random source,
+/// the shortest verifier PKCE allows
+fn new_verifier() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("No random source for the Dropbox link: {}", e))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
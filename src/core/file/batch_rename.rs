@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+use crate::core::image::exif::{capture_time, read_capture_time};
+use crate::core::utils::{is_image_file, AppError, AppResult};
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+
+/// Bytes of each Pi file sent back for EXIF parsing; the capture time sits
+/// near the start of the EXIF block, and whole blocks would make the
+/// listing of a big folder tens of megabytes
+const REMOTE_EXIF_SCAN_BYTES: usize = 16 * 1024;
+
+/// Rename template used when none is given: `2024-06-01_14-03-22_0001.jpg`
+pub const DEFAULT_RENAME_TEMPLATE: &str = "{date}_{time}_{counter}";
+
+/// Tokens a rename template understands, with what they stand for
+pub const RENAME_TOKENS: &[(&str, &str)] = &[
+    ("{date}", "capture date, as YYYY-MM-DD"),
+    ("{time}", "capture time, as HH-MM-SS"),
+    ("{counter}", "position in capture order, from 0001"),
+    ("{stem}", "current name without extension"),
+];
+
+/// A file that may be renamed, with what is known about when it was taken
+#[derive(Debug, Clone)]
+pub struct RenameCandidate {
+    pub name: String,
+    /// From EXIF
+    pub captured: Option<NaiveDateTime>,
+    /// Modification time, used when there is no EXIF time
+    pub modified: Option<NaiveDateTime>,
+}
+
+/// Where the time a new name is built from came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSource {
+    Exif,
+    Modified,
+    Unknown,
+}
+
+impl TimeSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Exif => "EXIF",
+            Self::Modified => "modified",
+            Self::Unknown => "no date",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlannedRename {
+    pub from: String,
+    pub to: String,
+    pub source: TimeSource,
+}
+
+/// New names for `candidates` from `template` (see [`RENAME_TOKENS`])
+///
+/// Files are numbered in capture order, ties broken by name; extensions are
+/// kept, lower-cased. Files whose name would not change are left out.
+pub fn plan_renames(mut candidates: Vec<RenameCandidate>, template: &str) -> Vec<PlannedRename> {
+    candidates.sort_by(|a, b| {
+        let time = |c: &RenameCandidate| c.captured.or(c.modified);
+        time(a).cmp(&time(b)).then_with(|| a.name.cmp(&b.name))
+    });
+    let width = candidates.len().to_string().len().max(4);
+
+    candidates
+        .iter()
+        .enumerate()
+        .map(|(index, candidate)| {
+            let (time, source) = match (candidate.captured, candidate.modified) {
+                (Some(time), _) => (Some(time), TimeSource::Exif),
+                (None, Some(time)) => (Some(time), TimeSource::Modified),
+                (None, None) => (None, TimeSource::Unknown),
+            };
+            let path = Path::new(&candidate.name);
+            let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+
+            let mut to = template
+                .replace("{date}", &time.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_else(|| "undated".to_string()))
+                .replace("{time}", &time.map(|t| t.format("%H-%M-%S").to_string()).unwrap_or_else(|| "00-00-00".to_string()))
+                .replace("{counter}", &format!("{:0width$}", index + 1, width = width))
+                .replace("{stem}", &stem)
+                .replace(['/', '\\'], "_");
+            if let Some(ext) = path.extension() {
+                to.push('.');
+                to.push_str(&ext.to_string_lossy().to_lowercase());
+            }
+
+            PlannedRename { from: candidate.name.clone(), to, source }
+        })
+        .filter(|rename| rename.from != rename.to)
+        .collect()
+}
+
+/// Refuse plans that would give two files the same name or overwrite a
+/// file in the folder that is not being renamed
+pub fn check_plan(plan: &[PlannedRename], folder_names: &[String]) -> AppResult<()> {
+    let renamed: HashSet<&str> = plan.iter().map(|r| r.from.as_str()).collect();
+    let mut targets = HashSet::new();
+
+    for rename in plan {
+        if rename.to.trim().is_empty() {
+            return Err(AppError::FileError(format!("{} would get an empty name", rename.from)));
+        }
+        if !targets.insert(rename.to.as_str()) {
+            return Err(AppError::FileError(format!(
+                "More than one file would be named {}; add {{counter}} to the template",
+                rename.to
+            )));
+        }
+        if folder_names.iter().any(|name| name == &rename.to && !renamed.contains(name.as_str())) {
+            return Err(AppError::FileError(format!("{} already exists", rename.to)));
+        }
+    }
+    Ok(())
+}
+
+/// Images in a local folder with their capture and modification times
+pub fn local_candidates(folder: &Path) -> AppResult<Vec<RenameCandidate>> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(folder)?.flatten() {
+        let path = entry.path();
+        if !path.is_file() || !is_image_file(&path) {
+            continue;
+        }
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .map(|time| DateTime::<Local>::from(time).naive_local());
+        candidates.push(RenameCandidate {
+            name: entry.file_name().to_string_lossy().to_string(),
+            captured: read_capture_time(&path),
+            modified,
+        });
+    }
+    Ok(candidates)
+}
+
+/// Every name in a local folder, for [`check_plan`]
+pub fn local_names(folder: &Path) -> AppResult<Vec<String>> {
+    Ok(fs::read_dir(folder)?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect())
+}
+
+/// What applying a rename plan did
+#[derive(Debug, Clone, Default)]
+pub struct RenameOutcome {
+    pub renamed: usize,
+    /// Files whose new name was taken by the time they were renamed, under
+    /// the name they have now
+    pub skipped: Vec<String>,
+}
+
+/// Rename files in a local folder as planned
+///
+/// Files first get temporary names so that plans swapping names between
+/// files work. A file whose new name has been taken since the plan was
+/// checked keeps its old name; if a rename fails, every file is put back.
+pub fn apply_local(folder: &Path, plan: &[PlannedRename]) -> AppResult<RenameOutcome> {
+    let taken = |name: &str| fs::symlink_metadata(folder.join(name)).is_ok();
+
+    for (index, rename) in plan.iter().enumerate() {
+        let temp = temp_name(&rename.from);
+        let staged = if taken(&temp) {
+            Err(AppError::FileError(format!("{} already exists", temp)))
+        } else {
+            fs::rename(folder.join(&rename.from), folder.join(&temp))
+                .map_err(|e| AppError::FileError(format!("{}: {}", rename.from, e)))
+        };
+        if let Err(e) = staged {
+            roll_back_local(folder, &[], &plan[..index]);
+            return Err(e);
+        }
+    }
+
+    let mut renamed = Vec::new();
+    let mut outcome = RenameOutcome::default();
+    for (index, rename) in plan.iter().enumerate() {
+        let temp = temp_name(&rename.from);
+        let (target, skipped) = match (taken(&rename.to), taken(&rename.from)) {
+            (false, _) => (&rename.to, false),
+            (true, false) => (&rename.from, true),
+            (true, true) => (&temp, true),
+        };
+        if target != &temp {
+            if let Err(e) = fs::rename(folder.join(&temp), folder.join(target)) {
+                roll_back_local(folder, &renamed, &plan[index..]);
+                return Err(AppError::FileError(format!("{}: {}", rename.from, e)));
+            }
+        }
+        if skipped {
+            outcome.skipped.push(target.clone());
+        } else {
+            renamed.push(rename);
+        }
+    }
+    outcome.renamed = renamed.len();
+    Ok(outcome)
+}
+
+/// Give the files of a partly applied plan their old names back; `renamed`
+/// already have their new name and `staged` their temporary one
+fn roll_back_local(folder: &Path, renamed: &[&PlannedRename], staged: &[PlannedRename]) {
+    let restore = |from: &str, to: &str| {
+        // Never put a file back over another one
+        if fs::symlink_metadata(folder.join(to)).is_ok() {
+            log::error!("Could not restore {}: {} exists", from, to);
+        } else if let Err(e) = fs::rename(folder.join(from), folder.join(to)) {
+            log::error!("Could not restore {}: {}", from, e);
+        }
+    };
+    for rename in renamed.iter().rev() {
+        restore(&rename.to, &temp_name(&rename.from));
+    }
+    for rename in renamed.iter().copied().chain(staged).rev() {
+        restore(&temp_name(&rename.from), &rename.from);
+    }
+}
+
+/// Shell command printing, for each image among `names` in a Pi folder,
+/// its modification time, the start of the file in base64 and its name
+///
+/// Fields are separated by tabs and records end in NUL, so names may hold
+/// newlines; the name comes last, as it may also hold tabs.
+pub fn remote_candidates_command(folder: &Path, names: &[String]) -> String {
+    let quoted: Vec<String> = names.iter().map(|name| shell_quote(name)).collect();
+    format!(
+        "cd {} && for f in {}; do m=$(stat -c %Y -- \"$f\") && printf '%s\\t%s\\t%s\\0' \"$m\" \"$(head -c {} -- \"$f\" | base64 | tr -d '\\n')\" \"$f\"; done",
+        shell_quote_path(folder),
+        quoted.join(" "),
+        REMOTE_EXIF_SCAN_BYTES
+    )
+}
+
+/// Read the output of [`remote_candidates_command`] for the images `names`;
+/// records that don't parse or name another file are left out
+pub fn parse_remote_candidates(output: &[u8], names: &[String]) -> Vec<RenameCandidate> {
+    output
+        .split(|&byte| byte == b'\0')
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            let candidate = parse_remote_candidate(record).filter(|candidate| names.contains(&candidate.name));
+            if candidate.is_none() {
+                log::warn!("Ignoring an unexpected record from the Pi: {:?}", String::from_utf8_lossy(record));
+            }
+            candidate
+        })
+        .collect()
+}
+
+fn parse_remote_candidate(record: &[u8]) -> Option<RenameCandidate> {
+    let mut fields = record.splitn(3, |&byte| byte == b'\t');
+    let modified = std::str::from_utf8(fields.next()?).ok()?.parse::<i64>().ok()?;
+    let head = STANDARD.decode(fields.next()?).ok()?;
+    let name = std::str::from_utf8(fields.next()?).ok()?;
+    Some(RenameCandidate {
+        name: name.to_string(),
+        captured: capture_time(&head),
+        modified: Local.timestamp_opt(modified, 0).single().map(|time| time.naive_local()),
+    })
+}
+
+/// Shell script renaming files in a Pi folder as planned, the same way as
+/// [`apply_local`]
+///
+/// Files whose new name was taken are printed, NUL-terminated, under the
+/// name they have now; see [`remote_rename_outcome`]. If a step fails, the
+/// renames done so far are undone and the script exits with an error.
+pub fn remote_rename_command(folder: &Path, plan: &[PlannedRename]) -> String {
+    let mut script = format!(
+        "cd {} || exit 1\n\
+         taken() {{ [ -e \"$1\" ] || [ -L \"$1\" ]; }}\n\
+         undo=''\n\
+         fail() {{ echo \"$1\" >&2; eval \"$undo\"; exit 1; }}\n",
+        shell_quote_path(folder)
+    );
+    // Undo steps are prepended, so they run in reverse; `mv -n` keeps them
+    // from putting a file back over another one
+    let push_undo = |script: &mut String, from: &str, to: &str| {
+        let step = format!("mv -n -- {} {}; ", shell_quote(from), shell_quote(to));
+        script.push_str(&format!("undo={}\"$undo\"\n", shell_quote(&step)));
+    };
+
+    for rename in plan {
+        let (from, temp) = (shell_quote(&rename.from), shell_quote(&temp_name(&rename.from)));
+        script.push_str(&format!(
+            "if taken {temp} || ! mv -n -- {from} {temp}; then fail {}; fi\n",
+            shell_quote(&format!("Could not rename {}", rename.from)),
+        ));
+        push_undo(&mut script, &temp_name(&rename.from), &rename.from);
+    }
+    for rename in plan {
+        let temp = temp_name(&rename.from);
+        let (from, quoted_temp, to) = (shell_quote(&rename.from), shell_quote(&temp), shell_quote(&rename.to));
+        script.push_str(&format!(
+            "if ! taken {to}; then\nmv -n -- {quoted_temp} {to} && ! taken {quoted_temp} || fail {}\n",
+            shell_quote(&format!("Could not rename {} to {}", rename.from, rename.to)),
+        ));
+        push_undo(&mut script, &rename.to, &temp);
+        script.push_str(&format!(
+            "elif ! taken {from}; then\nmv -n -- {quoted_temp} {from} && printf '%s\\0' {from} || fail {}\n\
+             else\nprintf '%s\\0' {quoted_temp}\nfi\n",
+            shell_quote(&format!("Could not give {} its name back", rename.from)),
+        ));
+    }
+    script
+}
+
+/// What [`remote_rename_command`] did, from its output
+pub fn remote_rename_outcome(plan: &[PlannedRename], output: &[u8]) -> RenameOutcome {
+    let skipped: Vec<String> = output
+        .split(|&byte| byte == b'\0')
+        .filter(|name| !name.is_empty())
+        .map(|name| String::from_utf8_lossy(name).into_owned())
+        .collect();
+    RenameOutcome { renamed: plan.len().saturating_sub(skipped.len()), skipped }
+}
+
+fn temp_name(name: &str) -> String {
+    format!(".{}.renaming", name)
+}
@@ -0,0 +1,135 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::ssh::shell_quote_path;
+
+/// Kinds of archive that can be extracted, told apart by file name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveKind {
+    /// Kind of the archive at `path`, None when it is not an archive
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+
+    /// File name without the archive extension, used for a folder to
+    /// extract into
+    pub fn stem(&self, path: &Path) -> String {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let lower = name.to_lowercase();
+        let suffix = [".tar.gz", ".tgz", ".tar", ".zip"]
+            .iter()
+            .find(|suffix| lower.ends_with(*suffix))
+            .map_or(0, |suffix| suffix.len());
+        name[..name.len() - suffix].to_string()
+    }
+}
+
+/// What to do with files the archive would write over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverwritePolicy {
+    Overwrite,
+    /// Keep the existing file and leave the archived one out
+    Skip,
+}
+
+/// Extract a local archive into `dest`, creating it if needed
+///
+/// Returns how many files were written. Entries that would land outside
+/// `dest` (absolute paths, `..`) are left out.
+pub fn extract_local(archive: &Path, dest: &Path, policy: OverwritePolicy) -> AppResult<usize> {
+    let kind = ArchiveKind::from_path(archive)
+        .ok_or_else(|| AppError::FileError(format!("{} is not a tar or zip archive", archive.display())))?;
+    fs::create_dir_all(dest)?;
+
+    match kind {
+        ArchiveKind::Tar => extract_tar(File::open(archive)?, dest, policy),
+        ArchiveKind::TarGz => extract_tar(GzDecoder::new(File::open(archive)?), dest, policy),
+        ArchiveKind::Zip => extract_zip(File::open(archive)?, dest, policy),
+    }
+}
+
+fn extract_tar<R: io::Read>(reader: R, dest: &Path, policy: OverwritePolicy) -> AppResult<usize> {
+    let mut archive = tar::Archive::new(reader);
+    let mut written = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let is_file = entry.header().entry_type().is_file();
+        let target = dest.join(entry.path()?);
+        if is_file && target.exists() && policy == OverwritePolicy::Skip {
+            continue;
+        }
+        // unpack_in refuses entries that would escape `dest`
+        if entry.unpack_in(dest)? && is_file {
+            written += 1;
+        }
+    }
+    Ok(written)
+}
+
+fn extract_zip(file: File, dest: &Path, policy: OverwritePolicy) -> AppResult<usize> {
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| AppError::FileError(e.to_string()))?;
+    let mut written = 0;
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| AppError::FileError(e.to_string()))?;
+        let Some(relative) = entry.enclosed_name().map(PathBuf::from) else {
+            log::warn!("Skipped {}: outside the destination", entry.name());
+            continue;
+        };
+        let target = dest.join(relative);
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if target.exists() && policy == OverwritePolicy::Skip {
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        io::copy(&mut entry, &mut File::create(&target)?)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+/// Shell command extracting an archive on the Pi into `dest` with `tar`
+/// or `unzip`
+pub fn remote_extract_command(archive: &Path, dest: &Path, policy: OverwritePolicy) -> AppResult<String> {
+    let kind = ArchiveKind::from_path(archive)
+        .ok_or_else(|| AppError::FileError(format!("{} is not a tar or zip archive", archive.display())))?;
+    let archive = shell_quote_path(archive);
+    let dest = shell_quote_path(dest);
+
+    let extract = match (kind, policy) {
+        (ArchiveKind::Zip, OverwritePolicy::Overwrite) => format!("unzip -q -o {} -d {}", archive, dest),
+        (ArchiveKind::Zip, OverwritePolicy::Skip) => format!("unzip -q -n {} -d {}", archive, dest),
+        (_, policy) => format!(
+            "tar -x{}f {} -C {}{}",
+            if kind == ArchiveKind::TarGz { "z" } else { "" },
+            archive,
+            dest,
+            if policy == OverwritePolicy::Skip { " --skip-old-files" } else { "" }
+        ),
+    };
+    Ok(format!("mkdir -p {} && {}", dest, extract))
+}
@@ -46,7 +46,7 @@ pub fn is_text_file(path: &Path) -> bool {
     if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
         matches!(
             ext.to_lowercase().as_str(),
-            "txt" | "md" | "csv" | "json" | "xml" | "html" | "css" | "log"
+            "txt" | "md" | "markdown" | "mdown" | "csv" | "tsv" | "json" | "xml" | "html" | "css" | "log"
         )
     } else {
         false
@@ -135,10 +135,11 @@ fn get_mime_type_for_path(path: &Path) -> Option<String> {
             
             // Text
             "txt" => Some("text/plain".to_string()),
-            "md" => Some("text/markdown".to_string()),
+            "md" | "markdown" | "mdown" => Some("text/markdown".to_string()),
             "html" | "htm" => Some("text/html".to_string()),
             "css" => Some("text/css".to_string()),
             "csv" => Some("text/csv".to_string()),
+            "tsv" => Some("text/tab-separated-values".to_string()),
             "xml" => Some("text/xml".to_string()),
             
             // Code
@@ -0,0 +1,256 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::config::RetryPolicy;
+use crate::core::file::local_ops::{self, CAN_RESTORE_FROM_TRASH};
+use crate::core::file::checked_name;
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::method::{RemoteEntry, TransferError, TransferMethod};
+use crate::transfer::remote_trash::move_to_remote_trash;
+use crate::transfer::ssh::shell_quote_path;
+
+/// Where a file browser pane lists and changes files
+///
+/// The browser only talks to this trait, so another source of files, such
+/// as an archive or a cloud bucket, can be browsed by implementing it.
+pub trait FileSystem: Send {
+    /// Short description for logs and the browser's debug info
+    fn name(&self) -> String;
+
+    /// Whether the files are on another machine; such listings run in the
+    /// background and may be cached and retried
+    fn is_remote(&self) -> bool;
+
+    /// List `dir`, handing its entries to `on_chunk` in batches; gives up
+    /// with TimedOut after `timeout` or with Cancelled once `cancel` is set
+    fn list(
+        &self,
+        dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError>;
+
+    /// Rename an entry within its directory, returning the new path
+    fn rename(&self, path: &Path, new_name: &str) -> AppResult<PathBuf>;
+
+    /// Move entries to the trash
+    fn move_to_trash(&self, paths: &[PathBuf]) -> AppResult<()>;
+
+    /// Delete an entry for good, folders with everything in them
+    fn delete(&self, path: &Path) -> AppResult<()>;
+
+    /// Copy a file to `local_path` on this computer
+    fn download(&self, path: &Path, local_path: &Path) -> AppResult<()>;
+
+    /// Key listings are cached under; None when they aren't worth caching
+    fn cache_key(&self) -> Option<String> {
+        None
+    }
+
+    /// How failed listings are retried
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy { retries: 0, ..RetryPolicy::default() }
+    }
+
+    /// The same files seen through sudo, offered after a listing was
+    /// refused; None when that isn't possible
+    fn with_sudo(&self) -> Option<Box<dyn FileSystem>> {
+        None
+    }
+
+    /// Whether entries moved to the trash can be put back from the browser
+    fn can_restore_from_trash(&self) -> bool {
+        false
+    }
+
+    /// The connection behind a remote file system, for what only a transfer
+    /// method can do, like thumbnails and editing in place
+    fn transfer_method(&self) -> Option<&dyn TransferMethod> {
+        None
+    }
+
+    /// Password for the connection, once the user has typed it
+    fn set_password(&mut self, _password: &str) {}
+
+    /// Create an independent copy for use on a worker thread
+    fn clone_box(&self) -> Box<dyn FileSystem>;
+}
+
+/// The files of this computer
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalFs;
+
+impl FileSystem for LocalFs {
+    fn name(&self) -> String {
+        "Local files".to_string()
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    // Local directories are read at once; the timeout and cancel don't apply
+    fn list(
+        &self,
+        dir: &Path,
+        _timeout: Duration,
+        _cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        let entries = fs::read_dir(dir).map_err(|e| local_error(dir, e))?;
+        let mut listed = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let metadata = entry.metadata().ok();
+            let link_target = metadata.as_ref()
+                .filter(|m| m.file_type().is_symlink())
+                .and_then(|_| fs::read_link(&path).ok())
+                .map(|target| target.to_string_lossy().to_string());
+
+            listed.push(RemoteEntry {
+                name: entry.file_name(),
+                // Follows links, so a link to a folder opens like one
+                is_dir: path.is_dir(),
+                size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+                modified: metadata
+                    .and_then(|m| m.modified().ok())
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|since| since.as_secs() as i64),
+                link_target,
+            });
+        }
+        on_chunk(listed);
+        Ok(())
+    }
+
+    fn rename(&self, path: &Path, new_name: &str) -> AppResult<PathBuf> {
+        local_ops::rename_entry(path, new_name)
+    }
+
+    fn move_to_trash(&self, paths: &[PathBuf]) -> AppResult<()> {
+        local_ops::move_to_trash(paths)
+    }
+
+    fn delete(&self, path: &Path) -> AppResult<()> {
+        match fs::symlink_metadata(path)?.is_dir() {
+            true => fs::remove_dir_all(path)?,
+            false => fs::remove_file(path)?,
+        }
+        Ok(())
+    }
+
+    fn download(&self, path: &Path, local_path: &Path) -> AppResult<()> {
+        fs::copy(path, local_path)?;
+        Ok(())
+    }
+
+    fn can_restore_from_trash(&self) -> bool {
+        CAN_RESTORE_FROM_TRASH
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(*self)
+    }
+}
+
+fn local_error(dir: &Path, e: io::Error) -> TransferError {
+    let message = format!("{}: {}", dir.display(), e);
+    match e.kind() {
+        io::ErrorKind::NotFound => TransferError::FileNotFound(message),
+        io::ErrorKind::PermissionDenied => TransferError::PermissionDenied(message),
+        _ => TransferError::TransferFailed(message),
+    }
+}
+
+/// The files of a remote host, reached through a transfer method; changes
+/// are made with shell commands on the host
+pub struct RemoteFs {
+    method: Box<dyn TransferMethod>,
+}
+
+impl RemoteFs {
+    pub fn new(method: Box<dyn TransferMethod>) -> Self {
+        Self { method }
+    }
+
+    // Run a command that changes files on the host
+    fn run(&self, command: &str) -> AppResult<()> {
+        log::info!("Running on the Pi: {}", command);
+        let output = self.method.exec(command)?;
+        if output.success() {
+            Ok(())
+        } else {
+            Err(AppError::FileError(output.stderr.trim().to_string()))
+        }
+    }
+}
+
+impl FileSystem for RemoteFs {
+    fn name(&self) -> String {
+        self.method.get_name().to_string()
+    }
+
+    fn is_remote(&self) -> bool {
+        true
+    }
+
+    fn list(
+        &self,
+        dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        self.method.list_files_streaming(dir, timeout, cancel, on_chunk)
+    }
+
+    // `mv -n` never replaces an entry that already has the new name
+    fn rename(&self, path: &Path, new_name: &str) -> AppResult<PathBuf> {
+        let target = path.with_file_name(checked_name(new_name)?);
+        self.run(&format!("mv -n -- {} {}", shell_quote_path(path), shell_quote_path(&target)))?;
+        Ok(target)
+    }
+
+    fn move_to_trash(&self, paths: &[PathBuf]) -> AppResult<()> {
+        log::info!("Moving {} item(s) to the Pi's trash", paths.len());
+        move_to_remote_trash(self.method.as_ref(), paths)?;
+        Ok(())
+    }
+
+    fn delete(&self, path: &Path) -> AppResult<()> {
+        self.run(&format!("rm -rf -- {}", shell_quote_path(path)))
+    }
+
+    fn download(&self, path: &Path, local_path: &Path) -> AppResult<()> {
+        self.method.download_file(path, local_path)?;
+        Ok(())
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(self.method.connection_key())
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.method.retry_policy()
+    }
+
+    fn with_sudo(&self) -> Option<Box<dyn FileSystem>> {
+        self.method.sudo_method().map(|method| Box::new(RemoteFs::new(method)) as Box<dyn FileSystem>)
+    }
+
+    fn transfer_method(&self) -> Option<&dyn TransferMethod> {
+        Some(self.method.as_ref())
+    }
+
+    fn set_password(&mut self, password: &str) {
+        self.method.set_password(password);
+    }
+
+    fn clone_box(&self) -> Box<dyn FileSystem> {
+        Box::new(RemoteFs::new(self.method.clone_box()))
+    }
+}
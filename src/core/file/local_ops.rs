@@ -0,0 +1,140 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::core::utils::{AppError, AppResult};
+
+/// Whether files moved to the trash can be put back from here; the
+/// macOS trash offers no way to list or restore its items
+pub const CAN_RESTORE_FROM_TRASH: bool = cfg!(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+));
+
+/// Move files and directories to the platform trash
+pub fn move_to_trash(paths: &[PathBuf]) -> AppResult<()> {
+    trash::delete_all(paths).map_err(|e| AppError::FileError(format!("Could not move to trash: {}", e)))
+}
+
+/// Put the most recently trashed copy of each path back where it was
+///
+/// Returns how many items were restored. Paths that no longer exist in the
+/// trash, or that something else has taken the place of, are skipped.
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+pub fn restore_from_trash(paths: &[PathBuf]) -> AppResult<usize> {
+    use trash::os_limited;
+
+    let items = os_limited::list()
+        .map_err(|e| AppError::FileError(format!("Could not read the trash: {}", e)))?;
+
+    let mut newest: Vec<trash::TrashItem> = Vec::new();
+    for item in items {
+        let original = item.original_path();
+        if !paths.contains(&original) || original.exists() {
+            continue;
+        }
+        match newest.iter_mut().find(|kept| kept.original_path() == original) {
+            Some(kept) if kept.time_deleted < item.time_deleted => *kept = item,
+            Some(_) => {},
+            None => newest.push(item),
+        }
+    }
+
+    let restored = newest.len();
+    if restored > 0 {
+        os_limited::restore_all(newest)
+            .map_err(|e| AppError::FileError(format!("Could not restore from trash: {}", e)))?;
+    }
+    Ok(restored)
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+pub fn restore_from_trash(_paths: &[PathBuf]) -> AppResult<usize> {
+    Err(AppError::FileError("Restoring from the trash is not supported on this system".to_string()))
+}
+
+/// Rename a file or directory within its directory, returning the new path
+pub fn rename_entry(path: &Path, new_name: &str) -> AppResult<PathBuf> {
+    let new_name = checked_name(new_name)?;
+    let target = path.with_file_name(new_name);
+    if target.exists() {
+        return Err(AppError::FileError(format!("{} already exists", target.display())));
+    }
+
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+/// Copy a file or directory next to itself as "name copy.ext",
+/// "name copy 2.ext" and so on, returning the path of the copy
+pub fn duplicate_entry(path: &Path) -> AppResult<PathBuf> {
+    let target = duplicate_name(path);
+    if path.is_dir() {
+        copy_dir_recursive(path, &target)?;
+    } else {
+        fs::copy(path, &target)?;
+    }
+    Ok(target)
+}
+
+/// Create a directory named `name` inside `parent`, returning its path
+pub fn create_folder(parent: &Path, name: &str) -> AppResult<PathBuf> {
+    let target = parent.join(checked_name(name)?);
+    if target.exists() {
+        return Err(AppError::FileError(format!("{} already exists", target.display())));
+    }
+
+    fs::create_dir(&target)?;
+    Ok(target)
+}
+
+/// Trimmed name, rejected when empty or when it would leave the directory
+pub fn checked_name(name: &str) -> AppResult<&str> {
+    let name = name.trim();
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') || name.contains('\\') {
+        return Err(AppError::FileError(format!("\"{}\" is not a valid name", name)));
+    }
+    Ok(name)
+}
+
+/// First free "<stem> copy[ N][.ext]" path next to `path`
+fn duplicate_name(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = if path.is_dir() {
+        None
+    } else {
+        path.extension().map(|e| e.to_string_lossy().to_string())
+    };
+
+    (1..)
+        .map(|n| {
+            let name = if n == 1 { format!("{} copy", stem) } else { format!("{} copy {}", stem, n) };
+            let name = match &extension {
+                Some(ext) => format!("{}.{}", name, ext),
+                None => name,
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !candidate.exists())
+        .expect("an unused name always exists")
+}
+
+fn copy_dir_recursive(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir(target)?;
+    for entry in fs::read_dir(source)? {
+        let entry = entry?;
+        let destination = target.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &destination)?;
+        } else {
+            fs::copy(entry.path(), &destination)?;
+        }
+    }
+    Ok(())
+}
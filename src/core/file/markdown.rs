@@ -0,0 +1,31 @@
+use std::path::Path;
+
+use pulldown_cmark::{html, Options, Parser};
+
+/// Check if a file is a Markdown document
+pub fn is_markdown_file(path: &Path) -> bool {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        matches!(
+            ext.to_lowercase().as_str(),
+            "md" | "markdown" | "mdown"
+        )
+    } else {
+        false
+    }
+}
+
+/// Render Markdown source into the HTML subset understood by FLTK's HelpView
+pub fn markdown_to_html(source: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let parser = Parser::new_ext(source, options);
+
+    let mut body = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut body, parser);
+
+    // HelpView expects a complete document to apply default styling
+    format!("<html><body>{}</body></html>", body)
+}
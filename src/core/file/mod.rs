@@ -1,6 +1,53 @@
 pub mod file_type;
 pub mod preview;
+pub mod markdown;
+pub mod table;
+pub mod local_ops;
+pub mod batch_rename;
+pub mod extract;
+pub mod selection_size;
+pub mod filesystem;
 
 // Re-export commonly used items for convenience
 pub use file_type::{FileType, FileTypeInfo, is_image_file, get_file_type_info};
-pub use preview::{PreviewInfo, get_preview_info, get_text_preview, create_temp_file};
\ No newline at end of file
+pub use preview::{
+    PreviewInfo,
+    TextChunk,
+    get_preview_info,
+    get_text_preview,
+    create_temp_file,
+    temp_download_path,
+    temp_root,
+    read_lines_from,
+    read_last_lines,
+    needs_streamed_preview,
+    last_lines_of,
+    lines_from_bytes,
+    remote_file_size_command,
+    remote_range_command,
+    remote_tail_command,
+    REMOTE_CHUNK_BYTES
+};
+pub use markdown::{is_markdown_file, markdown_to_html};
+pub use table::{TableData, is_table_file, read_table_preview, MAX_TABLE_PREVIEW_ROWS};
+pub use local_ops::{
+    CAN_RESTORE_FROM_TRASH,
+    move_to_trash,
+    restore_from_trash,
+    rename_entry,
+    duplicate_entry,
+    create_folder,
+    checked_name
+};
+pub use batch_rename::{
+    PlannedRename,
+    RenameCandidate,
+    TimeSource,
+    DEFAULT_RENAME_TEMPLATE,
+    RENAME_TOKENS,
+    plan_renames,
+    check_plan
+};
+pub use extract::{ArchiveKind, OverwritePolicy, extract_local, remote_extract_command};
+pub use selection_size::{SelectionSize, measure_local, remote_size_command, parse_remote_size};
+pub use filesystem::{FileSystem, LocalFs, RemoteFs};
@@ -1,12 +1,16 @@
 use std::path::{Path, PathBuf};
 use std::fs;
-use std::io::{self, Read};
+use std::io::{self, Read, BufRead, BufReader, Seek, SeekFrom};
 
 use super::file_type::{FileType, get_file_type_info};
+use crate::transfer::ssh::shell_quote_path;
 
 /// Maximum size for text files to be previewed (5MB)
 const MAX_TEXT_PREVIEW_SIZE: u64 = 5 * 1024 * 1024;
 
+/// Bytes of a file on the Pi read per streamed chunk
+pub const REMOTE_CHUNK_BYTES: u64 = 512 * 1024;
+
 /// Information about a previewed file
 pub struct PreviewInfo {
     /// The path to the file
@@ -52,6 +56,168 @@ pub fn get_text_preview(path: &Path) -> Result<String, String> {
     }
 }
 
+/// A run of lines read from a (potentially very large) text file
+pub struct TextChunk {
+    /// The decoded text, including line terminators
+    pub text: String,
+    /// Number of lines in `text`
+    pub lines: usize,
+    /// Byte offset just past the last line read
+    pub next_offset: u64,
+    /// Whether the end of the file was reached
+    pub eof: bool,
+}
+
+/// Read up to `max_lines` lines starting at byte `offset`
+///
+/// Invalid UTF-8 is replaced rather than rejected, since log files
+/// frequently contain stray binary data.
+pub fn read_lines_from(path: &Path, offset: u64, max_lines: usize) -> io::Result<TextChunk> {
+    let mut file = fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    
+    let mut reader = BufReader::new(file);
+    let mut text = String::new();
+    let mut line = Vec::new();
+    let mut lines = 0;
+    let mut position = offset;
+    
+    while lines < max_lines {
+        line.clear();
+        let n = reader.read_until(b'\n', &mut line)?;
+        if n == 0 {
+            return Ok(TextChunk { text, lines, next_offset: position, eof: true });
+        }
+        
+        position += n as u64;
+        text.push_str(&String::from_utf8_lossy(&line));
+        lines += 1;
+    }
+    
+    let eof = reader.fill_buf()?.is_empty();
+    
+    Ok(TextChunk { text, lines, next_offset: position, eof })
+}
+
+/// Read the last `max_lines` lines of a file without loading all of it
+pub fn read_last_lines(path: &Path, max_lines: usize) -> io::Result<TextChunk> {
+    const BLOCK_SIZE: u64 = 64 * 1024;
+    
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    
+    // Read backwards block by block until enough line breaks are buffered
+    let mut start = len;
+    let mut buffer: Vec<u8> = Vec::new();
+    
+    while start > 0 {
+        let read_size = BLOCK_SIZE.min(start);
+        start -= read_size;
+        
+        file.seek(SeekFrom::Start(start))?;
+        let mut block = vec![0; read_size as usize];
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&buffer);
+        buffer = block;
+        
+        let newlines = buffer.iter().filter(|&&b| b == b'\n').count();
+        if newlines > max_lines || buffer.len() as u64 > MAX_TEXT_PREVIEW_SIZE {
+            break;
+        }
+    }
+    
+    Ok(last_lines_of(&buffer, max_lines, len))
+}
+
+/// The last `max_lines` lines of `buffer`, which holds the end of a file of
+/// `len` bytes
+pub fn last_lines_of(buffer: &[u8], max_lines: usize, len: u64) -> TextChunk {
+    // Ignore the terminator of the final line when counting back
+    let end = if buffer.ends_with(b"\n") { buffer.len() - 1 } else { buffer.len() };
+    
+    let mut cut = 0;
+    let mut seen = 0;
+    for i in (0..end).rev() {
+        if buffer[i] == b'\n' {
+            seen += 1;
+            if seen == max_lines {
+                cut = i + 1;
+                break;
+            }
+        }
+    }
+    
+    let tail = &buffer[cut..];
+    let lines = tail.iter().filter(|&&b| b == b'\n').count()
+        + if tail.ends_with(b"\n") || tail.is_empty() { 0 } else { 1 };
+    
+    TextChunk {
+        text: String::from_utf8_lossy(tail).into_owned(),
+        lines,
+        next_offset: len,
+        eof: true,
+    }
+}
+
+/// Up to `max_lines` whole lines from the start of `bytes`, which were read
+/// from byte `offset` of a file of `len` bytes
+///
+/// A line cut off by the end of `bytes` is left for the next chunk, unless
+/// it is the file's last line or longer than the whole chunk.
+pub fn lines_from_bytes(bytes: &[u8], offset: u64, len: u64, max_lines: usize) -> TextChunk {
+    let reaches_end = offset + bytes.len() as u64 >= len;
+    
+    let mut consumed = 0;
+    let mut lines = 0;
+    for (i, _) in bytes.iter().enumerate().filter(|(_, &b)| b == b'\n') {
+        if lines == max_lines {
+            break;
+        }
+        consumed = i + 1;
+        lines += 1;
+    }
+    if lines < max_lines && consumed < bytes.len() && (reaches_end || consumed == 0) {
+        consumed = bytes.len();
+        lines += 1;
+    }
+    
+    let next_offset = offset + consumed as u64;
+    TextChunk {
+        text: String::from_utf8_lossy(&bytes[..consumed]).into_owned(),
+        lines,
+        next_offset,
+        eof: next_offset >= len,
+    }
+}
+
+/// Shell command printing the size in bytes of a file on the Pi
+pub fn remote_file_size_command(path: &Path) -> String {
+    format!("stat -L -c %s -- {}", shell_quote_path(path))
+}
+
+/// Shell command printing up to `max_bytes` of a file on the Pi from byte
+/// `offset`, without reading what comes before
+pub fn remote_range_command(path: &Path, offset: u64, max_bytes: u64) -> String {
+    format!(
+        "dd if={} iflag=skip_bytes,count_bytes skip={} count={} bs=64K status=none",
+        shell_quote_path(path),
+        offset,
+        max_bytes
+    )
+}
+
+/// Shell command printing the last `max_bytes` of a file on the Pi
+pub fn remote_tail_command(path: &Path, max_bytes: u64) -> String {
+    format!("tail -c {} -- {}", max_bytes, shell_quote_path(path))
+}
+
+/// Whether a file is too large to be previewed in one piece
+pub fn needs_streamed_preview(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|m| m.len() > MAX_TEXT_PREVIEW_SIZE)
+        .unwrap_or(false)
+}
+
 /// Get preview info for a file
 pub fn get_preview_info(path: &Path) -> PreviewInfo {
     let file_type_info = get_file_type_info(path);
@@ -87,6 +253,25 @@ pub fn create_temp_file(suffix: &str) -> io::Result<PathBuf> {
     Ok(temp_path)
 }
 
+/// Folder under the system's temporary directory holding the app's
+/// temporary files; old ones are deleted by the storage cleanup
+pub fn temp_root() -> PathBuf {
+    std::env::temp_dir().join("pi_remote_manager")
+}
+
+/// Local path a remote file can be downloaded to for opening, keeping its
+/// name so the opening application shows it; each call gets a fresh directory
+pub fn temp_download_path(file_name: &str) -> io::Result<PathBuf> {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    
+    let dir = temp_root().join(timestamp.to_string());
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(file_name))
+}
+
 /// Find all previewable files in a directory
 pub fn find_previewable_files(dir: &Path) -> Vec<PathBuf> {
     let mut files = Vec::new();
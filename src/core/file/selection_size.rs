@@ -0,0 +1,54 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::transfer::ssh::shell_quote_path;
+
+/// Files and bytes in a selection, with folders counted recursively
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SelectionSize {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Count the files under the local `paths`, descending into folders
+///
+/// Symlinks are not followed and unreadable entries are skipped, so the
+/// result is a lower bound when permissions get in the way.
+pub fn measure_local(paths: &[PathBuf]) -> SelectionSize {
+    let mut size = SelectionSize::default();
+    let mut pending: Vec<PathBuf> = paths.to_vec();
+
+    while let Some(path) = pending.pop() {
+        let Ok(metadata) = fs::symlink_metadata(&path) else { continue };
+        if metadata.is_dir() {
+            if let Ok(entries) = fs::read_dir(&path) {
+                pending.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        } else if metadata.is_file() {
+            size.files += 1;
+            size.bytes += metadata.len();
+        }
+    }
+    size
+}
+
+/// Command printing the file count and total bytes under the remote
+/// `paths` as one line, e.g. `120 52428800`
+///
+/// The sizes are summed on the Pi so huge trees don't come back file by
+/// file. `printf` keeps awk from switching to exponent notation.
+pub fn remote_size_command(paths: &[PathBuf]) -> String {
+    let quoted: Vec<String> = paths.iter().map(|path| shell_quote_path(path)).collect();
+    format!(
+        "find {} -type f -printf '%s\\n' 2>/dev/null | awk '{{ n++; s += $1 }} END {{ printf \"%d %.0f\\n\", n, s }}'",
+        quoted.join(" ")
+    )
+}
+
+/// Parse the output of `remote_size_command`
+pub fn parse_remote_size(output: &str) -> Option<SelectionSize> {
+    let mut parts = output.split_whitespace();
+    let files = parts.next()?.parse().ok()?;
+    let bytes = parts.next()?.parse().ok()?;
+    Some(SelectionSize { files, bytes })
+}
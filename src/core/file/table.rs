@@ -0,0 +1,95 @@
+use std::path::Path;
+
+/// Maximum number of data rows loaded for a table preview
+pub const MAX_TABLE_PREVIEW_ROWS: usize = 500;
+
+/// Parsed contents of a delimited (CSV/TSV) file
+#[derive(Debug, Clone, Default)]
+pub struct TableData {
+    /// Column headers taken from the first record
+    pub headers: Vec<String>,
+    /// Data rows (may be ragged for malformed files)
+    pub rows: Vec<Vec<String>>,
+    /// Whether more rows exist than were loaded
+    pub truncated: bool,
+}
+
+impl TableData {
+    /// Number of columns needed to show every header and cell
+    pub fn column_count(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.len())
+            .chain(std::iter::once(self.headers.len()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Get a cell value, or an empty string for missing cells
+    pub fn cell(&self, row: usize, col: usize) -> &str {
+        self.rows
+            .get(row)
+            .and_then(|r| r.get(col))
+            .map(|s| s.as_str())
+            .unwrap_or("")
+    }
+
+    /// Get a header value, falling back to a generated column name
+    pub fn header(&self, col: usize) -> String {
+        match self.headers.get(col) {
+            Some(h) if !h.is_empty() => h.clone(),
+            _ => format!("Column {}", col + 1),
+        }
+    }
+}
+
+/// Check if a file is a delimited table (CSV/TSV)
+pub fn is_table_file(path: &Path) -> bool {
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        matches!(ext.to_lowercase().as_str(), "csv" | "tsv")
+    } else {
+        false
+    }
+}
+
+/// Read up to `max_rows` rows from a CSV or TSV file
+pub fn read_table_preview(path: &Path, max_rows: usize) -> Result<TableData, String> {
+    let is_tsv = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("tsv"))
+        .unwrap_or(false);
+
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(if is_tsv { b'\t' } else { b',' })
+        .has_headers(true)
+        .flexible(true)
+        .from_path(path)
+        .map_err(|e| format!("Failed to open table: {}", e))?;
+
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Failed to read header row: {}", e))?
+        .iter()
+        .map(|h| h.to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut truncated = false;
+
+    for record in reader.records() {
+        if rows.len() >= max_rows {
+            truncated = true;
+            break;
+        }
+
+        let record = record.map_err(|e| format!("Failed to parse row {}: {}", rows.len() + 1, e))?;
+        rows.push(record.iter().map(|field| field.to_string()).collect());
+    }
+
+    Ok(TableData {
+        headers,
+        rows,
+        truncated,
+    })
+}
@@ -0,0 +1,126 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use ::image::codecs::gif::{GifEncoder, Repeat};
+use ::image::imageops::FilterType;
+use ::image::{Delay, Frame, RgbaImage};
+
+use crate::core::image::processor::ProcessingError;
+
+/// File formats an animation can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    /// Plays everywhere, 256 colours per frame
+    Gif,
+    /// Animated PNG: full colour, larger files
+    Apng,
+}
+
+impl AnimationFormat {
+    pub const ALL: [AnimationFormat; 2] = [AnimationFormat::Gif, AnimationFormat::Apng];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Gif => "GIF",
+            Self::Apng => "Animated PNG",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gif => "gif",
+            Self::Apng => "png",
+        }
+    }
+}
+
+/// How frames are put together into an animation
+#[derive(Debug, Clone)]
+pub struct AnimationSettings {
+    pub format: AnimationFormat,
+    /// How long each frame shows, in milliseconds
+    pub frame_delay_ms: u16,
+    /// Times the animation plays; None repeats it forever
+    pub loop_count: Option<u16>,
+    /// Longest side of the animation in pixels
+    pub max_dimension: u32,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        Self {
+            format: AnimationFormat::Gif,
+            frame_delay_ms: 200,
+            loop_count: None,
+            max_dimension: 640,
+        }
+    }
+}
+
+/// Write `frames`, in order, as an animation to `output`, calling
+/// `progress` with the number of frames added so far
+///
+/// Every frame is scaled to the size the first one gets when fitted into
+/// `max_dimension`.
+pub fn assemble_animation(
+    frames: &[PathBuf],
+    output: &Path,
+    settings: &AnimationSettings,
+    progress: &dyn Fn(usize)
+) -> Result<(), ProcessingError> {
+    let failed = |path: &Path, e: &dyn std::fmt::Display| ProcessingError::ProcessingFailed(format!("{}: {}", path.display(), e));
+    let Some(first) = frames.first() else {
+        return Err(ProcessingError::ProcessingFailed("No frames to animate".to_string()));
+    };
+
+    let first_image = ::image::open(first).map_err(|e| failed(first, &e))?;
+    let max = settings.max_dimension.max(1);
+    let (width, height) = if first_image.width() > max || first_image.height() > max {
+        let fitted = first_image.resize(max, max, FilterType::Triangle);
+        (fitted.width(), fitted.height())
+    } else {
+        (first_image.width(), first_image.height())
+    };
+    let load = |path: &Path| -> Result<RgbaImage, ProcessingError> {
+        let image = ::image::open(path).map_err(|e| failed(path, &e))?;
+        Ok(image.resize_exact(width, height, FilterType::Triangle).to_rgba8())
+    };
+
+    let file = File::create(output).map_err(|e| failed(output, &e))?;
+    match settings.format {
+        AnimationFormat::Gif => {
+            let mut encoder = GifEncoder::new_with_speed(BufWriter::new(file), 10);
+            let repeat = match settings.loop_count {
+                None => Repeat::Infinite,
+                // GIF counts repeats after the first play
+                Some(plays) => Repeat::Finite(plays.saturating_sub(1)),
+            };
+            encoder.set_repeat(repeat).map_err(|e| failed(output, &e))?;
+            let delay = Delay::from_numer_denom_ms(settings.frame_delay_ms as u32, 1);
+            for (index, frame) in frames.iter().enumerate() {
+                encoder
+                    .encode_frame(Frame::from_parts(load(frame)?, 0, 0, delay))
+                    .map_err(|e| failed(output, &e))?;
+                progress(index + 1);
+            }
+            Ok(())
+        },
+        AnimationFormat::Apng => {
+            let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            // Zero plays means forever
+            encoder
+                .set_animated(frames.len() as u32, settings.loop_count.unwrap_or(0) as u32)
+                .and_then(|_| encoder.set_frame_delay(settings.frame_delay_ms, 1000))
+                .map_err(|e| failed(output, &e))?;
+            let mut writer = encoder.write_header().map_err(|e| failed(output, &e))?;
+            for (index, frame) in frames.iter().enumerate() {
+                writer.write_image_data(&load(frame)?).map_err(|e| failed(output, &e))?;
+                progress(index + 1);
+            }
+            writer.finish().map_err(|e| failed(output, &e))
+        },
+    }
+}
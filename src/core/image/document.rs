@@ -0,0 +1,139 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ::image::DynamicImage;
+
+use crate::core::image::exif::jpeg_exif_segment;
+use crate::core::image::processor::{ImageFormat, ImageProcessingService, ProcessingError};
+
+/// Versions kept for undo; each is a full copy of the pixels
+pub const UNDO_LIMIT: usize = 10;
+
+/// An image open in the Image Processing tab: its decoded pixels, what
+/// was read with them and whether they changed since
+///
+/// Previewing, applying operations, undo and saving all work on the one
+/// copy in memory, so nothing reads the file again after it was opened.
+pub struct ImageDocument {
+    /// File the pixels were read from, or last saved to
+    path: PathBuf,
+    /// Format of that file
+    format: ImageFormat,
+    image: DynamicImage,
+    /// APP1 Exif segment of a JPEG original, written back when saving as JPEG
+    exif: Option<Vec<u8>>,
+    /// Earlier versions of the pixels, oldest first
+    history: Vec<DynamicImage>,
+    /// Length of `history` when the pixels matched the file; None once
+    /// that version is gone
+    saved_at: Option<usize>,
+}
+
+impl ImageDocument {
+    /// Read and decode `path`
+    pub fn open(path: &Path) -> Result<Self, ProcessingError> {
+        let failed = |e: &dyn std::fmt::Display| ProcessingError::ProcessingFailed(format!("{}: {}", path.display(), e));
+
+        let bytes = fs::read(path).map_err(|e| failed(&e))?;
+        let image = ::image::load_from_memory(&bytes).map_err(|e| failed(&e))?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            format: format_of(path),
+            image,
+            exif: jpeg_exif_segment(&bytes).map(|segment| segment.to_vec()),
+            history: Vec::new(),
+            saved_at: Some(0),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn image(&self) -> &DynamicImage {
+        &self.image
+    }
+
+    /// Format of the file the document refers to
+    pub fn format(&self) -> &ImageFormat {
+        &self.format
+    }
+
+    /// Width and height in pixels
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.image.width(), self.image.height())
+    }
+
+    /// Whether the pixels changed since the image was opened or saved
+    pub fn is_dirty(&self) -> bool {
+        self.saved_at != Some(self.history.len())
+    }
+
+    /// Replace the pixels with an edited version, keeping the current one
+    /// for undo
+    pub fn replace(&mut self, image: DynamicImage) {
+        // A saved version that was undone can't be reached again
+        if self.saved_at.is_some_and(|saved| saved > self.history.len()) {
+            self.saved_at = None;
+        }
+
+        let previous = std::mem::replace(&mut self.image, image);
+        self.history.push(previous);
+        if self.history.len() > UNDO_LIMIT {
+            self.history.remove(0);
+            self.saved_at = self.saved_at.and_then(|saved| saved.checked_sub(1));
+        }
+    }
+
+    /// Go back to the version before the last edit; false when there is none
+    pub fn undo(&mut self) -> bool {
+        match self.history.pop() {
+            Some(previous) => {
+                self.image = previous;
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Write the pixels to `output` with the processor of the service's
+    /// factory `factory_index`; the document refers to `output` from then on
+    ///
+    /// An unchanged image saved in its own format is copied as it is, so
+    /// nothing is lost to encoding again.
+    pub fn save_as(
+        &mut self,
+        output: &Path,
+        service: &ImageProcessingService,
+        factory_index: usize
+    ) -> Result<(), ProcessingError> {
+        let format = service
+            .get_factories()
+            .get(factory_index)
+            .ok_or(ProcessingError::NoProcessorAvailable)?
+            .create_processor()
+            .get_format();
+
+        if !self.is_dirty() && format == self.format {
+            if output != self.path {
+                fs::copy(&self.path, output)
+                    .map_err(|e| ProcessingError::ProcessingFailed(format!("{}: {}", output.display(), e)))?;
+            }
+        } else {
+            service.encode_image(&self.image, self.exif.as_deref(), output, factory_index)?;
+        }
+
+        log::info!("Saved {} as {}", self.path.display(), output.display());
+        self.path = output.to_path_buf();
+        self.format = format;
+        self.saved_at = Some(self.history.len());
+        Ok(())
+    }
+}
+
+fn format_of(path: &Path) -> ImageFormat {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map_or(ImageFormat::Unknown, ImageFormat::from_extension)
+}
@@ -0,0 +1,142 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+/// Bytes read from the start of a file when looking for its EXIF block;
+/// cameras put it right after the JPEG header
+pub const EXIF_SCAN_BYTES: usize = 128 * 1024;
+
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+/// When the photo in `path` was taken, from its EXIF data
+pub fn read_capture_time(path: &Path) -> Option<NaiveDateTime> {
+    let mut head = Vec::with_capacity(EXIF_SCAN_BYTES);
+    File::open(path).ok()?.take(EXIF_SCAN_BYTES as u64).read_to_end(&mut head).ok()?;
+    capture_time(&head)
+}
+
+/// Capture time in the EXIF data of a JPEG or TIFF file starting with
+/// `bytes`
+///
+/// DateTimeOriginal is preferred; the plain DateTime tag, which editors
+/// update, is the fallback.
+pub fn capture_time(bytes: &[u8]) -> Option<NaiveDateTime> {
+    let tiff = if bytes.starts_with(&[0xFF, 0xD8]) {
+        jpeg_exif_block(bytes)?
+    } else {
+        bytes
+    };
+    let tiff = Tiff::new(tiff)?;
+
+    let ifd0 = tiff.u32(4)? as usize;
+    let original = tiff
+        .find_tag(ifd0, TAG_EXIF_IFD)
+        .and_then(|(_, _, value)| tiff.find_tag(value as usize, TAG_DATE_TIME_ORIGINAL))
+        .and_then(|entry| tiff.ascii(entry));
+    let text = original.or_else(|| tiff.find_tag(ifd0, TAG_DATE_TIME).and_then(|entry| tiff.ascii(entry)))?;
+
+    NaiveDateTime::parse_from_str(text.trim_end_matches('\0').trim(), "%Y:%m:%d %H:%M:%S").ok()
+}
+
+/// TIFF data inside the APP1 Exif segment of a JPEG
+fn jpeg_exif_block(bytes: &[u8]) -> Option<&[u8]> {
+    let (start, end) = find_exif_segment(bytes)?;
+    bytes.get(start + 10..end)
+}
+
+/// The whole APP1 Exif segment of a JPEG, marker included, for copying
+/// into another JPEG; None when there is none or it is cut short
+pub fn jpeg_exif_segment(bytes: &[u8]) -> Option<&[u8]> {
+    let (start, end) = find_exif_segment(bytes)?;
+    let length = u16::from_be_bytes([bytes[start + 2], bytes[start + 3]]) as usize;
+    (end == start + 2 + length).then(|| &bytes[start..end])
+}
+
+/// Copy of `jpeg` with `segment` (from [`jpeg_exif_segment`]) inserted
+/// after the start-of-image marker
+pub fn insert_exif_segment(jpeg: &[u8], segment: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(jpeg.len() + segment.len());
+    out.extend_from_slice(&jpeg[..2.min(jpeg.len())]);
+    out.extend_from_slice(segment);
+    out.extend_from_slice(jpeg.get(2..).unwrap_or_default());
+    out
+}
+
+/// Start of the APP1 Exif segment of a JPEG and where it ends within `bytes`
+fn find_exif_segment(bytes: &[u8]) -> Option<(usize, usize)> {
+    if !bytes.starts_with(&[0xFF, 0xD8]) {
+        return None;
+    }
+    let mut pos = 2;
+    while pos + 4 <= bytes.len() {
+        if bytes[pos] != 0xFF {
+            return None;
+        }
+        let marker = bytes[pos + 1];
+        // Start of scan: image data follows, no more metadata
+        if marker == 0xDA {
+            return None;
+        }
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        // The scan may end inside the segment; tags past the end are missed
+        let end = (pos + 2 + length).min(bytes.len());
+        if marker == 0xE1 && bytes.get(pos + 4..end)?.starts_with(b"Exif\0\0") {
+            return Some((pos, end));
+        }
+        pos += 2 + length;
+    }
+    None
+}
+
+/// Reader for the byte order and directories of a TIFF block
+struct Tiff<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let little_endian = match data.get(0..4)? {
+            [b'I', b'I', 42, 0] => true,
+            [b'M', b'M', 0, 42] => false,
+            _ => return None,
+        };
+        Some(Self { data, little_endian })
+    }
+
+    fn u16(&self, offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    /// (count, offset of the value field, value as u32) of `tag` in the
+    /// directory at `ifd`
+    fn find_tag(&self, ifd: usize, tag: u16) -> Option<(u32, usize, u32)> {
+        let entries = self.u16(ifd)? as usize;
+        (0..entries).find_map(|i| {
+            let entry = ifd + 2 + i * 12;
+            if self.u16(entry)? != tag {
+                return None;
+            }
+            let value_offset = entry + 8;
+            Some((self.u32(entry + 4)?, value_offset, self.u32(value_offset)?))
+        })
+    }
+
+    /// ASCII value of a tag found with `find_tag`
+    fn ascii(&self, (count, value_offset, value): (u32, usize, u32)) -> Option<&'a str> {
+        let count = count as usize;
+        // Values of up to four bytes are stored in the entry itself
+        let start = if count <= 4 { value_offset } else { value as usize };
+        std::str::from_utf8(self.data.get(start..start + count)?).ok()
+    }
+}
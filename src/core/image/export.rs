@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use ::image::codecs::jpeg::JpegEncoder;
+use ::image::imageops::FilterType;
+
+use crate::core::image::exif::{insert_exif_segment, jpeg_exif_segment};
+use crate::core::image::processor::{ImageFormat, ProcessingError};
+
+/// Size, format and metadata handling for images made to be shared
+#[derive(Debug, Clone)]
+pub struct ExportPreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Longest side in pixels; larger images are shrunk to fit
+    pub max_dimension: Option<u32>,
+    pub format: ImageFormat,
+    /// JPEG quality, 1-100
+    pub quality: u8,
+    /// Leave out EXIF data such as the camera, time and GPS position
+    pub strip_metadata: bool,
+}
+
+/// Presets offered by File > Export
+pub const EXPORT_PRESETS: &[ExportPreset] = &[
+    ExportPreset {
+        name: "Email",
+        description: "1280 px JPEG, small attachment, no metadata",
+        max_dimension: Some(1280),
+        format: ImageFormat::JPEG,
+        quality: 75,
+        strip_metadata: true,
+    },
+    ExportPreset {
+        name: "Social",
+        description: "2048 px JPEG, no metadata",
+        max_dimension: Some(2048),
+        format: ImageFormat::JPEG,
+        quality: 85,
+        strip_metadata: true,
+    },
+    ExportPreset {
+        name: "Print",
+        description: "Full size JPEG at high quality, metadata kept",
+        max_dimension: None,
+        format: ImageFormat::JPEG,
+        quality: 95,
+        strip_metadata: false,
+    },
+    ExportPreset {
+        name: "Lossless",
+        description: "Full size PNG",
+        max_dimension: None,
+        format: ImageFormat::PNG,
+        quality: 100,
+        strip_metadata: true,
+    },
+];
+
+impl ExportPreset {
+    /// File name for `input` exported with this preset: `photo_email.jpg`
+    pub fn output_name(&self, input: &Path) -> String {
+        let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_else(|| "image".to_string());
+        format!("{}_{}.{}", stem, self.name.to_lowercase(), self.format.extension())
+    }
+}
+
+/// Render `input` through `preset` into `output`
+pub fn export_image(input: &Path, output: &Path, preset: &ExportPreset) -> Result<(), ProcessingError> {
+    let failed = |e: &dyn std::fmt::Display| ProcessingError::ProcessingFailed(format!("{}: {}", input.display(), e));
+
+    let mut image = ::image::open(input).map_err(|e| failed(&e))?;
+    if let Some(max) = preset.max_dimension {
+        if image.width() > max || image.height() > max {
+            image = image.resize(max, max, FilterType::Lanczos3);
+        }
+    }
+
+    match &preset.format {
+        ImageFormat::JPEG => {
+            let mut encoded = Vec::new();
+            JpegEncoder::new_with_quality(&mut encoded, preset.quality.clamp(1, 100))
+                .encode_image(&image.to_rgb8())
+                .map_err(|e| failed(&e))?;
+
+            // The encoder writes no EXIF, so keeping it means copying it over
+            if !preset.strip_metadata {
+                let original = fs::read(input).map_err(|e| failed(&e))?;
+                if let Some(segment) = jpeg_exif_segment(&original) {
+                    encoded = insert_exif_segment(&encoded, segment);
+                }
+            }
+            fs::write(output, encoded).map_err(|e| failed(&e))
+        },
+        format => image
+            .save_with_format(output, image_crate_format(format))
+            .map_err(|e| failed(&e)),
+    }
+}
+
+fn image_crate_format(format: &ImageFormat) -> ::image::ImageFormat {
+    match format {
+        ImageFormat::JPEG => ::image::ImageFormat::Jpeg,
+        ImageFormat::GIF => ::image::ImageFormat::Gif,
+        ImageFormat::BMP => ::image::ImageFormat::Bmp,
+        ImageFormat::TIFF => ::image::ImageFormat::Tiff,
+        ImageFormat::WebP => ::image::ImageFormat::WebP,
+        ImageFormat::PNG | ImageFormat::Unknown => ::image::ImageFormat::Png,
+    }
+}
@@ -1,5 +1,15 @@
 pub mod processor;
 pub mod operations;
+pub mod presets;
+pub mod optimize;
+pub mod similarity;
+pub mod exif;
+pub mod export;
+pub mod timelapse;
+pub mod animation;
+pub mod plugins;
+pub mod script_export;
+pub mod document;
 
 // Re-export the types needed by other modules
 pub use processor::{
@@ -18,5 +28,36 @@ pub use operations::{
     ImageOperation,
     OperationError,
     ResizeOperation,
-    BrightnessOperation
-};
\ No newline at end of file
+    BrightnessOperation,
+    AutoLevelsOperation,
+    DenoiseMethod,
+    DenoiseOperation,
+    LensCorrectionOperation,
+    LensProfile,
+    LENS_PROFILES
+};
+
+pub use plugins::{find_operation_plugin, operation_plugins, OperationParam, OperationPlugin};
+
+pub use presets::{ProcessingPreset, BUILTIN_PRESETS};
+
+pub use script_export::{cron_line, install_cron_command, PiScript, CRON_SCHEDULES};
+
+pub use optimize::{optimize_image, optimized_copy};
+
+pub use export::{export_image, ExportPreset, EXPORT_PRESETS};
+
+pub use document::ImageDocument;
+
+pub use timelapse::{
+    ffmpeg_args,
+    frame_list,
+    parse_progress_frame,
+    TimeLapseSettings,
+    VideoCodec,
+    TIMELAPSE_WIDTHS
+};
+
+pub use animation::{assemble_animation, AnimationFormat, AnimationSettings};
+
+pub use similarity::{difference_hash, group_similar, hamming_distance, DEFAULT_SIMILARITY_THRESHOLD};
@@ -1,28 +1,32 @@
 use std::path::Path;
-use std::fmt;
-use std::error::Error;
 
-#[derive(Debug)]
+use ::image::imageops::FilterType;
+use ::image::DynamicImage;
+
+#[derive(Debug, thiserror::Error)]
 pub enum OperationError {
+    #[error("Invalid operation: {0}")]
     InvalidOperation(String),
+    #[error("Operation execution failed: {0}")]
     ExecutionFailed(String),
 }
 
-impl fmt::Display for OperationError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::InvalidOperation(msg) => write!(f, "Invalid operation: {}", msg),
-            Self::ExecutionFailed(msg) => write!(f, "Operation execution failed: {}", msg),
-        }
-    }
-}
-
-impl Error for OperationError {}
-
 pub trait ImageOperation: Send + Sync {
     fn apply(&self, image_path: &Path) -> Result<(), OperationError>;
     fn get_name(&self) -> &str;
     fn get_description(&self) -> String;
+    
+    // Run the operation on decoded pixels; operations that only work on
+    // files leave them as they are
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        Ok(image)
+    }
+    
+    // ImageMagick options doing the same, for scripts that process images
+    // on the Pi; None when ImageMagick has no equivalent
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        None
+    }
 }
 
 // Resize operation
@@ -39,15 +43,18 @@ impl ResizeOperation {
 
 impl ImageOperation for ResizeOperation {
     fn apply(&self, _image_path: &Path) -> Result<(), OperationError> {
-        // This would use an actual image processing library
-        println!("Resizing image to {}x{}", self.width, self.height);
-        
-        // Simulate processing
-        std::thread::sleep(std::time::Duration::from_millis(300));
-        
+        log::info!("Resizing image to {}x{}", self.width, self.height);
         Ok(())
     }
     
+    // The resize dialog already kept the ratio when asked to
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(OperationError::InvalidOperation(format!("Cannot resize to {}x{}", self.width, self.height)));
+        }
+        Ok(image.resize_exact(self.width, self.height, FilterType::Lanczos3))
+    }
+    
     fn get_name(&self) -> &str {
         "Resize"
     }
@@ -55,6 +62,10 @@ impl ImageOperation for ResizeOperation {
     fn get_description(&self) -> String {
         format!("Resize image to {}x{}", self.width, self.height)
     }
+    
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        Some(vec!["-resize".to_string(), format!("{}x{}", self.width, self.height)])
+    }
 }
 
 // Brightness adjustment
@@ -72,14 +83,15 @@ impl BrightnessOperation {
 
 impl ImageOperation for BrightnessOperation {
     fn apply(&self, _image_path: &Path) -> Result<(), OperationError> {
-        println!("Adjusting brightness by {}", self.level);
-        
-        // Simulate processing
-        std::thread::sleep(std::time::Duration::from_millis(200));
-        
+        log::info!("Adjusting brightness by {}", self.level);
         Ok(())
     }
     
+    // The level is a percentage of the full range, as for ImageMagick
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        Ok(image.brighten(self.level * 255 / 100))
+    }
+    
     fn get_name(&self) -> &str {
         "Brightness"
     }
@@ -87,6 +99,428 @@ impl ImageOperation for BrightnessOperation {
     fn get_description(&self) -> String {
         format!("Adjust brightness by {}", self.level)
     }
+    
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        Some(vec!["-brightness-contrast".to_string(), format!("{}x0", self.level)])
+    }
+}
+
+// Spread the tones over the full range to fix under- or over-exposed
+// captures; the darkest and brightest `clip_percent` of the pixels are
+// allowed to clip to black and white
+pub struct AutoLevelsOperation {
+    clip_percent: f64, // 0 to 10
+    // Equalize the histogram instead of stretching it linearly
+    equalize: bool,
+}
+
+impl AutoLevelsOperation {
+    pub fn new(clip_percent: f64, equalize: bool) -> Self {
+        Self {
+            clip_percent: clip_percent.max(0.0).min(10.0),
+            equalize,
+        }
+    }
+    
+    // Tone curve from the brightness histogram, applied to every colour
+    // channel alike so the colours keep their balance
+    fn levels(&self, histogram: &[u64; 256]) -> [u8; 256] {
+        let total: u64 = histogram.iter().sum();
+        let mut curve = [0u8; 256];
+        for (value, level) in curve.iter_mut().enumerate() {
+            *level = value as u8;
+        }
+        if total == 0 {
+            return curve;
+        }
+        
+        let mut cumulative = [0u64; 256];
+        let mut sum = 0;
+        for (value, count) in histogram.iter().enumerate() {
+            sum += count;
+            cumulative[value] = sum;
+        }
+        
+        let clipped = (total as f64 * self.clip_percent / 100.0) as u64;
+        let low = cumulative.iter().position(|&c| c > clipped).unwrap_or(0);
+        let high = cumulative.iter().position(|&c| c >= total - clipped).unwrap_or(255);
+        if high <= low {
+            return curve;
+        }
+        
+        let below = if low > 0 { cumulative[low - 1] } else { 0 };
+        let span = (cumulative[high] - below).max(1) as f64;
+        for (value, level) in curve.iter_mut().enumerate() {
+            *level = if value <= low {
+                0
+            } else if value >= high {
+                255
+            } else if self.equalize {
+                ((cumulative[value] - below) as f64 * 255.0 / span).round() as u8
+            } else {
+                ((value - low) as f64 * 255.0 / (high - low) as f64).round() as u8
+            };
+        }
+        curve
+    }
+}
+
+impl ImageOperation for AutoLevelsOperation {
+    fn apply(&self, _image_path: &Path) -> Result<(), OperationError> {
+        log::info!("Auto levels, clipping {}%", self.clip_percent);
+        Ok(())
+    }
+    
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        let mut histogram = [0u64; 256];
+        for pixel in image.to_luma8().pixels() {
+            histogram[pixel[0] as usize] += 1;
+        }
+        let curve = self.levels(&histogram);
+        
+        // Alpha is left alone
+        if image.color().has_alpha() {
+            let mut pixels = image.to_rgba8();
+            for pixel in pixels.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = curve[*channel as usize];
+                }
+            }
+            Ok(DynamicImage::ImageRgba8(pixels))
+        } else {
+            let mut pixels = image.to_rgb8();
+            for pixel in pixels.pixels_mut() {
+                for channel in &mut pixel.0 {
+                    *channel = curve[*channel as usize];
+                }
+            }
+            Ok(DynamicImage::ImageRgb8(pixels))
+        }
+    }
+    
+    fn get_name(&self) -> &str {
+        "Auto Levels"
+    }
+    
+    fn get_description(&self) -> String {
+        match self.equalize {
+            true => format!("Equalize levels, clipping {}%", self.clip_percent),
+            false => format!("Stretch levels, clipping {}%", self.clip_percent),
+        }
+    }
+    
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        match self.equalize {
+            true => Some(vec!["-equalize".to_string()]),
+            false => Some(vec!["-contrast-stretch".to_string(), format!("{}%", self.clip_percent)]),
+        }
+    }
+}
+
+// How DenoiseOperation smooths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DenoiseMethod {
+    // Median of the neighbourhood; removes speckles and hot pixels
+    Median,
+    // Average of similar neighbours; smooths grain but keeps edges sharp
+    Bilateral,
+}
+
+// Noise reduction for high-ISO night captures
+pub struct DenoiseOperation {
+    method: DenoiseMethod,
+    strength: u8, // 1 to 10
+}
+
+impl DenoiseOperation {
+    pub fn new(method: DenoiseMethod, strength: u8) -> Self {
+        Self {
+            method,
+            strength: strength.max(1).min(10),
+        }
+    }
+    
+    // Pixels around each one that are looked at: 1 to 3 for the median,
+    // 2 to 4 for the bilateral filter
+    fn radius(&self) -> usize {
+        match self.method {
+            DenoiseMethod::Median => 1 + (self.strength as usize - 1) / 4,
+            DenoiseMethod::Bilateral => 2 + self.strength as usize / 4,
+        }
+    }
+}
+
+impl ImageOperation for DenoiseOperation {
+    fn apply(&self, _image_path: &Path) -> Result<(), OperationError> {
+        log::info!("Reducing noise ({:?}, strength {})", self.method, self.strength);
+        Ok(())
+    }
+    
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        let radius = self.radius();
+        let filter = |data: &[u8], width: usize, height: usize, channels: usize| match self.method {
+            DenoiseMethod::Median => median_filter(data, width, height, channels, radius),
+            DenoiseMethod::Bilateral => {
+                bilateral_filter(data, width, height, channels, radius, 5.0 + self.strength as f64 * 5.0)
+            },
+        };
+        
+        // Alpha is left alone
+        if image.color().has_alpha() {
+            let mut pixels = image.to_rgba8();
+            let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+            let filtered = filter(&pixels, width, height, 4);
+            pixels.copy_from_slice(&filtered);
+            Ok(DynamicImage::ImageRgba8(pixels))
+        } else {
+            let mut pixels = image.to_rgb8();
+            let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+            let filtered = filter(&pixels, width, height, 3);
+            pixels.copy_from_slice(&filtered);
+            Ok(DynamicImage::ImageRgb8(pixels))
+        }
+    }
+    
+    fn get_name(&self) -> &str {
+        "Denoise"
+    }
+    
+    fn get_description(&self) -> String {
+        format!("Reduce noise ({:?}, strength {})", self.method, self.strength)
+    }
+    
+    // ImageMagick 6, as shipped on the Pi, has no bilateral filter
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        match self.method {
+            DenoiseMethod::Median => {
+                let size = 2 * self.radius() + 1;
+                Some(vec!["-statistic".to_string(), "Median".to_string(), format!("{}x{}", size, size)])
+            },
+            DenoiseMethod::Bilateral => None,
+        }
+    }
+}
+
+// Replace each colour channel of each pixel with the median of the square
+// of `radius` around it; `channels` is 3 or 4, a fourth is copied as it is
+fn median_filter(data: &[u8], width: usize, height: usize, channels: usize, radius: usize) -> Vec<u8> {
+    let mut filtered = data.to_vec();
+    let mut window = Vec::with_capacity((2 * radius + 1) * (2 * radius + 1));
+    
+    for y in 0..height {
+        for x in 0..width {
+            for channel in 0..3 {
+                window.clear();
+                for ny in y.saturating_sub(radius)..=(y + radius).min(height - 1) {
+                    for nx in x.saturating_sub(radius)..=(x + radius).min(width - 1) {
+                        window.push(data[(ny * width + nx) * channels + channel]);
+                    }
+                }
+                let middle = window.len() / 2;
+                filtered[(y * width + x) * channels + channel] = *window.select_nth_unstable(middle).1;
+            }
+        }
+    }
+    filtered
+}
+
+// Average each pixel with the neighbours within `radius`, weighing them by
+// distance and by how close their colour is; colours further apart than
+// about `range_sigma` barely count, so edges are kept
+fn bilateral_filter(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    channels: usize,
+    radius: usize,
+    range_sigma: f64
+) -> Vec<u8> {
+    let size = 2 * radius + 1;
+    let spatial_sigma = radius as f64 / 2.0 + 0.5;
+    let spatial: Vec<f64> = (0..size * size)
+        .map(|i| {
+            let dx = (i % size) as f64 - radius as f64;
+            let dy = (i / size) as f64 - radius as f64;
+            (-(dx * dx + dy * dy) / (2.0 * spatial_sigma * spatial_sigma)).exp()
+        })
+        .collect();
+    let range: Vec<f64> = (0..256)
+        .map(|d| (-((d * d) as f64) / (2.0 * range_sigma * range_sigma)).exp())
+        .collect();
+    
+    let mut filtered = data.to_vec();
+    for y in 0..height {
+        for x in 0..width {
+            let centre = (y * width + x) * channels;
+            let mut sums = [0.0; 3];
+            let mut total = 0.0;
+            
+            for ny in y.saturating_sub(radius)..=(y + radius).min(height - 1) {
+                for nx in x.saturating_sub(radius)..=(x + radius).min(width - 1) {
+                    let neighbour = (ny * width + nx) * channels;
+                    let difference = (0..3)
+                        .map(|c| (data[centre + c] as i32 - data[neighbour + c] as i32).unsigned_abs())
+                        .sum::<u32>() / 3;
+                    let weight = spatial[(ny + radius - y) * size + (nx + radius - x)] * range[difference as usize];
+                    for c in 0..3 {
+                        sums[c] += weight * data[neighbour + c] as f64;
+                    }
+                    total += weight;
+                }
+            }
+            
+            for c in 0..3 {
+                filtered[centre + c] = (sums[c] / total).round().min(255.0) as u8;
+            }
+        }
+    }
+    filtered
+}
+
+// Lens correction settings matching a Pi camera module, as starting points
+// for LensCorrectionOperation
+#[derive(Debug, Clone)]
+pub struct LensProfile {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub distortion: f64,
+    pub vignette: f64,
+}
+
+pub const LENS_PROFILES: &[LensProfile] = &[
+    LensProfile {
+        name: "Camera Module v2",
+        description: "Slight barrel distortion, dark corners",
+        distortion: 0.02,
+        vignette: 0.35,
+    },
+    LensProfile {
+        name: "HQ Camera, 6 mm lens",
+        description: "Moderate barrel distortion, mild vignetting",
+        distortion: 0.08,
+        vignette: 0.25,
+    },
+    LensProfile {
+        name: "Camera Module 3 Wide",
+        description: "Strong barrel distortion of the 120 degree lens",
+        distortion: 0.25,
+        vignette: 0.4,
+    },
+];
+
+// Straighten the lines a lens bows outward and brighten the corners it
+// darkens
+pub struct LensCorrectionOperation {
+    // Barrel distortion removed, 0 to 0.5; the middle is enlarged by this
+    // much while the edges stay in place
+    distortion: f64,
+    // Brightening of the corners, 0 to 1; 0.3 lifts them by 30%
+    vignette: f64,
+}
+
+impl LensCorrectionOperation {
+    pub fn new(distortion: f64, vignette: f64) -> Self {
+        Self {
+            distortion: distortion.max(0.0).min(0.5),
+            vignette: vignette.max(0.0).min(1.0),
+        }
+    }
+}
+
+impl ImageOperation for LensCorrectionOperation {
+    fn apply(&self, _image_path: &Path) -> Result<(), OperationError> {
+        log::info!("Correcting lens distortion {} and vignetting {}", self.distortion, self.vignette);
+        Ok(())
+    }
+    
+    fn apply_to(&self, image: DynamicImage) -> Result<DynamicImage, OperationError> {
+        if image.color().has_alpha() {
+            let mut pixels = image.to_rgba8();
+            let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+            let corrected = correct_lens(&pixels, width, height, 4, self.distortion, self.vignette);
+            pixels.copy_from_slice(&corrected);
+            Ok(DynamicImage::ImageRgba8(pixels))
+        } else {
+            let mut pixels = image.to_rgb8();
+            let (width, height) = (pixels.width() as usize, pixels.height() as usize);
+            let corrected = correct_lens(&pixels, width, height, 3, self.distortion, self.vignette);
+            pixels.copy_from_slice(&corrected);
+            Ok(DynamicImage::ImageRgb8(pixels))
+        }
+    }
+    
+    fn get_name(&self) -> &str {
+        "Lens Correction"
+    }
+    
+    fn get_description(&self) -> String {
+        format!("Correct lens distortion {} and vignetting {}", self.distortion, self.vignette)
+    }
+    
+    // ImageMagick's barrel distortion measures radii the same way; it has
+    // no simple equivalent of the vignette compensation
+    fn imagemagick_args(&self) -> Option<Vec<String>> {
+        if self.vignette > 0.0 {
+            return None;
+        }
+        Some(vec![
+            "-distort".to_string(),
+            "Barrel".to_string(),
+            format!("0 {} 0 {}", -self.distortion, 1.0 + self.distortion),
+        ])
+    }
+}
+
+// Resample the image so a point at radius r, in halves of the shorter side,
+// is taken from r * (1 + distortion - distortion * r²), then brighten it by
+// up to `vignette` towards the corners; `channels` is 3 or 4, and a fourth
+// is moved but not brightened
+fn correct_lens(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    channels: usize,
+    distortion: f64,
+    vignette: f64
+) -> Vec<u8> {
+    let centre_x = (width as f64 - 1.0) / 2.0;
+    let centre_y = (height as f64 - 1.0) / 2.0;
+    let unit = (width.min(height) as f64 / 2.0).max(1.0);
+    let corner = ((centre_x * centre_x + centre_y * centre_y) / (unit * unit)).max(f64::EPSILON);
+    
+    // Pixels beyond the edges repeat the edge
+    let sample = |x: f64, y: f64, channel: usize| -> f64 {
+        let x = x.max(0.0).min(width as f64 - 1.0);
+        let y = y.max(0.0).min(height as f64 - 1.0);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+        let (fx, fy) = (x - x0 as f64, y - y0 as f64);
+        let at = |x: usize, y: usize| data[(y * width + x) * channels + channel] as f64;
+        let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+        let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    };
+    
+    let mut corrected = vec![0; data.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let dx = (x as f64 - centre_x) / unit;
+            let dy = (y as f64 - centre_y) / unit;
+            let r2 = dx * dx + dy * dy;
+            let scale = 1.0 + distortion - distortion * r2;
+            let source_x = centre_x + dx * scale * unit;
+            let source_y = centre_y + dy * scale * unit;
+            let gain = 1.0 + vignette * r2 / corner;
+            
+            let pixel = (y * width + x) * channels;
+            for channel in 0..channels {
+                let value = sample(source_x, source_y, channel);
+                let value = if channel < 3 { value * gain } else { value };
+                corrected[pixel + channel] = value.round().max(0.0).min(255.0) as u8;
+            }
+        }
+    }
+    corrected
 }
 
 // Add more operations as needed (contrast, crop, rotate, etc.)
\ No newline at end of file
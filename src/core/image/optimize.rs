@@ -0,0 +1,68 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use ::image::codecs::jpeg::JpegEncoder;
+use ::image::imageops::FilterType;
+
+use crate::core::image::processor::{ImageFormat, ProcessingError};
+
+/// Shrink `input` to fit within `max_dimension` pixels on its longest side
+/// and write it to `output`, re-encoding JPEGs at `jpeg_quality`
+///
+/// Images already small enough keep their size; other formats keep their
+/// encoder's defaults.
+pub fn optimize_image(
+    input: &Path,
+    output: &Path,
+    max_dimension: u32,
+    jpeg_quality: u8
+) -> Result<(), ProcessingError> {
+    let failed = |e: &dyn std::fmt::Display| ProcessingError::ProcessingFailed(format!("{}: {}", input.display(), e));
+
+    let mut image = ::image::open(input).map_err(|e| failed(&e))?;
+    if image.width() > max_dimension || image.height() > max_dimension {
+        image = image.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+    }
+
+    let format = output
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(ImageFormat::from_extension)
+        .unwrap_or(ImageFormat::Unknown);
+
+    match format {
+        ImageFormat::JPEG => {
+            let file = File::create(output).map_err(|e| failed(&e))?;
+            JpegEncoder::new_with_quality(BufWriter::new(file), jpeg_quality.clamp(1, 100))
+                .encode_image(&image.to_rgb8())
+                .map_err(|e| failed(&e))
+        },
+        _ => image.save(output).map_err(|e| failed(&e)),
+    }
+}
+
+/// Optimized copy of `input` in the temp directory, for uploading in its place
+///
+/// Returns None when the copy would not be smaller than the original, in
+/// which case nothing is left behind. The caller removes the copy.
+pub fn optimized_copy(
+    input: &Path,
+    max_dimension: u32,
+    jpeg_quality: u8
+) -> Result<Option<PathBuf>, ProcessingError> {
+    let name = input.file_name().unwrap_or_default().to_string_lossy();
+    let copy = std::env::temp_dir().join(format!("pi_remote_manager-upload-{}-{}", std::process::id(), name));
+
+    optimize_image(input, &copy, max_dimension, jpeg_quality)?;
+
+    let original_size = fs::metadata(input).map(|m| m.len()).unwrap_or(u64::MAX);
+    let copy_size = fs::metadata(&copy).map(|m| m.len()).unwrap_or(u64::MAX);
+    if copy_size >= original_size {
+        let _ = fs::remove_file(&copy);
+        return Ok(None);
+    }
+
+    log::info!("Optimized {} from {} to {} bytes", input.display(), original_size, copy_size);
+    Ok(Some(copy))
+}
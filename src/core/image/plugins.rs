@@ -0,0 +1,165 @@
+use crate::core::image::operations::{
+    AutoLevelsOperation,
+    BrightnessOperation,
+    DenoiseMethod,
+    DenoiseOperation,
+    ImageOperation,
+    LensCorrectionOperation,
+    OperationError,
+    ResizeOperation,
+};
+
+/// A number an operation is configured with, asked for when it is added
+#[derive(Debug, Clone, Copy)]
+pub struct OperationParam {
+    pub name: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub default: f64,
+    /// Smallest change; 1.0 for whole numbers
+    pub step: f64,
+}
+
+/// An operation offered in the Operations panel's chooser
+///
+/// Any module or crate built into the application adds its own with
+/// `inventory::submit!`, without changes to the registry or the panel:
+///
+/// ```ignore
+/// inventory::submit! {
+///     OperationPlugin {
+///         name: "Sepia",
+///         description: "Tint the image brown",
+///         params: &[OperationParam { name: "Strength", min: 0.0, max: 100.0, default: 80.0, step: 1.0 }],
+///         build: build_sepia,
+///     }
+/// }
+/// ```
+pub struct OperationPlugin {
+    /// Shown in the chooser; must be unique
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [OperationParam],
+    /// Make the operation from one value per parameter, in order
+    pub build: BuildOperation,
+}
+
+/// Makes an operation from the values of its parameters
+pub type BuildOperation = fn(&[f64]) -> Result<Box<dyn ImageOperation>, OperationError>;
+
+inventory::collect!(OperationPlugin);
+
+inventory::submit! {
+    OperationPlugin {
+        name: "Resize",
+        description: "Scale to a width and height",
+        params: &[
+            OperationParam { name: "Width", min: 1.0, max: 20_000.0, default: 1920.0, step: 1.0 },
+            OperationParam { name: "Height", min: 1.0, max: 20_000.0, default: 1080.0, step: 1.0 },
+        ],
+        build: build_resize,
+    }
+}
+
+inventory::submit! {
+    OperationPlugin {
+        name: "Brightness",
+        description: "Lighten or darken",
+        params: &[OperationParam { name: "Level", min: -100.0, max: 100.0, default: 0.0, step: 1.0 }],
+        build: build_brightness,
+    }
+}
+
+inventory::submit! {
+    OperationPlugin {
+        name: "Auto Levels",
+        description: "Stretch or equalize the tones of dull or dark captures",
+        params: &[
+            OperationParam { name: "Clip %", min: 0.0, max: 10.0, default: 0.5, step: 0.1 },
+            OperationParam { name: "Equalize (0 or 1)", min: 0.0, max: 1.0, default: 0.0, step: 1.0 },
+        ],
+        build: build_auto_levels,
+    }
+}
+
+inventory::submit! {
+    OperationPlugin {
+        name: "Denoise",
+        description: "Smooth the grain of high-ISO night captures",
+        params: &[
+            OperationParam { name: "Strength", min: 1.0, max: 10.0, default: 4.0, step: 1.0 },
+            OperationParam { name: "Median (0 or 1)", min: 0.0, max: 1.0, default: 0.0, step: 1.0 },
+        ],
+        build: build_denoise,
+    }
+}
+
+inventory::submit! {
+    OperationPlugin {
+        name: "Lens Correction",
+        description: "Straighten barrel distortion and brighten dark corners",
+        params: &[
+            OperationParam { name: "Barrel", min: 0.0, max: 0.5, default: 0.05, step: 0.01 },
+            OperationParam { name: "Vignette", min: 0.0, max: 1.0, default: 0.3, step: 0.05 },
+        ],
+        build: build_lens_correction,
+    }
+}
+
+fn build_resize(values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+    Ok(Box::new(ResizeOperation::new(values[0] as u32, values[1] as u32)))
+}
+
+fn build_brightness(values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+    Ok(Box::new(BrightnessOperation::new(values[0] as i32)))
+}
+
+fn build_auto_levels(values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+    Ok(Box::new(AutoLevelsOperation::new(values[0], values[1] >= 0.5)))
+}
+
+fn build_denoise(values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+    let method = if values[1] >= 0.5 { DenoiseMethod::Median } else { DenoiseMethod::Bilateral };
+    Ok(Box::new(DenoiseOperation::new(method, values[0] as u8)))
+}
+
+fn build_lens_correction(values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+    Ok(Box::new(LensCorrectionOperation::new(values[0], values[1])))
+}
+
+/// Every operation on offer: the built-in ones first, then the rest by name
+pub fn operation_plugins() -> Vec<&'static OperationPlugin> {
+    let mut plugins: Vec<&'static OperationPlugin> = inventory::iter::<OperationPlugin>.into_iter().collect();
+
+    let is_builtin = |plugin: &OperationPlugin| matches!(plugin.name, "Resize" | "Brightness" | "Auto Levels" | "Denoise" | "Lens Correction");
+    plugins.sort_by_key(|plugin| (!is_builtin(plugin), plugin.name));
+    plugins
+}
+
+/// The operation named `name`, ignoring case
+pub fn find_operation_plugin(name: &str) -> Option<&'static OperationPlugin> {
+    operation_plugins().into_iter().find(|plugin| plugin.name.eq_ignore_ascii_case(name))
+}
+
+impl OperationPlugin {
+    /// Make the operation, checking each value against its parameter
+    pub fn create(&self, values: &[f64]) -> Result<Box<dyn ImageOperation>, OperationError> {
+        if values.len() != self.params.len() {
+            return Err(OperationError::InvalidOperation(format!(
+                "{} takes {} values, got {}",
+                self.name,
+                self.params.len(),
+                values.len()
+            )));
+        }
+        for (param, value) in self.params.iter().zip(values) {
+            if !(param.min..=param.max).contains(value) {
+                return Err(OperationError::InvalidOperation(format!(
+                    "{} must be from {} to {}",
+                    param.name, param.min, param.max
+                )));
+            }
+        }
+        (self.build)(values)
+    }
+}
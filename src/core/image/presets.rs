@@ -0,0 +1,99 @@
+use crate::core::image::operations::ImageOperation;
+use crate::core::image::plugins::find_operation_plugin;
+use crate::core::image::processor::{
+    ImageFormat,
+    ImageProcessingService,
+    JPEGProcessorFactory,
+    PNGProcessorFactory,
+};
+
+/// A named combination of operations and output format
+#[derive(Debug, Clone)]
+pub struct ProcessingPreset {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub format: ImageFormat,
+    pub resize: Option<(u32, u32)>,
+    pub brightness: Option<i32>,
+}
+
+/// Presets available without any configuration
+pub const BUILTIN_PRESETS: &[ProcessingPreset] = &[
+    ProcessingPreset {
+        name: "thumbs",
+        description: "320x240 JPEG thumbnails",
+        format: ImageFormat::JPEG,
+        resize: Some((320, 240)),
+        brightness: None,
+    },
+    ProcessingPreset {
+        name: "web",
+        description: "1920x1080 JPEG for sharing",
+        format: ImageFormat::JPEG,
+        resize: Some((1920, 1080)),
+        brightness: None,
+    },
+    ProcessingPreset {
+        name: "archive",
+        description: "Full size lossless PNG",
+        format: ImageFormat::PNG,
+        resize: None,
+        brightness: None,
+    },
+];
+
+impl ProcessingPreset {
+    /// Look up a built-in preset by name (case-insensitive)
+    pub fn find(name: &str) -> Option<&'static ProcessingPreset> {
+        BUILTIN_PRESETS.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Processing service with the GUI's processors and this preset's operations
+    pub fn service(&self) -> ImageProcessingService {
+        let mut service = ImageProcessingService::new();
+        service.register_factory(Box::new(JPEGProcessorFactory::new(85)));
+        service.register_factory(Box::new(PNGProcessorFactory::new(6)));
+        for operation in self.operations() {
+            service.add_operation(operation);
+        }
+        service
+    }
+
+    /// Index of the processor for this preset's format in [`Self::service`]
+    pub fn factory_index(&self) -> usize {
+        match self.format {
+            ImageFormat::PNG => 1,
+            _ => 0,
+        }
+    }
+
+    /// Operations to register with the processing service, made by the
+    /// same plugins the Operations panel offers
+    pub fn operations(&self) -> Vec<Box<dyn ImageOperation>> {
+        let mut steps: Vec<(&str, Vec<f64>)> = Vec::new();
+        if let Some((width, height)) = self.resize {
+            steps.push(("Resize", vec![width as f64, height as f64]));
+        }
+        if let Some(level) = self.brightness {
+            steps.push(("Brightness", vec![level as f64]));
+        }
+
+        steps
+            .into_iter()
+            .filter_map(|(name, values)| {
+                let created = find_operation_plugin(name).map(|plugin| plugin.create(&values));
+                match created {
+                    Some(Ok(operation)) => Some(operation),
+                    Some(Err(e)) => {
+                        log::error!("Preset {}: {}", self.name, e);
+                        None
+                    },
+                    None => {
+                        log::error!("Preset {}: no {} operation", self.name, name);
+                        None
+                    },
+                }
+            })
+            .collect()
+    }
+}
@@ -1,8 +1,15 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
 use std::path::Path;
 use std::error::Error;
-use std::fmt;
 
+use ::image::codecs::jpeg::JpegEncoder;
+use ::image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use ::image::{DynamicImage, ImageEncoder};
+
+use crate::core::image::exif::{insert_exif_segment, jpeg_exif_segment};
 use crate::core::image::operations::{ImageOperation, OperationError};
+use crate::core::image::plugins::OperationParam;
 
 // Define image format types
 #[derive(Debug, Clone, PartialEq)]
@@ -40,11 +47,19 @@ impl ImageFormat {
             Self::Unknown => "",
         }
     }
+    
+    // Whether writing this format throws detail away
+    pub fn is_lossy(&self) -> bool {
+        matches!(self, Self::JPEG)
+    }
 }
 
 // Image processor trait - this is the "Product" in our Factory Method pattern
 pub trait ImageProcessor {
     fn process_image(&self, input_path: &Path, output_path: &Path) -> Result<(), Box<dyn Error>>;
+    // Write pixels that are already decoded; `exif` is the APP1 segment of
+    // a JPEG original, kept by formats that can hold it
+    fn encode(&self, image: &DynamicImage, exif: Option<&[u8]>, output_path: &Path) -> Result<(), Box<dyn Error>>;
     fn get_name(&self) -> &str;
     fn get_format(&self) -> ImageFormat;
     fn get_description(&self) -> String;
@@ -65,13 +80,25 @@ impl JPEGProcessor {
 
 impl ImageProcessor for JPEGProcessor {
     fn process_image(&self, input_path: &Path, output_path: &Path) -> Result<(), Box<dyn Error>> {
-        // This would use a real image processing library
-        println!("Processing JPEG: {} -> {}", input_path.display(), output_path.display());
-        println!("Using quality setting: {}", self.quality);
+        log::info!("Processing JPEG: {} -> {}", input_path.display(), output_path.display());
+        log::info!("Using quality setting: {}", self.quality);
+        
+        let original = fs::read(input_path)?;
+        let image = ::image::load_from_memory(&original)?;
+        self.encode(&image, jpeg_exif_segment(&original), output_path)
+    }
+    
+    fn encode(&self, image: &DynamicImage, exif: Option<&[u8]>, output_path: &Path) -> Result<(), Box<dyn Error>> {
+        let mut encoded = Vec::new();
+        JpegEncoder::new_with_quality(&mut encoded, self.quality.max(1))
+            .encode_image(&image.to_rgb8())?;
         
-        // Simulate processing
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        // The encoder writes no EXIF, so the original's is copied over
+        if let Some(segment) = exif {
+            encoded = insert_exif_segment(&encoded, segment);
+        }
         
+        fs::write(output_path, encoded)?;
         Ok(())
     }
     
@@ -102,12 +129,25 @@ impl PNGProcessor {
 
 impl ImageProcessor for PNGProcessor {
     fn process_image(&self, input_path: &Path, output_path: &Path) -> Result<(), Box<dyn Error>> {
-        println!("Processing PNG: {} -> {}", input_path.display(), output_path.display());
-        println!("Using compression level: {}", self.compression_level);
+        log::info!("Processing PNG: {} -> {}", input_path.display(), output_path.display());
+        log::info!("Using compression level: {}", self.compression_level);
         
-        // Simulate processing
-        std::thread::sleep(std::time::Duration::from_millis(500));
+        let image = ::image::open(input_path)?;
+        self.encode(&image, None, output_path)
+    }
+    
+    // PNG files written here carry no EXIF
+    fn encode(&self, image: &DynamicImage, _exif: Option<&[u8]>, output_path: &Path) -> Result<(), Box<dyn Error>> {
+        // The encoder offers three levels; 0-9 is spread over them
+        let compression = match self.compression_level {
+            0..=3 => CompressionType::Fast,
+            4..=6 => CompressionType::Default,
+            _ => CompressionType::Best,
+        };
         
+        let output = BufWriter::new(File::create(output_path)?);
+        PngEncoder::new_with_quality(output, compression, FilterType::Adaptive)
+            .write_image(image.as_bytes(), image.width(), image.height(), image.color())?;
         Ok(())
     }
     
@@ -127,9 +167,17 @@ impl ImageProcessor for PNGProcessor {
 // Additional processor types for other formats would go here
 
 // ImageProcessorFactory trait - this is the "Creator" in our Factory Method pattern
-pub trait ImageProcessorFactory {
+pub trait ImageProcessorFactory: Send + Sync {
     fn create_processor(&self) -> Box<dyn ImageProcessor>;
     fn get_name(&self) -> String;
+    
+    // Settings the user may change, each with its current value as default
+    fn settings(&self) -> Vec<OperationParam> {
+        Vec::new()
+    }
+    
+    // Take new values for `settings`, in the same order
+    fn apply_settings(&mut self, _values: &[f64]) {}
 }
 
 // Concrete factories for each image processor type
@@ -151,6 +199,16 @@ impl ImageProcessorFactory for JPEGProcessorFactory {
     fn get_name(&self) -> String {
         format!("JPEG Processor (Quality: {}%)", self.quality)
     }
+    
+    fn settings(&self) -> Vec<OperationParam> {
+        vec![OperationParam { name: "Quality", min: 1.0, max: 100.0, default: self.quality as f64, step: 1.0 }]
+    }
+    
+    fn apply_settings(&mut self, values: &[f64]) {
+        if let Some(&quality) = values.first() {
+            self.quality = quality.round().clamp(1.0, 100.0) as u8;
+        }
+    }
 }
 
 pub struct PNGProcessorFactory {
@@ -171,6 +229,16 @@ impl ImageProcessorFactory for PNGProcessorFactory {
     fn get_name(&self) -> String {
         format!("PNG Processor (Compression: {})", self.compression_level)
     }
+    
+    fn settings(&self) -> Vec<OperationParam> {
+        vec![OperationParam { name: "Compression", min: 0.0, max: 9.0, default: self.compression_level as f64, step: 1.0 }]
+    }
+    
+    fn apply_settings(&mut self, values: &[f64]) {
+        if let Some(&level) = values.first() {
+            self.compression_level = level.round().clamp(0.0, 9.0) as u8;
+        }
+    }
 }
 
 // Image processing service that manages processors and applies operations
@@ -207,6 +275,46 @@ impl ImageProcessingService {
         &self.factories
     }
     
+    pub fn get_factory_mut(&mut self, index: usize) -> Option<&mut Box<dyn ImageProcessorFactory>> {
+        self.factories.get_mut(index)
+    }
+    
+    // Formats the processors can write, each with the first factory
+    // writing it
+    pub fn output_formats(&self) -> Vec<(ImageFormat, usize)> {
+        let mut formats: Vec<(ImageFormat, usize)> = Vec::new();
+        for (index, factory) in self.factories.iter().enumerate() {
+            let format = factory.create_processor().get_format();
+            if !formats.iter().any(|(known, _)| *known == format) {
+                formats.push((format, index));
+            }
+        }
+        formats
+    }
+    
+    // Run the operations on decoded pixels, such as a part of the open
+    // image to preview them with
+    pub fn apply_operations(&self, mut image: DynamicImage) -> Result<DynamicImage, ProcessingError> {
+        for operation in &self.operations {
+            image = operation.apply_to(image).map_err(ProcessingError::OperationFailed)?;
+        }
+        Ok(image)
+    }
+    
+    // Write decoded pixels with a processor, without running the operations
+    pub fn encode_image(
+        &self,
+        image: &DynamicImage,
+        exif: Option<&[u8]>,
+        output_path: &Path,
+        factory_index: usize
+    ) -> Result<(), ProcessingError> {
+        let factory = self.factories.get(factory_index).ok_or(ProcessingError::NoProcessorAvailable)?;
+        factory.create_processor()
+            .encode(image, exif, output_path)
+            .map_err(|e| ProcessingError::ProcessingFailed(format!("{}: {}", output_path.display(), e)))
+    }
+    
     pub fn process_image(
         &self, 
         input_path: &Path, 
@@ -227,28 +335,31 @@ impl ImageProcessingService {
             }
         }
         
+        // Without operations the processor only converts the file
+        if self.operations.is_empty() {
+            return processor.process_image(input_path, output_path)
+                .map_err(|e| ProcessingError::ProcessingFailed(e.to_string()));
+        }
+        
+        // Operations that change pixels work on one decoded copy
+        let failed = |e: &dyn std::fmt::Display| ProcessingError::ProcessingFailed(format!("{}: {}", input_path.display(), e));
+        let original = fs::read(input_path).map_err(|e| failed(&e))?;
+        let image = ::image::load_from_memory(&original).map_err(|e| failed(&e))?;
+        let image = self.apply_operations(image)?;
+        
         // Process the image
-        processor.process_image(input_path, output_path)
+        processor.encode(&image, jpeg_exif_segment(&original), output_path)
             .map_err(|e| ProcessingError::ProcessingFailed(e.to_string()))
     }
 }
 
 // Error type for image processing
-#[derive(Debug)]
+#[derive(Debug, thiserror::Error)]
 pub enum ProcessingError {
+    #[error("No suitable image processor available")]
     NoProcessorAvailable,
-    OperationFailed(OperationError),
+    #[error("Operation failed: {0}")]
+    OperationFailed(#[source] OperationError),
+    #[error("Processing failed: {0}")]
     ProcessingFailed(String),
 }
-
-impl fmt::Display for ProcessingError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::NoProcessorAvailable => write!(f, "No suitable image processor available"),
-            Self::OperationFailed(err) => write!(f, "Operation failed: {}", err),
-            Self::ProcessingFailed(msg) => write!(f, "Processing failed: {}", msg),
-        }
-    }
-}
-
-impl Error for ProcessingError {}
\ No newline at end of file
@@ -0,0 +1,142 @@
+use crate::core::image::operations::{ImageOperation, OperationError};
+use crate::core::image::processor::ImageFormat;
+use crate::transfer::ssh::shell_quote;
+
+/// Extensions of the images an exported script picks up, any case
+const SCRIPT_INPUT_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "tif", "tiff", "bmp", "webp"];
+
+/// Quality of JPEGs a script writes, as the app's own JPEG processor
+const SCRIPT_JPEG_QUALITY: u8 = 85;
+
+/// Schedules offered for running an exported script from cron
+pub const CRON_SCHEDULES: &[(&str, &str)] = &[
+    ("Every 15 minutes", "*/15 * * * *"),
+    ("Every hour", "0 * * * *"),
+    ("Every night at 03:00", "0 3 * * *"),
+];
+
+/// Folders and output format of a script that runs the operation pipeline
+/// on the Pi
+#[derive(Debug, Clone)]
+pub struct PiScript {
+    /// Folder the images are taken from; the script's first argument
+    /// overrides it
+    pub input_dir: String,
+    /// Folder the results are written to; the second argument overrides it
+    pub output_dir: String,
+    pub format: ImageFormat,
+}
+
+impl PiScript {
+    /// Shell script running `operations` on every image of the input folder
+    /// with ImageMagick
+    ///
+    /// An image is skipped when its result is newer than it, so the script
+    /// can run from cron and only handle new pictures. Fails when an
+    /// operation has no ImageMagick equivalent.
+    pub fn render(&self, operations: &[Box<dyn ImageOperation>]) -> Result<String, OperationError> {
+        let unsupported: Vec<&str> = operations
+            .iter()
+            .filter(|operation| operation.imagemagick_args().is_none())
+            .map(|operation| operation.get_name())
+            .collect();
+        if !unsupported.is_empty() {
+            return Err(OperationError::InvalidOperation(format!(
+                "ImageMagick cannot do: {}",
+                unsupported.join(", ")
+            )));
+        }
+
+        let mut options = vec!["-auto-orient".to_string()];
+        for operation in operations {
+            options.extend(operation.imagemagick_args().unwrap_or_default().iter().map(|arg| shell_quote(arg)));
+        }
+        match self.format {
+            ImageFormat::PNG => options.push("-define png:compression-level=6".to_string()),
+            _ => options.push(format!("-quality {}", SCRIPT_JPEG_QUALITY)),
+        }
+
+        let steps: Vec<String> = operations.iter().map(|operation| format!("#   {}", operation.get_description())).collect();
+        let find_names = SCRIPT_INPUT_EXTENSIONS
+            .iter()
+            .map(|extension| format!("-iname '*.{}'", extension))
+            .collect::<Vec<_>>()
+            .join(" -o ");
+        let extension = match self.format {
+            ImageFormat::PNG => "png",
+            _ => "jpg",
+        };
+
+        let steps = if steps.is_empty() { "#   (no operations; only converts)".to_string() } else { steps.join("\n") };
+        Ok(format!(
+            r#"#!/bin/bash
+# Made by Pi Remote Manager. Runs these operations with ImageMagick:
+{steps}
+#
+# Usage: <script> [input folder] [output folder]
+# Images already processed are skipped, so it is safe to run from cron.
+set -eu
+
+INPUT_DIR="${{1:-{input}}}"
+OUTPUT_DIR="${{2:-{output}}}"
+
+if command -v magick >/dev/null 2>&1; then
+    CONVERT=magick
+elif command -v convert >/dev/null 2>&1; then
+    CONVERT=convert
+else
+    echo "ImageMagick is not installed; run: sudo apt-get install imagemagick" >&2
+    exit 1
+fi
+
+mkdir -p "$OUTPUT_DIR"
+find "$INPUT_DIR" -maxdepth 1 -type f \( {find_names} \) | while IFS= read -r input; do
+    name=$(basename "$input")
+    output="$OUTPUT_DIR/${{name%.*}}.{extension}"
+    if [ -e "$output" ] && [ "$output" -nt "$input" ]; then
+        continue
+    fi
+    if "$CONVERT" "$input" {options} "$output"; then
+        echo "$output"
+    else
+        echo "Failed: $input" >&2
+    fi
+done
+"#,
+            steps = steps,
+            input = escape_double_quoted(&self.input_dir),
+            output = escape_double_quoted(&self.output_dir),
+            find_names = find_names,
+            extension = extension,
+            options = options.join(" "),
+        ))
+    }
+}
+
+/// Crontab line running the script at `script_path` on `schedule`,
+/// appending its output to a log next to it
+pub fn cron_line(schedule: &str, script_path: &str) -> String {
+    format!("{} {} >> {} 2>&1", schedule, shell_quote(script_path), shell_quote(&format!("{}.log", script_path)))
+}
+
+/// Shell command adding `line` to the Pi user's crontab, replacing lines
+/// that ran the same script before
+pub fn install_cron_command(line: &str, script_path: &str) -> String {
+    format!(
+        "(crontab -l 2>/dev/null | grep -vF {}; echo {}) | crontab -",
+        shell_quote(script_path),
+        shell_quote(line)
+    )
+}
+
+/// `text` escaped for use between double quotes in a shell script
+fn escape_double_quoted(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+use ::image::imageops::FilterType;
+
+use crate::core::image::processor::ProcessingError;
+
+/// Differing hash bits up to which two images count as near-duplicates
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 6;
+
+/// 64-bit difference hash of an image
+///
+/// The image is shrunk to 9x8 grey pixels and each bit records whether a
+/// pixel is brighter than its right neighbour, so re-encoding, resizing and
+/// small changes in exposure leave the hash (nearly) unchanged.
+pub fn difference_hash(path: &Path) -> Result<u64, ProcessingError> {
+    let image = ::image::open(path)
+        .map_err(|e| ProcessingError::ProcessingFailed(format!("{}: {}", path.display(), e)))?;
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(left > right);
+        }
+    }
+    Ok(hash)
+}
+
+/// Number of bits in which two hashes differ
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Group images whose hashes differ by at most `threshold` bits
+///
+/// Similarity is chained, so a slow drift through a time-lapse ends up in
+/// one group. Only groups of two or more are returned, each in the order
+/// the images were given and ordered by their first image.
+pub fn group_similar(hashes: &[(PathBuf, u64)], threshold: u32) -> Vec<Vec<PathBuf>> {
+    // Union-find over image indices
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if hamming_distance(hashes[i].1, hashes[j].1) <= threshold {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                if a != b {
+                    parent[b.max(a)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut groups: Vec<(usize, Vec<PathBuf>)> = Vec::new();
+    for (i, (path, _)) in hashes.iter().enumerate() {
+        let group_root = root(&mut parent, i);
+        match groups.iter_mut().find(|(r, _)| *r == group_root) {
+            Some((_, members)) => members.push(path.clone()),
+            None => groups.push((group_root, vec![path.clone()])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(_, members)| members)
+        .filter(|members| members.len() > 1)
+        .collect()
+}
@@ -0,0 +1,112 @@
+use std::path::Path;
+
+/// Codecs a time-lapse can be encoded with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    /// Plays everywhere
+    H264,
+    /// About half the size, slower to encode and not played by every device
+    H265,
+}
+
+impl VideoCodec {
+    pub const ALL: [VideoCodec; 2] = [VideoCodec::H264, VideoCodec::H265];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::H264 => "H.264",
+            Self::H265 => "H.265 (HEVC)",
+        }
+    }
+
+    fn encoder(&self) -> &'static str {
+        match self {
+            Self::H264 => "libx264",
+            Self::H265 => "libx265",
+        }
+    }
+}
+
+/// How frames are turned into a video
+#[derive(Debug, Clone)]
+pub struct TimeLapseSettings {
+    /// Frames shown per second of video
+    pub fps: u32,
+    /// Video width in pixels, height following the frames' aspect ratio;
+    /// None keeps the frames' size
+    pub width: Option<u32>,
+    pub codec: VideoCodec,
+}
+
+impl Default for TimeLapseSettings {
+    fn default() -> Self {
+        Self {
+            fps: 24,
+            width: Some(1920),
+            codec: VideoCodec::H264,
+        }
+    }
+}
+
+/// Video widths offered, with their names
+pub const TIMELAPSE_WIDTHS: &[(&str, Option<u32>)] = &[
+    ("Frame size", None),
+    ("1080p", Some(1920)),
+    ("720p", Some(1280)),
+    ("480p", Some(854)),
+];
+
+/// ffmpeg concat list showing each of `frames`, in order, for one frame of
+/// a `fps` video
+///
+/// Relative paths are taken from the list file's folder, so frames are best
+/// given as absolute paths.
+pub fn frame_list(frames: &[String], fps: u32) -> String {
+    let duration = 1.0 / fps.max(1) as f64;
+    let mut list = String::new();
+    for frame in frames {
+        // Single quotes are closed, escaped and reopened in concat lists
+        list.push_str(&format!("file '{}'\nduration {:.6}\n", frame.replace('\'', "'\\''"), duration));
+    }
+    // The last frame's duration only counts when it is listed again
+    if let Some(last) = frames.last() {
+        list.push_str(&format!("file '{}'\n", last.replace('\'', "'\\''")));
+    }
+    list
+}
+
+/// Arguments for ffmpeg (without the program name) making `output` from
+/// the concat list `list`, printing `frame=N` progress lines on stdout
+pub fn ffmpeg_args(list: &Path, output: &Path, settings: &TimeLapseSettings) -> Vec<String> {
+    // Encoders need even dimensions, and yuv420p for players to cope
+    let filter = match settings.width {
+        Some(width) => format!("scale={}:-2,format=yuv420p", width - width % 2),
+        None => "scale=trunc(iw/2)*2:trunc(ih/2)*2,format=yuv420p".to_string(),
+    };
+
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-y", "-f", "concat", "-safe", "0", "-i"]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+    args.push(list.to_string_lossy().to_string());
+    args.extend([
+        "-vf".to_string(),
+        filter,
+        "-r".to_string(),
+        settings.fps.max(1).to_string(),
+        "-c:v".to_string(),
+        settings.codec.encoder().to_string(),
+        "-movflags".to_string(),
+        "+faststart".to_string(),
+        "-progress".to_string(),
+        "pipe:1".to_string(),
+        "-nostats".to_string(),
+        output.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+/// Frame count from a `frame=N` line of ffmpeg's progress output
+pub fn parse_progress_frame(line: &str) -> Option<usize> {
+    line.strip_prefix("frame=")?.trim().parse().ok()
+}
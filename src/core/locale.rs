@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::{self, Write};
+use std::sync::{OnceLock, RwLock};
+
+/// Languages the UI is translated to; English is the language of the source
+/// and needs no catalogue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 3] = [Language::English, Language::German, Language::Spanish];
+
+    /// ISO 639-1 code, as kept in the settings
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::German => "de",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// Name of the language in itself, for the language menu
+    pub fn native_name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::Spanish => "Español",
+        }
+    }
+
+    /// Language of a code or POSIX locale such as "de_DE.UTF-8"
+    pub fn from_code(code: &str) -> Option<Language> {
+        let code = code.split(&['_', '-', '.', '@'][..]).next().unwrap_or_default().to_ascii_lowercase();
+        Language::ALL.into_iter().find(|language| language.code() == code)
+    }
+
+    /// Language of the system locale, English when it has no catalogue
+    pub fn from_environment() -> Language {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Language::from_code(&value))
+            .unwrap_or(Language::English)
+    }
+
+    /// English text mapped to this language's translation
+    fn catalogue(&self) -> &'static HashMap<String, String> {
+        static GERMAN: OnceLock<HashMap<String, String>> = OnceLock::new();
+        static SPANISH: OnceLock<HashMap<String, String>> = OnceLock::new();
+        static ENGLISH: OnceLock<HashMap<String, String>> = OnceLock::new();
+        match self {
+            Language::English => ENGLISH.get_or_init(HashMap::new),
+            Language::German => GERMAN.get_or_init(|| parse_po(include_str!("../../locales/de.po"))),
+            Language::Spanish => SPANISH.get_or_init(|| parse_po(include_str!("../../locales/es.po"))),
+        }
+    }
+}
+
+static CURRENT: RwLock<Language> = RwLock::new(Language::English);
+
+/// Switch the language `tr` translates to; widgets already shown keep their
+/// text until they are translated again
+pub fn set_language(language: Language) {
+    *CURRENT.write().unwrap() = language;
+}
+
+pub fn language() -> Language {
+    *CURRENT.read().unwrap()
+}
+
+/// `text` in the current language, or unchanged when it has no translation
+///
+/// Leading and trailing whitespace, such as the tab of menu labels, is kept
+/// around the translation.
+pub fn tr(text: &str) -> String {
+    translate(text, language())
+}
+
+/// `text` in the current language with its `{}` placeholders filled from
+/// `args` in order
+///
+/// A translation may name the arguments as `{0}`, `{1}`, ... where its word
+/// order differs from English.
+pub fn trf(text: &str, args: &[&dyn fmt::Display]) -> String {
+    fill(&tr(text), args)
+}
+
+/// `text`, shown in `from`, in the current language
+///
+/// Used to translate widgets again after the language was switched; text
+/// that was not translated, such as file names, is left alone.
+pub fn retranslate(text: &str, from: Language) -> String {
+    tr(&source_in(text, from))
+}
+
+/// English original of `text` shown in the current language, for values
+/// kept in the settings such as the last open tab
+pub fn source_text(text: &str) -> String {
+    source_in(text, language())
+}
+
+fn translate(text: &str, language: Language) -> String {
+    let (lead, core, trail) = split_whitespace(text);
+    match language.catalogue().get(core) {
+        Some(translation) => format!("{}{}{}", lead, translation, trail),
+        None => text.to_string(),
+    }
+}
+
+fn source_in(text: &str, language: Language) -> String {
+    let (lead, core, trail) = split_whitespace(text);
+    match language.catalogue().iter().find(|(_, translation)| translation.as_str() == core) {
+        Some((source, _)) => format!("{}{}{}", lead, source, trail),
+        None => text.to_string(),
+    }
+}
+
+fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        filled.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let placeholder = after
+            .find('}')
+            .filter(|&close| after[..close].chars().all(|c| c.is_ascii_digit()));
+        let Some(close) = placeholder else {
+            filled.push('{');
+            rest = after;
+            continue;
+        };
+        let index = if close == 0 {
+            next += 1;
+            next - 1
+        } else {
+            after[..close].parse().unwrap_or(usize::MAX)
+        };
+        match args.get(index) {
+            Some(arg) => {
+                let _ = write!(filled, "{}", arg);
+            },
+            None => filled.push_str(&rest[open..open + close + 2]),
+        }
+        rest = &after[close + 1..];
+    }
+    filled.push_str(rest);
+    filled
+}
+
+fn split_whitespace(text: &str) -> (&str, &str, &str) {
+    let core = text.trim();
+    let start = text.len() - text.trim_start().len();
+    (&text[..start], core, &text[start + core.len()..])
+}
+
+/// Entries of a gettext catalogue; the header and untranslated entries are
+/// left out
+fn parse_po(source: &str) -> HashMap<String, String> {
+    enum Field {
+        None,
+        Id,
+        Str,
+    }
+
+    let mut entries = HashMap::new();
+    let (mut id, mut translation) = (String::new(), String::new());
+    let mut field = Field::None;
+    let mut finish = |id: &mut String, translation: &mut String| {
+        if !id.is_empty() && !translation.is_empty() {
+            entries.insert(std::mem::take(id), std::mem::take(translation));
+        }
+        id.clear();
+        translation.clear();
+    };
+
+    for line in source.lines().map(str::trim) {
+        if let Some(rest) = line.strip_prefix("msgid ") {
+            finish(&mut id, &mut translation);
+            id = unquote(rest);
+            field = Field::Id;
+        } else if let Some(rest) = line.strip_prefix("msgstr ") {
+            translation = unquote(rest);
+            field = Field::Str;
+        } else if line.starts_with('"') {
+            match field {
+                Field::Id => id.push_str(&unquote(line)),
+                Field::Str => translation.push_str(&unquote(line)),
+                Field::None => {},
+            }
+        } else if line.is_empty() || line.starts_with('#') {
+            field = Field::None;
+        }
+    }
+    finish(&mut id, &mut translation);
+    entries
+}
+
+/// Contents of a quoted .po string with its escapes resolved
+fn unquote(quoted: &str) -> String {
+    let inner = quoted.trim().strip_prefix('"').and_then(|rest| rest.strip_suffix('"')).unwrap_or_default();
+    let mut text = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => text.push('\n'),
+            Some('t') => text.push('\t'),
+            Some(other) => text.push(other),
+            None => {},
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_takes_arguments_in_order_or_by_index() {
+        assert_eq!(fill("{} of {} files", &[&3, &5]), "3 of 5 files");
+        assert_eq!(fill("{1} Dateien, davon {0}", &[&3, &5]), "5 Dateien, davon 3");
+        assert_eq!(fill("{name} {}", &[&1]), "{name} 1");
+        assert_eq!(fill("missing {}", &[]), "missing {}");
+    }
+
+    #[test]
+    fn catalogues_map_back_to_one_source() {
+        for language in Language::ALL {
+            let mut sources: HashMap<&str, &str> = HashMap::new();
+            for (source, translation) in language.catalogue() {
+                if let Some(other) = sources.insert(translation, source) {
+                    panic!("{} translates both {:?} and {:?} as {:?}", language.code(), other, source, translation);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn placeholders_survive_translation() {
+        let count = |text: &str| text.matches('{').count();
+        for language in Language::ALL {
+            for (source, translation) in language.catalogue() {
+                assert_eq!(count(source), count(translation), "{}: {:?}", language.code(), source);
+            }
+        }
+    }
+}
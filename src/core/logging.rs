@@ -0,0 +1,117 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::Local;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Maximum number of records kept for the Logs tab
+pub const MAX_LOG_RECORDS: usize = 5000;
+
+/// One captured log line
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    /// Local time formatted as HH:MM:SS.mmm
+    pub timestamp: String,
+    pub level: Level,
+    /// Module that emitted the record
+    pub target: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    /// Single line representation used for display and copying
+    pub fn format(&self) -> String {
+        format!("{} {:<5} [{}] {}", self.timestamp, self.level, self.target, self.message)
+    }
+}
+
+struct LogStore {
+    records: VecDeque<LogRecord>,
+    /// Number of records ever logged, so viewers can detect new output
+    total: u64,
+}
+
+static STORE: Mutex<LogStore> = Mutex::new(LogStore {
+    records: VecDeque::new(),
+    total: 0,
+});
+
+/// Logger that mirrors every record to stderr and keeps recent ones in memory
+struct AppLogger {
+    level: LevelFilter,
+}
+
+impl Log for AppLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogRecord {
+            timestamp: Local::now().format("%H:%M:%S%.3f").to_string(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+
+        eprintln!("{}", entry.format());
+
+        if let Ok(mut store) = STORE.lock() {
+            if store.records.len() >= MAX_LOG_RECORDS {
+                store.records.pop_front();
+            }
+            store.records.push_back(entry);
+            store.total += 1;
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the application logger
+///
+/// The level comes from `RUST_LOG` (error, warn, info, debug or trace) and
+/// defaults to debug so the Logs tab shows the full connection trace.
+pub fn init() {
+    let level = std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|value| value.parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::Debug);
+
+    if log::set_boxed_logger(Box::new(AppLogger { level })).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Number of records logged so far, including ones already evicted
+pub fn total_records() -> u64 {
+    STORE.lock().map(|s| s.total).unwrap_or(0)
+}
+
+/// Copy of the records currently held in memory, oldest first
+pub fn records() -> Vec<LogRecord> {
+    STORE.lock()
+        .map(|s| s.records.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Number of records held in memory and the bytes of their text
+pub fn usage() -> (usize, u64) {
+    STORE.lock()
+        .map(|s| {
+            let bytes = s.records.iter().map(|r| (r.timestamp.len() + r.target.len() + r.message.len()) as u64).sum();
+            (s.records.len(), bytes)
+        })
+        .unwrap_or_default()
+}
+
+/// Drop all records held in memory
+pub fn clear() {
+    if let Ok(mut store) = STORE.lock() {
+        store.records.clear();
+    }
+}
@@ -1,5 +1,12 @@
 pub mod image;
 pub mod utils;
 pub mod file;
+pub mod device;
+pub mod logging;
+pub mod scripting;
+pub mod dropbox;
+pub mod upload_target;
+pub mod locale;
+pub mod storage;
 
 pub use utils::image_utils;
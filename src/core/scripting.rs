@@ -0,0 +1,243 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::SystemTime;
+
+use chrono::{DateTime, Local};
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use crate::core::image::exif::read_capture_time;
+use crate::core::image::{ProcessingPreset, BUILTIN_PRESETS};
+use crate::core::image_utils::{generate_output_filename, is_image_file, OutputNameContext};
+use crate::transfer::method::TransferMethod;
+
+/// Moment in the app a script can act on; each calls the script function
+/// of the same name, when the script defines one
+#[derive(Debug, Clone)]
+pub enum Hook {
+    /// `on_connect(host)`: the remote pane connected to `host`
+    Connect { host: String },
+    /// `on_download_complete(local_path, remote_path)`
+    DownloadComplete { local_path: PathBuf, remote_path: PathBuf },
+    /// `on_batch_finished(name, done, failed, cancelled)`: a processing job
+    /// ended
+    BatchFinished { name: String, done: usize, failed: usize, cancelled: bool },
+}
+
+impl Hook {
+    pub fn function(&self) -> &'static str {
+        match self {
+            Self::Connect { .. } => "on_connect",
+            Self::DownloadComplete { .. } => "on_download_complete",
+            Self::BatchFinished { .. } => "on_batch_finished",
+        }
+    }
+
+    fn args(&self) -> Vec<Dynamic> {
+        match self {
+            Self::Connect { host } => vec![host.clone().into()],
+            Self::DownloadComplete { local_path, remote_path } => vec![
+                local_path.display().to_string().into(),
+                remote_path.display().to_string().into(),
+            ],
+            Self::BatchFinished { name, done, failed, cancelled } => vec![
+                name.clone().into(),
+                (*done as i64).into(),
+                (*failed as i64).into(),
+                (*cancelled).into(),
+            ],
+        }
+    }
+}
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Connection of the hook being run, used by the transfer functions
+type Connection = Rc<RefCell<Option<Box<dyn TransferMethod>>>>;
+
+/// Runs the hook functions of a user's Rhai script, one hook at a time on
+/// a thread of its own so scripts can copy and process without holding up
+/// the window
+///
+/// The script is compiled again when the file changes, so edits apply to
+/// the next hook without a restart.
+#[derive(Clone)]
+pub struct ScriptHooks {
+    sender: Sender<(Hook, Option<Box<dyn TransferMethod>>)>,
+}
+
+impl ScriptHooks {
+    /// Run hooks from `script`; processed images are named by
+    /// `name_template` and `on_error` hears of scripts that fail
+    pub fn start<E>(script: PathBuf, name_template: String, on_error: E) -> Self
+    where
+        E: Fn(String) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<(Hook, Option<Box<dyn TransferMethod>>)>();
+
+        thread::spawn(move || {
+            let connection: Connection = Rc::new(RefCell::new(None));
+            let engine = build_engine(connection.clone(), name_template);
+            let mut compiled: Option<(Option<SystemTime>, AST)> = None;
+
+            for (hook, method) in receiver {
+                let modified = fs::metadata(&script).and_then(|m| m.modified()).ok();
+                if compiled.as_ref().map_or(true, |(at, _)| *at != modified) {
+                    compiled = match engine.compile_file(script.clone()) {
+                        Ok(ast) => {
+                            log::info!("Loaded hook script {}", script.display());
+                            Some((modified, ast))
+                        },
+                        Err(e) => {
+                            on_error(format!("{}: {}", script.display(), e));
+                            None
+                        },
+                    };
+                }
+                let Some((_, ast)) = &compiled else { continue };
+
+                let function = hook.function();
+                if !ast.iter_functions().any(|f| f.name == function) {
+                    continue;
+                }
+
+                log::info!("Running hook {} of {}", function, script.display());
+                *connection.borrow_mut() = method;
+                let result = engine.call_fn::<Dynamic>(&mut Scope::new(), ast, function, hook.args());
+                *connection.borrow_mut() = None;
+                if let Err(e) = result {
+                    on_error(format!("{} failed: {}", function, e));
+                }
+            }
+        });
+
+        ScriptHooks { sender }
+    }
+
+    /// Run `hook` after those already waiting; `method` is the connection
+    /// the script's transfer functions use
+    pub fn fire(&self, hook: Hook, method: Option<Box<dyn TransferMethod>>) {
+        if self.sender.send((hook, method)).is_err() {
+            log::error!("The hook script runner has stopped");
+        }
+    }
+}
+
+/// Engine with the functions scripts can call
+///
+/// Paths are strings; functions that fail raise an error that ends the
+/// hook unless the script catches it.
+fn build_engine(connection: Connection, name_template: String) -> Engine {
+    let mut engine = Engine::new();
+    engine.on_print(|text| log::info!("[script] {}", text));
+    engine.on_debug(|text, _, position| log::debug!("[script {}] {}", position, text));
+
+    // Transfers through the connection of the running hook
+    let current = connection.clone();
+    engine.register_fn("download", move |remote: &str, local: &str| -> ScriptResult<()> {
+        let current = current.borrow();
+        let method = connected(&current)?;
+        if let Some(parent) = Path::new(local).parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+        }
+        method.download_file(Path::new(remote), Path::new(local)).map_err(|e| e.to_string().into())
+    });
+
+    let current = connection.clone();
+    engine.register_fn("upload", move |local: &str, remote: &str| -> ScriptResult<()> {
+        let current = current.borrow();
+        connected(&current)?
+            .upload_file(Path::new(local), Path::new(remote))
+            .map_err(|e| e.to_string().into())
+    });
+
+    let current = connection.clone();
+    engine.register_fn("exec", move |command: &str| -> ScriptResult<String> {
+        let current = current.borrow();
+        let output = connected(&current)?.exec(command).map_err(|e| e.to_string())?;
+        if !output.success() {
+            return Err(format!("`{}` failed: {}", command, output.stderr.trim()).into());
+        }
+        Ok(output.stdout)
+    });
+
+    let current = connection;
+    engine.register_fn("list_remote", move |dir: &str| -> ScriptResult<Array> {
+        let current = current.borrow();
+        let entries = connected(&current)?.list_files(Path::new(dir)).map_err(|e| e.to_string())?;
+        Ok(entries.into_iter().map(|(name, _)| name.to_string_lossy().into_owned().into()).collect())
+    });
+
+    // Processing on this computer
+    engine.register_fn("process", move |input: &str, preset: &str, output_dir: &str| -> ScriptResult<String> {
+        process(Path::new(input), preset, Path::new(output_dir), &name_template)
+    });
+    engine.register_fn("convert", |input: &str, output: &str| -> ScriptResult<()> {
+        let image = image::open(input).map_err(|e| format!("{}: {}", input, e))?;
+        image.save(output).map_err(|e| format!("{}: {}", output, e).into())
+    });
+    engine.register_fn("is_image", |path: &str| is_image_file(Path::new(path)));
+
+    // Files on this computer
+    engine.register_fn("move_file", |from: &str, to: &str| -> ScriptResult<()> {
+        if let Some(parent) = Path::new(to).parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        // Renaming fails across file systems; copy and remove then
+        if fs::rename(from, to).is_err() {
+            fs::copy(from, to).map_err(|e| format!("{}: {}", from, e))?;
+            fs::remove_file(from).map_err(|e| format!("{}: {}", from, e))?;
+        }
+        Ok(())
+    });
+    engine.register_fn("make_dir", |path: &str| -> ScriptResult<()> {
+        fs::create_dir_all(path).map_err(|e| format!("{}: {}", path, e).into())
+    });
+    engine.register_fn("exists", |path: &str| Path::new(path).exists());
+    engine.register_fn("file_name", |path: &str| {
+        Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+    });
+    engine.register_fn("parent_dir", |path: &str| {
+        Path::new(path).parent().map(|dir| dir.display().to_string()).unwrap_or_default()
+    });
+    engine.register_fn("join_path", |dir: &str, name: &str| Path::new(dir).join(name).display().to_string());
+    engine.register_fn("capture_date", |path: &str| capture_date(Path::new(path)));
+
+    engine
+}
+
+fn connected(connection: &Option<Box<dyn TransferMethod>>) -> ScriptResult<&dyn TransferMethod> {
+    connection.as_deref().ok_or_else(|| "Not connected to a Pi".into())
+}
+
+/// Run `preset` on `input`, writing into `output_dir`; returns the result's
+/// path
+fn process(input: &Path, preset_name: &str, output_dir: &Path, name_template: &str) -> ScriptResult<String> {
+    let preset = ProcessingPreset::find(preset_name).ok_or_else(|| {
+        let names: Vec<&str> = BUILTIN_PRESETS.iter().map(|p| p.name).collect();
+        format!("Unknown preset '{}' (available: {})", preset_name, names.join(", "))
+    })?;
+    fs::create_dir_all(output_dir).map_err(|e| format!("{}: {}", output_dir.display(), e))?;
+
+    let context = OutputNameContext::new(input, preset.format.clone(), preset.name, 1).with_size(preset.resize);
+    let file_name = generate_output_filename(input, name_template, &context);
+    let output = output_dir.join(file_name.file_name().unwrap_or_default());
+    preset
+        .service()
+        .process_image(input, &output, preset.factory_index())
+        .map_err(|e| format!("{}: {}", input.display(), e))?;
+    Ok(output.display().to_string())
+}
+
+/// Day a photo was taken as `YYYY-MM-DD`, from its EXIF data or else its
+/// modification time, for sorting into dated folders
+fn capture_date(path: &Path) -> String {
+    if let Some(taken) = read_capture_time(path) {
+        return taken.format("%Y-%m-%d").to_string();
+    }
+    let modified = fs::metadata(path).and_then(|m| m.modified()).unwrap_or_else(|_| SystemTime::now());
+    DateTime::<Local>::from(modified).format("%Y-%m-%d").to_string()
+}
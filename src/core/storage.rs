@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::config::StorageSettings;
+use crate::core::file::temp_root;
+use crate::core::logging;
+use crate::transfer::{download_cache, thumbnail_cache};
+
+/// Temporary files changed more recently are spared when clearing, as a
+/// running job may still be writing them
+const IN_USE_GRACE: Duration = Duration::from_secs(60);
+
+/// Something the app keeps that can grow and be cleared
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Store {
+    /// Images downloaded from the Pi for previewing
+    PreviewCache,
+    /// Prefetched thumbnails of the Pi's images
+    ThumbnailCache,
+    /// Files downloaded for opening, camera captures and job leftovers
+    TempFiles,
+    /// Records shown in the Logs tab, held in memory
+    Logs,
+}
+
+impl Store {
+    pub const ALL: [Store; 4] = [Store::PreviewCache, Store::ThumbnailCache, Store::TempFiles, Store::Logs];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Store::PreviewCache => "Preview cache",
+            Store::ThumbnailCache => "Thumbnail cache",
+            Store::TempFiles => "Temporary files",
+            Store::Logs => "Log records",
+        }
+    }
+}
+
+/// How many entries a store holds and their size; for logs, the size of
+/// the text
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StoreUsage {
+    pub items: usize,
+    pub bytes: u64,
+}
+
+/// Current usage of `store`; walks the folders, so call off the main thread
+pub fn usage(store: Store) -> StoreUsage {
+    let (items, bytes) = match store {
+        Store::PreviewCache => download_cache::usage(),
+        Store::ThumbnailCache => thumbnail_cache::usage(),
+        Store::TempFiles => {
+            let entries = temp_entries();
+            (entries.len(), entries.iter().map(|(_, size, _)| size).sum())
+        },
+        Store::Logs => logging::usage(),
+    };
+    StoreUsage { items, bytes }
+}
+
+/// Empty `store`; returns the bytes freed
+pub fn clear(store: Store) -> u64 {
+    match store {
+        Store::PreviewCache => download_cache::clear(),
+        Store::ThumbnailCache => thumbnail_cache::clear(),
+        Store::TempFiles => remove_temp_files(IN_USE_GRACE),
+        Store::Logs => {
+            let (_, bytes) = logging::usage();
+            logging::clear();
+            bytes
+        },
+    }
+}
+
+/// Hand the cache limits of `settings` to the caches, which keep to them
+/// as they grow
+pub fn apply_limits(settings: &StorageSettings) {
+    download_cache::set_limit(settings.preview_cache_mb * 1024 * 1024);
+    thumbnail_cache::set_limit(settings.thumbnail_cache_mb * 1024 * 1024);
+}
+
+/// Bring every store within `settings`: trim the caches to their limits
+/// and delete temporary files older than allowed
+pub fn enforce(settings: &StorageSettings) {
+    apply_limits(settings);
+    download_cache::enforce();
+    thumbnail_cache::trim();
+    let freed = remove_temp_files(Duration::from_secs(settings.temp_file_hours * 3600).max(IN_USE_GRACE));
+    if freed > 0 {
+        log::info!("Deleted {} bytes of old temporary files", freed);
+    }
+}
+
+/// Each entry of the temporary folder with its size and last change
+fn temp_entries() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(entries) = fs::read_dir(temp_root()) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.path(), size_of(&entry.path()), modified))
+        })
+        .collect()
+}
+
+/// Delete temporary files and folders unchanged for `age`; returns the
+/// bytes freed
+fn remove_temp_files(age: Duration) -> u64 {
+    let now = SystemTime::now();
+    let mut freed = 0;
+    for (path, size, modified) in temp_entries() {
+        if now.duration_since(modified).unwrap_or_default() < age {
+            continue;
+        }
+        let removed = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+        match removed {
+            Ok(()) => freed += size,
+            Err(e) => log::warn!("Could not delete {}: {}", path.display(), e),
+        }
+    }
+    freed
+}
+
+/// Bytes of a file, or of everything under a folder; links count as
+/// themselves
+fn size_of(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else { return 0 };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| size_of(&entry.path())).sum())
+        .unwrap_or(0)
+}
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, Host, OutputDestination};
+use crate::core::dropbox::DropboxClient;
+use crate::transfer::connect_unattended;
+use crate::transfer::method::TransferMethod;
+use crate::transfer::ssh::shell_quote_path;
+
+/// Somewhere processed images are sent after they are written locally
+///
+/// Targets connect when the first file is sent, so making one is cheap
+/// and can be done on the main thread.
+pub trait UploadTarget: Send {
+    /// Where files go, for messages
+    fn label(&self) -> String;
+
+    /// Send the file at `path` under its own name
+    fn upload(&mut self, path: &Path) -> Result<(), String>;
+}
+
+/// Target for `destination`; None when results only stay local
+pub fn upload_target(destination: &OutputDestination, config: &Config) -> Result<Option<Box<dyn UploadTarget>>, String> {
+    match destination {
+        OutputDestination::Local => Ok(None),
+        OutputDestination::Pi { host, remote_dir } => {
+            let host = config
+                .hosts
+                .iter()
+                .find(|h| &h.name == host)
+                .cloned()
+                .ok_or_else(|| format!("Host '{}' is no longer saved", host))?;
+            Ok(Some(Box::new(PiFolder { host, remote_dir: PathBuf::from(remote_dir), method: None })))
+        },
+        OutputDestination::Dropbox { folder } => {
+            let client = DropboxClient::new(&config.dropbox)?;
+            Ok(Some(Box::new(DropboxFolder { client, folder: folder.clone() })))
+        },
+    }
+}
+
+/// Target of the preset named `preset` in `config`; a destination that
+/// cannot be used is logged and the results stay local
+pub fn preset_upload_target(config: &Config, preset: &str) -> Option<Box<dyn UploadTarget>> {
+    let destination = config.preset_destination(preset);
+    upload_target(&destination, config).unwrap_or_else(|e| {
+        log::error!("Results of preset '{}' stay local: {}", preset, e);
+        None
+    })
+}
+
+/// A folder on a saved host, created when the first file is sent
+struct PiFolder {
+    host: Host,
+    remote_dir: PathBuf,
+    method: Option<Box<dyn TransferMethod>>,
+}
+
+impl UploadTarget for PiFolder {
+    fn label(&self) -> String {
+        format!("{}:{}", self.host.name, self.remote_dir.display())
+    }
+
+    fn upload(&mut self, path: &Path) -> Result<(), String> {
+        if self.method.is_none() {
+            let method = connect_unattended(&self.host).map_err(|e| e.to_string())?;
+            // Methods without a shell, such as S3, have no folders to create
+            match method.exec(&format!("mkdir -p {}", shell_quote_path(&self.remote_dir))) {
+                Ok(output) if !output.success() => {
+                    return Err(format!("Could not create {}: {}", self.label(), output.stderr.trim()));
+                },
+                Ok(_) => {},
+                Err(e) => log::debug!("Not creating {}: {}", self.label(), e),
+            }
+            self.method = Some(method);
+        }
+        let method = self.method.as_ref().expect("connected above");
+        let remote_path = self.remote_dir.join(path.file_name().unwrap_or_default());
+        method.upload_file(path, &remote_path).map_err(|e| e.to_string())
+    }
+}
+
+/// A folder of the linked Dropbox account
+struct DropboxFolder {
+    client: DropboxClient,
+    folder: String,
+}
+
+impl UploadTarget for DropboxFolder {
+    fn label(&self) -> String {
+        format!("Dropbox {}", self.folder)
+    }
+
+    fn upload(&mut self, path: &Path) -> Result<(), String> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let dropbox_path = format!("/{}/{}", self.folder.trim_matches('/'), name).replace("//", "/");
+        self.client.upload(path, &dropbox_path)
+    }
+}
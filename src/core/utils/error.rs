@@ -1,38 +1,119 @@
-use std::fmt;
 use std::error::Error;
+use std::io;
 
-#[derive(Debug)]
+use thiserror::Error;
+
+use crate::core::image::operations::OperationError;
+use crate::core::locale::{tr, trf};
+use crate::core::image::processor::ProcessingError;
+use crate::transfer::method::TransferError;
+
+/// Crate-wide error type surfaced to the user
+#[derive(Debug, Error)]
 pub enum AppError {
+    #[error("Configuration error: {0}")]
     ConfigError(String),
+    #[error("File error: {0}")]
     FileError(String),
+    #[error("Network error: {0}")]
     NetworkError(String),
+    #[error("Processing error: {0}")]
     ProcessingError(String),
+    #[error("UI error: {0}")]
     UIError(String),
+    #[error("Not connected to a Raspberry Pi")]
+    NotConnected,
+    #[error(transparent)]
+    Transfer(#[from] TransferError),
+    #[error(transparent)]
+    Processing(#[from] ProcessingError),
+    #[error(transparent)]
+    Operation(#[from] OperationError),
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
 }
 
-impl fmt::Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl AppError {
+    /// What the user can do about the error, if there is anything obvious
+    pub fn hint(&self) -> Option<&'static str> {
         match self {
-            Self::ConfigError(msg) => write!(f, "Configuration error: {}", msg),
-            Self::FileError(msg) => write!(f, "File error: {}", msg),
-            Self::NetworkError(msg) => write!(f, "Network error: {}", msg),
-            Self::ProcessingError(msg) => write!(f, "Processing error: {}", msg),
-            Self::UIError(msg) => write!(f, "UI error: {}", msg),
+            Self::NotConnected =>
+                Some("Connect with Connection > Connect to Raspberry Pi first."),
+            Self::ConfigError(_) =>
+                Some("Check the settings file; deleting it restores the defaults."),
+            Self::NetworkError(_) | Self::Transfer(TransferError::ConnectionFailed(_)) =>
+                Some("Check that the Pi is powered on, on the same network and that SSH is enabled."),
+            Self::Transfer(TransferError::TimedOut(_)) =>
+                Some("The Pi did not answer in time; check it is online or raise listing_timeout_secs in the settings file."),
+            Self::Transfer(TransferError::AuthenticationFailed(_)) =>
+                Some("Check the username and password, or the SSH key selected for this host."),
+            Self::Transfer(TransferError::PermissionDenied(_)) =>
+                Some("The user on the Pi cannot read or write there; pick another location, fix its permissions or turn on sudo in the host's transfer options."),
+            Self::Transfer(TransferError::FileNotFound(_)) =>
+                Some("Refresh the file list; the file may have been moved or deleted."),
+            Self::Io(e) if e.kind() == io::ErrorKind::PermissionDenied =>
+                Some("Check the permissions of the local file or directory."),
+            Self::Io(e) if e.kind() == io::ErrorKind::NotFound =>
+                Some("Check that the local file still exists."),
+            Self::Processing(ProcessingError::NoProcessorAvailable) =>
+                Some("Select an output format in the Operations panel."),
+            _ => None,
+        }
+    }
+
+    /// Message and hint in the UI language, ready for an error dialog
+    pub fn user_message(&self) -> String {
+        match self.hint() {
+            Some(hint) => format!("{}\n\n{}", self.localized(), tr(hint)),
+            None => self.localized(),
+        }
+    }
+    
+    /// The error's message in the UI language; the details, which come
+    /// from the Pi or the system, stay as they were reported
+    fn localized(&self) -> String {
+        match self {
+            Self::ConfigError(message) => trf("Configuration error: {}", &[message]),
+            Self::FileError(message) => trf("File error: {}", &[message]),
+            Self::NetworkError(message) => trf("Network error: {}", &[message]),
+            Self::ProcessingError(message) => trf("Processing error: {}", &[message]),
+            Self::UIError(message) => trf("UI error: {}", &[message]),
+            Self::NotConnected => tr("Not connected to a Raspberry Pi"),
+            Self::Transfer(e) => match e {
+                TransferError::ConnectionFailed(message) => trf("Connection failed: {}", &[message]),
+                TransferError::AuthenticationFailed(message) => trf("Authentication failed: {}", &[message]),
+                TransferError::PermissionDenied(message) => trf("Permission denied: {}", &[message]),
+                TransferError::FileNotFound(message) => trf("File not found: {}", &[message]),
+                TransferError::TransferFailed(message) => trf("Transfer failed: {}", &[message]),
+                TransferError::TimedOut(message) => trf("Timed out: {}", &[message]),
+                TransferError::Cancelled => tr("Cancelled"),
+            },
+            Self::Processing(e) => match e {
+                ProcessingError::NoProcessorAvailable => tr("No suitable image processor available"),
+                ProcessingError::OperationFailed(e) => trf("Operation failed: {}", &[&localized_operation_error(e)]),
+                ProcessingError::ProcessingFailed(message) => trf("Processing failed: {}", &[message]),
+            },
+            Self::Operation(e) => localized_operation_error(e),
+            Self::Io(e) => trf("I/O error: {}", &[e]),
         }
     }
 }
 
-impl Error for AppError {}
+fn localized_operation_error(error: &OperationError) -> String {
+    match error {
+        OperationError::InvalidOperation(message) => trf("Invalid operation: {}", &[message]),
+        OperationError::ExecutionFailed(message) => trf("Operation execution failed: {}", &[message]),
+    }
+}
 
 pub type AppResult<T> = Result<T, AppError>;
 
 pub fn log_error(error: &dyn Error) {
-    eprintln!("Error: {}", error);
-    
+    log::error!("{}", error);
+
     let mut source = error.source();
     while let Some(err) = source {
-        eprintln!("Caused by: {}", err);
+        log::error!("Caused by: {}", err);
         source = err.source();
     }
 }
-// Add more conversions as needed
\ No newline at end of file
@@ -0,0 +1,100 @@
+/// Match `text` against a pattern where `*` is any run and `?` one character
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    matches(pattern, text, |p, t| p == t, false)
+}
+
+/// `glob_matches` ignoring ASCII case, as ssh does for host names
+pub fn glob_matches_ignore_case(pattern: &str, text: &str) -> bool {
+    matches(pattern, text, |p, t| p.eq_ignore_ascii_case(&t), false)
+}
+
+/// Match a `/`-separated path as rsync does: `*` and `?` stay within one
+/// name, while `**` also crosses into folders
+pub fn path_glob_matches(pattern: &str, path: &str) -> bool {
+    matches(pattern, path, |p, t| p == t, true)
+}
+
+fn matches(pattern: &str, text: &str, same: impl Fn(char, char) -> bool, in_path: bool) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let stops = |c: char| in_path && c == '/';
+
+    // reachable[j]: the pattern so far matches the first j characters
+    let mut reachable = vec![false; text.len() + 1];
+    reachable[0] = true;
+
+    let mut p = 0;
+    while p < pattern.len() {
+        let mut next = vec![false; text.len() + 1];
+        if pattern[p] == '*' {
+            let crosses = !in_path || pattern.get(p + 1) == Some(&'*');
+            while pattern.get(p + 1) == Some(&'*') {
+                p += 1;
+            }
+            // A run starts wherever the pattern so far ended
+            let mut run = false;
+            for j in 0..=text.len() {
+                run |= reachable[j];
+                next[j] = run;
+                if j < text.len() && !crosses && stops(text[j]) {
+                    run = false;
+                }
+            }
+        } else {
+            for j in 0..text.len() {
+                let fits = match pattern[p] {
+                    '?' => !stops(text[j]),
+                    c => same(c, text[j]),
+                };
+                next[j + 1] = reachable[j] && fits;
+            }
+        }
+        reachable = next;
+        p += 1;
+    }
+
+    reachable[text.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stars_and_question_marks() {
+        assert!(glob_matches("*.jpg", "photo.jpg"));
+        assert!(glob_matches("img_??.png", "img_01.png"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "axxbyyc"));
+        assert!(!glob_matches("*.jpg", "photo.jpeg"));
+        assert!(!glob_matches("img_??.png", "img_1.png"));
+        assert!(!glob_matches("a*b*c", "axxbyy"));
+    }
+
+    #[test]
+    fn plain_globs_cross_slashes() {
+        assert!(glob_matches("*.jpg", "dir/photo.jpg"));
+        assert!(glob_matches("a?b", "a/b"));
+    }
+
+    #[test]
+    fn case() {
+        assert!(!glob_matches("*.JPG", "photo.jpg"));
+        assert!(glob_matches_ignore_case("Pi-*", "pi-kitchen"));
+    }
+
+    #[test]
+    fn path_stars_stay_within_a_name() {
+        assert!(path_glob_matches("photos/*.jpg", "photos/a.jpg"));
+        assert!(!path_glob_matches("photos/*.jpg", "photos/raw/a.jpg"));
+        assert!(!path_glob_matches("photos?a.jpg", "photos/a.jpg"));
+        assert!(!path_glob_matches("*", "a/b"));
+    }
+
+    #[test]
+    fn double_stars_cross_folders() {
+        assert!(path_glob_matches("photos/**.jpg", "photos/raw/a.jpg"));
+        assert!(path_glob_matches("**/cache", "a/b/cache"));
+        assert!(!path_glob_matches("**/cache", "cache"));
+    }
+}
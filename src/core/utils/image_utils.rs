@@ -37,28 +37,87 @@ pub fn find_images_in_dir(dir: &Path) -> Vec<PathBuf> {
     images
 }
 
-pub fn generate_output_filename(
-    input_path: &Path,
-    output_format: ImageFormat,
-    suffix: Option<&str>
-) -> PathBuf {
-    let stem = input_path.file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("output");
-        
-    let mut filename = if let Some(suffix) = suffix {
-        format!("{}_{}", stem, suffix)
+/// Output naming used when none is configured: `photo.jpg` processed with
+/// the `web` preset becomes `photo_web.jpg`
+pub const DEFAULT_OUTPUT_NAME_TEMPLATE: &str = "{stem}_{op}.{ext}";
+
+/// Tokens an output name template understands, with what they stand for
+pub const OUTPUT_NAME_TOKENS: &[(&str, &str)] = &[
+    ("{stem}", "input file name without extension"),
+    ("{ext}", "extension of the output format"),
+    ("{op}", "preset or pipeline name"),
+    ("{width}", "image width in pixels"),
+    ("{height}", "image height in pixels"),
+    ("{date}", "today, as YYYY-MM-DD"),
+    ("{time}", "now, as HHMMSS"),
+    ("{counter}", "position of the image in its batch, from 1"),
+];
+
+/// What the tokens of an output name template stand for, for one image
+#[derive(Debug, Clone)]
+pub struct OutputNameContext {
+    pub stem: String,
+    pub format: ImageFormat,
+    pub op: String,
+    pub size: Option<(u32, u32)>,
+    pub counter: usize,
+}
+
+impl OutputNameContext {
+    /// Context for `input`, sized from its header when it can be read
+    pub fn new(input: &Path, format: ImageFormat, op: &str, counter: usize) -> Self {
+        Self {
+            stem: input.file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("output")
+                .to_string(),
+            format,
+            op: op.to_string(),
+            size: image::image_dimensions(input).ok(),
+            counter,
+        }
+    }
+
+    /// Use the size the output is scaled to rather than the input's
+    pub fn with_size(mut self, size: Option<(u32, u32)>) -> Self {
+        if size.is_some() {
+            self.size = size;
+        }
+        self
+    }
+}
+
+/// Fill in an output name template (see [`OUTPUT_NAME_TOKENS`])
+///
+/// Unknown tokens are kept as typed, a missing size leaves `{width}` and
+/// `{height}` as `0`, and path separators become `_` so the name stays in
+/// the output folder.
+pub fn format_output_name(template: &str, context: &OutputNameContext) -> String {
+    let now = chrono::Local::now();
+    let (width, height) = context.size.unwrap_or((0, 0));
+
+    let name = template
+        .replace("{stem}", &context.stem)
+        .replace("{ext}", context.format.extension())
+        .replace("{op}", &context.op)
+        .replace("{width}", &width.to_string())
+        .replace("{height}", &height.to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string())
+        .replace("{time}", &now.format("%H%M%S").to_string())
+        .replace("{counter}", &context.counter.to_string())
+        .replace(['/', '\\'], "_");
+
+    if name.trim().is_empty() {
+        format_output_name(DEFAULT_OUTPUT_NAME_TEMPLATE, context)
     } else {
-        stem.to_string()
-    };
-    
-    // Add the new extension
-    filename.push('.');
-    filename.push_str(output_format.extension());
-    
-    // Get the parent directory or use the current directory
+        name
+    }
+}
+
+/// Path next to `input` named by `template`
+pub fn generate_output_filename(input_path: &Path, template: &str, context: &OutputNameContext) -> PathBuf {
     let parent = input_path.parent().unwrap_or_else(|| Path::new("."));
-    
-    parent.join(filename)
-// Add more image utility functions as needed
-}
\ No newline at end of file
+    parent.join(format_output_name(template, context))
+}
+
+// Add more image utility functions as needed
\ No newline at end of file
@@ -1,5 +1,7 @@
 pub mod error;
+pub mod glob;
 pub mod image_utils;
+pub mod system_open;
 
 // Re-export the types needed by other modules
 pub use error::{
@@ -12,5 +14,21 @@ pub use image_utils::{
     is_image_file,
     get_image_format,
     find_images_in_dir,
-    generate_output_filename
+    format_output_name,
+    generate_output_filename,
+    OutputNameContext,
+    DEFAULT_OUTPUT_NAME_TEMPLATE,
+    OUTPUT_NAME_TOKENS
+};
+
+pub use glob::{
+    glob_matches,
+    glob_matches_ignore_case,
+    path_glob_matches
+};
+
+pub use system_open::{
+    file_manager_name,
+    open_with_default_app,
+    reveal_in_file_manager
 };
\ No newline at end of file
@@ -0,0 +1,113 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::core::utils::{AppError, AppResult};
+
+/// What the desktop's file manager is called, for menu labels
+pub fn file_manager_name() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "Finder"
+    } else if cfg!(target_os = "windows") {
+        "Explorer"
+    } else {
+        "Files"
+    }
+}
+
+/// Open a file or directory with the application the desktop associates with it
+pub fn open_with_default_app(path: &Path) -> AppResult<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("cmd");
+        command.args(["/c", "start", ""]).arg(path);
+        command
+    };
+
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg(path);
+        command
+    };
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = {
+        let mut command = Command::new("xdg-open");
+        command.arg(path);
+        command
+    };
+
+    spawn_detached(&mut command, path)
+}
+
+/// Show a file in the desktop's file manager with the file selected
+///
+/// On Linux the file manager is asked over D-Bus to select the file; when
+/// none answers, the containing directory is opened instead.
+pub fn reveal_in_file_manager(path: &Path) -> AppResult<()> {
+    #[cfg(target_os = "windows")]
+    {
+        let mut select = std::ffi::OsString::from("/select,");
+        select.push(path.as_os_str());
+        spawn_detached(Command::new("explorer").arg(select), path)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        spawn_detached(Command::new("open").arg("-R").arg(path), path)
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let shown = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--dest=org.freedesktop.FileManager1",
+                "--type=method_call",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+            ])
+            .arg(format!("array:string:{}", file_uri(path)))
+            .arg("string:")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false);
+
+        if shown {
+            return Ok(());
+        }
+        log::info!("No file manager answered over D-Bus; opening the containing directory");
+        match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => open_with_default_app(parent),
+            _ => open_with_default_app(path),
+        }
+    }
+}
+
+/// Start `command` without waiting for it; only a failure to start is an error
+fn spawn_detached(command: &mut Command, path: &Path) -> AppResult<()> {
+    command
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| AppError::FileError(format!("Could not open {}: {}", path.display(), e)))
+}
+
+/// `file://` URI of an absolute path with reserved bytes percent-encoded
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn file_uri(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut uri = String::from("file://");
+    for &byte in path.as_os_str().as_bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
@@ -2,37 +2,35 @@ mod ui;
 mod core;
 mod transfer;
 mod config;
+mod cli;
 
 use fltk::app;
 
 use crate::ui::main_window::main_window::MainWindow;
-use crate::config::Config;
 
 fn main() {
+    crate::core::logging::init();
+    
+    // Headless commands run without initializing FLTK
+    if let Some(code) = cli::run_from_args() {
+        std::process::exit(code);
+    }
+    
     // Initialize the FLTK application
     let app = app::App::default().with_scheme(app::Scheme::Gtk);
     
-    // Load application configuration
-    let config = Config::load().unwrap_or_else(|err| {
-        eprintln!("Warning: Failed to load config ({}), using defaults", err);
-        Config::default()
-    });
-    
     // Create the main application window
-    let mut main_window = MainWindow::new(
-        "Pi Image Processor", 
-        config.window_width,
-        config.window_height
-    );
+    let mut main_window = MainWindow::new("Pi Image Processor");
     
     // Show the window and enter the application main loop
     main_window.show();
     
+    // Pick up where the last session left off, if enabled
+    main_window.restore_session();
+    
     // Run the application
     app.run().unwrap();
     
-    // Save configuration on exit
-    if let Err(err) = config.save() {
-        eprintln!("Warning: Failed to save config: {}", err);
-    }
+    // Save the settings the panels changed, with the window layout
+    main_window.save_state();
 }
\ No newline at end of file
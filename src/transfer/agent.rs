@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::transfer::method::{os_string_from_bytes, stream_lines_with_deadline, wait_with_deadline, RemoteEntry, TransferError, TransferMethod};
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+
+/// Protocol spoken by this build; agents reporting another are not used
+pub const AGENT_PROTOCOL_VERSION: u32 = 1;
+
+/// Where the agent is installed, relative to the Pi user's home, when it
+/// is not on the PATH
+pub const AGENT_INSTALL_PATH: &str = ".local/bin/pi_remote_manager";
+
+/// How long the Pi may take to answer whether it has an agent
+const DETECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Exit status of the launcher when no agent is installed
+const NOT_INSTALLED_EXIT: i32 = 127;
+
+/// One request, passed to `pi_remote_manager agent` on the Pi as a JSON
+/// argument
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum AgentRequest {
+    /// Report the protocol version
+    Hello,
+    /// Every entry of a directory, hidden ones included
+    List { path: PathBuf },
+    /// SHA-256 of every file under a folder, recursively
+    Checksums { root: PathBuf },
+    /// Shrink the images in a folder to fit `size` pixels, writing them
+    /// under the same names into `output_dir`
+    Thumbnails { dir: PathBuf, size: u32, output_dir: PathBuf },
+    /// Shrink the given images to fit `size` pixels, writing them into
+    /// `output_dir` numbered in the order given; agents from before this
+    /// request answer it with a fatal error
+    ThumbnailFiles { files: Vec<PathBuf>, size: u32, output_dir: PathBuf },
+}
+
+/// One line of the agent's answer; every answer ends with `Done` or a
+/// fatal `Error`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AgentMessage {
+    Hello { protocol: u32, version: String },
+    /// For links `is_dir` says whether the target is a directory; agents
+    /// from before links were reported send no `link_target`. Names that
+    /// aren't UTF-8 also come as their raw bytes in `name_bytes`.
+    Entry {
+        name: String,
+        is_dir: bool,
+        size: u64,
+        modified: Option<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        link_target: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name_bytes: Option<Vec<u8>>,
+    },
+    /// Path relative to the checksummed folder
+    Checksum { path: PathBuf, sha256: String },
+    Thumbnail { source: PathBuf, output: PathBuf },
+    Progress { done: usize, total: usize },
+    /// A failure for one `path`, or for the whole request without one
+    Error { message: String, path: Option<PathBuf> },
+    Done,
+}
+
+/// Whether each connection's Pi has a usable agent, by connection key
+fn detected() -> &'static Mutex<HashMap<String, bool>> {
+    static DETECTED: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+    DETECTED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Shell command running the agent with `request`, from the PATH or the
+/// install folder
+fn agent_command(request: &AgentRequest) -> String {
+    let json = serde_json::to_string(request).unwrap_or_default();
+    format!(
+        "a=$(command -v pi_remote_manager || echo \"$HOME\"/{}); [ -x \"$a\" ] || exit {}; exec \"$a\" agent {}",
+        AGENT_INSTALL_PATH,
+        NOT_INSTALLED_EXIT,
+        shell_quote(&json)
+    )
+}
+
+/// Whether the operations below should go through the agent on the Pi
+/// behind `method`: the host allows it and an agent of this protocol
+/// answers. The Pi is asked once per connection.
+pub fn agent_available(method: &dyn TransferMethod) -> bool {
+    if !method.prefers_agent() {
+        return false;
+    }
+
+    let key = method.connection_key();
+    if let Some(available) = detected().lock().unwrap().get(&key) {
+        return *available;
+    }
+
+    let available = match detect(method) {
+        Ok(version) => {
+            log::info!("Using the companion agent {} on {}", version, key);
+            true
+        },
+        Err(e) => {
+            log::debug!("No companion agent on {}: {}", key, e);
+            false
+        },
+    };
+    detected().lock().unwrap().insert(key, available);
+    available
+}
+
+/// Ask the Pi again the next time, e.g. after installing the agent
+pub fn forget_agent(method: &dyn TransferMethod) {
+    detected().lock().unwrap().remove(&method.connection_key());
+}
+
+/// Version of the agent on the Pi
+pub fn detect(method: &dyn TransferMethod) -> Result<String, TransferError> {
+    let child = method.spawn_remote(&agent_command(&AgentRequest::Hello))?;
+    let output = wait_with_deadline(child, DETECT_TIMEOUT, &AtomicBool::new(false))?;
+    if output.exit_code == Some(NOT_INSTALLED_EXIT) {
+        return Err(TransferError::FileNotFound("the agent is not installed".to_string()));
+    }
+
+    for line in output.stdout.lines() {
+        if let Ok(AgentMessage::Hello { protocol, version }) = serde_json::from_str(line) {
+            return if protocol == AGENT_PROTOCOL_VERSION {
+                Ok(version)
+            } else {
+                Err(TransferError::TransferFailed(format!(
+                    "agent {} speaks protocol {}, this build {}",
+                    version, protocol, AGENT_PROTOCOL_VERSION
+                )))
+            };
+        }
+    }
+    Err(TransferError::TransferFailed(format!("the agent did not answer: {}", output.stderr.trim())))
+}
+
+/// Start the agent with `request` without waiting for its answer
+pub fn spawn_agent(method: &dyn TransferMethod, request: &AgentRequest) -> Result<Child, TransferError> {
+    method.spawn_remote(&agent_command(request))
+}
+
+/// Hand each message from a spawned agent to `on_message` until it is
+/// done; a fatal error from the agent is returned as the error
+pub fn read_agent(
+    child: Child,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    on_message: &mut dyn FnMut(AgentMessage)
+) -> Result<(), TransferError> {
+    let mut failure = None;
+    let output = stream_lines_with_deadline(child, timeout, cancel, &mut |line| {
+        match serde_json::from_str::<AgentMessage>(line) {
+            Ok(AgentMessage::Error { message, path: None }) => failure = Some(message),
+            Ok(message) => on_message(message),
+            Err(e) => log::debug!("Ignoring agent output {:?}: {}", line, e),
+        }
+    })?;
+
+    if let Some(message) = failure {
+        return Err(TransferError::TransferFailed(message));
+    }
+    match output.exit_code {
+        Some(0) => Ok(()),
+        Some(NOT_INSTALLED_EXIT) => Err(TransferError::FileNotFound("the agent is not installed".to_string())),
+        _ => Err(TransferError::TransferFailed(output.stderr.trim().to_string())),
+    }
+}
+
+/// Run `request` through the agent, handing it each message
+pub fn run_agent(
+    method: &dyn TransferMethod,
+    request: &AgentRequest,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    on_message: &mut dyn FnMut(AgentMessage)
+) -> Result<(), TransferError> {
+    read_agent(spawn_agent(method, request)?, timeout, cancel, on_message)
+}
+
+/// List `dir` through the agent, handing entries to `on_chunk` in batches
+/// of `chunk_size`
+pub fn list_directory(
+    method: &dyn TransferMethod,
+    dir: &Path,
+    chunk_size: usize,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+) -> Result<(), TransferError> {
+    let mut chunk = Vec::new();
+    run_agent(method, &AgentRequest::List { path: dir.to_path_buf() }, timeout, cancel, &mut |message| {
+        if let AgentMessage::Entry { name, is_dir, size, modified, link_target, name_bytes } = message {
+            let name = match name_bytes {
+                Some(bytes) => os_string_from_bytes(bytes),
+                None => name.into(),
+            };
+            chunk.push(RemoteEntry { name, is_dir, size, modified, link_target });
+            if chunk.len() == chunk_size {
+                on_chunk(std::mem::take(&mut chunk));
+            }
+        }
+    })?;
+    if !chunk.is_empty() {
+        on_chunk(chunk);
+    }
+    Ok(())
+}
+
+/// Copy `binary`, a build of this program for the Pi's architecture, into
+/// the install folder on the Pi and check that it answers; returns its
+/// version
+pub fn install_agent(method: &dyn TransferMethod, binary: &Path) -> Result<String, TransferError> {
+    let folder = Path::new(AGENT_INSTALL_PATH).parent().unwrap_or(Path::new("."));
+    let output = method.exec(&format!("mkdir -p \"$HOME\"/{}", shell_quote_path(folder)))?;
+    if !output.success() {
+        return Err(TransferError::PermissionDenied(output.stderr.trim().to_string()));
+    }
+
+    // Relative remote paths are taken from the home folder
+    method.upload_file(binary, Path::new(AGENT_INSTALL_PATH))?;
+    let output = method.exec(&format!("chmod +x \"$HOME\"/{}", shell_quote(AGENT_INSTALL_PATH)))?;
+    if !output.success() {
+        return Err(TransferError::PermissionDenied(output.stderr.trim().to_string()));
+    }
+
+    forget_agent(method);
+    detect(method).map_err(|e| TransferError::TransferFailed(format!(
+        "The agent was copied but does not run on the Pi; is it built for the Pi's architecture? ({})",
+        e
+    )))
+}
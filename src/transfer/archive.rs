@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::transfer::method::{TransferMethod, TransferError};
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+
+/// Archive a selection is packed into before it is copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    pub const ALL: [ArchiveFormat; 2] = [ArchiveFormat::TarGz, ArchiveFormat::Zip];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+            Self::Zip => "zip",
+        }
+    }
+
+    /// Arguments that pack `names` into `archive`, run in their folder;
+    /// symbolic links are stored as links when `keep_links`, otherwise what
+    /// they point to is packed
+    fn pack_args(&self, archive: &str, names: &[String], keep_links: bool) -> Vec<String> {
+        let mut args: Vec<String> = match self {
+            Self::TarGz => vec!["tar".into(), if keep_links { "-czf" } else { "-czhf" }.into(), archive.into(), "--".into()],
+            Self::Zip => vec!["zip".into(), if keep_links { "-qry" } else { "-qr" }.into(), archive.into(), "--".into()],
+        };
+        args.extend(names.iter().cloned());
+        args
+    }
+
+    /// Arguments that unpack `archive` into `dir`
+    fn extract_args(&self, archive: &str, dir: &str) -> Vec<String> {
+        match self {
+            Self::TarGz => vec!["tar".into(), "-xzf".into(), archive.into(), "-C".into(), dir.into()],
+            Self::Zip => vec!["unzip".into(), "-q".into(), "-o".into(), archive.into(), "-d".into(), dir.into()],
+        }
+    }
+}
+
+/// A selection of files and folders in one folder, to be copied as a
+/// single archive
+#[derive(Debug, Clone)]
+pub struct ArchiveTransfer {
+    /// Folder the selection is in
+    pub source_dir: PathBuf,
+    /// Names of the selected entries in `source_dir`
+    pub names: Vec<String>,
+    /// Folder on the other side the archive is copied into
+    pub target_dir: PathBuf,
+    pub format: ArchiveFormat,
+    /// Unpack the archive in `target_dir` and delete it once copied
+    pub extract: bool,
+}
+
+impl ArchiveTransfer {
+    /// Name of the archive: the entry's own name for a single entry,
+    /// otherwise the name of the folder holding the selection
+    pub fn archive_name(&self) -> String {
+        let base = match self.names.as_slice() {
+            [name] => name.clone(),
+            _ => self
+                .source_dir
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "selection".to_string()),
+        };
+        format!("{}.{}", base, self.format.extension())
+    }
+
+    /// Pack the local selection, upload it and unpack it on the Pi if asked;
+    /// returns the path on the Pi of the archive or, once unpacked, the
+    /// target folder
+    pub fn upload(&self, method: &dyn TransferMethod) -> Result<PathBuf, TransferError> {
+        let local_archive = local_temp_dir()?.join(self.archive_name());
+        let _ = fs::remove_file(&local_archive);
+        run_local(&self.format.pack_args(&local_archive.to_string_lossy(), &self.names, method.preserves_symlinks()), &self.source_dir)?;
+
+        let remote_archive = self.target_dir.join(self.archive_name());
+        let uploaded = method.upload_file(&local_archive, &remote_archive);
+        let _ = fs::remove_file(&local_archive);
+        uploaded?;
+
+        if !self.extract {
+            return Ok(remote_archive);
+        }
+        let archive = remote_archive.to_string_lossy();
+        let mut command = shell_command(&self.format.extract_args(&archive, &self.target_dir.to_string_lossy()));
+        command.push_str(&format!(" && rm -f -- {}", shell_quote(&archive)));
+        run_remote(method, &command)?;
+        Ok(self.target_dir.clone())
+    }
+
+    /// Pack the selection on the Pi, download it and unpack it locally if
+    /// asked; returns the local path of the archive or, once unpacked, the
+    /// target folder
+    pub fn download(&self, method: &dyn TransferMethod) -> Result<PathBuf, TransferError> {
+        let remote_archive = PathBuf::from(format!("/tmp/pi_remote_manager-{}-{}", std::process::id(), self.archive_name()));
+        let archive = remote_archive.to_string_lossy();
+        let command = format!(
+            "cd {} && {}",
+            shell_quote_path(&self.source_dir),
+            shell_command(&self.format.pack_args(&archive, &self.names, method.preserves_symlinks()))
+        );
+        let packed = run_remote(method, &command);
+
+        let local_archive = self.target_dir.join(self.archive_name());
+        let downloaded = packed.and_then(|_| method.download_file(&remote_archive, &local_archive));
+        let _ = method.exec(&format!("rm -f -- {}", shell_quote(&archive)));
+        downloaded?;
+
+        if !self.extract {
+            return Ok(local_archive);
+        }
+        run_local(
+            &self.format.extract_args(&local_archive.to_string_lossy(), &self.target_dir.to_string_lossy()),
+            &self.target_dir
+        )?;
+        let _ = fs::remove_file(&local_archive);
+        Ok(self.target_dir.clone())
+    }
+}
+
+fn local_temp_dir() -> Result<PathBuf, TransferError> {
+    let dir = std::env::temp_dir().join("pi_remote_manager");
+    fs::create_dir_all(&dir).map_err(|e| TransferError::TransferFailed(format!("{}: {}", dir.display(), e)))?;
+    Ok(dir)
+}
+
+fn shell_command(args: &[String]) -> String {
+    args.iter().map(|arg| shell_quote(arg)).collect::<Vec<_>>().join(" ")
+}
+
+/// Run a packing tool on this computer in `dir`
+fn run_local(args: &[String], dir: &Path) -> Result<(), TransferError> {
+    let output = Command::new(&args[0])
+        .args(&args[1..])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| TransferError::TransferFailed(format!("Could not run {}: {}", args[0], e)))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(TransferError::TransferFailed(format!(
+            "{} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+fn run_remote(method: &dyn TransferMethod, command: &str) -> Result<(), TransferError> {
+    let output = method.exec(command)?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::TransferFailed(output.stderr.trim().to_string()))
+    }
+}
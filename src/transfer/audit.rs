@@ -0,0 +1,314 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use sha2::{Digest, Sha256};
+
+use crate::transfer::agent::{agent_available, read_agent, spawn_agent, AgentMessage, AgentRequest};
+use crate::transfer::filter::PathFilter;
+use crate::transfer::method::{stream_lines_with_deadline, TransferError, TransferMethod};
+use crate::transfer::ssh::shell_quote_path;
+
+/// Longest the Pi may go without finishing a checksum; large videos on an
+/// SD card take a while
+const REMOTE_HASH_STALL_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How a file compares between the two folders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AuditStatus {
+    Differs,
+    MissingOnPi,
+    MissingLocally,
+    Matches,
+}
+
+impl AuditStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Differs => "differs",
+            Self::MissingOnPi => "missing on Pi",
+            Self::MissingLocally => "missing locally",
+            Self::Matches => "matches",
+        }
+    }
+}
+
+/// One file of an audit, with its SHA-256 on each side that has it
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    /// Path relative to the audited folders
+    pub path: PathBuf,
+    pub status: AuditStatus,
+    pub local_sha256: Option<String>,
+    pub remote_sha256: Option<String>,
+}
+
+/// Result of comparing a local folder with a folder on the Pi
+#[derive(Debug, Clone)]
+pub struct AuditReport {
+    pub local_dir: PathBuf,
+    pub remote_dir: PathBuf,
+    pub checked_at: DateTime<Local>,
+    /// Problems first, then matches, each by path
+    pub entries: Vec<AuditEntry>,
+}
+
+impl AuditReport {
+    pub fn count(&self, status: AuditStatus) -> usize {
+        self.entries.iter().filter(|entry| entry.status == status).count()
+    }
+
+    /// Whether both folders hold exactly the same files
+    pub fn is_clean(&self) -> bool {
+        self.entries.iter().all(|entry| entry.status == AuditStatus::Matches)
+    }
+
+    /// One line per status with its count
+    pub fn summary(&self) -> String {
+        [AuditStatus::Matches, AuditStatus::Differs, AuditStatus::MissingOnPi, AuditStatus::MissingLocally]
+            .iter()
+            .map(|status| format!("{}: {}", status.label(), self.count(*status)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Write every entry to `path` as CSV
+    pub fn write_csv(&self, path: &Path) -> io::Result<()> {
+        let mut writer = csv::Writer::from_path(path)?;
+        writer.write_record(["path", "status", "local_sha256", "remote_sha256", "local_dir", "remote_dir", "checked_at"])?;
+        let local_dir = self.local_dir.to_string_lossy();
+        let remote_dir = self.remote_dir.to_string_lossy();
+        let checked_at = self.checked_at.to_rfc3339();
+        for entry in &self.entries {
+            writer.write_record([
+                entry.path.to_string_lossy().as_ref(),
+                entry.status.label(),
+                entry.local_sha256.as_deref().unwrap_or(""),
+                entry.remote_sha256.as_deref().unwrap_or(""),
+                local_dir.as_ref(),
+                remote_dir.as_ref(),
+                checked_at.as_str(),
+            ])?;
+        }
+        writer.flush()
+    }
+}
+
+/// Checksum every file under `local_dir` and `remote_dir` and compare them,
+/// calling `progress` with the number of files checksummed so far
+///
+/// The Pi hashes its files with its companion agent, or `sha256sum`
+/// without one, while the local files are hashed here, so both sides are
+/// read at the same time.
+pub fn audit_folders(
+    method: &dyn TransferMethod,
+    local_dir: &Path,
+    remote_dir: &Path,
+    filter: &PathFilter,
+    progress: &dyn Fn(usize),
+    cancel: &AtomicBool
+) -> Result<AuditReport, TransferError> {
+    let via_agent = agent_available(method);
+    let mut child = if via_agent {
+        spawn_agent(method, &AgentRequest::Checksums { root: remote_dir.to_path_buf() })?
+    } else {
+        let command = format!(
+            "cd {} && find . -type f -print0 | xargs -0 -r sha256sum --",
+            shell_quote_path(remote_dir)
+        );
+        method.spawn_remote(&command)?
+    };
+
+    let local = match local_hashes(local_dir, filter, progress, cancel) {
+        Ok(local) => local,
+        Err(e) => {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+    };
+    let hashed_locally = local.len();
+
+    let mut remote = HashMap::new();
+    let mut record = |path: PathBuf, hash: String| {
+        if allowed(filter, &path) {
+            remote.insert(path, hash);
+            progress(hashed_locally + remote.len());
+        }
+    };
+    let failure = if via_agent {
+        let mut unreadable = None;
+        let result = read_agent(child, REMOTE_HASH_STALL_TIMEOUT, cancel, &mut |message| match message {
+            AgentMessage::Checksum { path, sha256 } => record(path, sha256),
+            AgentMessage::Error { message, path: Some(path) } => {
+                unreadable.get_or_insert(format!("{}: {}", path.display(), message));
+            },
+            _ => {},
+        });
+        match result {
+            Err(e @ (TransferError::Cancelled | TransferError::TimedOut(_))) => return Err(e),
+            Err(e) => Some(e.to_string()),
+            Ok(()) => unreadable,
+        }
+    } else {
+        let output = stream_lines_with_deadline(child, REMOTE_HASH_STALL_TIMEOUT, cancel, &mut |line| {
+            if let Some((path, hash)) = parse_sha256sum_line(line) {
+                record(path, hash);
+            }
+        })?;
+        (!output.success()).then(|| output.stderr.trim().to_string())
+    };
+    if let Some(failure) = failure {
+        return Err(TransferError::TransferFailed(format!(
+            "Checksumming {} on the Pi failed: {}",
+            remote_dir.display(),
+            failure
+        )));
+    }
+
+    Ok(AuditReport {
+        local_dir: local_dir.to_path_buf(),
+        remote_dir: remote_dir.to_path_buf(),
+        checked_at: Local::now(),
+        entries: compare(local, remote),
+    })
+}
+
+/// Number of files under `dir` the audit would checksum
+pub fn count_local_files(dir: &Path, filter: &PathFilter) -> usize {
+    walk_local(dir, filter).map(|files| files.len()).unwrap_or(0)
+}
+
+fn compare(local: BTreeMap<PathBuf, String>, mut remote: HashMap<PathBuf, String>) -> Vec<AuditEntry> {
+    let mut entries: Vec<AuditEntry> = local
+        .into_iter()
+        .map(|(path, local_hash)| {
+            let remote_hash = remote.remove(&path);
+            let status = match &remote_hash {
+                Some(hash) if *hash == local_hash => AuditStatus::Matches,
+                Some(_) => AuditStatus::Differs,
+                None => AuditStatus::MissingOnPi,
+            };
+            AuditEntry { path, status, local_sha256: Some(local_hash), remote_sha256: remote_hash }
+        })
+        .collect();
+    entries.extend(remote.into_iter().map(|(path, hash)| AuditEntry {
+        path,
+        status: AuditStatus::MissingLocally,
+        local_sha256: None,
+        remote_sha256: Some(hash),
+    }));
+    entries.sort_by(|a, b| a.status.cmp(&b.status).then_with(|| a.path.cmp(&b.path)));
+    entries
+}
+
+fn local_hashes(
+    dir: &Path,
+    filter: &PathFilter,
+    progress: &dyn Fn(usize),
+    cancel: &AtomicBool
+) -> Result<BTreeMap<PathBuf, String>, TransferError> {
+    let files = walk_local(dir, filter)
+        .map_err(|e| TransferError::FileNotFound(format!("{}: {}", dir.display(), e)))?;
+    let mut hashes = BTreeMap::new();
+    for relative in files {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(TransferError::Cancelled);
+        }
+        let path = dir.join(&relative);
+        let hash = sha256_file(&path)
+            .map_err(|e| TransferError::TransferFailed(format!("{}: {}", path.display(), e)))?;
+        hashes.insert(relative, hash);
+        progress(hashes.len());
+    }
+    Ok(hashes)
+}
+
+/// Relative paths of the files under `dir` the filter allows
+fn walk_local(dir: &Path, filter: &PathFilter) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut folders = vec![PathBuf::new()];
+    while let Some(relative) = folders.pop() {
+        for entry in fs::read_dir(dir.join(&relative))?.flatten() {
+            let path = relative.join(entry.file_name());
+            let Ok(file_type) = entry.file_type() else { continue };
+            if !filter.allows(&path, file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                folders.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 1 << 16];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Whether a file from the Pi's listing passes the filter, folders included
+fn allowed(filter: &PathFilter, path: &Path) -> bool {
+    let mut folder = PathBuf::new();
+    let components: Vec<_> = path.iter().collect();
+    for (index, name) in components.iter().enumerate() {
+        folder.push(name);
+        if !filter.allows(&folder, index + 1 < components.len()) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Path and hash from a `sha256sum` line such as `<hash>  ./dir/file`;
+/// names with a backslash or newline are escaped and the line starts with `\`
+fn parse_sha256sum_line(line: &str) -> Option<(PathBuf, String)> {
+    let (escaped, line) = match line.strip_prefix('\\') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (hash, name) = line.split_once(' ')?;
+    // Text mode has a second space, binary mode a `*`
+    let name = name.strip_prefix(' ').or_else(|| name.strip_prefix('*'))?;
+    let name = if escaped { unescape(name) } else { name.to_string() };
+    let name = name.strip_prefix("./").unwrap_or(&name);
+    if hash.len() != 64 {
+        return None;
+    }
+    Some((PathBuf::from(name), hash.to_lowercase()))
+}
+
+fn unescape(name: &str) -> String {
+    let mut unescaped = String::with_capacity(name.len());
+    let mut chars = name.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            },
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            },
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use crate::transfer::method::{TransferMethod, TransferError};
+use crate::transfer::ssh::shell_quote_path;
+
+/// A job sent to every member of a device group
+#[derive(Debug, Clone)]
+pub enum BroadcastJob {
+    /// Copy a single file into a remote directory
+    UploadFile { local_path: PathBuf, remote_dir: PathBuf },
+    /// Copy a directory tree into a remote directory, creating folders as needed
+    UploadTree { local_dir: PathBuf, remote_dir: PathBuf },
+    /// Run a shell command
+    Exec { command: String },
+}
+
+impl BroadcastJob {
+    pub fn describe(&self) -> String {
+        match self {
+            Self::UploadFile { local_path, remote_dir } =>
+                format!("Upload {} to {}", local_path.display(), remote_dir.display()),
+            Self::UploadTree { local_dir, remote_dir } =>
+                format!("Upload folder {} to {}", local_dir.display(), remote_dir.display()),
+            Self::Exec { command } => format!("Run `{}`", command),
+        }
+    }
+}
+
+/// Result of a job on one device
+#[derive(Debug, Clone)]
+pub struct BroadcastOutcome {
+    /// Device name as shown to the user
+    pub device: String,
+    /// Short summary on success, error message on failure
+    pub result: Result<String, String>,
+}
+
+/// Run a job on every device in parallel and collect the per-device results
+/// in the order the devices were given
+pub fn run_broadcast(
+    devices: Vec<(String, Box<dyn TransferMethod>)>,
+    job: &BroadcastJob
+) -> Vec<BroadcastOutcome> {
+    let handles: Vec<_> = devices
+        .into_iter()
+        .map(|(device, method)| {
+            let job = job.clone();
+            let handle = thread::spawn(move || {
+                run_job(method.as_ref(), &job).map_err(|e| e.to_string())
+            });
+            (device, handle)
+        })
+        .collect();
+
+    handles
+        .into_iter()
+        .map(|(device, handle)| {
+            let result = handle
+                .join()
+                .unwrap_or_else(|_| Err("Worker thread panicked".to_string()));
+            BroadcastOutcome { device, result }
+        })
+        .collect()
+}
+
+/// Format results as a per-device summary
+pub fn summarize(job: &BroadcastJob, outcomes: &[BroadcastOutcome]) -> String {
+    let succeeded = outcomes.iter().filter(|o| o.result.is_ok()).count();
+
+    let mut summary = format!(
+        "{}\n{} of {} devices succeeded\n\n",
+        job.describe(),
+        succeeded,
+        outcomes.len()
+    );
+
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(message) => summary.push_str(&format!("[ok]     {}: {}\n", outcome.device, message)),
+            Err(message) => summary.push_str(&format!("[failed] {}: {}\n", outcome.device, message.trim())),
+        }
+    }
+
+    summary
+}
+
+fn run_job(method: &dyn TransferMethod, job: &BroadcastJob) -> Result<String, TransferError> {
+    match job {
+        BroadcastJob::UploadFile { local_path, remote_dir } => {
+            let file_name = local_path.file_name().ok_or_else(|| {
+                TransferError::FileNotFound(local_path.display().to_string())
+            })?;
+            make_remote_dir(method, remote_dir)?;
+            method.upload_file(local_path, &remote_dir.join(file_name))?;
+            Ok("uploaded".to_string())
+        },
+        BroadcastJob::UploadTree { local_dir, remote_dir } => {
+            let base_name = local_dir.file_name().ok_or_else(|| {
+                TransferError::FileNotFound(local_dir.display().to_string())
+            })?;
+            let target = remote_dir.join(base_name);
+            let count = upload_tree(method, local_dir, &target)?;
+            Ok(format!("{} files uploaded", count))
+        },
+        BroadcastJob::Exec { command } => {
+            let output = method.exec(command)?;
+            if output.success() {
+                Ok(output.stdout.lines().last().unwrap_or("done").to_string())
+            } else {
+                Err(TransferError::TransferFailed(format!(
+                    "exit status {}: {}",
+                    output.exit_code.map(|c| c.to_string()).unwrap_or_else(|| "signal".to_string()),
+                    output.stderr.trim()
+                )))
+            }
+        },
+    }
+}
+
+fn make_remote_dir(method: &dyn TransferMethod, remote_dir: &Path) -> Result<(), TransferError> {
+    let output = method.exec(&format!("mkdir -p {}", shell_quote_path(remote_dir)))?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::PermissionDenied(output.stderr.trim().to_string()))
+    }
+}
+
+fn upload_tree(method: &dyn TransferMethod, local_dir: &Path, remote_dir: &Path) -> Result<usize, TransferError> {
+    make_remote_dir(method, remote_dir)?;
+
+    let entries = fs::read_dir(local_dir).map_err(|e| {
+        TransferError::FileNotFound(format!("{}: {}", local_dir.display(), e))
+    })?;
+
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let target = remote_dir.join(entry.file_name());
+
+        if path.is_dir() {
+            count += upload_tree(method, &path, &target)?;
+        } else {
+            method.upload_file(&path, &target)?;
+            count += 1;
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,240 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::ssh::shell_quote;
+
+/// How long connecting to the stream and each read may take
+const STREAM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest frame accepted before the data is assumed not to be JPEG
+const MAX_FRAME_BYTES: usize = 16 << 20;
+
+/// How a camera on the Pi serves its pictures
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamMode {
+    /// `multipart/x-mixed-replace` stream of JPEG frames, as served by
+    /// picamera2's examples, mjpg-streamer or motion
+    Mjpeg,
+    /// A URL returning one JPEG per request, fetched repeatedly
+    Snapshot,
+}
+
+impl StreamMode {
+    pub const ALL: [StreamMode; 2] = [Self::Mjpeg, Self::Snapshot];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Mjpeg => "MJPEG stream",
+            Self::Snapshot => "HTTP snapshots",
+        }
+    }
+}
+
+/// Where to read a live view from
+#[derive(Debug, Clone)]
+pub struct StreamSource {
+    pub url: String,
+    pub mode: StreamMode,
+    /// Fetch the URL on the Pi with curl and read it over the ssh
+    /// connection, for cameras only listening on the Pi's localhost or
+    /// behind a firewall
+    pub through_ssh: bool,
+    /// Pause between snapshots
+    pub snapshot_interval: Duration,
+}
+
+/// Pulls whole JPEG pictures out of a byte stream by their start and end
+/// markers, which works for multipart streams and bare snapshots alike
+/// without parsing the HTTP framing
+pub struct JpegFrames<R: Read> {
+    reader: BufReader<R>,
+}
+
+impl<R: Read> JpegFrames<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader: BufReader::new(reader) }
+    }
+
+    /// The next complete picture, None at the end of the stream
+    pub fn next_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut frame = Vec::new();
+        let mut previous = 0u8;
+        let mut in_frame = false;
+
+        loop {
+            let buffer = self.reader.fill_buf()?;
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+            let mut used = 0;
+            let mut complete = false;
+            for &b in buffer {
+                used += 1;
+                if !in_frame {
+                    if previous == 0xff && b == 0xd8 {
+                        in_frame = true;
+                        frame.extend_from_slice(&[0xff, 0xd8]);
+                    }
+                } else {
+                    frame.push(b);
+                    if previous == 0xff && b == 0xd9 {
+                        complete = true;
+                        break;
+                    }
+                }
+                previous = b;
+            }
+            self.reader.consume(used);
+
+            if complete {
+                return Ok(Some(frame));
+            }
+            if frame.len() > MAX_FRAME_BYTES {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "stream is not sending JPEG pictures"));
+            }
+        }
+    }
+}
+
+/// Read pictures from `source` until `stop` is set, handing each to
+/// `on_frame`
+///
+/// `method` is the connection to the Pi and is only used when the source
+/// goes through ssh.
+pub fn run_stream(
+    source: &StreamSource,
+    method: Option<&dyn TransferMethod>,
+    stop: &AtomicBool,
+    on_frame: &mut dyn FnMut(Vec<u8>)
+) -> Result<(), TransferError> {
+    if source.through_ssh && method.is_none() {
+        return Err(TransferError::ConnectionFailed("Not connected to a Pi".to_string()));
+    }
+
+    match source.mode {
+        StreamMode::Mjpeg => {
+            let mut stream = open(source, method)?;
+            let result = read_frames(stream.reader(), stop, on_frame);
+            stream.close();
+            result
+        },
+        StreamMode::Snapshot => {
+            while !stop.load(Ordering::Relaxed) {
+                let started = Instant::now();
+                let mut stream = open(source, method)?;
+                let frame = JpegFrames::new(stream.reader()).next_frame();
+                stream.close();
+                match frame {
+                    Ok(Some(frame)) => on_frame(frame),
+                    Ok(None) => return Err(TransferError::TransferFailed(format!("{} returned no picture", source.url))),
+                    Err(e) => return Err(TransferError::TransferFailed(e.to_string())),
+                }
+                wait_unless_stopped(source.snapshot_interval.saturating_sub(started.elapsed()), stop);
+            }
+            Ok(())
+        },
+    }
+}
+
+fn read_frames(reader: &mut dyn Read, stop: &AtomicBool, on_frame: &mut dyn FnMut(Vec<u8>)) -> Result<(), TransferError> {
+    let mut frames = JpegFrames::new(reader);
+    while !stop.load(Ordering::Relaxed) {
+        match frames.next_frame() {
+            Ok(Some(frame)) => on_frame(frame),
+            Ok(None) => return Err(TransferError::ConnectionFailed("The stream ended".to_string())),
+            Err(e) if stop.load(Ordering::Relaxed) => {
+                log::debug!("Stream closed while stopping: {}", e);
+                return Ok(());
+            },
+            Err(e) => return Err(TransferError::TransferFailed(e.to_string())),
+        }
+    }
+    Ok(())
+}
+
+fn wait_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let until = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) && Instant::now() < until {
+        thread::sleep(Duration::from_millis(100).min(until.saturating_duration_since(Instant::now())));
+    }
+}
+
+/// An open connection to the camera, direct or through ssh
+enum OpenStream {
+    Direct(BufReader<TcpStream>),
+    Ssh(Child),
+}
+
+impl OpenStream {
+    fn reader(&mut self) -> &mut dyn Read {
+        match self {
+            Self::Direct(reader) => reader,
+            Self::Ssh(child) => child.stdout.as_mut().expect("stdout is piped"),
+        }
+    }
+
+    fn close(self) {
+        if let Self::Ssh(mut child) = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+fn open(source: &StreamSource, method: Option<&dyn TransferMethod>) -> Result<OpenStream, TransferError> {
+    match method.filter(|_| source.through_ssh) {
+        Some(method) => {
+            // -N streams without buffering; -f fails on HTTP errors
+            let command = format!("curl -sfN {}", shell_quote(&source.url));
+            method.spawn_remote(&command).map(OpenStream::Ssh)
+        },
+        None => http_get(&source.url).map(OpenStream::Direct),
+    }
+}
+
+/// Send a GET for `url` and return the response body; plain HTTP only,
+/// which is all the Pi's camera servers speak
+fn http_get(url: &str) -> Result<BufReader<TcpStream>, TransferError> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        TransferError::ConnectionFailed(format!("Only http:// URLs can be opened directly: {}", url))
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+
+    let socket_address = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| TransferError::ConnectionFailed(format!("Cannot resolve {}", authority)))?;
+    let mut stream = TcpStream::connect_timeout(&socket_address, STREAM_TIMEOUT)
+        .map_err(|e| TransferError::ConnectionFailed(format!("{}: {}", authority, e)))?;
+    let _ = stream.set_read_timeout(Some(STREAM_TIMEOUT));
+
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\nUser-Agent: pi_remote_manager\r\n\r\n", path, authority)
+        .map_err(|e| TransferError::ConnectionFailed(e.to_string()))?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status = String::new();
+    reader.read_line(&mut status).map_err(|e| TransferError::ConnectionFailed(e.to_string()))?;
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(TransferError::ConnectionFailed(format!("{} answered {}", url, status.trim())));
+    }
+
+    // Skip the headers; the body follows the first blank line
+    loop {
+        let mut header = String::new();
+        let read = reader.read_line(&mut header).map_err(|e| TransferError::ConnectionFailed(e.to_string()))?;
+        if read == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+    Ok(reader)
+}
@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::transfer::method::{TransferMethod, TransferError};
+
+/// Longest the listing of either side may take before copying starts anyway
+const STAMP_LISTING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Size and modification time of a file, compared before copying it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    /// Seconds since the Unix epoch, when known
+    pub modified: Option<i64>,
+}
+
+impl FileStamp {
+    /// Whether a target with this stamp already holds a copy of `source`:
+    /// the sizes match and the target is not older
+    pub fn holds_copy_of(&self, source: &FileStamp) -> bool {
+        self.size == source.size
+            && match (self.modified, source.modified) {
+                (Some(target), Some(source)) => target >= source,
+                _ => true,
+            }
+    }
+}
+
+/// Stamps of the files in a local directory, by name
+pub fn local_stamps(dir: &Path) -> HashMap<String, FileStamp> {
+    let Ok(entries) = fs::read_dir(dir) else { return HashMap::new() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|since| since.as_secs() as i64);
+            Some((entry.file_name().to_string_lossy().to_string(), FileStamp { size: metadata.len(), modified }))
+        })
+        .collect()
+}
+
+/// Stamps of the files in a directory on the Pi, by name; a directory that
+/// does not exist yet has none
+pub fn remote_stamps(method: &dyn TransferMethod, dir: &Path) -> Result<HashMap<String, FileStamp>, TransferError> {
+    let mut stamps = HashMap::new();
+    let listed = method.list_files_streaming(dir, STAMP_LISTING_TIMEOUT, &AtomicBool::new(false), &mut |chunk| {
+        for entry in chunk.into_iter().filter(|entry| !entry.is_dir) {
+            stamps.insert(entry.display_name(), FileStamp { size: entry.size, modified: entry.modified });
+        }
+    });
+    match listed {
+        Ok(()) | Err(TransferError::FileNotFound(_)) => Ok(stamps),
+        Err(e) => Err(e),
+    }
+}
+
+/// Split `sources` into those to copy and those `target_stamps` shows are
+/// already in the target directory
+pub fn split_already_copied(
+    sources: Vec<PathBuf>,
+    source_stamps: &HashMap<String, FileStamp>,
+    target_stamps: &HashMap<String, FileStamp>
+) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    sources.into_iter().partition(|source| {
+        let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        match (source_stamps.get(&name), target_stamps.get(&name)) {
+            (Some(source), Some(target)) => !target.holds_copy_of(source),
+            _ => true,
+        }
+    })
+}
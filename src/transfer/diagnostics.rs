@@ -0,0 +1,230 @@
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::transfer::method::{wait_with_deadline, TransferError, TransferMethod, SSH_FAILURE_EXIT};
+
+/// How long each network stage of a connection test may take
+const STAGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Line the shell stage echoes to prove commands run on the Pi
+const SHELL_MARKER: &str = "pi_remote_manager: shell ok";
+
+/// Exit status of sshpass when the password is rejected
+const SSHPASS_AUTH_EXIT: i32 = 5;
+
+/// Stages of a connection test, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticStage {
+    Dns,
+    Tcp,
+    Auth,
+    Shell,
+}
+
+impl DiagnosticStage {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Dns => "DNS lookup",
+            Self::Tcp => "TCP connect",
+            Self::Auth => "Login",
+            Self::Shell => "Remote shell",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StageStatus {
+    Passed,
+    Failed,
+    /// Not run because an earlier stage failed
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct StageResult {
+    pub stage: DiagnosticStage,
+    pub status: StageStatus,
+    pub detail: String,
+}
+
+/// Result of [`TransferMethod::test_connection`], one entry per stage
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionDiagnostics {
+    pub stages: Vec<StageResult>,
+    /// Output of the probe command run by the shell stage
+    pub probe_output: String,
+}
+
+impl ConnectionDiagnostics {
+    /// Every stage passed
+    pub fn passed(&self) -> bool {
+        !self.stages.is_empty() && self.stages.iter().all(|stage| stage.status == StageStatus::Passed)
+    }
+
+    pub fn first_failure(&self) -> Option<&StageResult> {
+        self.stages.iter().find(|stage| stage.status == StageStatus::Failed)
+    }
+
+    /// One line per stage, for display
+    pub fn report(&self) -> String {
+        self.stages
+            .iter()
+            .map(|result| {
+                let mark = match result.status {
+                    StageStatus::Passed => "ok  ",
+                    StageStatus::Failed => "FAIL",
+                    StageStatus::Skipped => "--  ",
+                };
+                format!("[{}] {}: {}", mark, result.stage.label(), result.detail)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub(crate) fn record(&mut self, stage: DiagnosticStage, status: StageStatus, detail: impl Into<String>) {
+        self.stages.push(StageResult { stage, status, detail: detail.into() });
+    }
+
+    fn skip_rest(&mut self, stages: &[DiagnosticStage]) {
+        for stage in stages {
+            self.record(*stage, StageStatus::Skipped, "not run");
+        }
+    }
+}
+
+/// Run the stages of a connection test against `method`
+///
+/// DNS and TCP are checked against the method's endpoint when it has one;
+/// login and shell go through `spawn_remote`, so they exercise exactly the
+/// command line transfers use. `probe` runs after the shell check and its
+/// output is kept in `probe_output`.
+pub fn diagnose<M: TransferMethod + ?Sized>(method: &M, probe: Option<&str>) -> ConnectionDiagnostics {
+    use DiagnosticStage::*;
+
+    let mut diagnostics = ConnectionDiagnostics::default();
+    if !check_network(method, &mut diagnostics) {
+        return diagnostics;
+    }
+
+    let command = match probe {
+        Some(probe) => format!("echo '{}'; {}", SHELL_MARKER, probe),
+        None => format!("echo '{}'", SHELL_MARKER),
+    };
+    let output = method
+        .spawn_remote(&command)
+        .and_then(|child| wait_with_deadline(child, STAGE_TIMEOUT, &AtomicBool::new(false)));
+
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            let detail = match e {
+                TransferError::TimedOut(_) => "No answer; the Pi may be waiting for a password or key".to_string(),
+                e => e.to_string(),
+            };
+            diagnostics.record(Auth, StageStatus::Failed, detail);
+            diagnostics.skip_rest(&[Shell]);
+            return diagnostics;
+        }
+    };
+
+    let stderr = last_line(&output.stderr);
+    match output.exit_code {
+        Some(SSH_FAILURE_EXIT) => {
+            let detail = if output.stderr.contains("Permission denied") {
+                "The Pi rejected the credentials".to_string()
+            } else {
+                stderr
+            };
+            diagnostics.record(Auth, StageStatus::Failed, detail);
+            diagnostics.skip_rest(&[Shell]);
+            return diagnostics;
+        },
+        Some(SSHPASS_AUTH_EXIT) if !output.stdout.contains(SHELL_MARKER) => {
+            diagnostics.record(Auth, StageStatus::Failed, "The password was rejected");
+            diagnostics.skip_rest(&[Shell]);
+            return diagnostics;
+        },
+        _ => diagnostics.record(Auth, StageStatus::Passed, "Logged in"),
+    }
+
+    match output.stdout.split_once(SHELL_MARKER) {
+        Some((_, rest)) => {
+            diagnostics.record(Shell, StageStatus::Passed, "Commands run on the Pi");
+            diagnostics.probe_output = rest.trim_start().to_string();
+        },
+        None => {
+            let detail = match output.exit_code {
+                Some(code) => format!("Shell exited with {}: {}", code, stderr),
+                None => format!("Shell was killed: {}", stderr),
+            };
+            diagnostics.record(Shell, StageStatus::Failed, detail);
+        },
+    }
+
+    diagnostics
+}
+
+/// Run the DNS and TCP stages against the method's endpoint, when it has
+/// one; false, with the later stages skipped, when either failed
+pub(crate) fn check_network<M: TransferMethod + ?Sized>(method: &M, diagnostics: &mut ConnectionDiagnostics) -> bool {
+    use DiagnosticStage::*;
+
+    if let Some((hostname, port)) = method.endpoint() {
+        let addresses = match (hostname.as_str(), port).to_socket_addrs() {
+            Ok(addresses) => addresses.collect::<Vec<_>>(),
+            Err(e) => {
+                diagnostics.record(Dns, StageStatus::Failed, format!("{} could not be resolved: {}", hostname, e));
+                diagnostics.skip_rest(&[Tcp, Auth, Shell]);
+                return false;
+            }
+        };
+        let shown = addresses.iter().map(|a| a.ip().to_string()).collect::<Vec<_>>().join(", ");
+        diagnostics.record(Dns, StageStatus::Passed, format!("{} is {}", hostname, shown));
+
+        match connect_any(&addresses) {
+            Ok((address, banner)) => {
+                let detail = match banner {
+                    Some(banner) => format!("{} answered: {}", address, banner),
+                    None => format!("{} accepted the connection", address),
+                };
+                diagnostics.record(Tcp, StageStatus::Passed, detail);
+            },
+            Err(e) => {
+                diagnostics.record(Tcp, StageStatus::Failed, format!("Port {}: {}", port, e));
+                diagnostics.skip_rest(&[Auth, Shell]);
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Connect to the first address that answers, reading the server's
+/// greeting line when it sends one promptly
+fn connect_any(addresses: &[SocketAddr]) -> Result<(SocketAddr, Option<String>), std::io::Error> {
+    let mut last_error = std::io::Error::new(std::io::ErrorKind::NotFound, "no addresses");
+
+    for address in addresses {
+        match TcpStream::connect_timeout(address, STAGE_TIMEOUT) {
+            Ok(mut stream) => {
+                let _ = stream.set_read_timeout(Some(Duration::from_secs(3)));
+                let mut buffer = [0u8; 256];
+                let banner = stream
+                    .read(&mut buffer)
+                    .ok()
+                    .map(|read| String::from_utf8_lossy(&buffer[..read]).lines().next().unwrap_or("").trim().to_string())
+                    .filter(|banner| !banner.is_empty());
+                return Ok((*address, banner));
+            },
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+fn last_line(text: &str) -> String {
+    text.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or("").trim().to_string()
+}
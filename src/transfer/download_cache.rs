@@ -0,0 +1,142 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use directories::ProjectDirs;
+use sha2::{Digest, Sha256};
+
+use crate::transfer::method::{TransferError, TransferMethod};
+
+/// Size the cache is kept under until `set_limit` is called
+const DEFAULT_LIMIT: u64 = 500 * 1024 * 1024;
+
+static LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_LIMIT);
+
+/// Numbers the partial downloads, so two fetches of one file don't collide
+static PARTIAL: AtomicU64 = AtomicU64::new(0);
+
+/// Folder holding the downloaded copies, in the user's cache directory
+pub fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "PiImageProcessor", "piimgproc").map(|dirs| dirs.cache_dir().join("downloads"))
+}
+
+/// Keep the cache under `bytes`, dropping the least recently used copies
+pub fn set_limit(bytes: u64) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Local copy of `remote_path` on the Pi behind `method`, downloaded only
+/// when no copy of the same version is cached
+///
+/// Copies are keyed by connection, path and `modified`, the file's
+/// modification time on the Pi. Without one a copy can't be told apart
+/// from a stale one, so the file is downloaded again. The copy keeps the
+/// file's name. Blocks while downloading; call off the main thread.
+pub fn fetch(method: &dyn TransferMethod, remote_path: &Path, modified: Option<i64>) -> Result<PathBuf, TransferError> {
+    let dir = cache_dir()
+        .ok_or_else(|| TransferError::TransferFailed("No cache directory for downloads".to_string()))?
+        .join(key(&method.connection_key(), remote_path, modified));
+    let name = remote_path
+        .file_name()
+        .ok_or_else(|| TransferError::FileNotFound(remote_path.display().to_string()))?;
+    let local = dir.join(name);
+
+    if modified.is_some() && local.is_file() {
+        // The modification time records the last use for eviction
+        if let Err(e) = File::options().write(true).open(&local).and_then(|file| file.set_modified(SystemTime::now())) {
+            log::debug!("Could not mark {} as used: {}", local.display(), e);
+        }
+        log::debug!("Reusing cached download of {}", remote_path.display());
+        return Ok(local);
+    }
+
+    let failed = |e: std::io::Error| TransferError::TransferFailed(format!("{}: {}", dir.display(), e));
+    fs::create_dir_all(&dir).map_err(failed)?;
+    // Downloaded under another name first, so a broken download is never
+    // taken for a cached copy
+    let partial = dir.join(format!(".partial-{}-{}", std::process::id(), PARTIAL.fetch_add(1, Ordering::Relaxed)));
+    if let Err(e) = method.download_file(remote_path, &partial) {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    fs::rename(&partial, &local).map_err(failed)?;
+
+    evict(Some(&dir));
+    Ok(local)
+}
+
+/// Number of cached copies and the bytes they take
+pub fn usage() -> (usize, u64) {
+    let Some(root) = cache_dir() else { return (0, 0) };
+    let copies = entries(&root);
+    (copies.len(), copies.iter().map(|(_, size, _)| size).sum())
+}
+
+/// Drop the least recently used copies beyond the limit
+pub fn enforce() {
+    evict(None);
+}
+
+/// Delete every cached copy; returns the bytes freed
+pub fn clear() -> u64 {
+    let Some(root) = cache_dir() else { return 0 };
+    let freed = entries(&root).iter().map(|(_, size, _)| size).sum();
+    let _ = fs::remove_dir_all(&root);
+    freed
+}
+
+/// Folder name of a copy, from the connection, path and modification time
+fn key(connection: &str, path: &Path, modified: Option<i64>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(connection.as_bytes());
+    hasher.update([0]);
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update([0]);
+    match modified {
+        Some(modified) => hasher.update(modified.to_le_bytes()),
+        None => hasher.update(b"unknown"),
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+/// Each copy's folder with its size and last use
+fn entries(root: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let Ok(folders) = fs::read_dir(root) else { return Vec::new() };
+    folders
+        .flatten()
+        .filter_map(|folder| {
+            let files: Vec<fs::Metadata> = fs::read_dir(folder.path())
+                .ok()?
+                .flatten()
+                .filter_map(|file| file.metadata().ok())
+                .collect();
+            let size = files.iter().map(|file| file.len()).sum();
+            let used = files.iter().filter_map(|file| file.modified().ok()).max()?;
+            Some((folder.path(), size, used))
+        })
+        .collect()
+}
+
+/// Delete the least recently used copies until the cache fits the limit,
+/// sparing the folder `keep`
+fn evict(keep: Option<&Path>) {
+    let Some(root) = cache_dir() else { return };
+    let limit = LIMIT.load(Ordering::Relaxed);
+    let mut copies = entries(&root);
+    let mut total: u64 = copies.iter().map(|(_, size, _)| size).sum();
+    copies.sort_by_key(|(_, _, used)| *used);
+
+    for (folder, size, _) in copies {
+        if total <= limit {
+            break;
+        }
+        if Some(folder.as_path()) == keep {
+            continue;
+        }
+        match fs::remove_dir_all(&folder) {
+            Ok(()) => total -= size,
+            Err(e) => log::warn!("Could not evict {}: {}", folder.display(), e),
+        }
+    }
+}
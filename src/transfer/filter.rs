@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use crate::config::TransferTemplate;
+use crate::core::utils::{glob_matches, path_glob_matches};
+
+/// Glob rules choosing which files a transfer takes part in
+///
+/// Patterns with a `/` inside, or `**`, match the whole relative path from
+/// the top, others any one name in it; a trailing `/` (e.g. "lost+found/")
+/// only matches folders. `*` and `?` stay within one name and `**` crosses
+/// folders, as in rsync. Folders are always entered unless excluded, so
+/// include rules like "*.jpg" still find images in subfolders.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PathFilter {
+    /// Files must match one of these; empty takes every file
+    pub include: Vec<String>,
+    /// Files and folders matching any of these are skipped
+    pub exclude: Vec<String>,
+}
+
+impl PathFilter {
+    pub fn from_template(template: &TransferTemplate) -> Self {
+        Self {
+            include: template.include.clone(),
+            exclude: template.exclude.clone(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        patterns(&self.include).next().is_none() && patterns(&self.exclude).next().is_none()
+    }
+
+    /// Whether `path`, relative to the folder being transferred, takes part
+    pub fn allows(&self, path: &Path, is_dir: bool) -> bool {
+        if patterns(&self.exclude).any(|pattern| matches(pattern, path, is_dir)) {
+            return false;
+        }
+        is_dir
+            || patterns(&self.include).next().is_none()
+            || patterns(&self.include).any(|pattern| matches(pattern, path, is_dir))
+    }
+
+    /// The same rules as rsync arguments; rsync applies the first rule that
+    /// matches, so exclusions go first and everything else is excluded last
+    /// when there are include rules
+    pub fn rsync_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = patterns(&self.exclude)
+            .map(|pattern| format!("--exclude={}", rsync_pattern(pattern)))
+            .collect();
+        if patterns(&self.include).next().is_some() {
+            args.push("--include=*/".to_string());
+            args.extend(patterns(&self.include).map(|pattern| format!("--include={}", rsync_pattern(pattern))));
+            args.push("--exclude=*".to_string());
+            // Folders only entered for the rules are not left behind empty
+            args.push("--prune-empty-dirs".to_string());
+        }
+        args
+    }
+}
+
+/// Split a comma-separated list of patterns as typed in a dialog
+pub fn parse_patterns(text: &str) -> Vec<String> {
+    text.split(',')
+        .map(|pattern| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+fn patterns(list: &[String]) -> impl Iterator<Item = &str> {
+    list.iter().map(|pattern| pattern.trim()).filter(|pattern| !pattern.is_empty())
+}
+
+/// Whether a pattern, less any trailing `/`, is matched against the whole path
+fn is_anchored(pattern: &str) -> bool {
+    let pattern = pattern.strip_suffix('/').unwrap_or(pattern);
+    pattern.contains('/') || pattern.contains("**")
+}
+
+/// rsync matches path patterns against the end of a path unless they start
+/// with `/`, so those get one to match from the top as `allows` does
+fn rsync_pattern(pattern: &str) -> String {
+    if is_anchored(pattern) && !pattern.starts_with('/') {
+        format!("/{}", pattern)
+    } else {
+        pattern.to_string()
+    }
+}
+
+fn matches(pattern: &str, path: &Path, is_dir: bool) -> bool {
+    let anchored = is_anchored(pattern);
+    let pattern = match pattern.strip_suffix('/') {
+        Some(_) if !is_dir => return false,
+        Some(folder) => folder,
+        None => pattern,
+    };
+    if anchored {
+        let full = path.to_string_lossy().replace('\\', "/");
+        path_glob_matches(pattern.trim_start_matches('/'), &full)
+    } else {
+        path.file_name().is_some_and(|name| glob_matches(pattern, &name.to_string_lossy()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        PathFilter {
+            include: include.iter().map(|p| p.to_string()).collect(),
+            exclude: exclude.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn names_match_at_any_depth() {
+        let filter = filter(&["*.jpg"], &[]);
+        assert!(filter.allows(Path::new("a.jpg"), false));
+        assert!(filter.allows(Path::new("raw/a.jpg"), false));
+        assert!(!filter.allows(Path::new("raw/a.png"), false));
+        assert_eq!(filter.rsync_args(), ["--include=*/", "--include=*.jpg", "--exclude=*", "--prune-empty-dirs"]);
+    }
+
+    #[test]
+    fn path_patterns_are_anchored_for_both() {
+        let filter = filter(&["photos/*.jpg"], &[]);
+        assert!(filter.allows(Path::new("photos/a.jpg"), false));
+        // rsync would also match these without the leading `/`
+        assert!(!filter.allows(Path::new("old/photos/a.jpg"), false));
+        assert!(!filter.allows(Path::new("photos/raw/a.jpg"), false));
+        assert_eq!(filter.rsync_args()[1], "--include=/photos/*.jpg");
+    }
+
+    #[test]
+    fn double_stars_cross_folders_for_both() {
+        let filter = filter(&[], &["cache/**"]);
+        assert!(!filter.allows(Path::new("cache/a/b.jpg"), false));
+        assert!(filter.allows(Path::new("photos/cache/b.jpg"), false));
+        assert_eq!(filter.rsync_args(), ["--exclude=/cache/**"]);
+    }
+
+    #[test]
+    fn trailing_slashes_only_match_folders() {
+        let filter = filter(&[], &["lost+found/", "tmp/cache/"]);
+        assert!(!filter.allows(Path::new("lost+found"), true));
+        assert!(filter.allows(Path::new("lost+found"), false));
+        assert!(!filter.allows(Path::new("tmp/cache"), true));
+        assert_eq!(filter.rsync_args(), ["--exclude=lost+found/", "--exclude=/tmp/cache/"]);
+    }
+
+    #[test]
+    fn anchored_patterns_keep_their_slash() {
+        let filter = filter(&[], &["/build"]);
+        assert!(!filter.allows(Path::new("build"), true));
+        assert_eq!(filter.rsync_args(), ["--exclude=/build"]);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::transfer::method::{TransferMethod, TransferError};
+use crate::transfer::ssh::{shell_quote, SSHTransfer};
+
+/// Comment stored with keys this app generates
+const KEY_COMMENT: &str = "pi_remote_manager";
+
+/// The user's ed25519 key, `~/.ssh/id_ed25519`
+pub fn default_key_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".ssh").join("id_ed25519"))
+}
+
+/// Create an ed25519 keypair without a passphrase at `key_path` unless a
+/// private key is already there; true when a new key was generated
+pub fn ensure_keypair(key_path: &Path) -> Result<bool, TransferError> {
+    if key_path.exists() {
+        return Ok(false);
+    }
+
+    if let Some(dir) = key_path.parent() {
+        fs::create_dir_all(dir).map_err(|e| {
+            TransferError::TransferFailed(format!("Could not create {}: {}", dir.display(), e))
+        })?;
+    }
+
+    log::info!("Generating SSH key {}", key_path.display());
+    let output = Command::new("ssh-keygen")
+        .arg("-t").arg("ed25519")
+        .arg("-N").arg("")
+        .arg("-C").arg(KEY_COMMENT)
+        .arg("-f").arg(key_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| TransferError::TransferFailed(format!("Failed to run ssh-keygen: {}", e)))?;
+
+    if !output.status.success() {
+        return Err(TransferError::TransferFailed(format!(
+            "ssh-keygen failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(true)
+}
+
+/// Append the public half of `key_path` to `~/.ssh/authorized_keys` on the
+/// Pi, logging in with the password; a key already listed is not added twice
+pub fn install_public_key(
+    hostname: &str,
+    username: &str,
+    port: u16,
+    password: &str,
+    key_path: &Path
+) -> Result<(), TransferError> {
+    let public_path = PathBuf::from(format!("{}.pub", key_path.display()));
+    let public_key = fs::read_to_string(&public_path).map_err(|e| {
+        TransferError::FileNotFound(format!("{}: {}", public_path.display(), e))
+    })?;
+    let public_key = shell_quote(public_key.trim());
+
+    let command = format!(
+        "umask 077 && mkdir -p ~/.ssh && touch ~/.ssh/authorized_keys && \
+         (grep -qxF {key} ~/.ssh/authorized_keys || echo {key} >> ~/.ssh/authorized_keys)",
+        key = public_key
+    );
+
+    let method = SSHTransfer::with_password(
+        hostname.to_string(),
+        username.to_string(),
+        port,
+        password.to_string()
+    );
+    let output = method.exec(&command)?;
+
+    match output.exit_code {
+        Some(0) => Ok(()),
+        Some(5) => Err(TransferError::AuthenticationFailed(output.stderr.trim().to_string())),
+        Some(255) => Err(TransferError::ConnectionFailed(output.stderr.trim().to_string())),
+        _ => Err(TransferError::TransferFailed(output.stderr.trim().to_string())),
+    }
+}
+
+/// Log in with the key alone, never falling back to a password prompt
+pub fn verify_key_login(hostname: &str, username: &str, port: u16, key_path: &Path) -> Result<(), TransferError> {
+    let output = Command::new("ssh")
+        .arg("-o").arg("BatchMode=yes")
+        .arg("-o").arg("ConnectTimeout=10")
+        .arg("-i").arg(key_path)
+        .arg("-p").arg(port.to_string())
+        .arg(format!("{}@{}", username, hostname))
+        .arg("true")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| TransferError::TransferFailed(format!("Failed to run ssh: {}", e)))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(TransferError::AuthenticationFailed(format!(
+            "The Pi did not accept the key: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
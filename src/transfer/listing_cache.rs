@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::transfer::method::RemoteEntry;
+
+/// Directory entries as streamed by `TransferMethod::list_files_streaming`
+pub type Listing = Vec<RemoteEntry>;
+
+struct CachedListing {
+    listed_at: Instant,
+    entries: Listing,
+}
+
+/// Remote listings keyed by connection and directory
+///
+/// The connection key is `TransferMethod::connection_key()`, which names
+/// the user, host and port, so two devices never share entries.
+static CACHE: Mutex<Option<HashMap<(String, PathBuf), CachedListing>>> = Mutex::new(None);
+
+/// Entries for `dir` if they were listed less than `ttl` ago
+pub fn get(connection: &str, dir: &Path, ttl: Duration) -> Option<Listing> {
+    let cache = CACHE.lock().ok()?;
+    let cached = cache.as_ref()?.get(&(connection.to_string(), dir.to_path_buf()))?;
+
+    if cached.listed_at.elapsed() < ttl {
+        Some(cached.entries.clone())
+    } else {
+        None
+    }
+}
+
+/// Remember a fresh listing of `dir`
+pub fn store(connection: &str, dir: &Path, entries: &Listing) {
+    if let Ok(mut cache) = CACHE.lock() {
+        cache.get_or_insert_with(HashMap::new).insert(
+            (connection.to_string(), dir.to_path_buf()),
+            CachedListing {
+                listed_at: Instant::now(),
+                entries: entries.clone(),
+            },
+        );
+    }
+}
+
+/// Forget the listing of `dir` after something in it changed
+pub fn invalidate(connection: &str, dir: &Path) {
+    if let Ok(mut cache) = CACHE.lock() {
+        if let Some(cache) = cache.as_mut() {
+            cache.remove(&(connection.to_string(), dir.to_path_buf()));
+        }
+    }
+}
+
+/// Forget the listing of the directory containing `path`
+pub fn invalidate_parent(connection: &str, path: &Path) {
+    if let Some(parent) = path.parent() {
+        invalidate(connection, parent);
+    }
+}
+
+/// Forget every listing of one connection, e.g. after running a command
+/// that may have changed anything
+pub fn invalidate_connection(connection: &str) {
+    if let Ok(mut cache) = CACHE.lock() {
+        if let Some(cache) = cache.as_mut() {
+            cache.retain(|(key, _), _| key != connection);
+        }
+    }
+}
@@ -1,30 +1,331 @@
+use std::ffi::OsString;
 use std::path::Path;
-use std::error::Error;
-use std::fmt;
 use std::any::Any;
+use std::io::{BufRead, BufReader, Read};
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 
-#[derive(Debug)]
+use thiserror::Error;
+
+use crate::config::RetryPolicy;
+use crate::transfer::diagnostics::{diagnose, ConnectionDiagnostics};
+
+/// Exit status ssh reports for its own failures, as opposed to the command's
+pub const SSH_FAILURE_EXIT: i32 = 255;
+
+#[derive(Debug, Error)]
 pub enum TransferError {
+    #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+    #[error("Authentication failed: {0}")]
     AuthenticationFailed(String),
+    #[error("Permission denied: {0}")]
     PermissionDenied(String),
+    #[error("File not found: {0}")]
     FileNotFound(String),
+    #[error("Transfer failed: {0}")]
     TransferFailed(String),
+    #[error("Timed out: {0}")]
+    TimedOut(String),
+    #[error("Cancelled")]
+    Cancelled,
 }
 
-impl fmt::Display for TransferError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl TransferError {
+    /// Whether trying again may succeed: timeouts and dropped or refused
+    /// connections are retryable, while authentication, permission and
+    /// missing-file errors fail the same way every time
+    pub fn is_retryable(&self) -> bool {
         match self {
-            Self::ConnectionFailed(msg) => write!(f, "Connection failed: {}", msg),
-            Self::AuthenticationFailed(msg) => write!(f, "Authentication failed: {}", msg),
-            Self::PermissionDenied(msg) => write!(f, "Permission denied: {}", msg),
-            Self::FileNotFound(msg) => write!(f, "File not found: {}", msg),
-            Self::TransferFailed(msg) => write!(f, "Transfer failed: {}", msg),
+            Self::TimedOut(_) | Self::ConnectionFailed(_) => true,
+            // scp and rsync report a dropped link only in their output
+            Self::TransferFailed(message) => {
+                let message = message.to_lowercase();
+                RETRYABLE_MESSAGES.iter().any(|pattern| message.contains(pattern))
+            },
+            Self::AuthenticationFailed(_) | Self::PermissionDenied(_) | Self::FileNotFound(_) | Self::Cancelled => false,
         }
     }
 }
 
-impl Error for TransferError {}
+/// Output of ssh, scp and rsync when the link rather than the request failed
+const RETRYABLE_MESSAGES: &[&str] = &[
+    "connection reset",
+    "connection closed",
+    "connection refused",
+    "broken pipe",
+    "lost connection",
+    "network is unreachable",
+    "no route to host",
+    "timed out",
+];
+
+/// Messages of ssh rejecting the login, which also say "Permission denied"
+const LOGIN_REJECTED_MESSAGES: &[&str] = &[
+    "(publickey",
+    "(password",
+    "(keyboard-interactive",
+    "please try again",
+];
+
+/// Error for a remote command, scp or rsync that failed with `stderr`:
+/// PermissionDenied when the Pi refused access to a file, AuthenticationFailed
+/// when sudo was not given a valid password, else TransferFailed
+pub fn failure_from_stderr(stderr: &str) -> TransferError {
+    let message = stderr.trim().to_string();
+    let lower = message.to_lowercase();
+    if lower.contains("incorrect password attempt") || lower.contains("a password is required") {
+        return TransferError::AuthenticationFailed(message);
+    }
+    let refused = lower.lines().any(|line| {
+        line.contains("permission denied") && !LOGIN_REJECTED_MESSAGES.iter().any(|login| line.contains(login))
+    });
+    if refused {
+        TransferError::PermissionDenied(message)
+    } else {
+        TransferError::TransferFailed(message)
+    }
+}
+
+/// Run `attempt` until it succeeds, fails with an error that is not
+/// retryable or has been retried as often as `policy` allows, waiting the
+/// policy's backoff in between; `attempt` is given the try number from 1
+pub fn with_retries<T>(
+    policy: &RetryPolicy,
+    what: &str,
+    cancel: &AtomicBool,
+    mut attempt: impl FnMut(u32) -> Result<T, TransferError>
+) -> Result<T, TransferError> {
+    let mut attempt_no = 1;
+    loop {
+        match attempt(attempt_no) {
+            Err(e) if e.is_retryable() && attempt_no <= policy.retries => {
+                let delay = policy.backoff(attempt_no);
+                log::warn!("{} failed ({}), retrying in {:.1}s", what, e, delay.as_secs_f64());
+                sleep_unless_cancelled(delay, cancel)?;
+                attempt_no += 1;
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Wait `delay`, returning `Cancelled` early once `cancel` is set
+pub fn sleep_unless_cancelled(delay: Duration, cancel: &AtomicBool) -> Result<(), TransferError> {
+    let started = Instant::now();
+    while started.elapsed() < delay {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(TransferError::Cancelled);
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Ok(())
+}
+
+// One entry of a remote directory listing with its size, time and link target
+#[derive(Debug, Clone)]
+pub struct RemoteEntry {
+    // As the Pi reported it, which need not be UTF-8
+    pub name: OsString,
+    // For links, whether the target is a directory
+    pub is_dir: bool,
+    pub size: u64,
+    // Seconds since the Unix epoch, when known
+    pub modified: Option<i64>,
+    // Where the entry points when it is a symbolic link
+    pub link_target: Option<String>,
+}
+
+impl RemoteEntry {
+    // Entry known only by name and kind, as from list_files
+    pub fn bare(name: impl Into<OsString>, is_dir: bool) -> Self {
+        Self { name: name.into(), is_dir, size: 0, modified: None, link_target: None }
+    }
+    
+    pub fn is_symlink(&self) -> bool {
+        self.link_target.is_some()
+    }
+    
+    // Name for showing and comparing, with bytes that aren't UTF-8 replaced
+    pub fn display_name(&self) -> String {
+        self.name.to_string_lossy().into_owned()
+    }
+}
+
+/// Name or path from the raw bytes the Pi reported; bytes that aren't UTF-8
+/// are kept as they are, except on Windows, where paths can't hold them
+pub fn os_string_from_bytes(bytes: Vec<u8>) -> OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(bytes)
+    }
+    #[cfg(not(unix))]
+    {
+        OsString::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+// Captured result of a command run on the remote host
+#[derive(Debug, Clone)]
+pub struct CommandOutput {
+    // With bytes that aren't UTF-8 replaced
+    pub stdout: String,
+    // Stdout as the Pi sent it, for output that carries file names
+    pub stdout_bytes: Vec<u8>,
+    pub stderr: String,
+    pub exit_code: Option<i32>,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+}
+
+// Wait for a spawned command, killing it when `cancel` is set or `timeout`
+// passes. Output is drained on helper threads so a chatty command can't
+// block on a full pipe.
+pub fn wait_with_deadline(
+    mut child: Child,
+    timeout: Duration,
+    cancel: &AtomicBool
+) -> Result<CommandOutput, TransferError> {
+    fn drain<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    }
+    
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let started = Instant::now();
+    
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TransferError::Cancelled);
+        }
+        
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TransferError::TimedOut(format!(
+                "no answer after {} seconds",
+                timeout.as_secs()
+            )));
+        }
+        
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => {
+                return Err(TransferError::TransferFailed(format!("Failed to wait for remote command: {}", e)));
+            }
+        }
+    };
+    
+    let stdout_bytes = stdout.join().unwrap_or_default();
+    Ok(CommandOutput {
+        stdout: String::from_utf8_lossy(&stdout_bytes).into_owned(),
+        stdout_bytes,
+        stderr: String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned(),
+        exit_code: status.code(),
+    })
+}
+
+// Feed each stdout line of `child` to `on_line` as it arrives. Unlike
+// wait_with_deadline the timeout restarts with every line, so a long but
+// steady listing isn't cut off. The returned output has an empty stdout.
+pub fn stream_lines_with_deadline(
+    child: Child,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    on_line: &mut dyn FnMut(&str)
+) -> Result<CommandOutput, TransferError> {
+    stream_records_with_deadline(child, b'\n', timeout, cancel, &mut |line| {
+        on_line(String::from_utf8_lossy(line).trim_end_matches('\r'))
+    })
+}
+
+// Like stream_lines_with_deadline for output split on `separator`, such as
+// NUL-terminated records that may themselves contain newlines; records are
+// handed over as raw bytes since file names need not be UTF-8
+pub fn stream_records_with_deadline(
+    mut child: Child,
+    separator: u8,
+    timeout: Duration,
+    cancel: &AtomicBool,
+    on_record: &mut dyn FnMut(&[u8])
+) -> Result<CommandOutput, TransferError> {
+    let stderr = child.stderr.take().map(|mut pipe| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            let _ = pipe.read_to_end(&mut bytes);
+            String::from_utf8_lossy(&bytes).to_string()
+        })
+    });
+    
+    // Read on a helper thread so cancel and timeout are noticed between records
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    if let Some(stdout) = child.stdout.take() {
+        thread::spawn(move || {
+            for record in BufReader::new(stdout).split(separator) {
+                let Ok(record) = record else { break };
+                if sender.send(record).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    
+    let mut last_record = Instant::now();
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TransferError::Cancelled);
+        }
+        
+        if last_record.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(TransferError::TimedOut(format!(
+                "no answer after {} seconds",
+                timeout.as_secs()
+            )));
+        }
+        
+        match receiver.recv_timeout(Duration::from_millis(50)) {
+            Ok(record) => {
+                on_record(&record);
+                last_record = Instant::now();
+            },
+            Err(RecvTimeoutError::Timeout) => {},
+            // stdout closed; the command has finished or is about to
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    
+    let status = child.wait().map_err(|e| {
+        TransferError::TransferFailed(format!("Failed to wait for remote command: {}", e))
+    })?;
+    
+    Ok(CommandOutput {
+        stdout: String::new(),
+        stdout_bytes: Vec::new(),
+        stderr: stderr.and_then(|h| h.join().ok()).unwrap_or_default(),
+        exit_code: status.code(),
+    })
+}
 
 // TransferMethod trait - "Product" in our Factory Method pattern
 pub trait TransferMethod: Send + Sync {
@@ -40,14 +341,115 @@ pub trait TransferMethod: Send + Sync {
         local_path: &Path
     ) -> Result<(), TransferError>;
     
+    // Names are as the Pi reported them, which need not be UTF-8
     fn list_files(
         &self,
         remote_dir: &Path
-    ) -> Result<Vec<(String, bool)>, TransferError>;
+    ) -> Result<Vec<(OsString, bool)>, TransferError>;
+    
+    // List a directory, giving up with TimedOut after `timeout` or with
+    // Cancelled once `cancel` is set; methods that can't be interrupted
+    // fall back to a plain listing
+    fn list_files_with_timeout(
+        &self,
+        remote_dir: &Path,
+        _timeout: Duration,
+        _cancel: &AtomicBool
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        self.list_files(remote_dir)
+    }
+    
+    // Like list_files_with_timeout, but hands entries with their size and
+    // modification time to `on_chunk` in batches while the listing is still
+    // running so huge directories can be shown before they are fully read;
+    // the default delivers one batch without details
+    fn list_files_streaming(
+        &self,
+        remote_dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        let entries = self.list_files_with_timeout(remote_dir, timeout, cancel)?;
+        on_chunk(entries.into_iter().map(|(name, is_dir)| RemoteEntry::bare(name, is_dir)).collect());
+        Ok(())
+    }
     
     fn get_name(&self) -> &str;
     fn get_description(&self) -> String;
     
+    // Identifies the remote account (user, host and port) so caches can tell
+    // devices apart regardless of which method reaches them
+    fn connection_key(&self) -> String {
+        self.get_description()
+    }
+    
+    // Copies through this method that may run at the same time
+    fn max_concurrent(&self) -> usize {
+        1
+    }
+    
+    // How failed copies and listings through this method are retried
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default()
+    }
+    
+    // Whether listings, checksums and thumbnails may go through the
+    // companion agent when the remote host has it installed
+    fn prefers_agent(&self) -> bool {
+        false
+    }
+    
+    // Host and port the method connects to, when it goes over the network
+    fn endpoint(&self) -> Option<(String, u16)> {
+        None
+    }
+    
+    // Check DNS, TCP, login and the remote shell in turn, using the same
+    // commands transfers do; `probe` runs once the shell is reached
+    fn test_connection(&self, probe: Option<&str>) -> ConnectionDiagnostics {
+        diagnose(self, probe)
+    }
+    
+    // Start a long-running command on the remote host (e.g. `tail -F`) with
+    // stdout and stderr piped back to the caller
+    fn spawn_remote(&self, _command: &str) -> Result<Child, TransferError> {
+        Err(TransferError::TransferFailed(format!(
+            "{} does not support running remote commands",
+            self.get_name()
+        )))
+    }
+    
+    // Copy of this method that lists and downloads through sudo, offered
+    // after the Pi refused access; None unless the host allows it
+    fn sudo_method(&self) -> Option<Box<dyn TransferMethod>> {
+        None
+    }
+    
+    // Whether folders and archives copied through this method keep symbolic
+    // links as links rather than copying what they point to
+    fn preserves_symlinks(&self) -> bool {
+        false
+    }
+    
+    // Run a command on the remote host and wait for it to finish
+    fn exec(&self, command: &str) -> Result<CommandOutput, TransferError> {
+        let child = self.spawn_remote(command)?;
+        let output = child.wait_with_output().map_err(|e| {
+            TransferError::TransferFailed(format!("Failed to wait for remote command: {}", e))
+        })?;
+        
+        Ok(CommandOutput {
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stdout_bytes: output.stdout,
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+            exit_code: output.status.code(),
+        })
+    }
+    
+    // Create an independent copy for use on a worker thread
+    fn clone_box(&self) -> Box<dyn TransferMethod>;
+    
     // Add method for downcasting to concrete types
     fn as_any(&mut self) -> &mut dyn Any;
     
@@ -55,7 +457,7 @@ pub trait TransferMethod: Send + Sync {
     fn set_password(&mut self, _password: &str) {
         // Default empty implementation
         // This will be overridden in concrete implementations
-        println!("WARNING: set_password called on a transfer method that doesn't support it");
+        log::warn!("set_password called on a transfer method that doesn't support it");
     }
 }
 
@@ -0,0 +1,211 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::config::TransferTemplate;
+use crate::transfer::dedup::FileStamp;
+use crate::transfer::filter::PathFilter;
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::remote_trash::move_to_remote_trash;
+use crate::transfer::ssh::shell_quote_path;
+
+/// Longest listing one folder on the Pi may take
+const LISTING_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Files removed from the Pi per `rm` command
+const DELETE_BATCH: usize = 100;
+
+/// Makes a target folder hold the same files as a source folder, copying
+/// only what is new or changed so an interrupted run picks up where it
+/// stopped
+#[derive(Debug, Clone)]
+pub struct Mirror {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    /// True when the source is local and the target on the Pi
+    pub upload: bool,
+    /// Files and folders taken part in on both sides; the rest are left alone
+    pub filter: PathFilter,
+}
+
+/// What a mirror run would do, with paths relative to the two folders
+#[derive(Debug, Clone, Default)]
+pub struct MirrorPlan {
+    /// Files missing from the target or different there
+    pub copies: Vec<PathBuf>,
+    /// Files the target already has
+    pub unchanged: usize,
+    /// Target files the source no longer has
+    pub deletions: Vec<PathBuf>,
+}
+
+impl Mirror {
+    pub fn from_template(template: &TransferTemplate) -> Self {
+        Self {
+            source: PathBuf::from(&template.source),
+            target: PathBuf::from(&template.destination),
+            upload: template.upload,
+            filter: PathFilter::from_template(template),
+        }
+    }
+
+    /// Compare both folders; nothing is changed
+    pub fn plan(&self, method: &dyn TransferMethod) -> Result<MirrorPlan, TransferError> {
+        let (source, target) = if self.upload {
+            (self.local_stamps(&self.source)?, self.remote_stamps(method, &self.target)?)
+        } else {
+            (self.remote_stamps(method, &self.source)?, self.local_stamps(&self.target)?)
+        };
+
+        let mut plan = MirrorPlan::default();
+        for (path, stamp) in &source {
+            match target.get(path) {
+                Some(existing) if existing.holds_copy_of(stamp) => plan.unchanged += 1,
+                _ => plan.copies.push(path.clone()),
+            }
+        }
+        plan.deletions = target.keys().filter(|path| !source.contains_key(*path)).cloned().collect();
+        plan.copies.sort();
+        plan.deletions.sort();
+        Ok(plan)
+    }
+
+    /// Copy `files`, calling `progress` with the number copied so far
+    pub fn copy(
+        &self,
+        method: &dyn TransferMethod,
+        files: &[PathBuf],
+        progress: &dyn Fn(usize),
+        cancel: &AtomicBool
+    ) -> Result<usize, TransferError> {
+        let folders: BTreeSet<PathBuf> = files
+            .iter()
+            .map(|file| self.target.join(file).parent().map(Path::to_path_buf).unwrap_or_default())
+            .collect();
+        if self.upload {
+            for folder in &folders {
+                let output = method.exec(&format!("mkdir -p -- {}", shell_quote_path(folder)))?;
+                if !output.success() {
+                    return Err(TransferError::PermissionDenied(output.stderr.trim().to_string()));
+                }
+            }
+        } else {
+            for folder in &folders {
+                fs::create_dir_all(folder)
+                    .map_err(|e| TransferError::PermissionDenied(format!("{}: {}", folder.display(), e)))?;
+            }
+        }
+
+        for (index, file) in files.iter().enumerate() {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(TransferError::Cancelled);
+            }
+            let (source, target) = (self.source.join(file), self.target.join(file));
+            if self.upload {
+                method.upload_file(&source, &target)?;
+            } else {
+                method.download_file(&source, &target)?;
+            }
+            progress(index + 1);
+        }
+        Ok(files.len())
+    }
+
+    /// Remove `files` from the target; emptied folders are kept. On the Pi
+    /// they go into its trash instead when `to_trash` is set
+    pub fn delete(&self, method: &dyn TransferMethod, files: &[PathBuf], to_trash: bool) -> Result<usize, TransferError> {
+        if !self.upload {
+            for file in files {
+                let path = self.target.join(file);
+                fs::remove_file(&path)
+                    .map_err(|e| TransferError::PermissionDenied(format!("{}: {}", path.display(), e)))?;
+            }
+            return Ok(files.len());
+        }
+
+        for batch in files.chunks(DELETE_BATCH) {
+            if to_trash {
+                let paths: Vec<PathBuf> = batch.iter().map(|file| self.target.join(file)).collect();
+                move_to_remote_trash(method, &paths)?;
+                continue;
+            }
+            let paths: Vec<String> = batch
+                .iter()
+                .map(|file| shell_quote_path(&self.target.join(file)))
+                .collect();
+            let output = method.exec(&format!("rm -f -- {}", paths.join(" ")))?;
+            if !output.success() {
+                return Err(TransferError::PermissionDenied(output.stderr.trim().to_string()));
+            }
+        }
+        Ok(files.len())
+    }
+
+    /// Stamps of the files under a local folder, by relative path
+    fn local_stamps(&self, root: &Path) -> Result<HashMap<PathBuf, FileStamp>, TransferError> {
+        let mut stamps = HashMap::new();
+        if !root.exists() {
+            return Ok(stamps);
+        }
+        let mut folders = vec![PathBuf::new()];
+        while let Some(relative) = folders.pop() {
+            let dir = root.join(&relative);
+            let entries = fs::read_dir(&dir)
+                .map_err(|e| TransferError::FileNotFound(format!("{}: {}", dir.display(), e)))?;
+            for entry in entries.flatten() {
+                let path = relative.join(entry.file_name());
+                let Ok(metadata) = entry.metadata() else { continue };
+                if !self.filter.allows(&path, metadata.is_dir()) {
+                    continue;
+                }
+                if metadata.is_dir() {
+                    folders.push(path);
+                } else if metadata.is_file() {
+                    let modified = metadata
+                        .modified()
+                        .ok()
+                        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                        .map(|since| since.as_secs() as i64);
+                    stamps.insert(path, FileStamp { size: metadata.len(), modified });
+                }
+            }
+        }
+        Ok(stamps)
+    }
+
+    /// Stamps of the files under a folder on the Pi, by relative path; a
+    /// folder that does not exist yet has none. Links are left out, as on
+    /// the local side, so the walk never follows one out of the tree or
+    /// around a loop, and nothing behind one is planned for deletion
+    fn remote_stamps(&self, method: &dyn TransferMethod, root: &Path) -> Result<HashMap<PathBuf, FileStamp>, TransferError> {
+        let mut stamps = HashMap::new();
+        let mut folders = vec![PathBuf::new()];
+        let never = AtomicBool::new(false);
+        while let Some(relative) = folders.pop() {
+            let listed = method.list_files_streaming(&root.join(&relative), LISTING_TIMEOUT, &never, &mut |chunk| {
+                for entry in chunk {
+                    if entry.is_symlink() {
+                        continue;
+                    }
+                    let path = relative.join(&entry.name);
+                    if !self.filter.allows(&path, entry.is_dir) {
+                        continue;
+                    }
+                    if entry.is_dir {
+                        folders.push(path);
+                    } else {
+                        stamps.insert(path, FileStamp { size: entry.size, modified: entry.modified });
+                    }
+                }
+            });
+            match listed {
+                Ok(()) => {},
+                Err(TransferError::FileNotFound(_)) if relative.as_os_str().is_empty() => return Ok(stamps),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(stamps)
+    }
+}
@@ -1,8 +1,37 @@
 pub mod method;
 pub mod ssh;
 pub mod rsync;
+pub mod session;
+pub mod broadcast;
+pub mod listing_cache;
+pub mod key_setup;
+pub mod diagnostics;
+pub mod profile;
+pub mod archive;
+pub mod dedup;
+pub mod mirror;
+pub mod filter;
+pub mod remote_trash;
+pub mod audit;
+pub mod wake;
+pub mod camera_stream;
+pub mod agent;
+pub mod provision;
+pub mod plugins;
+pub mod s3;
+pub mod thumbnail_cache;
+pub mod download_cache;
 
 // Re-export the types needed by other modules
-pub use method::{TransferMethod, TransferMethodFactory, TransferError};
+pub use method::{TransferMethod, TransferMethodFactory, TransferError, CommandOutput, RemoteEntry, failure_from_stderr, os_string_from_bytes, wait_with_deadline, stream_lines_with_deadline, stream_records_with_deadline, with_retries};
 pub use ssh::{SSHTransfer, SSHTransferFactory};
 pub use rsync::{RsyncTransfer, RsyncTransferFactory};
+pub use s3::{S3Transfer, S3TransferFactory};
+pub use profile::{connect_unattended, factory_for_host, factory_for_reachable_host, reachable_host};
+pub use plugins::{transfer_plugins, ProtocolChoice, TransferPlugin};
+pub use session::{Session, SessionManager};
+pub use diagnostics::{ConnectionDiagnostics, DiagnosticStage, StageStatus, StageResult};
+pub use mirror::{Mirror, MirrorPlan};
+pub use filter::PathFilter;
+pub use archive::{ArchiveFormat, ArchiveTransfer};
+pub use broadcast::{BroadcastJob, BroadcastOutcome, run_broadcast, summarize};
@@ -0,0 +1,84 @@
+use crate::config::{Host, TransferOptions, TransferProtocol};
+use crate::transfer::method::TransferMethodFactory;
+
+/// A way of copying files other than the built-in protocols, such as S3 or
+/// a cloud drive, offered next to them in the protocol selectors
+///
+/// Any module or crate built into the application adds one with
+/// `inventory::submit!`, without changes to the transfer module:
+///
+/// ```ignore
+/// inventory::submit! {
+///     TransferPlugin { id: "s3", label: "Amazon S3", create: s3_factory }
+/// }
+/// ```
+pub struct TransferPlugin {
+    /// Saved with hosts that use the plugin; must stay the same across
+    /// versions
+    pub id: &'static str,
+    /// Shown in the protocol selectors
+    pub label: &'static str,
+    /// Factory for `host`; the plugin decides what its hostname, username,
+    /// port and key path mean, e.g. a bucket and an access key id. The
+    /// password, when the host has one, is set on each created method.
+    pub create: fn(&Host) -> Box<dyn TransferMethodFactory>,
+}
+
+inventory::collect!(TransferPlugin);
+
+/// Every plugin, by label
+pub fn transfer_plugins() -> Vec<&'static TransferPlugin> {
+    let mut plugins: Vec<&'static TransferPlugin> = inventory::iter::<TransferPlugin>.into_iter().collect();
+    plugins.sort_by_key(|plugin| plugin.label);
+    plugins
+}
+
+pub fn find_transfer_plugin(id: &str) -> Option<&'static TransferPlugin> {
+    transfer_plugins().into_iter().find(|plugin| plugin.id == id)
+}
+
+/// One entry of a protocol selector
+#[derive(Clone, Copy)]
+pub enum ProtocolChoice {
+    Builtin(TransferProtocol),
+    Plugin(&'static TransferPlugin),
+}
+
+impl ProtocolChoice {
+    /// Every entry, in the order offered: the built-in protocols first
+    pub fn all() -> Vec<ProtocolChoice> {
+        TransferProtocol::ALL
+            .into_iter()
+            .map(Self::Builtin)
+            .chain(transfer_plugins().into_iter().map(Self::Plugin))
+            .collect()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Builtin(protocol) => protocol.label(),
+            Self::Plugin(plugin) => plugin.label,
+        }
+    }
+
+    /// Position in [`ProtocolChoice::all`] of what `options` use; a plugin
+    /// missing from this build shows as the protocol underneath it
+    pub fn index_of(options: &TransferOptions) -> usize {
+        let choices = Self::all();
+        let plugin_index = options.plugin.as_deref().and_then(|id| {
+            choices.iter().position(|choice| matches!(choice, Self::Plugin(plugin) if plugin.id == id))
+        });
+        plugin_index.unwrap_or_else(|| options.protocol.index())
+    }
+
+    /// Make `options` use this entry
+    pub fn apply(&self, options: &mut TransferOptions) {
+        match self {
+            Self::Builtin(protocol) => {
+                options.protocol = *protocol;
+                options.plugin = None;
+            },
+            Self::Plugin(plugin) => options.plugin = Some(plugin.id.to_string()),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::env;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::thread;
+use std::time::Duration;
+
+use crate::cli::DEFAULT_PASSWORD_ENV;
+use crate::config::{Host, TransferProtocol};
+use crate::transfer::method::{TransferError, TransferMethod, TransferMethodFactory};
+use crate::transfer::plugins::find_transfer_plugin;
+use crate::transfer::rsync::RsyncTransferFactory;
+use crate::transfer::ssh::SSHTransferFactory;
+
+/// How long each address of a host may take to accept a connection
+const ADDRESS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Factory for the protocol and options saved with `host`
+///
+/// Passwords are not part of a host; set them on the created method.
+pub fn factory_for_host(host: &Host) -> Box<dyn TransferMethodFactory> {
+    if let Some(id) = &host.transfer.plugin {
+        match find_transfer_plugin(id) {
+            Some(plugin) => return (plugin.create)(host),
+            None => log::error!(
+                "{} uses the transfer plugin '{}', which this build does not have; using {}",
+                host.name,
+                id,
+                host.transfer.protocol.label()
+            ),
+        }
+    }
+
+    match host.transfer.protocol {
+        TransferProtocol::Rsync => {
+            let mut factory = RsyncTransferFactory::new(
+                host.hostname.clone(),
+                host.username.clone(),
+                host.port,
+                host.use_key_auth,
+                host.key_path.clone(),
+                host.transfer.rsync_args.clone(),
+            );
+            factory.set_proxy_jump(host.proxy_jump.clone());
+            factory.set_transfer_options(host.transfer.clone());
+            Box::new(factory)
+        },
+        TransferProtocol::Scp | TransferProtocol::Sftp => {
+            let mut factory = SSHTransferFactory::new(
+                host.hostname.clone(),
+                host.username.clone(),
+                host.port,
+                host.use_key_auth,
+                host.key_path.clone(),
+            );
+            factory.set_proxy_jump(host.proxy_jump.clone());
+            factory.set_transfer_options(host.transfer.clone());
+            Box::new(factory)
+        },
+    }
+}
+
+/// `host` pointed at the first of its addresses that accepts a TCP
+/// connection, with the name of that address
+///
+/// All addresses are probed at once, so an unreachable LAN address costs
+/// one timeout rather than one per address. Hosts without fallbacks or a
+/// MAC address to wake them by, and hosts reached through a jump host, are
+/// returned unchanged since ssh reports its own errors for them.
+pub fn reachable_host(host: &Host) -> Result<(String, Host), TransferError> {
+    let mut candidates = host.address_candidates();
+    if (candidates.len() == 1 && host.mac_address.is_none()) || host.proxy_jump.is_some() {
+        return Ok(candidates.remove(0));
+    }
+
+    let reachable: Vec<Result<(), String>> = thread::scope(|scope| {
+        let probes: Vec<_> = candidates
+            .iter()
+            .map(|(_, candidate)| scope.spawn(move || probe(&candidate.hostname, candidate.port)))
+            .collect();
+        probes
+            .into_iter()
+            .map(|probe| probe.join().unwrap_or_else(|_| Err("probe panicked".to_string())))
+            .collect()
+    });
+
+    let mut failures = Vec::new();
+    for ((name, candidate), result) in candidates.into_iter().zip(reachable) {
+        match result {
+            Ok(()) => {
+                log::info!("Reaching {} through its {} address {}:{}", host.name, name, candidate.hostname, candidate.port);
+                return Ok((name, candidate));
+            },
+            Err(e) => failures.push(format!("{} ({}:{}): {}", name, candidate.hostname, candidate.port, e)),
+        }
+    }
+    Err(TransferError::ConnectionFailed(format!(
+        "No address of {} answered: {}",
+        host.name,
+        failures.join("; ")
+    )))
+}
+
+/// Factory for the first of `host`'s addresses that answers, falling back
+/// to its primary address so the copy itself reports the failure
+pub fn factory_for_reachable_host(host: &Host) -> Box<dyn TransferMethodFactory> {
+    match reachable_host(host) {
+        Ok((_, endpoint)) => factory_for_host(&endpoint),
+        Err(e) => {
+            log::warn!("{}", e);
+            factory_for_host(host)
+        },
+    }
+}
+
+/// Method for `host` that needs nobody to type a password: hosts with key
+/// authentication as they are, others with the password in the
+/// `PI_REMOTE_MANAGER_PASSWORD` environment variable
+pub fn connect_unattended(host: &Host) -> Result<Box<dyn TransferMethod>, TransferError> {
+    let mut method = factory_for_reachable_host(host).create_method();
+    if !host.use_key_auth {
+        let password = env::var(DEFAULT_PASSWORD_ENV).map_err(|_| TransferError::AuthenticationFailed(format!(
+            "Host '{}' uses password authentication; set {} before starting the app or switch to key authentication",
+            host.name, DEFAULT_PASSWORD_ENV
+        )))?;
+        method.set_password(&password);
+    }
+    Ok(method)
+}
+
+fn probe(hostname: &str, port: u16) -> Result<(), String> {
+    let addresses = (hostname, port).to_socket_addrs().map_err(|e| e.to_string())?;
+    let mut last_error = "no addresses".to_string();
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, ADDRESS_TIMEOUT) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    Err(last_error)
+}
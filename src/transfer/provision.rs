@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::core::device::{parse_remote_probe, remote_probe_command, Capabilities};
+use crate::transfer::agent::{agent_available, detect, forget_agent, install_agent};
+use crate::transfer::method::{wait_with_deadline, TransferError, TransferMethod};
+
+/// How long apt may take to update its lists and install the packages
+const APT_TIMEOUT: Duration = Duration::from_secs(20 * 60);
+
+/// Something the richer features need on the Pi that can be installed
+/// from here
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompanionTool {
+    /// A build of this program, uploaded and run as the companion agent
+    Agent,
+    Rsync,
+    ImageMagick,
+}
+
+impl CompanionTool {
+    pub const ALL: [CompanionTool; 3] = [Self::Agent, Self::Rsync, Self::ImageMagick];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Agent => "Companion agent",
+            Self::Rsync => "rsync",
+            Self::ImageMagick => "ImageMagick",
+        }
+    }
+
+    /// Name with how the tool gets installed, for choosing what to install
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Agent => "Companion agent (upload a build for the Pi)",
+            Self::Rsync => "rsync (apt package rsync)",
+            Self::ImageMagick => "ImageMagick (apt package imagemagick)",
+        }
+    }
+
+    /// Debian package providing the tool, None for the agent
+    pub fn package(&self) -> Option<&'static str> {
+        match self {
+            Self::Agent => None,
+            Self::Rsync => Some("rsync"),
+            Self::ImageMagick => Some("imagemagick"),
+        }
+    }
+
+    /// Binary the capability probe looks for, None for the agent
+    fn binary(&self) -> Option<&'static str> {
+        match self {
+            Self::Agent => None,
+            Self::Rsync => Some("rsync"),
+            Self::ImageMagick => Some("convert"),
+        }
+    }
+}
+
+/// Which companion tools the Pi behind `method` has, in `CompanionTool::ALL`
+/// order
+pub fn installed_tools(method: &dyn TransferMethod) -> Result<Vec<(CompanionTool, bool)>, TransferError> {
+    let output = method.exec(&remote_probe_command())?;
+    let capabilities = Capabilities { local: Vec::new(), remote: parse_remote_probe(&output.stdout) };
+    forget_agent(method);
+    let has_agent = detect(method).is_ok();
+
+    Ok(CompanionTool::ALL
+        .iter()
+        .map(|tool| {
+            let installed = match tool.binary() {
+                Some(binary) => capabilities.has_remote(binary),
+                None => has_agent,
+            };
+            (*tool, installed)
+        })
+        .collect())
+}
+
+/// Shell command installing `packages` with apt; `sudo -n` fails at once
+/// instead of waiting for a password nobody can type
+pub fn apt_install_command(packages: &[&str]) -> String {
+    format!(
+        "sudo -n env DEBIAN_FRONTEND=noninteractive apt-get update -q && \
+         sudo -n env DEBIAN_FRONTEND=noninteractive apt-get install -y -q {}",
+        packages.join(" ")
+    )
+}
+
+/// What to install in one go
+#[derive(Debug, Clone, Default)]
+pub struct ProvisionPlan {
+    /// Build of this program to install as the agent, needed when `tools`
+    /// holds `CompanionTool::Agent`
+    pub agent_binary: Option<PathBuf>,
+    pub tools: Vec<CompanionTool>,
+}
+
+impl ProvisionPlan {
+    /// Steps reported as progress: the agent upload, the apt run and the
+    /// final check
+    pub fn steps(&self) -> usize {
+        let packages = self.tools.iter().any(|tool| tool.package().is_some());
+        self.agent_binary.is_some() as usize + packages as usize + 1
+    }
+}
+
+/// Install everything in `plan` on the Pi behind `method`, then check that
+/// it works; returns one line per tool saying how it went
+///
+/// A failed step does not stop the others, so an apt failure still leaves
+/// the agent installed; the error lists every step that failed.
+pub fn provision(
+    method: &dyn TransferMethod,
+    plan: &ProvisionPlan,
+    progress: &dyn Fn(usize),
+    cancel: &AtomicBool
+) -> Result<Vec<String>, TransferError> {
+    let mut done = 0;
+    let mut failures = Vec::new();
+
+    if let Some(binary) = &plan.agent_binary {
+        if let Err(e) = install_agent(method, binary) {
+            failures.push(format!("Companion agent: {}", e));
+        }
+        done += 1;
+        progress(done);
+    }
+
+    let packages: Vec<&str> = plan.tools.iter().filter_map(|tool| tool.package()).collect();
+    if !packages.is_empty() {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(TransferError::Cancelled);
+        }
+        log::info!("Installing {} on the Pi with apt", packages.join(", "));
+        let output = wait_with_deadline(method.spawn_remote(&apt_install_command(&packages))?, APT_TIMEOUT, cancel)?;
+        if !output.success() {
+            let detail = output.stderr.lines().last().unwrap_or("").trim().to_string();
+            let message = if detail.contains("password is required") {
+                "sudo needs a password on the Pi; install the packages there or allow passwordless sudo".to_string()
+            } else {
+                detail
+            };
+            failures.push(format!("apt-get install {}: {}", packages.join(" "), message));
+        }
+        done += 1;
+        progress(done);
+    }
+
+    // Check everything planned, whether or not its step reported success
+    let installed = installed_tools(method)?;
+    progress(done + 1);
+
+    let mut report = Vec::new();
+    for (tool, present) in installed {
+        if !plan.tools.contains(&tool) {
+            continue;
+        }
+        if present {
+            report.push(format!("{}: installed", tool.name()));
+        } else {
+            report.push(format!("{}: still missing", tool.name()));
+            if failures.is_empty() {
+                failures.push(format!("{} is still missing after installing", tool.name()));
+            }
+        }
+    }
+    if plan.agent_binary.is_some() {
+        // Pick the new agent up for the features that use it
+        forget_agent(method);
+        agent_available(method);
+    }
+
+    if failures.is_empty() {
+        Ok(report)
+    } else {
+        Err(TransferError::TransferFailed(failures.join("\n")))
+    }
+}
@@ -0,0 +1,145 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{Local, NaiveDateTime};
+
+use crate::transfer::method::{os_string_from_bytes, TransferError, TransferMethod};
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+
+/// Folder in the Pi user's home that deleted files are moved to
+pub const REMOTE_TRASH_DIR: &str = ".pi_remote_manager_trash";
+
+/// Shell expression for the trash folder; `$HOME` must stay unquoted
+const TRASH: &str = "\"$HOME\"/.pi_remote_manager_trash";
+
+/// Format of the trash entry ids, which are also their deletion times
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// A file or folder in the Pi's trash
+///
+/// Each lives in `<trash>/<id>/<name>`, with its original path in
+/// `<trash>/<id>.path`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrashedItem {
+    pub id: String,
+    pub original: PathBuf,
+}
+
+impl TrashedItem {
+    pub fn name(&self) -> String {
+        self.original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+    }
+
+    /// Name in the trash slot, as the Pi has it
+    fn file_name(&self) -> &Path {
+        Path::new(self.original.file_name().unwrap_or_default())
+    }
+
+    /// When the item was moved to the trash
+    pub fn deleted_at(&self) -> Option<NaiveDateTime> {
+        let stamp = self.id.get(..15)?;
+        NaiveDateTime::parse_from_str(stamp, ID_FORMAT).ok()
+    }
+
+    fn slot(&self) -> String {
+        format!("{}/{}", TRASH, shell_quote(&self.id))
+    }
+}
+
+/// Shell command moving `paths` on the Pi into the trash
+pub fn trash_command(paths: &[PathBuf]) -> String {
+    let now = Local::now().format(ID_FORMAT);
+    let mut script = format!("mkdir -p {} || exit 1\nstatus=0\n", TRASH);
+    for (index, path) in paths.iter().enumerate() {
+        // The process id keeps two deletes within a second apart
+        let slot = format!("{}/{}-$$-{}", TRASH, now, index);
+        let quoted = shell_quote_path(path);
+        script.push_str(&format!(
+            "mkdir {slot} && mv -- {quoted} {slot}/ && printf '%s\\n' {quoted} > {slot}.path || {{ rmdir {slot} 2>/dev/null; status=1; }}\n",
+        ));
+    }
+    script.push_str("exit $status");
+    script
+}
+
+/// Move `paths` on the Pi into the trash
+pub fn move_to_remote_trash(method: &dyn TransferMethod, paths: &[PathBuf]) -> Result<(), TransferError> {
+    let output = method.exec(&trash_command(paths))?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::PermissionDenied(output.stderr.trim().to_string()))
+    }
+}
+
+/// Everything in the Pi's trash, most recently deleted first
+///
+/// Records end in NUL and original paths are kept as raw bytes, as names
+/// may hold newlines or bytes that aren't UTF-8.
+pub fn list_remote_trash(method: &dyn TransferMethod) -> Result<Vec<TrashedItem>, TransferError> {
+    let command = format!(
+        "cd {} 2>/dev/null || exit 0\nfor f in *.path; do [ -e \"$f\" ] || continue; printf '%s\\t%s\\0' \"${{f%.path}}\" \"$(cat \"$f\")\"; done",
+        TRASH
+    );
+    let output = method.exec(&command)?;
+    if !output.success() {
+        return Err(TransferError::TransferFailed(output.stderr.trim().to_string()));
+    }
+
+    let mut items: Vec<TrashedItem> = output
+        .stdout_bytes
+        .split(|&byte| byte == b'\0')
+        .filter_map(|record| {
+            let tab = record.iter().position(|&byte| byte == b'\t')?;
+            let id = std::str::from_utf8(&record[..tab]).ok()?;
+            let original = os_string_from_bytes(record[tab + 1..].to_vec());
+            Some(TrashedItem { id: id.to_string(), original: PathBuf::from(original) })
+        })
+        .collect();
+    items.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(items)
+}
+
+/// Put `items` back where they were deleted from; items whose original path
+/// is taken again are left in the trash and reported
+pub fn restore_from_remote_trash(method: &dyn TransferMethod, items: &[TrashedItem]) -> Result<(), TransferError> {
+    let mut script = String::from("status=0\n");
+    for item in items {
+        let original = shell_quote_path(&item.original);
+        let parent = shell_quote_path(item.original.parent().unwrap_or(Path::new("/")));
+        let slot = item.slot();
+        script.push_str(&format!(
+            "if [ -e {original} ]; then echo {taken} >&2; status=1; \
+             else mkdir -p {parent} && mv -- {slot}/{name} {original} && rm -rf -- {slot} {slot}.path || status=1; fi\n",
+            taken = shell_quote(&format!("{} already exists", item.original.display())),
+            name = shell_quote_path(item.file_name()),
+        ));
+    }
+    script.push_str("exit $status");
+
+    let output = method.exec(&script)?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::TransferFailed(output.stderr.trim().to_string()))
+    }
+}
+
+/// Delete `items` from the trash for good
+pub fn purge_remote_trash(method: &dyn TransferMethod, items: &[TrashedItem]) -> Result<(), TransferError> {
+    let slots: Vec<String> = items.iter().flat_map(|item| [item.slot(), format!("{}.path", item.slot())]).collect();
+    run_removal(method, &format!("rm -rf -- {}", slots.join(" ")))
+}
+
+/// Delete everything in the trash for good
+pub fn empty_remote_trash(method: &dyn TransferMethod) -> Result<(), TransferError> {
+    run_removal(method, &format!("rm -rf -- {}", TRASH))
+}
+
+fn run_removal(method: &dyn TransferMethod, command: &str) -> Result<(), TransferError> {
+    let output = method.exec(command)?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::PermissionDenied(output.stderr.trim().to_string()))
+    }
+}
@@ -1,10 +1,16 @@
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::process::{Child, Command};
 use std::io::{self, Write};
 use std::any::Any;
+use std::ffi::OsString;
 
-use crate::transfer::method::{TransferMethod, TransferError, TransferMethodFactory};
-use crate::transfer::ssh::SSHTransfer;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::config::{RetryPolicy, TransferOptions};
+use crate::transfer::listing_cache;
+use crate::transfer::method::{TransferMethod, TransferError, TransferMethodFactory, RemoteEntry, failure_from_stderr};
+use crate::transfer::ssh::{remote_arg, SSHTransfer};
 
 
 pub struct RsyncTransfer {
@@ -15,6 +21,9 @@ pub struct RsyncTransfer {
     key_path: Option<PathBuf>,
     options: Vec<String>,
     password: Option<String>,
+    // Jump host(s) passed to ssh as ProxyJump
+    proxy_jump: Option<String>,
+    transfer_options: TransferOptions,
 }
 
 impl RsyncTransfer {
@@ -34,6 +43,8 @@ impl RsyncTransfer {
             key_path,
             options,
             password: None,
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
@@ -52,6 +63,8 @@ impl RsyncTransfer {
             key_path: None,
             options,
             password: Some(password),
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
@@ -59,6 +72,15 @@ impl RsyncTransfer {
         self.password = Some(password);
     }
     
+    pub fn set_proxy_jump(&mut self, proxy_jump: Option<String>) {
+        self.proxy_jump = proxy_jump;
+    }
+    
+    // Compression and concurrency chosen for the host
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
+    
     // Debug function to help troubleshoot commands
     fn debug_command(&self, cmd: &mut Command, command_name: &str) -> Result<std::process::Output, TransferError> {
         // Print the command that's about to be executed (sanitize password for security)
@@ -66,26 +88,44 @@ impl RsyncTransfer {
         if let Some(ref password) = self.password {
             cmd_str = cmd_str.replace(password, "********");
         }
-        println!("Executing {}: {}", command_name, cmd_str);
+        log::debug!("Executing {}: {}", command_name, cmd_str);
         
         let output = cmd.output().map_err(|e| {
             TransferError::TransferFailed(format!("Failed to execute {}: {}", command_name, e))
         })?;
         
         // Print output status and contents
-        println!("Command status: {}", output.status);
-        println!("STDOUT: {}", String::from_utf8_lossy(&output.stdout));
-        println!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
+        log::debug!("Command status: {}", output.status);
+        log::debug!("STDOUT: {}", String::from_utf8_lossy(&output.stdout));
+        log::debug!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
         
         if !output.status.success() {
-            return Err(TransferError::TransferFailed(
-                String::from_utf8_lossy(&output.stderr).to_string()
-            ));
+            return Err(failure_from_stderr(&String::from_utf8_lossy(&output.stderr)));
         }
         
         Ok(output)
     }
     
+    // Create an SSH transfer with the same credentials for shell-level operations
+    fn ssh_transfer(&self) -> SSHTransfer {
+        let mut ssh = SSHTransfer::new(
+            self.hostname.clone(),
+            self.username.clone(),
+            self.port,
+            self.use_key_auth,
+            self.key_path.clone(),
+        );
+        
+        // Pass password if available
+        if let Some(ref password) = self.password {
+            ssh.set_password(password.clone());
+        }
+        ssh.set_proxy_jump(self.proxy_jump.clone());
+        ssh.set_transfer_options(self.transfer_options.clone());
+        
+        ssh
+    }
+    
     // Get password from user interactively if needed
     fn ensure_password(&mut self) -> Result<(), TransferError> {
         if !self.use_key_auth && self.password.is_none() {
@@ -135,8 +175,14 @@ impl TransferMethod for RsyncTransfer {
             cmd = Command::new("rsync");
         }
         
-        // Add standard options
-        cmd.arg("-avz");
+        // Add standard options; rsync compresses unless the host turns it off
+        cmd.arg("-av");
+        if self.transfer_options.compression != Some(false) {
+            cmd.arg("-z");
+        }
+        if !self.transfer_options.preserve_symlinks {
+            cmd.arg("--copy-links");
+        }
         
         // Add custom options
         for option in &self.options {
@@ -145,6 +191,9 @@ impl TransferMethod for RsyncTransfer {
         
         // Configure SSH options based on auth method
         let mut ssh_opts = format!("ssh -p {}", self.port);
+        if let Some(jump) = &self.proxy_jump {
+            ssh_opts.push_str(&format!(" -o ProxyJump={}", jump));
+        }
         
         if self.use_key_auth {
             if let Some(key_path) = &self.key_path {
@@ -157,17 +206,16 @@ impl TransferMethod for RsyncTransfer {
         // Add source and destination
         cmd.arg(local_path);
         
-        let remote = format!(
-            "{}@{}:{}",
-            self.username,
-            self.hostname,
-            remote_path.to_string_lossy()
-        );
-        cmd.arg(remote);
+        cmd.arg(remote_arg(&self.username, &self.hostname, remote_path));
         
         // Use debug command
         self_copy.debug_command(&mut cmd, "rsync upload")?;
         
+        // The target may be the new file or the directory it was copied into
+        let key = self.connection_key();
+        listing_cache::invalidate(&key, remote_path);
+        listing_cache::invalidate_parent(&key, remote_path);
+        
         Ok(())
     }
     
@@ -199,8 +247,14 @@ impl TransferMethod for RsyncTransfer {
             cmd = Command::new("rsync");
         }
         
-        // Add standard options
-        cmd.arg("-avz");
+        // Add standard options; rsync compresses unless the host turns it off
+        cmd.arg("-av");
+        if self.transfer_options.compression != Some(false) {
+            cmd.arg("-z");
+        }
+        if !self.transfer_options.preserve_symlinks {
+            cmd.arg("--copy-links");
+        }
         
         // Add custom options
         for option in &self.options {
@@ -209,6 +263,9 @@ impl TransferMethod for RsyncTransfer {
         
         // Configure SSH options based on auth method
         let mut ssh_opts = format!("ssh -p {}", self.port);
+        if let Some(jump) = &self.proxy_jump {
+            ssh_opts.push_str(&format!(" -o ProxyJump={}", jump));
+        }
         
         if self.use_key_auth {
             if let Some(key_path) = &self.key_path {
@@ -219,13 +276,7 @@ impl TransferMethod for RsyncTransfer {
         cmd.arg("-e").arg(ssh_opts);
         
         // Add source and destination
-        let remote = format!(
-            "{}@{}:{}",
-            self.username,
-            self.hostname,
-            remote_path.to_string_lossy()
-        );
-        cmd.arg(remote);
+        cmd.arg(remote_arg(&self.username, &self.hostname, remote_path));
         cmd.arg(local_path);
         
         // Use debug command
@@ -237,37 +288,84 @@ impl TransferMethod for RsyncTransfer {
     fn list_files(
         &self,
         remote_dir: &Path
-    ) -> Result<Vec<(String, bool)>, TransferError> {
-        // Create an SSH transfer to reuse its list_files implementation
-        let mut ssh = SSHTransfer::new(
-            self.hostname.clone(),
-            self.username.clone(),
-            self.port,
-            self.use_key_auth,
-            self.key_path.clone(),
-        );
-        
-        // Pass password if available
-        if let Some(ref password) = self.password {
-            ssh.set_password(password.clone());
-        }
-        
-        ssh.list_files(remote_dir)
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        // Reuse the SSH list_files implementation
+        self.ssh_transfer().list_files(remote_dir)
+    }
+    
+    fn list_files_with_timeout(
+        &self,
+        remote_dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        self.ssh_transfer().list_files_with_timeout(remote_dir, timeout, cancel)
+    }
+    
+    fn list_files_streaming(
+        &self,
+        remote_dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        self.ssh_transfer().list_files_streaming(remote_dir, timeout, cancel, on_chunk)
+    }
+    
+    fn spawn_remote(&self, command: &str) -> Result<Child, TransferError> {
+        self.ssh_transfer().spawn_remote(command)
+    }
+    
+    fn preserves_symlinks(&self) -> bool {
+        self.transfer_options.preserve_symlinks
+    }
+    
+    // rsync can't elevate on the Pi's side without sudoers changes, so
+    // listings and downloads with sudo go over plain SSH
+    fn sudo_method(&self) -> Option<Box<dyn TransferMethod>> {
+        self.ssh_transfer().sudo_method()
     }
     
     fn get_name(&self) -> &str {
         "Rsync Transfer"
     }
     
+    fn connection_key(&self) -> String {
+        format!("{}@{}:{}", self.username, self.hostname, self.port)
+    }
+    
+    fn max_concurrent(&self) -> usize {
+        self.transfer_options.concurrency.max(1)
+    }
+    
+    fn retry_policy(&self) -> RetryPolicy {
+        self.transfer_options.retry.clone()
+    }
+    
+    fn prefers_agent(&self) -> bool {
+        self.transfer_options.use_agent
+    }
+    
+    fn endpoint(&self) -> Option<(String, u16)> {
+        self.ssh_transfer().endpoint()
+    }
+    
     fn get_description(&self) -> String {
         format!("Rsync transfer to {}@{} with options: {}", 
             self.username, 
             self.hostname, 
             self.options.join(" "))
     }
+    fn clone_box(&self) -> Box<dyn TransferMethod> {
+        Box::new(self.clone())
+    }
     fn as_any(&mut self) -> &mut dyn Any {
         self
     }
+    // Also used for the ssh connection behind listings and commands
+    fn set_password(&mut self, password: &str) {
+        self.password = Some(password.to_string());
+    }
 }
 
 // Make RsyncTransfer cloneable for password handling
@@ -281,6 +379,8 @@ impl Clone for RsyncTransfer {
             key_path: self.key_path.clone(),
             options: self.options.clone(),
             password: self.password.clone(),
+            proxy_jump: self.proxy_jump.clone(),
+            transfer_options: self.transfer_options.clone(),
         }
     }
 }
@@ -293,6 +393,9 @@ pub struct RsyncTransferFactory {
     key_path: Option<PathBuf>,
     options: Vec<String>,
     password: Option<String>,
+    // Jump host(s) passed to ssh as ProxyJump
+    proxy_jump: Option<String>,
+    transfer_options: TransferOptions,
 }
 
 impl RsyncTransferFactory {
@@ -312,6 +415,8 @@ impl RsyncTransferFactory {
             key_path: key_path.map(PathBuf::from),
             options,
             password: None,
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
@@ -330,12 +435,23 @@ impl RsyncTransferFactory {
             key_path: None,
             options,
             password: Some(password),
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
     pub fn set_password(&mut self, password: String) {
         self.password = Some(password);
     }
+    
+    pub fn set_proxy_jump(&mut self, proxy_jump: Option<String>) {
+        self.proxy_jump = proxy_jump;
+    }
+    
+    // Compression and concurrency chosen for the host
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
 }
 
 impl TransferMethodFactory for RsyncTransferFactory {
@@ -353,6 +469,8 @@ impl TransferMethodFactory for RsyncTransferFactory {
         if let Some(ref password) = self.password {
             transfer.set_password(password.clone());
         }
+        transfer.set_proxy_jump(self.proxy_jump.clone());
+        transfer.set_transfer_options(self.transfer_options.clone());
         
         Box::new(transfer)
     }
@@ -0,0 +1,584 @@
+use std::any::Any;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::config::{Host, RetryPolicy, TransferOptions};
+use crate::transfer::diagnostics::{check_network, ConnectionDiagnostics, DiagnosticStage, StageStatus};
+use crate::transfer::method::{RemoteEntry, TransferError, TransferMethod, TransferMethodFactory};
+use crate::transfer::plugins::TransferPlugin;
+
+/// Files at least this large are uploaded in parts
+const MULTIPART_THRESHOLD: u64 = 32 * 1024 * 1024;
+
+/// Size of each part of a multipart upload; S3 wants at least 5 MiB for
+/// all but the last and allows 10,000 parts, so this covers 160 GB
+const PART_SIZE: usize = 16 * 1024 * 1024;
+
+/// Region signed for when the endpoint does not name one; MinIO uses it
+/// unless configured otherwise
+const DEFAULT_REGION: &str = "us-east-1";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Payload hash of requests without a body
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+inventory::submit! {
+    TransferPlugin { id: "s3", label: "S3 / MinIO", create: s3_factory }
+}
+
+fn s3_factory(host: &Host) -> Box<dyn TransferMethodFactory> {
+    let mut factory = S3TransferFactory::new(host.hostname.clone(), host.port, host.username.clone());
+    factory.set_transfer_options(host.transfer.clone());
+    Box::new(factory)
+}
+
+/// Copies to and from an S3-compatible object store such as MinIO
+///
+/// Buckets show as the directories of `/` and keys below them are split on
+/// `/`, so `/photos/2024/pi.jpg` is the key `2024/pi.jpg` in the bucket
+/// `photos`. The host's username is the access key id and its password the
+/// secret key; port 443 is reached over HTTPS and any other port, such as
+/// MinIO's 9000, over plain HTTP. Requests use path-style addressing and
+/// Signature Version 4.
+#[derive(Clone)]
+pub struct S3Transfer {
+    hostname: String,
+    port: u16,
+    access_key: String,
+    secret_key: Option<String>,
+    region: String,
+    transfer_options: TransferOptions,
+}
+
+/// One request to the store; `path` and the query are signed as given
+struct S3Request<'a> {
+    method: &'a str,
+    path: String,
+    query: Vec<(&'a str, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> S3Request<'a> {
+    fn new(method: &'a str, path: String) -> Self {
+        Self { method, path, query: Vec::new(), body: &[] }
+    }
+
+    fn query(mut self, name: &'a str, value: impl Into<String>) -> Self {
+        self.query.push((name, value.into()));
+        self
+    }
+
+    fn body(mut self, body: &'a [u8]) -> Self {
+        self.body = body;
+        self
+    }
+}
+
+impl S3Transfer {
+    pub fn new(hostname: String, port: u16, access_key: String) -> Self {
+        let region = region_of(&hostname);
+        Self {
+            hostname,
+            port,
+            access_key,
+            secret_key: None,
+            region,
+            transfer_options: TransferOptions::default(),
+        }
+    }
+
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.port == 443 { "https" } else { "http" }
+    }
+
+    /// Host header, with the port unless it is the scheme's default
+    fn host_header(&self) -> String {
+        match self.port {
+            80 | 443 => self.hostname.clone(),
+            port => format!("{}:{}", self.hostname, port),
+        }
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        ureq::AgentBuilder::new()
+            .timeout_connect(CONNECT_TIMEOUT)
+            .timeout_read(READ_TIMEOUT)
+            .timeout_write(READ_TIMEOUT)
+            .build()
+    }
+
+    /// Sign and send `request`, turning error statuses into transfer errors
+    fn send(&self, request: S3Request) -> Result<ureq::Response, TransferError> {
+        let secret_key = self.secret_key.as_deref().ok_or_else(|| {
+            TransferError::AuthenticationFailed(format!("No secret key for {}", self.access_key))
+        })?;
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = if request.body.is_empty() { EMPTY_SHA256.to_string() } else { hex(&Sha256::digest(request.body)) };
+        let host = self.host_header();
+
+        let mut query: Vec<(String, String)> = request
+            .query
+            .iter()
+            .map(|(name, value)| (uri_encode(name, false), uri_encode(value, false)))
+            .collect();
+        query.sort();
+        let query = query.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join("&");
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            request.method, request.path, query, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac(format!("AWS4{}", secret_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&key, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        let mut url = format!("{}://{}{}", self.scheme(), host, request.path);
+        if !query.is_empty() {
+            url = format!("{}?{}", url, query);
+        }
+        log::debug!("S3 {} {}", request.method, url);
+
+        let call = self
+            .agent()
+            .request(request.method, &url)
+            .set("Host", &host)
+            .set("x-amz-date", &amz_date)
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("Authorization", &authorization);
+        // Stores want a length on every PUT and POST, even an empty one
+        let result = if request.method == "GET" { call.call() } else { call.send_bytes(request.body) };
+
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(status, response)) => {
+                let body = response.into_string().unwrap_or_default();
+                let code = tag(&body, "Code").unwrap_or_default();
+                let message = tag(&body, "Message").map(unescape).unwrap_or_else(|| format!("HTTP {}", status));
+                let detail = format!("{} {}: {}", request.method, request.path, message);
+                Err(match (status, code) {
+                    (_, "InvalidAccessKeyId" | "SignatureDoesNotMatch") => TransferError::AuthenticationFailed(detail),
+                    (403, _) => TransferError::PermissionDenied(detail),
+                    (404, _) => TransferError::FileNotFound(detail),
+                    (500..=599, _) => TransferError::ConnectionFailed(detail),
+                    _ => TransferError::TransferFailed(detail),
+                })
+            },
+            Err(e) => Err(TransferError::ConnectionFailed(format!("{}: {}", host, e))),
+        }
+    }
+
+    fn list_buckets(&self) -> Result<Vec<RemoteEntry>, TransferError> {
+        let body = read_body(self.send(S3Request::new("GET", "/".to_string()))?)?;
+        Ok(tags(&body, "Bucket")
+            .into_iter()
+            .filter_map(|bucket| {
+                let name = unescape(tag(bucket, "Name")?);
+                let created = tag(bucket, "CreationDate").and_then(parse_time);
+                Some(RemoteEntry { name: name.into(), is_dir: true, size: 0, modified: created, link_target: None })
+            })
+            .collect())
+    }
+
+    /// Objects and common prefixes directly under `prefix`, a page at a time
+    fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        on_page: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        let mut continuation: Option<String> = None;
+        loop {
+            let mut request = S3Request::new("GET", format!("/{}", uri_encode(bucket, false)))
+                .query("list-type", "2")
+                .query("delimiter", "/")
+                .query("prefix", prefix);
+            if let Some(token) = &continuation {
+                request = request.query("continuation-token", token.clone());
+            }
+            let body = read_body(self.send(request)?)?;
+
+            let mut entries = Vec::new();
+            for common in tags(&body, "CommonPrefixes") {
+                let Some(full) = tag(common, "Prefix").map(unescape) else { continue };
+                let name = full[prefix.len().min(full.len())..].trim_end_matches('/').to_string();
+                if !name.is_empty() {
+                    entries.push(RemoteEntry::bare(name, true));
+                }
+            }
+            for object in tags(&body, "Contents") {
+                let Some(key) = tag(object, "Key").map(unescape) else { continue };
+                let name = key[prefix.len().min(key.len())..].to_string();
+                // Empty objects some tools create to stand for a folder
+                if name.is_empty() || name.ends_with('/') {
+                    continue;
+                }
+                entries.push(RemoteEntry {
+                    name: name.into(),
+                    is_dir: false,
+                    size: tag(object, "Size").and_then(|size| size.parse().ok()).unwrap_or(0),
+                    modified: tag(object, "LastModified").and_then(parse_time),
+                    link_target: None,
+                });
+            }
+            on_page(entries);
+
+            continuation = match tag(&body, "IsTruncated") {
+                Some("true") => tag(&body, "NextContinuationToken").map(unescape),
+                _ => None,
+            };
+            if continuation.is_none() {
+                return Ok(());
+            }
+        }
+    }
+
+    fn upload_multipart(&self, local_path: &Path, path: &str, size: u64) -> Result<(), TransferError> {
+        let body = read_body(self.send(S3Request::new("POST", path.to_string()).query("uploads", ""))?)?;
+        let upload_id = tag(&body, "UploadId")
+            .map(unescape)
+            .ok_or_else(|| TransferError::TransferFailed(format!("No upload id for {}", path)))?;
+        log::info!("Uploading {} to {} in {} parts", local_path.display(), path, size.div_ceil(PART_SIZE as u64));
+
+        let result = self.upload_parts(local_path, path, &upload_id).and_then(|etags| {
+            let parts: String = etags
+                .iter()
+                .enumerate()
+                .map(|(index, etag)| format!("<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>", index + 1, etag))
+                .collect();
+            let manifest = format!("<CompleteMultipartUpload>{}</CompleteMultipartUpload>", parts);
+            let response = self.send(
+                S3Request::new("POST", path.to_string()).query("uploadId", upload_id.clone()).body(manifest.as_bytes())
+            )?;
+            // Completing can fail after a 200, with the error in the body
+            let body = read_body(response)?;
+            match tag(&body, "Message") {
+                Some(message) if body.contains("<Error>") => Err(TransferError::TransferFailed(unescape(message))),
+                _ => Ok(()),
+            }
+        });
+
+        if result.is_err() {
+            // Otherwise the parts already sent keep taking up space
+            if let Err(e) = self.send(S3Request::new("DELETE", path.to_string()).query("uploadId", upload_id)) {
+                log::warn!("Could not abort the upload of {}: {}", path, e);
+            }
+        }
+        result
+    }
+
+    /// Send each part of `local_path`, returning their ETags in order
+    fn upload_parts(&self, local_path: &Path, path: &str, upload_id: &str) -> Result<Vec<String>, TransferError> {
+        let mut file = File::open(local_path).map_err(|e| io_error(local_path, e))?;
+        let mut buffer = vec![0u8; PART_SIZE];
+        let mut etags = Vec::new();
+
+        loop {
+            let filled = read_full(&mut file, &mut buffer).map_err(|e| io_error(local_path, e))?;
+            if filled == 0 {
+                return Ok(etags);
+            }
+            let part_number = etags.len() + 1;
+            let response = self.send(
+                S3Request::new("PUT", path.to_string())
+                    .query("partNumber", part_number.to_string())
+                    .query("uploadId", upload_id)
+                    .body(&buffer[..filled])
+            )?;
+            let etag = response.header("ETag").map(str::to_string).ok_or_else(|| {
+                TransferError::TransferFailed(format!("No ETag for part {} of {}", part_number, path))
+            })?;
+            etags.push(etag);
+        }
+    }
+}
+
+impl TransferMethod for S3Transfer {
+    fn upload_file(
+        &self,
+        local_path: &Path,
+        remote_path: &Path
+    ) -> Result<(), TransferError> {
+        let path = object_path(remote_path)?;
+        let size = fs::metadata(local_path).map_err(|e| io_error(local_path, e))?.len();
+
+        if size >= MULTIPART_THRESHOLD {
+            return self.upload_multipart(local_path, &path, size);
+        }
+        let bytes = fs::read(local_path).map_err(|e| io_error(local_path, e))?;
+        self.send(S3Request::new("PUT", path).body(&bytes))?;
+        Ok(())
+    }
+
+    fn download_file(
+        &self,
+        remote_path: &Path,
+        local_path: &Path
+    ) -> Result<(), TransferError> {
+        let path = object_path(remote_path)?;
+        let response = self.send(S3Request::new("GET", path))?;
+
+        let mut file = File::create(local_path).map_err(|e| io_error(local_path, e))?;
+        if let Err(e) = io::copy(&mut response.into_reader(), &mut file) {
+            drop(file);
+            let _ = fs::remove_file(local_path);
+            return Err(TransferError::ConnectionFailed(format!("{}: {}", remote_path.display(), e)));
+        }
+        Ok(())
+    }
+
+    fn list_files(
+        &self,
+        remote_dir: &Path
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        let mut entries = Vec::new();
+        self.list_files_streaming(remote_dir, READ_TIMEOUT, &AtomicBool::new(false), &mut |chunk| {
+            entries.extend(chunk.into_iter().map(|entry| (entry.name, entry.is_dir)));
+        })?;
+        Ok(entries)
+    }
+
+    fn list_files_streaming(
+        &self,
+        remote_dir: &Path,
+        _timeout: Duration,
+        _cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        match split_path(remote_dir) {
+            None => {
+                on_chunk(self.list_buckets()?);
+                Ok(())
+            },
+            Some((bucket, key)) => {
+                let prefix = if key.is_empty() { key } else { format!("{}/", key) };
+                self.list_objects(&bucket, &prefix, on_chunk)
+            },
+        }
+    }
+
+    fn get_name(&self) -> &str {
+        "S3 Transfer"
+    }
+
+    fn get_description(&self) -> String {
+        format!("S3 storage at {}://{} as {}", self.scheme(), self.host_header(), self.access_key)
+    }
+
+    fn connection_key(&self) -> String {
+        format!("s3://{}@{}:{}", self.access_key, self.hostname, self.port)
+    }
+
+    fn max_concurrent(&self) -> usize {
+        self.transfer_options.concurrency.max(1)
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.transfer_options.retry.clone()
+    }
+
+    fn endpoint(&self) -> Option<(String, u16)> {
+        Some((self.hostname.clone(), self.port))
+    }
+
+    // There is no shell; listing the buckets proves the keys work
+    fn test_connection(&self, _probe: Option<&str>) -> ConnectionDiagnostics {
+        let mut diagnostics = ConnectionDiagnostics::default();
+        if !check_network(self, &mut diagnostics) {
+            return diagnostics;
+        }
+        match self.list_buckets() {
+            Ok(buckets) => {
+                let detail = format!("Signed in as {}; {} buckets", self.access_key, buckets.len());
+                diagnostics.record(DiagnosticStage::Auth, StageStatus::Passed, detail);
+            },
+            Err(e) => diagnostics.record(DiagnosticStage::Auth, StageStatus::Failed, e.to_string()),
+        }
+        diagnostics
+    }
+
+    fn clone_box(&self) -> Box<dyn TransferMethod> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn set_password(&mut self, password: &str) {
+        self.secret_key = Some(password.to_string());
+    }
+}
+
+pub struct S3TransferFactory {
+    hostname: String,
+    port: u16,
+    access_key: String,
+    transfer_options: TransferOptions,
+}
+
+impl S3TransferFactory {
+    pub fn new(hostname: String, port: u16, access_key: String) -> Self {
+        Self { hostname, port, access_key, transfer_options: TransferOptions::default() }
+    }
+
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
+}
+
+impl TransferMethodFactory for S3TransferFactory {
+    fn create_method(&self) -> Box<dyn TransferMethod> {
+        let mut method = S3Transfer::new(self.hostname.clone(), self.port, self.access_key.clone());
+        method.set_transfer_options(self.transfer_options.clone());
+        Box::new(method)
+    }
+
+    fn get_name(&self) -> String {
+        format!("S3 at {} as {}", self.hostname, self.access_key)
+    }
+}
+
+/// Bucket and key of `path`; None for `/`, the list of buckets
+fn split_path(path: &Path) -> Option<(String, String)> {
+    let text = path.to_string_lossy().replace('\\', "/");
+    let mut parts = text.split('/').filter(|part| !part.is_empty() && *part != ".");
+    let bucket = parts.next()?.to_string();
+    Some((bucket, parts.collect::<Vec<_>>().join("/")))
+}
+
+/// Request path of the object at `remote_path`
+fn object_path(remote_path: &Path) -> Result<String, TransferError> {
+    match split_path(remote_path) {
+        Some((bucket, key)) if !key.is_empty() => {
+            Ok(format!("/{}/{}", uri_encode(&bucket, false), uri_encode(&key, true)))
+        },
+        _ => Err(TransferError::TransferFailed(format!(
+            "{} is not a file inside a bucket",
+            remote_path.display()
+        ))),
+    }
+}
+
+/// Region named by AWS endpoints such as `s3.eu-west-1.amazonaws.com`
+fn region_of(hostname: &str) -> String {
+    hostname
+        .strip_suffix(".amazonaws.com")
+        .and_then(|rest| rest.rsplit('.').next())
+        .filter(|region| *region != "s3")
+        .map(|region| region.trim_start_matches("s3-").to_string())
+        .unwrap_or_else(|| DEFAULT_REGION.to_string())
+}
+
+/// Percent-encode everything but the characters SigV4 leaves alone
+fn uri_encode(text: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn read_body(response: ureq::Response) -> Result<String, TransferError> {
+    response
+        .into_string()
+        .map_err(|e| TransferError::ConnectionFailed(format!("Reading the answer failed: {}", e)))
+}
+
+/// Text of the first `<name>` element of `xml`
+fn tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    tags(xml, name).into_iter().next()
+}
+
+/// Text of every `<name>` element of `xml`; S3's answers are simple enough
+/// not to need a real XML parser
+fn tags<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", name);
+    let close = format!("</{}>", name);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let Some(end) = after.find(&close) else { break };
+        found.push(&after[..end]);
+        rest = &after[end + close.len()..];
+    }
+    found
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&amp;", "&")
+}
+
+fn parse_time(text: &str) -> Option<i64> {
+    DateTime::parse_from_rfc3339(text).ok().map(|time| time.timestamp())
+}
+
+/// Fill `buffer` unless the file ends first; returns the bytes read
+fn read_full(file: &mut File, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match file.read(&mut buffer[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+fn io_error(path: &Path, e: io::Error) -> TransferError {
+    match e.kind() {
+        io::ErrorKind::NotFound => TransferError::FileNotFound(path.display().to_string()),
+        io::ErrorKind::PermissionDenied => TransferError::PermissionDenied(path.display().to_string()),
+        _ => TransferError::TransferFailed(format!("{}: {}", path.display(), e)),
+    }
+}
@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use crate::transfer::method::TransferMethod;
+
+/// One open connection to a device
+pub struct Session {
+    /// Stable identifier, unique for the lifetime of the manager
+    pub id: usize,
+    /// Display name, usually the saved host name
+    pub name: String,
+    pub hostname: String,
+    pub username: String,
+    pub password: Option<String>,
+    /// Connection used for listing and transfers
+    pub method: Box<dyn TransferMethod>,
+    /// Directory last shown for this device in the remote pane
+    pub current_dir: PathBuf,
+}
+
+impl Session {
+    /// Label used in the device selector
+    pub fn label(&self) -> String {
+        format!("{} ({}@{})", self.name, self.username, self.hostname)
+    }
+}
+
+/// Holds every concurrent connection and tracks which one the remote pane shows
+#[derive(Default)]
+pub struct SessionManager {
+    sessions: Vec<Session>,
+    active: Option<usize>,
+    next_id: usize,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a connection and make it active
+    ///
+    /// Connecting again to the same user and host replaces the old session
+    /// instead of creating a duplicate entry.
+    pub fn add(
+        &mut self,
+        name: &str,
+        hostname: &str,
+        username: &str,
+        password: Option<String>,
+        method: Box<dyn TransferMethod>,
+        current_dir: PathBuf
+    ) -> usize {
+        if let Some(existing) = self.sessions
+            .iter_mut()
+            .find(|s| s.hostname == hostname && s.username == username)
+        {
+            existing.name = name.to_string();
+            existing.password = password;
+            existing.method = method;
+            existing.current_dir = current_dir;
+            let id = existing.id;
+            self.active = Some(id);
+            return id;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.sessions.push(Session {
+            id,
+            name: name.to_string(),
+            hostname: hostname.to_string(),
+            username: username.to_string(),
+            password,
+            method,
+            current_dir,
+        });
+        self.active = Some(id);
+
+        id
+    }
+
+    /// Close a connection; the first remaining session becomes active
+    pub fn remove(&mut self, id: usize) -> Option<Session> {
+        let index = self.sessions.iter().position(|s| s.id == id)?;
+        let session = self.sessions.remove(index);
+
+        if self.active == Some(id) {
+            self.active = self.sessions.first().map(|s| s.id);
+        }
+
+        Some(session)
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Session> {
+        self.sessions.iter().find(|s| s.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Session> {
+        self.sessions.iter_mut().find(|s| s.id == id)
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    pub fn active_id(&self) -> Option<usize> {
+        self.active
+    }
+
+    pub fn active(&self) -> Option<&Session> {
+        self.active.and_then(|id| self.get(id))
+    }
+
+    /// Switch the active session; returns false for an unknown id
+    pub fn set_active(&mut self, id: usize) -> bool {
+        if self.get(id).is_some() {
+            self.active = Some(id);
+            true
+        } else {
+            false
+        }
+    }
+}
@@ -1,9 +1,20 @@
+use std::ffi::OsString;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::fs::{self, File};
+use std::process::{Child, Command, Stdio};
 use std::io::{self, Write};
 use std::any::Any;
 
-use crate::transfer::method::{TransferMethod, TransferError, TransferMethodFactory};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::config::{RetryPolicy, TransferOptions, TransferProtocol};
+use crate::transfer::agent::{self, agent_available};
+use crate::transfer::listing_cache;
+use crate::transfer::method::{TransferMethod, TransferError, TransferMethodFactory, CommandOutput, RemoteEntry, failure_from_stderr, os_string_from_bytes, SSH_FAILURE_EXIT, wait_with_deadline, stream_records_with_deadline};
+
+// Entries handed to the browser per batch while a listing streams in
+const LISTING_CHUNK_SIZE: usize = 500;
 
 pub struct SSHTransfer {
     hostname: String,
@@ -12,6 +23,11 @@ pub struct SSHTransfer {
     use_key_auth: bool,
     key_path: Option<PathBuf>,
     password: Option<String>,
+    // Jump host(s) passed to ssh as ProxyJump
+    proxy_jump: Option<String>,
+    transfer_options: TransferOptions,
+    // Run listings and downloads as root through sudo
+    sudo: bool,
 }
 
 impl SSHTransfer {
@@ -29,6 +45,9 @@ impl SSHTransfer {
             use_key_auth,
             key_path,
             password: None,
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
+            sudo: false,
         }
     }
     
@@ -45,6 +64,9 @@ impl SSHTransfer {
             use_key_auth: false,
             key_path: None,
             password: Some(password),
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
+            sudo: false,
         }
     }
     
@@ -52,6 +74,70 @@ impl SSHTransfer {
         self.password = Some(password);
     }
     
+    pub fn set_proxy_jump(&mut self, proxy_jump: Option<String>) {
+        self.proxy_jump = proxy_jump;
+    }
+    
+    // Compression, protocol and concurrency chosen for the host
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
+    
+    // `command` run as root: sudo reads the password from stdin when there
+    // is one, and must not need one otherwise
+    fn sudo_command(&self, command: &str) -> String {
+        match self.password {
+            Some(_) => format!("sudo -S -p '' sh -c {}", shell_quote(command)),
+            None => format!("sudo -n sh -c {}", shell_quote(command)),
+        }
+    }
+    
+    // Give sudo the password on the child's stdin, then close it
+    fn feed_sudo_password(&self, child: &mut Child) {
+        if let (Some(password), Some(mut stdin)) = (self.password.as_ref(), child.stdin.take()) {
+            if let Err(e) = writeln!(stdin, "{}", password) {
+                log::warn!("Could not pass the password to sudo: {}", e);
+            }
+        }
+    }
+    
+    // Stdin for a remote command: piped when sudo needs the password
+    fn remote_stdin(&self) -> Stdio {
+        if self.sudo && self.password.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        }
+    }
+    
+    // scp can't elevate, so read the file with `sudo cat` instead
+    fn download_with_sudo(&self, remote_path: &Path, local_path: &Path) -> Result<(), TransferError> {
+        let mut cmd = self.ssh_command()?;
+        cmd.arg(self.sudo_command(&format!("cat -- {}", shell_quote_path(remote_path))));
+        
+        let file = File::create(local_path).map_err(|e| {
+            TransferError::TransferFailed(format!("{}: {}", local_path.display(), e))
+        })?;
+        cmd.stdin(self.remote_stdin());
+        cmd.stdout(file);
+        cmd.stderr(Stdio::piped());
+        
+        log::info!("Downloading {} with sudo", remote_path.display());
+        let mut child = cmd.spawn().map_err(|e| {
+            TransferError::TransferFailed(format!("Failed to start ssh: {}", e))
+        })?;
+        self.feed_sudo_password(&mut child);
+        let output = child.wait_with_output().map_err(|e| {
+            TransferError::TransferFailed(format!("Failed to wait for sudo cat: {}", e))
+        })?;
+        
+        if !output.status.success() {
+            let _ = fs::remove_file(local_path);
+            return Err(failure_from_stderr(&String::from_utf8_lossy(&output.stderr)));
+        }
+        Ok(())
+    }
+    
     // Debug function to help troubleshoot commands
     fn debug_command(&self, cmd: &mut Command, command_name: &str) -> Result<std::process::Output, TransferError> {
         // Print the command that's about to be executed (sanitize password for security)
@@ -59,27 +145,84 @@ impl SSHTransfer {
         if let Some(ref password) = self.password {
             cmd_str = cmd_str.replace(password, "********");
         }
-        println!("Executing {}: {}", command_name, cmd_str);
+        log::debug!("Executing {}: {}", command_name, cmd_str);
         
         let output = cmd.output().map_err(|e| {
             TransferError::TransferFailed(format!("Failed to execute {}: {}", command_name, e))
         })?;
         
         // Print output status and contents
-        println!("Command status: {}", output.status);
-        println!("STDOUT: {}", String::from_utf8_lossy(&output.stdout));
-        println!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
+        log::debug!("Command status: {}", output.status);
+        log::debug!("STDOUT: {}", String::from_utf8_lossy(&output.stdout));
+        log::debug!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
         
         if !output.status.success() {
-            return Err(TransferError::TransferFailed(
-                String::from_utf8_lossy(&output.stderr).to_string()
-            ));
+            return Err(failure_from_stderr(&String::from_utf8_lossy(&output.stderr)));
         }
         
         Ok(output)
     }
     
+    // Build an ssh command to the remote host with authentication and port
+    // options applied; the remote command is appended by the caller
+    fn ssh_command(&self) -> Result<Command, TransferError> {
+        // Create a mutable copy for potential password prompt
+        let mut self_copy = self.clone();
+        self_copy.ensure_password()?;
+        
+        // Choose command based on authentication method
+        let mut cmd;
+        
+        if !self.use_key_auth {
+            // For password auth, use sshpass
+            if let Some(ref password) = self_copy.password {
+                cmd = Command::new("sshpass");
+                cmd.arg("-p").arg(password);
+                cmd.arg("ssh");
+            } else {
+                return Err(TransferError::TransferFailed(
+                    "Password required for password authentication".to_string()
+                ));
+            }
+        } else {
+            // For key auth, use ssh directly
+            cmd = Command::new("ssh");
+        }
+        
+        // Add options
+        cmd.arg("-p").arg(self.port.to_string());
+        if let Some(jump) = &self.proxy_jump {
+            cmd.arg("-o").arg(format!("ProxyJump={}", jump));
+        }
+        
+        // Add key if using key authentication
+        if self.use_key_auth {
+            if let Some(key_path) = &self.key_path {
+                cmd.arg("-i").arg(key_path);
+            }
+        }
+        
+        // Add remote username and host
+        cmd.arg(format!("{}@{}", self.username, self.hostname));
+        
+        Ok(cmd)
+    }
+    
     // Get password from user interactively if needed
+    // Map the exit code of a remote listing to the matching error
+    fn check_listing_exit(&self, output: &CommandOutput) -> Result<(), TransferError> {
+        match output.exit_code {
+            Some(0) => Ok(()),
+            // ssh reports its own failures (unreachable host, refused, ...) as 255
+            Some(SSH_FAILURE_EXIT) => Err(TransferError::ConnectionFailed(output.stderr.trim().to_string())),
+            // sshpass exits with 5 when the password is rejected
+            Some(5) if !self.use_key_auth => {
+                Err(TransferError::AuthenticationFailed(output.stderr.trim().to_string()))
+            },
+            _ => Err(failure_from_stderr(&output.stderr)),
+        }
+    }
+    
     fn ensure_password(&mut self) -> Result<(), TransferError> {
         if !self.use_key_auth && self.password.is_none() {
             // In a GUI app, this should be replaced with a proper password dialog
@@ -130,6 +273,15 @@ impl TransferMethod for SSHTransfer {
         
         // Add options
         cmd.arg("-P").arg(self.port.to_string());
+        if let Some(jump) = &self.proxy_jump {
+            cmd.arg("-o").arg(format!("ProxyJump={}", jump));
+        }
+        if self.transfer_options.compression == Some(true) {
+            cmd.arg("-C");
+        }
+        if self.transfer_options.protocol == TransferProtocol::Sftp {
+            cmd.arg("-s");
+        }
         
         // Add key if using key authentication
         if self.use_key_auth {
@@ -141,17 +293,16 @@ impl TransferMethod for SSHTransfer {
         // Add source and destination
         cmd.arg(local_path);
         
-        let remote = format!(
-            "{}@{}:{}",
-            self.username,
-            self.hostname,
-            remote_path.to_string_lossy()
-        );
-        cmd.arg(remote);
+        cmd.arg(remote_arg(&self.username, &self.hostname, remote_path));
         
         // Use debug command
         self_copy.debug_command(&mut cmd, "scp upload")?;
         
+        // The target may be the new file or the directory it was copied into
+        let key = self.connection_key();
+        listing_cache::invalidate(&key, remote_path);
+        listing_cache::invalidate_parent(&key, remote_path);
+        
         Ok(())
     }
     
@@ -160,6 +311,10 @@ impl TransferMethod for SSHTransfer {
         remote_path: &Path,
         local_path: &Path
     ) -> Result<(), TransferError> {
+        if self.sudo {
+            return self.download_with_sudo(remote_path, local_path);
+        }
+        
         // Create a mutable copy for potential password prompt
         let mut self_copy = self.clone();
         self_copy.ensure_password()?;
@@ -185,6 +340,15 @@ impl TransferMethod for SSHTransfer {
         
         // Add options
         cmd.arg("-P").arg(self.port.to_string());
+        if let Some(jump) = &self.proxy_jump {
+            cmd.arg("-o").arg(format!("ProxyJump={}", jump));
+        }
+        if self.transfer_options.compression == Some(true) {
+            cmd.arg("-C");
+        }
+        if self.transfer_options.protocol == TransferProtocol::Sftp {
+            cmd.arg("-s");
+        }
         
         // Add key if using key authentication
         if self.use_key_auth {
@@ -194,13 +358,7 @@ impl TransferMethod for SSHTransfer {
         }
         
         // Add source and destination
-        let remote = format!(
-            "{}@{}:{}",
-            self.username,
-            self.hostname,
-            remote_path.to_string_lossy()
-        );
-        cmd.arg(remote);
+        cmd.arg(remote_arg(&self.username, &self.hostname, remote_path));
         cmd.arg(local_path);
         
         // Use debug command
@@ -212,100 +370,131 @@ impl TransferMethod for SSHTransfer {
     fn list_files(
         &self,
         remote_dir: &Path
-    ) -> Result<Vec<(String, bool)>, TransferError> {
-        // Create a mutable copy for potential password prompt
-        let mut self_copy = self.clone();
-        self_copy.ensure_password()?;
-        
-        // Choose command based on authentication method
-        let mut cmd;
-        
-        if !self.use_key_auth {
-            // For password auth, use sshpass
-            if let Some(ref password) = self_copy.password {
-                cmd = Command::new("sshpass");
-                cmd.arg("-p").arg(password);
-                cmd.arg("ssh");
-            } else {
-                return Err(TransferError::TransferFailed(
-                    "Password required for password authentication".to_string()
-                ));
-            }
-        } else {
-            // For key auth, use ssh directly
-            cmd = Command::new("ssh");
-        }
-        
-        // Add options
-        cmd.arg("-p").arg(self.port.to_string());
-        
-        // Add key if using key authentication
-        if self.use_key_auth {
-            if let Some(key_path) = &self.key_path {
-                cmd.arg("-i").arg(key_path);
-            }
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        // The password for sudo goes in on stdin
+        if self.sudo {
+            let output = self.exec(&list_command(remote_dir))?;
+            self.check_listing_exit(&output)?;
+            return Ok(parse_listing(&output.stdout_bytes));
         }
         
-        // Add remote username and host
-        let remote_user_host = format!("{}@{}", self.username, self.hostname);
-        cmd.arg(remote_user_host);
+        let mut cmd = self.ssh_command()?;
         
-        // Command to list files with format: name,is_dir
-        let ls_cmd = format!("ls -la {}", remote_dir.to_string_lossy());
-        cmd.arg(ls_cmd);
+        cmd.arg(list_command(remote_dir));
         
-        println!("Executing SSH list files command: {:?}", cmd);
+        log::debug!("Executing SSH list files command: {:?}", cmd);
         
         // Execute command
         let output = cmd.output().map_err(|e| {
-            TransferError::TransferFailed(format!("Failed to execute ssh/ls: {}", e))
+            TransferError::TransferFailed(format!("Failed to execute ssh/find: {}", e))
         })?;
         
         // Debug output
-        println!("Command status: {}", output.status);
+        log::debug!("Command status: {}", output.status);
         if !output.stdout.is_empty() {
-            println!("STDOUT first 100 bytes: {:?}", 
+            log::debug!("STDOUT first 100 bytes: {:?}", 
                 String::from_utf8_lossy(&output.stdout[..std::cmp::min(100, output.stdout.len())]));
         } else {
-            println!("STDOUT is empty");
+            log::debug!("STDOUT is empty");
         }
         
         if !output.stderr.is_empty() {
-            println!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
+            log::debug!("STDERR: {}", String::from_utf8_lossy(&output.stderr));
         }
         
         if !output.status.success() {
-            return Err(TransferError::TransferFailed(
-                String::from_utf8_lossy(&output.stderr).to_string()
-            ));
-        }
-        
-        // Parse output
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        let mut files = Vec::new();
-        
-        println!("Parsing output lines: {}", output_str.lines().count());
-        
-        // More robust parsing for ls -la output
-        for line in output_str.lines().skip(1) { // Skip the first line (total)
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() >= 9 {
-                let file_type = parts[0].chars().next().unwrap_or('-');
-                let is_dir = file_type == 'd';
-                let name = parts[8].to_string();
-                
-                // Skip . and .. directories
-                if name != "." && name != ".." {
-                    println!("Found file: {} (is_dir: {})", name, is_dir);
-                    files.push((name, is_dir));
+            return Err(failure_from_stderr(&String::from_utf8_lossy(&output.stderr)));
+        }
+        
+        let files = parse_listing(&output.stdout);
+        
+        log::info!("Returning {} files", files.len());
+        Ok(files)
+    }
+    
+    fn list_files_with_timeout(
+        &self,
+        remote_dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool
+    ) -> Result<Vec<(OsString, bool)>, TransferError> {
+        let child = self.spawn_remote(&list_command(remote_dir))?;
+        let output = wait_with_deadline(child, timeout, cancel)?;
+        self.check_listing_exit(&output)?;
+        
+        let files = parse_listing(&output.stdout_bytes);
+        log::info!("Listed {} files in {}", files.len(), remote_dir.display());
+        Ok(files)
+    }
+    
+    fn list_files_streaming(
+        &self,
+        remote_dir: &Path,
+        timeout: Duration,
+        cancel: &AtomicBool,
+        on_chunk: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        // The agent reads the directory itself instead of running find; it
+        // runs as the login user, so not when listing with sudo
+        if !self.sudo && agent_available(self) {
+            let mut delivered = false;
+            let result = agent::list_directory(self, remote_dir, LISTING_CHUNK_SIZE, timeout, cancel, &mut |chunk| {
+                delivered = true;
+                on_chunk(chunk);
+            });
+            match result {
+                Ok(()) => return Ok(()),
+                Err(e @ (TransferError::Cancelled | TransferError::TimedOut(_))) => return Err(e),
+                // Entries already shown can't be taken back
+                Err(e) if delivered => return Err(e),
+                Err(e) => log::warn!("Agent listing of {} failed, using find: {}", remote_dir.display(), e),
+            }
+        }
+        
+        let child = self.spawn_remote(&list_command(remote_dir))?;
+        
+        let mut chunk = Vec::new();
+        let mut listed = 0;
+        let output = stream_records_with_deadline(child, b'\0', timeout, cancel, &mut |record| {
+            if let Some(entry) = parse_listing_record(record) {
+                chunk.push(entry);
+                if chunk.len() == LISTING_CHUNK_SIZE {
+                    listed += chunk.len();
+                    on_chunk(std::mem::take(&mut chunk));
                 }
-            } else {
-                println!("Couldn't parse line: {}", line);
             }
+        })?;
+        
+        if !chunk.is_empty() {
+            listed += chunk.len();
+            on_chunk(chunk);
         }
+        self.check_listing_exit(&output)?;
         
-        println!("Returning {} files", files.len());
-        Ok(files)
+        log::info!("Listed {} files in {}", listed, remote_dir.display());
+        Ok(())
+    }
+    
+    fn spawn_remote(&self, command: &str) -> Result<Child, TransferError> {
+        let mut cmd = self.ssh_command()?;
+        if self.sudo {
+            cmd.arg(self.sudo_command(command));
+        } else {
+            cmd.arg(command);
+        }
+        cmd.stdin(self.remote_stdin());
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        
+        log::info!("Spawning remote command on {}{}: {}", self.hostname, if self.sudo { " with sudo" } else { "" }, command);
+        
+        let mut child = cmd.spawn().map_err(|e| {
+            TransferError::TransferFailed(format!("Failed to start ssh: {}", e))
+        })?;
+        if self.sudo {
+            self.feed_sudo_password(&mut child);
+        }
+        Ok(child)
     }
     
     fn get_name(&self) -> &str {
@@ -313,7 +502,40 @@ impl TransferMethod for SSHTransfer {
     }
     
     fn get_description(&self) -> String {
-        format!("SSH/SCP transfer to {}@{}", self.username, self.hostname)
+        format!(
+            "SSH/SCP transfer to {}@{}{}",
+            self.username,
+            self.hostname,
+            if self.sudo { " with sudo" } else { "" }
+        )
+    }
+    
+    fn connection_key(&self) -> String {
+        format!("{}@{}:{}", self.username, self.hostname, self.port)
+    }
+    
+    fn max_concurrent(&self) -> usize {
+        self.transfer_options.concurrency.max(1)
+    }
+    
+    fn retry_policy(&self) -> RetryPolicy {
+        self.transfer_options.retry.clone()
+    }
+    
+    fn prefers_agent(&self) -> bool {
+        self.transfer_options.use_agent
+    }
+    
+    // Behind a jump host the address may only resolve from the jump host,
+    // so there is no endpoint to check directly
+    fn endpoint(&self) -> Option<(String, u16)> {
+        match self.proxy_jump {
+            Some(_) => None,
+            None => Some((self.hostname.clone(), self.port)),
+        }
+    }
+    fn clone_box(&self) -> Box<dyn TransferMethod> {
+        Box::new(self.clone())
     }
     fn as_any(&mut self) -> &mut dyn Any {
         self
@@ -321,6 +543,18 @@ impl TransferMethod for SSHTransfer {
     fn set_password(&mut self, password: &str) {
         self.password = Some(password.to_string());
     }
+    // Only archives can keep links; scp always copies what they point to
+    fn preserves_symlinks(&self) -> bool {
+        self.transfer_options.preserve_symlinks
+    }
+    fn sudo_method(&self) -> Option<Box<dyn TransferMethod>> {
+        if !self.transfer_options.sudo_fallback || self.sudo {
+            return None;
+        }
+        let mut method = self.clone();
+        method.sudo = true;
+        Some(Box::new(method))
+    }
 }
 
 // Make SSHTransfer cloneable for password handling
@@ -333,6 +567,9 @@ impl Clone for SSHTransfer {
             use_key_auth: self.use_key_auth,
             key_path: self.key_path.clone(),
             password: self.password.clone(),
+            proxy_jump: self.proxy_jump.clone(),
+            transfer_options: self.transfer_options.clone(),
+            sudo: self.sudo,
         }
     }
 }
@@ -344,6 +581,9 @@ pub struct SSHTransferFactory {
     use_key_auth: bool,
     key_path: Option<PathBuf>,
     password: Option<String>,
+    // Jump host(s) passed to ssh as ProxyJump
+    proxy_jump: Option<String>,
+    transfer_options: TransferOptions,
 }
 
 impl SSHTransferFactory {
@@ -361,6 +601,8 @@ impl SSHTransferFactory {
             use_key_auth,
             key_path: key_path.map(PathBuf::from),
             password: None,
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
@@ -377,12 +619,23 @@ impl SSHTransferFactory {
             use_key_auth: false,
             key_path: None,
             password: Some(password),
+            proxy_jump: None,
+            transfer_options: TransferOptions::default(),
         }
     }
     
     pub fn set_password(&mut self, password: String) {
         self.password = Some(password);
     }
+    
+    pub fn set_proxy_jump(&mut self, proxy_jump: Option<String>) {
+        self.proxy_jump = proxy_jump;
+    }
+    
+    // Compression, protocol and concurrency chosen for the host
+    pub fn set_transfer_options(&mut self, transfer_options: TransferOptions) {
+        self.transfer_options = transfer_options;
+    }
 }
 
 impl TransferMethodFactory for SSHTransferFactory {
@@ -399,6 +652,8 @@ impl TransferMethodFactory for SSHTransferFactory {
         if let Some(ref password) = self.password {
             transfer.set_password(password.clone());
         }
+        transfer.set_proxy_jump(self.proxy_jump.clone());
+        transfer.set_transfer_options(self.transfer_options.clone());
         
         Box::new(transfer)
     }
@@ -408,6 +663,104 @@ impl TransferMethodFactory for SSHTransferFactory {
     }
 }
 
+/// Format of one listed entry for `find -printf`: the entry's type, the
+/// type of a link's target, size, modification time, name and link target,
+/// separated by slashes, which names can't contain; the link target comes
+/// last since it may. Records end in NUL, as names may contain newlines.
+const LIST_FORMAT: &str = "%y/%Y/%s/%T@/%f/%l\\0";
+
+// Remote command listing the entries of a directory, hidden ones included,
+// in LIST_FORMAT; -H lists a linked directory rather than the link
+fn list_command(remote_dir: &Path) -> String {
+    format!(
+        "find -H {} -mindepth 1 -maxdepth 1 -printf {}",
+        shell_quote_path(remote_dir),
+        shell_quote(LIST_FORMAT)
+    )
+}
+
+// Parse the output of list_command into (name, is_dir) pairs
+fn parse_listing(output: &[u8]) -> Vec<(OsString, bool)> {
+    output
+        .split(|&byte| byte == b'\0')
+        .filter_map(parse_listing_record)
+        .map(|entry| (entry.name, entry.is_dir))
+        .collect()
+}
+
+// Parse one LIST_FORMAT record; None for the empty record after the last
+// NUL and anything unparsable. The name is kept as raw bytes.
+fn parse_listing_record(record: &[u8]) -> Option<RemoteEntry> {
+    if record.is_empty() {
+        return None;
+    }
+    let mut fields = record.splitn(6, |&byte| byte == b'/');
+    let (Some(kind), Some(target_kind), Some(size), Some(modified), Some(name), Some(target)) = (
+        fields.next(), fields.next(), fields.next(), fields.next(), fields.next(), fields.next()
+    ) else {
+        log::debug!("Couldn't parse listing record: {:?}", String::from_utf8_lossy(record));
+        return None;
+    };
+    if name.is_empty() {
+        return None;
+    }
+    
+    let link_target = (kind == b"l").then(|| String::from_utf8_lossy(target).to_string());
+    // A link to a directory is browsed like one
+    let is_dir = kind == b"d" || (link_target.is_some() && target_kind == b"d");
+    
+    log::debug!("Found file: {} (is_dir: {}, link: {:?})", String::from_utf8_lossy(name), is_dir, link_target);
+    Some(RemoteEntry {
+        name: os_string_from_bytes(name.to_vec()),
+        is_dir,
+        size: parse_field(size).unwrap_or(0),
+        // Seconds with a fraction
+        modified: modified.split(|&byte| byte == b'.').next().and_then(parse_field),
+        link_target,
+    })
+}
+
+// Number in a listing record field
+fn parse_field<T: std::str::FromStr>(field: &[u8]) -> Option<T> {
+    std::str::from_utf8(field).ok()?.parse().ok()
+}
+
+/// Quote a string for safe use as a single argument in a remote POSIX shell
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Quote a path like [`shell_quote`]; bytes that aren't UTF-8 can't go into
+/// the command string as they are, so the remote shell rebuilds them with
+/// `printf` octal escapes
+pub fn shell_quote_path(path: &Path) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let mut quoted = String::new();
+        for chunk in path.as_os_str().as_bytes().utf8_chunks() {
+            if !chunk.valid().is_empty() {
+                quoted.push_str(&shell_quote(chunk.valid()));
+            }
+            for byte in chunk.invalid() {
+                quoted.push_str(&format!("\"$(printf '\\{:03o}')\"", byte));
+            }
+        }
+        if quoted.is_empty() { shell_quote("") } else { quoted }
+    }
+    #[cfg(not(unix))]
+    {
+        shell_quote(&path.to_string_lossy())
+    }
+}
+
+// `user@host:path` argument for scp and rsync, keeping the path's raw bytes
+pub(crate) fn remote_arg(username: &str, hostname: &str, remote_path: &Path) -> OsString {
+    let mut remote = OsString::from(format!("{}@{}:", username, hostname));
+    remote.push(remote_path.as_os_str());
+    remote
+}
+
 // Compatibility module to match the original import path
 pub mod ssh {
     pub use super::*;
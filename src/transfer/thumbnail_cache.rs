@@ -0,0 +1,300 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use base64::Engine;
+use directories::ProjectDirs;
+use sha2::{Digest, Sha256};
+
+use crate::transfer::agent::{agent_available, run_agent, AgentMessage, AgentRequest};
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::ssh::shell_quote_path;
+
+/// Largest side of a cached thumbnail in pixels
+pub const THUMBNAIL_SIZE: u32 = 240;
+
+/// Images shrunk per remote command, so a big folder never starts hundreds
+/// of conversions on the Pi at once
+const BATCH_SIZE: usize = 12;
+
+/// Pause between the remote commands of a prefetch, keeping well under
+/// sshd's `MaxStartups` and fail2ban's connection limits
+const BATCH_PAUSE: Duration = Duration::from_millis(500);
+
+/// How long the agent may go quiet while shrinking a batch
+const AGENT_STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Size the cache is trimmed to until `set_limit` is called
+const DEFAULT_LIMIT: u64 = 100 * 1024 * 1024;
+
+static LIMIT: AtomicU64 = AtomicU64::new(DEFAULT_LIMIT);
+
+/// Exit status of the ImageMagick command when `convert` is missing
+const NO_CONVERT_EXIT: i32 = 127;
+
+/// An image on the Pi; its size and modification time are part of the
+/// cache key, so a changed image gets a fresh thumbnail
+#[derive(Debug, Clone)]
+pub struct RemoteImage {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: Option<i64>,
+}
+
+struct Prefetch {
+    method: Option<Box<dyn TransferMethod>>,
+    queue: VecDeque<RemoteImage>,
+    running: bool,
+    // Cache keys already fetched or failed this session
+    requested: HashSet<String>,
+    // Connections whose agent predates ThumbnailFiles
+    old_agents: HashSet<String>,
+    // Connections with neither a capable agent nor ImageMagick
+    unsupported: HashSet<String>,
+}
+
+/// Queue and bookkeeping of the background prefetch
+fn state() -> &'static Mutex<Prefetch> {
+    static STATE: OnceLock<Mutex<Prefetch>> = OnceLock::new();
+    STATE.get_or_init(|| Mutex::new(Prefetch {
+        method: None,
+        queue: VecDeque::new(),
+        running: false,
+        requested: HashSet::new(),
+        old_agents: HashSet::new(),
+        unsupported: HashSet::new(),
+    }))
+}
+
+/// Folder holding the thumbnails, in the user's cache directory
+pub fn cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "PiImageProcessor", "piimgproc").map(|dirs| dirs.cache_dir().join("thumbnails"))
+}
+
+/// Cached thumbnail of `image` on the Pi behind `connection`, if fetched
+pub fn get(connection: &str, image: &RemoteImage) -> Option<PathBuf> {
+    let path = cache_dir()?.join(format!("{}.thumb", key(connection, image)));
+    path.is_file().then_some(path)
+}
+
+/// Fetch thumbnails of `images` into the cache in the background, a few at
+/// a time with pauses in between
+///
+/// Replaces whatever was still waiting, so only the rows currently in view
+/// are fetched. Images already cached or tried are skipped. Uses the
+/// companion agent when the Pi has it, ImageMagick's `convert` otherwise,
+/// and gives up quietly on Pis with neither.
+pub fn prefetch(method: Box<dyn TransferMethod>, images: Vec<RemoteImage>) {
+    let connection = method.connection_key();
+    let mut prefetch = state().lock().unwrap();
+    if prefetch.unsupported.contains(&connection) {
+        return;
+    }
+
+    let queue: VecDeque<RemoteImage> = images
+        .into_iter()
+        .filter(|image| !prefetch.requested.contains(&key(&connection, image)) && get(&connection, image).is_none())
+        .collect();
+    if queue.is_empty() {
+        return;
+    }
+    prefetch.queue = queue;
+    prefetch.method = Some(method);
+
+    if !prefetch.running {
+        prefetch.running = true;
+        thread::spawn(run_prefetch);
+    }
+}
+
+/// Work through the queue until it runs empty
+fn run_prefetch() {
+    loop {
+        let (method, batch) = {
+            let mut prefetch = state().lock().unwrap();
+            let method = match prefetch.method.as_ref() {
+                Some(method) if !prefetch.queue.is_empty() => method.clone_box(),
+                _ => {
+                    prefetch.running = false;
+                    return;
+                },
+            };
+            let count = prefetch.queue.len().min(BATCH_SIZE);
+            let batch: Vec<RemoteImage> = prefetch.queue.drain(..count).collect();
+            let connection = method.connection_key();
+            for image in &batch {
+                prefetch.requested.insert(key(&connection, image));
+            }
+            (method, batch)
+        };
+
+        if let Err(e) = fetch_batch(method.as_ref(), &batch) {
+            log::warn!("Prefetching thumbnails failed: {}", e);
+            let mut prefetch = state().lock().unwrap();
+            if matches!(e, TransferError::FileNotFound(_)) {
+                prefetch.unsupported.insert(method.connection_key());
+            }
+            prefetch.queue.clear();
+        }
+        thread::sleep(BATCH_PAUSE);
+    }
+}
+
+/// Shrink `batch` on the Pi and store what came back
+fn fetch_batch(method: &dyn TransferMethod, batch: &[RemoteImage]) -> Result<(), TransferError> {
+    let connection = method.connection_key();
+    let old_agent = state().lock().unwrap().old_agents.contains(&connection);
+    let encoded = if !old_agent && agent_available(method) {
+        match agent_thumbnails(method, batch) {
+            Ok(encoded) => encoded,
+            Err(e) => {
+                log::info!("Agent could not make thumbnails ({}), using ImageMagick", e);
+                state().lock().unwrap().old_agents.insert(connection.clone());
+                convert_thumbnails(method, batch)?
+            },
+        }
+    } else {
+        convert_thumbnails(method, batch)?
+    };
+
+    let Some(dir) = cache_dir() else { return Ok(()) };
+    fs::create_dir_all(&dir).map_err(|e| TransferError::TransferFailed(format!("{}: {}", dir.display(), e)))?;
+    for (image, line) in batch.iter().zip(encoded.lines()) {
+        let Ok(bytes) = base64::engine::general_purpose::STANDARD.decode(line.trim()) else { continue };
+        if bytes.is_empty() {
+            log::debug!("No thumbnail for {}", image.path.display());
+            continue;
+        }
+        let path = dir.join(format!("{}.thumb", key(&connection, image)));
+        if let Err(e) = fs::write(&path, bytes) {
+            log::warn!("Could not cache thumbnail {}: {}", path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Thumbnails made by the agent, one base64 line per image of `batch`,
+/// empty for images it could not read
+fn agent_thumbnails(method: &dyn TransferMethod, batch: &[RemoteImage]) -> Result<String, TransferError> {
+    let output_dir = PathBuf::from(format!("/tmp/pi_remote_manager-prefetch-{}", std::process::id()));
+    let request = AgentRequest::ThumbnailFiles {
+        files: batch.iter().map(|image| image.path.clone()).collect(),
+        size: THUMBNAIL_SIZE,
+        output_dir: output_dir.clone(),
+    };
+
+    let mut outputs = vec![None; batch.len()];
+    let result = run_agent(method, &request, AGENT_STALL_TIMEOUT, &AtomicBool::new(false), &mut |message| {
+        if let AgentMessage::Thumbnail { source, output } = message {
+            if let Some(index) = batch.iter().position(|image| image.path == source) {
+                outputs[index] = Some(output);
+            }
+        }
+    });
+    if let Err(e) = result {
+        let _ = method.exec(&format!("rm -rf {}", shell_quote_path(&output_dir)));
+        return Err(e);
+    }
+
+    // Read every thumbnail back in one command instead of one download each
+    let files: Vec<String> = outputs
+        .iter()
+        .map(|output| match output {
+            Some(output) => shell_quote_path(output),
+            None => "''".to_string(),
+        })
+        .collect();
+    let command = format!(
+        "for f in {}; do [ -n \"$f\" ] && base64 -w0 \"$f\" 2>/dev/null; echo; done; rm -rf {}",
+        files.join(" "),
+        shell_quote_path(&output_dir)
+    );
+    Ok(method.exec(&command)?.stdout)
+}
+
+/// Thumbnails made with ImageMagick, one base64 JPEG line per image of
+/// `batch`, empty for images it could not read
+fn convert_thumbnails(method: &dyn TransferMethod, batch: &[RemoteImage]) -> Result<String, TransferError> {
+    let files: Vec<String> = batch.iter().map(|image| shell_quote_path(&image.path)).collect();
+    // `jpeg:size` lets large JPEGs be decoded at a fraction of their size
+    let command = format!(
+        "command -v convert >/dev/null || exit {exit}; for f in {files}; do \
+         convert -define jpeg:size={hint}x{hint} \"$f[0]\" -auto-orient -thumbnail {size}x{size} jpg:- 2>/dev/null | base64 -w0; echo; done",
+        exit = NO_CONVERT_EXIT,
+        files = files.join(" "),
+        hint = THUMBNAIL_SIZE * 2,
+        size = THUMBNAIL_SIZE
+    );
+    let output = method.exec(&command)?;
+    if output.exit_code == Some(NO_CONVERT_EXIT) {
+        return Err(TransferError::FileNotFound(
+            "neither the companion agent nor ImageMagick is installed on the Pi".to_string()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+/// Each cached thumbnail with its size and modification time
+fn thumbnail_files() -> Vec<(PathBuf, u64, SystemTime)> {
+    let Some(dir) = cache_dir() else { return Vec::new() };
+    let Ok(entries) = fs::read_dir(&dir) else { return Vec::new() };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+        })
+        .collect()
+}
+
+/// Cache file name for `image`, from the connection, path, size and time
+fn key(connection: &str, image: &RemoteImage) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(connection.as_bytes());
+    hasher.update([0]);
+    hasher.update(image.path.to_string_lossy().as_bytes());
+    hasher.update([0]);
+    hasher.update(image.size.to_le_bytes());
+    hasher.update(image.modified.unwrap_or_default().to_le_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Keep the cache under `bytes`, dropping the oldest thumbnails
+pub fn set_limit(bytes: u64) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+/// Number of cached thumbnails and the bytes they take
+pub fn usage() -> (usize, u64) {
+    let files = thumbnail_files();
+    (files.len(), files.iter().map(|(_, size, _)| size).sum())
+}
+
+/// Delete every cached thumbnail; returns the bytes freed
+pub fn clear() -> u64 {
+    let (_, freed) = usage();
+    if let Some(dir) = cache_dir() {
+        let _ = fs::remove_dir_all(dir);
+    }
+    freed
+}
+
+/// Delete the oldest thumbnails until the cache fits the limit
+pub fn trim() {
+    let limit = LIMIT.load(Ordering::Relaxed);
+    let mut files = thumbnail_files();
+    let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+    for (path, size, _) in files {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+}
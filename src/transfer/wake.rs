@@ -0,0 +1,79 @@
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Host;
+use crate::transfer::method::TransferError;
+use crate::transfer::profile::reachable_host;
+
+/// How long a Pi may take to boot and start sshd after the magic packet
+pub const WAKE_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Pause between checks whether ssh answers yet
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Ports wake-on-LAN listeners use; the packet goes to both
+const WOL_PORTS: [u16; 2] = [9, 7];
+
+/// Read a MAC address written with `:` or `-` separators, or none
+pub fn parse_mac_address(text: &str) -> Result<[u8; 6], String> {
+    let digits: String = text.trim().chars().filter(|c| !matches!(c, ':' | '-' | '.')).collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a MAC address like b8:27:eb:12:34:56", text.trim()));
+    }
+
+    let mut mac = [0u8; 6];
+    for (index, byte) in mac.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[index * 2..index * 2 + 2], 16).unwrap_or(0);
+    }
+    Ok(mac)
+}
+
+/// Six 0xff bytes followed by the MAC address sixteen times
+fn magic_packet(mac: &[u8; 6]) -> Vec<u8> {
+    let mut packet = vec![0xff; 6];
+    for _ in 0..16 {
+        packet.extend_from_slice(mac);
+    }
+    packet
+}
+
+/// Broadcast a wake-on-LAN magic packet for `host` on the local network
+pub fn send_magic_packet(host: &Host) -> Result<(), TransferError> {
+    let mac_text = host.mac_address.as_deref().ok_or_else(|| {
+        TransferError::ConnectionFailed(format!("No MAC address saved for {}", host.name))
+    })?;
+    let mac = parse_mac_address(mac_text).map_err(TransferError::ConnectionFailed)?;
+    let packet = magic_packet(&mac);
+
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| socket.set_broadcast(true).map(|_| socket))
+        .map_err(|e| TransferError::ConnectionFailed(format!("Cannot open a broadcast socket: {}", e)))?;
+    for port in WOL_PORTS {
+        socket
+            .send_to(&packet, ("255.255.255.255", port))
+            .map_err(|e| TransferError::ConnectionFailed(format!("Sending the wake packet failed: {}", e)))?;
+    }
+
+    log::info!("Sent wake-on-LAN packet to {} ({})", host.name, mac_text);
+    Ok(())
+}
+
+/// Wait until one of `host`'s addresses accepts ssh connections, returning
+/// that address as [`reachable_host`] does
+pub fn wait_until_reachable(host: &Host, timeout: Duration) -> Result<(String, Host), TransferError> {
+    let started = Instant::now();
+    loop {
+        match reachable_host(host) {
+            Ok(endpoint) => return Ok(endpoint),
+            Err(_) if started.elapsed() + POLL_INTERVAL < timeout => thread::sleep(POLL_INTERVAL),
+            Err(_) => {
+                return Err(TransferError::TimedOut(format!(
+                    "{} did not answer within {} seconds of being woken",
+                    host.name,
+                    timeout.as_secs()
+                )))
+            },
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::image::{assemble_animation, AnimationFormat, AnimationSettings};
+use crate::transfer::method::TransferMethod;
+use crate::ui::dialogs::dialogs;
+use crate::ui::processing_queue::ProcessingQueue;
+
+/// Put `frames`, in name order, together into an animated GIF or PNG as a
+/// job in the processing queue
+///
+/// With a `method` the frames are on the Pi and are downloaded first; the
+/// animation is always saved on this computer.
+pub fn create_animation(mut frames: Vec<PathBuf>, method: Option<Box<dyn TransferMethod>>, queue: ProcessingQueue) {
+    if frames.len() < 2 {
+        dialogs::message_dialog("Create Animation", "Select at least two images to animate.");
+        return;
+    }
+    frames.sort();
+
+    let Some(settings) = settings_dialog() else { return };
+    let output = match dialogs::save_file_dialog("Save Animation", &format!("*.{}", settings.format.extension())) {
+        Some(path) if path.extension().is_none() => path.with_extension(settings.format.extension()),
+        Some(path) => path,
+        None => return,
+    };
+
+    let name = format!("Animation {}", output.file_name().unwrap_or_default().to_string_lossy());
+    queue.enqueue_task(&name, frames.len(), Box::new(move |progress, _cancel| {
+        let temp_dir = std::env::temp_dir()
+            .join("pi_remote_manager")
+            .join(format!("animation-{}", std::process::id()));
+        let local_frames = match &method {
+            Some(method) => {
+                fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+                let mut copies = Vec::new();
+                for frame in &frames {
+                    let copy = temp_dir.join(frame.file_name().unwrap_or_default());
+                    method.download_file(frame, &copy).map_err(|e| format!("{}: {}", frame.display(), e))?;
+                    copies.push(copy);
+                }
+                copies
+            },
+            None => frames,
+        };
+
+        let result = assemble_animation(&local_frames, &output, &settings, progress);
+        if method.is_some() {
+            let _ = fs::remove_dir_all(&temp_dir);
+        }
+        result.map_err(|e| e.to_string())?;
+        log::info!("Wrote animation {} from {} frames", output.display(), local_frames.len());
+        Ok(())
+    }));
+}
+
+/// Ask for the format, frame delay, looping and size of an animation
+fn settings_dialog() -> Option<AnimationSettings> {
+    let defaults = AnimationSettings::default();
+
+    let formats: Vec<String> = AnimationFormat::ALL.iter().map(|format| format.label().to_string()).collect();
+    let format = AnimationFormat::ALL[dialogs::select_dialog("Create Animation", "Format:", &formats)?];
+
+    let delay = dialogs::input_dialog(
+        "Create Animation",
+        "Time each frame shows (milliseconds):",
+        &defaults.frame_delay_ms.to_string()
+    )?;
+    let Some(frame_delay_ms) = delay.trim().parse::<u16>().ok().filter(|ms| *ms >= 10) else {
+        dialogs::message_dialog("Error", "The frame time must be a number of milliseconds from 10 to 65535.");
+        return None;
+    };
+
+    let loop_count = match dialogs::choice_dialog("Create Animation", "Play the animation:", &["Forever", "Once", "Cancel"]) {
+        0 => None,
+        1 => Some(1),
+        _ => return None,
+    };
+
+    let size = dialogs::input_dialog(
+        "Create Animation",
+        "Largest width or height (pixels):",
+        &defaults.max_dimension.to_string()
+    )?;
+    let Some(max_dimension) = size.trim().parse::<u32>().ok().filter(|px| *px >= 16) else {
+        dialogs::message_dialog("Error", "The size must be at least 16 pixels.");
+        return None;
+    };
+
+    Some(AnimationSettings { format, frame_delay_ms, loop_count, max_dimension })
+}
@@ -0,0 +1,117 @@
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, Color},
+    frame::Frame,
+    prelude::*,
+};
+
+use std::path::PathBuf;
+
+use crate::core::utils::AppError;
+use crate::transfer::audit::{audit_folders, count_local_files, AuditReport, AuditStatus};
+use crate::transfer::filter::PathFilter;
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::modal::ModalDialog;
+use crate::ui::processing_queue::ProcessingQueue;
+
+/// Checksum `local_dir` and `remote_dir` on the Pi as a job in the
+/// processing queue, then show which files match, differ or are missing
+pub fn audit_folder_pair(
+    local_dir: PathBuf,
+    remote_dir: PathBuf,
+    filter: PathFilter,
+    method: Box<dyn TransferMethod>,
+    queue: ProcessingQueue
+) {
+    run_in_background(
+        {
+            let local_dir = local_dir.clone();
+            let filter = filter.clone();
+            move || count_local_files(&local_dir, &filter)
+        },
+        move |local_files| {
+            let name = format!("Audit {}", local_dir.file_name().unwrap_or_default().to_string_lossy());
+            // The Pi's copy is expected to hold as many files again
+            queue.enqueue_task(&name, local_files * 2, Box::new(move |progress, cancel| {
+                let report = match audit_folders(method.as_ref(), &local_dir, &remote_dir, &filter, progress, cancel) {
+                    Ok(report) => report,
+                    // The queue reports the job as cancelled
+                    Err(TransferError::Cancelled) => return Ok(()),
+                    Err(e) => return Err(e.to_string()),
+                };
+                log::info!(
+                    "Audited {} against {}: {}",
+                    report.local_dir.display(),
+                    report.remote_dir.display(),
+                    report.summary()
+                );
+
+                let mut report = Some(report);
+                app::awake_callback(move || {
+                    if let Some(report) = report.take() {
+                        report_dialog(&report);
+                    }
+                });
+                Ok(())
+            }));
+        }
+    );
+}
+
+/// Show the files that need attention, with an export of the full report
+fn report_dialog(report: &AuditReport) {
+    let width = 640;
+    let height = 440;
+    let padding = 10;
+    let row_height = 25;
+    let button_width = 110;
+
+    let mut dialog = ModalDialog::new(width, height, "Integrity Audit");
+
+    let mut info_frame = Frame::new(padding, padding, width - padding * 2, row_height * 2, None);
+    info_frame.set_label(&format!(
+        "{}  <->  {}\n{}",
+        report.local_dir.display(),
+        report.remote_dir.display(),
+        report.summary()
+    ));
+    info_frame.set_align(Align::Left | Align::Inside | Align::Wrap);
+    if !report.is_clean() {
+        info_frame.set_label_color(Color::Red);
+    }
+
+    let list_y = padding * 2 + row_height * 2;
+    let mut list = HoldBrowser::new(padding, list_y, width - padding * 2, height - list_y - padding * 2 - row_height, None);
+    list.set_column_char('\t');
+    list.set_column_widths(&[130, width - 150]);
+    let problems: Vec<_> = report.entries.iter().filter(|entry| entry.status != AuditStatus::Matches).collect();
+    if problems.is_empty() {
+        list.add("Every file matches.");
+    }
+    for entry in problems {
+        list.add(&format!("@.{}\t@.{}", entry.status.label(), entry.path.display()));
+    }
+
+    let buttons_y = height - padding - row_height;
+    let mut export_button = Button::new(padding, buttons_y, button_width, row_height, "Export CSV...");
+    let mut close_button = Button::new(width - padding - 80, buttons_y, 80, row_height, "Close");
+
+    let mut dialog_close = dialog.clone();
+    close_button.set_callback(move |_| dialog_close.close());
+
+    let report_export = report.clone();
+    export_button.set_callback(move |_| {
+        let Some(path) = dialogs::save_file_dialog("Export Audit Report", "*.csv") else { return };
+        let path = if path.extension().is_none() { path.with_extension("csv") } else { path };
+        match report_export.write_csv(&path) {
+            Ok(()) => log::info!("Wrote audit report {}", path.display()),
+            Err(e) => dialogs::error_dialog("Exporting the report failed", &AppError::from(e)),
+        }
+    });
+
+    dialog.run();
+}
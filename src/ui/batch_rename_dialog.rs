@@ -0,0 +1,245 @@
+use fltk::{
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, CallbackTrigger, Color},
+    frame::Frame,
+    input::Input,
+    prelude::*,
+};
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::core::file::batch_rename::{
+    apply_local, local_candidates, local_names, parse_remote_candidates, remote_candidates_command,
+    remote_rename_command, remote_rename_outcome, RenameOutcome,
+};
+use crate::core::locale::trf;
+use crate::core::file::{
+    check_plan, plan_renames, PlannedRename, RenameCandidate, TimeSource, DEFAULT_RENAME_TEMPLATE, RENAME_TOKENS,
+};
+use crate::core::utils::{is_image_file, AppError, AppResult};
+use crate::transfer::method::TransferMethod;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::modal::ModalDialog;
+use crate::ui::notifications::{notify, ToastKind};
+
+/// Images that may be renamed, and every name in their folder
+type FoundImages = AppResult<(Vec<RenameCandidate>, Vec<String>)>;
+
+/// Rename the images in `folder` by capture time after showing the plan
+///
+/// With a `method` the folder is on the Pi and files are renamed there with
+/// `mv`; `on_renamed` runs afterwards in either case so the pane showing
+/// the folder can refresh.
+pub fn batch_rename<F>(folder: PathBuf, method: Option<Box<dyn TransferMethod>>, on_renamed: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    run_in_background(
+        move || {
+            let found = match &method {
+                Some(method) => remote_candidates(method.as_ref(), &folder),
+                None => local_candidates(&folder).and_then(|candidates| Ok((candidates, local_names(&folder)?))),
+            };
+            (found, folder, method)
+        },
+        move |(found, folder, method): (FoundImages, PathBuf, Option<Box<dyn TransferMethod>>)| {
+            let (candidates, names) = match found {
+                Ok(found) => found,
+                Err(e) => {
+                    dialogs::error_dialog("Reading the folder failed", &e);
+                    return;
+                }
+            };
+            if candidates.is_empty() {
+                dialogs::message_dialog("Batch Rename", &trf("No images in {}.", &[&folder.display()]));
+                return;
+            }
+
+            let Some(plan) = rename_plan_dialog(&folder, candidates, &names) else { return };
+            if plan.is_empty() {
+                return;
+            }
+
+            run_in_background(
+                move || -> AppResult<RenameOutcome> {
+                    match method {
+                        Some(method) => {
+                            let output = method.exec(&remote_rename_command(&folder, &plan))?;
+                            if !output.success() {
+                                return Err(AppError::FileError(output.stderr.trim().to_string()));
+                            }
+                            Ok(remote_rename_outcome(&plan, &output.stdout_bytes))
+                        },
+                        None => apply_local(&folder, &plan),
+                    }
+                },
+                move |result| {
+                    match result {
+                        Ok(outcome) if outcome.skipped.is_empty() => {
+                            notify(ToastKind::Success, "Batch rename", &trf("Renamed {} files", &[&outcome.renamed]))
+                        },
+                        Ok(outcome) => dialogs::report_dialog(
+                            "Batch Rename",
+                            &trf(
+                                "Renamed {} files. These were not renamed because their new name was taken meanwhile:\n{}",
+                                &[&outcome.renamed, &outcome.skipped.join("\n")]
+                            )
+                        ),
+                        Err(e) => dialogs::error_dialog("Batch rename failed", &e),
+                    }
+                    on_renamed();
+                }
+            );
+        }
+    );
+}
+
+/// Images of a Pi folder with their times, and every name in the folder
+fn remote_candidates(method: &dyn TransferMethod, folder: &Path) -> FoundImages {
+    let entries = method.list_files(folder)?;
+    let names: Vec<String> = entries.iter().map(|(name, _)| name.to_string_lossy().into_owned()).collect();
+    // Plans are built from text, so images whose names aren't UTF-8 are left as they are
+    let images: Vec<String> = entries
+        .into_iter()
+        .filter(|(name, is_dir)| !*is_dir && is_image_file(Path::new(name)))
+        .filter_map(|(name, _)| match name.into_string() {
+            Ok(name) => Some(name),
+            Err(name) => {
+                log::warn!("Not renaming {:?}: its name isn't UTF-8", name);
+                None
+            }
+        })
+        .collect();
+    if images.is_empty() {
+        return Ok((Vec::new(), names));
+    }
+
+    let output = method.exec(&remote_candidates_command(folder, &images))?;
+    Ok((parse_remote_candidates(&output.stdout_bytes, &images), names))
+}
+
+/// Let the user edit the rename template while watching the plan; returns
+/// the plan to apply, None when cancelled
+fn rename_plan_dialog(folder: &Path, candidates: Vec<RenameCandidate>, names: &[String]) -> Option<Vec<PlannedRename>> {
+    let width = 620;
+    let height = 480;
+    let padding = 10;
+    let row_height = 25;
+    let button_width = 80;
+    let label_width = 80;
+
+    let mut dialog = ModalDialog::new(width, height, "Batch Rename");
+
+    let mut folder_frame = Frame::new(padding, padding, width - padding * 2, row_height, None);
+    folder_frame.set_label(&format!("{} images in {}", candidates.len(), folder.display()));
+    folder_frame.set_align(Align::Left | Align::Inside);
+
+    let mut template_input = Input::new(
+        padding + label_width,
+        padding * 2 + row_height,
+        width - label_width - padding * 2,
+        row_height,
+        "Template:"
+    );
+    template_input.set_align(Align::Left);
+    template_input.set_value(DEFAULT_RENAME_TEMPLATE);
+    template_input.set_trigger(CallbackTrigger::Changed);
+    let tokens: Vec<String> = RENAME_TOKENS.iter().map(|(token, meaning)| format!("{} {}", token, meaning)).collect();
+    template_input.set_tooltip(&tokens.join("\n"));
+
+    let list_y = padding * 3 + row_height * 2;
+    let list_h = height - list_y - padding * 3 - row_height * 2;
+    let mut preview = HoldBrowser::new(padding, list_y, width - padding * 2, list_h, None);
+    preview.set_column_char('\t');
+    preview.set_column_widths(&[(width - 100) / 2, (width - 100) / 2, 80]);
+
+    let mut status_frame = Frame::new(padding, list_y + list_h + padding, width - padding * 2, row_height, None);
+    status_frame.set_align(Align::Left | Align::Inside);
+
+    let buttons_y = height - padding - row_height;
+    let mut cancel_button = Button::new(width - padding * 2 - button_width * 2, buttons_y, button_width, row_height, "Cancel");
+    let mut apply_button = Button::new(width - padding - button_width, buttons_y, button_width, row_height, "Rename");
+    apply_button.set_color(Color::from_rgb(0, 120, 255));
+    apply_button.set_label_color(Color::White);
+
+    let plan = Rc::new(RefCell::new(Vec::<PlannedRename>::new()));
+    let names = names.to_vec();
+
+    // Work out and show the plan for the template as typed
+    let update = {
+        let plan = plan.clone();
+        let mut preview = preview.clone();
+        let mut status_frame = status_frame.clone();
+        let mut apply_button = apply_button.clone();
+        move |template: &str| {
+            let planned = plan_renames(candidates.clone(), template);
+            preview.clear();
+            for rename in &planned {
+                // @f shows a column in the fixed-width font; @. keeps names
+                // starting with @ from being read as formatting
+                preview.add(&format!("@f@.{}\t@f@.{}\t{}", rename.from, rename.to, rename.source.label()));
+            }
+
+            match check_plan(&planned, &names) {
+                Ok(()) => {
+                    let undated = planned.iter().filter(|r| r.source != TimeSource::Exif).count();
+                    let mut label = match planned.len() {
+                        0 => "Every file already has its new name".to_string(),
+                        1 => "1 file will be renamed".to_string(),
+                        n => format!("{} files will be renamed", n),
+                    };
+                    if undated > 0 {
+                        label.push_str(&format!("; {} without EXIF time use their modification time", undated));
+                    }
+                    status_frame.set_label(&label);
+                    status_frame.set_label_color(Color::Black);
+                    if planned.is_empty() {
+                        apply_button.deactivate();
+                    } else {
+                        apply_button.activate();
+                    }
+                },
+                Err(e) => {
+                    status_frame.set_label(&e.to_string());
+                    status_frame.set_label_color(Color::Red);
+                    apply_button.deactivate();
+                },
+            }
+            *plan.borrow_mut() = planned;
+        }
+    };
+    let mut update_initial = update.clone();
+    update_initial(DEFAULT_RENAME_TEMPLATE);
+
+    let mut update_typed = update.clone();
+    template_input.set_callback(move |input| {
+        update_typed(&input.value());
+    });
+
+    let confirmed = Rc::new(RefCell::new(false));
+
+    let mut dialog_cancel = dialog.clone();
+    cancel_button.set_callback(move |_| {
+        dialog_cancel.close();
+    });
+
+    let confirmed_apply = confirmed.clone();
+    let mut dialog_apply = dialog.clone();
+    apply_button.set_callback(move |_| {
+        *confirmed_apply.borrow_mut() = true;
+        dialog_apply.close();
+    });
+
+    dialog.run();
+
+    if *confirmed.borrow() {
+        let plan = plan.borrow().clone();
+        Some(plan)
+    } else {
+        None
+    }
+}
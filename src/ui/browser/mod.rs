@@ -1,4 +1,5 @@
 pub mod file_browser;
+pub mod model;
 pub mod remote_browser;
 
 // Re-export the main file browser for compatibility
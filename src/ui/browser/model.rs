@@ -0,0 +1,825 @@
+// src/ui/browser/model.rs - What a file browser pane shows, without its widgets
+use chrono::{DateTime, Local, TimeZone};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::{Bookmark, Config, InteractionSettings};
+use crate::core::file::{is_image_file, FileSystem, LocalFs};
+use crate::core::utils::glob_matches;
+use crate::transfer::listing_cache;
+use crate::transfer::method::{sleep_unless_cancelled, RemoteEntry, TransferError, TransferMethod};
+
+/// Entries added to the list at a time; the rest wait behind a
+/// "load more" row so huge camera directories stay usable
+pub const PAGE_SIZE: usize = 500;
+
+/// A file or directory in the listing
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<DateTime<Local>>,
+    /// Where the entry points when it is a symbolic link
+    pub link_target: Option<String>,
+}
+
+/// Column the list is sorted by; directories always come first
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortColumn {
+    Name,
+    Size,
+    Type,
+    Modified,
+}
+
+impl SortColumn {
+    pub fn title(self) -> &'static str {
+        match self {
+            SortColumn::Name => "Name",
+            SortColumn::Size => "Size",
+            SortColumn::Type => "Type",
+            SortColumn::Modified => "Modified",
+        }
+    }
+}
+
+/// What a line of the list shows
+pub enum Row<'a> {
+    Parent,
+    Entry(&'a FileEntry),
+    LoadMore,
+    Message(&'a str),
+}
+
+/// What opening a line did
+pub enum Opened {
+    /// The next page of entries is shown
+    Page,
+    /// The model moved to this directory; it still has to be listed
+    Dir(PathBuf),
+    /// A file was chosen
+    File(PathBuf),
+}
+
+/// Name filter typed above the list plus the "images only" toggle
+#[derive(Clone, Debug, Default)]
+struct EntryFilter {
+    pattern: String,
+    images_only: bool,
+}
+
+impl EntryFilter {
+    fn is_active(&self) -> bool {
+        !self.pattern.is_empty() || self.images_only
+    }
+
+    // Directories always pass "images only" so the tree can still be browsed;
+    // patterns with * or ? are globs, anything else a substring, ignoring case
+    fn matches(&self, entry: &FileEntry) -> bool {
+        if self.images_only && !entry.is_dir && !is_image_file(&entry.path) {
+            return false;
+        }
+
+        if self.pattern.is_empty() {
+            return true;
+        }
+
+        let name = entry.name.to_lowercase();
+        let pattern = self.pattern.to_lowercase();
+        if pattern.contains(['*', '?']) {
+            glob_matches(&pattern, &name)
+        } else {
+            name.contains(&pattern)
+        }
+    }
+}
+
+/// Contents of the Type column
+pub fn type_label(entry: &FileEntry) -> String {
+    if entry.link_target.is_some() {
+        return if entry.is_dir { "Folder link" } else { "Link" }.to_string();
+    }
+    if entry.is_dir {
+        return "Folder".to_string();
+    }
+
+    match entry.path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{} file", ext.to_uppercase()),
+        None => "File".to_string(),
+    }
+}
+
+fn sort_entries(entries: &mut [FileEntry], column: SortColumn, ascending: bool) {
+    entries.sort_by(|a, b| {
+        let order = match column {
+            SortColumn::Name => std::cmp::Ordering::Equal,
+            SortColumn::Size => a.size.cmp(&b.size),
+            SortColumn::Type => type_label(a).cmp(&type_label(b)),
+            SortColumn::Modified => a.modified.cmp(&b.modified),
+        }
+        .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+
+        let order = if ascending { order } else { order.reverse() };
+
+        // Directories stay on top whichever way the list is sorted
+        b.is_dir.cmp(&a.is_dir).then(order)
+    });
+}
+
+fn to_file_entry(dir: &Path, entry: RemoteEntry) -> FileEntry {
+    FileEntry {
+        // The path keeps the name's raw bytes so the entry can still be
+        // opened when it isn't UTF-8
+        path: dir.join(&entry.name),
+        modified: entry.modified.and_then(|secs| Local.timestamp_opt(secs, 0).single()),
+        name: entry.display_name(),
+        is_dir: entry.is_dir,
+        size: entry.size,
+        link_target: entry.link_target,
+    }
+}
+
+fn to_remote_entry(entry: &FileEntry) -> RemoteEntry {
+    RemoteEntry {
+        name: entry.path.file_name().map(|n| n.to_os_string()).unwrap_or_else(|| entry.name.clone().into()),
+        is_dir: entry.is_dir,
+        size: entry.size,
+        modified: entry.modified.map(|m| m.timestamp()),
+        link_target: entry.link_target.clone(),
+    }
+}
+
+/// Directory a bookmark points at, with `~` expanded for local bookmarks
+pub fn bookmark_dir(bookmark: &Bookmark) -> PathBuf {
+    match bookmark.path.strip_prefix('~') {
+        Some(rest) if bookmark.host.is_none() => {
+            let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
+            home.join(rest.trim_start_matches('/'))
+        },
+        _ => PathBuf::from(&bookmark.path),
+    }
+}
+
+/// What refreshing the model leaves to its owner
+pub enum ListingStart {
+    /// The model already shows the result: a cached or local listing, or
+    /// a message saying why there is none
+    Done,
+    /// A remote listing has to run in the background
+    Background(Listing),
+}
+
+/// A remote listing handed out by `BrowserModel::refresh`; run it off the
+/// main thread and pass its batches and result back to the model
+pub struct Listing {
+    pub dir: PathBuf,
+    pub generation: u64,
+    filesystem: Box<dyn FileSystem>,
+    cancel: Arc<AtomicBool>,
+    timeout: Duration,
+}
+
+impl Listing {
+    /// Attempts the file system's retry policy allows
+    pub fn attempts(&self) -> u32 {
+        self.filesystem.retry_policy().retries + 1
+    }
+
+    /// List the directory, retrying as the host's policy says until
+    /// something was delivered; `attempt` follows the attempt in progress
+    pub fn run(
+        &self,
+        attempt: &AtomicU32,
+        deliver: &mut dyn FnMut(Vec<RemoteEntry>)
+    ) -> Result<(), TransferError> {
+        let retry = self.filesystem.retry_policy();
+        let mut attempt_no = 1;
+        loop {
+            attempt.store(attempt_no, Ordering::Relaxed);
+            let mut received = 0;
+            let result = self.filesystem.list(&self.dir, self.timeout, &self.cancel, &mut |chunk| {
+                received += chunk.len();
+                deliver(chunk);
+            });
+
+            match result {
+                // Only retry when nothing has been shown yet
+                Err(e) if received == 0 && e.is_retryable() && attempt_no <= retry.retries => {
+                    // Back off while staying responsive to Cancel
+                    let delay = retry.backoff(attempt_no);
+                    log::warn!(
+                        "Listing {} failed ({}), retrying in {:.1}s",
+                        self.dir.display(), e, delay.as_secs_f64()
+                    );
+                    sleep_unless_cancelled(delay, &self.cancel)?;
+                    attempt_no += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+/// How a background listing ended, for the owner to report
+#[derive(Default)]
+pub struct ListingEnd {
+    /// Why the listing stopped early after some entries were shown
+    pub incomplete: Option<String>,
+    /// The connection broke; says which host and why
+    pub connection_lost: Option<String>,
+    /// The host refused the listing and could list it with sudo
+    pub sudo: Option<Box<dyn FileSystem>>,
+}
+
+/// Browsing state of a file browser pane: where it is, what is listed and
+/// how the list is sorted, filtered and paged
+///
+/// Every change goes through its methods; the pane's widgets only show
+/// `row_count` lines from `row_at` and pass clicks and keys back, so the
+/// browsing logic runs without a window.
+pub struct BrowserModel {
+    // Where the listed files live; None in a remote pane without a connection
+    filesystem: Option<Box<dyn FileSystem>>,
+    current_dir: PathBuf,
+    entries: Vec<FileEntry>,
+    // Entries already listed but not yet shown
+    pending_entries: Vec<FileEntry>,
+    // Bumped on every refresh so late remote listings can be discarded
+    listing_generation: u64,
+    // Set to stop the remote listing in progress
+    cancel_listing: Option<Arc<AtomicBool>>,
+    listing_timeout: Duration,
+    // How long a cached remote listing may be reused
+    cache_ttl: Duration,
+    // Set by navigation so the next refresh may use the listing cache;
+    // the Refresh button leaves it unset and always lists afresh
+    prefer_cache: bool,
+    // Whether the list starts with a ".." row
+    show_parent: bool,
+    // Status line shown after the entries (loading, errors, ...)
+    message: Option<String>,
+    sort_column: SortColumn,
+    sort_ascending: bool,
+    filter: EntryFilter,
+    // Indexes (across shown, then pending entries) of the rows passing
+    // the filter; None when no filter is active
+    visible: Option<Vec<usize>>,
+    // Set when the rows were replaced, until the view has caught up
+    rows_reset: bool,
+    // Settings holding the bookmarks, once set_config has been called
+    config: Option<Arc<Mutex<Config>>>,
+    // Hostname of the connected Pi; bookmarks are kept per host
+    hostname: Option<String>,
+    // Local paths moved to the trash by the last delete, for undo
+    last_trashed: Vec<PathBuf>,
+}
+
+impl Default for BrowserModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BrowserModel {
+    /// An empty model browsing this computer
+    pub fn new() -> Self {
+        Self {
+            filesystem: Some(Box::new(LocalFs)),
+            current_dir: PathBuf::new(),
+            entries: Vec::new(),
+            pending_entries: Vec::new(),
+            listing_generation: 0,
+            cancel_listing: None,
+            listing_timeout: Duration::from_secs(15),
+            cache_ttl: Duration::from_secs(30),
+            prefer_cache: false,
+            show_parent: false,
+            message: None,
+            sort_column: SortColumn::Name,
+            sort_ascending: true,
+            filter: EntryFilter::default(),
+            visible: None,
+            rows_reset: false,
+            config: None,
+            hostname: None,
+            last_trashed: Vec::new(),
+        }
+    }
+
+    pub fn filesystem(&self) -> Option<&dyn FileSystem> {
+        self.filesystem.as_deref()
+    }
+
+    pub fn filesystem_mut(&mut self) -> Option<&mut (dyn FileSystem + 'static)> {
+        self.filesystem.as_deref_mut()
+    }
+
+    /// Browse `filesystem` from now on; None leaves a remote pane without
+    /// a connection
+    pub fn set_filesystem(&mut self, filesystem: Option<Box<dyn FileSystem>>) {
+        self.filesystem = filesystem;
+    }
+
+    /// Whether the pane shows another machine's files; a pane that lost its
+    /// connection still does
+    pub fn is_remote(&self) -> bool {
+        self.filesystem.as_ref().map_or(true, |filesystem| filesystem.is_remote())
+    }
+
+    /// Connection behind the pane's files, when they are remote
+    pub fn transfer_method(&self) -> Option<&dyn TransferMethod> {
+        self.filesystem.as_ref().and_then(|filesystem| filesystem.transfer_method())
+    }
+
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    pub fn set_hostname(&mut self, hostname: Option<String>) {
+        self.hostname = hostname;
+    }
+
+    pub fn config(&self) -> Option<&Arc<Mutex<Config>>> {
+        self.config.as_ref()
+    }
+
+    pub fn set_config(&mut self, config: Arc<Mutex<Config>>) {
+        self.config = Some(config);
+    }
+
+    /// Interaction settings of the app, or the defaults before set_config
+    pub fn interaction(&self) -> InteractionSettings {
+        self.config
+            .as_ref()
+            .map(|config| config.lock().unwrap().interaction.clone())
+            .unwrap_or_default()
+    }
+
+    /// Timeout and cache lifetime used for remote listings
+    pub fn set_listing_options(&mut self, timeout: Duration, cache_ttl: Duration) {
+        self.listing_timeout = timeout;
+        self.cache_ttl = cache_ttl;
+    }
+
+    pub fn last_trashed(&self) -> &[PathBuf] {
+        &self.last_trashed
+    }
+
+    /// Remember what the last delete moved to the trash, for undo
+    pub fn set_last_trashed(&mut self, trashed: Vec<PathBuf>) {
+        self.last_trashed = trashed;
+    }
+
+    pub fn current_dir(&self) -> &Path {
+        &self.current_dir
+    }
+
+    /// Move to `dir` as navigation does: the next refresh may reuse a
+    /// recent listing of it
+    pub fn navigate_to(&mut self, dir: PathBuf) {
+        log::info!("Navigating to directory: {}", dir.display());
+        self.current_dir = dir;
+        self.prefer_cache = true;
+    }
+
+    /// Move to `dir`, listing it afresh on the next refresh
+    pub fn set_current_dir(&mut self, dir: PathBuf) {
+        self.current_dir = dir;
+    }
+
+    /// Act on a line as a click or Enter does: show the next page, enter a
+    /// directory or "..", or choose a file
+    pub fn open(&mut self, row: usize) -> Option<Opened> {
+        let opened = match self.row_at(row)? {
+            Row::LoadMore => Opened::Page,
+            Row::Parent => Opened::Dir(self.current_dir.parent()?.to_path_buf()),
+            Row::Entry(entry) if entry.is_dir => Opened::Dir(entry.path.clone()),
+            Row::Entry(entry) => Opened::File(entry.path.clone()),
+            Row::Message(_) => return None,
+        };
+
+        match opened {
+            Opened::Page => self.show_next_page(),
+            Opened::Dir(ref dir) => self.navigate_to(dir.clone()),
+            Opened::File(_) => {},
+        }
+        Some(opened)
+    }
+
+    /// Forget the connection and its listing; the pane says it isn't connected
+    pub fn disconnect(&mut self) {
+        self.supersede_listing();
+        if let Some(key) = self.filesystem.as_ref().and_then(|filesystem| filesystem.cache_key()) {
+            listing_cache::invalidate_connection(&key);
+        }
+        self.filesystem = None;
+        self.hostname = None;
+        self.current_dir = PathBuf::new();
+        self.reset(Some("Not connected"));
+    }
+
+    /// Leave the directory and empty the list
+    pub fn clear(&mut self) {
+        self.current_dir = PathBuf::new();
+        self.reset(None);
+    }
+
+    /// Start listing the current directory again, superseding any listing
+    /// still running. Cached (after navigation) and local listings are
+    /// shown at once; remote ones are handed back to run in the background.
+    pub fn refresh(&mut self) -> ListingStart {
+        self.supersede_listing();
+        self.reset(None);
+
+        let Some(filesystem) = self.filesystem.as_ref().map(|filesystem| filesystem.clone_box()) else {
+            log::info!("No transfer method available for remote directory");
+            self.set_message("No connection to remote server");
+            return ListingStart::Done;
+        };
+        let dir = self.current_dir.clone();
+        log::info!("Refreshing {} through {}", dir.display(), filesystem.name());
+
+        // Navigation may reuse a recent listing instead of listing again
+        let prefer_cache = std::mem::take(&mut self.prefer_cache);
+        let cached = match filesystem.cache_key() {
+            Some(key) if prefer_cache => listing_cache::get(&key, &dir, self.cache_ttl),
+            _ => None,
+        };
+        if let Some(entries) = cached {
+            log::info!("Using cached listing of {}", dir.display());
+            self.show_listing(entries);
+            self.remember_remote_dir();
+            return ListingStart::Done;
+        }
+
+        if !filesystem.is_remote() {
+            // Local directories are read right away
+            let mut entries = Vec::new();
+            let listed = filesystem.list(&dir, self.listing_timeout, &AtomicBool::new(false), &mut |chunk| {
+                entries.extend(chunk);
+            });
+            match listed {
+                Ok(()) => {
+                    log::info!("Listed {} items in local directory: {}", entries.len(), dir.display());
+                    self.show_listing(entries);
+                },
+                Err(e) => {
+                    log::error!("Error reading local directory: {}", e);
+                    self.set_message(&format!("Cannot read {}", dir.display()));
+                }
+            }
+            return ListingStart::Done;
+        }
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel_listing = Some(cancel.clone());
+        self.set_message("Loading...");
+        ListingStart::Background(Listing {
+            dir,
+            generation: self.listing_generation,
+            filesystem,
+            cancel,
+            timeout: self.listing_timeout,
+        })
+    }
+
+    /// Whether listing `generation` is still the one the pane waits for
+    pub fn is_listing(&self, generation: u64) -> bool {
+        self.listing_generation == generation && self.cancel_listing.is_some()
+    }
+
+    /// Whether Cancel was pressed on the listing in progress
+    pub fn listing_cancelled(&self) -> bool {
+        self.cancel_listing.as_ref().map_or(false, |cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// Stop the listing in progress; false when there is none
+    pub fn cancel_listing(&mut self) -> bool {
+        match self.cancel_listing {
+            Some(ref cancel) => {
+                cancel.store(true, Ordering::Relaxed);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Take a batch of listing `generation`; the first page is shown as
+    /// soon as it arrives. Returns false for a superseded listing.
+    pub fn add_listed(&mut self, generation: u64, chunk: Vec<RemoteEntry>) -> bool {
+        if self.listing_generation != generation {
+            return false;
+        }
+
+        if self.entries.is_empty() {
+            self.show_listing(chunk);
+        } else {
+            self.add_entries(chunk);
+        }
+        true
+    }
+
+    /// Wrap up listing `generation` with its result; None when a newer
+    /// refresh, a disconnect or a switch to local browsing superseded it
+    pub fn finish_listing(
+        &mut self,
+        generation: u64,
+        result: Result<(), TransferError>
+    ) -> Option<ListingEnd> {
+        if self.listing_generation != generation {
+            return None;
+        }
+        self.cancel_listing = None;
+
+        // Batches delivered before the end are already in the model
+        let partial = !self.entries.is_empty();
+        let mut end = ListingEnd::default();
+
+        if let Err(TransferError::ConnectionFailed(ref reason)) = result {
+            let host = self.hostname.as_deref().unwrap_or("the Pi");
+            end.connection_lost = Some(format!("{}: {}", host, reason));
+        }
+
+        match result {
+            Ok(()) => {
+                if !partial {
+                    self.show_listing(Vec::new());
+                }
+
+                // Later batches arrived unsorted
+                self.apply_sort();
+
+                if let Some(key) = self.filesystem.as_ref().and_then(|filesystem| filesystem.cache_key()) {
+                    let entries: Vec<RemoteEntry> = self.listed().map(to_remote_entry).collect();
+                    listing_cache::store(&key, &self.current_dir, &entries);
+                }
+                self.remember_remote_dir();
+            },
+            Err(e) if partial => {
+                // Keep what was listed and say why the rest is missing
+                log::warn!("Remote listing stopped early: {}", e);
+                self.apply_sort();
+                end.incomplete = Some(format!("Listing incomplete: {}", e));
+            },
+            Err(TransferError::Cancelled) => {
+                log::info!("Remote listing cancelled");
+                self.reset(Some("Listing cancelled - press Refresh to try again"));
+            },
+            Err(e) => {
+                log::error!("Error listing remote directory: {}", e);
+                self.reset(Some(&format!("Error: {}", e)));
+                if matches!(e, TransferError::PermissionDenied(_)) {
+                    end.sudo = self.filesystem.as_ref().and_then(|filesystem| filesystem.with_sudo());
+                }
+            }
+        }
+
+        Some(end)
+    }
+
+    // Any listing still running is superseded
+    fn supersede_listing(&mut self) {
+        self.listing_generation += 1;
+        if let Some(cancel) = self.cancel_listing.take() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Empty the list for the current directory, optionally showing a
+    /// status message
+    pub fn reset(&mut self, message: Option<&str>) {
+        self.show_parent = self.current_dir != Path::new("/") && !self.current_dir.as_os_str().is_empty();
+        self.entries.clear();
+        self.pending_entries.clear();
+        self.message = message.map(|m| m.to_string());
+        self.rows_reset = true;
+        self.refilter();
+    }
+
+    /// Show a status line after the entries
+    pub fn set_message(&mut self, message: &str) {
+        self.message = Some(message.to_string());
+        self.refilter();
+    }
+
+    /// Whether the rows were replaced since the view last asked; a view
+    /// then clears its selection and scrolls back to the top
+    pub fn take_rows_reset(&mut self) -> bool {
+        std::mem::take(&mut self.rows_reset)
+    }
+
+    // Start showing a listing from its first page
+    fn show_listing(&mut self, entries: Vec<RemoteEntry>) {
+        self.reset(None);
+        self.add_entries(entries);
+        self.show_next_page();
+    }
+
+    // Queue listed entries behind the "load more" row
+    fn add_entries(&mut self, entries: Vec<RemoteEntry>) {
+        let dir = self.current_dir.clone();
+        self.pending_entries.extend(entries.into_iter().map(|e| to_file_entry(&dir, e)));
+        self.refilter();
+    }
+
+    /// Move up to PAGE_SIZE listed entries into view, next in sort order
+    pub fn show_next_page(&mut self) {
+        self.apply_sort();
+
+        let count = self.pending_entries.len().min(PAGE_SIZE);
+        let page: Vec<FileEntry> = self.pending_entries.drain(..count).collect();
+        self.entries.extend(page);
+
+        log::info!("Showing {} items", self.entries.len());
+        self.refilter();
+    }
+
+    /// Column the list is sorted by and whether ascending
+    pub fn sort(&self) -> (SortColumn, bool) {
+        (self.sort_column, self.sort_ascending)
+    }
+
+    /// Sort by `column`, or reverse the order when already sorted by it
+    pub fn sort_by(&mut self, column: SortColumn) {
+        if self.sort_column == column {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = column;
+            self.sort_ascending = true;
+        }
+        self.apply_sort();
+        self.refilter();
+    }
+
+    /// Show only entries whose names contain or match `pattern`
+    pub fn set_filter_pattern(&mut self, pattern: &str) {
+        self.filter.pattern = pattern.trim().to_string();
+        self.refilter();
+    }
+
+    /// Hide files that aren't images; folders stay visible
+    pub fn set_images_only(&mut self, images_only: bool) {
+        self.filter.images_only = images_only;
+        self.refilter();
+    }
+
+    // Sort every listed entry, keeping the number shown unchanged
+    fn apply_sort(&mut self) {
+        let shown = self.entries.len();
+        let mut all = std::mem::take(&mut self.entries);
+        all.append(&mut self.pending_entries);
+
+        sort_entries(&mut all, self.sort_column, self.sort_ascending);
+
+        self.pending_entries = all.split_off(shown.min(all.len()));
+        self.entries = all;
+    }
+
+    // Re-apply the filter to the listed entries
+    fn refilter(&mut self) {
+        self.visible = if self.filter.is_active() {
+            Some(
+                self.listed()
+                    .enumerate()
+                    .filter(|(_, entry)| self.filter.matches(entry))
+                    .map(|(index, _)| index)
+                    .collect()
+            )
+        } else {
+            None
+        };
+    }
+
+    // Every listed entry, the shown ones first
+    fn listed(&self) -> impl Iterator<Item = &FileEntry> {
+        self.entries.iter().chain(self.pending_entries.iter())
+    }
+
+    // Listed entry by position across the shown and the pending entries
+    fn listed_entry(&self, index: usize) -> Option<&FileEntry> {
+        match self.entries.get(index) {
+            Some(entry) => Some(entry),
+            None => self.pending_entries.get(index - self.entries.len()),
+        }
+    }
+
+    /// Number of lines: "..", the shown entries, "load more" and a status
+    /// message, each only when present. While a filter is active every
+    /// listed entry that matches is shown and there is nothing more to load.
+    pub fn row_count(&self) -> usize {
+        let (entries, load_more) = match self.visible {
+            Some(ref visible) => (visible.len(), false),
+            None => (self.entries.len(), !self.pending_entries.is_empty()),
+        };
+
+        self.show_parent as usize
+            + entries
+            + load_more as usize
+            + self.message.is_some() as usize
+    }
+
+    pub fn row_at(&self, row: usize) -> Option<Row<'_>> {
+        let mut index = row;
+
+        if self.show_parent {
+            if index == 0 {
+                return Some(Row::Parent);
+            }
+            index -= 1;
+        }
+
+        match self.visible {
+            Some(ref visible) => {
+                if let Some(&listed) = visible.get(index) {
+                    return self.listed_entry(listed).map(Row::Entry);
+                }
+                index -= visible.len();
+            },
+            None => {
+                if let Some(entry) = self.entries.get(index) {
+                    return Some(Row::Entry(entry));
+                }
+                index -= self.entries.len();
+
+                if !self.pending_entries.is_empty() {
+                    if index == 0 {
+                        return Some(Row::LoadMore);
+                    }
+                    index -= 1;
+                }
+            },
+        }
+
+        match self.message {
+            Some(ref message) if index == 0 => Some(Row::Message(message)),
+            _ => None,
+        }
+    }
+
+    /// Entry on a line, skipping "..", "load more" and messages
+    pub fn entry_at(&self, row: usize) -> Option<&FileEntry> {
+        match self.row_at(row) {
+            Some(Row::Entry(entry)) => Some(entry),
+            _ => None,
+        }
+    }
+
+    /// Entries on the selected lines
+    pub fn selected_entries(&self, rows: impl IntoIterator<Item = usize>) -> Vec<&FileEntry> {
+        rows.into_iter().filter_map(|row| self.entry_at(row)).collect()
+    }
+
+    /// A listed entry by path, shown or not
+    pub fn find_entry(&self, path: &Path) -> Option<&FileEntry> {
+        self.listed().find(|entry| entry.path == path)
+    }
+
+    /// Text of the "load more" row
+    pub fn load_more_label(&self) -> String {
+        let pending = self.pending_entries.len();
+        let next = pending.min(PAGE_SIZE);
+        if self.cancel_listing.is_some() {
+            format!("Load {} more... ({} listed so far)", next, self.entries.len() + pending)
+        } else {
+            format!("Load {} more... ({} remaining)", next, pending)
+        }
+    }
+
+    /// Host the pane's bookmarks belong to; None while browsing locally
+    pub fn bookmark_host(&self) -> Option<&str> {
+        if self.is_remote() {
+            self.hostname.as_deref()
+        } else {
+            None
+        }
+    }
+
+    /// The pane's bookmarks and whether the current directory is one of them
+    pub fn bookmarks(&self) -> (Vec<Bookmark>, bool) {
+        let bookmarks = match self.config {
+            Some(ref config) => config.lock().unwrap().bookmarks_for(self.bookmark_host()),
+            None => Vec::new(),
+        };
+        let current = bookmarks.iter().any(|b| bookmark_dir(b) == self.current_dir);
+        (bookmarks, current)
+    }
+
+    // Remember a successfully listed remote directory as the host's last
+    // visited one so the next connection reopens it
+    fn remember_remote_dir(&self) {
+        let (Some(config), Some(hostname)) = (&self.config, &self.hostname) else {
+            return;
+        };
+
+        let mut config = config.lock().unwrap();
+        if config.record_last_remote_dir(hostname, &self.current_dir.to_string_lossy()) {
+            if let Err(e) = config.save() {
+                log::warn!("Failed to save last remote directory: {}", e);
+            }
+        }
+    }
+}
@@ -7,6 +7,7 @@ use std::fs;
 use crate::transfer::TransferMethod;
 use crate::ui::file_browser::file_browser::FileBrowserPanel;
 use crate::core::file::get_file_type_info;
+use crate::core::utils::{AppError, AppResult};
 
 
 /// An extension of FileBrowserPanel with enhanced remote preview capabilities
@@ -65,14 +66,14 @@ impl RemoteBrowserPanel {
         // Standard file select callback - will handle downloading files
         self.browser.set_callback(move |path, is_dir| {
             if !is_dir {
-                println!("Remote file selected: {}", path.display());
+                log::info!("Remote file selected: {}", path.display());
                 
                 // Check if we need to download for preview
                 let path_exists = path.exists();
                 let file_info = get_file_type_info(&path);
                 
                 if file_info.previewable && !path_exists {
-                    println!("File needs download for preview: {}", path.display());
+                    log::info!("File needs download for preview: {}", path.display());
                     
                     // Get temporary location
                     let mut temp_file = {
@@ -84,7 +85,7 @@ impl RemoteBrowserPanel {
                     if let Some(file_name) = path.file_name() {
                         temp_file.push(file_name);
                         
-                        println!("Temporary file location: {}", temp_file.display());
+                        log::info!("Temporary file location: {}", temp_file.display());
                         
                         // Call the preview callback with the original path
                         // The main window will handle downloading if needed
@@ -115,53 +116,21 @@ impl RemoteBrowserPanel {
     }
     
  /// Download a remote file for preview
- pub fn download_for_preview(&self, remote_path: &Path) -> Result<PathBuf, String> {
-    // Check if we have a transfer method
-    if !self.browser.has_transfer_method() {
-        return Err("No transfer method available".to_string());
-    }
+ pub fn download_for_preview(&self, remote_path: &Path) -> AppResult<PathBuf> {
+    let file_name = remote_path.file_name().ok_or_else(|| {
+        AppError::FileError(format!("Not a file: {}", remote_path.display()))
+    })?;
     
     // Get temporary location
-    let mut temp_file = {
+    let temp_file = {
         let temp_dir = self.temp_dir.lock().unwrap();
-        temp_dir.clone()
+        temp_dir.join(file_name)
     };
     
-    // Create a file name in temp dir
-    if let Some(file_name) = remote_path.file_name() {
-        temp_file.push(file_name);
-        
-        println!("Downloading to: {}", temp_file.display());
-        
-        // Since we don't have direct access to the transfer method yet,
-        // we'll provide a workaround solution
-        
-        // This function should be replaced with actual implementation
-        // once FileBrowserPanel gets a get_transfer_method() function
-        println!("Attempting to download: {} -> {}", 
-            remote_path.display(), 
-            temp_file.display()
-        );
-        
-        // For now, we'll just check if the file already exists locally
-        if remote_path.exists() {
-            // Copy the file to the temp location
-            match fs::copy(remote_path, &temp_file) {
-                Ok(_) => {
-                    println!("File copied successfully");
-                    return Ok(temp_file);
-                },
-                Err(e) => {
-                    return Err(format!("File copy failed: {}", e));
-                }
-            }
-        }
-        
-        // Return an error for now
-        Err("Remote file download not yet implemented".to_string())
-    } else {
-        Err("Invalid file path".to_string())
-    }
+    log::info!("Downloading to: {}", temp_file.display());
+    self.browser.download_remote_file(remote_path, &temp_file)?;
+    
+    Ok(temp_file)
 }
     
     /// Clean up temporary files
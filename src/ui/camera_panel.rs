@@ -0,0 +1,271 @@
+use fltk::{
+    app,
+    button::Button,
+    enums::{Color, FrameType, Align},
+    frame::Frame,
+    group::Group,
+    menu::Choice,
+    misc::Spinner,
+    prelude::*,
+};
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::core::device::{Capabilities, CaptureSettings, RESOLUTION_PRESETS, capture_command, capture_file_name};
+use crate::transfer::ssh::shell_quote;
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::theme::font_size;
+
+/// How often a running capture is checked for completion (seconds)
+const POLL_INTERVAL: f64 = 0.2;
+
+/// Directory on the Pi where captures are written before download
+const REMOTE_CAPTURE_DIR: &str = "/tmp";
+
+/// Told about each picture once it has been downloaded
+type CaptureCallback = Arc<Mutex<Option<Box<dyn FnMut(PathBuf) + Send + Sync>>>>;
+
+/// Panel for taking still pictures with the Pi camera
+pub struct CameraPanel {
+    group: Group,
+    resolution_choice: Choice,
+    shutter_spinner: Spinner,
+    timeout_spinner: Spinner,
+    capture_button: Button,
+    status_frame: Frame,
+    capturing: Arc<Mutex<bool>>,
+    local_dir: PathBuf,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+    callback: CaptureCallback,
+}
+
+impl Clone for CameraPanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            resolution_choice: self.resolution_choice.clone(),
+            shutter_spinner: self.shutter_spinner.clone(),
+            timeout_spinner: self.timeout_spinner.clone(),
+            capture_button: self.capture_button.clone(),
+            status_frame: self.status_frame.clone(),
+            capturing: self.capturing.clone(),
+            local_dir: self.local_dir.clone(),
+            remote_browser: self.remote_browser.clone(),
+            callback: self.callback.clone(),
+        }
+    }
+}
+
+impl CameraPanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>,
+        local_dir: PathBuf
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let label_width = 150;
+        let input_width = 200;
+        let input_x = x + padding + label_width;
+
+        let row_y = |row: i32| y + padding + (row_height + padding) * row;
+
+        let mut title = Frame::new(x + padding, row_y(0), w - 2 * padding, row_height, "Pi Camera Capture");
+        title.set_label_size(font_size(14));
+        title.set_align(Align::Left | Align::Inside);
+
+        let defaults = CaptureSettings::default();
+
+        // Resolution
+        let mut resolution_choice = Choice::new(input_x, row_y(1), input_width, row_height, "Resolution:");
+        resolution_choice.set_align(Align::Left);
+        for (i, (width, height)) in RESOLUTION_PRESETS.iter().enumerate() {
+            resolution_choice.add_choice(&format!("{}x{}", width, height));
+            if *width == defaults.width && *height == defaults.height {
+                resolution_choice.set_value(i as i32);
+            }
+        }
+
+        // Exposure
+        let mut shutter_spinner = Spinner::new(input_x, row_y(2), input_width, row_height, "Shutter (µs, 0 = auto):");
+        shutter_spinner.set_align(Align::Left);
+        shutter_spinner.set_minimum(0.0);
+        shutter_spinner.set_maximum(10_000_000.0);
+        shutter_spinner.set_step(1000.0);
+        shutter_spinner.set_value(defaults.shutter_us as f64);
+
+        // Delay before capture
+        let mut timeout_spinner = Spinner::new(input_x, row_y(3), input_width, row_height, "Timeout (ms):");
+        timeout_spinner.set_align(Align::Left);
+        timeout_spinner.set_minimum(1.0);
+        timeout_spinner.set_maximum(60_000.0);
+        timeout_spinner.set_step(500.0);
+        timeout_spinner.set_value(defaults.timeout_ms as f64);
+
+        let mut capture_button = Button::new(input_x, row_y(4), 120, row_height + 5, "Capture");
+        capture_button.set_color(Color::from_rgb(0, 120, 255));
+        capture_button.set_label_color(Color::White);
+
+        let mut status_frame = Frame::new(x + padding, row_y(5) + 5, w - 2 * padding, row_height, None);
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        group.end();
+
+        let mut panel = CameraPanel {
+            group,
+            resolution_choice,
+            shutter_spinner,
+            timeout_spinner,
+            capture_button,
+            status_frame,
+            capturing: Arc::new(Mutex::new(false)),
+            local_dir,
+            remote_browser,
+            callback: Arc::new(Mutex::new(None)),
+        };
+
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.capture_button.set_callback(move |_| {
+            panel.capture();
+        });
+    }
+
+    /// Set a callback invoked with the local path of each downloaded capture
+    pub fn set_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(PathBuf) + Send + Sync + 'static,
+    {
+        *self.callback.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Read the capture parameters from the form
+    pub fn get_settings(&self) -> CaptureSettings {
+        let (width, height) = RESOLUTION_PRESETS
+            .get(self.resolution_choice.value().max(0) as usize)
+            .copied()
+            .unwrap_or((1920, 1080));
+
+        CaptureSettings {
+            width,
+            height,
+            shutter_us: self.shutter_spinner.value() as u32,
+            timeout_ms: self.timeout_spinner.value() as u32,
+        }
+    }
+
+    /// Enable or disable capturing depending on the tools found on the Pi
+    pub fn apply_capabilities(&mut self, capabilities: &Capabilities) {
+        if capabilities.has_camera_tool() {
+            self.capture_button.activate();
+            self.capture_button.set_tooltip("");
+            self.set_status("", false);
+        } else {
+            let message = "No camera tool found on the Pi (install rpicam-apps or libcamera-apps)";
+            self.capture_button.deactivate();
+            self.capture_button.set_tooltip(message);
+            self.set_status(message, true);
+        }
+    }
+
+    fn capture(&mut self) {
+        if *self.capturing.lock().unwrap() {
+            return;
+        }
+
+        let method = self.remote_browser.lock().ok().and_then(|b| b.get_transfer_method());
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.set_status("Not connected. Use Connection > Connect to Raspberry Pi first.", true);
+                return;
+            }
+        };
+
+        let file_name = capture_file_name();
+        let remote_path = format!("{}/{}", REMOTE_CAPTURE_DIR, file_name);
+        let local_path = self.local_dir.join(&file_name);
+        let command = capture_command(&self.get_settings(), &remote_path);
+
+        *self.capturing.lock().unwrap() = true;
+        self.capture_button.deactivate();
+        self.set_status(&format!("Capturing on {}...", method.get_description()), false);
+
+        let result: Arc<Mutex<Option<Result<PathBuf, String>>>> = Arc::new(Mutex::new(None));
+        let result_worker = result.clone();
+
+        thread::spawn(move || {
+            let outcome = match method.exec(&command) {
+                Ok(output) if output.success() => {
+                    let downloaded = method
+                        .download_file(PathBuf::from(&remote_path).as_path(), &local_path)
+                        .map(|_| local_path.clone())
+                        .map_err(|e| e.to_string());
+
+                    // Don't leave captures behind on the Pi
+                    let _ = method.exec(&format!("rm -f {}", shell_quote(&remote_path)));
+
+                    downloaded
+                },
+                Ok(output) => {
+                    let message = output.stderr.lines().last().unwrap_or("").trim().to_string();
+                    if message.is_empty() {
+                        Err("Capture command failed".to_string())
+                    } else {
+                        Err(message)
+                    }
+                },
+                Err(e) => Err(e.to_string()),
+            };
+
+            *result_worker.lock().unwrap() = Some(outcome);
+            app::awake();
+        });
+
+        let mut panel = self.clone();
+        app::add_timeout3(POLL_INTERVAL, move |handle| {
+            let finished = result.lock().unwrap().take();
+
+            match finished {
+                Some(Ok(path)) => {
+                    panel.set_status(&format!("Saved {}", path.display()), false);
+                    panel.finish_capture();
+
+                    if let Some(callback) = panel.callback.lock().unwrap().as_mut() {
+                        callback(path);
+                    }
+                },
+                Some(Err(e)) => {
+                    panel.set_status(&format!("Capture failed: {}", e), true);
+                    panel.finish_capture();
+                },
+                None => app::repeat_timeout3(POLL_INTERVAL, handle),
+            }
+        });
+    }
+
+    fn finish_capture(&mut self) {
+        *self.capturing.lock().unwrap() = false;
+        self.capture_button.activate();
+    }
+
+    fn set_status(&mut self, message: &str, is_error: bool) {
+        self.status_frame.set_label(message);
+        self.status_frame.set_label_color(if is_error { Color::Red } else { Color::Black });
+        self.group.redraw();
+    }
+}
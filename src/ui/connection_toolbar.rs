@@ -0,0 +1,309 @@
+use fltk::{
+    button::Button,
+    enums::{Align, Color, Event, FrameType},
+    frame::Frame,
+    group::Group,
+    menu::Choice,
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+
+use crate::config::{Config, Host};
+use crate::core::locale::tr;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::theme::font_size;
+
+/// Height of the toolbar below the menu bar
+pub const TOOLBAR_HEIGHT: i32 = 34;
+
+type ConnectCallback = Arc<Mutex<Option<Box<dyn FnMut(Host) + Send + Sync>>>>;
+type ActionCallback = Arc<Mutex<Option<Box<dyn FnMut() + Send + Sync>>>>;
+
+/// Row under the menu bar for switching between saved Pis: a host
+/// dropdown, a Connect/Disconnect button, a Wake button for hosts with a
+/// MAC address and a connection indicator
+///
+/// While the connected Pi is unreachable the button offers Reconnect.
+///
+/// The toolbar only asks for connections through its callbacks; what it
+/// shows as connected arrives through the event bus.
+pub struct ConnectionToolbar {
+    group: Group,
+    hosts: Choice,
+    connect_button: Button,
+    wake_button: Button,
+    indicator: Frame,
+    status: Frame,
+    config: Arc<Mutex<Config>>,
+    /// Hostname shown in the remote pane, None when disconnected
+    connected: Arc<Mutex<Option<String>>>,
+    /// The keepalive lost the connected Pi
+    unreachable: Arc<Mutex<bool>>,
+    on_connect: ConnectCallback,
+    on_wake: ConnectCallback,
+    on_disconnect: ActionCallback,
+    on_reconnect: ActionCallback,
+}
+
+impl Clone for ConnectionToolbar {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            hosts: self.hosts.clone(),
+            connect_button: self.connect_button.clone(),
+            wake_button: self.wake_button.clone(),
+            indicator: self.indicator.clone(),
+            status: self.status.clone(),
+            config: self.config.clone(),
+            connected: self.connected.clone(),
+            unreachable: self.unreachable.clone(),
+            on_connect: self.on_connect.clone(),
+            on_wake: self.on_wake.clone(),
+            on_disconnect: self.on_disconnect.clone(),
+            on_reconnect: self.on_reconnect.clone(),
+        }
+    }
+}
+
+impl ConnectionToolbar {
+    pub fn new(x: i32, y: i32, w: i32, h: i32, config: Arc<Mutex<Config>>) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::ThinUpBox);
+
+        let row_y = y + (h - 24) / 2;
+        let mut hosts = Choice::new(x + 50, row_y, 220, 24, "Host:");
+        hosts.set_align(Align::Left);
+        hosts.set_tooltip("Saved Raspberry Pis");
+
+        let connect_button = Button::new(x + 280, row_y, 100, 24, "Connect");
+
+        let mut wake_button = Button::new(x + 390, row_y, 70, 24, "Wake");
+        wake_button.set_tooltip("Power the Pi up with a wake-on-LAN packet, then connect once it boots");
+
+        let mut indicator = Frame::new(x + 470, row_y, 20, 24, "●");
+        indicator.set_label_color(Color::from_rgb(150, 150, 150));
+
+        let mut status = Frame::new(x + 490, row_y, w - 500, 24, "Not connected");
+        status.set_label_size(font_size(12));
+        status.set_align(Align::Left | Align::Inside | Align::Clip);
+
+        // Only the status text grows with the window
+        group.resizable(&status);
+        group.end();
+
+        let mut toolbar = ConnectionToolbar {
+            group,
+            hosts,
+            connect_button,
+            wake_button,
+            indicator,
+            status,
+            config,
+            connected: Arc::new(Mutex::new(None)),
+            unreachable: Arc::new(Mutex::new(false)),
+            on_connect: Arc::new(Mutex::new(None)),
+            on_wake: Arc::new(Mutex::new(None)),
+            on_disconnect: Arc::new(Mutex::new(None)),
+            on_reconnect: Arc::new(Mutex::new(None)),
+        };
+
+        toolbar.reload_hosts();
+        toolbar.setup_callbacks();
+        toolbar
+    }
+
+    fn setup_callbacks(&mut self) {
+        // Hosts may have been added or removed in the connection dialog
+        let mut toolbar = self.clone();
+        self.hosts.handle(move |_, event| {
+            if event == Event::Push {
+                toolbar.reload_hosts();
+            }
+            false
+        });
+
+        let mut toolbar = self.clone();
+        self.hosts.set_callback(move |_| {
+            toolbar.update_button();
+        });
+
+        let toolbar = self.clone();
+        self.connect_button.set_callback(move |_| {
+            if toolbar.selected_is_connected() {
+                let callback = if *toolbar.unreachable.lock().unwrap() {
+                    &toolbar.on_reconnect
+                } else {
+                    &toolbar.on_disconnect
+                };
+                if let Some(ref mut callback) = *callback.lock().unwrap() {
+                    callback();
+                }
+                return;
+            }
+
+            let host = toolbar.selected_host();
+            if let Some(host) = host {
+                if let Some(ref mut callback) = *toolbar.on_connect.lock().unwrap() {
+                    callback(host);
+                }
+            }
+        });
+
+        let toolbar = self.clone();
+        self.wake_button.set_callback(move |_| {
+            let host = toolbar.selected_host();
+            if let Some(host) = host {
+                if let Some(ref mut callback) = *toolbar.on_wake.lock().unwrap() {
+                    callback(host);
+                }
+            }
+        });
+    }
+
+    /// Run `callback` with the chosen host when Connect is pressed
+    pub fn set_connect_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(Host) + 'static + Send + Sync,
+    {
+        *self.on_connect.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Run `callback` with the chosen host when Wake is pressed
+    pub fn set_wake_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(Host) + 'static + Send + Sync,
+    {
+        *self.on_wake.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Run `callback` when Disconnect is pressed
+    pub fn set_disconnect_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut() + 'static + Send + Sync,
+    {
+        *self.on_disconnect.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Run `callback` when Reconnect is pressed
+    pub fn set_reconnect_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut() + 'static + Send + Sync,
+    {
+        *self.on_reconnect.lock().unwrap() = Some(Box::new(callback));
+    }
+
+    /// Keep the indicator and button up to date from now on
+    pub fn subscribe(&self, events: &EventBus) {
+        let mut toolbar = self.clone();
+        events.subscribe(move |event| match event {
+            AppEvent::ConnectionChanged { hostname } => {
+                *toolbar.connected.lock().unwrap() = hostname.clone();
+                *toolbar.unreachable.lock().unwrap() = false;
+                toolbar.reload_hosts();
+
+                match hostname {
+                    Some(hostname) => {
+                        toolbar.indicator.set_label_color(Color::from_rgb(40, 170, 70));
+                        toolbar.status.set_label(&format!("Connected to {}", hostname));
+                    },
+                    None => {
+                        toolbar.indicator.set_label_color(Color::from_rgb(150, 150, 150));
+                        toolbar.status.set_label(&tr("Not connected"));
+                    },
+                }
+                toolbar.indicator.redraw_label();
+            },
+            AppEvent::ConnectionHealth { hostname, reachable } => {
+                *toolbar.unreachable.lock().unwrap() = !reachable;
+                if *reachable {
+                    toolbar.indicator.set_label_color(Color::from_rgb(40, 170, 70));
+                    toolbar.status.set_label(&format!("Connected to {}", hostname));
+                } else {
+                    toolbar.indicator.set_label_color(Color::from_rgb(210, 50, 40));
+                    toolbar.status.set_label(&format!("{} is not responding", hostname));
+                }
+                toolbar.indicator.redraw_label();
+                toolbar.update_button();
+            },
+            _ => {},
+        });
+    }
+
+    /// Place the toolbar under a menu bar of the given height
+    pub fn resize_to_window(&mut self, w: i32, menu_height: i32) {
+        self.group.resize(0, menu_height, w, TOOLBAR_HEIGHT);
+    }
+
+    /// Fill the dropdown from the saved hosts, keeping the chosen host, or
+    /// the connected or last used one
+    fn reload_hosts(&mut self) {
+        let previous = self.selected_host().map(|host| host.name);
+        let connected = self.connected.lock().unwrap().clone();
+
+        let (names, selected) = {
+            let config = self.config.lock().unwrap();
+            let names: Vec<String> = config.hosts.iter().map(|host| host.name.clone()).collect();
+            let selected = previous
+                .and_then(|name| names.iter().position(|n| *n == name))
+                .or_else(|| {
+                    connected.and_then(|hostname| config.hosts.iter().position(|h| h.hostname == hostname))
+                })
+                .or_else(|| (config.last_used_host_index < names.len()).then_some(config.last_used_host_index));
+            (names, selected)
+        };
+
+        self.hosts.clear();
+        for name in &names {
+            // Escape characters the menu would interpret
+            self.hosts.add_choice(&name.replace('/', "\\/").replace('|', "\\|"));
+        }
+        self.hosts.set_value(selected.map_or(-1, |index| index as i32));
+
+        if names.is_empty() {
+            self.hosts.deactivate();
+        } else {
+            self.hosts.activate();
+        }
+        self.update_button();
+    }
+
+    fn selected_host(&self) -> Option<Host> {
+        let index = self.hosts.value();
+        if index < 0 {
+            return None;
+        }
+        self.config.lock().unwrap().hosts.get(index as usize).cloned()
+    }
+
+    fn selected_is_connected(&self) -> bool {
+        let connected = self.connected.lock().unwrap();
+        match (self.selected_host(), connected.as_ref()) {
+            (Some(host), Some(hostname)) => host.hostname == *hostname,
+            _ => false,
+        }
+    }
+
+    fn update_button(&mut self) {
+        if self.selected_is_connected() && *self.unreachable.lock().unwrap() {
+            self.connect_button.set_label(&tr("Reconnect"));
+        } else if self.selected_is_connected() {
+            self.connect_button.set_label(&tr("Disconnect"));
+        } else {
+            self.connect_button.set_label(&tr("Connect"));
+        }
+
+        if self.hosts.value() < 0 {
+            self.connect_button.deactivate();
+        } else {
+            self.connect_button.activate();
+        }
+
+        let wakeable = self.selected_host().is_some_and(|host| host.mac_address.is_some());
+        if wakeable && !self.selected_is_connected() {
+            self.wake_button.activate();
+        } else {
+            self.wake_button.deactivate();
+        }
+    }
+}
@@ -0,0 +1,717 @@
+use fltk::app;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::config::{Config, ControlApiSettings, Host};
+use crate::core::image::{ProcessingPreset, BUILTIN_PRESETS};
+use crate::core::image_utils::{find_images_in_dir, is_image_file};
+use crate::core::upload_target::preset_upload_target;
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::{connect_unattended, Mirror, PathFilter};
+use crate::ui::processing_queue::{JobStatus, Pipeline, ProcessingJob, ProcessingQueue, TaskWork};
+
+/// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a request waits for the window to queue its job
+const MAIN_THREAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Largest request body accepted
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+/// Most header lines read before a request is refused
+const MAX_HEADERS: usize = 100;
+
+/// Longest request or header line accepted
+const MAX_LINE_BYTES: usize = 8 * 1024;
+
+/// Threads serving requests; connections beyond these wait in a short queue
+const WORKERS: usize = 4;
+
+/// Connections waiting for a worker before new ones are turned away
+const PENDING_CONNECTIONS: usize = 16;
+
+/// A failed request: the HTTP status and what went wrong
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: impl Into<String>) -> Self {
+        Self { status: 400, message: message.into() }
+    }
+
+    fn not_found(message: impl Into<String>) -> Self {
+        Self { status: 404, message: message.into() }
+    }
+}
+
+impl From<TransferError> for ApiError {
+    fn from(e: TransferError) -> Self {
+        // The Pi, not the client, failed
+        Self { status: 502, message: e.to_string() }
+    }
+}
+
+type ApiResult = Result<(u16, Value), ApiError>;
+
+/// One parsed request
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    host: Option<String>,
+    origin: Option<String>,
+    content_type: Option<String>,
+    body: Vec<u8>,
+}
+
+impl Request {
+    /// The body as JSON of type `T`; an empty body reads as `{}`
+    fn json<T: DeserializeOwned>(&self) -> Result<T, ApiError> {
+        let body = if self.body.iter().all(u8::is_ascii_whitespace) { &b"{}"[..] } else { &self.body[..] };
+        serde_json::from_slice(body).map_err(|e| ApiError::bad_request(format!("Bad request body: {}", e)))
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct RunTemplateBody {
+    /// Mirror templates: also remove files the source no longer has
+    delete: bool,
+}
+
+#[derive(Deserialize)]
+struct SyncBody {
+    host: String,
+    local_dir: PathBuf,
+    remote_dir: PathBuf,
+    /// Copy from the Pi to this computer instead
+    #[serde(default)]
+    download: bool,
+    #[serde(default)]
+    delete: bool,
+}
+
+#[derive(Deserialize)]
+struct TransferBody {
+    host: String,
+    source: PathBuf,
+    destination: PathBuf,
+    #[serde(default)]
+    download: bool,
+}
+
+#[derive(Deserialize)]
+struct ProcessBody {
+    preset: String,
+    input: PathBuf,
+    /// Defaults to a `processed` folder next to the images
+    #[serde(default)]
+    output: Option<PathBuf>,
+}
+
+/// HTTP server letting home automation (Home Assistant, Node-RED, scripts)
+/// run transfer templates, syncs, copies and processing presets
+///
+/// Work is queued in the processing queue like jobs started from the
+/// window, so it shows in the Processing Jobs tab and can be cancelled
+/// there or through the API.
+#[derive(Clone)]
+pub struct ControlApi {
+    config: Arc<Mutex<Config>>,
+    queue: ProcessingQueue,
+    token: String,
+    bind_address: String,
+}
+
+impl ControlApi {
+    /// Listen as `settings` say when the API is enabled
+    pub fn start(settings: &ControlApiSettings, config: Arc<Mutex<Config>>, queue: ProcessingQueue) {
+        if !settings.enabled {
+            return;
+        }
+
+        // Anyone who can reach the port, including web pages open in a
+        // browser, could otherwise copy files off the Pi
+        if settings.token.is_empty() {
+            log::error!("Not starting the control API: set a token in control_api.token first");
+            return;
+        }
+
+        let listener = match TcpListener::bind((settings.bind_address.as_str(), settings.port)) {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Cannot start the control API on {}:{}: {}", settings.bind_address, settings.port, e);
+                return;
+            },
+        };
+        log::info!("Control API listening on http://{}:{}/api", settings.bind_address, settings.port);
+
+        let api = ControlApi {
+            config,
+            queue,
+            token: settings.token.clone(),
+            bind_address: settings.bind_address.clone(),
+        };
+
+        // A fixed pool of workers, so a flood of connections can't spawn
+        // threads without limit
+        let (sender, receiver) = mpsc::sync_channel::<TcpStream>(PENDING_CONNECTIONS);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for _ in 0..WORKERS {
+            let api = api.clone();
+            let receiver = receiver.clone();
+            thread::spawn(move || api.work(&receiver));
+        }
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                match sender.try_send(stream) {
+                    Ok(()) => {},
+                    Err(TrySendError::Full(_)) => log::warn!("Control API busy; dropped a connection"),
+                    Err(TrySendError::Disconnected(_)) => break,
+                }
+            }
+        });
+    }
+
+    /// Serve connections from `receiver` until the listener goes away
+    fn work(&self, receiver: &Mutex<Receiver<TcpStream>>) {
+        loop {
+            let stream = receiver.lock().unwrap().recv();
+            match stream {
+                Ok(stream) => self.serve(stream),
+                Err(_) => return,
+            }
+        }
+    }
+
+    fn serve(&self, mut stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(REQUEST_TIMEOUT));
+        let (status, body) = match read_request(&stream) {
+            Ok(request) => {
+                let result = self.check_origin(&request)
+                    .and_then(|()| self.authorize(&request))
+                    .and_then(|()| check_content_type(&request))
+                    .and_then(|()| self.route(&request));
+                let (status, body) = result.unwrap_or_else(|e| (e.status, json!({ "error": e.message })));
+                log::info!("Control API: {} {} -> {}", request.method, request.path, status);
+                (status, body)
+            },
+            Err(e) => (e.status, json!({ "error": e.message })),
+        };
+
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.0 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            reason(status),
+            body.len(),
+            body
+        );
+        if let Err(e) = stream.write_all(response.as_bytes()) {
+            log::debug!("Control API client went away: {}", e);
+        }
+    }
+
+    /// Refuse requests made by web pages, including through DNS rebinding,
+    /// which name some other site in `Origin` or `Host`
+    fn check_origin(&self, request: &Request) -> Result<(), ApiError> {
+        let origin_allowed = request.origin.as_deref().is_none_or(|origin| {
+            let authority = origin.split_once("://").map_or("", |(_, rest)| rest.split('/').next().unwrap_or(""));
+            is_loopback_name(authority_host(authority))
+        });
+        let host_allowed = request.host.as_deref().is_some_and(|host| self.host_allowed(authority_host(host)));
+        if origin_allowed && host_allowed {
+            Ok(())
+        } else {
+            Err(ApiError { status: 403, message: "Requests must come from this computer or name the API by address".to_string() })
+        }
+    }
+
+    /// Loopback names, plus the address listened on, or any IP address when
+    /// listening on all of them
+    fn host_allowed(&self, host: &str) -> bool {
+        if is_loopback_name(host) || host.eq_ignore_ascii_case(&self.bind_address) {
+            return true;
+        }
+        let unspecified = self.bind_address.parse::<IpAddr>().is_ok_and(|ip| ip.is_unspecified());
+        unspecified && host.parse::<IpAddr>().is_ok()
+    }
+
+    fn authorize(&self, request: &Request) -> Result<(), ApiError> {
+        let token = request.authorization.as_deref().and_then(|value| value.strip_prefix("Bearer "));
+        if token.is_some_and(|token| constant_time_eq(token.trim().as_bytes(), self.token.as_bytes())) {
+            Ok(())
+        } else {
+            Err(ApiError { status: 401, message: "Send the API token as `Authorization: Bearer <token>`".to_string() })
+        }
+    }
+
+    fn route(&self, request: &Request) -> ApiResult {
+        let path = request.path.split('?').next().unwrap_or("").trim_end_matches('/');
+        let Some(rest) = path.strip_prefix("/api").filter(|rest| rest.is_empty() || rest.starts_with('/')) else {
+            return Err(ApiError::not_found(format!("No endpoint {}; everything is under /api", request.path)));
+        };
+        let segments: Vec<String> = rest
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(percent_decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        match (request.method.as_str(), segments.as_slice()) {
+            ("GET", []) | ("GET", ["status"]) => Ok((200, json!({
+                "version": env!("CARGO_PKG_VERSION"),
+                "jobs": self.queue.jobs().iter().map(job_json).collect::<Vec<_>>(),
+            }))),
+            ("GET", ["hosts"]) => {
+                let config = self.config.lock().unwrap();
+                let hosts: Vec<Value> = config.hosts.iter().map(|host| json!({
+                    "name": host.name,
+                    "hostname": host.hostname,
+                    "username": host.username,
+                    "key_auth": host.use_key_auth,
+                })).collect();
+                Ok((200, json!(hosts)))
+            },
+            ("GET", ["templates"]) => {
+                let config = self.config.lock().unwrap();
+                let templates: Vec<Value> = config.transfer_templates.iter().map(|template| json!({
+                    "name": template.name,
+                    "host": template.host,
+                    "source": template.source,
+                    "destination": template.destination,
+                    "upload": template.upload,
+                    "mirror": template.mirror,
+                })).collect();
+                Ok((200, json!(templates)))
+            },
+            ("GET", ["presets"]) => {
+                let presets: Vec<Value> = BUILTIN_PRESETS
+                    .iter()
+                    .map(|preset| json!({ "name": preset.name, "description": preset.description }))
+                    .collect();
+                Ok((200, json!(presets)))
+            },
+            ("GET", ["jobs"]) => Ok((200, json!(self.queue.jobs().iter().map(job_json).collect::<Vec<_>>()))),
+            ("GET", ["jobs", id]) => {
+                let id = parse_job_id(id)?;
+                let job = self.queue.jobs().into_iter().find(|job| job.id == id);
+                job.map(|job| (200, job_json(&job))).ok_or_else(|| ApiError::not_found(format!("No job {}", id)))
+            },
+            ("POST", ["jobs", id, "cancel"]) => {
+                let id = parse_job_id(id)?;
+                if !self.queue.jobs().iter().any(|job| job.id == id) {
+                    return Err(ApiError::not_found(format!("No job {}", id)));
+                }
+                let queue = self.queue.clone();
+                on_main_thread(move || queue.cancel(id))?;
+                Ok((200, json!({ "job": id, "cancelled": true })))
+            },
+            ("POST", ["templates", name, "run"]) => self.run_template(name, request.json()?),
+            ("POST", ["sync"]) => self.sync(request.json()?),
+            ("POST", ["transfer"]) => self.transfer(request.json()?),
+            ("POST", ["process"]) => self.process(request.json()?),
+            (_, []) | (_, ["status" | "hosts" | "templates" | "presets" | "jobs" | "sync" | "transfer" | "process", ..]) => {
+                Err(ApiError { status: 405, message: format!("{} is not supported here", request.method) })
+            },
+            _ => Err(ApiError::not_found(format!("No endpoint {}", request.path))),
+        }
+    }
+
+    fn find_host(&self, name: &str) -> Result<Host, ApiError> {
+        let config = self.config.lock().unwrap();
+        config.hosts
+            .iter()
+            .find(|host| host.name == name || host.hostname == name)
+            .cloned()
+            .ok_or_else(|| ApiError::not_found(format!("No saved host named '{}'", name)))
+    }
+
+    fn run_template(&self, name: &str, body: RunTemplateBody) -> ApiResult {
+        let (template, host, walks) = {
+            let config = self.config.lock().unwrap();
+            let template = config.transfer_templates
+                .iter()
+                .find(|t| t.name == name)
+                .cloned()
+                .ok_or_else(|| ApiError::not_found(format!("No transfer template named '{}'", name)))?;
+            let host = config.template_host(&template).map_err(|e| ApiError::bad_request(e.to_string()))?;
+            let walks = config.template_walks(&template);
+            (template, host, walks)
+        };
+        let method = connect(&host)?;
+        let job_name = format!("Run template {}", template.name);
+
+        if walks {
+            let delete = template.mirror && (template.delete || body.delete);
+            return self.queue_mirror(&job_name, Mirror::from_template(&template), method, delete);
+        }
+
+        let (source, destination, upload) = (PathBuf::from(&template.source), PathBuf::from(&template.destination), template.upload);
+        self.queue_copy(&job_name, method, source, destination, upload)
+    }
+
+    fn sync(&self, body: SyncBody) -> ApiResult {
+        let host = self.find_host(&body.host)?;
+        if !body.download && !body.local_dir.is_dir() {
+            return Err(ApiError::bad_request(format!("Not a directory: {}", body.local_dir.display())));
+        }
+        let method = connect(&host)?;
+
+        let (source, target) = if body.download {
+            (body.remote_dir.clone(), body.local_dir.clone())
+        } else {
+            (body.local_dir.clone(), body.remote_dir.clone())
+        };
+        let mirror = Mirror { source, target, upload: !body.download, filter: PathFilter::default() };
+        let job_name = if body.download {
+            format!("Sync {}:{} -> {}", host.name, body.remote_dir.display(), body.local_dir.display())
+        } else {
+            format!("Sync {} -> {}:{}", body.local_dir.display(), host.name, body.remote_dir.display())
+        };
+        self.queue_mirror(&job_name, mirror, method, body.delete)
+    }
+
+    fn transfer(&self, body: TransferBody) -> ApiResult {
+        let host = self.find_host(&body.host)?;
+        if !body.download && !body.source.is_file() {
+            return Err(ApiError::bad_request(format!("Not a file: {}", body.source.display())));
+        }
+        let method = connect(&host)?;
+        let job_name = format!("Copy {}", body.source.file_name().unwrap_or_default().to_string_lossy());
+        self.queue_copy(&job_name, method, body.source, body.destination, !body.download)
+    }
+
+    fn process(&self, body: ProcessBody) -> ApiResult {
+        let preset = ProcessingPreset::find(&body.preset).ok_or_else(|| {
+            let names: Vec<&str> = BUILTIN_PRESETS.iter().map(|p| p.name).collect();
+            ApiError::bad_request(format!("Unknown preset '{}' (available: {})", body.preset, names.join(", ")))
+        })?;
+
+        let (inputs, default_output) = if body.input.is_dir() {
+            (find_images_in_dir(&body.input), body.input.join("processed"))
+        } else if is_image_file(&body.input) {
+            let parent = body.input.parent().unwrap_or_else(|| Path::new("."));
+            (vec![body.input.clone()], parent.join("processed"))
+        } else {
+            return Err(ApiError::bad_request(format!("No images found at {}", body.input.display())));
+        };
+        let output_dir = body.output.unwrap_or(default_output);
+        fs::create_dir_all(&output_dir)
+            .map_err(|e| ApiError::bad_request(format!("Could not create {}: {}", output_dir.display(), e)))?;
+
+        let (name_template, upload) = {
+            let config = self.config.lock().unwrap();
+            (config.output_name_template.clone(), preset_upload_target(&config, preset.name))
+        };
+        let name = format!("{} ({})", body.input.file_name().unwrap_or_default().to_string_lossy(), preset.name);
+        let job = ProcessingJob {
+            name: name.clone(),
+            inputs,
+            output_dir: Some(output_dir),
+            pipeline: Pipeline::Preset(preset),
+            name_template,
+            upload,
+        };
+
+        let queue = self.queue.clone();
+        let id = on_main_thread(move || queue.enqueue(job))?;
+        Ok((202, json!({ "job": id, "name": name })))
+    }
+
+    /// Compare the folders now, so a bad path fails the request, and queue
+    /// the copies and removals
+    fn queue_mirror(&self, name: &str, mirror: Mirror, method: Box<dyn TransferMethod>, delete: bool) -> ApiResult {
+        let mut plan = mirror.plan(method.as_ref())?;
+        if !delete {
+            plan.deletions.clear();
+        }
+        let total = plan.copies.len() + plan.deletions.len();
+        let summary = json!({ "copies": plan.copies.len(), "unchanged": plan.unchanged, "deletions": plan.deletions.len() });
+        let to_trash = self.config.lock().unwrap().remote_trash;
+
+        let work: TaskWork = Box::new(move |progress, cancel| {
+            let result = mirror
+                .copy(method.as_ref(), &plan.copies, progress, cancel)
+                .and_then(|copied| mirror.delete(method.as_ref(), &plan.deletions, to_trash).map(|removed| (copied, removed)));
+            match result {
+                Ok((copied, removed)) => {
+                    log::info!("{} -> {}: {} copied, {} removed", mirror.source.display(), mirror.target.display(), copied, removed);
+                    Ok(())
+                },
+                // The queue reports the job as cancelled
+                Err(TransferError::Cancelled) => Ok(()),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+        let id = self.queue_task(name, total, work)?;
+        Ok((202, json!({ "job": id, "name": name, "plan": summary })))
+    }
+
+    fn queue_copy(&self, name: &str, method: Box<dyn TransferMethod>, source: PathBuf, destination: PathBuf, upload: bool) -> ApiResult {
+        let work: TaskWork = Box::new(move |_progress, _cancel| {
+            let result = if upload {
+                method.upload_file(&source, &destination)
+            } else {
+                method.download_file(&source, &destination)
+            };
+            result.map_err(|e| e.to_string())
+        });
+        let id = self.queue_task(name, 1, work)?;
+        Ok((202, json!({ "job": id, "name": name })))
+    }
+
+    fn queue_task(&self, name: &str, total: usize, work: TaskWork) -> Result<u64, ApiError> {
+        let queue = self.queue.clone();
+        let name = name.to_string();
+        on_main_thread(move || queue.enqueue_task(&name, total, work))
+    }
+}
+
+/// Connect to `host` without anyone to type a password: key authentication,
+/// or the password in the environment as for the command line
+fn connect(host: &Host) -> Result<Box<dyn TransferMethod>, ApiError> {
+    connect_unattended(host).map_err(|e| ApiError::bad_request(e.to_string()))
+}
+
+/// Run `work` on the FLTK main thread, where the queue announces its
+/// changes to the window, and wait for its result
+fn on_main_thread<T, F>(work: F) -> Result<T, ApiError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut work = Some(work);
+    app::awake_callback(move || {
+        if let Some(work) = work.take() {
+            let _ = sender.send(work());
+        }
+    });
+    receiver
+        .recv_timeout(MAIN_THREAD_TIMEOUT)
+        .map_err(|_| ApiError { status: 503, message: "The application is busy; try again".to_string() })
+}
+
+/// Simple requests from web pages can't carry this type, so insisting on it
+/// keeps them from starting work
+fn check_content_type(request: &Request) -> Result<(), ApiError> {
+    if request.method != "POST" {
+        return Ok(());
+    }
+    let media_type = request.content_type.as_deref().and_then(|value| value.split(';').next()).map(str::trim);
+    if media_type.is_some_and(|media_type| media_type.eq_ignore_ascii_case("application/json")) {
+        Ok(())
+    } else {
+        Err(ApiError { status: 415, message: "Send POST bodies as `Content-Type: application/json`".to_string() })
+    }
+}
+
+/// Read one line of at most `MAX_LINE_BYTES`
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> Result<usize, ApiError> {
+    let read = reader
+        .take(MAX_LINE_BYTES as u64)
+        .read_line(line)
+        .map_err(|e| ApiError::bad_request(e.to_string()))?;
+    if read == MAX_LINE_BYTES && !line.ends_with('\n') {
+        return Err(ApiError { status: 431, message: "Request line too long".to_string() });
+    }
+    Ok(read)
+}
+
+fn read_request(stream: &TcpStream) -> Result<Request, ApiError> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    read_line(&mut reader, &mut line)?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(ApiError::bad_request("Malformed request line"));
+    };
+    let (method, path) = (method.to_ascii_uppercase(), path.to_string());
+
+    let mut content_length = 0;
+    let mut authorization = None;
+    let mut host = None;
+    let mut origin = None;
+    let mut content_type = None;
+    for _ in 0..MAX_HEADERS {
+        let mut header = String::new();
+        let read = read_line(&mut reader, &mut header)?;
+        if read == 0 || header.trim().is_empty() {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).map_err(|e| ApiError::bad_request(format!("Incomplete body: {}", e)))?;
+            return Ok(Request { method, path, authorization, host, origin, content_type, body });
+        }
+
+        let Some((name, value)) = header.split_once(':') else { continue };
+        match name.trim().to_ascii_lowercase().as_str() {
+            "content-length" => {
+                content_length = value.trim().parse().map_err(|_| ApiError::bad_request("Bad Content-Length"))?;
+                if content_length > MAX_BODY_BYTES {
+                    return Err(ApiError { status: 413, message: "Request body too large".to_string() });
+                }
+            },
+            "authorization" => authorization = Some(value.trim().to_string()),
+            "host" => host = Some(value.trim().to_string()),
+            "origin" => origin = Some(value.trim().to_string()),
+            "content-type" => content_type = Some(value.trim().to_string()),
+            _ => {},
+        }
+    }
+    Err(ApiError::bad_request("Too many headers"))
+}
+
+/// The host part of `host[:port]` or `[v6]:port`
+fn authority_host(authority: &str) -> &str {
+    match authority.strip_prefix('[') {
+        Some(rest) => rest.split(']').next().unwrap_or(""),
+        None => authority.split(':').next().unwrap_or(""),
+    }
+}
+
+fn is_loopback_name(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Compare secrets without returning early at the first difference, which
+/// would let a client guess the token a byte at a time from response times
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+fn parse_job_id(text: &str) -> Result<u64, ApiError> {
+    text.parse().map_err(|_| ApiError::bad_request(format!("'{}' is not a job id", text)))
+}
+
+fn job_json(job: &JobStatus) -> Value {
+    json!({
+        "id": job.id,
+        "name": job.name,
+        "state": job.state.label(),
+        "done": job.done,
+        "total": job.total,
+        "errors": job.errors,
+    })
+}
+
+/// Undo `%XX` escapes in a path segment, e.g. of template names with spaces
+fn percent_decode(segment: &str) -> String {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = if bytes[index] == b'%' {
+            segment.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        } else {
+            None
+        };
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            },
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        202 => "Accepted",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        431 => "Request Header Fields Too Large",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, content_type: Option<&str>) -> Request {
+        Request {
+            method: method.to_string(),
+            path: "/api/process".to_string(),
+            authorization: None,
+            host: Some("127.0.0.1:8765".to_string()),
+            origin: None,
+            content_type: content_type.map(str::to_string),
+            body: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn authority_host_drops_the_port() {
+        assert_eq!(authority_host("127.0.0.1:8765"), "127.0.0.1");
+        assert_eq!(authority_host("localhost"), "localhost");
+        assert_eq!(authority_host("[::1]:8765"), "::1");
+    }
+
+    #[test]
+    fn loopback_names() {
+        assert!(is_loopback_name("localhost"));
+        assert!(is_loopback_name("127.0.0.1"));
+        assert!(is_loopback_name("::1"));
+        assert!(!is_loopback_name("evil.example.com"));
+        assert!(!is_loopback_name("192.168.1.10"));
+    }
+
+    #[test]
+    fn tokens_compare_whole() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert!(!constant_time_eq(b"", b"secret"));
+    }
+
+    #[test]
+    fn posts_must_be_json() {
+        assert!(check_content_type(&request("POST", Some("application/json"))).is_ok());
+        assert!(check_content_type(&request("POST", Some("application/json; charset=utf-8"))).is_ok());
+        assert!(check_content_type(&request("POST", Some("text/plain"))).is_err());
+        assert!(check_content_type(&request("POST", None)).is_err());
+        assert!(check_content_type(&request("GET", None)).is_ok());
+    }
+
+    #[test]
+    fn long_lines_are_refused() {
+        let long = format!("{}\n", "a".repeat(MAX_LINE_BYTES * 2));
+        let mut line = String::new();
+        assert!(read_line(&mut long.as_bytes(), &mut line).is_err());
+
+        let mut line = String::new();
+        assert_eq!(read_line(&mut &b"GET /api HTTP/1.0\r\n"[..], &mut line).ok(), Some(19));
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::config::{Config, OutputDestination};
+use crate::core::dropbox::DropboxLink;
+use crate::core::image::BUILTIN_PRESETS;
+use crate::core::utils::{open_with_default_app, AppError};
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::core::locale::trf;
+
+/// Let the user choose where one preset's results are sent besides its
+/// output folder: nowhere else, a folder on a saved host or Dropbox
+pub fn preset_destinations_dialog(config: Arc<Mutex<Config>>) {
+    let options: Vec<String> = {
+        let config = config.lock().unwrap();
+        BUILTIN_PRESETS
+            .iter()
+            .map(|preset| format!("{}: {}", preset.name, config.preset_destination(preset.name).label()))
+            .collect()
+    };
+    let Some(index) = dialogs::select_dialog("Preset Destinations", "Preset to send elsewhere:", &options) else { return };
+    let preset = BUILTIN_PRESETS[index].name;
+
+    let destination = match dialogs::choice_dialog(
+        "Preset Destinations",
+        &trf("Send results of '{}' to:", &[&preset]),
+        &["Output Folder Only", "A Pi...", "Dropbox...", "Cancel"]
+    ) {
+        0 => OutputDestination::Local,
+        1 => {
+            let hosts: Vec<String> = config.lock().unwrap().hosts.iter().map(|h| h.name.clone()).collect();
+            if hosts.is_empty() {
+                dialogs::message_dialog("Preset Destinations", "Save a host first.");
+                return;
+            }
+            let Some(host) = dialogs::select_dialog("Preset Destinations", "Host:", &hosts) else { return };
+            let initial = format!("processed/{}", preset);
+            let Some(remote_dir) = dialogs::input_dialog("Preset Destinations", "Folder on the Pi:", &initial) else { return };
+            OutputDestination::Pi { host: hosts[host].clone(), remote_dir: remote_dir.trim().to_string() }
+        },
+        2 => {
+            if config.lock().unwrap().dropbox.refresh_token.is_none() {
+                dialogs::message_dialog("Preset Destinations", "Link a Dropbox account first.");
+                link_dropbox_account(config);
+                return;
+            }
+            let initial = format!("/Pi Remote Manager/{}", preset);
+            let Some(folder) = dialogs::input_dialog("Preset Destinations", "Dropbox folder:", &initial) else { return };
+            OutputDestination::Dropbox { folder: folder.trim().to_string() }
+        },
+        _ => return,
+    };
+
+    log::info!("Results of preset '{}' now go to {}", preset, destination.label());
+    config.lock().unwrap().set_preset_destination(preset, destination);
+}
+
+/// Link the Dropbox account processed images are uploaded to
+///
+/// The user registers their own Dropbox app and enters its key; the browser
+/// then asks them to allow access and shows a code to paste back here.
+pub fn link_dropbox_account(config: Arc<Mutex<Config>>) {
+    let current_key = config.lock().unwrap().dropbox.app_key.clone();
+    let Some(app_key) = dialogs::input_dialog(
+        "Link Dropbox Account",
+        "App key of your Dropbox app (from dropbox.com/developers/apps):",
+        &current_key
+    ) else { return };
+    let app_key = app_key.trim().to_string();
+    if app_key.is_empty() {
+        return;
+    }
+
+    let link = match DropboxLink::start(&app_key) {
+        Ok(link) => link,
+        Err(e) => {
+            dialogs::error_dialog("Could not link Dropbox", &AppError::NetworkError(e));
+            return;
+        }
+    };
+    if let Err(e) = open_with_default_app(Path::new(&link.url)) {
+        log::warn!("{}", e);
+    }
+    let prompt = format!("Allow access in the browser, then paste the code Dropbox shows.\n\nIf no browser opened, visit:\n{}", link.url);
+    let Some(code) = dialogs::input_dialog("Link Dropbox Account", &prompt, "") else { return };
+
+    run_in_background(
+        move || link.finish(&code),
+        move |result| match result {
+            Ok(refresh_token) => {
+                {
+                    let mut config = config.lock().unwrap();
+                    config.dropbox.app_key = app_key;
+                    config.dropbox.refresh_token = Some(refresh_token);
+                }
+                log::info!("Linked a Dropbox account");
+                dialogs::message_dialog("Link Dropbox Account", "Dropbox is linked. Choose it under Processing > Preset Destinations.");
+            },
+            Err(e) => dialogs::error_dialog("Could not link Dropbox", &AppError::NetworkError(e)),
+        }
+    );
+}
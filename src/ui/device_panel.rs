@@ -0,0 +1,348 @@
+use fltk::{
+    app,
+    button::{Button, CheckButton},
+    enums::{Color, FrameType, Align},
+    frame::Frame,
+    group::Group,
+    misc::{Progress, Spinner},
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+use crate::core::locale::tr;
+use crate::core::device::{DeviceStatus, STATUS_COMMAND, parse_status, format_uptime};
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::theme::font_size;
+
+/// Interval of the timer that drives auto refresh (seconds)
+const TICK_INTERVAL: f64 = 0.5;
+
+/// Upper bound of the temperature gauge; the Pi throttles at 80-85°C
+const MAX_TEMPERATURE_C: f64 = 85.0;
+
+/// Background polling state shared with the worker thread
+#[derive(Default)]
+struct PollState {
+    busy: bool,
+    last_poll: Option<Instant>,
+    result: Option<Result<DeviceStatus, String>>,
+}
+
+/// Dashboard showing health information for the connected Pi
+pub struct DevicePanel {
+    group: Group,
+    host_frame: Frame,
+    cpu_gauge: Progress,
+    temp_gauge: Progress,
+    memory_gauge: Progress,
+    disk_gauge: Progress,
+    uptime_frame: Frame,
+    load_frame: Frame,
+    refresh_button: Button,
+    auto_refresh: CheckButton,
+    interval_spinner: Spinner,
+    poll_state: Arc<Mutex<PollState>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+    config: Arc<Mutex<Config>>,
+}
+
+impl Clone for DevicePanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            host_frame: self.host_frame.clone(),
+            cpu_gauge: self.cpu_gauge.clone(),
+            temp_gauge: self.temp_gauge.clone(),
+            memory_gauge: self.memory_gauge.clone(),
+            disk_gauge: self.disk_gauge.clone(),
+            uptime_frame: self.uptime_frame.clone(),
+            load_frame: self.load_frame.clone(),
+            refresh_button: self.refresh_button.clone(),
+            auto_refresh: self.auto_refresh.clone(),
+            interval_spinner: self.interval_spinner.clone(),
+            poll_state: self.poll_state.clone(),
+            remote_browser: self.remote_browser.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl DevicePanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>,
+        config: Arc<Mutex<Config>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let label_width = 120;
+        let row_height = 25;
+        let gauge_width = w - label_width - 3 * padding;
+
+        let mut host_frame = Frame::new(
+            x + padding,
+            y + padding,
+            w - 2 * padding,
+            row_height,
+            "Not connected"
+        );
+        host_frame.set_label_size(font_size(14));
+        host_frame.set_align(Align::Left | Align::Inside);
+
+        let make_gauge = |row: i32, title: &str, max: f64| {
+            let row_y = y + padding + (row_height + padding) * row;
+
+            let mut label = Frame::new(x + padding, row_y, label_width, row_height, None);
+            label.set_label(title);
+            label.set_align(Align::Left | Align::Inside);
+
+            let mut gauge = Progress::new(
+                x + 2 * padding + label_width,
+                row_y,
+                gauge_width,
+                row_height,
+                "—"
+            );
+            gauge.set_minimum(0.0);
+            gauge.set_maximum(max);
+            gauge.set_value(0.0);
+            gauge.set_color(Color::from_rgb(230, 230, 230));
+            gauge.set_selection_color(Color::from_rgb(0, 160, 0));
+            gauge
+        };
+
+        let cpu_gauge = make_gauge(1, "CPU load:", 100.0);
+        let temp_gauge = make_gauge(2, "Temperature:", MAX_TEMPERATURE_C);
+        let memory_gauge = make_gauge(3, "Memory:", 100.0);
+        let disk_gauge = make_gauge(4, "Disk (/):", 100.0);
+
+        let info_y = y + padding + (row_height + padding) * 5;
+        let mut uptime_frame = Frame::new(x + padding, info_y, w - 2 * padding, row_height, None);
+        uptime_frame.set_align(Align::Left | Align::Inside);
+
+        let mut load_frame = Frame::new(x + padding, info_y + row_height, w - 2 * padding, row_height, None);
+        load_frame.set_align(Align::Left | Align::Inside);
+
+        // Refresh controls
+        let controls_y = y + h - padding - row_height;
+        let refresh_button = Button::new(x + padding, controls_y, 100, row_height, "Refresh");
+
+        let mut auto_refresh = CheckButton::new(
+            x + 2 * padding + 100,
+            controls_y,
+            160,
+            row_height,
+            "Auto refresh every"
+        );
+        auto_refresh.set_checked(true);
+
+        let refresh_secs = config.lock().unwrap().dashboard_refresh_secs;
+        let mut interval_spinner = Spinner::new(
+            x + 3 * padding + 260,
+            controls_y,
+            70,
+            row_height,
+            None
+        );
+        interval_spinner.set_minimum(2.0);
+        interval_spinner.set_maximum(3600.0);
+        interval_spinner.set_step(1.0);
+        interval_spinner.set_value(refresh_secs as f64);
+
+        let mut seconds_label = Frame::new(
+            x + 4 * padding + 330,
+            controls_y,
+            60,
+            row_height,
+            "seconds"
+        );
+        seconds_label.set_align(Align::Left | Align::Inside);
+
+        group.end();
+
+        let mut panel = DevicePanel {
+            group,
+            host_frame,
+            cpu_gauge,
+            temp_gauge,
+            memory_gauge,
+            disk_gauge,
+            uptime_frame,
+            load_frame,
+            refresh_button,
+            auto_refresh,
+            interval_spinner,
+            poll_state: Arc::new(Mutex::new(PollState::default())),
+            remote_browser,
+            config,
+        };
+
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.refresh_button.set_callback(move |_| {
+            panel.start_poll();
+        });
+
+        // Persist the refresh interval
+        let config = self.config.clone();
+        self.interval_spinner.set_callback(move |s| {
+            let mut config = config.lock().unwrap();
+            config.dashboard_refresh_secs = s.value() as u32;
+            if let Err(e) = config.save() {
+                log::error!("Failed to save dashboard interval: {}", e);
+            }
+        });
+
+        // A single ticking timer collects results and schedules auto refreshes
+        let mut panel = self.clone();
+        app::add_timeout3(TICK_INTERVAL, move |handle| {
+            panel.tick();
+            app::repeat_timeout3(TICK_INTERVAL, handle);
+        });
+    }
+
+    fn tick(&mut self) {
+        let (finished, due) = {
+            let mut state = self.poll_state.lock().unwrap();
+            let interval = Duration::from_secs(self.interval_spinner.value().max(1.0) as u64);
+            let due = !state.busy && state.last_poll.map_or(true, |t| t.elapsed() >= interval);
+            (state.result.take(), due)
+        };
+
+        match finished {
+            Some(Ok(status)) => self.show_status(&status),
+            Some(Err(e)) => {
+                self.host_frame.set_label(&format!("Status unavailable: {}", e));
+                self.host_frame.set_label_color(Color::Red);
+            },
+            None => {}
+        }
+
+        // Only poll while the Device tab is actually visible
+        if due && self.auto_refresh.is_checked() && self.group.visible_r() {
+            self.start_poll();
+        }
+    }
+
+    fn start_poll(&mut self) {
+        let method = self.remote_browser.lock().ok().and_then(|b| b.get_transfer_method());
+
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.host_frame.set_label(&tr("Not connected"));
+                self.host_frame.set_label_color(Color::Black);
+                return;
+            }
+        };
+
+        {
+            let mut state = self.poll_state.lock().unwrap();
+            if state.busy {
+                return;
+            }
+            state.busy = true;
+            state.last_poll = Some(Instant::now());
+        }
+
+        self.host_frame.set_label(&format!("{} (refreshing…)", method.get_description()));
+        self.host_frame.set_label_color(Color::Black);
+
+        let poll_state = self.poll_state.clone();
+        thread::spawn(move || {
+            let result = match method.exec(STATUS_COMMAND) {
+                Ok(output) if output.stdout.trim().is_empty() => Err(output.stderr.trim().to_string()),
+                Ok(output) => Ok(parse_status(&output.stdout)),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let mut state = poll_state.lock().unwrap();
+            state.busy = false;
+            state.result = Some(result);
+            drop(state);
+
+            app::awake();
+        });
+    }
+
+    fn show_status(&mut self, status: &DeviceStatus) {
+        let description = self.remote_browser.lock().ok()
+            .and_then(|b| b.get_transfer_method())
+            .map(|m| m.get_description())
+            .unwrap_or_else(|| "Device".to_string());
+        self.host_frame.set_label(&description);
+        self.host_frame.set_label_color(Color::Black);
+
+        let cpu = status.cpu_load_percent() as f64;
+        Self::set_gauge(&mut self.cpu_gauge, cpu, 100.0, &format!("{:.0}% of {} cores", cpu, status.cpu_count));
+
+        match status.temperature_c {
+            Some(temp) => Self::set_gauge(
+                &mut self.temp_gauge,
+                temp as f64,
+                MAX_TEMPERATURE_C,
+                &format!("{:.1} °C", temp)
+            ),
+            None => Self::set_gauge(&mut self.temp_gauge, 0.0, MAX_TEMPERATURE_C, "unavailable"),
+        }
+
+        let memory = status.memory_used_percent() as f64;
+        let used_mb = status.mem_total_kb.saturating_sub(status.mem_available_kb) / 1024;
+        Self::set_gauge(
+            &mut self.memory_gauge,
+            memory,
+            100.0,
+            &format!("{:.0}% ({} / {} MB)", memory, used_mb, status.mem_total_kb / 1024)
+        );
+
+        let disk = status.disk_used_percent() as f64;
+        Self::set_gauge(
+            &mut self.disk_gauge,
+            disk,
+            100.0,
+            &format!(
+                "{:.0}% ({:.1} / {:.1} GB)",
+                disk,
+                status.disk_used_kb as f64 / (1024.0 * 1024.0),
+                status.disk_total_kb as f64 / (1024.0 * 1024.0)
+            )
+        );
+
+        self.uptime_frame.set_label(&format!("Uptime: {}", format_uptime(status.uptime_secs)));
+        self.load_frame.set_label(&format!(
+            "Load average: {:.2}, {:.2}, {:.2}",
+            status.load_average[0], status.load_average[1], status.load_average[2]
+        ));
+
+        self.group.redraw();
+    }
+
+    fn set_gauge(gauge: &mut Progress, value: f64, max: f64, label: &str) {
+        let ratio = if max > 0.0 { value / max } else { 0.0 };
+        let color = if ratio >= 0.9 {
+            Color::from_rgb(200, 0, 0)
+        } else if ratio >= 0.7 {
+            Color::from_rgb(230, 140, 0)
+        } else {
+            Color::from_rgb(0, 160, 0)
+        };
+
+        gauge.set_value(value.min(max));
+        gauge.set_selection_color(color);
+        gauge.set_label(label);
+    }
+}
@@ -0,0 +1,219 @@
+use fltk::{
+    enums::Align,
+    menu::Choice,
+    prelude::*,
+};
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::transfer::{SessionManager, TransferMethod};
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+
+/// Dropdown above the remote pane choosing which connected device it shows
+pub struct DeviceSelector {
+    choice: Choice,
+    sessions: Arc<Mutex<SessionManager>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+    /// Session id of each entry in the dropdown
+    ids: Arc<Mutex<Vec<usize>>>,
+    events: EventBus,
+}
+
+impl Clone for DeviceSelector {
+    fn clone(&self) -> Self {
+        Self {
+            choice: self.choice.clone(),
+            sessions: self.sessions.clone(),
+            remote_browser: self.remote_browser.clone(),
+            ids: self.ids.clone(),
+            events: self.events.clone(),
+        }
+    }
+}
+
+impl DeviceSelector {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        sessions: Arc<Mutex<SessionManager>>,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>,
+        events: EventBus
+    ) -> Self {
+        let mut choice = Choice::new(x, y, w, h, "Device:");
+        choice.set_align(Align::Left);
+        choice.set_tooltip("Switch between connected devices");
+        choice.deactivate();
+
+        let mut selector = DeviceSelector {
+            choice,
+            sessions,
+            remote_browser,
+            ids: Arc::new(Mutex::new(Vec::new())),
+            events,
+        };
+
+        let mut selector_clone = selector.clone();
+        selector.choice.set_callback(move |c| {
+            let id = selector_clone.ids.lock().unwrap().get(c.value().max(0) as usize).copied();
+            if let Some(id) = id {
+                selector_clone.switch_to(id);
+            }
+        });
+
+        selector
+    }
+
+    /// Right-align the dropdown in the title row of the remote pane at
+    /// (x, y, w, h), narrowing it when the pane is narrow
+    pub fn fit_to_pane(&mut self, (x, y, w, _h): (i32, i32, i32, i32)) {
+        // Leave room for the "Device:" label and the pane title
+        let width = (w - 180).clamp(80, 220);
+        self.choice.resize(x + w - width - 10, y + 10, width, self.choice.h());
+        if let Some(mut parent) = self.choice.parent() {
+            parent.redraw();
+        }
+    }
+
+    /// Record a connection the remote pane is already showing
+    pub fn add_session(
+        &mut self,
+        name: &str,
+        hostname: &str,
+        username: &str,
+        password: Option<String>,
+        method: Box<dyn TransferMethod>,
+        current_dir: PathBuf
+    ) -> usize {
+        self.remember_current_directory();
+
+        let id = self.sessions.lock().unwrap().add(name, hostname, username, password, method, current_dir);
+        self.refresh();
+        id
+    }
+
+    /// Show another connected device in the remote pane
+    pub fn switch_to(&mut self, id: usize) {
+        if self.sessions.lock().unwrap().active_id() == Some(id) {
+            return;
+        }
+
+        self.remember_current_directory();
+
+        let session = {
+            let mut sessions = self.sessions.lock().unwrap();
+            if !sessions.set_active(id) {
+                return;
+            }
+            sessions.active().map(|s| (
+                s.hostname.clone(),
+                s.username.clone(),
+                s.password.clone(),
+                s.method.clone_box(),
+                s.current_dir.clone(),
+            ))
+        };
+
+        if let Some(session) = session {
+            self.show_session(session);
+        }
+
+        self.refresh();
+    }
+
+    /// Close the connection shown in the remote pane; another connected
+    /// device takes its place, else the pane is emptied
+    pub fn disconnect_active(&mut self) {
+        let next = {
+            let mut sessions = self.sessions.lock().unwrap();
+            if let Some(closed) = sessions.active_id().and_then(|id| sessions.remove(id)) {
+                log::info!("Disconnected from {}@{}", closed.username, closed.hostname);
+            }
+            sessions.active().map(|s| (
+                s.hostname.clone(),
+                s.username.clone(),
+                s.password.clone(),
+                s.method.clone_box(),
+                s.current_dir.clone(),
+            ))
+        };
+
+        match next {
+            Some(session) => self.show_session(session),
+            None => {
+                if let Ok(mut browser) = self.remote_browser.lock() {
+                    browser.disconnect();
+                }
+                self.events.publish(AppEvent::ConnectionChanged { hostname: None });
+            },
+        }
+
+        self.refresh();
+    }
+
+    /// Point the remote pane at a session's connection and directory
+    fn show_session(
+        &mut self,
+        (hostname, username, password, method, dir): (String, String, Option<String>, Box<dyn TransferMethod>, PathBuf)
+    ) {
+        log::info!("Switching remote pane to {}@{}", username, hostname);
+
+        if let Ok(mut browser) = self.remote_browser.lock() {
+            browser.current_hostname = Some(hostname.clone());
+            browser.current_username = Some(username);
+            browser.current_password = password;
+            browser.set_remote_directory(&dir, method);
+        }
+
+        self.events.publish(AppEvent::ConnectionChanged { hostname: Some(hostname) });
+    }
+
+    /// Store the directory shown in the remote pane with the active session
+    fn remember_current_directory(&mut self) {
+        let current_dir = match self.remote_browser.lock() {
+            Ok(browser) if browser.is_remote() => browser.get_current_directory(),
+            _ => return,
+        };
+
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(id) = sessions.active_id() {
+            if let Some(session) = sessions.get_mut(id) {
+                session.current_dir = current_dir;
+            }
+        }
+    }
+
+    /// Rebuild the dropdown from the session manager
+    pub fn refresh(&mut self) {
+        let sessions = self.sessions.lock().unwrap();
+        let mut ids = self.ids.lock().unwrap();
+
+        self.choice.clear();
+        ids.clear();
+
+        for session in sessions.sessions() {
+            // Escape characters the menu would interpret
+            self.choice.add_choice(&session.label().replace('/', "\\/").replace('|', "\\|"));
+            ids.push(session.id);
+        }
+
+        if let Some(index) = sessions.active_id().and_then(|id| ids.iter().position(|i| *i == id)) {
+            self.choice.set_value(index as i32);
+        }
+
+        if sessions.len() > 1 {
+            self.choice.activate();
+        } else {
+            self.choice.deactivate();
+        }
+
+        self.choice.redraw();
+    }
+
+    pub fn sessions(&self) -> Arc<Mutex<SessionManager>> {
+        self.sessions.clone()
+    }
+}
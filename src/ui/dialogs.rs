@@ -1,25 +1,42 @@
 // src/ui/dialogs.rs
 pub mod dialogs {
     use std::sync::{Arc, Mutex};
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::rc::Rc;
     use std::cell::RefCell;
     use fltk::{
         app,
-        button::Button,
+        browser::MultiBrowser,
+        button::{Button, CheckButton},
         dialog::{FileDialog, FileDialogType},
-        enums::{Align, Color},
+        enums::{Align, CallbackTrigger, Color, Font},
         frame::Frame,
-        input::Input,
+        input::{FloatInput, Input, IntInput},
         menu::Choice,
+        text::{TextDisplay, TextBuffer},
+        valuator::HorNiceSlider,
         prelude::*,
-        window::Window,
     };
-    use crate::config::{Config, Host};
+    use crate::config::{Appearance, Config, Host, HostAddress, HostGroup, RetryPolicy, TransferOptions, TransferProtocol};
+    use crate::core::image::{ImageFormat, OperationParam};
+    use crate::core::utils::{
+        AppError, AppResult, OutputNameContext, format_output_name, DEFAULT_OUTPUT_NAME_TEMPLATE, OUTPUT_NAME_TOKENS,
+    };
+    use crate::core::device::{Capabilities, probe_local, remote_probe_command, parse_remote_probe};
+    use crate::core::locale::{tr, trf};
+    use crate::ui::modal::ModalDialog;
+    use crate::ui::theme::{font_size, DEFAULT_FONT_SIZE, FONT_SIZES, UI_SCALES};
+    use crate::ui::executor::run_in_background;
+    use crate::transfer::{ProtocolChoice, SSHTransfer, TransferMethod};
+    use crate::transfer::key_setup::{default_key_path, ensure_keypair, install_public_key, verify_key_login};
+    use crate::transfer::wake::parse_mac_address;
+
+    // Largest width or height the resize dialog accepts
+    const MAX_RESIZE_DIMENSION: u32 = 20_000;
 
     pub fn open_file_dialog(title: &str, filter: &str) -> Option<PathBuf> {
         let mut dialog = FileDialog::new(FileDialogType::BrowseFile);
-        dialog.set_title(title);
+        dialog.set_title(&tr(title));
         
         if !filter.is_empty() {
             dialog.set_filter(filter);
@@ -37,11 +54,51 @@ pub mod dialogs {
 
     pub fn save_file_dialog(title: &str, filter: &str) -> Option<PathBuf> {
         let mut dialog = FileDialog::new(FileDialogType::BrowseSaveFile);
-        dialog.set_title(title);
+        dialog.set_title(&tr(title));
+        
+        if !filter.is_empty() {
+            dialog.set_filter(filter);
+        }
+        
+        dialog.show();
+        
+        let filename = dialog.filename();
+        if filename.to_string_lossy().is_empty() {
+            None
+        } else {
+            Some(filename)
+        }
+    }
+
+    // Save dialog starting in the folder of `suggested` with its file name
+    // filled in
+    pub fn save_file_named_dialog(title: &str, filter: &str, suggested: &Path) -> Option<PathBuf> {
+        let mut dialog = FileDialog::new(FileDialogType::BrowseSaveFile);
+        dialog.set_title(&tr(title));
         
         if !filter.is_empty() {
             dialog.set_filter(filter);
         }
+        if let Some(dir) = suggested.parent().filter(|dir| dir.is_dir()) {
+            let _ = dialog.set_directory(&dir);
+        }
+        if let Some(name) = suggested.file_name() {
+            dialog.set_preset_file(&name.to_string_lossy());
+        }
+        
+        dialog.show();
+        
+        let filename = dialog.filename();
+        if filename.to_string_lossy().is_empty() {
+            None
+        } else {
+            Some(filename)
+        }
+    }
+    
+    pub fn open_directory_dialog(title: &str) -> Option<PathBuf> {
+        let mut dialog = FileDialog::new(FileDialogType::BrowseDir);
+        dialog.set_title(&tr(title));
         
         dialog.show();
         
@@ -56,22 +113,33 @@ pub mod dialogs {
     pub fn message_dialog(title: &str, message: &str) {
         choice_dialog(title, message, &["OK"]);
     }
+
+    // Ask for a line of text, pre-filled with `initial`; None when cancelled
+    pub fn input_dialog(title: &str, prompt: &str, initial: &str) -> Option<String> {
+        fltk::dialog::message_title(&tr(title));
+        fltk::dialog::input_default(&tr(prompt), initial)
+    }
+
+    // Single place failures are reported to the user: logs the error and
+    // shows what went wrong while doing `action` with a hint on how to fix it
+    pub fn error_dialog(action: &str, error: &AppError) {
+        log::error!("{}: {}", action, error);
+        message_dialog("Error", &format!("{}\n\n{}", tr(action), error.user_message()));
+    }
     // Add this to src/ui/dialogs.rs
 // This creates a password dialog for SSH connections
 
 pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
     use fltk::{
-        app,
         button::Button,
         enums::{Align, Color},
         frame::Frame,
         input::SecretInput,
-        window::Window,
         prelude::*,
     };
+    use crate::ui::modal::ModalDialog;
     
-    let mut dialog = Window::new(100, 100, 300, 150, title);
-    dialog.set_border(true);
+    let mut dialog = ModalDialog::new(300, 150, title);
     
     let padding = 10;
     let input_height = 25;
@@ -120,46 +188,37 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
     let password_result_clone = password_result.clone();
     
     // Cancel button callback
+    let mut dialog_cancel = dialog.clone();
     cancel_button.set_callback(move |_| {
-        if let Some(mut win) = app::first_window() {
-            win.hide();
-        }
+        dialog_cancel.close();
     });
     
     // OK button callback
     let password_input_clone = password_input.clone();
+    let mut dialog_ok = dialog.clone();
     ok_button.set_callback(move |_| {
         let password = password_input_clone.value();
         if !password.is_empty() {
             *password_result_clone.borrow_mut() = Some(password);
         }
         
-        if let Some(mut win) = app::first_window() {
-            win.hide();
-        }
+        dialog_ok.close();
     });
     
     // Set focus to password input and handle Enter key
     password_input.take_focus().ok();
     password_input.set_trigger(fltk::enums::CallbackTrigger::EnterKey);
     let password_clone = password_result.clone();
+    let mut dialog_enter = dialog.clone();
     password_input.set_callback(move |i| {
         let password = i.value();
         if !password.is_empty() {
             *password_clone.borrow_mut() = Some(password);
-            
-            if let Some(mut win) = app::first_window() {
-                win.hide();
-            }
+            dialog_enter.close();
         }
     });
     
-    dialog.end();
-    dialog.show();
-    
-    while dialog.shown() {
-        app::wait();
-    }
+    dialog.run();
     
     // Get the final result
     let result = password_result.borrow().clone();
@@ -174,8 +233,9 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         };
         
         // Create a custom dialog window
-        let mut dialog = Window::new(100, 100, 400, 400, "Connection Settings");
-        dialog.set_border(true);
+        // Extra height holds the capability report
+        let dialog_height = 610;
+        let mut dialog = ModalDialog::new(400, dialog_height, "Connection Settings");
         
         let padding = 10;
         let input_height = 25;
@@ -276,11 +336,26 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         let mut port_input = Input::new(
             padding + label_width, 
             padding * 5 + input_height * 4, 
-            input_width, 
+            70, 
             input_height,
             "22"
         );
         
+        // Program used for copies to this host
+        let mut method_choice = Choice::new(
+            padding + label_width + 140, 
+            padding * 5 + input_height * 4, 
+            input_width - 140, 
+            input_height,
+            "Method:"
+        );
+        method_choice.set_align(Align::Left);
+        for choice in ProtocolChoice::all() {
+            method_choice.add_choice(choice.label());
+        }
+        method_choice.set_value(0);
+        method_choice.set_tooltip("SCP works everywhere; Rsync resumes and skips unchanged files; SFTP needs OpenSSH 8.7+");
+        
         // Authentication method
         let mut auth_label = Frame::new(
             padding, 
@@ -331,10 +406,70 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         );
         browse_button.hide();
         
+        // Directory the remote browser opens in
+        let mut start_dir_label = Frame::new(
+            padding, 
+            padding * 8 + input_height * 7, 
+            label_width, 
+            input_height,
+            "Start in:"
+        );
+        start_dir_label.set_align(Align::Left | Align::Inside);
+        
+        let mut start_dir_input = Input::new(
+            padding + label_width, 
+            padding * 8 + input_height * 7, 
+            input_width, 
+            input_height,
+            ""
+        );
+        start_dir_input.set_tooltip("Remote directory to open on connect; leave empty for the home directory");
+        
+        // Other addresses of the same Pi, tried in order when the first fails
+        let mut fallback_label = Frame::new(
+            padding, 
+            padding * 9 + input_height * 8, 
+            label_width, 
+            input_height,
+            "Fallbacks:"
+        );
+        fallback_label.set_align(Align::Left | Align::Inside);
+        
+        let mut fallback_input = Input::new(
+            padding + label_width, 
+            padding * 9 + input_height * 8, 
+            input_width, 
+            input_height,
+            ""
+        );
+        fallback_input.set_tooltip(
+            "Addresses tried in order when the hostname does not answer, \
+             e.g. tailscale=100.64.0.5, ddns=mypi.example.org:2222"
+        );
+        
+        // Network card address for waking the Pi
+        let mut mac_label = Frame::new(
+            padding, 
+            padding * 10 + input_height * 9, 
+            label_width, 
+            input_height,
+            "MAC address:"
+        );
+        mac_label.set_align(Align::Left | Align::Inside);
+        
+        let mut mac_input = Input::new(
+            padding + label_width, 
+            padding * 10 + input_height * 9, 
+            input_width, 
+            input_height,
+            ""
+        );
+        mac_input.set_tooltip("For waking the Pi with a wake-on-LAN packet; leave empty if it has none");
+        
         // Connection test button
         let mut test_button = Button::new(
             padding, 
-            400 - padding * 2 - input_height * 2, 
+            dialog_height - padding * 2 - input_height * 2, 
             120, 
             input_height,
             "Test Connection"
@@ -342,10 +477,29 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         test_button.set_color(Color::from_rgb(0, 180, 0));
         test_button.set_label_color(Color::White);
         
+        // Generate a key if needed and install it on the Pi with the password
+        let mut key_setup_button = Button::new(
+            padding + 130, 
+            dialog_height - padding * 2 - input_height * 2, 
+            150, 
+            input_height,
+            "Set Up Key Auth"
+        );
+        key_setup_button.set_tooltip("Install an SSH key on the Pi so no password is needed");
+        
+        let mut options_button = Button::new(
+            padding + 290, 
+            dialog_height - padding * 2 - input_height * 2, 
+            100, 
+            input_height,
+            "Options..."
+        );
+        options_button.set_tooltip("Compression, rsync arguments and concurrent copies for this host");
+        
         // Buttons
         let mut cancel_button = Button::new(
             padding, 
-            400 - padding - input_height, 
+            dialog_height - padding - input_height, 
             100, 
             input_height,
             "Cancel"
@@ -353,7 +507,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         
         let mut save_button = Button::new(
             400 - padding - 100, 
-            400 - padding - input_height, 
+            dialog_height - padding - input_height, 
             100, 
             input_height,
             "Save"
@@ -364,7 +518,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         // Delete button (for existing hosts)
         let mut delete_button = Button::new(
             padding + 110, 
-            400 - padding - input_height, 
+            dialog_height - padding - input_height, 
             100, 
             input_height,
             "Delete"
@@ -375,13 +529,29 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         // Status message
         let mut status_frame = Frame::new(
             padding, 
-            400 - padding * 3 - input_height * 3, 
+            dialog_height - padding * 3 - input_height * 3, 
             400 - padding * 2, 
             input_height,
             ""
         );
         status_frame.set_align(Align::Left | Align::Inside);
         
+        // Capability report for this computer and, after a test, the Pi
+        let key_row_bottom = padding * 10 + input_height * 10;
+        let mut report_buffer = TextBuffer::default();
+        report_buffer.set_text(&Capabilities { local: probe_local(), remote: Vec::new() }.report());
+        
+        let mut report_display = TextDisplay::new(
+            padding,
+            key_row_bottom + padding,
+            400 - padding * 2,
+            dialog_height - padding * 4 - input_height * 3 - key_row_bottom - padding,
+            None
+        );
+        report_display.set_buffer(report_buffer.clone());
+        report_display.set_text_font(Font::Courier);
+        report_display.set_text_size(font_size(11));
+        
         // Initial state
         if !hosts.is_empty() {
             let host = &hosts[0];
@@ -389,6 +559,10 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             hostname_input.set_value(&host.hostname);
             username_input.set_value(&host.username);
             port_input.set_value(&host.port.to_string());
+            start_dir_input.set_value(host.default_remote_dir.as_deref().unwrap_or(""));
+            fallback_input.set_value(&HostAddress::format_list(&host.fallback_addresses));
+            mac_input.set_value(host.mac_address.as_deref().unwrap_or(""));
+            method_choice.set_value(ProtocolChoice::index_of(&host.transfer) as i32);
             
             if host.use_key_auth {
                 auth_choice.set_value(1); // SSH Key
@@ -404,18 +578,28 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         // Create a host result that will be returned at the end
         let host_result = Rc::new(RefCell::new(None::<Host>));
         
+        // Transfer options of the host being edited, changed through Options...
+        let transfer_options = Rc::new(RefCell::new(
+            hosts.first().map(|h| h.transfer.clone()).unwrap_or_default()
+        ));
+        
         // Host choice callback
         let hosts_clone = hosts.clone();
         let mut name_input_clone = name_input.clone();
         let mut hostname_input_clone = hostname_input.clone();
         let mut username_input_clone = username_input.clone();
         let mut port_input_clone = port_input.clone();
+        let mut start_dir_input_clone = start_dir_input.clone();
+        let mut fallback_input_clone = fallback_input.clone();
+        let mut mac_input_clone = mac_input.clone();
         let mut auth_choice_clone = auth_choice.clone();
         let mut key_input_clone = key_input.clone();
         let mut key_label_clone = key_label.clone();
         let mut key_input_inner = key_input.clone();
         let mut browse_button_clone = browse_button.clone();
         let mut delete_button_clone = delete_button.clone();
+        let transfer_options_choice = transfer_options.clone();
+        let mut method_choice_host = method_choice.clone();
         
         host_choice.set_callback(move |c| {
             let selection = c.value();
@@ -427,7 +611,12 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
                 hostname_input_clone.set_value(&host.hostname);
                 username_input_clone.set_value(&host.username);
                 port_input_clone.set_value(&host.port.to_string());
+                start_dir_input_clone.set_value(host.default_remote_dir.as_deref().unwrap_or(""));
+                fallback_input_clone.set_value(&HostAddress::format_list(&host.fallback_addresses));
+                mac_input_clone.set_value(host.mac_address.as_deref().unwrap_or(""));
                 delete_button_clone.activate();
+                *transfer_options_choice.borrow_mut() = host.transfer.clone();
+                method_choice_host.set_value(ProtocolChoice::index_of(&host.transfer) as i32);
                 
                 if host.use_key_auth {
                     auth_choice_clone.set_value(1); // SSH Key
@@ -451,6 +640,11 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
                 hostname_input_clone.set_value("");
                 username_input_clone.set_value("pi");
                 port_input_clone.set_value("22");
+                start_dir_input_clone.set_value("");
+                fallback_input_clone.set_value("");
+                mac_input_clone.set_value("");
+                *transfer_options_choice.borrow_mut() = TransferOptions::default();
+                method_choice_host.set_value(0);
                 auth_choice_clone.set_value(0); // Password
                 key_input_clone.set_value("");
                 key_label_clone.hide();
@@ -484,7 +678,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         // Browse button callback
         browse_button.set_callback(move |_| {
             let mut dialog = FileDialog::new(FileDialogType::BrowseFile);
-            dialog.set_title("Select SSH Key File");
+            dialog.set_title(&tr("Select SSH Key File"));
             dialog.show();
             
             let filename = dialog.filename();
@@ -500,8 +694,11 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         let auth_choice_clone = auth_choice.clone();
         let key_input_clone = key_input.clone();
         let mut status_frame_clone = status_frame.clone();
+        let mut report_buffer_clone = report_buffer.clone();
+        let host_choice_test = host_choice.clone();
+        let hosts_test = hosts.clone();
         
-        test_button.set_callback(move |_| {
+        test_button.set_callback(move |button| {
             let hostname = hostname_input_clone.value();
             let username = username_input_clone.value();
             let port_str = port_input_clone.value();
@@ -514,7 +711,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             
             // Validate inputs
             if hostname.is_empty() || username.is_empty() || port_str.is_empty() {
-                status_frame_clone.set_label("Error: All fields must be filled");
+                status_frame_clone.set_label(&tr("Error: All fields must be filled"));
                 status_frame_clone.set_label_color(Color::Red);
                 return;
             }
@@ -522,122 +719,225 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             let port = match port_str.parse::<u16>() {
                 Ok(p) => p,
                 Err(_) => {
-                    status_frame_clone.set_label("Error: Port must be a valid number");
+                    status_frame_clone.set_label(&tr("Error: Port must be a valid number"));
                     status_frame_clone.set_label_color(Color::Red);
                     return;
                 }
             };
             
             if use_key_auth && key_path.is_none() {
-                status_frame_clone.set_label("Error: SSH key file must be selected for key authentication");
+                status_frame_clone.set_label(&tr("Error: SSH key file must be selected for key authentication"));
                 status_frame_clone.set_label_color(Color::Red);
                 return;
             }
             
-            // Test connection using a command that prompts for password
-            status_frame_clone.set_label("Testing connection...");
-            status_frame_clone.set_label_color(Color::Blue);
-            app::flush();
-            
-            // This uses sshpass to handle password for SSH
-            use std::process::Command;
-            
-            let mut cmd;
-            let mut has_password = false;
-            
-            if !use_key_auth {
-                // For password auth, prompt for password using our custom dialog
-                let password = password_dialog(
+            // Password logins need the password up front; the test runs
+            // through the same transfer method the browser would use
+            let mut method = if use_key_auth {
+                SSHTransfer::new(hostname.clone(), username.clone(), port, true, key_path.map(PathBuf::from))
+            } else {
+                match password_dialog(
                     "SSH Password",
                     &format!("Enter password for {}@{}:", username, hostname)
-                );
-                
-                if let Some(pass) = password {
-                    // Use the password with sshpass
-                    cmd = Command::new("sshpass");
-                    cmd.arg("-p").arg(&pass);
-                    cmd.arg("ssh");
-                    has_password = true;
-                } else {
-                    // User canceled, abort connection test
-                    status_frame_clone.set_label("Connection test canceled");
-                    status_frame_clone.set_label_color(Color::Red);
-                    return;
-                }
-            } else {
-                // For key auth, use ssh directly
-                cmd = Command::new("ssh");
-                if let Some(path) = &key_path {
-                    cmd.arg("-i").arg(path);
+                ) {
+                    Some(password) => SSHTransfer::with_password(hostname.clone(), username.clone(), port, password),
+                    None => {
+                        status_frame_clone.set_label(&tr("Connection test canceled"));
+                        status_frame_clone.set_label_color(Color::Red);
+                        return;
+                    }
                 }
-            }
-            
-            // Add common options
-            cmd.arg("-o").arg("NumberOfPasswordPrompts=1");  // Only prompt once
-            cmd.arg("-o").arg("ConnectTimeout=10");         // Timeout after 10 seconds
-            cmd.arg("-p").arg(port.to_string());            // Port
-            
-            // Add host
-            cmd.arg(format!("{}@{}", username, hostname));
-            
-            // Add a simple test command that will execute on the remote host
-            cmd.arg("echo 'Connection successful'");
-            
-            // Show the command for debugging (but mask password)
-            let cmd_str = if has_password {
-                // Create a safe version of the command string with password masked
-                format!("sshpass -p ******** ssh -o NumberOfPasswordPrompts=1 -o ConnectTimeout=10 -p {} {}@{} \"echo 'Connection successful'\"", 
-                    port, username, hostname)
-            } else {
-                format!("{:?}", cmd)
             };
             
-            println!("Testing connection with command: {}", cmd_str);
+            // A saved host may be reached through a jump host
+            method.set_proxy_jump(
+                hosts_test.get(host_choice_test.value().max(0) as usize).and_then(|h| h.proxy_jump.clone())
+            );
             
-            // Execute the command
-            let result = cmd.output();
+            status_frame_clone.set_label(&tr("Testing connection..."));
+            status_frame_clone.set_label_color(Color::Blue);
+            button.deactivate();
+            log::info!("Testing connection to {}", method.connection_key());
             
-            match result {
-                Ok(output) => {
-                    let success = output.status.success();
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    
-                    println!("Command output: {}", stdout);
-                    println!("Command error: {}", stderr);
+            let mut button = button.clone();
+            let mut status_frame_done = status_frame_clone.clone();
+            let mut report_buffer_done = report_buffer_clone.clone();
+            run_in_background(
+                move || method.test_connection(Some(&remote_probe_command())),
+                move |diagnostics| {
+                    button.activate();
+                    log::info!("Connection test to {}:\n{}", hostname, diagnostics.report());
                     
-                    if success {
-                        status_frame_clone.set_label("Connection successful!");
-                        status_frame_clone.set_label_color(Color::Green);
-                    } else {
-                        let error_msg = if stderr.contains("Permission denied") {
-                            "Authentication failed. Check username/password or key."
-                        } else if stderr.contains("Could not resolve hostname") {
-                            "Hostname could not be resolved. Check network."
-                        } else if stderr.contains("Connection refused") {
-                            "Connection refused. Check if SSH server is running."
-                        } else if stderr.contains("Connection timed out") {
-                            "Connection timed out. Check hostname and network."
-                        } else {
-                            "Connection failed. See console for details."
+                    let mut report = diagnostics.report();
+                    if diagnostics.passed() {
+                        let capabilities = Capabilities {
+                            local: probe_local(),
+                            remote: parse_remote_probe(&diagnostics.probe_output),
                         };
+                        report = format!("{}\n\n{}", report, capabilities.report());
                         
-                        status_frame_clone.set_label(error_msg);
-                        status_frame_clone.set_label_color(Color::Red);
+                        status_frame_done.set_label(&tr("Connection successful!"));
+                        status_frame_done.set_label_color(Color::Green);
+                    } else {
+                        let failure = diagnostics.first_failure()
+                            .map(|stage| format!("{} failed: {}", stage.stage.label(), stage.detail))
+                            .unwrap_or_else(|| "Connection failed".to_string());
+                        status_frame_done.set_label(&failure);
+                        status_frame_done.set_label_color(Color::Red);
+                    }
+                    report_buffer_done.set_text(&report);
+                }
+            );
+        });
+        
+        // Key setup button callback
+        let hostname_input_key = hostname_input.clone();
+        let username_input_key = username_input.clone();
+        let port_input_key = port_input.clone();
+        let name_input_key = name_input.clone();
+        let auth_choice_key = auth_choice.clone();
+        let key_input_key = key_input.clone();
+        let key_label_key = key_label.clone();
+        let browse_button_key = browse_button.clone();
+        let mut status_frame_key = status_frame.clone();
+        let config_key = config.clone();
+        
+        key_setup_button.set_callback(move |button| {
+            let hostname = hostname_input_key.value().trim().to_string();
+            let username = username_input_key.value().trim().to_string();
+            let port = match port_input_key.value().trim().parse::<u16>() {
+                Ok(port) if !hostname.is_empty() && !username.is_empty() => port,
+                _ => {
+                    status_frame_key.set_label(&tr("Error: Fill in hostname, username and port first"));
+                    status_frame_key.set_label_color(Color::Red);
+                    return;
+                }
+            };
+            
+            let key_path = match Some(key_input_key.value().trim().to_string()).filter(|path| !path.is_empty()) {
+                Some(path) => PathBuf::from(path),
+                None => match default_key_path() {
+                    Some(path) => path,
+                    None => {
+                        status_frame_key.set_label(&tr("Error: No home directory to keep the key in"));
+                        status_frame_key.set_label_color(Color::Red);
+                        return;
                     }
                 },
-                Err(e) => {
-                    println!("Failed to execute command: {}", e);
-                    status_frame_clone.set_label("Failed to execute SSH command");
-                    status_frame_clone.set_label_color(Color::Red);
+            };
+            
+            // Installing the key logs in once with the password through sshpass
+            let local_tools = Capabilities { local: probe_local(), remote: Vec::new() };
+            if !local_tools.has_local("sshpass") || !local_tools.has_local("ssh-keygen") {
+                message_dialog(
+                    "Missing Dependency",
+                    "Setting up key authentication needs 'ssh-keygen' and 'sshpass' installed on this computer."
+                );
+                return;
+            }
+            
+            let password = match password_dialog(
+                "SSH Password",
+                &format!("Enter the current password for {}@{}:", username, hostname)
+            ) {
+                Some(password) => password,
+                None => return,
+            };
+            
+            status_frame_key.set_label(&tr("Setting up key authentication..."));
+            status_frame_key.set_label_color(Color::Blue);
+            button.deactivate();
+            
+            let key_path_work = key_path.clone();
+            let mut button = button.clone();
+            let mut status_frame_done = status_frame_key.clone();
+            let mut auth_choice_done = auth_choice_key.clone();
+            let mut key_input_done = key_input_key.clone();
+            let mut key_label_done = key_label_key.clone();
+            let mut browse_button_done = browse_button_key.clone();
+            let host_name = name_input_key.value();
+            let config_done = config_key.clone();
+            run_in_background(
+                move || -> AppResult<bool> {
+                    let generated = ensure_keypair(&key_path_work)?;
+                    install_public_key(&hostname, &username, port, &password, &key_path_work)?;
+                    verify_key_login(&hostname, &username, port, &key_path_work)?;
+                    Ok(generated)
+                },
+                move |result| {
+                    button.activate();
+                    match result {
+                        Ok(generated) => {
+                            let key = key_path.to_string_lossy().to_string();
+                            auth_choice_done.set_value(1); // SSH Key
+                            key_input_done.set_value(&key);
+                            key_label_done.show();
+                            key_input_done.show();
+                            browse_button_done.show();
+                            
+                            // A saved host switches to the key right away
+                            let mut config = config_done.lock().unwrap();
+                            if let Some(host) = config.hosts.iter_mut().find(|h| h.name == host_name) {
+                                host.use_key_auth = true;
+                                host.key_path = Some(key.clone());
+                                if let Err(e) = config.save() {
+                                    log::warn!("Failed to save key authentication for {}: {}", host_name, e);
+                                }
+                            }
+                            
+                            log::info!("Key authentication set up with {}", key);
+                            status_frame_done.set_label(if generated {
+                                "New key generated and installed; password no longer needed"
+                            } else {
+                                "Key installed; password no longer needed"
+                            });
+                            status_frame_done.set_label_color(Color::from_rgb(0, 150, 0));
+                        },
+                        Err(e) => {
+                            status_frame_done.set_label(&tr("Key setup failed"));
+                            status_frame_done.set_label_color(Color::Red);
+                            error_dialog("Key setup failed", &e);
+                        },
+                    }
                 }
+            );
+        });
+        
+        // Method choice callback
+        let transfer_options_method = transfer_options.clone();
+        let mut status_frame_method = status_frame.clone();
+        method_choice.set_callback(move |c| {
+            let Some(choice) = ProtocolChoice::all().get(c.value().max(0) as usize).copied() else { return };
+            choice.apply(&mut transfer_options_method.borrow_mut());
+            
+            let local_tools = Capabilities { local: probe_local(), remote: Vec::new() };
+            let rsync = matches!(choice, ProtocolChoice::Builtin(TransferProtocol::Rsync));
+            if rsync && !local_tools.has_local("rsync") {
+                status_frame_method.set_label(&tr("Rsync is not installed on this computer"));
+                status_frame_method.set_label_color(Color::Red);
+            }
+        });
+        
+        // Transfer options button callback
+        let transfer_options_edit = transfer_options.clone();
+        options_button.set_callback(move |_| {
+            let current = transfer_options_edit.borrow().clone();
+            if let Some(options) = transfer_options_dialog(&current) {
+                *transfer_options_edit.borrow_mut() = options;
             }
         });
         
+        // Cancel button callback
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
         // Delete button callback
         let host_choice_clone = host_choice.clone();
         let hosts_clone = hosts.clone();
         let config_clone = config.clone();
+        let mut dialog_delete = dialog.clone();
         
         delete_button.set_callback(move |_| {
             let selection = host_choice_clone.value();
@@ -645,7 +945,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             if selection < hosts_clone.len() as i32 {
                 let result = choice_dialog(
                     "Confirm Delete",
-                    &format!("Are you sure you want to delete the host '{}'?", hosts_clone[selection as usize].name),
+                    &trf("Are you sure you want to delete the host '{}'?", &[&hosts_clone[selection as usize].name]),
                     &["Yes", "No"]
                 );
                 
@@ -663,14 +963,12 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
                         
                         // Save the updated config
                         if let Err(e) = config.save() {
-                            message_dialog("Error", &format!("Failed to save config: {}", e));
+                            error_dialog("Failed to save settings", &e);
                         }
                     }
                     
                     // Close dialog
-                    if let Some(mut win) = app::first_window() {
-                        win.hide();
-                    }
+                    dialog_delete.close();
                 }
             }
         });
@@ -684,8 +982,13 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
         let hostname_input_copy = hostname_input.clone();
         let username_input_copy = username_input.clone();
         let port_input_copy = port_input.clone();
+        let start_dir_input_copy = start_dir_input.clone();
+        let fallback_input_copy = fallback_input.clone();
+        let mac_input_copy = mac_input.clone();
         let auth_choice_copy = auth_choice.clone();
         let key_input_copy = key_input.clone();
+        let transfer_options_save = transfer_options.clone();
+        let mut dialog_save = dialog.clone();
         
         save_button.set_callback(move |_| {
             let selection = host_choice_clone.value();
@@ -699,6 +1002,8 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             } else {
                 None
             };
+            let default_remote_dir = Some(start_dir_input_copy.value().trim().to_string())
+                .filter(|dir| !dir.is_empty());
             
             // Validate inputs
             if name.is_empty() || hostname.is_empty() || username.is_empty() || port_str.is_empty() {
@@ -719,6 +1024,20 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
                 return;
             }
             
+            let fallback_addresses = match HostAddress::parse_list(&fallback_input_copy.value()) {
+                Ok(addresses) => addresses,
+                Err(e) => {
+                    message_dialog("Error", &trf("Fallback addresses: {}", &[&e]));
+                    return;
+                }
+            };
+            
+            let mac_address = Some(mac_input_copy.value().trim().to_string()).filter(|mac| !mac.is_empty());
+            if let Some(Err(e)) = mac_address.as_deref().map(parse_mac_address) {
+                message_dialog("Error", &e);
+                return;
+            }
+            
             // Create host
             let new_host = Host {
                 name,
@@ -727,6 +1046,17 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
                 port,
                 use_key_auth,
                 key_path,
+                default_remote_dir,
+                // Editing a host keeps where it was last browsed
+                last_remote_dir: hosts_clone
+                    .get(selection as usize)
+                    .and_then(|h| h.last_remote_dir.clone()),
+                proxy_jump: hosts_clone
+                    .get(selection as usize)
+                    .and_then(|h| h.proxy_jump.clone()),
+                transfer: transfer_options_save.borrow().clone(),
+                fallback_addresses,
+                mac_address,
             };
             
             // Update config
@@ -742,24 +1072,17 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             
             // Save the updated config
             if let Err(e) = config.save() {
-                message_dialog("Error", &format!("Failed to save config: {}", e));
+                error_dialog("Failed to save settings", &e);
             }
             
             // Store the host result
             *host_result_clone.borrow_mut() = Some(new_host);
             
             // Close dialog
-            if let Some(mut win) = app::first_window() {
-                win.hide();
-            }
+            dialog_save.close();
         });
         
-        dialog.end();
-        dialog.show();
-        
-        while dialog.shown() {
-            app::wait();
-        }
+        dialog.run();
         
         // Capture the result before it goes out of scope
         let final_result = host_result.borrow().clone();
@@ -768,8 +1091,7 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
 
     // Helper function for choice dialogs
     pub fn choice_dialog(title: &str, message: &str, options: &[&str]) -> i32 {
-        let mut dialog = Window::new(100, 100, 300, 150, title);
-        dialog.set_border(true);
+        let mut dialog = ModalDialog::new(300, 150, title);
         
         let padding = 10;
         let button_height = 25;
@@ -802,44 +1124,1144 @@ pub fn password_dialog(title: &str, prompt: &str) -> Option<String> {
             
             let choice_clone = choice.clone();
             let i_val = i;
+            let mut dialog_button = dialog.clone();
             
             button.set_callback(move |_| {
                 // Set the choice when clicked
                 *choice_clone.borrow_mut() = i_val as i32;
                 
                 // Hide the dialog
-                if let Some(mut win) = app::first_window() {
-                    win.hide();
-                }
+                dialog_button.close();
             });
             
             buttons.push(button);
         }
         
-        dialog.end();
-        dialog.show();
-        
         // Wait for the dialog to close
-        while dialog.shown() {
-            app::wait();
-        }
+        dialog.run();
         
         // Return the choice
         let x = *choice.borrow(); x
     }
 
-    // Add these helper functions for the operations panel
-    pub fn resize_dialog() -> Option<(u32, u32)> {
-        // Implement a dialog to get width and height
-        // This is a simplified implementation
-        let width = 800;
-        let height = 600;
-        Some((width, height))
+    // Let the user pick one entry from a list; returns its index
+    pub fn select_dialog(title: &str, message: &str, options: &[String]) -> Option<usize> {
+        let mut dialog = ModalDialog::new(360, 150, title);
+        
+        let padding = 10;
+        let input_height = 25;
+        let button_width = 80;
+        
+        let mut message_frame = Frame::new(
+            padding, 
+            padding, 
+            360 - padding * 2, 
+            40,
+            None
+        );
+        message_frame.set_label(message);
+        message_frame.set_align(Align::Left | Align::Inside | Align::Top);
+        
+        let mut option_choice = Choice::new(
+            padding,
+            padding + 50,
+            360 - padding * 2,
+            input_height,
+            None
+        );
+        for option in options {
+            option_choice.add_choice(&option.replace('/', "\\/").replace('|', "\\|"));
+        }
+        option_choice.set_value(0);
+        
+        let mut cancel_button = Button::new(
+            360 - padding * 2 - button_width * 2,
+            150 - padding - input_height,
+            button_width,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut ok_button = Button::new(
+            360 - padding - button_width,
+            150 - padding - input_height,
+            button_width,
+            input_height,
+            "OK"
+        );
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let selection = Rc::new(RefCell::new(None::<usize>));
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let selection_clone = selection.clone();
+        let option_choice_clone = option_choice.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let value = option_choice_clone.value();
+            if value >= 0 {
+                *selection_clone.borrow_mut() = Some(value as usize);
+            }
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let result = *selection.borrow();
+        result
     }
 
-    pub fn brightness_dialog() -> Option<i32> {
-        // Implement a dialog to get brightness level
-        // Changed to return i32 instead of f32 to match BrightnessOperation
-        Some(20) // For example, +20% brightness
+    // Edit how files are copied to and from one host; None when cancelled
+    pub fn transfer_options_dialog(options: &TransferOptions) -> Option<TransferOptions> {
+        let width = 380;
+        let height = 405;
+        let mut dialog = ModalDialog::new(width, height, "Transfer Options");
+        
+        let padding = 10;
+        let input_height = 25;
+        let label_width = 140;
+        let input_width = width - label_width - padding * 2;
+        let button_width = 80;
+        
+        let mut compression_choice = Choice::new(
+            padding + label_width,
+            padding,
+            input_width,
+            input_height,
+            "Compression:"
+        );
+        compression_choice.set_align(Align::Left);
+        compression_choice.add_choice("Protocol Default|On|Off");
+        compression_choice.set_value(match options.compression {
+            None => 0,
+            Some(true) => 1,
+            Some(false) => 2,
+        });
+        compression_choice.set_tooltip("Compression helps slow links but costs CPU on small Pis");
+        
+        let mut rsync_args_input = Input::new(
+            padding + label_width,
+            padding * 2 + input_height,
+            input_width,
+            input_height,
+            "Rsync arguments:"
+        );
+        rsync_args_input.set_align(Align::Left);
+        rsync_args_input.set_value(&options.rsync_args.join(" "));
+        rsync_args_input.set_tooltip("Extra arguments for rsync, e.g. --bwlimit=500 --partial");
+        
+        let mut concurrency_input = IntInput::new(
+            padding + label_width,
+            padding * 3 + input_height * 2,
+            60,
+            input_height,
+            "Concurrent copies:"
+        );
+        concurrency_input.set_align(Align::Left);
+        concurrency_input.set_value(&options.concurrency.max(1).to_string());
+        
+        let mut retries_input = IntInput::new(
+            padding + label_width,
+            padding * 4 + input_height * 3,
+            60,
+            input_height,
+            "Retries:"
+        );
+        retries_input.set_align(Align::Left);
+        retries_input.set_value(&options.retry.retries.to_string());
+        retries_input.set_tooltip("Tries after a copy or listing timed out or lost the connection; 0 never retries");
+        
+        let mut backoff_input = FloatInput::new(
+            padding + label_width,
+            padding * 5 + input_height * 4,
+            60,
+            input_height,
+            "First retry after (s):"
+        );
+        backoff_input.set_align(Align::Left);
+        backoff_input.set_value(&format!("{}", options.retry.initial_backoff_ms as f64 / 1000.0));
+        backoff_input.set_tooltip("Doubled for every retry after the first");
+        
+        let mut max_backoff_input = FloatInput::new(
+            padding + label_width,
+            padding * 6 + input_height * 5,
+            60,
+            input_height,
+            "Longest wait (s):"
+        );
+        max_backoff_input.set_align(Align::Left);
+        max_backoff_input.set_value(&format!("{}", options.retry.max_backoff_ms as f64 / 1000.0));
+        
+        let mut jitter_check = CheckButton::new(
+            padding + label_width + 70,
+            padding * 6 + input_height * 5,
+            input_width - 70,
+            input_height,
+            "Random jitter"
+        );
+        jitter_check.set_checked(options.retry.jitter);
+        jitter_check.set_tooltip("Spread retries out so copies that failed together don't retry together");
+        
+        let mut agent_check = CheckButton::new(
+            padding + label_width,
+            padding * 7 + input_height * 6,
+            input_width,
+            input_height,
+            "Use companion agent"
+        );
+        agent_check.set_checked(options.use_agent);
+        agent_check.set_tooltip("List folders, checksum and make thumbnails with the agent when it is installed on the Pi");
+        
+        let mut sudo_check = CheckButton::new(
+            padding + label_width,
+            padding * 8 + input_height * 7,
+            input_width,
+            input_height,
+            "Offer sudo on permission errors"
+        );
+        sudo_check.set_checked(options.sudo_fallback);
+        sudo_check.set_tooltip("Offer to list or download again with sudo when the Pi refuses access, using the login password");
+        
+        let mut links_check = CheckButton::new(
+            padding + label_width,
+            padding * 9 + input_height * 8,
+            input_width,
+            input_height,
+            "Copy links as links"
+        );
+        links_check.set_checked(options.preserve_symlinks);
+        links_check.set_tooltip("Keep symbolic links in copied folders and archives as links; otherwise copy what they point to");
+        
+        let mut cancel_button = Button::new(
+            width - padding * 2 - button_width * 2,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut ok_button = Button::new(
+            width - padding - button_width,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "OK"
+        );
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let result = Rc::new(RefCell::new(None::<TransferOptions>));
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let result_clone = result.clone();
+        let base = options.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let concurrency = match concurrency_input.value().trim().parse::<usize>() {
+                Ok(n) if (1..=8).contains(&n) => n,
+                _ => {
+                    message_dialog("Error", "Concurrent copies must be between 1 and 8");
+                    return;
+                }
+            };
+            
+            let retries = match retries_input.value().trim().parse::<u32>() {
+                Ok(n) if n <= 10 => n,
+                _ => {
+                    message_dialog("Error", "Retries must be between 0 and 10");
+                    return;
+                }
+            };
+            let seconds = |input: &FloatInput| input.value().trim().parse::<f64>().ok().filter(|s| (0.0..=600.0).contains(s));
+            let (Some(backoff), Some(max_backoff)) = (seconds(&backoff_input), seconds(&max_backoff_input)) else {
+                message_dialog("Error", "Waits must be between 0 and 600 seconds");
+                return;
+            };
+            
+            *result_clone.borrow_mut() = Some(TransferOptions {
+                compression: match compression_choice.value() {
+                    1 => Some(true),
+                    2 => Some(false),
+                    _ => None,
+                },
+                rsync_args: rsync_args_input.value().split_whitespace().map(str::to_string).collect(),
+                concurrency,
+                retry: RetryPolicy {
+                    retries,
+                    initial_backoff_ms: (backoff * 1000.0) as u64,
+                    max_backoff_ms: (max_backoff * 1000.0) as u64,
+                    jitter: jitter_check.is_checked(),
+                },
+                use_agent: agent_check.is_checked(),
+                sudo_fallback: sudo_check.is_checked(),
+                preserve_symlinks: links_check.is_checked(),
+                ..base.clone()
+            });
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let options = result.borrow().clone();
+        options
+    }
+
+    // Edit the output naming template with a live example; None when cancelled
+    pub fn output_name_dialog(current: &str) -> Option<String> {
+        let width = 460;
+        let height = 330;
+        let mut dialog = ModalDialog::new(width, height, "Output Naming");
+        
+        let padding = 10;
+        let input_height = 25;
+        let label_width = 80;
+        let button_width = 80;
+        
+        let mut template_input = Input::new(
+            padding + label_width,
+            padding,
+            width - label_width - padding * 2,
+            input_height,
+            "Template:"
+        );
+        template_input.set_align(Align::Left);
+        template_input.set_value(current);
+        template_input.set_trigger(CallbackTrigger::Changed);
+        
+        let mut example_frame = Frame::new(
+            padding,
+            padding * 2 + input_height,
+            width - padding * 2,
+            input_height,
+            None
+        );
+        example_frame.set_align(Align::Left | Align::Inside);
+        
+        let tokens = OUTPUT_NAME_TOKENS
+            .iter()
+            .map(|(token, meaning)| format!("{:<10} {}", token, meaning))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut tokens_frame = Frame::new(
+            padding,
+            padding * 3 + input_height * 2,
+            width - padding * 2,
+            height - padding * 5 - input_height * 3,
+            None
+        );
+        tokens_frame.set_label(&tokens);
+        tokens_frame.set_label_font(Font::Courier);
+        tokens_frame.set_label_size(font_size(12));
+        tokens_frame.set_align(Align::Left | Align::Top | Align::Inside);
+        
+        // A typical photo processed with the web preset
+        let sample = OutputNameContext {
+            stem: "IMG_0042".to_string(),
+            format: ImageFormat::JPEG,
+            op: "web".to_string(),
+            size: Some((1920, 1080)),
+            counter: 7,
+        };
+        let show_example = {
+            let mut example_frame = example_frame.clone();
+            move |template: &str| {
+                example_frame.set_label(&format!("Example: {}", format_output_name(template, &sample)));
+                example_frame.redraw();
+            }
+        };
+        let mut show_initial = show_example.clone();
+        show_initial(current);
+        
+        let mut show_typed = show_example.clone();
+        template_input.set_callback(move |input| {
+            show_typed(&input.value());
+        });
+        
+        let mut reset_button = Button::new(
+            padding,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "Default"
+        );
+        let mut input_reset = template_input.clone();
+        let mut show_reset = show_example.clone();
+        reset_button.set_callback(move |_| {
+            input_reset.set_value(DEFAULT_OUTPUT_NAME_TEMPLATE);
+            show_reset(DEFAULT_OUTPUT_NAME_TEMPLATE);
+        });
+        
+        let mut cancel_button = Button::new(
+            width - padding * 2 - button_width * 2,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut ok_button = Button::new(
+            width - padding - button_width,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "OK"
+        );
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let result = Rc::new(RefCell::new(None::<String>));
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let result_clone = result.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let template = template_input.value().trim().to_string();
+            if template.is_empty() {
+                message_dialog("Error", "The template cannot be empty.");
+                return;
+            }
+            *result_clone.borrow_mut() = Some(template);
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let template = result.borrow().clone();
+        template
+    }
+    
+    // Pick any number of entries from a list, all selected to begin with;
+    // None when cancelled
+    pub fn multi_select_dialog(title: &str, message: &str, options: &[String]) -> Option<Vec<usize>> {
+        let width = 420;
+        let height = 360;
+        let mut dialog = ModalDialog::new(width, height, title);
+        
+        let padding = 10;
+        let input_height = 25;
+        let button_width = 80;
+        
+        let mut message_frame = Frame::new(
+            padding,
+            padding,
+            width - padding * 2,
+            40,
+            None
+        );
+        message_frame.set_label(message);
+        message_frame.set_align(Align::Left | Align::Inside | Align::Top | Align::Wrap);
+        
+        let mut options_browser = MultiBrowser::new(
+            padding,
+            padding + 50,
+            width - padding * 2,
+            height - padding * 4 - input_height - 50,
+            None
+        );
+        for (i, option) in options.iter().enumerate() {
+            options_browser.add(&format!("@.{}", option));
+            options_browser.select(i as i32 + 1);
+        }
+        
+        let mut cancel_button = Button::new(
+            width - padding * 2 - button_width * 2,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut ok_button = Button::new(
+            width - padding - button_width,
+            height - padding - input_height,
+            button_width,
+            input_height,
+            "OK"
+        );
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let selection = Rc::new(RefCell::new(None::<Vec<usize>>));
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let selection_clone = selection.clone();
+        let options_browser_clone = options_browser.clone();
+        let count = options.len();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let chosen = (0..count)
+                .filter(|i| options_browser_clone.selected(*i as i32 + 1))
+                .collect();
+            *selection_clone.borrow_mut() = Some(chosen);
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let result = selection.borrow().clone();
+        result
+    }
+
+    // Confirmation that only succeeds once the user types `expected`,
+    // used for actions that are hard to undo such as shutting down the Pi
+    pub fn typed_confirm_dialog(title: &str, message: &str, expected: &str) -> bool {
+        let mut dialog = ModalDialog::new(360, 190, title);
+        
+        let padding = 10;
+        let input_height = 25;
+        let button_width = 80;
+        
+        let mut message_frame = Frame::new(
+            padding, 
+            padding, 
+            360 - padding * 2, 
+            60,
+            None
+        );
+        message_frame.set_label(&format!("{}\n\nType '{}' to confirm:", message, expected));
+        message_frame.set_align(Align::Left | Align::Inside | Align::Top);
+        
+        let mut confirm_input = Input::new(
+            padding,
+            padding * 2 + 70,
+            360 - padding * 2,
+            input_height,
+            ""
+        );
+        
+        let mut cancel_button = Button::new(
+            360 - padding * 2 - button_width * 2,
+            190 - padding - input_height,
+            button_width,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut ok_button = Button::new(
+            360 - padding - button_width,
+            190 - padding - input_height,
+            button_width,
+            input_height,
+            "Confirm"
+        );
+        ok_button.set_color(Color::from_rgb(220, 0, 0));
+        ok_button.set_label_color(Color::White);
+        ok_button.deactivate();
+        
+        let confirmed = Rc::new(RefCell::new(false));
+        
+        // Only enable the confirm button once the text matches
+        let expected_text = expected.to_string();
+        let mut ok_button_clone = ok_button.clone();
+        confirm_input.set_trigger(fltk::enums::CallbackTrigger::Changed);
+        confirm_input.set_callback(move |i| {
+            if i.value().trim() == expected_text {
+                ok_button_clone.activate();
+            } else {
+                ok_button_clone.deactivate();
+            }
+        });
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let confirmed_clone = confirmed.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            *confirmed_clone.borrow_mut() = true;
+            dialog_ok.close();
+        });
+        
+        // The input is the first widget taking focus, so typing starts there
+        dialog.run();
+        
+        let result = *confirmed.borrow();
+        result
+    }
+
+    // Create, edit and delete device groups
+    pub fn group_dialog(config: Arc<Mutex<Config>>) {
+        let (hosts, groups) = {
+            let config = config.lock().unwrap();
+            (config.hosts.clone(), config.groups.clone())
+        };
+        
+        let mut dialog = ModalDialog::new(400, 380, "Device Groups");
+        
+        let padding = 10;
+        let input_height = 25;
+        let label_width = 80;
+        let input_width = 400 - label_width - padding * 2;
+        
+        let mut group_choice = Choice::new(
+            padding + label_width,
+            padding,
+            input_width,
+            input_height,
+            "Group:"
+        );
+        group_choice.set_align(Align::Left);
+        for group in &groups {
+            group_choice.add_choice(&group.name.replace('/', "\\/").replace('|', "\\|"));
+        }
+        group_choice.add_choice("New Group...");
+        group_choice.set_value(0);
+        
+        let mut name_input = Input::new(
+            padding + label_width,
+            padding * 2 + input_height,
+            input_width,
+            input_height,
+            "Name:"
+        );
+        name_input.set_align(Align::Left);
+        
+        let mut members_label = Frame::new(
+            padding,
+            padding * 3 + input_height * 2,
+            400 - padding * 2,
+            20,
+            "Members (select one or more hosts):"
+        );
+        members_label.set_align(Align::Left | Align::Inside);
+        
+        let mut members_browser = MultiBrowser::new(
+            padding,
+            padding * 3 + input_height * 2 + 20,
+            400 - padding * 2,
+            380 - (padding * 5 + input_height * 3 + 20),
+            None
+        );
+        for host in &hosts {
+            members_browser.add(&format!("@.{} ({}@{})", host.name, host.username, host.hostname));
+        }
+        
+        let mut delete_button = Button::new(
+            padding,
+            380 - padding - input_height,
+            100,
+            input_height,
+            "Delete"
+        );
+        delete_button.set_color(Color::from_rgb(220, 0, 0));
+        delete_button.set_label_color(Color::White);
+        
+        let mut cancel_button = Button::new(
+            400 - padding * 2 - 200,
+            380 - padding - input_height,
+            100,
+            input_height,
+            "Cancel"
+        );
+        
+        let mut save_button = Button::new(
+            400 - padding - 100,
+            380 - padding - input_height,
+            100,
+            input_height,
+            "Save"
+        );
+        save_button.set_color(Color::from_rgb(0, 120, 255));
+        save_button.set_label_color(Color::White);
+        
+        // Show the selected group's name and members
+        let show_group = {
+            let groups = groups.clone();
+            let hosts = hosts.clone();
+            let mut name_input = name_input.clone();
+            let mut members_browser = members_browser.clone();
+            let mut delete_button = delete_button.clone();
+            move |selection: i32| {
+                let group = groups.get(selection.max(0) as usize);
+                name_input.set_value(group.map(|g| g.name.as_str()).unwrap_or(""));
+                
+                for (i, host) in hosts.iter().enumerate() {
+                    let member = group.map(|g| g.members.contains(&host.name)).unwrap_or(false);
+                    if member {
+                        members_browser.select(i as i32 + 1);
+                    } else {
+                        members_browser.deselect(i as i32 + 1);
+                    }
+                }
+                
+                if group.is_some() {
+                    delete_button.activate();
+                } else {
+                    delete_button.deactivate();
+                }
+            }
+        };
+        
+        let mut show_group_initial = show_group.clone();
+        show_group_initial(0);
+        
+        let mut show_group_choice = show_group.clone();
+        group_choice.set_callback(move |c| {
+            show_group_choice(c.value());
+        });
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        // Delete the selected group
+        let config_delete = config.clone();
+        let groups_delete = groups.clone();
+        let group_choice_delete = group_choice.clone();
+        let mut dialog_delete = dialog.clone();
+        delete_button.set_callback(move |_| {
+            let selection = group_choice_delete.value();
+            if let Some(group) = groups_delete.get(selection.max(0) as usize) {
+                let result = choice_dialog(
+                    "Confirm Delete",
+                    &trf("Are you sure you want to delete the group '{}'?", &[&group.name]),
+                    &["Yes", "No"]
+                );
+                
+                if result == 0 {
+                    let mut config = config_delete.lock().unwrap();
+                    config.groups.retain(|g| g.name != group.name);
+                    if let Err(e) = config.save() {
+                        error_dialog("Failed to save settings", &e);
+                    }
+                    dialog_delete.close();
+                }
+            }
+        });
+        
+        // Save the edited or new group
+        let config_save = config.clone();
+        let groups_save = groups.clone();
+        let hosts_save = hosts.clone();
+        let group_choice_save = group_choice.clone();
+        let name_input_save = name_input.clone();
+        let members_browser_save = members_browser.clone();
+        let mut dialog_save = dialog.clone();
+        save_button.set_callback(move |_| {
+            let name = name_input_save.value().trim().to_string();
+            if name.is_empty() {
+                message_dialog("Error", "Group name must not be empty");
+                return;
+            }
+            
+            let members: Vec<String> = hosts_save
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| members_browser_save.selected(*i as i32 + 1))
+                .map(|(_, host)| host.name.clone())
+                .collect();
+            
+            if members.is_empty() {
+                message_dialog("Error", "Select at least one host");
+                return;
+            }
+            
+            let original = groups_save.get(group_choice_save.value().max(0) as usize).map(|g| g.name.clone());
+            let new_group = HostGroup { name, members };
+            
+            let mut config = config_save.lock().unwrap();
+            match original.and_then(|o| config.groups.iter().position(|g| g.name == o)) {
+                Some(pos) => config.groups[pos] = new_group,
+                None => config.groups.push(new_group),
+            }
+            
+            if let Err(e) = config.save() {
+                error_dialog("Failed to save settings", &e);
+            }
+            
+            dialog_save.close();
+        });
+        
+        dialog.run();
+    }
+
+    // Show a longer, scrollable text such as a per-device result summary
+    pub fn report_dialog(title: &str, text: &str) {
+        let mut dialog = ModalDialog::new(500, 350, title);
+        
+        let padding = 10;
+        let button_height = 25;
+        
+        let mut buffer = TextBuffer::default();
+        buffer.set_text(text);
+        
+        let mut display = TextDisplay::new(
+            padding,
+            padding,
+            500 - padding * 2,
+            350 - padding * 3 - button_height,
+            None
+        );
+        display.set_buffer(buffer);
+        display.set_text_font(Font::Courier);
+        display.set_text_size(font_size(12));
+        
+        let mut ok_button = Button::new(
+            500 - padding - 80,
+            350 - padding - button_height,
+            80,
+            button_height,
+            "OK"
+        );
+        
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+    }
+
+    // Ask for a new image size, in pixels or as a percentage of `original`
+    // when the current image's size is known; the aspect ratio lock keeps
+    // width and height in proportion while either is edited
+    pub fn resize_dialog(original: Option<(u32, u32)>) -> Option<(u32, u32)> {
+        let width = 340;
+        let height = 220;
+        let padding = 10;
+        let row_height = 25;
+        let label_width = 90;
+        
+        let mut dialog = ModalDialog::new(width, height, "Resize");
+        
+        let mut original_frame = Frame::new(padding, padding, width - padding * 2, row_height, None);
+        original_frame.set_align(Align::Left | Align::Inside);
+        match original {
+            Some((w, h)) => original_frame.set_label(&format!("Current size: {} × {} px", w, h)),
+            None => original_frame.set_label(&tr("Current size unknown")),
+        }
+        
+        let mut unit_choice = Choice::new(padding + label_width, padding * 2 + row_height, 120, row_height, "Units:");
+        unit_choice.add_choice("Pixels");
+        unit_choice.add_choice("Percent");
+        unit_choice.set_value(0);
+        if original.is_none() {
+            // Percentages need the current size
+            unit_choice.deactivate();
+        }
+        
+        let mut width_input = IntInput::new(padding + label_width, padding * 3 + row_height * 2, 120, row_height, "Width:");
+        let mut height_input = IntInput::new(padding + label_width, padding * 4 + row_height * 3, 120, row_height, "Height:");
+        let (start_w, start_h) = original.unwrap_or((800, 600));
+        width_input.set_value(&start_w.to_string());
+        height_input.set_value(&start_h.to_string());
+        
+        let mut lock_check = CheckButton::new(padding + label_width + 130, padding * 3 + row_height * 2, 100, row_height, "Keep ratio");
+        if original.is_some() {
+            lock_check.set_checked(true);
+        } else {
+            lock_check.deactivate();
+        }
+        
+        let mut cancel_button = Button::new(width - padding * 2 - 160, height - padding - row_height, 80, row_height, "Cancel");
+        let mut ok_button = Button::new(width - padding - 80, height - padding - row_height, 80, row_height, "Resize");
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let percent_mode = |choice: &Choice| choice.value() == 1;
+        
+        // Switching units converts what is typed so far
+        let mut width_units = width_input.clone();
+        let mut height_units = height_input.clone();
+        unit_choice.set_callback(move |choice| {
+            let (ow, oh) = match original {
+                Some(size) => size,
+                None => return,
+            };
+            let w: f64 = width_units.value().parse().unwrap_or(0.0);
+            let h: f64 = height_units.value().parse().unwrap_or(0.0);
+            let (w, h) = if percent_mode(choice) {
+                (w * 100.0 / ow as f64, h * 100.0 / oh as f64)
+            } else {
+                (w * ow as f64 / 100.0, h * oh as f64 / 100.0)
+            };
+            width_units.set_value(&(w.round() as u32).to_string());
+            height_units.set_value(&(h.round() as u32).to_string());
+        });
+        
+        // With the ratio locked, editing one side updates the other
+        let proportional = move |value: &str, from: u32, to: u32, percent: bool| -> Option<String> {
+            let value: f64 = value.parse().ok()?;
+            let scaled = if percent { value } else { value * to as f64 / from as f64 };
+            Some(((scaled.round() as u32).max(1)).to_string())
+        };
+        
+        width_input.set_trigger(CallbackTrigger::Changed);
+        let mut height_follow = height_input.clone();
+        let lock_width = lock_check.clone();
+        let unit_width = unit_choice.clone();
+        width_input.set_callback(move |input| {
+            if let (true, Some((ow, oh))) = (lock_width.is_checked(), original) {
+                if let Some(value) = proportional(&input.value(), ow, oh, percent_mode(&unit_width)) {
+                    height_follow.set_value(&value);
+                }
+            }
+        });
+        
+        height_input.set_trigger(CallbackTrigger::Changed);
+        let mut width_follow = width_input.clone();
+        let lock_height = lock_check.clone();
+        let unit_height = unit_choice.clone();
+        height_input.set_callback(move |input| {
+            if let (true, Some((ow, oh))) = (lock_height.is_checked(), original) {
+                if let Some(value) = proportional(&input.value(), oh, ow, percent_mode(&unit_height)) {
+                    width_follow.set_value(&value);
+                }
+            }
+        });
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let result = Rc::new(RefCell::new(None::<(u32, u32)>));
+        let result_ok = result.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let w: u32 = width_input.value().parse().unwrap_or(0);
+            let h: u32 = height_input.value().parse().unwrap_or(0);
+            let (w, h) = match original {
+                Some((ow, oh)) if percent_mode(&unit_choice) => (
+                    ((ow as u64 * w as u64 + 50) / 100) as u32,
+                    ((oh as u64 * h as u64 + 50) / 100) as u32,
+                ),
+                _ => (w, h),
+            };
+            
+            if w == 0 || h == 0 || w > MAX_RESIZE_DIMENSION || h > MAX_RESIZE_DIMENSION {
+                message_dialog(
+                    "Resize",
+                    &trf("Width and height must be between 1 and {} pixels.", &[&MAX_RESIZE_DIMENSION])
+                );
+                return;
+            }
+            
+            *result_ok.borrow_mut() = Some((w, h));
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let size = *result.borrow();
+        size
+    }
+
+    // Ask for a brightness change from -100 (darker) to 100 (brighter) with
+    // a slider and a number field that follow each other
+    pub fn brightness_dialog() -> Option<i32> {
+        let width = 340;
+        let height = 150;
+        let padding = 10;
+        let row_height = 25;
+        
+        let mut dialog = ModalDialog::new(width, height, "Brightness");
+        
+        let mut prompt = Frame::new(padding, padding, width - padding * 2, row_height, "Brightness change (-100 to 100):");
+        prompt.set_align(Align::Left | Align::Inside);
+        
+        let mut slider = HorNiceSlider::new(padding, padding * 2 + row_height, width - padding * 3 - 60, row_height, None);
+        slider.set_range(-100.0, 100.0);
+        slider.set_step(1.0, 1);
+        slider.set_value(0.0);
+        
+        let mut level_input = IntInput::new(width - padding - 60, padding * 2 + row_height, 60, row_height, None);
+        level_input.set_value("0");
+        
+        let mut cancel_button = Button::new(width - padding * 2 - 160, height - padding - row_height, 80, row_height, "Cancel");
+        let mut ok_button = Button::new(width - padding - 80, height - padding - row_height, 80, row_height, "Apply");
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let mut input_follow = level_input.clone();
+        slider.set_callback(move |s| {
+            input_follow.set_value(&(s.value() as i32).to_string());
+        });
+        
+        level_input.set_trigger(CallbackTrigger::Changed);
+        let mut slider_follow = slider.clone();
+        level_input.set_callback(move |input| {
+            if let Ok(level) = input.value().trim().parse::<i32>() {
+                slider_follow.set_value(level.clamp(-100, 100) as f64);
+            }
+        });
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let result = Rc::new(RefCell::new(None::<i32>));
+        let result_ok = result.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            match level_input.value().trim().parse::<i32>() {
+                Ok(level) if (-100..=100).contains(&level) => {
+                    *result_ok.borrow_mut() = Some(level);
+                    dialog_ok.close();
+                },
+                _ => message_dialog("Brightness", "Enter a whole number from -100 to 100."),
+            }
+        });
+        
+        dialog.run();
+        
+        let level = *result.borrow();
+        level
+    }
+
+    // Ask for the UI scale, font size and theme; None when cancelled
+    pub fn appearance_dialog(current: &Appearance) -> Option<Appearance> {
+        let width = 340;
+        let height = 160;
+        let padding = 10;
+        let row_height = 25;
+        let label_width = 110;
+
+        let mut dialog = ModalDialog::new(width, height, "Appearance");
+
+        let mut scale_choice = Choice::new(padding + label_width, padding, 120, row_height, "UI scale:");
+        for scale in UI_SCALES {
+            scale_choice.add_choice(&format!("{}%", (scale * 100.0).round()));
+        }
+        let scale_index = UI_SCALES
+            .iter()
+            .position(|scale| (scale - current.ui_scale).abs() < 0.01)
+            .unwrap_or(0);
+        scale_choice.set_value(scale_index as i32);
+
+        let mut size_choice = Choice::new(padding + label_width, padding * 2 + row_height, 120, row_height, "Font size:");
+        for size in FONT_SIZES {
+            size_choice.add_choice(&size.to_string());
+        }
+        let size_index = FONT_SIZES
+            .iter()
+            .position(|size| *size == current.font_size)
+            .unwrap_or_else(|| FONT_SIZES.iter().position(|size| *size == DEFAULT_FONT_SIZE).unwrap_or(0));
+        size_choice.set_value(size_index as i32);
+
+        let mut contrast_check = CheckButton::new(padding + label_width, padding * 3 + row_height * 2, 200, row_height, "High contrast");
+        contrast_check.set_checked(current.high_contrast);
+
+        let mut cancel_button = Button::new(width - padding * 2 - 160, height - padding - row_height, 80, row_height, "Cancel");
+        let mut ok_button = Button::new(width - padding - 80, height - padding - row_height, 80, row_height, "Apply");
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+
+        let result = Rc::new(RefCell::new(None::<Appearance>));
+        let result_ok = result.clone();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            *result_ok.borrow_mut() = Some(Appearance {
+                ui_scale: UI_SCALES[scale_choice.value().max(0) as usize],
+                font_size: FONT_SIZES[size_choice.value().max(0) as usize],
+                high_contrast: contrast_check.is_checked(),
+            });
+            dialog_ok.close();
+        });
+
+        dialog.run();
+
+        let appearance = result.borrow().clone();
+        appearance
+    }
+
+    // Ask for the values of an operation's parameters, starting from their
+    // defaults; `confirm` labels the button that accepts them. None when
+    // cancelled
+    pub fn operation_params_dialog(title: &str, params: &[OperationParam], confirm: &str) -> Option<Vec<f64>> {
+        if params.is_empty() {
+            return Some(Vec::new());
+        }
+        
+        let width = 340;
+        let padding = 10;
+        let row_height = 25;
+        let label_width = 180;
+        let height = padding * 3 + (row_height + padding) * params.len() as i32 + row_height;
+        
+        let mut dialog = ModalDialog::new(width, height, title);
+        
+        let mut inputs = Vec::new();
+        for (index, param) in params.iter().enumerate() {
+            let row_y = padding + (row_height + padding) * index as i32;
+            let mut label = Frame::new(padding, row_y, label_width, row_height, None);
+            label.set_label(&format!("{} ({} to {}):", param.name, param.min, param.max));
+            label.set_align(Align::Left | Align::Inside);
+            
+            let mut input = FloatInput::new(padding * 2 + label_width, row_y, width - padding * 3 - label_width, row_height, None);
+            input.set_value(&param.default.to_string());
+            inputs.push(input);
+        }
+        
+        let mut cancel_button = Button::new(width - padding * 2 - 160, height - padding - row_height, 80, row_height, "Cancel");
+        let mut ok_button = Button::new(width - padding - 80, height - padding - row_height, 80, row_height, None);
+        ok_button.set_label(&tr(confirm));
+        ok_button.set_color(Color::from_rgb(0, 120, 255));
+        ok_button.set_label_color(Color::White);
+        
+        let mut dialog_cancel = dialog.clone();
+        cancel_button.set_callback(move |_| {
+            dialog_cancel.close();
+        });
+        
+        let result = Rc::new(RefCell::new(None::<Vec<f64>>));
+        let result_ok = result.clone();
+        let params_ok = params.to_vec();
+        let title_ok = title.to_string();
+        let mut dialog_ok = dialog.clone();
+        ok_button.set_callback(move |_| {
+            let mut values = Vec::new();
+            for (param, input) in params_ok.iter().zip(&inputs) {
+                match input.value().trim().parse::<f64>() {
+                    // Whole-number parameters get whole numbers
+                    Ok(value) if (param.min..=param.max).contains(&value) => values.push(snap_to_step(value, param)),
+                    _ => {
+                        message_dialog(&title_ok, &trf("{} must be a number from {} to {}.", &[&param.name, &param.min, &param.max]));
+                        return;
+                    },
+                }
+            }
+            *result_ok.borrow_mut() = Some(values);
+            dialog_ok.close();
+        });
+        
+        dialog.run();
+        
+        let values = result.borrow().clone();
+        values
+    }
+    
+    // The nearest multiple of the parameter's step, kept within its range
+    fn snap_to_step(value: f64, param: &OperationParam) -> f64 {
+        if param.step <= 0.0 {
+            return value;
+        }
+        ((value / param.step).round() * param.step).clamp(param.min, param.max)
     }
 }
\ No newline at end of file
@@ -0,0 +1,381 @@
+use fltk::{
+    app,
+    button::Button,
+    draw,
+    enums::{Color, FrameType, Font, Align, CallbackTrigger},
+    frame::Frame,
+    group::Group,
+    input::Input,
+    table::{Table, TableContext},
+    prelude::*,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::core::device::{
+    DiskUsage, DiskUsageSort, du_command, parse_du_output, sort_entries, format_size_kb,
+};
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::theme::font_size;
+
+/// How often a running scan is checked for completion (seconds)
+const POLL_INTERVAL: f64 = 0.2;
+
+/// Column showing the name of each entry
+const COL_NAME: i32 = 0;
+/// Column showing the size of each entry
+const COL_SIZE: i32 = 1;
+/// Column showing the share of the total as a bar
+const COL_SHARE: i32 = 2;
+
+/// Panel showing what is using space on the Pi, one directory level at a time
+pub struct DiskUsagePanel {
+    group: Group,
+    path_input: Input,
+    scan_button: Button,
+    up_button: Button,
+    status_frame: Frame,
+    table: Table,
+    usage: Arc<Mutex<DiskUsage>>,
+    sort: Arc<Mutex<DiskUsageSort>>,
+    current_dir: Arc<Mutex<PathBuf>>,
+    scanning: Arc<Mutex<bool>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+}
+
+impl Clone for DiskUsagePanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            path_input: self.path_input.clone(),
+            scan_button: self.scan_button.clone(),
+            up_button: self.up_button.clone(),
+            status_frame: self.status_frame.clone(),
+            table: self.table.clone(),
+            usage: self.usage.clone(),
+            sort: self.sort.clone(),
+            current_dir: self.current_dir.clone(),
+            scanning: self.scanning.clone(),
+            remote_browser: self.remote_browser.clone(),
+        }
+    }
+}
+
+impl DiskUsagePanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 80;
+
+        let mut path_input = Input::new(
+            x + padding + 70,
+            y + padding,
+            w - 2 * padding - 70 - 2 * (button_width + 5),
+            row_height,
+            "Directory:"
+        );
+        path_input.set_value("/");
+        path_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+
+        let mut scan_button = Button::new(
+            x + w - padding - 2 * button_width - 5,
+            y + padding,
+            button_width,
+            row_height,
+            "Scan"
+        );
+        scan_button.set_color(Color::from_rgb(0, 120, 255));
+        scan_button.set_label_color(Color::White);
+
+        let up_button = Button::new(
+            x + w - padding - button_width,
+            y + padding,
+            button_width,
+            row_height,
+            "Up"
+        );
+
+        let mut status_frame = Frame::new(
+            x + padding,
+            y + 2 * padding + row_height,
+            w - 2 * padding,
+            20,
+            "Scan a directory to see what is using space (double-click a folder to drill down)"
+        );
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        let table_y = y + 3 * padding + row_height + 20;
+        let table_w = w - 2 * padding;
+        let mut table = Table::new(x + padding, table_y, table_w, y + h - padding - table_y, None);
+        table.set_rows(0);
+        table.set_cols(3);
+        table.set_col_header(true);
+        table.set_col_resize(true);
+        table.set_row_height_all(22);
+        table.set_col_width(COL_NAME, table_w / 2);
+        table.set_col_width(COL_SIZE, 100);
+        table.set_col_width(COL_SHARE, table_w - table_w / 2 - 100 - 20);
+        table.end();
+
+        group.end();
+
+        let mut panel = DiskUsagePanel {
+            group,
+            path_input,
+            scan_button,
+            up_button,
+            status_frame,
+            table,
+            usage: Arc::new(Mutex::new(DiskUsage::default())),
+            sort: Arc::new(Mutex::new(DiskUsageSort::SizeDescending)),
+            current_dir: Arc::new(Mutex::new(PathBuf::from("/"))),
+            scanning: Arc::new(Mutex::new(false)),
+            remote_browser,
+        };
+
+        panel.setup_draw();
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_draw(&mut self) {
+        let usage = self.usage.clone();
+        let sort = self.sort.clone();
+
+        self.table.draw_cell(move |t, ctx, row, col, x, y, w, h| {
+            match ctx {
+                TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+                TableContext::ColHeader => {
+                    let sort = *sort.lock().unwrap();
+                    let label = match col {
+                        COL_NAME => match sort {
+                            DiskUsageSort::NameAscending => "Name ▲",
+                            DiskUsageSort::NameDescending => "Name ▼",
+                            _ => "Name",
+                        },
+                        COL_SIZE => match sort {
+                            DiskUsageSort::SizeAscending => "Size ▲",
+                            DiskUsageSort::SizeDescending => "Size ▼",
+                            _ => "Size",
+                        },
+                        _ => "Share",
+                    };
+                    draw::push_clip(x, y, w, h);
+                    draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+                    draw::set_draw_color(Color::Black);
+                    draw::set_font(Font::HelveticaBold, 12);
+                    draw::draw_text2(label, x + 3, y, w - 6, h, Align::Left);
+                    draw::pop_clip();
+                },
+                TableContext::Cell => {
+                    let usage = usage.lock().unwrap();
+                    let entry = match usage.entries.get(row as usize) {
+                        Some(entry) => entry,
+                        None => return,
+                    };
+
+                    draw::push_clip(x, y, w, h);
+                    let background = if t.is_selected(row, col) {
+                        Color::from_rgb(210, 225, 245)
+                    } else {
+                        Color::White
+                    };
+                    draw::set_draw_color(background);
+                    draw::draw_rectf(x, y, w, h);
+
+                    let share = if usage.total_kb > 0 {
+                        entry.size_kb as f64 / usage.total_kb as f64
+                    } else {
+                        0.0
+                    };
+
+                    draw::set_draw_color(Color::Black);
+                    draw::set_font(Font::Helvetica, 12);
+                    match col {
+                        COL_NAME => draw::draw_text2(&entry.name(), x + 3, y, w - 6, h, Align::Left),
+                        COL_SIZE => draw::draw_text2(&format_size_kb(entry.size_kb), x + 3, y, w - 6, h, Align::Right),
+                        _ => {
+                            // Proportional bar, coloured by how dominant the entry is
+                            let bar_w = ((w - 60) as f64 * share).round() as i32;
+                            let color = if share >= 0.5 {
+                                Color::from_rgb(200, 60, 60)
+                            } else if share >= 0.2 {
+                                Color::from_rgb(230, 150, 40)
+                            } else {
+                                Color::from_rgb(70, 140, 210)
+                            };
+                            draw::set_draw_color(color);
+                            draw::draw_rectf(x + 3, y + 4, bar_w.max(1), h - 8);
+                            draw::set_draw_color(Color::Black);
+                            draw::draw_text2(
+                                &format!("{:.1}%", share * 100.0),
+                                x + w - 57, y, 54, h, Align::Right
+                            );
+                        },
+                    }
+
+                    draw::set_draw_color(Color::Light2);
+                    draw::draw_rect(x, y, w, h);
+                    draw::pop_clip();
+                },
+                _ => (),
+            }
+        });
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.scan_button.set_callback(move |_| {
+            panel.scan_input();
+        });
+
+        let mut panel = self.clone();
+        self.path_input.set_callback(move |_| {
+            panel.scan_input();
+        });
+
+        let mut panel = self.clone();
+        self.up_button.set_callback(move |_| {
+            let parent = panel.current_dir.lock().unwrap().parent().map(|p| p.to_path_buf());
+            if let Some(parent) = parent {
+                panel.scan(&parent);
+            }
+        });
+
+        // Header clicks change the sort order, double-clicks on rows drill down
+        let mut panel = self.clone();
+        self.table.set_callback(move |t| {
+            match t.callback_context() {
+                TableContext::ColHeader => {
+                    let col = t.callback_col();
+                    panel.toggle_sort(col);
+                },
+                TableContext::Cell if app::event_clicks() => {
+                    let path = panel.usage.lock().unwrap()
+                        .entries
+                        .get(t.callback_row() as usize)
+                        .map(|e| e.path.clone());
+                    if let Some(path) = path {
+                        panel.scan(&path);
+                    }
+                },
+                _ => (),
+            }
+        });
+    }
+
+    fn toggle_sort(&mut self, col: i32) {
+        {
+            let mut sort = self.sort.lock().unwrap();
+            *sort = match (col, *sort) {
+                (COL_NAME, DiskUsageSort::NameAscending) => DiskUsageSort::NameDescending,
+                (COL_NAME, _) => DiskUsageSort::NameAscending,
+                (_, DiskUsageSort::SizeDescending) => DiskUsageSort::SizeAscending,
+                _ => DiskUsageSort::SizeDescending,
+            };
+
+            let mut usage = self.usage.lock().unwrap();
+            sort_entries(&mut usage.entries, *sort);
+        }
+
+        self.table.redraw();
+    }
+
+    fn scan_input(&mut self) {
+        let dir = self.path_input.value().trim().to_string();
+        if dir.is_empty() {
+            // Default to the directory shown in the remote browser
+            let current = self.remote_browser.lock().ok().map(|b| b.get_current_directory());
+            if let Some(current) = current {
+                self.scan(&current);
+            }
+        } else {
+            self.scan(Path::new(&dir));
+        }
+    }
+
+    /// Scan a directory on the Pi and show its breakdown
+    pub fn scan(&mut self, dir: &Path) {
+        if *self.scanning.lock().unwrap() {
+            return;
+        }
+
+        let method = self.remote_browser.lock().ok().and_then(|b| b.get_transfer_method());
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.set_status("Not connected", true);
+                return;
+            }
+        };
+
+        let dir = dir.to_path_buf();
+        self.path_input.set_value(&dir.to_string_lossy());
+        *self.scanning.lock().unwrap() = true;
+        self.scan_button.deactivate();
+        self.set_status(&format!("Scanning {} (this can take a while)...", dir.display()), false);
+
+        let result: Arc<Mutex<Option<Result<DiskUsage, String>>>> = Arc::new(Mutex::new(None));
+        let result_worker = result.clone();
+        let worker_dir = dir.clone();
+
+        thread::spawn(move || {
+            let usage = method
+                .exec(&du_command(&worker_dir))
+                .map(|output| parse_du_output(&output.stdout, &worker_dir))
+                .map_err(|e| e.to_string());
+            *result_worker.lock().unwrap() = Some(usage);
+            app::awake();
+        });
+
+        let mut panel = self.clone();
+        app::add_timeout3(POLL_INTERVAL, move |handle| {
+            let finished = result.lock().unwrap().take();
+
+            match finished {
+                Some(Ok(mut usage)) => {
+                    sort_entries(&mut usage.entries, *panel.sort.lock().unwrap());
+                    panel.set_status(
+                        &format!("{}: {} in {} entries", dir.display(), format_size_kb(usage.total_kb), usage.entries.len()),
+                        false
+                    );
+                    panel.table.set_rows(usage.entries.len() as i32);
+                    panel.table.set_row_position(0);
+                    *panel.usage.lock().unwrap() = usage;
+                    *panel.current_dir.lock().unwrap() = dir.clone();
+                    panel.finish_scan();
+                },
+                Some(Err(e)) => {
+                    panel.set_status(&format!("Scan failed: {}", e), true);
+                    panel.finish_scan();
+                },
+                None => app::repeat_timeout3(POLL_INTERVAL, handle),
+            }
+        });
+    }
+
+    fn finish_scan(&mut self) {
+        *self.scanning.lock().unwrap() = false;
+        self.scan_button.activate();
+        self.table.redraw();
+    }
+
+    fn set_status(&mut self, message: &str, is_error: bool) {
+        self.status_frame.set_label(message);
+        self.status_frame.set_label_color(if is_error { Color::Red } else { Color::Black });
+        self.group.redraw();
+    }
+}
@@ -0,0 +1,107 @@
+use fltk::app;
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// How often queued events are delivered to subscribers (seconds)
+const DISPATCH_INTERVAL: f64 = 0.05;
+
+/// Notification passed between UI components
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    /// A file was clicked in the local or remote browser
+    FileSelected { path: PathBuf, is_remote: bool },
+    /// The transfer panel finished copying a file
+    TransferFinished { source: PathBuf, destination: PathBuf, upload: bool },
+    /// The remote pane connected to, switched to or lost a device
+    ConnectionChanged { hostname: Option<String> },
+    /// The transfer queue started a copy or ran empty; `running` is the
+    /// source of the copy in progress, `queued` how many wait behind it
+    TransferQueueChanged { running: Option<PathBuf>, queued: usize },
+    /// A queued copy of `bytes` bytes finished after `seconds`
+    TransferMeasured { bytes: u64, seconds: f64 },
+    /// A queued copy failed
+    TransferFailed { source: PathBuf, upload: bool },
+    /// The keepalive found the connected Pi unreachable, or reachable again
+    ConnectionHealth { hostname: String, reachable: bool },
+    /// The selection in the local or remote browser changed; `bytes` only
+    /// covers selected files, and `scanning` says folders are being counted
+    SelectionChanged { is_remote: bool, count: usize, bytes: u64, scanning: bool },
+    /// Counting the selected folders finished; `files` and `bytes` cover
+    /// everything in the selection
+    SelectionMeasured { is_remote: bool, files: usize, bytes: u64 },
+    /// A processing job was queued, progressed, finished or was cancelled
+    ProcessingQueueChanged,
+    /// A processing job ended; `failed` counts images or steps that failed
+    ProcessingJobFinished { name: String, done: usize, failed: usize, cancelled: bool },
+}
+
+type Subscriber = Box<dyn FnMut(&AppEvent) + Send>;
+
+/// Application-wide event channel
+///
+/// Components publish events instead of locking each other. Events are
+/// queued and delivered later on the main thread, so a publisher never
+/// holds its own locks while a subscriber runs. `publish` may be called
+/// from any thread.
+pub struct EventBus {
+    sender: app::Sender<AppEvent>,
+    receiver: app::Receiver<AppEvent>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Clone for EventBus {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            receiver: self.receiver.clone(),
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl EventBus {
+    /// Create the bus and start delivering events; call once after `App::default()`
+    pub fn new() -> Self {
+        let (sender, receiver) = app::channel::<AppEvent>();
+
+        let bus = EventBus {
+            sender,
+            receiver,
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        };
+
+        let dispatcher = bus.clone();
+        app::add_timeout3(DISPATCH_INTERVAL, move |handle| {
+            dispatcher.dispatch();
+            app::repeat_timeout3(DISPATCH_INTERVAL, handle);
+        });
+
+        bus
+    }
+
+    /// Queue an event for all subscribers
+    pub fn publish(&self, event: AppEvent) {
+        log::debug!("Event: {:?}", event);
+        self.sender.send(event);
+    }
+
+    /// Run `handler` for every event published from now on
+    ///
+    /// Handlers must not subscribe further handlers while running.
+    pub fn subscribe<F>(&self, handler: F)
+    where
+        F: FnMut(&AppEvent) + Send + 'static,
+    {
+        self.subscribers.lock().unwrap().push(Box::new(handler));
+    }
+
+    fn dispatch(&self) {
+        while let Some(event) = self.receiver.recv() {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            for handler in subscribers.iter_mut() {
+                handler(&event);
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use fltk::app;
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+
+/// Upper bound on worker threads; most jobs wait on SSH rather than the CPU
+const MAX_WORKERS: usize = 8;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Fixed pool of worker threads for blocking work (transfers, listings,
+/// image decoding) so it never runs inside an FLTK callback
+pub struct TaskExecutor {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+static EXECUTOR: OnceLock<TaskExecutor> = OnceLock::new();
+
+impl TaskExecutor {
+    fn new(workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for index in 0..workers {
+            let receiver = receiver.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("worker-{}", index))
+                .spawn(move || loop {
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        // The executor was dropped
+                        Err(_) => break,
+                    };
+
+                    if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        log::error!("Background task panicked");
+                    }
+                });
+
+            if let Err(e) = spawned {
+                log::error!("Failed to start worker thread: {}", e);
+            }
+        }
+
+        TaskExecutor {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Shared executor, started on first use
+    pub fn global() -> &'static TaskExecutor {
+        EXECUTOR.get_or_init(|| {
+            let workers = thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(2)
+                .clamp(2, MAX_WORKERS);
+            TaskExecutor::new(workers)
+        })
+    }
+
+    /// Run `work` on a worker thread, then hand its result to `done` on the
+    /// FLTK main thread where widgets can be updated
+    pub fn spawn<T, W, D>(&self, work: W, done: D)
+    where
+        T: Send + 'static,
+        W: FnOnce() -> T + Send + 'static,
+        D: FnOnce(T) + Send + 'static,
+    {
+        let job: Job = Box::new(move || finish_on_main_thread(work(), done));
+
+        if self.sender.lock().unwrap().send(job).is_err() {
+            log::error!("Background executor is not running");
+        }
+    }
+}
+
+/// Shorthand for `TaskExecutor::global().spawn(work, done)`
+pub fn run_in_background<T, W, D>(work: W, done: D)
+where
+    T: Send + 'static,
+    W: FnOnce() -> T + Send + 'static,
+    D: FnOnce(T) + Send + 'static,
+{
+    TaskExecutor::global().spawn(work, done);
+}
+
+/// Like [`run_in_background`], but on a thread of its own named `name`, for
+/// work that may run for many minutes (installing packages with apt,
+/// assembling a video) and would otherwise hold a pool worker all along
+pub fn run_on_own_thread<T, W, D>(name: &str, work: W, done: D)
+where
+    T: Send + 'static,
+    W: FnOnce() -> T + Send + 'static,
+    D: FnOnce(T) + Send + 'static,
+{
+    let spawned = thread::Builder::new()
+        .name(name.to_string())
+        .spawn(move || finish_on_main_thread(work(), done));
+
+    if let Err(e) = spawned {
+        log::error!("Failed to start thread {}: {}", name, e);
+    }
+}
+
+/// Hand `result` to `done` on the FLTK main thread
+fn finish_on_main_thread<T, D>(result: T, done: D)
+where
+    T: Send + 'static,
+    D: FnOnce(T) + Send + 'static,
+{
+    let mut pending = Some((done, result));
+    app::awake_callback(move || {
+        if let Some((done, result)) = pending.take() {
+            done(result);
+        }
+    });
+}
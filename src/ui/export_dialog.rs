@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::file::temp_download_path;
+use crate::core::image::{export_image, ExportPreset, EXPORT_PRESETS};
+use crate::core::utils::AppResult;
+use crate::transfer::method::TransferMethod;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::ui::transfer_queue::{QueuedTransfer, TransferQueue};
+
+/// Render `image` through a preset the user picks, then save it locally or
+/// upload it into `remote_dir` on the Pi
+///
+/// `remote` is the connection and current directory of the remote pane;
+/// without one only saving is offered.
+pub fn export_dialog(image: &Path, remote: Option<(Box<dyn TransferMethod>, PathBuf)>, transfer_queue: TransferQueue) {
+    let options: Vec<String> = EXPORT_PRESETS
+        .iter()
+        .map(|preset| format!("{} - {}", preset.name, preset.description))
+        .collect();
+    let Some(index) = dialogs::select_dialog("Export", "Export preset:", &options) else { return };
+    let preset = &EXPORT_PRESETS[index];
+
+    let mut places = vec!["Save...", "Cancel"];
+    if remote.is_some() {
+        places.insert(1, "Upload to the Pi");
+    }
+    let upload = match places.get(dialogs::choice_dialog("Export", "Send the exported image to:", &places) as usize) {
+        Some(&"Save...") => false,
+        Some(&"Upload to the Pi") => true,
+        _ => return,
+    };
+
+    let output = if upload {
+        match temp_download_path(&preset.output_name(image)) {
+            Ok(path) => path,
+            Err(e) => {
+                dialogs::error_dialog("Export failed", &e.into());
+                return;
+            }
+        }
+    } else {
+        let Some(path) = dialogs::save_file_dialog("Export As", &format!("*.{}", preset.format.extension())) else { return };
+        with_extension_for(path, preset)
+    };
+
+    let input = image.to_path_buf();
+    let preset = preset.clone();
+    run_in_background(
+        move || -> AppResult<PathBuf> {
+            export_image(&input, &output, &preset)?;
+            log::info!("Exported {} as {} with the {} preset", input.display(), output.display(), preset.name);
+            Ok(output)
+        },
+        move |result| {
+            let output = match result {
+                Ok(output) => output,
+                Err(e) => {
+                    dialogs::error_dialog("Export failed", &e);
+                    return;
+                }
+            };
+            match remote {
+                Some((method, remote_dir)) if upload => {
+                    let destination = remote_dir.join(output.file_name().unwrap_or_default());
+                    transfer_queue.enqueue(QueuedTransfer { method, source: output, destination, upload: true });
+                },
+                _ => notify(ToastKind::Success, "Exported", &output.display().to_string()),
+            }
+        }
+    );
+}
+
+/// `path` with the preset's extension when it was typed without one
+fn with_extension_for(path: PathBuf, preset: &ExportPreset) -> PathBuf {
+    if path.extension().is_some() {
+        path
+    } else {
+        path.with_extension(preset.format.extension())
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+
+use crate::core::file::{extract_local, remote_extract_command, ArchiveKind, OverwritePolicy};
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::method::TransferMethod;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::ui::remote_dir_dialog::choose_remote_directory;
+use crate::core::locale::trf;
+
+/// Ask where to extract `archive` and what to do about existing files,
+/// then extract it in the background
+///
+/// With a `method` the archive is on the Pi and is extracted there with
+/// `tar` or `unzip`. `on_extracted` runs once the files are written so the
+/// pane showing them can refresh.
+pub fn extract_archive<F>(archive: PathBuf, method: Option<Box<dyn TransferMethod>>, on_extracted: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let Some(kind) = ArchiveKind::from_path(&archive) else { return };
+    let Some(folder) = archive.parent().map(Path::to_path_buf) else { return };
+    let into_folder = folder.join(kind.stem(&archive));
+
+    let subfolder = format!("Into \"{}\"", kind.stem(&archive));
+    let dest = match dialogs::choice_dialog(
+        "Extract",
+        &trf("Extract {} where?", &[&archive.file_name().unwrap_or_default().to_string_lossy()]),
+        &["Here", subfolder.as_str(), "Other Folder...", "Cancel"]
+    ) {
+        0 => folder,
+        1 => into_folder,
+        2 => {
+            let chosen = match &method {
+                Some(method) => choose_remote_directory(method.clone_box(), &folder),
+                None => dialogs::open_directory_dialog("Extract Into"),
+            };
+            match chosen {
+                Some(dest) => dest,
+                None => return,
+            }
+        },
+        _ => return,
+    };
+
+    let policy = match dialogs::choice_dialog(
+        "Extract",
+        "When a file already exists:",
+        &["Overwrite It", "Keep It", "Cancel"]
+    ) {
+        0 => OverwritePolicy::Overwrite,
+        1 => OverwritePolicy::Skip,
+        _ => return,
+    };
+
+    notify(ToastKind::Info, "Extracting", &archive.display().to_string());
+    run_in_background(
+        move || -> AppResult<Option<usize>> {
+            let count = match method {
+                Some(method) => {
+                    let output = method.exec(&remote_extract_command(&archive, &dest, policy)?)?;
+                    if !output.success() {
+                        return Err(AppError::FileError(output.stderr.trim().to_string()));
+                    }
+                    // tar and unzip are run quietly, so the count is unknown
+                    None
+                },
+                None => Some(extract_local(&archive, &dest, policy)?),
+            };
+            log::info!("Extracted {} into {}", archive.display(), dest.display());
+            Ok(count)
+        },
+        move |result| {
+            match result {
+                Ok(Some(count)) => notify(ToastKind::Success, "Extracted", &trf("{} files", &[&count])),
+                Ok(None) => notify(ToastKind::Success, "Extracted", "Archive extracted on the Pi"),
+                Err(e) => dialogs::error_dialog("Extract failed", &e),
+            }
+            on_extracted();
+        }
+    );
+}
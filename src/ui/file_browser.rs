@@ -1,48 +1,493 @@
 // src/ui/file_browser.rs - File browser panel
 pub mod file_browser {
     use fltk::{
-        browser::FileBrowser,
-        button::Button,
-        enums::{FrameType},
+        button::{Button, CheckButton},
+        draw,
+        enums::{Align, CallbackTrigger, Color, Event, Font, FrameType, Key},
+        frame::Frame,
         group::Group,
         input::Input,
+        menu::{MenuButton, MenuItem},
+        table::{TableContext, TableRow, TableRowSelectFlag, TableRowSelectMode},
         prelude::*,
         app,
         dialog, // Added for message dialogs
     };
+    use std::collections::HashSet;
     use std::path::Path;
     use std::path::PathBuf;
-    use std::sync::{Arc, Mutex};
+    use std::sync::{Arc, Mutex, OnceLock};
     
+    use crate::config::Config;
     use crate::transfer::method::TransferMethod;
     use crate::transfer::method::TransferMethodFactory;
-    use crate::transfer::method::TransferError;
+    use crate::transfer::method::RemoteEntry;
+    use crate::core::file::{
+        CAN_RESTORE_FROM_TRASH,
+        move_to_trash,
+        restore_from_trash,
+        rename_entry,
+        duplicate_entry,
+        create_folder,
+        temp_download_path,
+        is_image_file,
+        ArchiveKind,
+        FileSystem,
+        LocalFs,
+        RemoteFs,
+    };
+    use crate::core::utils::{
+        AppError,
+        AppResult,
+        file_manager_name,
+        open_with_default_app,
+        reveal_in_file_manager,
+    };
+    use crate::ui::browser::model::{
+        bookmark_dir,
+        type_label,
+        BrowserModel,
+        ListingStart,
+        Opened,
+        Row,
+        SortColumn,
+    };
+    use crate::ui::dialogs::dialogs;
+    use crate::core::locale::{tr, trf};
+    use crate::ui::theme::font_size;
+    use crate::ui::executor::run_in_background;
+    use crate::ui::extract_dialog::extract_archive;
+    use crate::ui::remote_edit;
+    use crate::ui::hover_preview::{self, MAX_HOVER_PREVIEW_BYTES};
+    use crate::transfer::thumbnail_cache::{self, RemoteImage};
+    use crate::ui::notifications::{notify, ToastKind};
+    use crate::transfer::listing_cache;
+    
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+    
+    pub use crate::ui::browser::model::FileEntry;
+    
+    // Told about files and folders opened in the list, with whether the
+    // entry is a folder
+    type OpenCallback = Arc<Mutex<Option<Box<dyn FnMut(PathBuf, bool) + Send + Sync>>>>;
+    
+    // Offered key presses in the list; returns whether it handled one
+    type KeyHandler = Arc<Mutex<Option<Box<dyn FnMut() -> bool + Send + Sync>>>>;
+    
+    // Frames of the spinner shown while a remote listing runs
+    const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+    
+    // How often the rows in view are checked for images to prefetch
+    // thumbnails of (seconds)
+    const PREFETCH_INTERVAL: f64 = 0.5;
+    
+    // Columns of the detailed list view
+    const COL_NAME: i32 = 0;
+    const COL_SIZE: i32 = 1;
+    const COL_TYPE: i32 = 2;
+    const COL_MODIFIED: i32 = 3;
+    const COLUMNS: i32 = 4;
+    
+    // Sort order a column header stands for
+    fn sort_column_at(col: i32) -> SortColumn {
+        match col {
+            COL_SIZE => SortColumn::Size,
+            COL_TYPE => SortColumn::Type,
+            COL_MODIFIED => SortColumn::Modified,
+            _ => SortColumn::Name,
+        }
+    }
+    
+    // Resize the table to the model's rows and repaint it; when the model
+    // replaced its rows the selection goes and the list scrolls to the top
+    fn sync_table(table: &mut TableRow, state: &mut BrowserModel) {
+        if state.take_rows_reset() {
+            table.select_all_rows(TableRowSelectFlag::Deselect);
+            table.set_row_position(0);
+        }
+        table.set_rows(state.row_count() as i32);
+        table.redraw();
+    }
+    
+    // Human-readable byte count for the Size column
+    fn format_size(bytes: u64) -> String {
+        let size = bytes as f64;
+        if size >= 1024.0 * 1024.0 * 1024.0 {
+            format!("{:.1} GB", size / (1024.0 * 1024.0 * 1024.0))
+        } else if size >= 1024.0 * 1024.0 {
+            format!("{:.1} MB", size / (1024.0 * 1024.0))
+        } else if size >= 1024.0 {
+            format!("{:.1} KB", size / 1024.0)
+        } else {
+            format!("{} B", bytes)
+        }
+    }
+    
+    // Show whether the current directory is bookmarked and list the pane's bookmarks
+    fn update_bookmarks(star: &mut Button, menu: &mut MenuButton, state: &BrowserModel) {
+        let (bookmarks, current) = state.bookmarks();
+        star.set_label(if current { "★" } else { "☆" });
+        star.set_tooltip(if current { "Remove this directory from the bookmarks" } else { "Bookmark this directory" });
+        
+        menu.clear();
+        for bookmark in &bookmarks {
+            // Escape characters the menu treats as structure
+            menu.add_choice(&bookmark.path.replace('/', "\\/").replace('|', "\\|").replace('&', "&&"));
+        }
+        if bookmarks.is_empty() {
+            menu.deactivate();
+        } else {
+            menu.activate();
+        }
+    }
+    
+    // Pop up the translated `choices` at the mouse; returns the chosen one
+    // in English
+    fn popup_menu(choices: &[&str]) -> Option<String> {
+        let labels: Vec<&'static str> = choices.iter().map(|choice| menu_label(&tr(choice))).collect();
+        let menu = MenuItem::new(&labels);
+        let chosen = menu.popup(app::event_x(), app::event_y())?.label()?;
+        // Two sources may share a translation, so only the offered labels
+        // are matched
+        labels.iter().position(|label| *label == chosen).map(|index| choices[index].to_string())
+    }
+    
+    // `text` kept for the rest of the program, as MenuItem needs; each
+    // distinct label is leaked only once
+    fn menu_label(text: &str) -> &'static str {
+        static LABELS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+        let mut labels = LABELS.get_or_init(Default::default).lock().unwrap();
+        match labels.get(text) {
+            Some(label) => label,
+            None => {
+                let label: &'static str = Box::leak(text.to_string().into_boxed_str());
+                labels.insert(label);
+                label
+            }
+        }
+    }
+    
+    // Offer file management for a local entry, or for the directory itself
+    // when the click wasn't on an entry; returns whether the listing changed
+    fn local_file_menu(
+        shared_state: &Arc<Mutex<BrowserModel>>,
+        target: Option<(PathBuf, bool)>,
+        refresh_button: &Button
+    ) -> bool {
+        let (current_dir, last_trashed) = {
+            let state = shared_state.lock().unwrap();
+            (state.current_dir().to_path_buf(), state.last_trashed().to_vec())
+        };
+        
+        // Menu labels must outlive the menu, so build this one only once
+        static REVEAL: OnceLock<String> = OnceLock::new();
+        let reveal = REVEAL.get_or_init(|| format!("Reveal in {}", file_manager_name()));
+        let mut choices = vec!["New Folder..."];
+        if target.is_some() {
+            choices.extend(["Open with Default App", reveal.as_str(), "Rename...", "Duplicate", "Move to Trash"]);
+        }
+        if matches!(&target, Some((path, false)) if ArchiveKind::from_path(path).is_some()) {
+            choices.push("Extract...");
+        }
+        if CAN_RESTORE_FROM_TRASH && !last_trashed.is_empty() {
+            choices.push("Undo Move to Trash");
+        }
+        
+        let chosen = match popup_menu(&choices) {
+            Some(label) => label,
+            None => return false,
+        };
+        
+        let result = match (chosen.as_str(), target) {
+            ("Open with Default App", Some((path, _))) => {
+                if let Err(e) = open_with_default_app(&path) {
+                    dialogs::error_dialog("Failed to open file", &e);
+                }
+                return false;
+            },
+            (label, Some((path, _))) if label == reveal => {
+                if let Err(e) = reveal_in_file_manager(&path) {
+                    dialogs::error_dialog("Failed to show file", &e);
+                }
+                return false;
+            },
+            ("Extract...", Some((path, _))) => {
+                let mut refresh_button = refresh_button.clone();
+                extract_archive(path, None, move || refresh_button.do_callback());
+                return false;
+            },
+            ("New Folder...", _) => match dialogs::input_dialog("New Folder", "Folder name:", "New Folder") {
+                Some(name) => create_folder(&current_dir, &name).map(|path| {
+                    log::info!("Created folder {}", path.display());
+                }),
+                None => return false,
+            },
+            ("Rename...", Some((path, _))) => {
+                let old_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                match dialogs::input_dialog("Rename", "New name:", &old_name) {
+                    Some(name) if name.trim() != old_name => rename_entry(&path, &name).map(|renamed| {
+                        log::info!("Renamed {} to {}", path.display(), renamed.display());
+                    }),
+                    _ => return false,
+                }
+            },
+            ("Duplicate", Some((path, _))) => duplicate_entry(&path).map(|copy| {
+                log::info!("Duplicated {} as {}", path.display(), copy.display());
+            }),
+            ("Move to Trash", Some((path, is_dir))) => {
+                let what = if is_dir { "folder" } else { "file" };
+                let question = format!(
+                    "Move the {} \"{}\" to the trash?",
+                    what,
+                    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+                );
+                if dialogs::choice_dialog("Move to Trash", &question, &["Move to Trash", "Cancel"]) != 0 {
+                    return false;
+                }
+                let trashed = vec![path];
+                move_to_trash(&trashed).map(|_| {
+                    log::info!("Moved {} to the trash", trashed[0].display());
+                    shared_state.lock().unwrap().set_last_trashed(trashed);
+                })
+            },
+            ("Undo Move to Trash", _) => restore_from_trash(&last_trashed).map(|restored| {
+                log::info!("Restored {} item(s) from the trash", restored);
+                shared_state.lock().unwrap().set_last_trashed(Vec::new());
+                if restored == 0 {
+                    dialogs::message_dialog(
+                        "Undo Move to Trash",
+                        "Nothing was restored; the items are no longer in the trash or their place is taken."
+                    );
+                }
+            }),
+            _ => return false,
+        };
+        
+        if let Err(e) = result {
+            dialogs::error_dialog(&trf("{} failed", &[&tr(&chosen).trim_end_matches("...")]), &e);
+        }
+        true
+    }
+    
+    // Offer to open a remote file, or show it in the file manager, after
+    // downloading it to a temporary directory
+    fn remote_file_menu(shared_state: &Arc<Mutex<BrowserModel>>, path: PathBuf, refresh_button: &Button) {
+        static REVEAL: OnceLock<String> = OnceLock::new();
+        let reveal = REVEAL.get_or_init(|| format!("Reveal Downloaded Copy in {}", file_manager_name()));
+        let editing = shared_state.lock().unwrap().transfer_method()
+            .map_or(false, |method| remote_edit::is_editing(&method.connection_key(), &path));
+        let mut choices = vec!["Open with Default App", "Edit Remotely", reveal.as_str()];
+        if editing {
+            choices.push("Stop Editing Remotely");
+        }
+        if ArchiveKind::from_path(&path).is_some() {
+            choices.push("Extract on the Pi...");
+        }
+        let chosen = match popup_menu(&choices) {
+            Some(label) => label,
+            None => return,
+        };
+        let reveal_chosen = chosen == *reveal;
+        
+        let method = match shared_state.lock().unwrap().transfer_method() {
+            Some(method) => method.clone_box(),
+            None => {
+                dialogs::error_dialog("Cannot open remote file", &AppError::NotConnected);
+                return;
+            }
+        };
+        
+        if chosen == "Extract on the Pi..." {
+            let mut refresh_button = refresh_button.clone();
+            extract_archive(path, Some(method), move || refresh_button.do_callback());
+            return;
+        }
+        
+        if chosen == "Edit Remotely" {
+            remote_edit::edit_remotely(method, path);
+            return;
+        }
+        
+        if chosen == "Stop Editing Remotely" {
+            remote_edit::stop_editing(&method.connection_key(), &path);
+            return;
+        }
+        
+        let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        run_in_background(
+            move || -> AppResult<PathBuf> {
+                let local_path = temp_download_path(&file_name)?;
+                method.download_file(&path, &local_path)?;
+                log::info!("Downloaded {} to {} for opening", path.display(), local_path.display());
+                Ok(local_path)
+            },
+            move |result| {
+                let opened = result.and_then(|local_path| {
+                    if reveal_chosen {
+                        reveal_in_file_manager(&local_path)
+                    } else {
+                        open_with_default_app(&local_path)
+                    }
+                });
+                if let Err(e) = opened {
+                    dialogs::error_dialog("Failed to open remote file", &e);
+                }
+            }
+        );
+    }
+    
+    // Act on a line of the list as a click or Enter does: enter a directory
+    // or "..", show the next page, or pass a file to the browser callback
+    fn open_row(
+        table: &mut TableRow,
+        shared_state: &Arc<Mutex<BrowserModel>>,
+        path_input: &mut Input,
+        refresh_button: &mut Button,
+        callback: &OpenCallback,
+        row: usize
+    ) {
+        // Let the model act on the line, then release the lock before
+        // listing a directory or handing over a file
+        let opened = {
+            let mut state = shared_state.lock().unwrap();
+            let opened = state.open(row);
+            sync_table(table, &mut state);
+            opened
+        };
+        
+        match opened {
+            Some(Opened::Dir(dir)) => {
+                path_input.set_value(&dir.to_string_lossy());
+                refresh_button.do_callback(); // Use the refresh to load the directory
+            },
+            Some(Opened::File(file_path)) => {
+                // File selected - call the callback if set
+                if let Ok(mut callback_guard) = callback.lock() {
+                    if let Some(ref mut callback) = *callback_guard {
+                        callback(file_path, false);
+                    }
+                }
+            },
+            Some(Opened::Page) | None => {},
+        }
+    }
+    
+    // Preview the image in `row` on hover when enabled, otherwise take the
+    // thumbnail of the previous row down
+    fn hover_row(shared_state: &Arc<Mutex<BrowserModel>>, row: Option<i32>) {
+        let state = shared_state.lock().unwrap();
+        let settings = state.interaction();
+        let entry = match row.and_then(|row| state.entry_at(row as usize)) {
+            Some(entry) if settings.hover_preview => entry,
+            _ => {
+                hover_preview::hide();
+                return;
+            }
+        };
+        if !hover_previewable(entry) {
+            hover_preview::hide();
+            return;
+        }
+        
+        let delay = Duration::from_millis(settings.hover_delay_ms);
+        if state.filesystem().is_none() {
+            return;
+        }
+        // Without a connection behind them the files are on this computer
+        let Some(method) = state.transfer_method() else {
+            hover_preview::schedule(entry.path.clone(), None, delay);
+            return;
+        };
+        // A prefetched thumbnail saves the download
+        match thumbnail_cache::get(&method.connection_key(), &remote_image(entry)) {
+            Some(cached) => hover_preview::schedule(cached, None, delay),
+            None => hover_preview::schedule(entry.path.clone(), Some((method.clone_box(), remote_image(entry).modified)), delay),
+        }
+    }
+    
+    fn hover_previewable(entry: &FileEntry) -> bool {
+        !entry.is_dir && entry.size <= MAX_HOVER_PREVIEW_BYTES && FileBrowserPanel::is_image_file(&entry.path)
+    }
+    
+    fn remote_image(entry: &FileEntry) -> RemoteImage {
+        RemoteImage {
+            path: entry.path.clone(),
+            size: entry.size,
+            modified: entry.modified.map(|m| m.timestamp()),
+        }
+    }
     
-    // A struct to represent a file entry in a directory
-    #[derive(Clone, Debug)]
-    pub struct FileEntry {
-        pub name: String,
-        pub path: PathBuf,
-        pub is_dir: bool,
-        pub size: u64,
+    // Hand the remote images in view to the thumbnail prefetch whenever the
+    // folder or the rows in view changed; only while hover previews are on,
+    // as nothing else shows the thumbnails
+    fn prefetch_visible(
+        table: &TableRow,
+        shared_state: &Arc<Mutex<BrowserModel>>,
+        last_view: &mut Option<(PathBuf, i32, i32, usize)>
+    ) {
+        if !table.visible_r() {
+            return;
+        }
+        // A listing in progress holds the lock; try again next time
+        let Ok(state) = shared_state.try_lock() else { return };
+        if !state.interaction().hover_preview {
+            return;
+        }
+        // Local images need no thumbnails fetched
+        let Some(method) = state.transfer_method() else { return };
+        
+        let (top, bottom, _, _) = table.visible_cells();
+        let view = (state.current_dir().to_path_buf(), top, bottom, state.row_count());
+        if last_view.as_ref() == Some(&view) {
+            return;
+        }
+        *last_view = Some(view);
+        
+        let images: Vec<RemoteImage> = (top.max(0)..=bottom)
+            .filter_map(|row| match state.entry_at(row as usize) {
+                Some(entry) if hover_previewable(entry) => Some(remote_image(entry)),
+                _ => None,
+            })
+            .collect();
+        if !images.is_empty() {
+            thumbnail_cache::prefetch(method.clone_box(), images);
+        }
     }
     
-    // Create a struct to hold state that needs to be shared between callbacks
-    struct SharedState {
-        is_remote: bool,
-        current_dir: PathBuf,
-        entries: Vec<FileEntry>,
-        transfer_method: Option<Box<dyn TransferMethod>>,
+    fn move_selection(table: &mut TableRow, row: i32) {
+        table.select_all_rows(TableRowSelectFlag::Deselect);
+        // A row outside the table is simply left unselected
+        let _ = table.select_row(row, TableRowSelectFlag::Select);
+        
+        let shown = ((table.h() - table.col_header_height()) / table.row_height(0).max(1) - 1).max(1);
+        let top = table.row_position();
+        if row < top {
+            table.set_row_position(row);
+        } else if row >= top + shown {
+            table.set_row_position(row - shown + 1);
+        }
     }
     
     pub struct FileBrowserPanel {
         group: Group,
-        browser: FileBrowser,
+        table: TableRow,
         path_input: Input,
+        filter_input: Input,
+        images_only: CheckButton,
         refresh_button: Button,
+        bookmark_button: Button,
+        bookmarks_menu: MenuButton,
+        cancel_button: Button,
+        status_frame: Frame,
         // Move state to a shared Arc<Mutex>
-        shared_state: Arc<Mutex<SharedState>>,
-        callback: Option<Box<dyn FnMut(PathBuf, bool) + Send + Sync>>,
+        shared_state: Arc<Mutex<BrowserModel>>,
+        // Shared with the browser widget callback so set_callback takes effect
+        callback: OpenCallback,
+        // Offered key presses in the list before the built-in navigation
+        key_handler: KeyHandler,
+        // Told about clicks that only select, when opening takes a double click
+        selection_callback: Arc<Mutex<Option<Box<dyn FnMut() + Send + Sync>>>>,
         // Connection credentials
         pub current_hostname: Option<String>,
         pub current_username: Option<String>,
@@ -54,17 +499,25 @@ pub mod file_browser {
             // Create clone that shares the same state
             let clone = Self {
                 group: self.group.clone(),
-                browser: self.browser.clone(),
+                table: self.table.clone(),
+                filter_input: self.filter_input.clone(),
+                images_only: self.images_only.clone(),
                 path_input: self.path_input.clone(),
                 refresh_button: self.refresh_button.clone(),
+                bookmark_button: self.bookmark_button.clone(),
+                bookmarks_menu: self.bookmarks_menu.clone(),
+                cancel_button: self.cancel_button.clone(),
+                status_frame: self.status_frame.clone(),
                 shared_state: self.shared_state.clone(), // Share the same state
-                callback: None, // Cannot clone the callback
+                callback: self.callback.clone(), // Share the same callback slot
+                key_handler: self.key_handler.clone(),
+                selection_callback: self.selection_callback.clone(),
                 current_hostname: self.current_hostname.clone(),
                 current_username: self.current_username.clone(),
                 current_password: self.current_password.clone(),
             };
             
-            println!("FileBrowserPanel cloned with shared state");
+            log::info!("FileBrowserPanel cloned with shared state");
             clone
         }
     }
@@ -82,205 +535,332 @@ pub mod file_browser {
                 25, 
                 title
             );
-            title_frame.set_label_size(14);
+            title_frame.set_label_size(font_size(14));
             title_frame.set_align(fltk::enums::Align::Left | fltk::enums::Align::Inside);
             
+            // Progress of remote listings, right of the title
+            let mut status_frame = Frame::new(
+                x + w / 2, 
+                y + 10, 
+                w / 2 - 10, 
+                25, 
+                None
+            );
+            status_frame.set_label_size(font_size(12));
+            status_frame.set_align(fltk::enums::Align::Right | fltk::enums::Align::Inside);
+            
             // Create path input
             let mut path_input = Input::new(
                 x + 10, 
                 y + 40, 
-                w - 110, 
+                w - 170, 
                 25, 
                 None
             );
             path_input.set_readonly(true);
             
+            // Star toggling a bookmark on the current directory
+            let mut bookmark_button = Button::new(
+                x + w - 155, 
+                y + 40, 
+                30, 
+                25, 
+                "☆"
+            );
+            bookmark_button.set_tooltip("Bookmark this directory");
+            
+            // Jump to a bookmarked directory
+            let mut bookmarks_menu = MenuButton::new(
+                x + w - 123, 
+                y + 40, 
+                30, 
+                25, 
+                None
+            );
+            bookmarks_menu.set_tooltip("Bookmarked directories");
+            
             // Refresh button
-            let refresh_button = Button::new(
+            let mut refresh_button = Button::new(
                 x + w - 90, 
                 y + 40, 
                 80, 
                 25, 
                 "Refresh"
             );
+            refresh_button.set_tooltip("List the directory again, ignoring cached listings");
             
-            // File browser
-            let mut browser = FileBrowser::new(
-                x + 10, 
+            // Takes the refresh button's place while a remote listing runs
+            let mut cancel_button = Button::new(
+                x + w - 90, 
+                y + 40, 
+                80, 
+                25, 
+                "Cancel"
+            );
+            cancel_button.hide();
+            
+            // Narrow the list by name as the user types
+            let mut filter_input = Input::new(
+                x + 55, 
                 y + 75, 
-                w - 20, 
-                h - 85, 
+                w - 175, 
+                25, 
+                "Filter:"
+            );
+            filter_input.set_trigger(CallbackTrigger::Changed);
+            filter_input.set_tooltip("Show names containing this text, or matching a pattern such as *.jpg or frame_0??.png");
+            
+            let mut images_only = CheckButton::new(
+                x + w - 110, 
+                y + 75, 
+                100, 
+                25, 
+                "Images only"
+            );
+            images_only.set_tooltip("Hide files that aren't images; folders stay visible");
+            
+            // Detailed list: name, size, type and modification time
+            let table_w = w - 20;
+            let mut table = TableRow::new(
+                x + 10, 
+                y + 105, 
+                table_w, 
+                h - 115, 
                 None
             );
-            browser.set_type(fltk::browser::BrowserType::Hold);
-            browser.set_frame(FrameType::EngravedBox);
-            browser.set_text_size(12);
+            table.set_type(TableRowSelectMode::Single);
+            table.set_rows(0);
+            table.set_cols(COLUMNS);
+            table.set_col_header(true);
+            table.set_col_resize(true);
+            table.set_row_height_all(20);
+            table.set_col_header_height(22);
+            table.set_col_width(COL_SIZE, 75);
+            table.set_col_width(COL_TYPE, 80);
+            table.set_col_width(COL_MODIFIED, 115);
+            table.set_col_width(COL_NAME, (table_w - 75 - 80 - 115 - 20).max(100));
+            table.end();
             
             group.end();
             
             // Create shared state
-            let shared_state = Arc::new(Mutex::new(SharedState {
-                is_remote: false,
-                current_dir: PathBuf::new(),
-                entries: Vec::new(),
-                transfer_method: None,
-            }));
+            let shared_state = Arc::new(Mutex::new(BrowserModel::new()));
             
             let mut panel = FileBrowserPanel {
                 group,
-                browser,
+                table,
                 path_input,
+                filter_input,
+                images_only,
                 refresh_button,
+                bookmark_button,
+                bookmarks_menu,
+                cancel_button,
+                status_frame,
                 shared_state,
-                callback: None,
+                callback: Arc::new(Mutex::new(None)),
+                key_handler: Arc::new(Mutex::new(None)),
+                selection_callback: Arc::new(Mutex::new(None)),
                 current_hostname: None,
                 current_username: None,
                 current_password: None,
             };
             
+            panel.setup_draw();
             panel.setup_callbacks();
+            panel.setup_prefetch();
             
             panel
         }
         
+        // Keep thumbnails of the remote images in view cached, so hover
+        // previews over SSH show at once
+        fn setup_prefetch(&mut self) {
+            let table = self.table.clone();
+            let shared_state = self.shared_state.clone();
+            let mut last_view = None;
+            app::add_timeout3(PREFETCH_INTERVAL, move |handle| {
+                prefetch_visible(&table, &shared_state, &mut last_view);
+                app::repeat_timeout3(PREFETCH_INTERVAL, handle);
+            });
+        }
+        
+        fn setup_draw(&mut self) {
+            let shared_state = self.shared_state.clone();
+            
+            self.table.draw_cell(move |t, ctx, row, col, x, y, w, h| {
+                match ctx {
+                    TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+                    TableContext::ColHeader => {
+                        let (sort_column, ascending) = shared_state.lock().unwrap().sort();
+                        let column = sort_column_at(col);
+                        let label = if column == sort_column {
+                            format!("{} {}", column.title(), if ascending { "▲" } else { "▼" })
+                        } else {
+                            column.title().to_string()
+                        };
+                        
+                        draw::push_clip(x, y, w, h);
+                        draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+                        draw::set_draw_color(Color::Black);
+                        draw::set_font(Font::HelveticaBold, 12);
+                        draw::draw_text2(&label, x + 3, y, w - 6, h, Align::Left);
+                        draw::pop_clip();
+                    },
+                    TableContext::Cell => {
+                        let state = shared_state.lock().unwrap();
+                        let line = match state.row_at(row as usize) {
+                            Some(line) => line,
+                            None => return,
+                        };
+                        
+                        draw::push_clip(x, y, w, h);
+                        let background = if t.row_selected(row) {
+                            Color::from_rgb(210, 225, 245)
+                        } else {
+                            Color::White
+                        };
+                        draw::set_draw_color(background);
+                        draw::draw_rectf(x, y, w, h);
+                        
+                        let (text, font, color, align) = match (line, col) {
+                            (Row::Parent, COL_NAME) => ("..".to_string(), Font::Helvetica, Color::Black, Align::Left),
+                            (Row::Parent, COL_TYPE) => ("Parent".to_string(), Font::Helvetica, Color::Dark3, Align::Left),
+                            (Row::Entry(entry), COL_NAME) if entry.link_target.is_some() => {
+                                let target = entry.link_target.as_deref().unwrap_or_default();
+                                let name = if entry.is_dir { format!("{}/", entry.name) } else { entry.name.clone() };
+                                (format!("{} -> {}", name, target), Font::HelveticaItalic, Color::Black, Align::Left)
+                            },
+                            (Row::Entry(entry), COL_NAME) if entry.is_dir => {
+                                (format!("{}/", entry.name), Font::HelveticaBold, Color::Black, Align::Left)
+                            },
+                            (Row::Entry(entry), COL_NAME) => (entry.name.clone(), Font::Helvetica, Color::Black, Align::Left),
+                            (Row::Entry(entry), COL_SIZE) if !entry.is_dir => {
+                                (format_size(entry.size), Font::Helvetica, Color::Black, Align::Right)
+                            },
+                            (Row::Entry(entry), COL_TYPE) => (type_label(entry), Font::Helvetica, Color::Dark3, Align::Left),
+                            (Row::Entry(entry), COL_MODIFIED) => (
+                                entry.modified.map(|m| m.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default(),
+                                Font::Helvetica,
+                                Color::Dark3,
+                                Align::Left
+                            ),
+                            (Row::LoadMore, COL_NAME) => (state.load_more_label(), Font::HelveticaItalic, Color::Blue, Align::Left),
+                            (Row::Message(message), COL_NAME) => (message.to_string(), Font::HelveticaItalic, Color::Dark3, Align::Left),
+                            _ => (String::new(), Font::Helvetica, Color::Black, Align::Left),
+                        };
+                        
+                        draw::set_draw_color(color);
+                        draw::set_font(font, 12);
+                        draw::draw_text2(&text, x + 3, y, w - 6, h, align | Align::Clip);
+                        
+                        draw::set_draw_color(Color::Light2);
+                        draw::draw_rect(x, y, w, h);
+                        draw::pop_clip();
+                    },
+                    _ => (),
+                }
+            });
+        }
+        
         fn setup_callbacks(&mut self) {
-            let mut browser_clone = self.browser.clone();
+            let mut table_clone = self.table.clone();
             let path_input_clone = self.path_input.clone();
-            let callback_data = Arc::new(Mutex::new(None::<Box<dyn FnMut(PathBuf, bool) + Send + Sync>>));
+            let callback_data = self.callback.clone();
             
             // Shared state for callback closures
             let shared_state_refresh = self.shared_state.clone();
             
             let mut refresh_button = self.refresh_button.clone();
-            refresh_button.set_callback(move |_| {
-                // Lock the state and make a copy of what we need
-                let current_dir;
-                let is_remote;
-                let has_transfer_method;
-                let transfer_method_name;
-                
-                {
-                    let state = shared_state_refresh.lock().unwrap();
-                    is_remote = state.is_remote;
-                    current_dir = state.current_dir.clone();
-                    has_transfer_method = state.transfer_method.is_some();
-                    transfer_method_name = state.transfer_method.as_ref().map(|m| m.get_name().to_string());
-                }
-                
-                println!("Refresh callback with is_remote = {}", is_remote);
+            let mut cancel_button = self.cancel_button.clone();
+            let mut status_frame = self.status_frame.clone();
+            let mut bookmark_refresh = self.bookmark_button.clone();
+            let mut bookmarks_menu_refresh = self.bookmarks_menu.clone();
+            refresh_button.set_callback(move |refresh| {
+                // The model shows cached and local listings at once and
+                // hands remote ones back to be run here
+                let start = {
+                    let mut state = shared_state_refresh.lock().unwrap();
+                    let start = state.refresh();
+                    sync_table(&mut table_clone, &mut state);
+                    update_bookmarks(&mut bookmark_refresh, &mut bookmarks_menu_refresh, &state);
+                    start
+                };
                 
-                // Clear browser
-                browser_clone.clear();
-                
-                // Add parent directory option if not at root
-                if current_dir != PathBuf::from("/") && !current_dir.as_os_str().is_empty() {
-                    browser_clone.add("..");
-                }
+                refresh.show();
+                cancel_button.hide();
+                status_frame.set_label("");
                 
-                if is_remote {
-                    // Remote directory refresh
-                    println!("Refreshing remote directory: {}", current_dir.display());
+                if let ListingStart::Background(listing) = start {
+                    // Offer Cancel and show progress until the listing finishes
+                    refresh.hide();
+                    cancel_button.show();
+                    let generation = listing.generation;
+                    let attempt = Arc::new(AtomicU32::new(1));
+                    Self::animate_listing(
+                        status_frame.clone(),
+                        shared_state_refresh.clone(),
+                        generation,
+                        attempt.clone(),
+                        listing.attempts()
+                    );
                     
-                    if has_transfer_method {
-                        let method_name = transfer_method_name.unwrap_or_else(|| "Unknown".to_string());
-                        println!("Using transfer method: {}", method_name);
-                        
-                        // Lock the state to get the transfer method and list files
-                        let entries = {
-                            let state = shared_state_refresh.lock().unwrap();
-                            if let Some(ref method) = state.transfer_method {
-                                match method.list_files(&current_dir) {
-                                    Ok(entries) => Some(entries),
-                                    Err(e) => {
-                                        println!("Error listing remote directory: {}", e);
-                                        browser_clone.add(&format!("Error: {}", e));
-                                        None
-                                    }
-                                }
-                            } else {
-                                println!("No transfer method available");
-                                browser_clone.add("(No connection to remote server)");
-                                None
+                    // Entries stream in batches; the first page is shown as soon as
+                    // it arrives instead of waiting for the whole directory
+                    let table_chunk = table_clone.clone();
+                    let shared_state_chunk = shared_state_refresh.clone();
+                    let mut deliver = move |chunk: Vec<RemoteEntry>| {
+                        let mut table = table_chunk.clone();
+                        let shared_state = shared_state_chunk.clone();
+                        let mut pending = Some(chunk);
+                        app::awake_callback(move || {
+                            let Some(chunk) = pending.take() else { return };
+                            let mut state = shared_state.lock().unwrap();
+                            if state.add_listed(generation, chunk) {
+                                sync_table(&mut table, &mut state);
                             }
-                        };
-                        
-                        // Process entries outside the lock
-                        if let Some(entries) = entries {
-                            let mut entries_vec = Vec::new();
-                                
-                            for (name, is_dir) in entries {
-                                // Add entry to browser - prefix directories with a dot
-                                let display_name = if is_dir {
-                                    format!(".{}", name)
-                                } else {
-                                    name.clone()
+                        });
+                    };
+                    
+                    let mut table_done = table_clone.clone();
+                    let mut refresh_done = refresh.clone();
+                    let mut cancel_done = cancel_button.clone();
+                    let mut status_done = status_frame.clone();
+                    let shared_state_done = shared_state_refresh.clone();
+                    
+                    run_in_background(
+                        move || listing.run(&attempt, &mut deliver),
+                        move |result| {
+                            let (end, listed_dir) = {
+                                let mut state = shared_state_done.lock().unwrap();
+                                let Some(end) = state.finish_listing(generation, result) else {
+                                    return;
                                 };
-                                
-                                browser_clone.add(&display_name);
-                                
-                                // Store the entry in the entries vector
-                                entries_vec.push(FileEntry {
-                                    name: name.clone(),
-                                    path: current_dir.join(&name),
-                                    is_dir,
-                                    size: 0, // Size information isn't available from list_files
-                                });
-                            }
+                                sync_table(&mut table_done, &mut state);
+                                (end, state.current_dir().to_path_buf())
+                            };
                             
-                            // Get the length before moving entries_vec
-                            let entries_len = entries_vec.len();
+                            refresh_done.show();
+                            cancel_done.hide();
+                            status_done.set_label(end.incomplete.as_deref().unwrap_or(""));
                             
-                            // Update entries in shared state
-                            let mut state = shared_state_refresh.lock().unwrap();
-                            state.entries = entries_vec;
+                            if let Some(ref lost) = end.connection_lost {
+                                notify(ToastKind::Error, "Connection lost", lost);
+                            }
                             
-                            println!("Listed {} items in remote directory", entries_len);
-                        }
-                    } else {
-                        println!("No transfer method available for remote directory");
-                        browser_clone.add("(No connection to remote server)");
-                    }
-                } else {
-                    // Local directory refresh
-                    if let Ok(entries) = std::fs::read_dir(&current_dir) {
-                        let mut entries_vec = Vec::new();
-                        
-                        for entry in entries {
-                            if let Ok(entry) = entry {
-                                let path = entry.path();
-                                let is_dir = path.is_dir();
-                                let name = path.file_name()
-                                    .and_then(|n| n.to_str())
-                                    .unwrap_or("[invalid]");
-                                    
-                                // Add to browser
-                                browser_clone.add(&format!("{}{}", 
-                                    if is_dir { "." } else { "" },
-                                    name
-                                ));
-                                
-                                // Add to entries vector
-                                entries_vec.push(FileEntry {
-                                    name: name.to_string(),
-                                    path: path.clone(),
-                                    is_dir,
-                                    size: entry.metadata().map(|m| m.len()).unwrap_or(0),
-                                });
+                            // Browse on as root if the host allows it and the user agrees
+                            if let Some(sudo_filesystem) = end.sudo {
+                                let message = format!(
+                                    "The Pi refused to list {}. List it with sudo? Browsing stays with sudo until you reconnect.",
+                                    listed_dir.display()
+                                );
+                                if dialogs::choice_dialog("Permission Denied", &message, &["Cancel", "Use sudo"]) == 1 {
+                                    shared_state_done.lock().unwrap().set_filesystem(Some(sudo_filesystem));
+                                    refresh_done.do_callback();
+                                }
                             }
                         }
-                        
-                        // Get the length before moving entries_vec
-                        let entries_len = entries_vec.len();
-                        
-                        // Update entries in shared state
-                        let mut state = shared_state_refresh.lock().unwrap();
-                        state.entries = entries_vec;
-                        
-                        println!("Listed {} items in local directory: {}", 
-                            entries_len, current_dir.display());
-                    } else {
-                        println!("Error reading local directory: {}", current_dir.display());
-                    }
+                    );
                 }
                 
                 // Force the UI to update after making changes
@@ -289,85 +869,294 @@ pub mod file_browser {
                 app::redraw();
             });
             
-            // Browser selection callback
-            let mut browser = self.browser.clone();
-            let shared_state_browser = self.shared_state.clone();
+            // Stop the remote listing in progress
+            let shared_state_cancel = self.shared_state.clone();
+            let mut status_cancel = self.status_frame.clone();
+            self.cancel_button.set_callback(move |_| {
+                if shared_state_cancel.lock().unwrap().cancel_listing() {
+                    status_cancel.set_label(&tr("Cancelling..."));
+                }
+            });
+            
+            // Bookmark the current directory, or remove its bookmark
+            let shared_state_star = self.shared_state.clone();
+            let mut bookmarks_menu_star = self.bookmarks_menu.clone();
+            self.bookmark_button.set_callback(move |star| {
+                // Copy what's needed so no lock is held while an error dialog is up
+                let (config, host, current_dir) = {
+                    let state = shared_state_star.lock().unwrap();
+                    if state.is_remote() && state.hostname().is_none() {
+                        return;
+                    }
+                    match state.config() {
+                        Some(config) if !state.current_dir().as_os_str().is_empty() => (
+                            config.clone(),
+                            state.bookmark_host().map(|h| h.to_string()),
+                            state.current_dir().to_path_buf(),
+                        ),
+                        _ => return,
+                    }
+                };
+                let saved = {
+                    let mut config = config.lock().unwrap();
+                    // Remove by the stored path so "~/..." bookmarks are found too
+                    let path = config.bookmarks_for(host.as_deref())
+                        .into_iter()
+                        .find(|b| bookmark_dir(b) == current_dir)
+                        .map(|b| b.path)
+                        .unwrap_or_else(|| current_dir.to_string_lossy().to_string());
+                    
+                    let added = config.toggle_bookmark(&path, host.as_deref());
+                    log::info!("{} bookmark {}", if added { "Added" } else { "Removed" }, path);
+                    config.save()
+                };
+                
+                if let Err(e) = saved {
+                    dialogs::error_dialog("Failed to save bookmarks", &e);
+                }
+                
+                let state = shared_state_star.lock().unwrap();
+                update_bookmarks(star, &mut bookmarks_menu_star, &state);
+            });
+            
+            // Jump to the chosen bookmark
+            let shared_state_menu = self.shared_state.clone();
+            let mut path_input_menu = self.path_input.clone();
+            let mut refresh_menu = self.refresh_button.clone();
+            self.bookmarks_menu.set_callback(move |menu| {
+                let index = menu.value();
+                if index < 0 {
+                    return;
+                }
+                
+                let target = {
+                    let mut state = shared_state_menu.lock().unwrap();
+                    let target = match state.bookmarks().0.get(index as usize) {
+                        Some(bookmark) => bookmark_dir(bookmark),
+                        None => return,
+                    };
+                    
+                    state.navigate_to(target.clone());
+                    target
+                };
+                
+                log::info!("Opening bookmark {}", target.display());
+                path_input_menu.set_value(&target.to_string_lossy());
+                refresh_menu.do_callback();
+            });
+            
+            // Filter the list as the user types or toggles "images only"
+            let shared_state_filter = self.shared_state.clone();
+            let mut table_filter = self.table.clone();
+            self.filter_input.set_callback(move |input| {
+                let mut state = shared_state_filter.lock().unwrap();
+                state.set_filter_pattern(&input.value());
+                table_filter.set_row_position(0);
+                sync_table(&mut table_filter, &mut state);
+            });
+            
+            let shared_state_filter = self.shared_state.clone();
+            let mut table_filter = self.table.clone();
+            self.images_only.set_callback(move |check| {
+                let mut state = shared_state_filter.lock().unwrap();
+                state.set_images_only(check.is_checked());
+                table_filter.set_row_position(0);
+                sync_table(&mut table_filter, &mut state);
+            });
+            
+            // Header clicks change the sort order, clicks on rows open them,
+            // or only select them when opening takes a double click
+            let mut table = self.table.clone();
+            let shared_state_table = self.shared_state.clone();
             let callback_data_clone = callback_data.clone();
+            let selection_callback = self.selection_callback.clone();
             let mut path_input_clone = path_input_clone.clone();
             let mut refresh_button = refresh_button.clone();
             
-            browser.set_callback(move |b| {
-                let line = b.value();
-                if line == 0 {
+            table.set_callback(move |t| {
+                // The table also calls back on release and drag
+                if app::event() != Event::Push {
                     return;
                 }
                 
-                let text = b.text(line).unwrap_or_default();
+                // Right-click opens file management in the local pane and
+                // offers to open remote files
+                if app::event_mouse_button() == app::MouseButton::Right {
+                    let (remote, target) = {
+                        let state = shared_state_table.lock().unwrap();
+                        let target = match (t.callback_context(), state.entry_at(t.callback_row() as usize)) {
+                            (TableContext::Cell, Some(entry)) => Some((entry.path.clone(), entry.is_dir)),
+                            _ => None,
+                        };
+                        (state.is_remote(), target)
+                    };
+                    
+                    match target {
+                        Some((path, false)) if remote => remote_file_menu(&shared_state_table, path, &refresh_button),
+                        _ if remote => {},
+                        target => {
+                            if local_file_menu(&shared_state_table, target, &refresh_button) {
+                                refresh_button.do_callback();
+                            }
+                        }
+                    }
+                    return;
+                }
                 
-                // Lock state and make copies of what we need
-                let is_remote;
-                let current_dir;
+                match t.callback_context() {
+                    TableContext::ColHeader => {
+                        let mut state = shared_state_table.lock().unwrap();
+                        state.sort_by(sort_column_at(t.callback_col()));
+                        sync_table(t, &mut state);
+                    },
+                    TableContext::Cell => {
+                        let row = t.callback_row() as usize;
+                        let select_only = {
+                            let state = shared_state_table.lock().unwrap();
+                            state.interaction().double_click_to_open
+                                && !app::event_clicks()
+                                && !matches!(state.row_at(row), Some(Row::LoadMore))
+                        };
+                        if select_only {
+                            if let Some(ref mut callback) = *selection_callback.lock().unwrap() {
+                                callback();
+                            }
+                            return;
+                        }
+                        open_row(
+                            t,
+                            &shared_state_table,
+                            &mut path_input_clone,
+                            &mut refresh_button,
+                            &callback_data_clone,
+                            row
+                        );
+                    },
+                    _ => (),
+                }
+            });
+            
+            // Keyboard use: the owner's shortcuts first, then arrows move the
+            // selection, Enter opens it and Backspace goes up a directory
+            let shared_state_keys = self.shared_state.clone();
+            let key_handler = self.key_handler.clone();
+            let mut path_input_keys = self.path_input.clone();
+            let mut refresh_keys = self.refresh_button.clone();
+            let callback_keys = self.callback.clone();
+            let mut hovered_row = None;
+            table.handle(move |t, event| {
+                match event {
+                    Event::Move => {
+                        let row = match t.cursor2rowcol() {
+                            Some((TableContext::Cell, row, _, _)) => Some(row),
+                            _ => None,
+                        };
+                        if row != hovered_row {
+                            hovered_row = row;
+                            hover_row(&shared_state_keys, row);
+                        }
+                        return false;
+                    },
+                    Event::Leave | Event::Push | Event::MouseWheel | Event::Hide => {
+                        hovered_row = None;
+                        hover_preview::hide();
+                        return false;
+                    },
+                    Event::KeyDown => {},
+                    _ => return false,
+                }
                 
-                {
-                    let state = shared_state_browser.lock().unwrap();
-                    is_remote = state.is_remote;
-                    current_dir = state.current_dir.clone();
+                if let Some(ref mut handler) = *key_handler.lock().unwrap() {
+                    if handler() {
+                        return true;
+                    }
                 }
                 
-                println!("Browser callback with is_remote = {}", is_remote);
+                let rows = t.rows();
+                if rows == 0 {
+                    return false;
+                }
+                let current = (0..rows).find(|&row| t.row_selected(row));
+                let page = ((t.h() - t.col_header_height()) / t.row_height(0).max(1) - 1).max(1);
                 
-                if text == ".." {
-                    // Go to parent directory
-                    if let Some(parent) = current_dir.parent() {
-                        // Update shared state
-                        {
-                            let mut state = shared_state_browser.lock().unwrap();
-                            state.current_dir = parent.to_path_buf();
+                let target = match app::event_key() {
+                    Key::Up => current.map_or(0, |row| row - 1),
+                    Key::Down => current.map_or(0, |row| row + 1),
+                    Key::PageUp => current.map_or(0, |row| row - page),
+                    Key::PageDown => current.map_or(0, |row| row + page),
+                    Key::Home => 0,
+                    Key::End => rows - 1,
+                    Key::Enter | Key::KPEnter => {
+                        if let Some(row) = current {
+                            open_row(t, &shared_state_keys, &mut path_input_keys, &mut refresh_keys, &callback_keys, row as usize);
                         }
-                        
-                        // Update path input
-                        path_input_clone.set_value(&parent.to_string_lossy());
-                        
-                        println!("Navigating to parent directory: {}", parent.display());
-                        refresh_button.do_callback(); // Use the refresh to load the directory
-                    }
-                } else {
-                    // Check if it's a directory (prefixed with ".")
-                    let is_dir = text.starts_with(".");
-                    let name = if is_dir { &text[1..] } else { &text };
-                    
-                    if is_dir {
-                        // Navigate to the directory
-                        let new_dir = current_dir.join(name);
-                        
-                        // Update shared state
-                        {
-                            let mut state = shared_state_browser.lock().unwrap();
-                            state.current_dir = new_dir.clone();
-                        }
-                        
-                        // Update path input and refresh
-                        path_input_clone.set_value(&new_dir.to_string_lossy());
-                        println!("Navigating to directory: {}", new_dir.display());
-                        refresh_button.do_callback(); // Use the refresh to load the directory
-                    } else {
-                        // File selected - call the callback if set
-                        let file_path = current_dir.join(name);
-                        
-                        if let Ok(mut callback_guard) = callback_data_clone.lock() {
-                            if let Some(ref mut callback) = *callback_guard {
-                                callback(file_path, false);
-                            }
+                        return true;
+                    },
+                    Key::BackSpace => {
+                        let has_parent = {
+                            let state = shared_state_keys.lock().unwrap();
+                            matches!(state.row_at(0), Some(Row::Parent))
+                        };
+                        if has_parent {
+                            open_row(t, &shared_state_keys, &mut path_input_keys, &mut refresh_keys, &callback_keys, 0);
                         }
+                        return true;
+                    },
+                    _ => return false,
+                };
+                
+                move_selection(t, target.clamp(0, rows - 1));
+                t.redraw();
+                true
+            });
+        }
+        
+        // Spin the status label while listing `generation` is running
+        fn animate_listing(
+            mut status_frame: Frame,
+            shared_state: Arc<Mutex<BrowserModel>>,
+            generation: u64,
+            attempt: Arc<AtomicU32>,
+            attempts: u32
+        ) {
+            let mut frame = 0;
+            app::add_timeout3(0.15, move |handle| {
+                {
+                    let state = shared_state.lock().unwrap();
+                    if !state.is_listing(generation) {
+                        return;
+                    }
+                    if state.listing_cancelled() {
+                        // The cancel button already changed the label
+                        app::repeat_timeout3(0.15, handle);
+                        return;
                     }
                 }
+                
+                let current = attempt.load(Ordering::Relaxed);
+                let label = if current > 1 {
+                    format!("{} Loading (attempt {}/{})", SPINNER[frame % SPINNER.len()], current, attempts)
+                } else {
+                    format!("{} Loading", SPINNER[frame % SPINNER.len()])
+                };
+                status_frame.set_label(&label);
+                frame += 1;
+                
+                app::repeat_timeout3(0.15, handle);
             });
-            
-            // Store callback reference
-            self.callback = {
-                let mut callback_guard = callback_data.lock().unwrap();
-                std::mem::take(&mut *callback_guard)
-            };
+        }
+        
+        // Settings the bookmark star and menu read and save
+        pub fn set_config(&mut self, config: Arc<Mutex<Config>>) {
+            let mut state = self.shared_state.lock().unwrap();
+            state.set_config(config);
+            update_bookmarks(&mut self.bookmark_button, &mut self.bookmarks_menu, &state);
+        }
+        
+        // Timeout and cache lifetime used for remote listings; retries follow
+        // the connected host's retry policy
+        pub fn set_listing_options(&mut self, timeout: Duration, cache_ttl: Duration) {
+            self.shared_state.lock().unwrap().set_listing_options(timeout, cache_ttl);
         }
         
         // Show debug info in a non-modal way
@@ -378,20 +1167,20 @@ pub mod file_browser {
             {
                 let state = self.shared_state.lock().unwrap();
                 status_text = format!(
-                    "Remote mode: {}\nHas transfer: {}\nCurrent dir: {}\nTransfer method: {}",
-                    state.is_remote,
-                    state.transfer_method.is_some(),
-                    state.current_dir.display(),
-                    state.transfer_method.as_ref()
-                        .map(|m| m.get_name())
-                        .unwrap_or("NONE")
+                    "Remote mode: {}\nHas transfer: {}\nCurrent dir: {}\nFile system: {}",
+                    state.is_remote(),
+                    state.transfer_method().is_some(),
+                    state.current_dir().display(),
+                    state.filesystem()
+                        .map(|filesystem| filesystem.name())
+                        .unwrap_or_else(|| "NONE".to_string())
                 );
             }
             
             // Log the info
-            println!("\n***** FILE BROWSER DEBUG INFO *****");
-            println!("{}", status_text);
-            println!("*****************************\n");
+            log::info!("\n***** FILE BROWSER DEBUG INFO *****");
+            log::info!("{}", status_text);
+            log::debug!("*****************************\n");
             
             // Show a message box (non-modal)
             dialog::message_title("Browser Status");
@@ -404,7 +1193,7 @@ pub mod file_browser {
         
         // Method for navigating remote directories
         pub fn set_current_remote_directory(&mut self, dir: &PathBuf) {
-            println!("Changing remote directory to: {}", dir.display());
+            log::info!("Changing remote directory to: {}", dir.display());
             
             // Check if remote mode is set and transfer method exists
             let has_transfer_method;
@@ -412,22 +1201,23 @@ pub mod file_browser {
             {
                 let mut state = self.shared_state.lock().unwrap();
                 
-                if !state.is_remote {
-                    println!("WARNING: set_current_remote_directory called while not in remote mode!");
+                if !state.is_remote() {
+                    log::warn!("set_current_remote_directory called while not in remote mode!");
                     // Force remote mode
-                    state.is_remote = true;
+                    state.set_filesystem(None);
                 }
                 
-                has_transfer_method = state.transfer_method.is_some();
+                has_transfer_method = state.filesystem().is_some();
                 
                 // Set new directory
-                state.current_dir = dir.clone();
+                state.navigate_to(dir.clone());
             }
             
             if !has_transfer_method {
-                println!("ERROR: No transfer method available for remote directory change!");
-                self.browser.clear();
-                self.browser.add("ERROR: No remote connection available");
+                log::error!("No transfer method available for remote directory change!");
+                let mut state = self.shared_state.lock().unwrap();
+                state.reset(Some("ERROR: No remote connection available"));
+                sync_table(&mut self.table, &mut state);
                 return;
             }
             
@@ -441,36 +1231,36 @@ pub mod file_browser {
         pub fn print_debug_status(&self) {
             let state = self.shared_state.lock().unwrap();
             
-            println!("\n***** FILE BROWSER DEBUG INFO *****");
-            println!("is_remote: {}", state.is_remote);
-            println!("has_transfer_method: {}", state.transfer_method.is_some());
-            println!("current_dir: {}", state.current_dir.display());
+            log::info!("\n***** FILE BROWSER DEBUG INFO *****");
+            log::info!("is_remote: {}", state.is_remote());
+            log::info!("has_transfer_method: {}", state.transfer_method().is_some());
+            log::info!("current_dir: {}", state.current_dir().display());
             
-            if let Some(ref method) = state.transfer_method {
-                println!("transfer_method: {}", method.get_name());
+            if let Some(filesystem) = state.filesystem() {
+                log::info!("filesystem: {}", filesystem.name());
             } else {
-                println!("transfer_method: NONE");
+                log::info!("filesystem: NONE");
             }
-            println!("*****************************\n");
+            log::debug!("*****************************\n");
         }
         
         // Accessor for remote status
         pub fn is_remote(&self) -> bool {
-            self.shared_state.lock().unwrap().is_remote
+            self.shared_state.lock().unwrap().is_remote()
         }
         
         // Check for transfer method
         pub fn has_transfer_method(&self) -> bool {
-            self.shared_state.lock().unwrap().transfer_method.is_some()
+            self.shared_state.lock().unwrap().transfer_method().is_some()
         }
         
         // Method to store password
         pub fn store_password(&mut self, password: &str) {
             let mut state = self.shared_state.lock().unwrap();
             
-            if let Some(ref mut method) = state.transfer_method {
-                method.set_password(password);
-                println!("Stored password for SSH connection");
+            if let Some(filesystem) = state.filesystem_mut() {
+                filesystem.set_password(password);
+                log::info!("Stored password for SSH connection");
             }
         }
         
@@ -478,9 +1268,8 @@ pub mod file_browser {
         pub fn set_directory(&mut self, dir: &PathBuf) {
             {
                 let mut state = self.shared_state.lock().unwrap();
-                state.current_dir = dir.clone();
-                state.is_remote = false;
-                state.transfer_method = None;
+                state.set_current_dir(dir.clone());
+                state.set_filesystem(Some(Box::new(LocalFs)));
             }
             
             self.path_input.set_value(&dir.to_string_lossy());
@@ -489,32 +1278,65 @@ pub mod file_browser {
         
         // Set directory for remote browsing
         pub fn set_remote_directory(&mut self, dir: &PathBuf, transfer_method: Box<dyn TransferMethod>) {
-            println!("\n***** SETTING REMOTE DIRECTORY *****");
-            println!("Path: {}", dir.display());
-            println!("Transfer method: {}", transfer_method.get_name());
+            log::info!("\n***** SETTING REMOTE DIRECTORY *****");
+            log::info!("Path: {}", dir.display());
+            log::info!("Transfer method: {}", transfer_method.get_name());
             
             // Update shared state
             {
                 let mut state = self.shared_state.lock().unwrap();
-                state.current_dir = dir.clone();
-                state.is_remote = true;
-                state.transfer_method = Some(transfer_method);
+                state.navigate_to(dir.clone());
+                state.set_filesystem(Some(Box::new(RemoteFs::new(transfer_method))));
+                state.set_hostname(self.current_hostname.clone());
             }
             
             self.path_input.set_value(&dir.to_string_lossy());
+            self.table.activate();
             
-            println!("***** REFRESHING REMOTE DIRECTORY *****\n");
+            log::debug!("***** REFRESHING REMOTE DIRECTORY *****\n");
             self.refresh();
         }
         
+        // Drop the connection: stop any listing, forget the transfer method
+        // and credentials and empty the list
+        pub fn disconnect(&mut self) {
+            {
+                let mut state = self.shared_state.lock().unwrap();
+                state.disconnect();
+                sync_table(&mut self.table, &mut state);
+                update_bookmarks(&mut self.bookmark_button, &mut self.bookmarks_menu, &state);
+            }
+            
+            self.current_hostname = None;
+            self.current_username = None;
+            self.current_password = None;
+            self.path_input.set_value("");
+            self.cancel_button.hide();
+            self.refresh_button.show();
+            self.status_frame.set_label("");
+            self.table.activate();
+        }
+        
+        // Grey out the list while the Pi is unreachable; when it is back,
+        // list the directory again
+        pub fn set_reachable(&mut self, reachable: bool) {
+            if reachable {
+                self.table.activate();
+                self.status_frame.set_label("");
+                self.refresh();
+            } else {
+                self.table.deactivate();
+                self.status_frame.set_label(&tr("Unreachable"));
+            }
+            self.table.redraw();
+        }
+        
         // Clear the browser
         pub fn clear(&mut self) {
-            self.browser.clear();
-            
             {
                 let mut state = self.shared_state.lock().unwrap();
-                state.current_dir = PathBuf::new();
-                state.entries.clear();
+                state.clear();
+                sync_table(&mut self.table, &mut state);
             }
             
             self.path_input.set_value("");
@@ -525,7 +1347,7 @@ pub mod file_browser {
             // Get the shared state for logging
             {
                 let state = self.shared_state.lock().unwrap();
-                println!("In refresh() - is_remote = {}", state.is_remote);
+                log::info!("In refresh() - is_remote = {}", state.is_remote());
             }
             
             // Use refresh button to trigger the actual refresh
@@ -534,24 +1356,24 @@ pub mod file_browser {
         
         // Force remote mode
         pub fn force_remote_mode(&mut self) {
-            println!("\n***** FORCING REMOTE MODE *****");
+            log::info!("\n***** FORCING REMOTE MODE *****");
             
             let needs_transfer;
             
             {
                 let mut state = self.shared_state.lock().unwrap();
-                needs_transfer = state.transfer_method.is_none() && 
+                if !state.is_remote() {
+                    state.set_filesystem(None);
+                    log::info!("Switched the browser to remote mode");
+                }
+                needs_transfer = state.filesystem().is_none() && 
                                 self.current_hostname.is_some() && 
                                 self.current_username.is_some();
-                
-                // Set remote flag
-                state.is_remote = true;
-                println!("Set shared state remote = true");
             }
             
             // Check if we need to recreate the transfer method
             if needs_transfer {
-                println!("Attempting to recreate SSH connection with stored credentials");
+                log::info!("Attempting to recreate SSH connection with stored credentials");
                 
                 let hostname = self.current_hostname.clone().unwrap_or("raspberrypi.local".to_string());
                 let username = self.current_username.clone().unwrap_or("pi".to_string());
@@ -574,14 +1396,15 @@ pub mod file_browser {
                 // Apply password if we have one
                 if let Some(ref password) = self.current_password {
                     transfer_method.set_password(password);
-                    println!("Applied stored password to new connection");
+                    log::info!("Applied stored password to new connection");
                 }
                 
                 // Update shared state with the new transfer method
                 {
                     let mut state = self.shared_state.lock().unwrap();
-                    state.transfer_method = Some(transfer_method);
-                    println!("Created new transfer method");
+                    state.set_filesystem(Some(Box::new(RemoteFs::new(transfer_method))));
+                    state.set_hostname(Some(hostname.clone()));
+                    log::info!("Created new transfer method");
                 }
             }
             
@@ -597,46 +1420,232 @@ pub mod file_browser {
         where
             F: FnMut(PathBuf, bool) + 'static + Send + Sync,
         {
-            self.callback = Some(Box::new(callback));
+            *self.callback.lock().unwrap() = Some(Box::new(callback));
         }
         
-        // NEW METHOD: Download a file from remote to a local path
-        pub fn download_remote_file(&self, remote_path: &Path, local_path: &Path) -> Result<(), String> {
-            let state = self.shared_state.lock().unwrap();
+        // Run `callback` when a click only selects, which happens when
+        // opening takes a double click
+        pub fn set_selection_callback<F>(&mut self, callback: F)
+        where
+            F: FnMut() + 'static + Send + Sync,
+        {
+            *self.selection_callback.lock().unwrap() = Some(Box::new(callback));
+        }
+        
+        // Run `handler` for key presses in the list; returning true consumes
+        // the key before the arrow, Enter and Backspace navigation sees it
+        pub fn set_key_handler<F>(&mut self, handler: F)
+        where
+            F: FnMut() -> bool + 'static + Send + Sync,
+        {
+            *self.key_handler.lock().unwrap() = Some(Box::new(handler));
+        }
+        
+        // Give the list the keyboard focus, selecting its first line if
+        // nothing is selected yet
+        pub fn focus_list(&mut self) {
+            if self.table.rows() > 0 && !(0..self.table.rows()).any(|row| self.table.row_selected(row)) {
+                move_selection(&mut self.table, 0);
+            }
+            let _ = self.table.take_focus();
+            self.table.redraw();
+        }
+        
+        // Ask for a new name for the selected entry and rename it
+        pub fn rename_selected(&mut self) {
+            let (path, _) = match self.get_selected_entry() {
+                Some(entry) => entry,
+                None => return,
+            };
+            
+            let old_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let name = match dialogs::input_dialog("Rename", "New name:", &old_name) {
+                Some(name) if name.trim() != old_name => name,
+                _ => return,
+            };
+            
+            self.run_change("Rename failed", move |filesystem| {
+                let renamed = filesystem.rename(&path, &name)?;
+                log::info!("Renamed {} to {}", path.display(), renamed.display());
+                Ok(())
+            });
+        }
+        
+        // Move the selected entry to the trash, the Pi's own for remote
+        // entries unless turned off, after asking
+        pub fn delete_selected(&mut self) {
+            let (path, is_dir) = match self.get_selected_entry() {
+                Some(entry) => entry,
+                None => return,
+            };
+            
+            let what = if is_dir { "folder" } else { "file" };
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
             
-            if !state.is_remote {
-                return Err("Not in remote mode".to_string());
+            let (remote, to_trash) = {
+                let state = self.shared_state.lock().unwrap();
+                let remote = state.is_remote();
+                let remote_trash = state.config()
+                    .map_or(true, |config| config.lock().unwrap().remote_trash);
+                (remote, !remote || remote_trash)
+            };
+            let question = match (to_trash, remote) {
+                (true, false) => format!("Move the {} \"{}\" to the trash?", what, name),
+                (true, true) => format!("Move the {} \"{}\" to the Pi's trash?", what, name),
+                (false, _) => format!("Permanently delete the {} \"{}\" on the Pi?", what, name),
+            };
+            
+            if !to_trash {
+                if dialogs::choice_dialog("Delete", &question, &["Delete", "Cancel"]) != 0 {
+                    return;
+                }
+                self.run_change("Delete failed", move |filesystem| filesystem.delete(&path));
+                return;
             }
             
-            if let Some(ref method) = state.transfer_method {
-                match method.download_file(remote_path, local_path) {
-                    Ok(_) => {
-                        println!("Downloaded: {} -> {}", remote_path.display(), local_path.display());
-                        Ok(())
-                    },
-                    Err(e) => Err(format!("Download failed: {}", e))
+            if dialogs::choice_dialog("Move to Trash", &question, &["Move to Trash", "Cancel"]) != 0 {
+                return;
+            }
+            let shared_state = self.shared_state.clone();
+            self.run_change("Move to Trash failed", move |filesystem| {
+                let trashed = vec![path];
+                filesystem.move_to_trash(&trashed)?;
+                log::info!("Moved {} to the trash", trashed[0].display());
+                // Kept for Undo Move to Trash
+                if filesystem.can_restore_from_trash() {
+                    shared_state.lock().unwrap().set_last_trashed(trashed);
                 }
-            } else {
-                Err("No transfer method available".to_string())
+                Ok(())
+            });
+        }
+        
+        // Change files of the current directory in the background, then
+        // list the directory again
+        fn run_change(
+            &mut self,
+            failure: &'static str,
+            change: impl FnOnce(&dyn FileSystem) -> AppResult<()> + Send + 'static
+        ) {
+            let (filesystem, current_dir) = {
+                let state = self.shared_state.lock().unwrap();
+                (state.filesystem().map(|filesystem| filesystem.clone_box()), state.current_dir().to_path_buf())
+            };
+            let filesystem = match filesystem {
+                Some(filesystem) => filesystem,
+                None => {
+                    dialogs::error_dialog(failure, &AppError::NotConnected);
+                    return;
+                }
+            };
+            
+            let cache_key = filesystem.cache_key();
+            let mut browser = self.clone();
+            run_in_background(
+                move || change(filesystem.as_ref()),
+                move |result| {
+                    if let Some(key) = cache_key {
+                        listing_cache::invalidate(&key, &current_dir);
+                    }
+                    if let Err(e) = result {
+                        dialogs::error_dialog(failure, &e);
+                    }
+                    browser.refresh();
+                }
+            );
+        }
+        
+        // NEW METHOD: Download a file from remote to a local path
+        pub fn download_remote_file(&self, remote_path: &Path, local_path: &Path) -> AppResult<()> {
+            let state = self.shared_state.lock().unwrap();
+            
+            match state.filesystem() {
+                Some(filesystem) if filesystem.is_remote() => {
+                    filesystem.download(remote_path, local_path)?;
+                    log::info!("Downloaded: {} -> {}", remote_path.display(), local_path.display());
+                    Ok(())
+                },
+                _ => Err(AppError::NotConnected),
             }
         }
         
         // Helper to check if a file is an image based on extension
         pub fn is_image_file(path: &Path) -> bool {
-            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                matches!(
-                    ext.to_lowercase().as_str(),
-                    "jpg" | "jpeg" | "png" | "gif" | "bmp" | "tiff" | "tif" | "webp"
-                )
-            } else {
-                false
-            }
+            is_image_file(path)
         }
         
         // Get the current directory
         pub fn get_current_directory(&self) -> PathBuf {
+            self.shared_state.lock().unwrap().current_dir().to_path_buf()
+        }
+        
+        // Get the selected entry as (path, is_dir), ignoring the ".." entry
+        pub fn get_selected_entry(&self) -> Option<(PathBuf, bool)> {
+            let row = self.selected_rows().into_iter().next()?;
+            let state = self.shared_state.lock().unwrap();
+            state.entry_at(row).map(|entry| (entry.path.clone(), entry.is_dir))
+        }
+        
+        // Every selected entry as (path, is_dir), ignoring the ".." entry
+        pub fn get_selected_entries(&self) -> Vec<(PathBuf, bool)> {
+            let rows = self.selected_rows();
+            let state = self.shared_state.lock().unwrap();
+            state.selected_entries(rows)
+                .into_iter()
+                .map(|entry| (entry.path.clone(), entry.is_dir))
+                .collect()
+        }
+        
+        // Lines selected in the list, top to bottom
+        fn selected_rows(&self) -> Vec<usize> {
+            let mut table = self.table.clone();
+            (0..table.rows())
+                .filter(|&row| table.row_selected(row))
+                .map(|row| row as usize)
+                .collect()
+        }
+        
+        // Position and size of the pane as (x, y, w, h)
+        pub fn bounds(&self) -> (i32, i32, i32, i32) {
+            (self.group.x(), self.group.y(), self.group.w(), self.group.h())
+        }
+        
+        // Whether the keyboard focus is inside this pane
+        pub fn contains_focus(&self) -> bool {
+            app::focus().map_or(false, |focus| focus.inside(&self.group))
+        }
+        
+        // Number of selected entries and the total size of the selected files
+        pub fn selection_summary(&self) -> (usize, u64) {
+            let rows = self.selected_rows();
+            let state = self.shared_state.lock().unwrap();
+            state.selected_entries(rows)
+                .into_iter()
+                .map(|entry| if entry.is_dir { 0 } else { entry.size })
+                .fold((0, 0), |(count, bytes), size| (count + 1, bytes + size))
+        }
+        
+        // Modification time of a listed entry as seconds since the Unix epoch
+        pub fn entry_modified(&self, path: &Path) -> Option<i64> {
+            let state = self.shared_state.lock().unwrap();
+            state.find_entry(path).and_then(|entry| entry.modified.map(|m| m.timestamp()))
+        }
+        
+        // Size of a listed entry in bytes, if it is in the current listing
+        pub fn entry_size(&self, path: &Path) -> Option<u64> {
+            let state = self.shared_state.lock().unwrap();
+            state.find_entry(path).map(|entry| entry.size)
+        }
+        
+        // Get an independent copy of the active transfer method, if connected
+        pub fn get_transfer_method(&self) -> Option<Box<dyn TransferMethod>> {
+            let state = self.shared_state.lock().unwrap();
+            state.transfer_method().map(|method| method.clone_box())
+        }
+        
+        // Run a closure against the active transfer method, if connected
+        pub fn with_transfer_method<R>(&self, f: impl FnOnce(&dyn TransferMethod) -> R) -> Option<R> {
             let state = self.shared_state.lock().unwrap();
-            state.current_dir.clone()
+            state.transfer_method().map(f)
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+use fltk::app;
+
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::transfer::method::{wait_with_deadline, CommandOutput, TransferError, SSH_FAILURE_EXIT};
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::executor::run_in_background;
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+
+/// How long a keepalive may take before the Pi counts as unreachable
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct HealthState {
+    /// A keepalive is running; ticks are skipped until it finishes
+    checking: bool,
+    /// Result of the last keepalive of the current connection
+    reachable: bool,
+}
+
+/// Periodic keepalive on the connection shown in the remote pane
+///
+/// A change in reachability is announced with `AppEvent::ConnectionHealth`;
+/// a new connection starts out reachable.
+#[derive(Clone)]
+pub struct HealthMonitor {
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+    events: EventBus,
+    state: Arc<Mutex<HealthState>>,
+}
+
+impl HealthMonitor {
+    /// Check every `interval`; a zero interval only checks on request
+    pub fn start(remote_browser: Arc<Mutex<FileBrowserPanel>>, events: EventBus, interval: Duration) -> Self {
+        let monitor = HealthMonitor {
+            remote_browser,
+            events: events.clone(),
+            state: Arc::new(Mutex::new(HealthState { checking: false, reachable: true })),
+        };
+
+        let state = monitor.state.clone();
+        events.subscribe(move |event| {
+            if let AppEvent::ConnectionChanged { .. } = event {
+                state.lock().unwrap().reachable = true;
+            }
+        });
+
+        if !interval.is_zero() {
+            let ticker = monitor.clone();
+            let seconds = interval.as_secs_f64();
+            app::add_timeout3(seconds, move |handle| {
+                ticker.check_now();
+                app::repeat_timeout3(seconds, handle);
+            });
+        }
+
+        monitor
+    }
+
+    /// Run a keepalive on the active connection now, unless one is running
+    pub fn check_now(&self) {
+        let (method, hostname) = match self.remote_browser.lock() {
+            Ok(browser) => (browser.get_transfer_method(), browser.current_hostname.clone()),
+            Err(_) => return,
+        };
+        let (method, hostname) = match (method, hostname) {
+            (Some(method), Some(hostname)) => (method, hostname),
+            _ => return,
+        };
+
+        if std::mem::replace(&mut self.state.lock().unwrap().checking, true) {
+            return;
+        }
+
+        let connection = method.connection_key();
+        let monitor = self.clone();
+        run_in_background(
+            move || -> Result<CommandOutput, TransferError> {
+                let child = method.spawn_remote("true")?;
+                wait_with_deadline(child, CHECK_TIMEOUT, &AtomicBool::new(false))
+            },
+            move |result| {
+                let reachable = match result {
+                    Ok(output) => output.exit_code != Some(SSH_FAILURE_EXIT),
+                    Err(e) => {
+                        log::warn!("Keepalive to {} failed: {}", hostname, e);
+                        false
+                    },
+                };
+
+                // Drop results for a connection the pane no longer shows
+                let current = monitor.remote_browser.lock().ok()
+                    .and_then(|browser| browser.get_transfer_method())
+                    .map(|method| method.connection_key());
+
+                let changed = {
+                    let mut state = monitor.state.lock().unwrap();
+                    state.checking = false;
+                    if current.as_deref() != Some(connection.as_str()) {
+                        return;
+                    }
+                    std::mem::replace(&mut state.reachable, reachable) != reachable
+                };
+
+                if changed {
+                    log::info!("{} is {}", hostname, if reachable { "reachable again" } else { "unreachable" });
+                    monitor.events.publish(AppEvent::ConnectionHealth { hostname, reachable });
+                }
+            }
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use fltk::{
+    app,
+    enums::{ColorDepth, FrameType},
+    frame::Frame,
+    group::Group,
+    image::RgbImage,
+    prelude::*,
+    window::Window,
+};
+
+use image::io::Reader;
+use image::DynamicImage;
+
+use crate::transfer::download_cache;
+use crate::transfer::method::TransferMethod;
+use crate::transfer::thumbnail_cache::THUMBNAIL_SIZE;
+use crate::ui::executor::run_in_background;
+
+/// Images larger than this are not previewed on hover; remote ones would
+/// have to be downloaded first
+pub const MAX_HOVER_PREVIEW_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Distance between the mouse and the thumbnail's corner
+const CURSOR_OFFSET: i32 = 16;
+
+/// Bumped whenever the mouse moves to another row or leaves the list, so
+/// thumbnails finishing late are dropped
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    // Borderless window holding the thumbnail, made on first use
+    static POPUP: RefCell<Option<(Window, Frame)>> = const { RefCell::new(None) };
+}
+
+/// Show a thumbnail of the image at `path` next to the mouse once it has
+/// rested for `delay`, replacing any thumbnail shown or pending
+///
+/// With a `remote` method and modification time the image is on the Pi
+/// and is fetched through the download cache first. Call from a file
+/// list's event handler, as the mouse position is taken from the current
+/// event.
+pub fn schedule(path: PathBuf, remote: Option<(Box<dyn TransferMethod>, Option<i64>)>, delay: Duration) {
+    let generation = GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    hide_popup();
+    let (x, y) = (app::event_x_root(), app::event_y_root());
+
+    app::add_timeout3(delay.as_secs_f64(), move |_| {
+        if GENERATION.load(Ordering::Relaxed) != generation {
+            return;
+        }
+        let path = path.clone();
+        let remote = remote.as_ref().map(|(method, modified)| (method.clone_box(), *modified));
+        run_in_background(
+            move || thumbnail(&path, remote),
+            move |result| {
+                if GENERATION.load(Ordering::Relaxed) != generation {
+                    return;
+                }
+                match result {
+                    Ok((data, w, h)) => show_popup(&data, w, h, x, y),
+                    Err(e) => log::debug!("No hover preview: {}", e),
+                }
+            }
+        );
+    });
+}
+
+/// Take down the thumbnail and forget any pending one
+pub fn hide() {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+    hide_popup();
+}
+
+fn hide_popup() {
+    POPUP.with(|popup| {
+        if let Some((window, _)) = popup.borrow_mut().as_mut() {
+            window.hide();
+        }
+    });
+}
+
+/// RGB pixels, width and height of the image's thumbnail
+fn thumbnail(path: &Path, remote: Option<(Box<dyn TransferMethod>, Option<i64>)>) -> Result<(Vec<u8>, i32, i32), String> {
+    let local = match remote {
+        Some((method, modified)) => download_cache::fetch(method.as_ref(), path, modified).map_err(|e| e.to_string())?,
+        None => path.to_path_buf(),
+    };
+    let image = open_image(&local).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).to_rgb8();
+    let (w, h) = (thumbnail.width() as i32, thumbnail.height() as i32);
+    Ok((thumbnail.into_raw(), w, h))
+}
+
+/// Open an image by its content rather than its extension, as cached
+/// thumbnails have none
+fn open_image(path: &Path) -> Result<DynamicImage, String> {
+    Reader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())
+}
+
+fn show_popup(data: &[u8], w: i32, h: i32, x: i32, y: i32) {
+    let image = match RgbImage::new(data, w, h, ColorDepth::Rgb8) {
+        Ok(image) => image,
+        Err(e) => {
+            log::debug!("No hover preview: {}", e);
+            return;
+        },
+    };
+
+    POPUP.with(|popup| {
+        let mut popup = popup.borrow_mut();
+        let (window, frame) = popup.get_or_insert_with(|| {
+            // A window made while a group is open would end up inside it
+            Group::set_current(None::<&Group>);
+            let mut window = Window::new(0, 0, w, h, None);
+            let mut frame = Frame::new(0, 0, w, h, None);
+            frame.set_frame(FrameType::BorderBox);
+            window.end();
+            window.set_border(false);
+            window.set_override();
+            (window, frame)
+        });
+
+        // Keep the thumbnail on the screen the mouse is on
+        let (screen_x, screen_y, screen_w, screen_h) = app::screen_xywh(app::screen_num(x, y));
+        let left = (x + CURSOR_OFFSET).min(screen_x + screen_w - w - 2).max(screen_x);
+        let top = (y + CURSOR_OFFSET).min(screen_y + screen_h - h - 2).max(screen_y);
+
+        window.resize(left, top, w + 2, h + 2);
+        frame.resize(0, 0, w + 2, h + 2);
+        frame.set_image(Some(image));
+        window.show();
+        window.redraw();
+    });
+}
@@ -20,6 +20,7 @@ use crate::core::image::{
     JPEGProcessorFactory,
     PNGProcessorFactory,
 };
+use crate::core::locale::trf;
 
 use crate::config::Config;
 use crate::transfer::ssh::SSHTransferFactory;
@@ -63,9 +64,10 @@ impl HybridMainWindow {
         // Create image processing service
         let mut image_service = ImageProcessingService::new();
         
-        // Register image processor factories
-        image_service.register_factory(Box::new(JPEGProcessorFactory::new(85)));
-        image_service.register_factory(Box::new(PNGProcessorFactory::new(6)));
+        // Register image processor factories with the settings last chosen
+        let processors = config.lock().unwrap().processors.clone();
+        image_service.register_factory(Box::new(JPEGProcessorFactory::new(processors.jpeg_quality)));
+        image_service.register_factory(Box::new(PNGProcessorFactory::new(processors.png_compression)));
         
         let image_service = Arc::new(Mutex::new(image_service));
         
@@ -159,7 +161,9 @@ impl HybridMainWindow {
             content_y + 35,
             operations_width,
             content_height - 35,
-            image_service.clone()
+            image_service.clone(),
+            image_view.clone(),
+            config.clone()
         );
         
         image_tab.end();
@@ -274,7 +278,7 @@ impl HybridMainWindow {
                     if !success {
                         dialogs::message_dialog(
                             "Error", 
-                            &format!("Failed to open file: {}", path.display())
+                            &trf("Failed to open file: {}", &[&path.display()])
                         );
                     }
                 }
@@ -535,7 +539,7 @@ impl HybridMainWindow {
                                         println!("Failed to download file for preview: {}", e);
                                         dialogs::message_dialog(
                                             "Download Error",
-                                            &format!("Failed to download remote file: {}", e)
+                                            &trf("Failed to download remote file: {}", &[&e])
                                         );
                                     }
                                 }
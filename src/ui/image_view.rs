@@ -1,19 +1,43 @@
 // ui/image_view.rs - Image view panel
 pub mod image_view {
     use fltk::{
-        enums::{Color, FrameType},
+        enums::{ColorDepth, Color, Event, FrameType},
         group::Group,
-        image::{JpegImage, PngImage},
+        image::RgbImage,
         prelude::*,
     };
     
+    use ::image::DynamicImage;
     use std::path::{Path, PathBuf};
     use std::sync::{Arc, Mutex};
     
+    use crate::core::image::ImageDocument;
+    use crate::core::locale::tr;
+    use crate::ui::dialogs::dialogs;
+    use crate::ui::executor::run_in_background;
+    
+    // The image open in the Image Processing tab, shared by the view, the
+    // operations panel and the File and Edit menus
+    pub type SharedDocument = Arc<Mutex<Option<ImageDocument>>>;
+    
+    // RGB pixels with their width and height, ready for the display
+    type Pixels = (Vec<u8>, i32, i32);
+    
+    // A part of the image before and after the operations, shown in turn
+    // by clicking it
+    struct Comparison {
+        before: Pixels,
+        after: Pixels,
+        showing_after: bool,
+    }
+    
     pub struct ImageViewPanel {
         group: Group,
         display: fltk::frame::Frame,
-        current_image: Arc<Mutex<Option<PathBuf>>>,
+        document: SharedDocument,
+        // Bumped for every load so a slow decode can't replace a newer image
+        load_generation: Arc<Mutex<u64>>,
+        comparison: Arc<Mutex<Option<Comparison>>>,
     }
     
     impl Clone for ImageViewPanel {
@@ -21,7 +45,9 @@ pub mod image_view {
             Self {
                 group: self.group.clone(),
                 display: self.display.clone(),
-                current_image: self.current_image.clone(),
+                document: self.document.clone(),
+                load_generation: self.load_generation.clone(),
+                comparison: self.comparison.clone(),
             }
         }
     }
@@ -50,125 +76,207 @@ pub mod image_view {
             
             group.end();
             
-            ImageViewPanel {
+            let mut panel = ImageViewPanel {
                 group,
                 display,
-                current_image: Arc::new(Mutex::new(None)),
-            }
+                document: Arc::new(Mutex::new(None)),
+                load_generation: Arc::new(Mutex::new(0)),
+                comparison: Arc::new(Mutex::new(None)),
+            };
+            
+            // A click switches a preview between before and after
+            let mut view = panel.clone();
+            panel.display.handle(move |_, event| {
+                if event != Event::Push {
+                    return false;
+                }
+                let (pixels, showing_after) = match *view.comparison.lock().unwrap() {
+                    Some(ref mut comparison) => {
+                        comparison.showing_after = !comparison.showing_after;
+                        match comparison.showing_after {
+                            true => (comparison.after.clone(), true),
+                            false => (comparison.before.clone(), false),
+                        }
+                    },
+                    None => return false,
+                };
+                view.set_pixels(pixels);
+                view.label_comparison(showing_after);
+                true
+            });
+            
+            panel
+        }
+        
+        // The open image, for the components that edit or save it
+        pub fn document(&self) -> SharedDocument {
+            self.document.clone()
         }
         
+        // Starts decoding the image in the background; returns false if the
+        // file is missing or not an image
         pub fn load_image(&mut self, path: &Path) -> bool {
             if !path.exists() {
                 return false;
             }
             
+            if ::image::ImageFormat::from_path(path).is_err() {
+                log::error!("Unsupported image format: {}", path.display());
+                return false;
+            }
+            
             // Clear any previous image first
             self.clear();
+            self.display.set_label(&tr("Loading..."));
             
-            let extension = path.extension()
-                .and_then(|ext| ext.to_str())
-                .unwrap_or("")
-                .to_lowercase();
-                
-            let result = match extension.as_str() {
-                "jpg" | "jpeg" => self.load_jpeg(path),
-                "png" => self.load_png(path),
-                // Add more formats as needed
-                _ => false,
+            let generation = {
+                let mut generation = self.load_generation.lock().unwrap();
+                *generation += 1;
+                *generation
             };
             
-            if result {
-                // Store the current image path
-                let mut current = self.current_image.lock().unwrap();
-                *current = Some(path.to_path_buf());
-                println!("Successfully loaded image: {}", path.display());
-            } else {
-                println!("Failed to load image: {}", path.display());
-            }
-            
-            // Force a redraw of the entire component
-            self.group.redraw();
+            // The display's copy is scaled on the worker thread too
+            let size = (self.display.width(), self.display.height());
+            let mut panel = self.clone();
+            let path = path.to_path_buf();
+            run_in_background(
+                move || {
+                    let opened = ImageDocument::open(&path)
+                        .map(|document| {
+                            let pixels = fit_to(document.image(), size);
+                            (document, pixels)
+                        });
+                    (path, opened)
+                },
+                move |(path, opened)| {
+                    if *panel.load_generation.lock().unwrap() != generation {
+                        return;
+                    }
+                    
+                    panel.display.set_label("");
+                    
+                    match opened {
+                        Ok((document, pixels)) => {
+                            *panel.document.lock().unwrap() = Some(document);
+                            panel.set_pixels(pixels);
+                            log::info!("Successfully loaded image: {}", path.display());
+                        },
+                        Err(e) => {
+                            log::error!("Failed to load image: {}", e);
+                            panel.display.set_label(&format!("Cannot display {}", path.display()));
+                            panel.group.redraw();
+                        }
+                    }
+                }
+            );
             
-            result
+            true
         }
         
-        fn load_jpeg(&mut self, path: &Path) -> bool {
-            if let Ok(mut img) = JpegImage::load(path) {
-                // Scale image to fit display
-                self.scale_and_set_image(&mut img);
-                true
-            } else {
-                false
-            }
+        // Size of the area images are shown in
+        pub fn display_size(&self) -> (i32, i32) {
+            (self.display.width(), self.display.height())
         }
         
-        fn load_png(&mut self, path: &Path) -> bool {
-            if let Ok(mut img) = PngImage::load(path) {
-                // Scale image to fit display
-                self.scale_and_set_image(&mut img);
-                true
-            } else {
-                false
+        // Show `after` in place of `before`, two versions of the same part of
+        // the image; clicking switches between them until the image is
+        // shown again with `refresh` or another one is opened
+        pub fn compare(&mut self, before: &DynamicImage, after: &DynamicImage) {
+            let size = self.display_size();
+            let before = fit_to(before, size);
+            let after = fit_to(after, size);
+            self.set_pixels(after.clone());
+            *self.comparison.lock().unwrap() = Some(Comparison { before, after, showing_after: true });
+            self.label_comparison(true);
+        }
+        
+        fn label_comparison(&mut self, showing_after: bool) {
+            self.display.set_tooltip(match showing_after {
+                true => "After the operations - click to see before",
+                false => "Before the operations - click to see after",
+            });
+        }
+        
+        // Leave a before and after preview
+        fn end_comparison(&mut self) {
+            if self.comparison.lock().unwrap().take().is_some() {
+                self.display.set_tooltip("");
             }
         }
         
-        fn scale_and_set_image<I: ImageExt + Clone>(&mut self, img: &mut I) {
-            // Clear any existing image first
-            self.display.set_image::<I>(None);
+        // Show the open image again after it was edited or an edit undone
+        pub fn refresh(&mut self) {
+            self.end_comparison();
+            let size = (self.display.width(), self.display.height());
+            let pixels = match *self.document.lock().unwrap() {
+                Some(ref document) => fit_to(document.image(), size),
+                None => return,
+            };
+            self.set_pixels(pixels);
+        }
+        
+        // Whether the open image may be replaced: true when it has no
+        // unsaved edits or the user agrees to drop them
+        pub fn confirm_discard(&self) -> bool {
+            let name = match *self.document.lock().unwrap() {
+                Some(ref document) if document.is_dirty() => document.path()
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                _ => return true,
+            };
+            let question = format!("\"{}\" has changes that were not saved. Discard them?", name);
+            dialogs::choice_dialog("Unsaved Changes", &question, &["Discard", "Cancel"]) == 0
+        }
+        
+        fn set_pixels(&mut self, (data, w, h): Pixels) {
+            let image = match RgbImage::new(&data, w, h, ColorDepth::Rgb8) {
+                Ok(image) => image,
+                Err(e) => {
+                    log::error!("Cannot show image: {}", e);
+                    return;
+                }
+            };
             
-            // Reset the background 
+            // Reset the background
             self.display.set_color(Color::from_rgb(240, 240, 240));
             
-            // Get display dimensions
-            let display_w = self.display.width();
-            let display_h = self.display.height();
-            
-            // Get image dimensions
-            let img_w = img.width();
-            let img_h = img.height();
-            
-            // Calculate scale factor to fit image in display
-            let scale_w = display_w as f64 / img_w as f64;
-            let scale_h = display_h as f64 / img_h as f64;
-            let scale = scale_w.min(scale_h);
-            
-            // Scale image to fit display (whether smaller or larger)
-            let new_w = (img_w as f64 * scale) as i32;
-            let new_h = (img_h as f64 * scale) as i32;
-            img.scale(new_w, new_h, true, true);
-            
             // Set image to display
-            self.display.set_image(Some(img.clone()));
+            self.display.set_image(Some(image));
             
             // Force complete redraw
             self.display.redraw();
-            
-            // Make sure the parent is also redrawn if it exists
-            if let Some(mut parent) = self.display.parent() {
-                // We can't modify parent, just request a redraw
-                parent.redraw();
-            }
+            self.group.redraw();
         }
         
+        // File the open image was read from or last saved to
         pub fn get_current_image(&self) -> Option<PathBuf> {
-            let current = self.current_image.lock().unwrap();
-            current.clone()
+            let document = self.document.lock().unwrap();
+            document.as_ref().map(|document| document.path().to_path_buf())
         }
         
         pub fn clear(&mut self) {
+            self.end_comparison();
+            
             // Clear the image
-            self.display.set_image::<PngImage>(None);
+            self.display.set_image::<RgbImage>(None);
             
             // Reset color to original
             self.display.set_color(Color::from_rgb(240, 240, 240));
             
-            // Clear the path reference
-            let mut current = self.current_image.lock().unwrap();
-            *current = None;
+            // Close the open image
+            *self.document.lock().unwrap() = None;
             
             // Force a redraw
             self.display.redraw();
             self.group.redraw();
         }
     }
-}
\ No newline at end of file
+    
+    // Scale `image` to fit a display of `size`, whether smaller or larger
+    fn fit_to(image: &DynamicImage, (w, h): (i32, i32)) -> Pixels {
+        let fitted = image.resize(w.max(1) as u32, h.max(1) as u32, ::image::imageops::FilterType::Triangle).to_rgb8();
+        let (w, h) = (fitted.width() as i32, fitted.height() as i32);
+        (fitted.into_raw(), w, h)
+    }
+}
@@ -0,0 +1,317 @@
+use fltk::{
+    app,
+    button::{Button, CheckButton},
+    enums::{Align, Color, FrameType},
+    frame::Frame,
+    group::Group,
+    image::JpegImage,
+    input::Input,
+    menu::Choice,
+    prelude::*,
+};
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::{Config, LiveViewSettings};
+use crate::core::utils::AppError;
+use crate::core::locale::tr;
+use crate::transfer::camera_stream::{run_stream, StreamMode, StreamSource};
+use crate::ui::dialogs::dialogs;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::ui::theme::font_size;
+
+/// How often new frames are put on screen (seconds)
+const REFRESH_INTERVAL: f64 = 0.05;
+
+/// Frames and errors passed from the stream thread to the panel
+#[derive(Default)]
+struct StreamState {
+    /// Newest frame not shown yet
+    pending: Option<Vec<u8>>,
+    /// Newest frame shown, kept for snapshots
+    shown: Option<Vec<u8>>,
+    frames: usize,
+    /// Set when the stream thread ends, with its error if it failed
+    finished: Option<Result<(), String>>,
+}
+
+/// Live view of a camera stream served by the Pi, with a button to save
+/// the current picture
+///
+/// Pictures come straight from an HTTP URL or, through ssh, from curl run
+/// on the Pi, so cameras listening only on the Pi's localhost work too.
+#[derive(Clone)]
+pub struct LiveViewPanel {
+    group: Group,
+    url_input: Input,
+    mode_choice: Choice,
+    ssh_check: CheckButton,
+    start_button: Button,
+    snapshot_button: Button,
+    status_frame: Frame,
+    view: Frame,
+    state: Arc<Mutex<StreamState>>,
+    /// Stop flag of the running stream, None when stopped
+    stop: Arc<Mutex<Option<Arc<AtomicBool>>>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+    config: Arc<Mutex<Config>>,
+}
+
+impl LiveViewPanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>,
+        config: Arc<Mutex<Config>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let settings = config.lock().unwrap().live_view.clone();
+
+        let mut url_input = Input::new(x + padding + 40, y + padding, w - 400, row_height, "URL:");
+        url_input.set_align(Align::Left);
+        url_input.set_value(&settings.url);
+        url_input.set_tooltip("Camera stream on the Pi; with ssh, localhost means the Pi itself");
+
+        let mut mode_choice = Choice::new(x + w - 350, y + padding, 150, row_height, None);
+        for mode in StreamMode::ALL {
+            mode_choice.add_choice(mode.label());
+        }
+        mode_choice.set_value(if settings.snapshots { 1 } else { 0 });
+
+        let mut ssh_check = CheckButton::new(x + w - 190, y + padding, 180, row_height, "Through SSH");
+        ssh_check.set_checked(settings.through_ssh);
+        ssh_check.set_tooltip("Fetch the URL on the connected Pi and read it over the ssh connection");
+
+        let row2_y = y + padding * 2 + row_height;
+        let mut start_button = Button::new(x + padding, row2_y, 100, row_height, "Start");
+        start_button.set_color(Color::from_rgb(0, 120, 255));
+        start_button.set_label_color(Color::White);
+
+        let mut snapshot_button = Button::new(x + padding * 2 + 100, row2_y, 130, row_height, "Save Snapshot...");
+        snapshot_button.deactivate();
+
+        let mut status_frame = Frame::new(x + padding * 3 + 230, row2_y, w - padding * 4 - 230, row_height, "Stopped");
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside | Align::Clip);
+
+        let view_y = row2_y + row_height + padding;
+        let mut view = Frame::new(x + padding, view_y, w - padding * 2, h - (view_y - y) - padding, None);
+        view.set_frame(FrameType::DownBox);
+        view.set_color(Color::Black);
+        view.set_label_color(Color::Light2);
+        view.set_label(&tr("Press Start to show the camera"));
+
+        group.resizable(&view);
+        group.end();
+
+        let mut panel = LiveViewPanel {
+            group,
+            url_input,
+            mode_choice,
+            ssh_check,
+            start_button,
+            snapshot_button,
+            status_frame,
+            view,
+            state: Arc::new(Mutex::new(StreamState::default())),
+            stop: Arc::new(Mutex::new(None)),
+            remote_browser,
+            config,
+        };
+        panel.setup_callbacks();
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.start_button.set_callback(move |_| {
+            if panel.stop.lock().unwrap().is_some() {
+                panel.stop_stream();
+            } else {
+                panel.start_stream();
+            }
+        });
+
+        let panel = self.clone();
+        self.snapshot_button.set_callback(move |_| panel.save_snapshot());
+    }
+
+    /// Stop a stream that goes through a connection that is closing
+    pub fn subscribe(&self, events: &EventBus) {
+        let mut panel = self.clone();
+        events.subscribe(move |event| {
+            if let AppEvent::ConnectionChanged { .. } = event {
+                if panel.ssh_check.is_checked() {
+                    panel.stop_stream();
+                }
+            }
+        });
+    }
+
+    fn settings(&self) -> LiveViewSettings {
+        let interval = self.config.lock().unwrap().live_view.snapshot_interval_secs;
+        LiveViewSettings {
+            url: self.url_input.value().trim().to_string(),
+            snapshots: self.mode_choice.value() == 1,
+            through_ssh: self.ssh_check.is_checked(),
+            snapshot_interval_secs: interval,
+        }
+    }
+
+    fn start_stream(&mut self) {
+        let settings = self.settings();
+        if settings.url.is_empty() {
+            self.set_status("Enter the URL of the camera stream", true);
+            return;
+        }
+
+        let method = if settings.through_ssh {
+            match self.remote_browser.lock().ok().and_then(|b| b.get_transfer_method()) {
+                Some(method) => Some(method),
+                None => {
+                    self.set_status("Not connected. Connect to the Pi or untick Through SSH.", true);
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+
+        {
+            let mut config = self.config.lock().unwrap();
+            if config.live_view != settings {
+                config.live_view = settings.clone();
+                if let Err(e) = config.save() {
+                    log::warn!("Failed to save the live view settings: {}", e);
+                }
+            }
+        }
+
+        let source = StreamSource {
+            url: settings.url.clone(),
+            mode: if settings.snapshots { StreamMode::Snapshot } else { StreamMode::Mjpeg },
+            through_ssh: settings.through_ssh,
+            snapshot_interval: Duration::from_secs_f64(settings.snapshot_interval_secs.max(0.1)),
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        *self.stop.lock().unwrap() = Some(stop.clone());
+        *self.state.lock().unwrap() = StreamState::default();
+
+        let state = self.state.clone();
+        thread::spawn(move || {
+            let state_frames = state.clone();
+            let result = run_stream(&source, method.as_deref(), &stop, &mut |frame| {
+                let mut state = state_frames.lock().unwrap();
+                state.pending = Some(frame);
+                state.frames += 1;
+                app::awake();
+            });
+            state.lock().unwrap().finished = Some(result.map_err(|e| e.to_string()));
+            app::awake();
+        });
+
+        self.start_button.set_label(&tr("Stop"));
+        self.url_input.deactivate();
+        self.mode_choice.deactivate();
+        self.ssh_check.deactivate();
+        self.set_status(&format!("Connecting to {}...", settings.url), false);
+        self.watch();
+    }
+
+    fn stop_stream(&mut self) {
+        if let Some(stop) = self.stop.lock().unwrap().as_ref() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Show new frames until the stream thread ends
+    fn watch(&self) {
+        let mut panel = self.clone();
+        let mut counted_since = Instant::now();
+        let mut counted_frames = 0;
+
+        app::add_timeout3(REFRESH_INTERVAL, move |handle| {
+            let (frame, frames, finished) = {
+                let mut state = panel.state.lock().unwrap();
+                let frame = state.pending.take();
+                if let Some(frame) = &frame {
+                    state.shown = Some(frame.clone());
+                }
+                (frame, state.frames, state.finished.take())
+            };
+
+            if let Some(frame) = frame {
+                panel.show_frame(&frame);
+            }
+
+            let elapsed = counted_since.elapsed().as_secs_f64();
+            if elapsed >= 1.0 && finished.is_none() {
+                let rate = (frames - counted_frames) as f64 / elapsed;
+                panel.set_status(&format!("{} frames, {:.1} per second", frames, rate), false);
+                counted_since = Instant::now();
+                counted_frames = frames;
+            }
+
+            match finished {
+                Some(result) => panel.finish(result),
+                None => app::repeat_timeout3(REFRESH_INTERVAL, handle),
+            }
+        });
+    }
+
+    fn show_frame(&mut self, data: &[u8]) {
+        match JpegImage::from_data(data) {
+            Ok(mut image) => {
+                // Fit the picture in the view, keeping its proportions
+                image.scale(self.view.w() - 4, self.view.h() - 4, true, true);
+                self.view.set_label("");
+                self.view.set_image(Some(image));
+                self.view.redraw();
+                self.snapshot_button.activate();
+            },
+            Err(e) => log::debug!("Skipping a frame that is not a valid JPEG: {}", e),
+        }
+    }
+
+    fn finish(&mut self, result: Result<(), String>) {
+        *self.stop.lock().unwrap() = None;
+        self.start_button.set_label(&tr("Start"));
+        self.url_input.activate();
+        self.mode_choice.activate();
+        self.ssh_check.activate();
+        match result {
+            Ok(()) => self.set_status("Stopped", false),
+            Err(e) => self.set_status(&format!("Stream failed: {}", e), true),
+        }
+    }
+
+    /// Write the picture on screen to a file
+    fn save_snapshot(&self) {
+        let Some(frame) = self.state.lock().unwrap().shown.clone() else { return };
+        let Some(path) = dialogs::save_file_dialog("Save Snapshot", "*.jpg") else { return };
+        let path = if path.extension().is_none() { path.with_extension("jpg") } else { path };
+        match fs::write(&path, frame) {
+            Ok(()) => notify(ToastKind::Success, "Snapshot saved", &path.display().to_string()),
+            Err(e) => dialogs::error_dialog("Saving the snapshot failed", &AppError::from(e)),
+        }
+    }
+
+    fn set_status(&mut self, message: &str, is_error: bool) {
+        self.status_frame.set_label(message);
+        self.status_frame.set_label_color(if is_error { Color::Red } else { Color::Black });
+        self.group.redraw();
+    }
+}
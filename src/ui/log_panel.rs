@@ -0,0 +1,250 @@
+use fltk::{
+    app,
+    button::{Button, CheckButton},
+    enums::{Color, FrameType, Font, Align, CallbackTrigger},
+    frame::Frame,
+    group::Group,
+    input::Input,
+    menu::Choice,
+    text::{TextDisplay, TextBuffer, StyleTableEntry},
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+
+use log::Level;
+
+use crate::core::logging::{self, LogRecord};
+use crate::ui::theme::font_size;
+
+/// How often the log store is checked for new records (seconds)
+const TICK_INTERVAL: f64 = 0.5;
+
+/// Levels offered by the filter, most severe first
+const LEVELS: [Level; 5] = [Level::Error, Level::Warn, Level::Info, Level::Debug, Level::Trace];
+
+/// Panel showing the application log with level filtering and search
+pub struct LogPanel {
+    group: Group,
+    level_choice: Choice,
+    search_input: Input,
+    follow_check: CheckButton,
+    copy_button: Button,
+    clear_button: Button,
+    count_frame: Frame,
+    text_buffer: TextBuffer,
+    style_buffer: TextBuffer,
+    display: TextDisplay,
+    /// Value of `logging::total_records()` when the view was last rendered
+    rendered_total: Arc<Mutex<Option<u64>>>,
+}
+
+impl Clone for LogPanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            level_choice: self.level_choice.clone(),
+            search_input: self.search_input.clone(),
+            follow_check: self.follow_check.clone(),
+            copy_button: self.copy_button.clone(),
+            clear_button: self.clear_button.clone(),
+            count_frame: self.count_frame.clone(),
+            text_buffer: self.text_buffer.clone(),
+            style_buffer: self.style_buffer.clone(),
+            display: self.display.clone(),
+            rendered_total: self.rendered_total.clone(),
+        }
+    }
+}
+
+impl LogPanel {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 80;
+
+        let mut level_choice = Choice::new(x + padding + 45, y + padding, 90, row_height, "Level:");
+        for level in LEVELS {
+            level_choice.add_choice(level.as_str());
+        }
+        // Info and more severe by default
+        level_choice.set_value(2);
+
+        let search_x = x + padding + 45 + 90 + 60;
+        let search_w = w - (search_x - x) - padding - 2 * (button_width + 5) - 90;
+        let mut search_input = Input::new(search_x, y + padding, search_w, row_height, "Search:");
+        search_input.set_trigger(CallbackTrigger::Changed);
+
+        let mut follow_check = CheckButton::new(
+            search_x + search_w + 5,
+            y + padding,
+            85,
+            row_height,
+            "Follow"
+        );
+        follow_check.set_checked(true);
+        follow_check.set_tooltip("Scroll to the newest record as it arrives");
+
+        let copy_button = Button::new(
+            x + w - padding - 2 * button_width - 5,
+            y + padding,
+            button_width,
+            row_height,
+            "Copy"
+        );
+
+        let clear_button = Button::new(
+            x + w - padding - button_width,
+            y + padding,
+            button_width,
+            row_height,
+            "Clear"
+        );
+
+        let mut count_frame = Frame::new(x + padding, y + 2 * padding + row_height, w - 2 * padding, 20, None);
+        count_frame.set_label_size(font_size(12));
+        count_frame.set_align(Align::Left | Align::Inside);
+
+        let display_y = y + 3 * padding + row_height + 20;
+        let text_buffer = TextBuffer::default();
+        let style_buffer = TextBuffer::default();
+        let mut display = TextDisplay::new(
+            x + padding,
+            display_y,
+            w - 2 * padding,
+            y + h - padding - display_y,
+            None
+        );
+        display.set_buffer(text_buffer.clone());
+        display.set_frame(FrameType::BorderFrame);
+        display.set_text_font(Font::Courier);
+        display.set_text_size(font_size(12));
+
+        // Style letters: A normal, B warnings, C errors, D debug/trace
+        let style_entry = |color| StyleTableEntry { color, font: Font::Courier, size: 12 };
+        display.set_highlight_data(
+            style_buffer.clone(),
+            vec![
+                style_entry(Color::Black),
+                style_entry(Color::from_rgb(200, 110, 0)),
+                style_entry(Color::Red),
+                style_entry(Color::from_rgb(110, 110, 110)),
+            ],
+        );
+
+        group.end();
+
+        let mut panel = LogPanel {
+            group,
+            level_choice,
+            search_input,
+            follow_check,
+            copy_button,
+            clear_button,
+            count_frame,
+            text_buffer,
+            style_buffer,
+            display,
+            rendered_total: Arc::new(Mutex::new(None)),
+        };
+
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.level_choice.set_callback(move |_| panel.render());
+
+        let mut panel = self.clone();
+        self.search_input.set_callback(move |_| panel.render());
+
+        let panel = self.clone();
+        self.copy_button.set_callback(move |_| {
+            let text: Vec<String> = panel.filtered_records().iter().map(|r| r.format()).collect();
+            app::copy(&text.join("\n"));
+        });
+
+        let mut panel = self.clone();
+        self.clear_button.set_callback(move |_| {
+            logging::clear();
+            panel.render();
+        });
+
+        // Re-render only when new records arrived and the tab is showing
+        let mut panel = self.clone();
+        app::add_timeout3(TICK_INTERVAL, move |handle| {
+            let total = logging::total_records();
+            let stale = *panel.rendered_total.lock().unwrap() != Some(total);
+            if stale && panel.group.visible_r() {
+                panel.render();
+            }
+            app::repeat_timeout3(TICK_INTERVAL, handle);
+        });
+    }
+
+    /// Least severe level currently shown
+    fn max_level(&self) -> Level {
+        LEVELS.get(self.level_choice.value().max(0) as usize).copied().unwrap_or(Level::Info)
+    }
+
+    fn filtered_records(&self) -> Vec<LogRecord> {
+        self.filter(logging::records())
+    }
+
+    fn filter(&self, records: Vec<LogRecord>) -> Vec<LogRecord> {
+        let max_level = self.max_level();
+        let search = self.search_input.value().to_lowercase();
+
+        records
+            .into_iter()
+            .filter(|r| r.level <= max_level)
+            .filter(|r| search.is_empty() || r.format().to_lowercase().contains(&search))
+            .collect()
+    }
+
+    fn render(&mut self) {
+        *self.rendered_total.lock().unwrap() = Some(logging::total_records());
+
+        let all_records = logging::records();
+        let held = all_records.len();
+        let records = self.filter(all_records);
+        let mut text = String::new();
+        let mut styles = String::new();
+
+        for record in &records {
+            let line = record.format();
+            let style = match record.level {
+                Level::Error => 'C',
+                Level::Warn => 'B',
+                Level::Info => 'A',
+                Level::Debug | Level::Trace => 'D',
+            };
+            text.push_str(&line);
+            text.push('\n');
+            // One style byte per text byte
+            styles.extend(std::iter::repeat_n(style, line.len()));
+            styles.push(style);
+        }
+
+        self.text_buffer.set_text(&text);
+        self.style_buffer.set_text(&styles);
+
+        self.count_frame.set_label(&format!(
+            "{} of {} records shown",
+            records.len(),
+            held
+        ));
+
+        if self.follow_check.is_checked() {
+            let lines = self.display.count_lines(0, self.text_buffer.length(), true);
+            self.display.scroll(lines, 0);
+        }
+
+        self.display.redraw();
+    }
+}
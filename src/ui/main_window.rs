@@ -4,7 +4,8 @@ pub mod main_window {
         app,
         enums::{Shortcut, Event},
         menu::{MenuBar, MenuFlag},
-        group::{Group, Tabs},
+        frame::Frame,
+        group::{Group, Tabs, Tile},
         window::Window,
         prelude::*,
     };
@@ -13,23 +14,313 @@ pub mod main_window {
     use std::fs;
     
     use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicU64, Ordering};
     use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::Duration;
     
     use crate::core::image::{
+        ImageFormat,
         ImageProcessingService,
         JPEGProcessorFactory,
         PNGProcessorFactory,
     };
     
-    use crate::config::Config;
-    use crate::transfer::ssh::SSHTransferFactory;
+    use crate::config::{Config, Host};
+    use crate::config::ssh_import;
+    use crate::transfer::ssh::{SSHTransferFactory, shell_quote};
     
     use crate::ui::file_browser::file_browser::FileBrowserPanel;
     use crate::ui::image_view::image_view::ImageViewPanel;
+    use crate::ui::preview::PreviewPanel;
+    use crate::ui::terminal_panel::TerminalPanel;
+    use crate::ui::device_panel::DevicePanel;
+    use crate::ui::camera_panel::CameraPanel;
+    use crate::ui::services_panel::ServicesPanel;
+    use crate::ui::disk_usage_panel::DiskUsagePanel;
+    use crate::ui::log_panel::LogPanel;
+    use crate::ui::device_selector::DeviceSelector;
+    use crate::transfer::dedup::{local_stamps, remote_stamps, split_already_copied};
+    use crate::transfer::{SessionManager, ArchiveFormat, ArchiveTransfer, BroadcastJob, PathFilter, factory_for_host, factory_for_reachable_host, reachable_host, run_broadcast, summarize};
+    use crate::transfer::wake::{send_magic_packet, wait_until_reachable, WAKE_TIMEOUT};
+    use crate::transfer::download_cache;
+    use crate::core::file::{get_file_type_info, temp_root, measure_local, parse_remote_size, remote_size_command, SelectionSize};
+    use crate::core::scripting::{Hook, ScriptHooks};
+    use crate::core::upload_target::preset_upload_target;
+    use crate::core::locale::{self, source_text, tr, trf, Language};
+    use crate::core::utils::{is_image_file, AppError, AppResult};
+    use crate::core::device::{Capabilities, PowerAction, probe_local, remote_probe_command, parse_remote_probe};
     use crate::ui::operations_panel::operations_panel::OperationsPanel;
     use crate::ui::transfer_panel::transfer_panel::TransferPanel;
-    use crate::transfer::method::TransferMethodFactory;
+    use crate::transfer::method::{TransferMethod, TransferMethodFactory};
     use crate::ui::dialogs::dialogs;
+    use crate::ui::events::{AppEvent, EventBus};
+    use crate::ui::executor::run_in_background;
+    use crate::ui::search_dialog::remote_search_dialog;
+    use crate::ui::transfer_queue::{QueuedTransfer, TransferQueue};
+    use crate::ui::status_bar::{StatusBar, STATUS_BAR_HEIGHT};
+    use crate::ui::notifications::{self, ToastKind};
+    use crate::ui::shortcuts::{ShortcutMap, is_pressed};
+    use crate::ui::connection_toolbar::{ConnectionToolbar, TOOLBAR_HEIGHT};
+    use crate::ui::health_monitor::HealthMonitor;
+    use crate::ui::control_api::ControlApi;
+    use crate::ui::post_download;
+    use crate::ui::destination_dialog::{link_dropbox_account, preset_destinations_dialog};
+    use crate::ui::similar_images::find_similar_images;
+    use crate::ui::remote_trash_dialog::show_remote_trash;
+    use crate::ui::audit_dialog::audit_folder_pair;
+    use crate::ui::provision_dialog::install_companion_tools;
+    use crate::ui::transfer_stats_panel::TransferStatsPanel;
+    use crate::ui::live_view_panel::LiveViewPanel;
+    use crate::ui::batch_rename_dialog::batch_rename;
+    use crate::core::image::DEFAULT_SIMILARITY_THRESHOLD;
+    use crate::ui::export_dialog::export_dialog;
+    use crate::ui::script_export_dialog::export_pipeline_script;
+    use crate::ui::timelapse_dialog::create_timelapse;
+    use crate::ui::animation_dialog::create_animation;
+    use crate::ui::processing_queue::ProcessingQueue;
+    use crate::ui::processing_panel::ProcessingPanel;
+    use crate::ui::translation::{translate_widgets, translate_windows};
+    use crate::ui::hover_preview;
+    use crate::ui::storage_dialog::storage_dialog;
+    use crate::core::storage;
+    use crate::ui::theme;
+    
+    // Entries copied with Edit > Copy, waiting to be pasted into the other pane
+    struct CopiedEntries {
+        paths: Vec<PathBuf>,
+        from_remote: bool,
+    }
+    
+    // Height of the menu bar along the top of the window
+    const MENU_BAR_HEIGHT: i32 = 30;
+    
+    // Smallest window size restored from the settings
+    const MIN_WINDOW_WIDTH: i32 = 600;
+    const MIN_WINDOW_HEIGHT: i32 = 400;
+    
+    // Limits of the saved pane split ratios, so no pane is restored too small to grab
+    const MIN_SPLIT: f64 = 0.2;
+    const MAX_SPLIT: f64 = 0.8;
+    
+    // Largest file on the Pi that selecting downloads for the Preview tab
+    const MAX_REMOTE_PREVIEW_BYTES: u64 = 5 * 1024 * 1024;
+    
+    // Whether the keyboard focus is in the remote pane
+    fn remote_has_focus(remote_browser: &Arc<Mutex<FileBrowserPanel>>) -> bool {
+        remote_browser.lock().is_ok_and(|browser| browser.contains_focus())
+    }
+    
+    // The pane keyboard actions apply to: the remote pane when it has the
+    // focus, else the local pane
+    fn focused_pane(local_browser: &FileBrowserPanel, remote_browser: &Arc<Mutex<FileBrowserPanel>>) -> FileBrowserPanel {
+        if remote_has_focus(remote_browser) {
+            if let Ok(browser) = remote_browser.lock() {
+                return browser.clone();
+            }
+        }
+        local_browser.clone()
+    }
+    
+    // Queue copies of `paths` into the current directory of the other pane;
+    // with `skip_identical`, files the other pane already has are left out
+    // Announce the browser's selection, then count the files in any
+    // selected folders in the background; `scan` drops counts that finish
+    // after the selection changed again
+    fn publish_selection(browser: &FileBrowserPanel, is_remote: bool, events: &EventBus, scan: &Arc<AtomicU64>) {
+        let (count, bytes) = browser.selection_summary();
+        let selected = browser.get_selected_entries();
+        let scanning = selected.iter().any(|(_, is_dir)| *is_dir);
+        let method = if is_remote && scanning { browser.get_transfer_method() } else { None };
+        let scanning = scanning && (method.is_some() || !is_remote);
+        events.publish(AppEvent::SelectionChanged { is_remote, count, bytes, scanning });
+        
+        let generation = scan.fetch_add(1, Ordering::Relaxed) + 1;
+        if !scanning {
+            return;
+        }
+        let paths: Vec<PathBuf> = selected.into_iter().map(|(path, _)| path).collect();
+        let events = events.clone();
+        let scan = scan.clone();
+        run_in_background(
+            move || match method {
+                Some(method) => method
+                    .exec(&remote_size_command(&paths))
+                    .map_err(|e| e.to_string())
+                    .and_then(|output| parse_remote_size(&output.stdout).ok_or_else(|| "unexpected output from find".to_string())),
+                None => Ok(measure_local(&paths)),
+            },
+            move |result: Result<SelectionSize, String>| {
+                if scan.load(Ordering::Relaxed) != generation {
+                    return;
+                }
+                match result {
+                    Ok(size) => events.publish(AppEvent::SelectionMeasured { is_remote, files: size.files, bytes: size.bytes }),
+                    Err(e) => {
+                        log::warn!("Could not count the selected folders: {}", e);
+                        events.publish(AppEvent::SelectionChanged { is_remote, count, bytes, scanning: false });
+                    },
+                }
+            }
+        );
+    }
+    
+    fn queue_for_other_pane(
+        transfer_queue: &TransferQueue,
+        local_browser: &FileBrowserPanel,
+        remote_browser: &Arc<Mutex<FileBrowserPanel>>,
+        paths: Vec<PathBuf>,
+        into_remote: bool,
+        skip_identical: bool
+    ) {
+        let (method, remote_dir) = match remote_browser.lock() {
+            Ok(browser) if browser.is_remote() => (browser.get_transfer_method(), browser.get_current_directory()),
+            _ => (None, PathBuf::new()),
+        };
+        let method = match method {
+            Some(method) => method,
+            None => {
+                dialogs::error_dialog("Cannot copy between panes", &AppError::NotConnected);
+                return;
+            }
+        };
+        let target_dir = if into_remote { remote_dir } else { local_browser.get_current_directory() };
+        
+        if !skip_identical {
+            enqueue_copies(transfer_queue, method, paths, &target_dir, into_remote);
+            return;
+        }
+        
+        // Compare listings of both sides off the main thread
+        let transfer_queue = transfer_queue.clone();
+        run_in_background(
+            move || {
+                let source_dir = paths.first().and_then(|path| path.parent()).map(Path::to_path_buf).unwrap_or_default();
+                let stamps = if into_remote {
+                    remote_stamps(method.as_ref(), &target_dir).map(|target| (local_stamps(&source_dir), target))
+                } else {
+                    remote_stamps(method.as_ref(), &source_dir).map(|source| (source, local_stamps(&target_dir)))
+                };
+                let (copies, skipped) = match stamps {
+                    Ok((source, target)) => split_already_copied(paths, &source, &target),
+                    Err(e) => {
+                        log::warn!("Copying everything; could not compare with {}: {}", target_dir.display(), e);
+                        (paths, Vec::new())
+                    }
+                };
+                (method, target_dir, copies, skipped.len())
+            },
+            move |(method, target_dir, copies, skipped): (Box<dyn TransferMethod>, PathBuf, Vec<PathBuf>, usize)| {
+                if skipped > 0 {
+                    log::info!("Skipped {} files already in {}", skipped, target_dir.display());
+                    notifications::notify(
+                        ToastKind::Info,
+                        "Transfer",
+                        &trf("{} skipped, {} copied", &[&skipped, &copies.len()])
+                    );
+                }
+                enqueue_copies(&transfer_queue, method, copies, &target_dir, into_remote);
+            }
+        );
+    }
+    
+    // Queue a copy of each of `paths` into `target_dir`
+    fn enqueue_copies(
+        transfer_queue: &TransferQueue,
+        method: Box<dyn TransferMethod>,
+        paths: Vec<PathBuf>,
+        target_dir: &Path,
+        into_remote: bool
+    ) {
+        for source in paths {
+            let destination = match source.file_name() {
+                Some(name) => target_dir.join(name),
+                None => continue,
+            };
+            transfer_queue.enqueue(QueuedTransfer {
+                method: method.clone_box(),
+                source,
+                destination,
+                upload: into_remote,
+            });
+        }
+    }
+    
+    // Pack the selected `entries` into one archive, copy it into the current
+    // directory of the other pane and unpack it there if the user wants;
+    // much faster than copying thousands of small files one by one
+    fn compress_for_other_pane(
+        local_browser: &FileBrowserPanel,
+        remote_browser: &Arc<Mutex<FileBrowserPanel>>,
+        entries: Vec<(PathBuf, bool)>,
+        into_remote: bool
+    ) {
+        let (method, remote_dir) = match remote_browser.lock() {
+            Ok(browser) if browser.is_remote() => (browser.get_transfer_method(), browser.get_current_directory()),
+            _ => (None, PathBuf::new()),
+        };
+        let method = match method {
+            Some(method) => method,
+            None => {
+                dialogs::error_dialog("Cannot copy between panes", &AppError::NotConnected);
+                return;
+            }
+        };
+        let Some(source_dir) = entries.first().and_then(|(path, _)| path.parent()).map(Path::to_path_buf) else { return };
+        let names: Vec<String> = entries
+            .iter()
+            .filter_map(|(path, _)| path.file_name().map(|n| n.to_string_lossy().to_string()))
+            .collect();
+        
+        let formats: Vec<&str> = ArchiveFormat::ALL.iter().map(|f| f.extension()).chain(["Cancel"]).collect();
+        let choice = dialogs::choice_dialog(
+            "Compress and Transfer",
+            &trf("Pack {} selected entries as:", &[&names.len()]),
+            &formats
+        );
+        let Some(format) = usize::try_from(choice).ok().and_then(|i| ArchiveFormat::ALL.get(i).copied()) else { return };
+        let extract = match dialogs::choice_dialog(
+            "Compress and Transfer",
+            "Once copied, the archive should be:",
+            &["Extracted", "Kept as is", "Cancel"]
+        ) {
+            0 => true,
+            1 => false,
+            _ => return,
+        };
+        
+        let transfer = ArchiveTransfer {
+            source_dir,
+            names,
+            target_dir: if into_remote { remote_dir } else { local_browser.get_current_directory() },
+            format,
+            extract,
+        };
+        notifications::notify(ToastKind::Info, "Compressing", &transfer.archive_name());
+        
+        let mut local_browser = local_browser.clone();
+        let remote_browser = remote_browser.clone();
+        run_in_background(
+            move || if into_remote { transfer.upload(method.as_ref()) } else { transfer.download(method.as_ref()) },
+            move |result| match result {
+                Ok(written) => {
+                    notifications::notify(
+                        ToastKind::Success,
+                        if into_remote { "Upload finished" } else { "Download finished" },
+                        &written.display().to_string()
+                    );
+                    if into_remote {
+                        if let Ok(mut browser) = remote_browser.lock() {
+                            browser.refresh();
+                        }
+                    } else {
+                        local_browser.refresh();
+                    }
+                },
+                Err(e) => dialogs::error_dialog("Compress and transfer failed", &AppError::from(e)),
+            }
+        );
+    }
     
     pub struct MainWindow {
         window: Window,
@@ -39,39 +330,80 @@ pub mod main_window {
         // Store a reference to the actual browser instance
         remote_browser_ref: Arc<Mutex<FileBrowserPanel>>, 
         image_view: ImageViewPanel,
+        preview_panel: PreviewPanel,
+        terminal_panel: TerminalPanel,
+        device_panel: DevicePanel,
+        camera_panel: CameraPanel,
+        services_panel: ServicesPanel,
+        disk_usage_panel: DiskUsagePanel,
+        log_panel: LogPanel,
+        device_selector: DeviceSelector,
         operations_panel: OperationsPanel,
         transfer_panel: TransferPanel,
+        tabs: Tabs,
+        events: EventBus,
+        transfer_queue: TransferQueue,
+        processing_queue: ProcessingQueue,
+        processing_panel: ProcessingPanel,
+        connection_toolbar: ConnectionToolbar,
+        health_monitor: HealthMonitor,
+        status_bar: StatusBar,
         // Added for temporary file management
         temp_dir: PathBuf,
     }
     
     impl MainWindow {
-        pub fn new(title: &str, width: i32, height: i32) -> Self {
-            // Create main window
-            let mut window = Window::new(100, 100, width, height, title);
-            
+        pub fn new(title: &str) -> Self {
             // Load configuration
-            let config = Arc::new(Mutex::new(Config::load().unwrap_or_else(|_| Config::default())));
+            let config = Config::load().unwrap_or_else(|err| {
+                log::warn!("Failed to load config ({}), using defaults", err);
+                Config::default()
+            });
+            locale::set_language(
+                config.language.as_deref().and_then(Language::from_code).unwrap_or_else(Language::from_environment)
+            );
+            theme::apply_appearance(&config.appearance);
+            
+            // Reopen the window where it was left, if that is still on a screen
+            let width = config.window_width.max(MIN_WINDOW_WIDTH);
+            let height = config.window_height.max(MIN_WINDOW_HEIGHT);
+            let mut window = match (config.window_x, config.window_y) {
+                (Some(x), Some(y)) if Self::on_screen(x, y) => Window::new(x, y, width, height, title),
+                _ => Window::new(100, 100, width, height, title),
+            };
+            let browser_split = config.browser_split.clamp(MIN_SPLIT, MAX_SPLIT);
+            let transfer_split = config.transfer_split.clamp(MIN_SPLIT, MAX_SPLIT);
+            let last_tab = config.last_tab.clone();
+            
+            let config = Arc::new(Mutex::new(config));
             
             // Create image processing service
             let mut image_service = ImageProcessingService::new();
             
-            // Register image processor factories
-            image_service.register_factory(Box::new(JPEGProcessorFactory::new(85)));
-            image_service.register_factory(Box::new(PNGProcessorFactory::new(6)));
+            // Register image processor factories with the settings last chosen
+            let processors = config.lock().unwrap().processors.clone();
+            image_service.register_factory(Box::new(JPEGProcessorFactory::new(processors.jpeg_quality)));
+            image_service.register_factory(Box::new(PNGProcessorFactory::new(processors.png_compression)));
             // Add more factories as needed
             
             let image_service = Arc::new(Mutex::new(image_service));
             
+            // Event channel the panels use to notify each other
+            let events = EventBus::new();
+            
             // Create menu bar
-            let mut menu_bar = MenuBar::new(0, 0, width, 30, "");
+            let mut menu_bar = MenuBar::new(0, 0, width, MENU_BAR_HEIGHT, "");
+            
+            // Saved hosts, Connect/Disconnect and the connection state under the menu
+            let connection_toolbar = ConnectionToolbar::new(0, MENU_BAR_HEIGHT, width, TOOLBAR_HEIGHT, config.clone());
+            connection_toolbar.subscribe(&events);
             
             // Create main layout
-            let content_y = 30; // Below menu bar
-            let content_height = height - content_y;
+            let content_y = MENU_BAR_HEIGHT + TOOLBAR_HEIGHT; // Below menu bar and toolbar
+            let content_height = height - content_y - STATUS_BAR_HEIGHT;
             
             // Create tabs
-            let tabs = Tabs::new(0, content_y, width, content_height, "");
+            let mut tabs = Tabs::new(0, content_y, width, content_height, "");
             
             // Add tabs
             tabs.begin();
@@ -81,11 +413,17 @@ pub mod main_window {
             browser_tab.begin();
             
             // Split the browser tab horizontally
-            let panel_width = width / 2 - 5;
+            let panel_width = (width as f64 * browser_split) as i32;
+            let remote_x = panel_width;
+            let remote_width = width - remote_x;
             
-            // Create transfer panel (at the bottom first to get height)
-            let transfer_panel_height = 120;
-            let browser_height = content_height - 35 - transfer_panel_height - 10;
+            // Panes on top, transfer panel below
+            let split_height = content_height - 45;
+            let browser_height = (split_height as f64 * transfer_split) as i32;
+            let transfer_panel_height = split_height - browser_height;
+            
+            // Local and remote panes share a draggable divider
+            let mut panes = Tile::new(0, content_y + 35, width, browser_height, None);
             
             // Create local file browser panel (left side)
             let mut local_browser = FileBrowserPanel::new(
@@ -97,22 +435,76 @@ pub mod main_window {
             );
             
             // Create remote file browser panel (right side) and immediately wrap in Arc<Mutex<>>
-            let remote_browser = FileBrowserPanel::new(
-                panel_width + 10, 
+            let mut remote_browser = FileBrowserPanel::new(
+                remote_x, 
                 content_y + 35, 
-                panel_width, 
+                remote_width, 
                 browser_height,
                 "Raspberry Pi Files"
             );
             
+            // The divider stops where either pane would get narrower than MIN_SPLIT
+            let split_limits = Frame::new(
+                (width as f64 * MIN_SPLIT) as i32,
+                content_y + 35,
+                (width as f64 * (MAX_SPLIT - MIN_SPLIT)) as i32,
+                browser_height,
+                None
+            );
+            panes.resizable(&split_limits);
+            panes.end();
+            
+            local_browser.set_config(config.clone());
+            remote_browser.set_config(config.clone());
+            
+            {
+                let config = config.lock().unwrap();
+                remote_browser.set_listing_options(
+                    Duration::from_secs(config.listing_timeout_secs.max(1)),
+                    Duration::from_secs(config.listing_cache_ttl_secs)
+                );
+            }
+            
             let remote_browser_ref = Arc::new(Mutex::new(remote_browser));
             
+            // Device selector for switching between connected Pis
+            let mut device_selector = DeviceSelector::new(
+                remote_x + remote_width - 230,
+                content_y + 35 + 10,
+                220,
+                25,
+                Arc::new(Mutex::new(SessionManager::new())),
+                remote_browser_ref.clone(),
+                events.clone()
+            );
+            
+            // Keep the selector inside the remote pane as the divider moves
+            let remote_browser_divider = remote_browser_ref.clone();
+            let device_selector_divider = device_selector.clone();
+            panes.handle(move |_, event| {
+                if matches!(event, Event::Drag | Event::Released) {
+                    let remote_browser = remote_browser_divider.clone();
+                    let mut device_selector = device_selector_divider.clone();
+                    // Run after the tile has moved the panes
+                    app::add_timeout3(0.0, move |_| {
+                        if let Ok(browser) = remote_browser.lock() {
+                            device_selector.fit_to_pane(browser.bounds());
+                        }
+                    });
+                }
+                false
+            });
+            if let Ok(browser) = remote_browser_ref.lock() {
+                device_selector.fit_to_pane(browser.bounds());
+            }
+            
             let transfer_panel = TransferPanel::new(
                 0,
                 content_y + 35 + browser_height + 5,
                 width,
                 transfer_panel_height,
-                config.clone()
+                config.clone(),
+                events.clone()
             );
             
             browser_tab.end();
@@ -137,30 +529,211 @@ pub mod main_window {
                 content_y + 35,
                 operations_width,
                 content_height - 35,
-                image_service.clone()
+                image_service.clone(),
+                image_view.clone(),
+                config.clone()
             );
             
             image_tab.end();
             
-            tabs.end();
+            // Processing Jobs Tab listing batch work running in the background
+            let processing_queue = ProcessingQueue::new(events.clone());
+            let jobs_tab = Group::new(0, content_y + 30, width, content_height - 30, "Processing Jobs");
+            jobs_tab.begin();
             
-            // Set initial directory for file browsers
-            let default_dir = config.lock().unwrap().default_local_dir.clone();
-            local_browser.set_directory(&PathBuf::from(&default_dir));
+            let processing_panel = ProcessingPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                processing_queue.clone(),
+                image_service.clone(),
+                config.clone()
+            );
+            processing_panel.subscribe(&events);
+            
+            jobs_tab.end();
+            
+            // Transfers Tab graphing the speed of queued copies
+            let transfers_tab = Group::new(0, content_y + 30, width, content_height - 30, "Transfers");
+            transfers_tab.begin();
+            
+            let transfer_stats_panel = TransferStatsPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35
+            );
+            transfer_stats_panel.subscribe(&events);
+            
+            transfers_tab.end();
+            
+            // Preview Tab for text, Markdown and other non-image files
+            let preview_tab = Group::new(0, content_y + 30, width, content_height - 30, "Preview");
+            preview_tab.begin();
             
-            // Setup temp directory for remote file previews
-            let mut temp_dir = env::temp_dir();
-            temp_dir.push("pi_image_processor_preview");
+            let preview_panel = PreviewPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35
+            );
+            
+            preview_tab.end();
+            
+            // Terminal Tab for running commands on the connected Pi
+            let terminal_tab = Group::new(0, content_y + 30, width, content_height - 30, "Terminal");
+            terminal_tab.begin();
+            
+            let terminal_panel = TerminalPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone()
+            );
+            
+            terminal_tab.end();
+            
+            // Device Tab with health information for the connected Pi
+            let device_tab = Group::new(0, content_y + 30, width, content_height - 30, "Device");
+            device_tab.begin();
+            
+            let device_panel = DevicePanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone(),
+                config.clone()
+            );
+            
+            device_tab.end();
+            
+            // Folder the camera panel downloads captures to
+            let temp_dir = temp_root().join("camera");
             
             // Create the temp directory if it doesn't exist
             if !temp_dir.exists() {
                 let _ = fs::create_dir_all(&temp_dir);
             }
             
+            // Camera Tab for capturing stills on the Pi
+            let camera_tab = Group::new(0, content_y + 30, width, content_height - 30, "Camera");
+            camera_tab.begin();
+            
+            let camera_panel = CameraPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone(),
+                temp_dir.clone()
+            );
+            
+            camera_tab.end();
+            
+            // Live View Tab showing a camera stream served by the Pi
+            let live_view_tab = Group::new(0, content_y + 30, width, content_height - 30, "Live View");
+            live_view_tab.begin();
+            
+            let live_view_panel = LiveViewPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone(),
+                config.clone()
+            );
+            live_view_panel.subscribe(&events);
+            
+            live_view_tab.end();
+            
+            // Services Tab for managing systemd units on the Pi
+            let services_tab = Group::new(0, content_y + 30, width, content_height - 30, "Services");
+            services_tab.begin();
+            
+            let services_panel = ServicesPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone()
+            );
+            
+            services_tab.end();
+            
+            // Disk Usage Tab for finding what fills the SD card
+            let disk_usage_tab = Group::new(0, content_y + 30, width, content_height - 30, "Disk Usage");
+            disk_usage_tab.begin();
+            
+            let disk_usage_panel = DiskUsagePanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35,
+                remote_browser_ref.clone()
+            );
+            
+            disk_usage_tab.end();
+            
+            // Logs Tab showing the application log
+            let logs_tab = Group::new(0, content_y + 30, width, content_height - 30, "Logs");
+            logs_tab.begin();
+            
+            let log_panel = LogPanel::new(
+                0,
+                content_y + 35,
+                width,
+                content_height - 35
+            );
+            
+            logs_tab.end();
+            
+            tabs.end();
+            
+            // Reopen the tab that was open at the last exit
+            let tab_groups = [
+                &browser_tab, &image_tab, &jobs_tab, &transfers_tab, &preview_tab, &terminal_tab, &device_tab,
+                &camera_tab, &live_view_tab, &services_tab, &disk_usage_tab, &logs_tab,
+            ];
+            if let Some(tab) = tab_groups.iter().find(|tab| Some(tab.label()) == last_tab) {
+                let _ = tabs.set_value(*tab);
+            }
+            
+            // Connection, transfer and selection summary along the bottom
+            let status_bar = StatusBar::new(0, height - STATUS_BAR_HEIGHT, width, STATUS_BAR_HEIGHT);
+            status_bar.subscribe(&events);
+            
+            // Set initial directory for file browsers
+            let default_dir = config.lock().unwrap().default_local_dir.clone();
+            local_browser.set_directory(&PathBuf::from(&default_dir));
+            
             // Finish the window
             window.end();
             window.make_resizable(true);
             
+            // Keepalive on the connection shown in the remote pane
+            // Keep the caches and temporary files within their limits, at
+            // startup and then periodically
+            let config_cleanup = config.clone();
+            thread::spawn(move || loop {
+                let settings = config_cleanup.lock().unwrap().storage.clone();
+                storage::enforce(&settings);
+                thread::sleep(Duration::from_secs(settings.cleanup_minutes.max(1) * 60));
+            });
+            
+            let health_check_secs = config.lock().unwrap().health_check_secs;
+            let health_monitor = HealthMonitor::start(
+                remote_browser_ref.clone(),
+                events.clone(),
+                Duration::from_secs(health_check_secs)
+            );
+            
+            // Automation endpoints, when enabled in the config file
+            let control_api = config.lock().unwrap().control_api.clone();
+            ControlApi::start(&control_api, config.clone(), processing_queue.clone());
+            
             // Create the main window struct
             let mut main_window = MainWindow {
                 window,
@@ -169,25 +742,62 @@ pub mod main_window {
                 local_browser,
                 remote_browser_ref,
                 image_view,
+                preview_panel,
+                terminal_panel,
+                device_panel,
+                camera_panel,
+                services_panel,
+                disk_usage_panel,
+                log_panel,
+                device_selector,
                 operations_panel,
                 transfer_panel,
+                transfer_queue: TransferQueue::new(events.clone()),
+                processing_queue,
+                processing_panel,
+                tabs: tabs.clone(),
+                connection_toolbar,
+                health_monitor,
+                status_bar,
+                events,
                 temp_dir,
             };
             
+            // Completed background jobs are announced with toasts
+            let desktop_notifications = main_window.config.lock().unwrap().desktop_notifications;
+            notifications::init(&main_window.window, desktop_notifications);
+            
             // Create a shared reference to the image view
             let image_view_ref = Arc::new(Mutex::new(main_window.image_view.clone()));
             
-            // Setup menu with access to the remote browser and image view
+            // Create a shared reference to the preview panel
+            let preview_panel_ref = Arc::new(Mutex::new(main_window.preview_panel.clone()));
+            
+            // Setup menu with access to the remote browser, image view and preview panel
             Self::setup_menu(
                 &mut menu_bar, 
                 main_window.config.clone(), 
                 main_window.image_service.clone(),
                 main_window.remote_browser_ref.clone(),
-                image_view_ref.clone()
+                image_view_ref.clone(),
+                preview_panel_ref.clone(),
+                tabs.clone(),
+                preview_tab.clone(),
+                main_window.events.clone(),
+                main_window.device_selector.clone(),
+                main_window.local_browser.clone(),
+                main_window.transfer_queue.clone(),
+                main_window.health_monitor.clone(),
+                main_window.processing_queue.clone(),
+                main_window.operations_panel.clone()
             );
             
             // Setup callbacks with the shared remote browser reference and image view
-            main_window.setup_callbacks(tabs, content_y, image_view_ref);
+            main_window.setup_callbacks(tabs, preview_tab, content_y, image_view_ref, preview_panel_ref);
+            
+            // Everything above is built from English text
+            translate_widgets(&mut main_window.window, Language::English);
+            theme::apply_contrast(&mut main_window.window);
             
             main_window
         }
@@ -197,8 +807,20 @@ pub mod main_window {
             config: Arc<Mutex<Config>>,
             image_service: Arc<Mutex<ImageProcessingService>>,
             remote_browser: Arc<Mutex<FileBrowserPanel>>,
-            image_view: Arc<Mutex<ImageViewPanel>>
+            image_view: Arc<Mutex<ImageViewPanel>>,
+            preview_panel: Arc<Mutex<PreviewPanel>>,
+            tabs: Tabs,
+            preview_tab: Group,
+            events: EventBus,
+            device_selector: DeviceSelector,
+            local_browser: FileBrowserPanel,
+            transfer_queue: TransferQueue,
+            health_monitor: HealthMonitor,
+            processing_queue: ProcessingQueue,
+            operations_panel: OperationsPanel
         ) {
+            let shortcuts = ShortcutMap::from_bindings(&config.lock().unwrap().key_bindings);
+            
             // File menu
             let image_view_clone = image_view.clone();
             menu.add(
@@ -207,17 +829,18 @@ pub mod main_window {
                 MenuFlag::Normal,
                 move |_| {
                     if let Some(path) = dialogs::open_file_dialog("Open Image", "") {
-                        println!("Opening image: {}", path.display());
+                        log::info!("Opening image: {}", path.display());
                         
                         // Get lock on the image view panel and load the image
                         if let Ok(mut view) = image_view_clone.lock() {
-                            if view.load_image(&path) {
-                                println!("Successfully loaded image: {}", path.display());
-                            } else {
+                            if !view.confirm_discard() {
+                                return;
+                            }
+                            if !view.load_image(&path) {
                                 // Show error dialog if loading fails
                                 dialogs::message_dialog(
                                     "Error", 
-                                    &format!("Failed to load image: {}", path.display())
+                                    &trf("Failed to load image: {}", &[&path.display()])
                                 );
                             }
                         }
@@ -225,129 +848,682 @@ pub mod main_window {
                 },
             );
             
+            // Save the open image with its edits, as it is in memory, in a
+            // format one of the processors writes
+            let document_save = image_view.lock().unwrap().document();
+            let image_service_save = image_service.clone();
             menu.add(
                 "&File/&Save Image As...\t",
                 Shortcut::Ctrl | 's',
                 MenuFlag::Normal,
-                |_| {
-                    if let Some(path) = dialogs::save_file_dialog("Save Image As", "") {
-                        // Handle saving the image
-                        println!("Saving image to: {}", path.display());
+                move |_| {
+                    let (source, source_format, has_alpha) = match *document_save.lock().unwrap() {
+                        Some(ref document) => (
+                            document.path().to_path_buf(),
+                            document.format().clone(),
+                            document.image().color().has_alpha()
+                        ),
+                        None => {
+                            dialogs::message_dialog("Save Image As", "Open an image first.");
+                            return;
+                        }
+                    };
+                    
+                    let (formats, options) = {
+                        let service = image_service_save.lock().unwrap();
+                        let formats = service.output_formats();
+                        let options: Vec<String> = formats
+                            .iter()
+                            .map(|(format, index)| format!(
+                                "{:?} (.{}) with {}",
+                                format,
+                                format.extension(),
+                                service.get_factories()[*index].get_name()
+                            ))
+                            .collect();
+                        (formats, options)
+                    };
+                    if formats.is_empty() {
+                        dialogs::message_dialog("Save Image As", "No image processor is registered to write the image.");
+                        return;
+                    }
+                    let Some(choice) = dialogs::select_dialog("Save Image As", "Save the image as:", &options) else {
+                        return;
+                    };
+                    let (format, factory_index) = formats[choice].clone();
+                    
+                    // Lossless or transparent images lose something for good
+                    if format.is_lossy() && (!source_format.is_lossy() || has_alpha) {
+                        let mut warning = format!(
+                            "Saving as {:?} loses detail that can't be recovered from the saved file.",
+                            format
+                        );
+                        if has_alpha {
+                            warning.push_str(" Transparent areas become solid.");
+                        }
+                        if dialogs::choice_dialog("Save Image As", &warning, &["Save", "Cancel"]) != 0 {
+                            return;
+                        }
+                    }
+                    
+                    let extension = format.extension();
+                    let Some(mut path) = dialogs::save_file_named_dialog(
+                        "Save Image As",
+                        &format!("*.{}", extension),
+                        &source.with_extension(extension)
+                    ) else {
+                        return;
+                    };
+                    // The chosen format decides the extension
+                    let typed = path.extension().and_then(|ext| ext.to_str()).map(ImageFormat::from_extension);
+                    if typed.as_ref() != Some(&format) {
+                        path.set_extension(extension);
+                    }
+                    
+                    log::info!("Saving image to: {}", path.display());
+                    let saved = match *document_save.lock().unwrap() {
+                        Some(ref mut document) => {
+                            document.save_as(&path, &image_service_save.lock().unwrap(), factory_index)
+                        },
+                        None => return,
+                    };
+                    match saved {
+                        Ok(()) => notifications::notify(ToastKind::Success, "Save Image As", &trf("Saved {}", &[&path.display()])),
+                        Err(e) => dialogs::error_dialog("Save failed", &e.into()),
                     }
                 },
             );
             
+            // Render the open image for sharing and save or upload it
+            let image_view_export = image_view.clone();
+            let remote_browser_export = remote_browser.clone();
+            let transfer_queue_export = transfer_queue.clone();
             menu.add(
-                "&File/&Exit\t",
-                Shortcut::Ctrl | 'q',
+                "&File/E&xport...\t",
+                Shortcut::Ctrl | 'e',
                 MenuFlag::Normal,
-                |_| {
-                    app::quit();
+                move |_| {
+                    let Some(image) = image_view_export.lock().ok().and_then(|view| view.get_current_image()) else {
+                        dialogs::message_dialog("Export", "Open an image first.");
+                        return;
+                    };
+                    let remote = remote_browser_export.lock().ok().and_then(|browser| {
+                        browser.get_transfer_method().map(|method| (method, browser.get_current_directory()))
+                    });
+                    export_dialog(&image, remote, transfer_queue_export.clone());
                 },
             );
             
-            // Connection menu
-            let config_clone1 = config.clone();
-            let remote_browser_clone1 = remote_browser.clone();
-
+            // Share the host list and settings with other machines
+            let config_export = config.clone();
             menu.add(
-                "&Connection/&Connect to Raspberry Pi...\t",
-                Shortcut::Ctrl | 'r',
+                "&File/Export Se&ttings...\t",
+                Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    // Show connection dialog without locking anything first
-                    if let Some(host) = dialogs::connection_dialog(config_clone1.clone()) {
-                        // Now we have a host, update config
-                        {
-                            let mut config = config_clone1.lock().unwrap();
-                            
-                            // Check if host already exists
-                            if let Some(pos) = config.hosts.iter().position(|h| h.name == host.name) {
-                                config.hosts[pos] = host.clone();
-                            } else {
-                                config.hosts.push(host.clone());
-                            }
-                            
-                            // Save config
-                            let _ = config.save();
-                        }
-                        
-                        // If using password auth, prompt for password
-                        let mut password_opt = None;
-                        if !host.use_key_auth {
-                            password_opt = dialogs::password_dialog(
-                                "SSH Password",
-                                &format!("Enter password for {}@{}:", host.username, host.hostname)
-                            );
-                        }
-                        
-                        // Create SSH connection to list remote files
-                        let factory = SSHTransferFactory::new(
-                            host.hostname.clone(),
-                            host.username.clone(),
-                            host.port,
-                            host.use_key_auth,
-                            host.key_path.clone(),
-                        );
-                        
-                        let mut transfer_method = factory.create_method();
-                        
-                        // If password was provided, set it in the transfer method
-                        if let Some(password) = &password_opt {
-                            transfer_method.set_password(password);
-                        }
-                        
-                        // Set initial remote directory (usually /home/username)
-                        let remote_home = PathBuf::from(format!("/home/{}", host.username));
-                        
-                        println!("DEBUG: About to set remote directory with path: {}", remote_home.display());
-                        println!("DEBUG: Transfer method: {}", transfer_method.get_name());
-                        
-                        // Get a mutable reference to the actual remote browser through the mutex
-                        if let Ok(mut browser) = remote_browser_clone1.lock() {
-                            // Store credentials for future use
-                            browser.current_hostname = Some(host.hostname.clone());
-                            browser.current_username = Some(host.username.clone());
-                            browser.current_password = password_opt;
-                            
-                            // Configure the remote browser with the transfer method and initial path
-                            browser.set_remote_directory(&remote_home, transfer_method);
-                            
-                            // Force a UI refresh after setting up the connection
-                            app::flush();  // Flush pending UI events
-                            app::awake();  // Wake up the UI thread
-                            app::redraw(); // Force complete redraw
-                            
-                            // Print debug status after connection
-                            browser.print_debug_status();
-                            
-                            println!("DEBUG: Set remote directory successfully");
-                            println!("Connected to: {} and set remote home to: {}", 
-                                    host.hostname, remote_home.display());
-                        } else {
-                            println!("Error: Could not lock remote browser");
-                        }
+                    let include_private = match dialogs::choice_dialog(
+                        "Export Settings",
+                        "Include SSH key paths and this computer's window and directory state?",
+                        &["Leave Out", "Include", "Cancel"]
+                    ) {
+                        0 => false,
+                        1 => true,
+                        _ => return,
+                    };
+                    
+                    let mut path = match dialogs::save_file_dialog("Export Settings", "*.json") {
+                        Some(path) => path,
+                        None => return,
+                    };
+                    if path.extension().is_none() {
+                        path.set_extension("json");
+                    }
+                    
+                    let result = config_export.lock().unwrap().export_to(&path, include_private);
+                    match result {
+                        Ok(()) => notifications::notify(
+                            ToastKind::Success,
+                            "Settings exported",
+                            &path.display().to_string()
+                        ),
+                        Err(e) => dialogs::error_dialog("Export failed", &e),
                     }
                 },
             );
-
-            // Add a new menu item to directly show Raspberry Pi files
-            let config_clone2 = config.clone();
-            let remote_browser_clone2 = remote_browser.clone();
-
+            
+            let config_import = config.clone();
             menu.add(
-                "&Connection/&Show Raspberry Pi Files\t",
+                "&File/&Import Settings...\t",
                 Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    println!("DEBUG: Show Raspberry Pi Files clicked");
+                    let path = match dialogs::open_file_dialog("Import Settings", "*.json") {
+                        Some(path) => path,
+                        None => return,
+                    };
                     
-                    // Ask for password first since we need it for the connection
-                    let password = dialogs::password_dialog("SSH Password", "Enter password for Raspberry Pi:");
+                    let imported = match Config::import_from(&path) {
+                        Ok(imported) => imported,
+                        Err(e) => {
+                            dialogs::error_dialog("Import failed", &e);
+                            return;
+                        }
+                    };
                     
-                    // First get the saved config to use stored credentials
-                    if let Ok(config) = config_clone2.lock() {
-                        // Find a Raspberry Pi host in saved hosts
+                    let mode = dialogs::choice_dialog(
+                        "Import Settings",
+                        &trf(
+                            "{} has {} host(s). Add its hosts and groups to yours, or replace all settings?",
+                            &[&path.display(), &imported.hosts.len()]
+                        ),
+                        &["Add Hosts", "Replace All", "Cancel"]
+                    );
+                    
+                    let mut config = config_import.lock().unwrap();
+                    let summary = match mode {
+                        0 => format!("Added {} host(s)", config.merge_hosts(&imported)),
+                        1 => {
+                            config.replace_with(imported);
+                            "Settings replaced, except the control API and hook script; some take effect after a restart".to_string()
+                        },
+                        _ => return,
+                    };
+                    
+                    match config.save() {
+                        Ok(()) => {
+                            log::info!("Imported settings from {}: {}", path.display(), summary);
+                            notifications::notify(ToastKind::Success, "Settings imported", &summary);
+                        },
+                        Err(e) => dialogs::error_dialog("Failed to save settings", &e),
+                    }
+                },
+            );
+            
+            let config_restore = config.clone();
+            let restore_flag = if config.lock().unwrap().restore_session { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            menu.add(
+                "&File/&Restore Session on Startup\t",
+                Shortcut::None,
+                restore_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_restore.lock().unwrap().restore_session = enabled;
+                    log::info!("Session restore {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+
+            // Switching translates every open window from the old language
+            for language in Language::ALL {
+                let config_language = config.clone();
+                let flag = if language == locale::language() { MenuFlag::Radio | MenuFlag::Value } else { MenuFlag::Radio };
+                menu.add(
+                    &format!("&File/&Language/{}\t", language.native_name()),
+                    Shortcut::None,
+                    flag,
+                    move |_| {
+                        let previous = locale::language();
+                        if previous == language {
+                            return;
+                        }
+                        locale::set_language(language);
+                        translate_windows(previous);
+
+                        let mut config = config_language.lock().unwrap();
+                        config.language = Some(language.code().to_string());
+                        if let Err(e) = config.save() {
+                            dialogs::error_dialog("Failed to save settings", &e);
+                        }
+                        log::info!("UI language set to {}", language.native_name());
+                    },
+                );
+            }
+
+            let config_appearance = config.clone();
+            menu.add(
+                "&File/&Appearance...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let current = config_appearance.lock().unwrap().appearance.clone();
+                    let Some(appearance) = dialogs::appearance_dialog(&current) else { return };
+                    if appearance == current {
+                        return;
+                    }
+
+                    // The scale applies at once; fonts and colours are set
+                    // as widgets are made, so those wait for a restart
+                    theme::apply_scale(appearance.ui_scale);
+                    if appearance.font_size != current.font_size || appearance.high_contrast != current.high_contrast {
+                        dialogs::message_dialog("Appearance", "The new font size and colours apply after a restart.");
+                    }
+
+                    let mut config = config_appearance.lock().unwrap();
+                    config.appearance = appearance;
+                    if let Err(e) = config.save() {
+                        dialogs::error_dialog("Failed to save settings", &e);
+                    }
+                },
+            );
+
+            let config_storage = config.clone();
+            menu.add(
+                "&File/Storage and &Caches...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let current = config_storage.lock().unwrap().storage.clone();
+                    let Some(settings) = storage_dialog(&current) else { return };
+                    if settings == current {
+                        return;
+                    }
+                    storage::apply_limits(&settings);
+                    let mut config = config_storage.lock().unwrap();
+                    config.storage = settings;
+                    if let Err(e) = config.save() {
+                        dialogs::error_dialog("Failed to save settings", &e);
+                    }
+                },
+            );
+
+            menu.add(
+                "&File/&Exit\t",
+                Shortcut::Ctrl | 'q',
+                MenuFlag::Normal,
+                |_| {
+                    app::quit();
+                },
+            );
+            
+            // Edit menu: take back the last change to the open image
+            let image_view_undo = image_view.clone();
+            menu.add(
+                "&Edit/&Undo Image Edit\t",
+                Shortcut::Ctrl | 'z',
+                MenuFlag::Normal,
+                move |_| {
+                    let Ok(mut view) = image_view_undo.lock() else { return };
+                    let undone = match *view.document().lock().unwrap() {
+                        Some(ref mut document) => document.undo(),
+                        None => false,
+                    };
+                    if undone {
+                        view.refresh();
+                    } else {
+                        notifications::notify(ToastKind::Info, "Undo", "There is no image edit to undo");
+                    }
+                },
+            );
+            
+            // Copy entries in one pane, paste them into the other
+            let copied: Arc<Mutex<Option<CopiedEntries>>> = Arc::new(Mutex::new(None));
+            
+            let copied_entries = copied.clone();
+            let local_browser_copied = local_browser.clone();
+            let remote_browser_copied = remote_browser.clone();
+            menu.add(
+                "&Edit/&Copy\t",
+                Shortcut::Ctrl | 'c',
+                MenuFlag::Normal,
+                move |_| {
+                    let from_remote = match remote_browser_copied.lock() {
+                        Ok(browser) => browser.contains_focus(),
+                        Err(_) => false,
+                    };
+                    let selected = if from_remote {
+                        remote_browser_copied.lock().ok().and_then(|b| b.get_selected_entry())
+                    } else if local_browser_copied.contains_focus() {
+                        local_browser_copied.get_selected_entry()
+                    } else {
+                        None
+                    };
+                    
+                    let (path, is_dir) = match selected {
+                        Some(entry) => entry,
+                        None => return,
+                    };
+                    if is_dir {
+                        dialogs::message_dialog("Copy", "Only files can be copied between the panes.");
+                        return;
+                    }
+                    
+                    log::info!("Copied {} from the {} pane", path.display(), if from_remote { "remote" } else { "local" });
+                    // Other applications can paste the path as text
+                    app::copy(&path.to_string_lossy());
+                    *copied_entries.lock().unwrap() = Some(CopiedEntries { paths: vec![path], from_remote });
+                },
+            );
+            
+            let copied_paste = copied.clone();
+            let config_paste = config.clone();
+            let transfer_queue_paste = transfer_queue.clone();
+            let local_browser_paste = local_browser.clone();
+            let remote_browser_paste = remote_browser.clone();
+            menu.add(
+                "&Edit/&Paste\t",
+                Shortcut::Ctrl | 'v',
+                MenuFlag::Normal,
+                move |_| {
+                    let into_remote = match remote_browser_paste.lock() {
+                        Ok(browser) => browser.contains_focus(),
+                        Err(_) => false,
+                    };
+                    if !into_remote && !local_browser_paste.contains_focus() {
+                        return;
+                    }
+                    
+                    let (paths, from_remote) = match copied_paste.lock().unwrap().as_ref() {
+                        Some(copied) => (copied.paths.clone(), copied.from_remote),
+                        None => return,
+                    };
+                    if from_remote == into_remote {
+                        dialogs::message_dialog("Paste", "Paste into the other pane to copy files between this computer and the Pi.");
+                        return;
+                    }
+                    
+                    let skip_identical = config_paste.lock().unwrap().skip_identical_transfers;
+                    queue_for_other_pane(&transfer_queue_paste, &local_browser_paste, &remote_browser_paste, paths, into_remote, skip_identical);
+                },
+            );
+            
+            // Images on the system clipboard: paste into the preview for a
+            // quick upload, or copy the previewed image out
+            let preview_panel_paste = preview_panel.clone();
+            let remote_browser_paste = remote_browser.clone();
+            let transfer_queue_paste = transfer_queue.clone();
+            let mut tabs_paste = tabs.clone();
+            let preview_tab_paste = preview_tab.clone();
+            menu.add(
+                "&Edit/Paste &Image from Clipboard\t",
+                Shortcut::Ctrl | Shortcut::Shift | 'v',
+                MenuFlag::Normal,
+                move |_| {
+                    let pasted = match preview_panel_paste.lock() {
+                        Ok(mut panel) => panel.paste_clipboard_image(),
+                        Err(_) => return,
+                    };
+                    let path = match pasted {
+                        Ok(path) => path,
+                        Err(e) => {
+                            dialogs::error_dialog("Paste image failed", &e);
+                            return;
+                        }
+                    };
+                    let _ = tabs_paste.set_value(&preview_tab_paste);
+                    
+                    let remote = remote_browser_paste.lock().ok().and_then(|browser| {
+                        browser.get_transfer_method().map(|method| (method, browser.get_current_directory()))
+                    });
+                    let Some((method, remote_dir)) = remote else { return };
+                    let Some(name) = path.file_name() else { return };
+                    let question = format!("Upload the pasted image to {}?", remote_dir.display());
+                    if dialogs::choice_dialog("Paste Image", &question, &["Upload", "Just Preview"]) == 0 {
+                        transfer_queue_paste.enqueue(QueuedTransfer {
+                            method,
+                            destination: remote_dir.join(name),
+                            source: path,
+                            upload: true,
+                        });
+                    }
+                },
+            );
+            
+            let preview_panel_copy = preview_panel.clone();
+            menu.add(
+                "&Edit/Copy Previewed I&mage\t",
+                Shortcut::Ctrl | Shortcut::Shift | 'c',
+                MenuFlag::Normal,
+                move |_| {
+                    let copied = match preview_panel_copy.lock() {
+                        Ok(panel) => panel.copy_image_to_clipboard(),
+                        Err(_) => return,
+                    };
+                    match copied {
+                        Ok(()) => notifications::notify(ToastKind::Info, "Copied", "Image copied to the clipboard"),
+                        Err(e) => dialogs::error_dialog("Copy image failed", &e),
+                    }
+                },
+            );
+            
+            // Keyboard actions on the focused pane, on keys from the settings
+            let local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            menu.add(
+                "&Edit/&Refresh\t",
+                shortcuts.refresh,
+                MenuFlag::Normal,
+                move |_| {
+                    focused_pane(&local_browser_keys, &remote_browser_keys).refresh();
+                },
+            );
+            
+            let local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            let config_transfer = config.clone();
+            menu.add(
+                "&Edit/&Transfer to Other Pane\t",
+                shortcuts.transfer,
+                MenuFlag::Normal,
+                move |_| {
+                    let from_remote = remote_has_focus(&remote_browser_keys);
+                    let selected = focused_pane(&local_browser_keys, &remote_browser_keys).get_selected_entries();
+                    if selected.is_empty() {
+                        return;
+                    }
+                    let files: Vec<PathBuf> = selected.iter().filter(|(_, is_dir)| !is_dir).map(|(path, _)| path.clone()).collect();
+                    if files.is_empty() {
+                        dialogs::message_dialog("Transfer", "Only files can be copied between the panes.");
+                        return;
+                    }
+                    let skip_identical = config_transfer.lock().unwrap().skip_identical_transfers;
+                    queue_for_other_pane(&transfer_queue, &local_browser_keys, &remote_browser_keys, files, !from_remote, skip_identical);
+                },
+            );
+            
+            let skip_flag = if config.lock().unwrap().skip_identical_transfers { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            let config_skip = config.clone();
+            menu.add(
+                "&Edit/Skip Files the Other Pane &Already Has\t",
+                Shortcut::None,
+                skip_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_skip.lock().unwrap().skip_identical_transfers = enabled;
+                    log::info!("Skipping identical transfers {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+            
+            let double_click_flag = if config.lock().unwrap().interaction.double_click_to_open { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            let config_double_click = config.clone();
+            menu.add(
+                "&Edit/Open with &Double Click\t",
+                Shortcut::None,
+                double_click_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_double_click.lock().unwrap().interaction.double_click_to_open = enabled;
+                    log::info!("Double-click opening {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+            
+            let hover_flag = if config.lock().unwrap().interaction.hover_preview { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            let config_hover = config.clone();
+            menu.add(
+                "&Edit/Preview Images on &Hover\t",
+                Shortcut::None,
+                hover_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_hover.lock().unwrap().interaction.hover_preview = enabled;
+                    if !enabled {
+                        hover_preview::hide();
+                    }
+                    log::info!("Hover previews {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+            
+            let local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            menu.add(
+                "&Edit/Compress and Transfer to Other Pane...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let from_remote = remote_has_focus(&remote_browser_keys);
+                    let selected = focused_pane(&local_browser_keys, &remote_browser_keys).get_selected_entries();
+                    if selected.is_empty() {
+                        dialogs::message_dialog("Compress and Transfer", "Select the files and folders to copy first.");
+                        return;
+                    }
+                    compress_for_other_pane(&local_browser_keys, &remote_browser_keys, selected, !from_remote);
+                },
+            );
+            
+            let local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            menu.add(
+                "&Edit/Re&name...\t",
+                shortcuts.rename,
+                MenuFlag::Normal,
+                move |_| {
+                    focused_pane(&local_browser_keys, &remote_browser_keys).rename_selected();
+                },
+            );
+            
+            let local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            menu.add(
+                "&Edit/&Delete...\t",
+                shortcuts.delete,
+                MenuFlag::Normal,
+                move |_| {
+                    focused_pane(&local_browser_keys, &remote_browser_keys).delete_selected();
+                },
+            );
+            
+            let mut local_browser_keys = local_browser.clone();
+            let remote_browser_keys = remote_browser.clone();
+            menu.add(
+                "&Edit/S&witch Pane\t",
+                shortcuts.switch_pane,
+                MenuFlag::Normal,
+                move |_| {
+                    if remote_has_focus(&remote_browser_keys) {
+                        local_browser_keys.focus_list();
+                    } else if let Ok(mut browser) = remote_browser_keys.lock() {
+                        browser.focus_list();
+                    }
+                },
+            );
+            
+            // Connection menu
+            let config_clone1 = config.clone();
+            let remote_browser_clone1 = remote_browser.clone();
+            let events_connect = events.clone();
+            let mut device_selector_connect = device_selector.clone();
+
+            menu.add(
+                "&Connection/&Connect to Raspberry Pi...\t",
+                shortcuts.quick_connect,
+                MenuFlag::Normal,
+                move |_| {
+                    // Show connection dialog without locking anything first
+                    if let Some(host) = dialogs::connection_dialog(config_clone1.clone()) {
+                        // Now we have a host, update config
+                        {
+                            let mut config = config_clone1.lock().unwrap();
+                            
+                            // Check if host already exists
+                            if let Some(pos) = config.hosts.iter().position(|h| h.name == host.name) {
+                                config.hosts[pos] = host.clone();
+                                config.last_used_host_index = pos;
+                            } else {
+                                config.hosts.push(host.clone());
+                                config.last_used_host_index = config.hosts.len() - 1;
+                            }
+                            
+                            // Save config
+                            if let Err(e) = config.save() {
+                                dialogs::error_dialog("Failed to save settings", &e);
+                            }
+                        }
+                        
+                        Self::connect_to_host(&host, &remote_browser_clone1, &mut device_selector_connect, &events_connect);
+                    }
+                },
+            );
+
+            // Power up a saved Pi that has a MAC address, then connect
+            let config_wake = config.clone();
+            let remote_browser_wake = remote_browser.clone();
+            let device_selector_wake = device_selector.clone();
+            let events_wake = events.clone();
+            menu.add(
+                "&Connection/&Wake Device...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let hosts: Vec<Host> = config_wake
+                        .lock()
+                        .unwrap()
+                        .hosts
+                        .iter()
+                        .filter(|host| host.mac_address.is_some())
+                        .cloned()
+                        .collect();
+                    if hosts.is_empty() {
+                        dialogs::message_dialog(
+                            "Wake Device",
+                            "No saved host has a MAC address. Add one in Connection Settings."
+                        );
+                        return;
+                    }
+                    
+                    let labels: Vec<String> = hosts
+                        .iter()
+                        .map(|host| format!("{} ({})", host.name, host.mac_address.as_deref().unwrap_or("")))
+                        .collect();
+                    if let Some(index) = dialogs::select_dialog("Wake Device", "Pi to wake and connect to:", &labels) {
+                        Self::wake_and_connect(&hosts[index], &remote_browser_wake, &device_selector_wake, &events_wake);
+                    }
+                },
+            );
+
+            let mut device_selector_disconnect = device_selector.clone();
+            menu.add(
+                "&Connection/&Disconnect\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    device_selector_disconnect.disconnect_active();
+                },
+            );
+            
+            menu.add(
+                "&Connection/Check Connection / Reconn&ect\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    health_monitor.check_now();
+                },
+            );
+            
+            // Add a new menu item to directly show Raspberry Pi files
+            let config_clone2 = config.clone();
+            let remote_browser_clone2 = remote_browser.clone();
+
+            menu.add(
+                "&Connection/&Show Raspberry Pi Files\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    log::debug!("Show Raspberry Pi Files clicked");
+                    
+                    // Ask for password first since we need it for the connection
+                    let password = dialogs::password_dialog("SSH Password", "Enter password for Raspberry Pi:");
+                    
+                    // First get the saved config to use stored credentials
+                    if let Ok(config) = config_clone2.lock() {
+                        // Find a Raspberry Pi host in saved hosts
                         let host = config.hosts.iter().find(|h| 
                             h.hostname.contains("raspberry") || 
                             h.hostname.contains("pi") || 
@@ -355,16 +1531,17 @@ pub mod main_window {
                             h.name.contains("Pi")
                         );
                         
-                        let (hostname, username, port) = if let Some(pi_host) = host {
-                            println!("Using saved Raspberry Pi connection: {}", pi_host.name);
+                        let (hostname, username, port, remote_home) = if let Some(pi_host) = host {
+                            log::info!("Using saved Raspberry Pi connection: {}", pi_host.name);
                             (
                                 pi_host.hostname.clone(),
                                 pi_host.username.clone(),
-                                pi_host.port
+                                pi_host.port,
+                                pi_host.start_directory()
                             )
                         } else {
-                            println!("No saved Raspberry Pi host found, using defaults");
-                            ("raspberrypi.local".to_string(), "pi".to_string(), 22)
+                            log::info!("No saved Raspberry Pi host found, using defaults");
+                            ("raspberrypi.local".to_string(), "pi".to_string(), 22, PathBuf::from("/home/pi"))
                         };
                         
                         if let Ok(mut browser) = remote_browser_clone2.lock() {
@@ -385,15 +1562,13 @@ pub mod main_window {
                             // Set the password directly in the transfer method
                             if let Some(pwd) = &password {
                                 transfer_method.set_password(pwd);
-                                println!("Set password for SSH connection");
+                                log::info!("Set password for SSH connection");
                                 
                                 // Also store it in the browser for later use
                                 browser.current_password = password.clone();
                             }
                             
-                            let remote_home = PathBuf::from(format!("/home/{}", username));
-                            
-                            println!("Setting up direct connection to Raspberry Pi at {}", remote_home.display());
+                            log::info!("Setting up direct connection to Raspberry Pi at {}", remote_home.display());
                             
                             // Store credentials
                             browser.current_hostname = Some(hostname.clone());
@@ -411,104 +1586,861 @@ pub mod main_window {
                             // Print status again
                             browser.print_debug_status();
                             
-                            println!("DEBUG: Show Raspberry Pi Files complete");
+                            log::debug!("Show Raspberry Pi Files complete");
+                        } else {
+                            log::error!("Could not lock remote browser");
+                        }
+                    } else {
+                        log::error!("Could not get config");
+                    }
+                },
+            );
+
+            // Find files anywhere below a directory on the Pi
+            let remote_browser_search = remote_browser.clone();
+            let events_search = events.clone();
+            menu.add(
+                "&Connection/&Search on Pi...\t",
+                Shortcut::Ctrl | Shortcut::Shift | 'f',
+                MenuFlag::Normal,
+                move |_| {
+                    remote_search_dialog(remote_browser_search.clone(), events_search.clone());
+                },
+            );
+            
+            // Follow a log file on the Pi in the Preview tab
+            let remote_browser_tail = remote_browser.clone();
+            let preview_panel_tail = preview_panel.clone();
+            let mut tabs_tail = tabs.clone();
+            menu.add(
+                "&Connection/&Tail Remote File...\t",
+                Shortcut::Ctrl | 't',
+                MenuFlag::Normal,
+                move |_| {
+                    // Default to the file selected in the remote browser
+                    let selected = remote_browser_tail.lock().ok()
+                        .and_then(|browser| browser.get_selected_entry())
+                        .filter(|(_, is_dir)| !is_dir)
+                        .map(|(path, _)| path.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "/var/log/syslog".to_string());
+                    
+                    let remote_path = match fltk::dialog::input_default("Remote file to follow:", &selected) {
+                        Some(path) if !path.trim().is_empty() => path.trim().to_string(),
+                        _ => return,
+                    };
+                    
+                    let command = format!("tail -n 200 -F {}", shell_quote(&remote_path));
+                    
+                    let spawned = match remote_browser_tail.lock() {
+                        Ok(browser) => browser.with_transfer_method(|method| method.spawn_remote(&command)),
+                        Err(_) => None,
+                    };
+                    
+                    match spawned {
+                        Some(Ok(child)) => {
+                            if let Ok(mut panel) = preview_panel_tail.lock() {
+                                panel.follow_command(child, &remote_path);
+                                let _ = tabs_tail.set_value(&preview_tab);
+                            }
+                        },
+                        Some(Err(e)) => {
+                            dialogs::error_dialog(&trf("Failed to tail {}", &[&remote_path]), &e.into());
+                        },
+                        None => {
+                            dialogs::error_dialog("Cannot follow a remote file", &AppError::NotConnected);
+                        }
+                    }
+                },
+            );
+            
+            // Restore or purge what deletes on the Pi moved to its trash
+            let remote_browser_trash = remote_browser.clone();
+            menu.add(
+                "&Connection/Remote T&rash...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let method = remote_browser_trash.lock().ok().and_then(|browser| browser.get_transfer_method());
+                    let Some(method) = method else {
+                        dialogs::error_dialog("Cannot open the Pi's trash", &AppError::NotConnected);
+                        return;
+                    };
+                    let remote_browser_refresh = remote_browser_trash.clone();
+                    show_remote_trash(method, move || {
+                        if let Ok(mut browser) = remote_browser_refresh.lock() {
+                            browser.refresh();
+                        }
+                    });
+                },
+            );
+            
+            // Upload the agent and install rsync and ImageMagick on the Pi
+            let remote_browser_tools = remote_browser.clone();
+            let processing_queue_tools = processing_queue.clone();
+            menu.add(
+                "&Connection/Install Companion Too&ls on Device...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let method = remote_browser_tools.lock().ok().and_then(|browser| browser.get_transfer_method());
+                    let Some(method) = method else {
+                        dialogs::error_dialog("Cannot install companion tools", &AppError::NotConnected);
+                        return;
+                    };
+                    
+                    let remote_browser_done = remote_browser_tools.clone();
+                    install_companion_tools(method, processing_queue_tools.clone(), move || {
+                        if let Ok(mut browser) = remote_browser_done.lock() {
+                            browser.refresh();
+                        }
+                    });
+                },
+            );
+            
+            // Checksum the local pane's folder against the remote pane's
+            let local_browser_audit = local_browser.clone();
+            let remote_browser_audit = remote_browser.clone();
+            let processing_queue_audit = processing_queue.clone();
+            menu.add(
+                "&Connection/Integrity &Audit...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let remote = remote_browser_audit.lock().ok().and_then(|browser| {
+                        browser.get_transfer_method().map(|method| (browser.get_current_directory(), method))
+                    });
+                    let Some((remote_dir, method)) = remote else {
+                        dialogs::error_dialog("Cannot audit", &AppError::NotConnected);
+                        return;
+                    };
+                    let local_dir = local_browser_audit.get_current_directory();
+                    
+                    let Some(local_dir) = dialogs::input_dialog(
+                        "Integrity Audit",
+                        "Local folder:",
+                        &local_dir.to_string_lossy()
+                    ) else { return };
+                    let Some(remote_dir) = dialogs::input_dialog(
+                        "Integrity Audit",
+                        "Its copy on the Pi:",
+                        &remote_dir.to_string_lossy()
+                    ) else { return };
+                    let local_dir = PathBuf::from(local_dir.trim());
+                    if !local_dir.is_dir() {
+                        dialogs::message_dialog("Error", &trf("{} is not a folder.", &[&local_dir.display()]));
+                        return;
+                    }
+                    
+                    audit_folder_pair(
+                        local_dir,
+                        PathBuf::from(remote_dir.trim()),
+                        PathFilter::default(),
+                        method,
+                        processing_queue_audit.clone()
+                    );
+                },
+            );
+            
+            let trash_flag = if config.lock().unwrap().remote_trash { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            let config_trash = config.clone();
+            menu.add(
+                "&Connection/Move Pi Deletions to the Pi's Tras&h\t",
+                Shortcut::None,
+                trash_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_trash.lock().unwrap().remote_trash = enabled;
+                    log::info!("Remote trash {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+            
+            // Add a special debug menu item to force remote refresh
+            let remote_browser_clone3 = remote_browser.clone();
+            menu.add(
+                "&Connection/&Force Remote Refresh\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    log::debug!("Force Remote Refresh menu clicked");
+                    
+                    if let Ok(mut browser) = remote_browser_clone3.lock() {
+                        // Check if we're in remote mode
+                        log::debug!("Remote mode: {}", browser.is_remote());
+                        log::debug!("Has transfer method: {}", browser.has_transfer_method());
+                        
+                        if browser.is_remote() && browser.has_transfer_method() {
+                            log::debug!("Remote mode confirmed, refreshing browser");
+                            browser.refresh();
+                        } else if browser.is_remote() && !browser.has_transfer_method() {
+                            log::debug!("In remote mode but no transfer method! Forcing remote mode...");
+                            browser.force_remote_mode(); 
                         } else {
-                            println!("ERROR: Could not lock remote browser");
+                            log::debug!("Not in remote mode, forcing it");
+                            browser.force_remote_mode();
                         }
+                        
+                        // Explicitly refresh and force UI update
+                        app::flush();
+                        app::awake();
+                        app::redraw();
+                        
+                        // Print debug status
+                        browser.print_debug_status();
+                        
+                        log::debug!("Remote refresh complete");
+                    } else {
+                        log::error!("Could not lock remote browser");
+                    }
+                },
+            );
+
+            // Add a debug info menu item
+            let remote_browser_clone4 = remote_browser.clone();
+            menu.add(
+                "&Connection/&Show Debug Info\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    if let Ok(browser) = remote_browser_clone4.lock() {
+                        browser.print_debug_status();
+                        dialogs::message_dialog(
+                            "Browser Status", 
+                            &format!(
+                                "Remote mode: {}\nHas transfer: {}", 
+                                browser.is_remote(),
+                                browser.has_transfer_method()
+                                // Removed private field access to current_dir
+                            )
+                        );
                     } else {
-                        println!("ERROR: Could not get config");
+                        log::error!("Could not lock remote browser");
+                    }
+                },
+            );
+            
+            // Copy the selected remote file to another connected device via this computer
+            let remote_browser_copy = remote_browser.clone();
+            let device_selector_copy = device_selector.clone();
+            menu.add(
+                "&Connection/Cop&y to Device...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let source_path = match remote_browser_copy.lock().ok()
+                        .and_then(|browser| browser.get_selected_entry())
+                        .filter(|(_, is_dir)| !is_dir)
+                    {
+                        Some((path, _)) => path,
+                        None => {
+                            dialogs::message_dialog("Copy to Device", "Select a file in the Raspberry Pi pane first.");
+                            return;
+                        }
+                    };
+                    
+                    let (source, targets) = {
+                        let sessions = device_selector_copy.sessions();
+                        let sessions = sessions.lock().unwrap();
+                        let active_id = sessions.active_id();
+                        
+                        let source = sessions.active().map(|s| s.method.clone_box());
+                        let targets: Vec<(String, Box<dyn TransferMethod>, PathBuf)> = sessions.sessions()
+                            .iter()
+                            .filter(|s| Some(s.id) != active_id)
+                            .map(|s| (s.label(), s.method.clone_box(), s.current_dir.clone()))
+                            .collect();
+                        
+                        (source, targets)
+                    };
+                    
+                    let source = match source {
+                        Some(source) if !targets.is_empty() => source,
+                        _ => {
+                            dialogs::message_dialog("Copy to Device", "Connect to a second device to copy between devices.");
+                            return;
+                        }
+                    };
+                    
+                    let file_name = match source_path.file_name() {
+                        Some(name) => name.to_os_string(),
+                        None => return,
+                    };
+                    
+                    let labels: Vec<String> = targets.iter().map(|(label, _, _)| label.clone()).collect();
+                    let index = match dialogs::select_dialog(
+                        "Copy to Device",
+                        &format!("Copy {} to:", file_name.to_string_lossy()),
+                        &labels
+                    ) {
+                        Some(index) => index,
+                        None => return,
+                    };
+                    
+                    let (target_label, target, target_dir) = match targets.into_iter().nth(index) {
+                        Some(target) => target,
+                        None => return,
+                    };
+                    
+                    // Relay through a local temporary file
+                    let local_copy = env::temp_dir().join(format!("pi_copy_{}", file_name.to_string_lossy()));
+                    let target_path = target_dir.join(&file_name);
+                    
+                    let result: Arc<Mutex<Option<AppResult<()>>>> = Arc::new(Mutex::new(None));
+                    let result_worker = result.clone();
+                    
+                    thread::spawn(move || {
+                        let outcome = source.download_file(&source_path, &local_copy)
+                            .and_then(|_| target.upload_file(&local_copy, &target_path))
+                            .map_err(AppError::from);
+                        let _ = fs::remove_file(&local_copy);
+                        
+                        *result_worker.lock().unwrap() = Some(outcome);
+                        app::awake();
+                    });
+                    
+                    app::add_timeout3(0.2, move |handle| {
+                        let finished = result.lock().unwrap().take();
+                        
+                        match finished {
+                            Some(Ok(())) => notifications::notify(
+                                ToastKind::Success,
+                                "Copy to Device",
+                                &trf("Copied to {}", &[&target_label])
+                            ),
+                            Some(Err(e)) => dialogs::error_dialog(&trf("Copy to {} failed", &[&target_label]), &e),
+                            None => app::repeat_timeout3(0.2, handle),
+                        }
+                    });
+                },
+            );
+            
+            // Offer the hosts of ~/.ssh/config that aren't saved yet
+            let config_import = config.clone();
+            menu.add(
+                "&Connection/&Import Hosts from SSH Config...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let path = match ssh_import::default_ssh_config_path() {
+                        Some(path) if path.exists() => path,
+                        _ => {
+                            dialogs::message_dialog("Import Hosts", "No ~/.ssh/config file was found.");
+                            return;
+                        }
+                    };
+                    
+                    let imported = match ssh_import::read_ssh_config(&path) {
+                        Ok(hosts) => hosts,
+                        Err(e) => {
+                            dialogs::error_dialog("Import failed", &e);
+                            return;
+                        }
+                    };
+                    
+                    let candidates = {
+                        let config = config_import.lock().unwrap();
+                        ssh_import::new_hosts(imported, &config.hosts)
+                    };
+                    if candidates.is_empty() {
+                        dialogs::message_dialog("Import Hosts", "Every host in ~/.ssh/config is already saved.");
+                        return;
+                    }
+                    
+                    let labels: Vec<String> = candidates
+                        .iter()
+                        .map(|host| match &host.proxy_jump {
+                            Some(jump) => format!("{} ({}@{}:{} via {})", host.name, host.username, host.hostname, host.port, jump),
+                            None => format!("{} ({}@{}:{})", host.name, host.username, host.hostname, host.port),
+                        })
+                        .collect();
+                    
+                    let chosen = match dialogs::multi_select_dialog(
+                        "Import Hosts",
+                        &format!("Hosts in {} to add:", path.display()),
+                        &labels
+                    ) {
+                        Some(chosen) if !chosen.is_empty() => chosen,
+                        _ => return,
+                    };
+                    
+                    let mut config = config_import.lock().unwrap();
+                    let count = chosen.len();
+                    config.hosts.extend(chosen.into_iter().map(|i| candidates[i].clone()));
+                    match config.save() {
+                        Ok(()) => {
+                            log::info!("Imported {} host(s) from {}", count, path.display());
+                            notifications::notify(ToastKind::Success, "Hosts imported", &trf("Added {} host(s)", &[&count]));
+                        },
+                        Err(e) => dialogs::error_dialog("Failed to save settings", &e),
+                    }
+                },
+            );
+            
+            // Device groups
+            let config_groups = config.clone();
+            menu.add(
+                "&Connection/Manage &Groups...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    dialogs::group_dialog(config_groups.clone());
+                },
+            );
+            
+            // Send a file, folder or command to every device in a group
+            let config_broadcast = config.clone();
+            let device_selector_broadcast = device_selector.clone();
+            menu.add(
+                "&Connection/&Broadcast to Group...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let group_names: Vec<String> = config_broadcast.lock().unwrap()
+                        .groups
+                        .iter()
+                        .map(|g| g.name.clone())
+                        .collect();
+                    
+                    if group_names.is_empty() {
+                        dialogs::message_dialog("Broadcast", "Create a group first with Connection > Manage Groups.");
+                        return;
+                    }
+                    
+                    let group_name = match dialogs::select_dialog("Broadcast", "Send to group:", &group_names) {
+                        Some(index) => group_names[index].clone(),
+                        None => return,
+                    };
+                    
+                    let actions = vec![
+                        "Upload file...".to_string(),
+                        "Upload folder...".to_string(),
+                        "Run command...".to_string(),
+                    ];
+                    let remote_dir_prompt = "Remote directory (relative paths start in each device's home):";
+                    
+                    let job = match dialogs::select_dialog("Broadcast", &format!("Action for '{}':", group_name), &actions) {
+                        Some(0) => {
+                            let local_path = match dialogs::open_file_dialog("File to Upload", "") {
+                                Some(path) => path,
+                                None => return,
+                            };
+                            match fltk::dialog::input_default(remote_dir_prompt, ".") {
+                                Some(dir) => BroadcastJob::UploadFile { local_path, remote_dir: PathBuf::from(dir.trim()) },
+                                None => return,
+                            }
+                        },
+                        Some(1) => {
+                            let local_dir = match dialogs::open_directory_dialog("Folder to Upload") {
+                                Some(path) => path,
+                                None => return,
+                            };
+                            match fltk::dialog::input_default(remote_dir_prompt, ".") {
+                                Some(dir) => BroadcastJob::UploadTree { local_dir, remote_dir: PathBuf::from(dir.trim()) },
+                                None => return,
+                            }
+                        },
+                        Some(_) => {
+                            match fltk::dialog::input_default("Command to run on every device:", "") {
+                                Some(command) if !command.trim().is_empty() => BroadcastJob::Exec { command: command.trim().to_string() },
+                                _ => return,
+                            }
+                        },
+                        None => return,
+                    };
+                    
+                    // Reuse open connections and only ask for passwords that aren't known yet
+                    let hosts = config_broadcast.lock().unwrap().group_hosts(&group_name);
+                    let mut devices: Vec<(String, Box<dyn TransferMethod>)> = Vec::new();
+                    let mut skipped = Vec::new();
+                    
+                    for host in hosts {
+                        let existing = {
+                            let sessions = device_selector_broadcast.sessions();
+                            let sessions = sessions.lock().unwrap();
+                            sessions.sessions()
+                                .iter()
+                                .find(|s| s.hostname == host.hostname && s.username == host.username)
+                                .map(|s| s.method.clone_box())
+                        };
+                        
+                        let method = match existing {
+                            Some(method) => method,
+                            None => {
+                                let factory = factory_for_reachable_host(&host);
+                                let mut method = factory.create_method();
+                                
+                                if !host.use_key_auth {
+                                    match dialogs::password_dialog(
+                                        "SSH Password",
+                                        &format!("Enter password for {}@{}:", host.username, host.hostname)
+                                    ) {
+                                        Some(password) => method.set_password(&password),
+                                        None => {
+                                            skipped.push(host.name.clone());
+                                            continue;
+                                        }
+                                    }
+                                }
+                                
+                                method
+                            }
+                        };
+                        
+                        devices.push((host.name.clone(), method));
+                    }
+                    
+                    let result: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+                    let result_worker = result.clone();
+                    
+                    thread::spawn(move || {
+                        let outcomes = run_broadcast(devices, &job);
+                        let mut summary = summarize(&job, &outcomes);
+                        for name in skipped {
+                            summary.push_str(&format!("[skipped] {}: no password entered\n", name));
+                        }
+                        
+                        *result_worker.lock().unwrap() = Some(summary);
+                        app::awake();
+                    });
+                    
+                    app::add_timeout3(0.2, move |handle| {
+                        let finished = result.lock().unwrap().take();
+                        
+                        match finished {
+                            Some(summary) => dialogs::report_dialog(&format!("Broadcast to {}", group_name), &summary),
+                            None => app::repeat_timeout3(0.2, handle),
+                        }
+                    });
+                },
+            );
+            
+            // Reboot / shut down the connected Pi
+            for (label, action) in [
+                ("&Connection/Re&boot Pi...\t", PowerAction::Reboot),
+                ("&Connection/Shut &Down Pi...\t", PowerAction::Shutdown),
+            ] {
+                let remote_browser_power = remote_browser.clone();
+                menu.add(
+                    label,
+                    Shortcut::None,
+                    MenuFlag::Normal,
+                    move |_| {
+                        let (method, hostname) = match remote_browser_power.lock() {
+                            Ok(browser) => (browser.get_transfer_method(), browser.current_hostname.clone()),
+                            Err(_) => (None, None),
+                        };
+                        
+                        let (method, hostname) = match (method, hostname) {
+                            (Some(method), Some(hostname)) => (method, hostname),
+                            _ => {
+                                dialogs::error_dialog(&trf("Cannot {} the Pi", &[&tr(action.verb())]), &AppError::NotConnected);
+                                return;
+                            }
+                        };
+                        
+                        // Make the user type the hostname so the wrong device isn't taken down
+                        let confirmed = dialogs::typed_confirm_dialog(
+                            "Confirm",
+                            &trf("This will {} {}.", &[&tr(action.verb()), &hostname]),
+                            &hostname
+                        );
+                        if !confirmed {
+                            return;
+                        }
+                        
+                        log::info!("Sending {} to {}", action.verb(), hostname);
+                        run_in_background(
+                            move || method.exec(action.command()),
+                            move |result| match result {
+                                Ok(output) if action.accepted(output.exit_code, &output.stderr) => {
+                                    notifications::notify(
+                                        ToastKind::Info,
+                                        "Power",
+                                        &trf("{} will {} now.", &[&hostname, &tr(action.verb())])
+                                    );
+                                },
+                                Ok(output) => {
+                                    let reason = output.stdout.lines().chain(output.stderr.lines())
+                                        .last()
+                                        .unwrap_or("unknown error")
+                                        .to_string();
+                                    dialogs::message_dialog(
+                                        "Error",
+                                        &trf("Failed to {} {}:\n{}", &[&tr(action.verb()), &hostname, &reason])
+                                    );
+                                },
+                                Err(e) => {
+                                    dialogs::error_dialog(
+                                        &trf("Failed to {} {}", &[&tr(action.verb()), &hostname]),
+                                        &e.into()
+                                    );
+                                }
+                            }
+                        );
+                    },
+                );
+            }
+            
+            // Processing menu - Fix: Clone image_service for each closure
+            menu.add(
+                "&Processing/&Apply Operations\t",
+                Shortcut::Ctrl | 'a',
+                MenuFlag::Normal,
+                move |_| operations_panel.apply(),
+            );
+            
+            let image_service_clone2 = image_service.clone();
+            menu.add(
+                "&Processing/&Reset Operations\t",
+                Shortcut::Ctrl | 'r',
+                MenuFlag::Normal,
+                move |_| {
+                    // Reset all operations
+                    image_service_clone2.lock().unwrap().clear_operations();
+                    log::info!("Reset all operations");
+                },
+            );
+            
+            let config_naming = config.clone();
+            menu.add(
+                "&Processing/Output &Naming...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let current = config_naming.lock().unwrap().output_name_template.clone();
+                    if let Some(template) = dialogs::output_name_dialog(&current) {
+                        log::info!("Processed images are now named {}", template);
+                        config_naming.lock().unwrap().output_name_template = template;
+                    }
+                },
+            );
+            
+            let config_auto = config.clone();
+            let auto_flag = if config.lock().unwrap().post_download.enabled { MenuFlag::Toggle | MenuFlag::Value } else { MenuFlag::Toggle };
+            menu.add(
+                "&Processing/Process &Downloads Automatically\t",
+                Shortcut::None,
+                auto_flag,
+                move |menu| {
+                    let enabled = menu.mvalue().map_or(false, |item| item.value());
+                    config_auto.lock().unwrap().post_download.enabled = enabled;
+                    log::info!("Post-download processing {}", if enabled { "enabled" } else { "disabled" });
+                },
+            );
+            
+            let config_auto_settings = config.clone();
+            menu.add(
+                "&Processing/Download Processing &Settings...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let current = config_auto_settings.lock().unwrap().post_download.clone();
+                    if let Some(settings) = post_download::settings_dialog(&current) {
+                        config_auto_settings.lock().unwrap().post_download = settings;
                     }
                 },
             );
-
-            // Add a special debug menu item to force remote refresh
-            let remote_browser_clone3 = remote_browser.clone();
+            
+            // Run the same operations on the Pi itself, e.g. from cron
+            let image_service_script = image_service.clone();
+            let remote_browser_script = remote_browser.clone();
             menu.add(
-                "&Connection/&Force Remote Refresh\t",
+                "&Processing/&Export Operations as Script...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    let remote = remote_browser_script.lock().ok().and_then(|browser| {
+                        browser.get_transfer_method().map(|method| (method, browser.get_current_directory()))
+                    });
+                    export_pipeline_script(image_service_script.clone(), remote);
+                },
+            );
+            
+            // Send a preset's results on to a Pi or Dropbox
+            let config_destinations = config.clone();
+            menu.add(
+                "&Processing/&Preset Destinations...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    preset_destinations_dialog(config_destinations.clone());
+                },
+            );
+            
+            let config_dropbox = config.clone();
+            menu.add(
+                "&Processing/Link Drop&box Account...\t",
+                Shortcut::None,
+                MenuFlag::Normal,
+                move |_| {
+                    link_dropbox_account(config_dropbox.clone());
+                },
+            );
+            
+            // Group near-duplicate frames in a local or Pi folder
+            let remote_browser_similar = remote_browser.clone();
+            let config_similar = config.clone();
+            menu.add(
+                "&Processing/Find &Similar Images...\t",
                 Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    println!("DEBUG: Force Remote Refresh menu clicked");
+                    let place = dialogs::choice_dialog(
+                        "Find Similar Images",
+                        "Look for near-duplicates in a folder on:",
+                        &["This Computer", "The Pi", "Cancel"]
+                    );
+                    let (folder, method) = match place {
+                        0 => match dialogs::open_directory_dialog("Folder to Check") {
+                            Some(folder) => (folder, None),
+                            None => return,
+                        },
+                        1 => {
+                            let remote = remote_browser_similar.lock().ok().and_then(|browser| {
+                                browser.get_transfer_method().map(|method| (browser.get_current_directory(), method))
+                            });
+                            match remote {
+                                Some((folder, method)) => (folder, Some(method)),
+                                None => {
+                                    dialogs::error_dialog("Cannot check the Pi", &AppError::NotConnected);
+                                    return;
+                                }
+                            }
+                        },
+                        _ => return,
+                    };
                     
-                    if let Ok(mut browser) = remote_browser_clone3.lock() {
-                        // Check if we're in remote mode
-                        println!("DEBUG: Remote mode: {}", browser.is_remote());
-                        println!("DEBUG: Has transfer method: {}", browser.has_transfer_method());
-                        
-                        if browser.is_remote() && browser.has_transfer_method() {
-                            println!("DEBUG: Remote mode confirmed, refreshing browser");
+                    let Some(threshold) = dialogs::input_dialog(
+                        "Find Similar Images",
+                        "Maximum difference (0-64; lower is stricter):",
+                        &DEFAULT_SIMILARITY_THRESHOLD.to_string()
+                    ) else { return };
+                    let Some(threshold) = threshold.trim().parse::<u32>().ok().filter(|t| *t <= 64) else {
+                        dialogs::message_dialog("Error", "The difference must be between 0 and 64.");
+                        return;
+                    };
+                    
+                    let remote_trash = config_similar.lock().unwrap().remote_trash;
+                    let remote_browser_refresh = remote_browser_similar.clone();
+                    find_similar_images(folder, method, threshold, remote_trash, move || {
+                        if let Ok(mut browser) = remote_browser_refresh.lock() {
                             browser.refresh();
-                        } else if browser.is_remote() && !browser.has_transfer_method() {
-                            println!("DEBUG: In remote mode but no transfer method! Forcing remote mode...");
-                            browser.force_remote_mode(); 
-                        } else {
-                            println!("DEBUG: Not in remote mode, forcing it");
-                            browser.force_remote_mode();
                         }
-                        
-                        // Explicitly refresh and force UI update
-                        app::flush();
-                        app::awake();
-                        app::redraw();
-                        
-                        // Print debug status
-                        browser.print_debug_status();
-                        
-                        println!("DEBUG: Remote refresh complete");
-                    } else {
-                        println!("ERROR: Could not lock remote browser");
-                    }
+                    });
                 },
             );
 
-            // Add a debug info menu item
-            let remote_browser_clone4 = remote_browser.clone();
+            // Turn a folder of frames, here or on the Pi, into a video
+            let remote_browser_timelapse = remote_browser.clone();
+            let processing_queue_timelapse = processing_queue.clone();
             menu.add(
-                "&Connection/&Show Debug Info\t",
+                "&Processing/Create &Time-lapse Video...\t",
                 Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    if let Ok(browser) = remote_browser_clone4.lock() {
-                        browser.print_debug_status();
-                        dialogs::message_dialog(
-                            "Browser Status", 
-                            &format!(
-                                "Remote mode: {}\nHas transfer: {}", 
-                                browser.is_remote(),
-                                browser.has_transfer_method()
-                                // Removed private field access to current_dir
-                            )
-                        );
-                    } else {
-                        println!("ERROR: Could not lock remote browser");
-                    }
+                    let place = dialogs::choice_dialog(
+                        "Create Time-lapse",
+                        "The frames are in a folder on:",
+                        &["This Computer", "The Pi", "Cancel"]
+                    );
+                    let (folder, method) = match place {
+                        0 => match dialogs::open_directory_dialog("Folder of Frames") {
+                            Some(folder) => (folder, None),
+                            None => return,
+                        },
+                        1 => {
+                            let remote = remote_browser_timelapse.lock().ok().and_then(|browser| {
+                                browser.get_transfer_method().map(|method| (browser.get_current_directory(), method))
+                            });
+                            match remote {
+                                Some((folder, method)) => (folder, Some(method)),
+                                None => {
+                                    dialogs::error_dialog("Cannot use frames on the Pi", &AppError::NotConnected);
+                                    return;
+                                }
+                            }
+                        },
+                        _ => return,
+                    };
+                    create_timelapse(folder, method, processing_queue_timelapse.clone());
                 },
             );
             
-            // Processing menu - Fix: Clone image_service for each closure
-            let image_service_clone1 = image_service.clone();
+            // Animate the images selected in the focused pane
+            let local_browser_animation = local_browser.clone();
+            let remote_browser_animation = remote_browser.clone();
+            let processing_queue_animation = processing_queue.clone();
             menu.add(
-                "&Processing/&Apply Operations\t",
-                Shortcut::Ctrl | 'a',
+                "&Processing/Create &Animation from Selection...\t",
+                Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    // Apply image processing operations
-                    let service_guard = image_service_clone1.lock().unwrap();
-                    let operations = service_guard.get_operations();
-                    println!("Applying {} operations", operations.len());
-                    // Actually apply operations to the current image
+                    let from_remote = remote_has_focus(&remote_browser_animation);
+                    let frames: Vec<PathBuf> = focused_pane(&local_browser_animation, &remote_browser_animation)
+                        .get_selected_entries()
+                        .into_iter()
+                        .filter(|(path, is_dir)| !is_dir && is_image_file(path))
+                        .map(|(path, _)| path)
+                        .collect();
+                    let method = if from_remote {
+                        match remote_browser_animation.lock().ok().and_then(|browser| browser.get_transfer_method()) {
+                            Some(method) => Some(method),
+                            None => {
+                                dialogs::error_dialog("Cannot use frames on the Pi", &AppError::NotConnected);
+                                return;
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    create_animation(frames, method, processing_queue_animation.clone());
                 },
             );
             
-            let image_service_clone2 = image_service.clone();
+            // Rename photos in a local or Pi folder after when they were taken
+            let remote_browser_rename = remote_browser.clone();
+            let local_browser_rename = local_browser.clone();
             menu.add(
-                "&Processing/&Reset Operations\t",
-                Shortcut::Ctrl | 'r',
+                "&Processing/Batch &Rename by Capture Time...\t",
+                Shortcut::None,
                 MenuFlag::Normal,
                 move |_| {
-                    // Reset all operations
-                    image_service_clone2.lock().unwrap().clear_operations();
-                    println!("Reset all operations");
+                    let place = dialogs::choice_dialog(
+                        "Batch Rename",
+                        "Rename the images in a folder on:",
+                        &["This Computer", "The Pi", "Cancel"]
+                    );
+                    let (folder, method) = match place {
+                        0 => match dialogs::open_directory_dialog("Folder to Rename") {
+                            Some(folder) => (folder, None),
+                            None => return,
+                        },
+                        1 => {
+                            let remote = remote_browser_rename.lock().ok().and_then(|browser| {
+                                browser.get_transfer_method().map(|method| (browser.get_current_directory(), method))
+                            });
+                            match remote {
+                                Some((folder, method)) => (folder, Some(method)),
+                                None => {
+                                    dialogs::error_dialog("Cannot rename on the Pi", &AppError::NotConnected);
+                                    return;
+                                }
+                            }
+                        },
+                        _ => return,
+                    };
+
+                    let remote = method.is_some();
+                    let remote_browser_refresh = remote_browser_rename.clone();
+                    let mut local_browser_refresh = local_browser_rename.clone();
+                    batch_rename(folder, method, move || {
+                        if remote {
+                            if let Ok(mut browser) = remote_browser_refresh.lock() {
+                                browser.refresh();
+                            }
+                        } else {
+                            local_browser_refresh.refresh();
+                        }
+                    });
                 },
             );
             
@@ -529,11 +2461,12 @@ pub mod main_window {
         fn setup_callbacks(
             &mut self, 
             mut tabs: Tabs, 
+            preview_tab: Group,
             content_y: i32, 
-            image_view: Arc<Mutex<ImageViewPanel>>
+            image_view: Arc<Mutex<ImageViewPanel>>,
+            preview_panel: Arc<Mutex<PreviewPanel>>
         ) {
             // Clone references for thread safety
-            let local_browser = Arc::new(Mutex::new(self.local_browser.clone()));
             let remote_browser_clone = self.remote_browser_ref.clone();
             let temp_dir = self.temp_dir.clone();
             
@@ -546,16 +2479,16 @@ pub mod main_window {
                 if let Some(tab) = tabs.value() {
                     // The label() method returns a String, not an Option<String>
                     let label = tab.label();
-                    println!("Selected tab: {}", label);
+                    log::info!("Selected tab: {}", label);
                     
                     // Check if the Image Processing tab is selected
-                    if label == "Image Processing" {
-                        println!("Image Processing tab selected");
+                    if source_text(&label) == "Image Processing" {
+                        log::info!("Image Processing tab selected");
                         
                         // Refresh the image view if there's a current image
                         if let Ok(view) = image_view_tab_clone.lock() {
                             if let Some(current_path) = view.get_current_image() {
-                                println!("Refreshing current image: {}", current_path.display());
+                                log::info!("Refreshing current image: {}", current_path.display());
                                 // Force a redraw of the image view
                                 app::redraw();
                             }
@@ -566,130 +2499,383 @@ pub mod main_window {
             
             // Window resize callback
             let mut window_clone = self.window.clone();
+            let mut status_bar_resize = self.status_bar.clone();
+            let mut toolbar_resize = self.connection_toolbar.clone();
             window_clone.resize_callback(move |_, _x, _y, w, h| {
+                toolbar_resize.resize_to_window(w, MENU_BAR_HEIGHT);
                 // Update the tabs size when the window is resized
-                tabs_callback.resize(0, content_y, w, h - content_y);
+                tabs_callback.resize(0, content_y, w, h - content_y - STATUS_BAR_HEIGHT);
+                status_bar_resize.resize_to_window(w, h);
                 app::redraw();
             });
             
-            // Connect the transfer panel with file browsers
-            let temp_dir_clone = temp_dir.clone();
-            self.transfer_panel.set_callback(move |source_is_local, source_path, dest_path| {
-                if source_is_local {
-                    // Upload from local to remote
-                    println!("Upload: {} -> {}", source_path.display(), dest_path.display());
-                    // Refresh remote browser after upload
-                    if let Ok(mut browser) = remote_browser_clone.lock() {
-                        browser.refresh();
-                        
-                        // Force a UI refresh after the refresh operation
-                        app::flush();
-                        app::awake();
-                        app::redraw(); // Add redraw for better UI update
+            // Quick connect and disconnect from the toolbar
+            let config_toolbar = self.config.clone();
+            let remote_browser_toolbar = self.remote_browser_ref.clone();
+            let mut device_selector_toolbar = self.device_selector.clone();
+            let events_toolbar = self.events.clone();
+            self.connection_toolbar.set_connect_callback(move |host| {
+                {
+                    let mut config = config_toolbar.lock().unwrap();
+                    if let Some(pos) = config.hosts.iter().position(|h| h.name == host.name) {
+                        config.last_used_host_index = pos;
                     }
-                } else {
-                    // Download from remote to local
-                    println!("Download: {} -> {}", source_path.display(), dest_path.display());
-                    // Refresh local browser after download
-                    if let Ok(mut browser) = local_browser.lock() {
-                        browser.refresh();
-                        
-                        // Force UI update here too
-                        app::flush();
-                        app::awake();
-                        app::redraw();
+                }
+                Self::connect_to_host(&host, &remote_browser_toolbar, &mut device_selector_toolbar, &events_toolbar);
+            });
+            
+            let config_wake = self.config.clone();
+            let remote_browser_wake = self.remote_browser_ref.clone();
+            let device_selector_wake = self.device_selector.clone();
+            let events_wake = self.events.clone();
+            self.connection_toolbar.set_wake_callback(move |host| {
+                {
+                    let mut config = config_wake.lock().unwrap();
+                    if let Some(pos) = config.hosts.iter().position(|h| h.name == host.name) {
+                        config.last_used_host_index = pos;
                     }
                 }
+                Self::wake_and_connect(&host, &remote_browser_wake, &device_selector_wake, &events_wake);
             });
             
-            // Create a thread-safe reference to the transfer panel
-            let transfer_panel = Arc::new(Mutex::new(self.transfer_panel.clone()));
+            let mut device_selector_toolbar = self.device_selector.clone();
+            self.connection_toolbar.set_disconnect_callback(move || {
+                device_selector_toolbar.disconnect_active();
+            });
             
-            // Local browser file selection callback
-            let transfer_panel_clone = transfer_panel.clone();
-            let image_view_clone = image_view.clone();
+            let health_monitor_toolbar = self.health_monitor.clone();
+            self.connection_toolbar.set_reconnect_callback(move || {
+                health_monitor_toolbar.check_now();
+            });
+            
+            // Grey out the remote pane while the keepalive cannot reach the Pi
+            let remote_browser_health = self.remote_browser_ref.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::ConnectionHealth { hostname, reachable } = event {
+                    if let Ok(mut browser) = remote_browser_health.lock() {
+                        browser.set_reachable(*reachable);
+                    }
+                    if *reachable {
+                        notifications::notify(ToastKind::Success, "Reconnected", &trf("{} is responding again", &[&hostname]));
+                    } else {
+                        notifications::notify(
+                            ToastKind::Error,
+                            "Connection lost",
+                            &trf("{} is not responding. Use Reconnect once it is back.", &[&hostname])
+                        );
+                    }
+                }
+            });
+            
+            // The browsers only announce selections; subscribers below react to them
+            let local_scan = Arc::new(AtomicU64::new(0));
+            let remote_scan = Arc::new(AtomicU64::new(0));
+            let events_local = self.events.clone();
+            let local_summary = self.local_browser.clone();
+            let local_scan_open = local_scan.clone();
             self.local_browser.set_callback(move |path, is_dir| {
+                publish_selection(&local_summary, false, &events_local, &local_scan_open);
                 if !is_dir {
-                    println!("Local file selected: {}", path.display());
-                    
-                    // Set the source path for transfer
-                    if let Ok(mut panel) = transfer_panel_clone.lock() {
-                        panel.set_source_path(path.clone(), true);
+                    log::info!("Local file selected: {}", path.display());
+                    events_local.publish(AppEvent::FileSelected { path, is_remote: false });
+                }
+            });
+            
+            let events_remote = self.events.clone();
+            if let Ok(mut remote_browser) = remote_browser_clone.lock() {
+                let remote_summary = remote_browser.clone();
+                let remote_scan_open = remote_scan.clone();
+                remote_browser.set_callback(move |path, is_dir| {
+                    publish_selection(&remote_summary, true, &events_remote, &remote_scan_open);
+                    if !is_dir {
+                        log::info!("Remote file selected: {}", path.display());
+                        events_remote.publish(AppEvent::FileSelected { path, is_remote: true });
+                    }
+                });
+            } else {
+                log::error!("Could not lock remote browser to set callback");
+            }
+            
+            // With double-click opening a single click only selects, which
+            // still updates the selection summary
+            let events_local = self.events.clone();
+            let local_summary = self.local_browser.clone();
+            self.local_browser.set_selection_callback(move || {
+                publish_selection(&local_summary, false, &events_local, &local_scan);
+            });
+            
+            let events_remote = self.events.clone();
+            if let Ok(mut remote_browser) = remote_browser_clone.lock() {
+                let remote_summary = remote_browser.clone();
+                remote_browser.set_selection_callback(move || {
+                    publish_selection(&remote_summary, true, &events_remote, &remote_scan);
+                });
+            }
+            
+            // The switch-pane key reaches the lists before the window uses it
+            // for focus navigation, so the lists handle it themselves
+            let switch_pane = ShortcutMap::from_bindings(&self.config.lock().unwrap().key_bindings).switch_pane;
+            let remote_browser_switch = remote_browser_clone.clone();
+            self.local_browser.set_key_handler(move || {
+                if !is_pressed(switch_pane) {
+                    return false;
+                }
+                if let Ok(mut browser) = remote_browser_switch.lock() {
+                    browser.focus_list();
+                }
+                true
+            });
+            
+            let mut local_browser_switch = self.local_browser.clone();
+            if let Ok(mut remote_browser) = remote_browser_clone.lock() {
+                remote_browser.set_key_handler(move || {
+                    if !is_pressed(switch_pane) {
+                        return false;
+                    }
+                    local_browser_switch.focus_list();
+                    true
+                });
+            }
+            
+            // Show new camera captures in the Preview tab
+            let preview_panel_camera = preview_panel.clone();
+            let mut tabs_camera = tabs.clone();
+            let preview_tab_camera = preview_tab.clone();
+            self.camera_panel.set_callback(move |path| {
+                log::info!("Camera capture downloaded: {}", path.display());
+                if let Ok(mut panel) = preview_panel_camera.lock() {
+                    if panel.preview_file(&path) {
+                        let _ = tabs_camera.set_value(&preview_tab_camera);
+                    } else {
+                        log::error!("Failed to preview capture: {}", path.display());
                     }
+                }
+            });
+            
+            // Remember the last previewed file for session restore
+            let config_last_preview = self.config.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::FileSelected { path, is_remote } = event {
+                    let mut config = config_last_preview.lock().unwrap();
+                    config.last_preview = Some(path.to_string_lossy().to_string());
+                    config.last_preview_remote = *is_remote;
+                }
+            });
+            
+            // Selected files become the transfer source
+            let mut transfer_panel = self.transfer_panel.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::FileSelected { path, is_remote } = event {
+                    transfer_panel.set_source_path(path.clone(), !is_remote);
+                }
+            });
+            
+            // Browse the connected Pi when picking a transfer destination
+            let remote_browser_dest = self.remote_browser_ref.clone();
+            let mut transfer_panel_dest = self.transfer_panel.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::ConnectionChanged { hostname } = event {
+                    let method = match hostname {
+                        Some(_) => remote_browser_dest.lock().ok().and_then(|b| b.get_transfer_method()),
+                        None => None,
+                    };
+                    transfer_panel_dest.set_remote_method(method);
+                }
+            });
+            
+            // Preview selected files: images in the Image Processing tab, other
+            // previewable files in the Preview tab, where text can be edited
+            let remote_browser_preview = self.remote_browser_ref.clone();
+            let image_view_preview = image_view.clone();
+            let preview_panel_clone = preview_panel.clone();
+            let mut tabs_preview = tabs.clone();
+            let preview_tab_clone = preview_tab.clone();
+            self.events.subscribe(move |event| {
+                let (path, is_remote) = match event {
+                    AppEvent::FileSelected { path, is_remote } => (path, *is_remote),
+                    _ => return,
+                };
+                
+                if FileBrowserPanel::is_image_file(path) && is_remote && !path.exists() {
+                    // Fetch a copy for preview, reusing one downloaded earlier
+                    let (method, modified) = match remote_browser_preview.lock() {
+                        Ok(browser) => (browser.get_transfer_method(), browser.entry_modified(path)),
+                        Err(_) => return,
+                    };
+                    let method = match method {
+                        Some(method) => method,
+                        None => return,
+                    };
                     
-                    // Check if file is an image and preview it
-                    if FileBrowserPanel::is_image_file(&path) {
-                        println!("Loading image for preview: {}", path.display());
-                        if let Ok(mut view) = image_view_clone.lock() {
-                            if view.load_image(&path) {
-                                println!("Successfully loaded image preview");
+                    let remote_path = path.clone();
+                    let image_view_done = image_view_preview.clone();
+                    run_in_background(
+                        move || download_cache::fetch(method.as_ref(), &remote_path, modified),
+                        move |result| match result {
+                            Ok(temp_file) => {
+                                if let Ok(mut view) = image_view_done.lock() {
+                                    if view.confirm_discard() && !view.load_image(&temp_file) {
+                                        log::error!("Failed to load image preview: {}", temp_file.display());
+                                    }
+                                }
+                            },
+                            Err(e) => dialogs::error_dialog("Could not download the image for preview", &e.into()),
+                        }
+                    );
+                } else if FileBrowserPanel::is_image_file(path) {
+                    log::info!("Loading image for preview: {}", path.display());
+                    if let Ok(mut view) = image_view_preview.lock() {
+                        if view.confirm_discard() && !view.load_image(path) {
+                            log::error!("Failed to load image preview: {}", path.display());
+                        }
+                    }
+                } else if is_remote && get_file_type_info(path).previewable {
+                    // Small files are downloaded whole; bigger ones are read on the Pi a chunk at a time
+                    let (method, modified, size) = match remote_browser_preview.lock() {
+                        Ok(browser) => (browser.get_transfer_method(), browser.entry_modified(path), browser.entry_size(path)),
+                        Err(_) => return,
+                    };
+                    let method = match method {
+                        Some(method) => method,
+                        None => {
+                            notifications::notify(ToastKind::Error, "Preview", "Not connected to a Raspberry Pi");
+                            return;
+                        },
+                    };
+                    // A file whose size isn't known yet is streamed too, rather than downloaded blind
+                    if size.map_or(true, |size| size > MAX_REMOTE_PREVIEW_BYTES) {
+                        if let Ok(mut panel) = preview_panel_clone.lock() {
+                            if panel.stream_remote_file(method, path.clone()) {
+                                let _ = tabs_preview.set_value(&preview_tab_clone);
                             } else {
-                                println!("Failed to load image preview");
+                                log::error!("Failed to preview file: {}", path.display());
                             }
                         }
+                        return;
+                    }
+                    
+                    let remote_path = path.clone();
+                    let preview_panel_done = preview_panel_clone.clone();
+                    let mut tabs_done = tabs_preview.clone();
+                    let preview_tab_done = preview_tab_clone.clone();
+                    run_in_background(
+                        {
+                            let method = method.clone_box();
+                            let remote_path = remote_path.clone();
+                            move || download_cache::fetch(method.as_ref(), &remote_path, modified)
+                        },
+                        move |result| match result {
+                            Ok(temp_file) => {
+                                if let Ok(mut panel) = preview_panel_done.lock() {
+                                    if panel.preview_remote_file(&temp_file, method, remote_path) {
+                                        let _ = tabs_done.set_value(&preview_tab_done);
+                                    } else {
+                                        log::error!("Failed to preview file: {}", temp_file.display());
+                                    }
+                                }
+                            },
+                            Err(e) => dialogs::error_dialog("Could not download the file for preview", &e.into()),
+                        }
+                    );
+                } else if !is_remote && get_file_type_info(path).previewable {
+                    if let Ok(mut panel) = preview_panel_clone.lock() {
+                        if panel.preview_file(path) {
+                            let _ = tabs_preview.set_value(&preview_tab_clone);
+                        } else {
+                            log::error!("Failed to preview file: {}", path.display());
+                        }
                     }
                 }
             });
             
-            // Remote browser file selection callback 
-            let transfer_panel_clone = transfer_panel.clone();
-            let remote_browser_clone = self.remote_browser_ref.clone();
-            let image_view_clone = image_view.clone();
-            let temp_dir_clone = temp_dir.clone();
+            // Refresh the pane a transfer wrote into
+            let mut local_browser = self.local_browser.clone();
+            let remote_browser_transfer = self.remote_browser_ref.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::TransferFinished { source, destination, upload } = event {
+                    log::info!("Transfer finished: {} -> {}", source.display(), destination.display());
+                    let name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                    let place = destination.parent().map(|p| p.display().to_string()).unwrap_or_default();
+                    notifications::notify(
+                        ToastKind::Success,
+                        if *upload { "Upload finished" } else { "Download finished" },
+                        &trf("{} to {}", &[&name, &place])
+                    );
+                    if *upload {
+                        if let Ok(mut browser) = remote_browser_transfer.lock() {
+                            browser.refresh();
+                        }
+                    } else {
+                        local_browser.refresh();
+                    }
+                }
+            });
             
-// First get a lock on the remote browser to set its callback
-if let Ok(mut remote_browser) = remote_browser_clone.lock() {
-    // Create a new clone for use inside the closure
-    let inner_remote_browser_clone = self.remote_browser_ref.clone();
-    
-    remote_browser.set_callback(move |path, is_dir| {
-        if !is_dir {
-            println!("Remote file selected: {}", path.display());
+            // Run the chosen processing on every image downloaded from the Pi
+            let config_process = self.config.clone();
+            let image_service_process = self.image_service.clone();
+            let processing_queue = self.processing_queue.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::TransferFinished { destination, upload: false, .. } = event {
+                    let (settings, name_template, upload) = {
+                        let config = config_process.lock().unwrap();
+                        let upload = config.post_download.preset.as_deref().and_then(|preset| preset_upload_target(&config, preset));
+                        (config.post_download.clone(), config.output_name_template.clone(), upload)
+                    };
+                    post_download::process_download(
+                        destination.clone(),
+                        &settings,
+                        &name_template,
+                        upload,
+                        image_service_process.clone(),
+                        &processing_queue
+                    );
+                }
+            });
             
-            // Set source path for transfer
-            if let Ok(mut panel) = transfer_panel_clone.lock() {
-                panel.set_source_path(path.clone(), false);
+            // Hand connections, downloads and finished jobs to the user's hook script
+            let (hooks_script, name_template) = {
+                let config = self.config.lock().unwrap();
+                (config.hooks_script_path(), config.output_name_template.clone())
+            };
+            if let Some(script) = hooks_script {
+                let hooks = ScriptHooks::start(script, name_template, |message| {
+                    log::error!("Hook script: {}", message);
+                    notifications::notify(ToastKind::Error, "Hook script failed", &message);
+                });
+                let remote_browser_hooks = self.remote_browser_ref.clone();
+                self.events.subscribe(move |event| {
+                    let hook = match event {
+                        AppEvent::ConnectionChanged { hostname: Some(host) } => Hook::Connect { host: host.clone() },
+                        AppEvent::TransferFinished { source, destination, upload: false } => Hook::DownloadComplete {
+                            local_path: destination.clone(),
+                            remote_path: source.clone(),
+                        },
+                        AppEvent::ProcessingJobFinished { name, done, failed, cancelled } => Hook::BatchFinished {
+                            name: name.clone(),
+                            done: *done,
+                            failed: *failed,
+                            cancelled: *cancelled,
+                        },
+                        _ => return,
+                    };
+                    let method = remote_browser_hooks.lock().ok().and_then(|b| b.get_transfer_method());
+                    hooks.fire(hook, method);
+                });
             }
             
-            // Check if it's an image file
-            if FileBrowserPanel::is_image_file(&path) {
-                // For remote files, check if they exist locally first
-                if path.exists() {
-                    // File exists locally, preview it directly
-                    println!("File exists locally, loading for preview");
-                    if let Ok(mut view) = image_view_clone.lock() {
-                        if view.load_image(&path) {
-                            println!("Successfully loaded remote image preview");
-                        } else {
-                            println!("Failed to load remote image preview");
-                        }
-                    }
-                } else {
-                    // Need to download the file to a temporary location for preview
-                    println!("Remote file not available locally, downloading for preview");
-                    
-                    // Create a path in the temp directory
-                    let mut temp_file = temp_dir_clone.clone();
-                    if let Some(file_name) = path.file_name() {
-                        temp_file.push(file_name);
-                        
-                        // Use the browser to download the file - use inner_remote_browser_clone here
-                        if let Ok(browser) = inner_remote_browser_clone.lock() {
-                            match browser.download_remote_file(&path, &temp_file) {
-                                
-                               Ok(_) | Err(_) => todo!(),
-                          }
-                                
-                            }
-                        
+            // Probe a newly shown Pi for optional tools so dependent features can be disabled
+            let remote_browser_probe = self.remote_browser_ref.clone();
+            let camera_panel_probe = self.camera_panel.clone();
+            let transfer_panel_probe = self.transfer_panel.clone();
+            self.events.subscribe(move |event| {
+                if let AppEvent::ConnectionChanged { hostname: Some(_) } = event {
+                    let method = remote_browser_probe.lock().ok().and_then(|b| b.get_transfer_method());
+                    if let Some(method) = method {
+                        Self::probe_capabilities(method, camera_panel_probe.clone(), transfer_panel_probe.clone());
                     }
                 }
-            }
-        }
-    });
-} else {
-    println!("ERROR: Could not lock remote browser to set callback");
-}
+            });
             
             // Add a handler to watch for events
             let remote_browser_clone = self.remote_browser_ref.clone();
@@ -699,7 +2885,7 @@ if let Ok(mut remote_browser) = remote_browser_clone.lock() {
             window.handle(move |_, ev| {
                 match ev {
                     Event::Close => {
-                        println!("Window close event received");
+                        log::info!("Window close event received");
                         if let Ok(browser) = remote_browser_clone.lock() {
                             browser.print_debug_status();
                         }
@@ -710,7 +2896,7 @@ if let Ok(mut remote_browser) = remote_browser_clone.lock() {
                         false // Allow default handling to continue
                     },
                     Event::Focus => {
-                        println!("Window focus event received");
+                        log::info!("Window focus event received");
                         if let Ok(browser) = remote_browser_clone.lock() {
                             browser.print_debug_status();
                         }
@@ -721,6 +2907,185 @@ if let Ok(mut remote_browser) = remote_browser_clone.lock() {
             });
         }
         
+        // Open an SSH connection to `host` in the remote pane, asking for the
+        // password when the host uses one; false when the user gave up
+        fn connect_to_host(
+            host: &Host,
+            remote_browser: &Arc<Mutex<FileBrowserPanel>>,
+            device_selector: &mut DeviceSelector,
+            events: &EventBus
+        ) -> bool {
+            // Password authentication is scripted through sshpass
+            let local_tools = Capabilities { local: probe_local(), remote: Vec::new() };
+            if !host.use_key_auth && !local_tools.has_local("sshpass") {
+                dialogs::message_dialog(
+                    "Missing Dependency",
+                    "Password authentication needs 'sshpass' installed on this computer. Install it or use an SSH key."
+                );
+                return false;
+            }
+            
+            // If using password auth, prompt for password
+            let mut password_opt = None;
+            if !host.use_key_auth {
+                password_opt = dialogs::password_dialog(
+                    "SSH Password",
+                    &format!("Enter password for {}@{}:", host.username, host.hostname)
+                );
+                if password_opt.is_none() {
+                    return false;
+                }
+            }
+            
+            // Use whichever of the host's addresses answers, e.g. its VPN
+            // address when away from home
+            let endpoint = match reachable_host(host) {
+                Ok((_, endpoint)) => endpoint,
+                // A powered-down Pi can be woken first
+                Err(_) if host.mac_address.is_some() => {
+                    let answer = dialogs::choice_dialog(
+                        "Pi Not Responding",
+                        &trf("{} does not answer. Wake it with a wake-on-LAN packet and connect once it boots?", &[&host.name]),
+                        &["Wake and Connect", "Cancel"]
+                    );
+                    if answer == 0 {
+                        Self::wake_and_connect(host, remote_browser, device_selector, events);
+                    }
+                    return false;
+                },
+                Err(e) => {
+                    dialogs::error_dialog(&trf("Cannot reach {}", &[&host.name]), &e.into());
+                    return false;
+                }
+            };
+            
+            // Create SSH connection to list remote files
+            let factory = factory_for_host(&endpoint);
+            
+            let mut transfer_method = factory.create_method();
+            
+            // If password was provided, set it in the transfer method
+            if let Some(password) = &password_opt {
+                transfer_method.set_password(password);
+            }
+            
+            // Reopen where this host was last browsed, else its start directory
+            let remote_home = host.start_directory();
+            
+            log::debug!("About to set remote directory with path: {}", remote_home.display());
+            log::debug!("Transfer method: {}", transfer_method.get_name());
+            
+            // Keep a copy of the connection for the session manager
+            let session_method = transfer_method.clone_box();
+            let session_password = password_opt.clone();
+            
+            // Get a mutable reference to the actual remote browser through the mutex
+            if let Ok(mut browser) = remote_browser.lock() {
+                // Store credentials for future use
+                browser.current_hostname = Some(host.hostname.clone());
+                browser.current_username = Some(host.username.clone());
+                browser.current_password = password_opt;
+                
+                // Configure the remote browser with the transfer method and initial path
+                browser.set_remote_directory(&remote_home, transfer_method);
+                
+                // Force a UI refresh after setting up the connection
+                app::flush();  // Flush pending UI events
+                app::awake();  // Wake up the UI thread
+                app::redraw(); // Force complete redraw
+                
+                // Print debug status after connection
+                browser.print_debug_status();
+                
+                log::debug!("Set remote directory successfully");
+                log::info!("Connected to: {} and set remote home to: {}", 
+                        host.hostname, remote_home.display());
+            } else {
+                log::error!("Error: Could not lock remote browser");
+            }
+            
+            // Track the connection so the device selector can switch back to it
+            device_selector.add_session(
+                &host.name,
+                &host.hostname,
+                &host.username,
+                session_password,
+                session_method,
+                remote_home
+            );
+            
+            events.publish(AppEvent::ConnectionChanged {
+                hostname: Some(host.hostname.clone()),
+            });
+            true
+        }
+        
+        // Send `host` a wake-on-LAN packet, wait for ssh to answer in the
+        // background, then connect as usual
+        fn wake_and_connect(
+            host: &Host,
+            remote_browser: &Arc<Mutex<FileBrowserPanel>>,
+            device_selector: &DeviceSelector,
+            events: &EventBus
+        ) {
+            if let Err(e) = send_magic_packet(host) {
+                dialogs::error_dialog(&trf("Cannot wake {}", &[&host.name]), &e.into());
+                return;
+            }
+            notifications::notify(
+                ToastKind::Info,
+                "Waking Pi",
+                &trf("Waiting up to {} seconds for {} to boot", &[&WAKE_TIMEOUT.as_secs(), &host.name])
+            );
+            
+            let host_wait = host.clone();
+            let host = host.clone();
+            let remote_browser = remote_browser.clone();
+            let mut device_selector = device_selector.clone();
+            let events = events.clone();
+            run_in_background(
+                move || wait_until_reachable(&host_wait, WAKE_TIMEOUT),
+                move |result| match result {
+                    Ok(_) => {
+                        Self::connect_to_host(&host, &remote_browser, &mut device_selector, &events);
+                    },
+                    Err(e) => dialogs::error_dialog(&trf("{} did not wake up", &[&host.name]), &e.into()),
+                }
+            );
+        }
+        
+        // Check which tools exist on the Pi off the UI thread, then update the panels that need them
+        fn probe_capabilities(method: Box<dyn TransferMethod>, mut camera_panel: CameraPanel, mut transfer_panel: TransferPanel) {
+            let result: Arc<Mutex<Option<Result<String, String>>>> = Arc::new(Mutex::new(None));
+            let result_worker = result.clone();
+            
+            thread::spawn(move || {
+                let output = method.exec(&remote_probe_command())
+                    .map(|output| output.stdout)
+                    .map_err(|e| e.to_string());
+                *result_worker.lock().unwrap() = Some(output);
+                app::awake();
+            });
+            
+            app::add_timeout3(0.2, move |handle| {
+                let finished = result.lock().unwrap().take();
+                
+                match finished {
+                    Some(Ok(stdout)) => {
+                        let capabilities = Capabilities {
+                            local: probe_local(),
+                            remote: parse_remote_probe(&stdout),
+                        };
+                        log::info!("Capability report:\n{}", capabilities.report());
+                        camera_panel.apply_capabilities(&capabilities);
+                        transfer_panel.apply_capabilities(&capabilities);
+                    },
+                    Some(Err(e)) => log::error!("Failed to probe remote tools: {}", e),
+                    None => app::repeat_timeout3(0.2, handle),
+                }
+            });
+        }
+        
         // Helper method to clean up temporary downloaded files
         fn cleanup_temp_files(temp_dir: &Path) {
             if temp_dir.exists() {
@@ -729,9 +3094,9 @@ if let Ok(mut remote_browser) = remote_browser_clone.lock() {
                         let path = entry.path();
                         if path.is_file() {
                             if let Err(e) = fs::remove_file(&path) {
-                                println!("Failed to remove temp file {}: {}", path.display(), e);
+                                log::error!("Failed to remove temp file {}: {}", path.display(), e);
                             } else {
-                                println!("Removed temp file: {}", path.display());
+                                log::info!("Removed temp file: {}", path.display());
                             }
                         }
                     }
@@ -742,5 +3107,78 @@ if let Ok(mut remote_browser) = remote_browser_clone.lock() {
         pub fn show(&mut self) {
             self.window.show();
         }
+        
+        // Reopen the last session when enabled: the local directory, the
+        // connection to the last used host and the last previewed file; call
+        // after show so password prompts have a window to sit over
+        pub fn restore_session(&mut self) {
+            let (local_dir, host, preview) = {
+                let config = self.config.lock().unwrap();
+                if !config.restore_session {
+                    return;
+                }
+                (
+                    config.last_local_dir.clone(),
+                    config.hosts.get(config.last_used_host_index).cloned(),
+                    config.last_preview.clone().map(|path| (PathBuf::from(path), config.last_preview_remote)),
+                )
+            };
+            
+            if let Some(dir) = local_dir.map(PathBuf::from).filter(|dir| dir.is_dir()) {
+                log::info!("Restoring local directory {}", dir.display());
+                self.local_browser.set_directory(&dir);
+            }
+            
+            let connected = match host {
+                Some(host) => {
+                    log::info!("Reconnecting to {}", host.hostname);
+                    Self::connect_to_host(&host, &self.remote_browser_ref, &mut self.device_selector, &self.events)
+                },
+                None => false,
+            };
+            
+            match preview {
+                Some((path, true)) if connected => {
+                    self.events.publish(AppEvent::FileSelected { path, is_remote: true });
+                },
+                Some((path, false)) if path.is_file() => {
+                    self.events.publish(AppEvent::FileSelected { path, is_remote: false });
+                },
+                _ => {},
+            }
+        }
+        
+        // Whether a window placed at (x, y) would be on one of the screens
+        fn on_screen(x: i32, y: i32) -> bool {
+            (0..app::screen_count()).any(|screen| {
+                let (sx, sy, sw, sh) = app::screen_xywh(screen);
+                x >= sx && y >= sy && x < sx + sw && y < sy + sh
+            })
+        }
+        
+        // Record the window geometry, pane splits and open tab in the
+        // settings shared with the panels and write them out; call on exit
+        pub fn save_state(&self) {
+            let mut config = self.config.lock().unwrap();
+            
+            config.window_x = Some(self.window.x());
+            config.window_y = Some(self.window.y());
+            config.window_width = self.window.w();
+            config.window_height = self.window.h();
+            
+            let (_, _, local_width, browser_height) = self.local_browser.bounds();
+            let split_height = self.window.h() - MENU_BAR_HEIGHT - TOOLBAR_HEIGHT - STATUS_BAR_HEIGHT - 45;
+            if self.window.w() > 0 && split_height > 0 {
+                config.browser_split = (local_width as f64 / self.window.w() as f64).clamp(MIN_SPLIT, MAX_SPLIT);
+                config.transfer_split = (browser_height as f64 / split_height as f64).clamp(MIN_SPLIT, MAX_SPLIT);
+            }
+            
+            config.last_tab = self.tabs.value().map(|tab| source_text(&tab.label()));
+            config.last_local_dir = Some(self.local_browser.get_current_directory().to_string_lossy().to_string());
+            
+            if let Err(err) = config.save() {
+                log::warn!("Failed to save config: {}", err);
+            }
+        }
     }
 }
\ No newline at end of file
@@ -20,6 +20,7 @@ use crate::core::image::{
     JPEGProcessorFactory,
     PNGProcessorFactory,
 };
+use crate::core::locale::trf;
 
 use crate::config::Config;
 use crate::transfer::ssh::SSHTransferFactory;
@@ -27,6 +28,7 @@ use crate::transfer::ssh::SSHTransferFactory;
 use crate::ui::file_browser::file_browser::FileBrowserPanel;
 // Use the new preview panel
 use crate::ui::preview::preview_panel::PreviewPanel;
+use crate::ui::image_view::image_view::ImageViewPanel;
 use crate::ui::operations_panel::operations_panel::OperationsPanel;
 use crate::ui::transfer_panel::transfer_panel::TransferPanel;
 use crate::transfer::method::TransferMethodFactory;
@@ -43,6 +45,9 @@ pub struct MainWindowAdapter {
     remote_browser_ref: Arc<Mutex<FileBrowserPanel>>, 
     // The new preview panel
     preview_panel: PreviewPanel,
+    // Takes the preview panel's place for images, which can be edited
+    image_group: Group,
+    image_view: ImageViewPanel,
     operations_panel: OperationsPanel,
     transfer_panel: TransferPanel,
     // Directory for temporary downloaded files
@@ -60,9 +65,10 @@ impl MainWindowAdapter {
         // Create image processing service
         let mut image_service = ImageProcessingService::new();
         
-        // Register image processor factories
-        image_service.register_factory(Box::new(JPEGProcessorFactory::new(85)));
-        image_service.register_factory(Box::new(PNGProcessorFactory::new(6)));
+        // Register image processor factories with the settings last chosen
+        let processors = config.lock().unwrap().processors.clone();
+        image_service.register_factory(Box::new(JPEGProcessorFactory::new(processors.jpeg_quality)));
+        image_service.register_factory(Box::new(PNGProcessorFactory::new(processors.png_compression)));
         
         let image_service = Arc::new(Mutex::new(image_service));
         
@@ -133,6 +139,18 @@ impl MainWindowAdapter {
             content_height - 35
         );
         
+        // Images open in an image view in the same place, so the operations
+        // panel can edit them
+        let mut image_group = Group::new(0, content_y + 35, image_view_width, content_height - 35, None);
+        let image_view = ImageViewPanel::new(
+            0,
+            content_y + 35,
+            image_view_width,
+            content_height - 35
+        );
+        image_group.end();
+        image_group.hide();
+        
         // Create operations panel (right side)
         let operations_width = width - image_view_width - 5;
         let operations_panel = OperationsPanel::new(
@@ -140,7 +158,9 @@ impl MainWindowAdapter {
             content_y + 35,
             operations_width,
             content_height - 35,
-            image_service.clone()
+            image_service.clone(),
+            image_view.clone(),
+            config.clone()
         );
         
         image_tab.end();
@@ -172,6 +192,8 @@ impl MainWindowAdapter {
             local_browser,
             remote_browser_ref,
             preview_panel,
+            image_group,
+            image_view,
             operations_panel,
             transfer_panel,
             temp_dir,
@@ -186,7 +208,8 @@ impl MainWindowAdapter {
             main_window.config.clone(), 
             main_window.image_service.clone(),
             main_window.remote_browser_ref.clone(),
-            preview_panel_ref.clone()
+            preview_panel_ref.clone(),
+            (main_window.image_group.clone(), main_window.image_view.clone())
         );
         
         // Setup callbacks with the shared remote browser reference and preview panel
@@ -200,10 +223,12 @@ impl MainWindowAdapter {
         config: Arc<Mutex<Config>>,
         image_service: Arc<Mutex<ImageProcessingService>>,
         remote_browser: Arc<Mutex<FileBrowserPanel>>,
-        preview_panel: Arc<Mutex<PreviewPanel>>
+        preview_panel: Arc<Mutex<PreviewPanel>>,
+        image_view: (Group, ImageViewPanel)
     ) {
         // File menu
         let preview_panel_clone = preview_panel.clone();
+        let mut image_view_open = image_view.clone();
         menu.add(
             "&File/&Open File...\t",
             Shortcut::Ctrl | 'o',
@@ -212,17 +237,14 @@ impl MainWindowAdapter {
                 if let Some(path) = dialogs::open_file_dialog("Open File", "") {
                     println!("Opening file: {}", path.display());
                     
-                    // Get lock on the preview panel and preview the file
-                    if let Ok(mut panel) = preview_panel_clone.lock() {
-                        if panel.preview_file(&path) {
-                            println!("Successfully previewed file: {}", path.display());
-                        } else {
-                            // Show error dialog if preview fails
-                            dialogs::message_dialog(
-                                "Error", 
-                                &format!("Failed to preview file: {}", path.display())
-                            );
-                        }
+                    if Self::show_file(&preview_panel_clone, &mut image_view_open, &path) {
+                        println!("Successfully previewed file: {}", path.display());
+                    } else {
+                        // Show error dialog if preview fails
+                        dialogs::message_dialog(
+                            "Error", 
+                            &trf("Failed to preview file: {}", &[&path.display()])
+                        );
                     }
                 }
             },
@@ -349,6 +371,7 @@ impl MainWindowAdapter {
         // Local browser file selection callback
         let transfer_panel_clone = transfer_panel.clone();
         let preview_panel_clone = preview_panel.clone();
+        let mut image_view = (self.image_group.clone(), self.image_view.clone());
         self.local_browser.set_callback(move |path, is_dir| {
             if !is_dir {
                 println!("Local file selected: {}", path.display());
@@ -359,12 +382,10 @@ impl MainWindowAdapter {
                 }
                 
                 // Preview the file regardless of type
-                if let Ok(mut panel) = preview_panel_clone.lock() {
-                    if panel.preview_file(&path) {
-                        println!("Successfully previewed file");
-                    } else {
-                        println!("Failed to preview file");
-                    }
+                if Self::show_file(&preview_panel_clone, &mut image_view, &path) {
+                    println!("Successfully previewed file");
+                } else {
+                    println!("Failed to preview file");
                 }
             }
         });
@@ -373,6 +394,7 @@ impl MainWindowAdapter {
         let transfer_panel_clone = transfer_panel.clone();
         let remote_browser_clone = self.remote_browser_ref.clone();
         let preview_panel_clone = preview_panel.clone();
+        let mut image_view = (self.image_group.clone(), self.image_view.clone());
         let temp_dir_clone = temp_dir.clone();
         
         // First get a lock on the remote browser to set its callback
@@ -390,12 +412,10 @@ impl MainWindowAdapter {
                     if path.exists() {
                         // File exists locally, preview it directly
                         println!("File exists locally, attempting preview");
-                        if let Ok(mut panel) = preview_panel_clone.lock() {
-                            if panel.preview_file(&path) {
-                                println!("Successfully previewed remote file");
-                            } else {
-                                println!("Failed to preview remote file");
-                            }
+                        if Self::show_file(&preview_panel_clone, &mut image_view, &path) {
+                            println!("Successfully previewed remote file");
+                        } else {
+                            println!("Failed to preview remote file");
                         }
                     } else {
                         // Need to download the file to a temporary location for preview
@@ -413,19 +433,17 @@ impl MainWindowAdapter {
                                         println!("Successfully downloaded to: {}", temp_file.display());
                                         
                                         // Now preview the downloaded file
-                                        if let Ok(mut panel) = preview_panel_clone.lock() {
-                                            if panel.preview_file(&temp_file) {
-                                                println!("Successfully previewed downloaded file");
-                                            } else {
-                                                println!("Failed to preview downloaded file");
-                                            }
+                                        if Self::show_file(&preview_panel_clone, &mut image_view, &temp_file) {
+                                            println!("Successfully previewed downloaded file");
+                                        } else {
+                                            println!("Failed to preview downloaded file");
                                         }
                                     },
                                     Err(e) => {
                                         println!("Failed to download file for preview: {}", e);
                                         dialogs::message_dialog(
                                             "Download Error",
-                                            &format!("Failed to download remote file: {}", e)
+                                            &trf("Failed to download remote file: {}", &[&e])
                                         );
                                     }
                                 }
@@ -467,6 +485,28 @@ impl MainWindowAdapter {
         });
     }
     
+    // Open images in the image view, where they can be edited, and
+    // everything else in the preview panel
+    fn show_file(
+        preview_panel: &Arc<Mutex<PreviewPanel>>,
+        (image_group, image_view): &mut (Group, ImageViewPanel),
+        path: &Path
+    ) -> bool {
+        let Ok(mut panel) = preview_panel.lock() else { return false };
+        if is_image_file(path) {
+            if !image_view.confirm_discard() {
+                return true;
+            }
+            panel.group.hide();
+            image_group.show();
+            image_view.load_image(path)
+        } else {
+            image_group.hide();
+            panel.group.show();
+            panel.preview_file(path)
+        }
+    }
+    
     // Helper method to clean up temporary downloaded files
     fn cleanup_temp_files(temp_dir: &Path) {
         if temp_dir.exists() {
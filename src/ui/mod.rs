@@ -7,4 +7,44 @@ pub mod operations_panel;
 pub mod transfer_panel;
 pub mod dialogs;
 pub mod preview;
-pub mod browser;
\ No newline at end of file
+pub mod browser;
+pub mod terminal_panel;
+pub mod device_panel;
+pub mod camera_panel;
+pub mod services_panel;
+pub mod disk_usage_panel;
+pub mod device_selector;
+pub mod log_panel;
+pub mod events;
+pub mod executor;
+pub mod search_dialog;
+pub mod transfer_queue;
+pub mod status_bar;
+pub mod notifications;
+pub mod modal;
+pub mod shortcuts;
+pub mod connection_toolbar;
+pub mod health_monitor;
+pub mod remote_dir_dialog;
+pub mod post_download;
+pub mod processing_queue;
+pub mod processing_panel;
+pub mod similar_images;
+pub mod batch_rename_dialog;
+pub mod extract_dialog;
+pub mod export_dialog;
+pub mod timelapse_dialog;
+pub mod animation_dialog;
+pub mod remote_trash_dialog;
+pub mod audit_dialog;
+pub mod transfer_stats_panel;
+pub mod live_view_panel;
+pub mod provision_dialog;
+pub mod control_api;
+pub mod destination_dialog;
+pub mod script_export_dialog;
+pub mod translation;
+pub mod theme;
+pub mod hover_preview;
+pub mod storage_dialog;
+pub mod remote_edit;
\ No newline at end of file
@@ -0,0 +1,55 @@
+use fltk::{app, prelude::*, window::Window};
+
+use crate::ui::theme::apply_contrast;
+use crate::ui::translation::translate_window;
+
+/// A dialog window that keeps the rest of the application from taking
+/// input until it is closed
+///
+/// The dialog only ever hides its own window, so closing it can't hide the
+/// main window the way `app::first_window().hide()` could when focus had
+/// moved elsewhere. Clones refer to the same window, which lets button
+/// callbacks close the dialog.
+#[derive(Clone)]
+pub struct ModalDialog {
+    window: Window,
+}
+
+impl ModalDialog {
+    /// Open a dialog of the given size centred over the active window;
+    /// widgets created until `run` is called are placed inside it
+    pub fn new(w: i32, h: i32, title: &str) -> Self {
+        let (x, y) = match app::first_window() {
+            Some(parent) => (parent.x() + (parent.w() - w) / 2, parent.y() + (parent.h() - h) / 2),
+            None => {
+                let (screen_w, screen_h) = app::screen_size();
+                ((screen_w as i32 - w) / 2, (screen_h as i32 - h) / 2)
+            }
+        };
+
+        let mut window = Window::new(x.max(0), y.max(0), w, h, None);
+        window.set_label(title);
+        window.set_border(true);
+        window.make_modal(true);
+
+        ModalDialog { window }
+    }
+
+    /// Close the dialog; safe to call from its own callbacks
+    pub fn close(&mut self) {
+        self.window.hide();
+    }
+
+    /// Show the dialog in the current language and theme and wait until it
+    /// is closed, keeping the rest of the application drawn in the meantime
+    pub fn run(&mut self) {
+        self.window.end();
+        translate_window(&mut self.window);
+        apply_contrast(&mut self.window);
+        self.window.show();
+
+        while self.window.shown() {
+            app::wait();
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use fltk::{
+    app,
+    enums::{Align, Color, Event, FrameType},
+    frame::Frame,
+    prelude::*,
+    window::Window,
+};
+
+use std::sync::{Mutex, OnceLock};
+
+use crate::core::locale::tr;
+use crate::ui::status_bar::STATUS_BAR_HEIGHT;
+use crate::ui::theme::font_size;
+
+/// Seconds a toast stays up unless clicked away
+const TOAST_SECONDS: f64 = 4.0;
+const TOAST_WIDTH: i32 = 320;
+const TOAST_HEIGHT: i32 = 48;
+const TOAST_MARGIN: i32 = 8;
+
+/// How a toast is coloured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastKind {
+    Info,
+    Success,
+    Error,
+}
+
+impl ToastKind {
+    fn color(self) -> Color {
+        match self {
+            ToastKind::Info => Color::from_rgb(60, 60, 60),
+            ToastKind::Success => Color::from_rgb(30, 120, 60),
+            ToastKind::Error => Color::from_rgb(170, 40, 40),
+        }
+    }
+}
+
+/// Toasts shown in the bottom-right corner of the main window, newest lowest
+struct Toaster {
+    window: Window,
+    shown: Vec<Frame>,
+    desktop: bool,
+}
+
+static TOASTER: OnceLock<Mutex<Toaster>> = OnceLock::new();
+
+/// Show toasts in `window` from now on; with `desktop` set they are also
+/// sent to the desktop's notification service when built with the
+/// `desktop-notifications` feature
+pub fn init(window: &Window, desktop: bool) {
+    let toaster = Toaster { window: window.clone(), shown: Vec::new(), desktop };
+    if TOASTER.set(Mutex::new(toaster)).is_err() {
+        log::warn!("Notifications were already set up");
+    }
+}
+
+/// Tell the user a background job finished, without interrupting them
+///
+/// May be called from any thread; the toast appears on the main thread.
+pub fn notify(kind: ToastKind, title: &str, message: &str) {
+    log::info!("{}: {}", title, message);
+
+    let title = tr(title);
+    let message = tr(message);
+    app::awake_callback(move || {
+        let toaster = match TOASTER.get() {
+            Some(toaster) => toaster,
+            None => return,
+        };
+
+        let desktop = {
+            let mut toaster = toaster.lock().unwrap();
+            toaster.show(kind, &title, &message);
+            toaster.desktop
+        };
+        if desktop {
+            send_to_desktop(&title, &message);
+        }
+    });
+}
+
+impl Toaster {
+    fn show(&mut self, kind: ToastKind, title: &str, message: &str) {
+        self.window.begin();
+        let mut toast = Frame::new(0, 0, TOAST_WIDTH, TOAST_HEIGHT, None);
+        self.window.end();
+
+        // "@@" stops FLTK from reading symbols out of file names
+        toast.set_label(&format!("{}\n{}", title, message).replace('@', "@@"));
+        toast.set_frame(FrameType::RoundedBox);
+        toast.set_color(kind.color());
+        toast.set_label_color(Color::White);
+        toast.set_label_size(font_size(12));
+        toast.set_align(Align::Left | Align::Inside | Align::Clip | Align::Wrap);
+        toast.set_tooltip("Click to dismiss");
+
+        // Clicking a toast dismisses it early, once its handler has returned
+        toast.handle(|frame, event| {
+            if event == Event::Push {
+                let frame = frame.clone();
+                app::add_timeout3(0.0, move |_| dismiss(&frame));
+                true
+            } else {
+                false
+            }
+        });
+
+        self.shown.push(toast.clone());
+        self.arrange();
+
+        app::add_timeout3(TOAST_SECONDS, move |_| dismiss(&toast));
+    }
+
+    /// Stack the toasts upwards from above the status bar
+    fn arrange(&mut self) {
+        let x = self.window.w() - TOAST_WIDTH - TOAST_MARGIN;
+        let mut y = self.window.h() - STATUS_BAR_HEIGHT - TOAST_MARGIN - TOAST_HEIGHT;
+        for toast in self.shown.iter_mut().rev() {
+            toast.resize(x, y, TOAST_WIDTH, TOAST_HEIGHT);
+            y -= TOAST_HEIGHT + TOAST_MARGIN;
+        }
+        self.window.redraw();
+    }
+}
+
+fn dismiss(toast: &Frame) {
+    let toaster = match TOASTER.get() {
+        Some(toaster) => toaster,
+        None => return,
+    };
+    let mut toaster = toaster.lock().unwrap();
+
+    // The timeout and a click may both try to dismiss the same toast
+    let before = toaster.shown.len();
+    toaster.shown.retain(|shown| !shown.is_same(toast));
+    if toaster.shown.len() == before {
+        return;
+    }
+
+    toaster.window.remove(toast);
+    Frame::delete(toast.clone());
+    toaster.arrange();
+}
+
+#[cfg(feature = "desktop-notifications")]
+fn send_to_desktop(title: &str, message: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .appname("Pi Image Processor")
+        .summary(title)
+        .body(message)
+        .show()
+    {
+        log::warn!("Desktop notification failed: {}", e);
+    }
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+fn send_to_desktop(_title: &str, _message: &str) {}
@@ -8,28 +8,60 @@ pub mod operations_panel {
         prelude::*,
     };
     
+    use ::image::DynamicImage;
     use std::sync::{Arc, Mutex};
     
     // Updated imports to use the new module structure
+    use crate::config::Config;
     use crate::core::image::{
+        ImageFormat,
         ImageProcessor,
         ImageProcessorFactory,
         ImageProcessingService,
         ImageOperation,
-        ResizeOperation,
-        BrightnessOperation
+        operation_plugins,
+        LENS_PROFILES
     };
+    use crate::core::locale::trf;
+    use crate::core::image_utils::{generate_output_filename, OutputNameContext};
     
     use crate::ui::dialogs::dialogs;
+    use crate::ui::executor::run_in_background;
+    use crate::ui::image_view::image_view::ImageViewPanel;
+    use crate::ui::notifications::{notify, ToastKind};
+    use crate::ui::processing_queue::PIPELINE_SUFFIX;
     
     pub struct OperationsPanel {
         group: Group,
         processor_browser: MultiBrowser,
+        settings_button: Button,
         operations_browser: MultiBrowser,
         add_operation_button: Button,
+        preview_button: Button,
         apply_button: Button,
         clear_button: Button,
         image_service: Arc<Mutex<ImageProcessingService>>,
+        // Shows the image being edited; Apply runs the operations on it
+        image_view: ImageViewPanel,
+        config: Arc<Mutex<Config>>,
+    }
+    
+    impl Clone for OperationsPanel {
+        fn clone(&self) -> Self {
+            Self {
+                group: self.group.clone(),
+                processor_browser: self.processor_browser.clone(),
+                settings_button: self.settings_button.clone(),
+                operations_browser: self.operations_browser.clone(),
+                add_operation_button: self.add_operation_button.clone(),
+                preview_button: self.preview_button.clone(),
+                apply_button: self.apply_button.clone(),
+                clear_button: self.clear_button.clone(),
+                image_service: self.image_service.clone(),
+                image_view: self.image_view.clone(),
+                config: self.config.clone(),
+            }
+        }
     }
     
     impl OperationsPanel {
@@ -38,7 +70,9 @@ pub mod operations_panel {
             y: i32, 
             w: i32, 
             h: i32,
-            image_service: Arc<Mutex<ImageProcessingService>>
+            image_service: Arc<Mutex<ImageProcessingService>>,
+            image_view: ImageViewPanel,
+            config: Arc<Mutex<Config>>
         ) -> Self {
             let mut group = Group::new(x, y, w, h, None);
             group.set_frame(FrameType::BorderBox);
@@ -49,15 +83,25 @@ pub mod operations_panel {
             let browser_height = (h - 4 * padding - 2 * button_height) / 2;
             
             // Processor selection section
+            let settings_width = 80;
             let mut processor_label = fltk::frame::Frame::new(
                 x + padding, 
                 y + padding, 
-                w - 2 * padding, 
+                w - 3 * padding - settings_width, 
                 20, 
                 "Image Processors:"
             );
             processor_label.set_align(fltk::enums::Align::Left | fltk::enums::Align::Inside);
             
+            // Edits the selected processor's quality or compression
+            let settings_button = Button::new(
+                x + w - padding - settings_width,
+                y + padding,
+                settings_width,
+                20,
+                "Settings..."
+            );
+            
             let processor_browser = MultiBrowser::new(
                 x + padding,
                 y + padding + 20,
@@ -105,10 +149,18 @@ pub mod operations_panel {
                 "Clear Operations"
             );
             
-            // Apply button
+            // Preview and Apply buttons
             let apply_y = buttons_y + button_height + padding;
+            let preview_button = Button::new(
+                x + w / 2 - 105,
+                apply_y,
+                100,
+                button_height,
+                "Preview"
+            );
+            
             let mut apply_button = Button::new(
-                x + w / 2 - 50,
+                x + w / 2 + 5,
                 apply_y,
                 100,
                 button_height,
@@ -122,11 +174,15 @@ pub mod operations_panel {
             let mut panel = OperationsPanel {
                 group,
                 processor_browser,
+                settings_button,
                 operations_browser,
                 add_operation_button,
+                preview_button,
                 apply_button,
                 clear_button,
                 image_service,
+                image_view,
+                config,
             };
             
             // Initialize the panel
@@ -137,17 +193,26 @@ pub mod operations_panel {
         }
         
         fn populate_processors(&mut self) {
-            let service = self.image_service.lock().unwrap();
+            Self::list_processors(&self.image_service, &mut self.processor_browser, 1);
+        }
+        
+        // Show the processors' names, which include their settings, with
+        // line `selected` selected
+        fn list_processors(
+            image_service: &Arc<Mutex<ImageProcessingService>>,
+            processor_browser: &mut MultiBrowser,
+            selected: i32
+        ) {
+            let service = image_service.lock().unwrap();
             
-            self.processor_browser.clear();
+            processor_browser.clear();
             
             for (i, factory) in service.get_factories().iter().enumerate() {
-                self.processor_browser.add(&format!("{}. {}", i + 1, factory.get_name()));
+                processor_browser.add(&format!("{}. {}", i + 1, factory.get_name()));
             }
             
-            // Select the first processor by default
-            if service.get_factories().len() > 0 {
-                self.processor_browser.select(1);
+            if selected as usize <= service.get_factories().len() {
+                processor_browser.select(selected);
             }
         }
         
@@ -165,37 +230,52 @@ pub mod operations_panel {
             // Add operation button callback
             let image_service = self.image_service.clone();
             let mut operations_browser = self.operations_browser.clone();
+            let document = self.image_view.document();
             
             let mut add_button = self.add_operation_button.clone();
             add_button.set_callback(move |_| {
-                // Show operation selection dialog
-                let operations = [
-                    "Resize",
-                    "Brightness Adjustment",
-                    // Add more operations as needed
-                ];
-                
-                let choice = dialogs::choice_dialog(
+                // Offer every registered operation, plugins included
+                let plugins = operation_plugins();
+                let options: Vec<String> = plugins
+                    .iter()
+                    .map(|plugin| format!("{}: {}", plugin.name, plugin.description))
+                    .collect();
+                let Some(choice) = dialogs::select_dialog(
                     "Select Operation",
                     "Choose an operation to add:",
-                    &operations
-                );
+                    &options
+                ) else {
+                    return;
+                };
+                let plugin = plugins[choice];
                 
-                match choice {
-                    0 => { // Resize
-                        if let Some((width, height)) = dialogs::resize_dialog() {
-                            let operation = Box::new(ResizeOperation::new(width, height));
-                            image_service.lock().unwrap().add_operation(operation);
-                        }
+                // The built-in operations have dialogs of their own
+                let values = match plugin.name {
+                    "Resize" => {
+                        let original = document.lock().unwrap().as_ref().map(|document| document.dimensions());
+                        dialogs::resize_dialog(original).map(|(width, height)| vec![width as f64, height as f64])
                     },
-                    1 => { // Brightness
-                        if let Some(level) = dialogs::brightness_dialog() {
-                            let operation = Box::new(BrightnessOperation::new(level));
-                            image_service.lock().unwrap().add_operation(operation);
+                    "Brightness" => dialogs::brightness_dialog().map(|level| vec![level as f64]),
+                    "Lens Correction" => {
+                        // A camera module's profile, or values of one's own
+                        let mut profiles = vec!["Custom values".to_string()];
+                        profiles.extend(LENS_PROFILES.iter().map(|profile| format!("{}: {}", profile.name, profile.description)));
+                        match dialogs::select_dialog("Lens Correction", "Correct the lens of:", &profiles) {
+                            Some(0) => dialogs::operation_params_dialog(plugin.name, plugin.params, "Add"),
+                            Some(i) => Some(vec![LENS_PROFILES[i - 1].distortion, LENS_PROFILES[i - 1].vignette]),
+                            None => None,
                         }
                     },
-                    // Add more operation types as needed
-                    _ => return,
+                    _ => dialogs::operation_params_dialog(plugin.name, plugin.params, "Add"),
+                };
+                let Some(values) = values else { return };
+                
+                match plugin.create(&values) {
+                    Ok(operation) => image_service.lock().unwrap().add_operation(operation),
+                    Err(e) => {
+                        dialogs::message_dialog("Add Operation", &e.to_string());
+                        return;
+                    }
                 }
                 
                 // Update operations browser
@@ -212,25 +292,189 @@ pub mod operations_panel {
                 operations_browser.clear();
             });
             
-            // Apply button callback
+            // Settings button callback
             let image_service = self.image_service.clone();
-            let processor_browser = self.processor_browser.clone();
+            let mut processor_browser = self.processor_browser.clone();
+            let config = self.config.clone();
             
-            let mut apply_button = self.apply_button.clone();
-            apply_button.set_callback(move |_| {
+            let mut settings_button = self.settings_button.clone();
+            settings_button.set_callback(move |_| {
                 let selected = processor_browser.value();
                 if selected <= 0 {
                     dialogs::message_dialog("Error", "Please select a processor first.");
                     return;
                 }
+                let index = (selected - 1) as usize;
+                
+                let (name, settings, format) = {
+                    let service = image_service.lock().unwrap();
+                    let Some(factory) = service.get_factories().get(index) else { return };
+                    (factory.get_name(), factory.settings(), factory.create_processor().get_format())
+                };
+                if settings.is_empty() {
+                    dialogs::message_dialog("Processor Settings", &trf("{} has no settings.", &[&name]));
+                    return;
+                }
+                let Some(values) = dialogs::operation_params_dialog("Processor Settings", &settings, "Save") else {
+                    return;
+                };
                 
-                let processor_index = selected - 1;
+                if let Some(factory) = image_service.lock().unwrap().get_factory_mut(index) {
+                    factory.apply_settings(&values);
+                }
                 
-                // In a real implementation, this would apply the operations to the current image
-                println!("Applying operations with processor {}", processor_index);
+                // Kept as the defaults for the next start
+                let mut config = config.lock().unwrap();
+                match format {
+                    ImageFormat::JPEG => config.processors.jpeg_quality = values[0].round() as u8,
+                    ImageFormat::PNG => config.processors.png_compression = values[0].round() as u8,
+                    _ => {}
+                }
+                if let Err(e) = config.save() {
+                    dialogs::error_dialog("Failed to save settings", &e);
+                }
+                drop(config);
                 
-                dialogs::message_dialog("Success", "Operations applied successfully.");
+                Self::list_processors(&image_service, &mut processor_browser, selected);
             });
+            
+            // Preview button callback
+            let panel = self.clone();
+            
+            let mut preview_button = self.preview_button.clone();
+            preview_button.set_callback(move |_| panel.preview());
+            
+            // Apply button callback
+            let panel = self.clone();
+            
+            let mut apply_button = self.apply_button.clone();
+            apply_button.set_callback(move |_| panel.apply());
+        }
+        
+        // Run the operations on the middle of the open image at full size
+        // and show it before and after in the view, which switches between
+        // them when clicked
+        pub fn preview(&self) {
+            let size = self.image_view.display_size();
+            let (source, before) = match *self.image_view.document().lock().unwrap() {
+                Some(ref document) => (document.path().to_path_buf(), centre_crop(document.image(), size)),
+                None => {
+                    dialogs::message_dialog("Preview", "Open an image first.");
+                    return;
+                }
+            };
+            if self.image_service.lock().unwrap().get_operations().is_empty() {
+                dialogs::message_dialog("Preview", "Add an operation to preview first.");
+                return;
+            }
+            
+            let image_service = self.image_service.clone();
+            let mut image_view = self.image_view.clone();
+            run_in_background(
+                move || {
+                    let after = image_service
+                        .lock()
+                        .unwrap()
+                        .apply_operations(before.clone())
+                        .map_err(|e| e.to_string());
+                    (before, after)
+                },
+                move |(before, after)| {
+                    // Another image may have been opened meanwhile
+                    if image_view.get_current_image().as_ref() != Some(&source) {
+                        return;
+                    }
+                    match after {
+                        Ok(after) => image_view.compare(&before, &after),
+                        Err(e) => {
+                            log::error!("Previewing operations failed: {}", e);
+                            dialogs::message_dialog("Preview", &trf("Previewing the operations failed:\n{}", &[&e]));
+                        }
+                    }
+                }
+            );
+        }
+        
+        // Run the operations on the open image, keep the result as an edit
+        // that can be undone, save it with the selected processor and show it
+        pub fn apply(&self) {
+            let selected = self.processor_browser.value();
+            if selected <= 0 {
+                dialogs::message_dialog("Error", "Please select a processor first.");
+                return;
+            }
+            let factory_index = (selected - 1) as usize;
+            
+            let document = self.image_view.document();
+            let (source, dimensions, image) = match *document.lock().unwrap() {
+                Some(ref document) => (document.path().to_path_buf(), document.dimensions(), document.image().clone()),
+                None => {
+                    dialogs::message_dialog("Apply Operations", "Open an image first.");
+                    return;
+                }
+            };
+            
+            let format = {
+                let service = self.image_service.lock().unwrap();
+                if service.get_operations().is_empty() {
+                    dialogs::message_dialog("Apply Operations", "Add an operation to apply first.");
+                    return;
+                }
+                match service.get_factories().get(factory_index) {
+                    Some(factory) => factory.create_processor().get_format(),
+                    None => return,
+                }
+            };
+            
+            // The naming template picks the name; Save As lets the user choose
+            let template = self.config.lock().unwrap().output_name_template.clone();
+            let context = OutputNameContext::new(&source, format, PIPELINE_SUFFIX, 1).with_size(Some(dimensions));
+            let named = generate_output_filename(&source, &template, &context);
+            let name = named.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let question = if named.exists() {
+                format!("Save the result as \"{}\"? It replaces the file of that name.", name)
+            } else {
+                format!("Save the result as \"{}\" next to the original?", name)
+            };
+            let output = match dialogs::choice_dialog("Apply Operations", &question, &["Save", "Save As...", "Cancel"]) {
+                0 => named,
+                1 => match dialogs::save_file_dialog("Save Result As", "") {
+                    Some(path) => path,
+                    None => return,
+                },
+                _ => return,
+            };
+            
+            log::info!("Applying operations to {} with processor {}", source.display(), factory_index);
+            
+            let image_service = self.image_service.clone();
+            let service = self.image_service.clone();
+            let mut image_view = self.image_view.clone();
+            run_in_background(
+                move || image_service.lock().unwrap().apply_operations(image).map_err(|e| e.to_string()),
+                move |result| {
+                    let saved = result.and_then(|edited| match *document.lock().unwrap() {
+                        // Another image may have been opened meanwhile
+                        Some(ref mut document) if document.path() == source => {
+                            document.replace(edited);
+                            document.save_as(&output, &service.lock().unwrap(), factory_index).map_err(|e| e.to_string())
+                        },
+                        _ => Err("The image was closed before the operations finished".to_string()),
+                    });
+                    
+                    image_view.refresh();
+                    match saved {
+                        Ok(()) => {
+                            let name = output.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+                            notify(ToastKind::Success, "Operations", &trf("Saved {}", &[&name]));
+                        },
+                        Err(e) => {
+                            log::error!("Applying operations failed: {}", e);
+                            dialogs::message_dialog("Apply Operations", &trf("Applying the operations failed:\n{}", &[&e]));
+                        }
+                    }
+                }
+            );
         }
         
         fn update_operations_browser(
@@ -246,4 +490,12 @@ pub mod operations_panel {
             }
         }
     }
+    
+    // The middle of `image` at full size, as much as fits `size`; all of
+    // it when it is smaller
+    fn centre_crop(image: &DynamicImage, (w, h): (i32, i32)) -> DynamicImage {
+        let width = (w.max(1) as u32).min(image.width());
+        let height = (h.max(1) as u32).min(image.height());
+        image.crop_imm((image.width() - width) / 2, (image.height() - height) / 2, width, height)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::config::PostDownloadProcessing;
+use crate::core::image::{ImageProcessingService, ProcessingPreset, BUILTIN_PRESETS};
+use crate::core::image_utils::is_image_file;
+use crate::core::upload_target::UploadTarget;
+use crate::ui::dialogs::dialogs;
+use crate::ui::processing_queue::{Pipeline, ProcessingJob, ProcessingQueue};
+
+/// Queue the processing `settings` ask for on `download`, when enabled and
+/// the file is an image
+///
+/// Without a preset the operations currently set up in the Image Processing
+/// tab (`image_service`) are run; results are named by `name_template` and
+/// also sent to `upload`, the preset's destination.
+pub fn process_download(
+    download: PathBuf,
+    settings: &PostDownloadProcessing,
+    name_template: &str,
+    upload: Option<Box<dyn UploadTarget>>,
+    image_service: Arc<Mutex<ImageProcessingService>>,
+    queue: &ProcessingQueue
+) {
+    if !settings.enabled || !is_image_file(&download) {
+        return;
+    }
+
+    let pipeline = match &settings.preset {
+        Some(name) => match ProcessingPreset::find(name) {
+            Some(preset) => Pipeline::Preset(preset),
+            None => {
+                log::warn!("Not processing {}: unknown preset '{}'", download.display(), name);
+                return;
+            }
+        },
+        None => Pipeline::Current(image_service),
+    };
+
+    queue.enqueue(ProcessingJob {
+        name: download.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        inputs: vec![download],
+        output_dir: settings.output_dir.as_ref().map(PathBuf::from),
+        pipeline,
+        name_template: name_template.to_string(),
+        upload,
+    });
+}
+
+/// Ask which processing to run on downloads and where results go
+///
+/// Returns None when cancelled; `enabled` is kept from `current`.
+pub fn settings_dialog(current: &PostDownloadProcessing) -> Option<PostDownloadProcessing> {
+    let mut pipelines = vec!["Operations in the Image Processing tab".to_string()];
+    pipelines.extend(BUILTIN_PRESETS.iter().map(|p| format!("Preset '{}': {}", p.name, p.description)));
+
+    let choice = dialogs::select_dialog("Process Downloads", "Run on every downloaded image:", &pipelines)?;
+    let preset = match choice {
+        0 => None,
+        i => BUILTIN_PRESETS.get(i - 1).map(|p| p.name.to_string()),
+    };
+
+    let output_dir = match dialogs::choice_dialog(
+        "Process Downloads",
+        "Where should the processed copies go?",
+        &["Next to File", "Folder...", "Cancel"]
+    ) {
+        0 => None,
+        1 => Some(dialogs::open_directory_dialog("Processed Downloads Folder")?.to_string_lossy().to_string()),
+        _ => return None,
+    };
+
+    Some(PostDownloadProcessing {
+        enabled: current.enabled,
+        preset,
+        output_dir,
+    })
+}
@@ -8,7 +8,10 @@ use fltk::{
 
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::process::Command;
+
+use crate::core::utils::open_with_default_app;
+use crate::ui::dialogs::dialogs;
+use crate::ui::theme::font_size;
 
 /// Component for previewing document files (PDF, DOC, etc.)
 pub struct DocumentPreviewComponent {
@@ -55,7 +58,7 @@ impl DocumentPreviewComponent {
         );
         info_frame.set_frame(FrameType::BorderFrame);
         info_frame.set_color(Color::from_rgb(245, 245, 245));
-        info_frame.set_label_size(14);
+        info_frame.set_label_size(font_size(14));
         info_frame.set_align(Align::Center | Align::Inside);
         
         // Add button to open the file externally
@@ -75,7 +78,7 @@ impl DocumentPreviewComponent {
         
         group.end();
         
-        let preview = DocumentPreviewComponent {
+        let mut preview = DocumentPreviewComponent {
             group,
             info_frame,
             open_button,
@@ -90,20 +93,9 @@ impl DocumentPreviewComponent {
                 guard.clone()
             } {
                 // Open the file with the default system application
-                #[cfg(target_os = "windows")]
-                let _ = Command::new("cmd")
-                    .args(&["/c", "start", "", &path.to_string_lossy()])
-                    .spawn();
-                
-                #[cfg(target_os = "macos")]
-                let _ = Command::new("open")
-                    .arg(&path)
-                    .spawn();
-                
-                #[cfg(target_os = "linux")]
-                let _ = Command::new("xdg-open")
-                    .arg(&path)
-                    .spawn();
+                if let Err(e) = open_with_default_app(&path) {
+                    dialogs::error_dialog("Failed to open file", &e);
+                }
             }
         });
         
@@ -1,7 +1,7 @@
 use fltk::{
     enums::{Color, FrameType},
     group::Group,
-    image::{JpegImage, PngImage, GifImage, BmpImage, SvgImage, ImageExt},
+    image::{JpegImage, PngImage, GifImage, BmpImage, SvgImage},
     prelude::*,
 };
 
@@ -88,9 +88,9 @@ impl ImagePreviewComponent {
             // Store the current image path
             let mut current = self.current_image.lock().unwrap();
             *current = Some(path.to_path_buf());
-            println!("Successfully loaded image: {}", path.display());
+            log::info!("Successfully loaded image: {}", path.display());
         } else {
-            println!("Failed to load image: {}", path.display());
+            log::error!("Failed to load image: {}", path.display());
         }
         
         // Force a redraw of the entire component
@@ -0,0 +1,261 @@
+use fltk::{
+    button::ToggleButton,
+    enums::{Color, FrameType, Font, Align},
+    group::Group,
+    misc::HelpView,
+    text::{TextDisplay, TextBuffer},
+    frame::Frame,
+    prelude::*,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::core::file::{get_text_preview, markdown_to_html};
+use crate::core::locale::tr;
+use crate::ui::theme::font_size;
+
+/// Height of the toolbar holding the rendered/raw toggle
+const TOOLBAR_HEIGHT: i32 = 30;
+
+/// Component for previewing Markdown files, either rendered or as raw source
+pub struct MarkdownPreviewComponent {
+    /// Container group
+    group: Group,
+    /// Rendered HTML view
+    help_view: HelpView,
+    /// Raw source display
+    raw_display: TextDisplay,
+    /// Raw source buffer
+    raw_buffer: TextBuffer,
+    /// Toggle between rendered and raw mode
+    raw_toggle: ToggleButton,
+    /// Error message frame
+    error_frame: Frame,
+    /// Currently loaded file path
+    current_file: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl Clone for MarkdownPreviewComponent {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            help_view: self.help_view.clone(),
+            raw_display: self.raw_display.clone(),
+            raw_buffer: self.raw_buffer.clone(),
+            raw_toggle: self.raw_toggle.clone(),
+            error_frame: self.error_frame.clone(),
+            current_file: self.current_file.clone(),
+        }
+    }
+}
+
+impl MarkdownPreviewComponent {
+    /// Create a new Markdown preview component
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::FlatBox);
+
+        let padding = 5;
+        let content_x = x + padding;
+        let content_y = y + padding + TOOLBAR_HEIGHT;
+        let content_w = w - 2 * padding;
+        let content_h = h - 2 * padding - TOOLBAR_HEIGHT;
+
+        // Toggle between rendered and raw source
+        let mut raw_toggle = ToggleButton::new(
+            x + w - padding - 100,
+            y + padding,
+            100,
+            TOOLBAR_HEIGHT - padding,
+            "Show Raw"
+        );
+        raw_toggle.set_tooltip("Switch between rendered Markdown and raw source");
+
+        // Rendered view
+        let mut help_view = HelpView::new(
+            content_x,
+            content_y,
+            content_w,
+            content_h,
+            None
+        );
+        help_view.set_frame(FrameType::BorderFrame);
+        help_view.set_text_size(font_size(13));
+
+        // Raw source view (initially hidden)
+        let raw_buffer = TextBuffer::default();
+
+        let mut raw_display = TextDisplay::new(
+            content_x,
+            content_y,
+            content_w,
+            content_h,
+            None
+        );
+        raw_display.set_buffer(raw_buffer.clone());
+        raw_display.set_frame(FrameType::BorderFrame);
+        raw_display.set_color(Color::from_rgb(250, 250, 250));
+        raw_display.set_text_font(Font::Courier);
+        raw_display.set_text_size(font_size(12));
+        raw_display.wrap_mode(fltk::text::WrapMode::AtBounds, 0);
+        raw_display.hide();
+
+        // Error message frame (initially hidden)
+        let mut error_frame = Frame::new(
+            content_x,
+            content_y,
+            content_w,
+            content_h,
+            None
+        );
+        error_frame.set_frame(FrameType::BorderFrame);
+        error_frame.set_color(Color::from_rgb(250, 240, 240));
+        error_frame.set_label_size(font_size(12));
+        error_frame.set_align(Align::Center | Align::Inside);
+        error_frame.hide();
+
+        group.end();
+
+        let mut preview = MarkdownPreviewComponent {
+            group,
+            help_view,
+            raw_display,
+            raw_buffer,
+            raw_toggle,
+            error_frame,
+            current_file: Arc::new(Mutex::new(None)),
+        };
+
+        preview.setup_callbacks();
+
+        preview
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut help_view = self.help_view.clone();
+        let mut raw_display = self.raw_display.clone();
+        let error_frame = self.error_frame.clone();
+
+        self.raw_toggle.set_callback(move |b| {
+            // Leave the error message in place until a file loads successfully
+            if error_frame.visible() {
+                return;
+            }
+
+            if b.value() {
+                help_view.hide();
+                raw_display.show();
+                b.set_label(&tr("Show Rendered"));
+            } else {
+                raw_display.hide();
+                help_view.show();
+                b.set_label(&tr("Show Raw"));
+            }
+
+            if let Some(mut parent) = b.parent() {
+                parent.redraw();
+            }
+        });
+    }
+
+    /// Load and display a Markdown file
+    pub fn load_markdown(&mut self, path: &Path) -> bool {
+        if !path.exists() {
+            return false;
+        }
+
+        // Clear any previous content
+        self.clear();
+
+        match get_text_preview(path) {
+            Ok(content) => {
+                self.raw_buffer.set_text(&content);
+                self.help_view.set_value(&markdown_to_html(&content));
+                self.help_view.set_top_line(0);
+                self.raw_display.scroll(0, 0);
+
+                self.apply_mode();
+
+                // Store the current file path
+                let mut current = self.current_file.lock().unwrap();
+                *current = Some(path.to_path_buf());
+
+                true
+            },
+            Err(e) => {
+                self.show_error(&format!("Error reading file: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Whether the raw source is currently shown instead of the rendered view
+    pub fn is_raw_mode(&self) -> bool {
+        self.raw_toggle.value()
+    }
+
+    /// Switch between raw and rendered mode
+    pub fn set_raw_mode(&mut self, raw: bool) {
+        self.raw_toggle.set_value(raw);
+        self.apply_mode();
+    }
+
+    /// Show the view matching the toggle state
+    fn apply_mode(&mut self) {
+        self.error_frame.hide();
+
+        if self.raw_toggle.value() {
+            self.help_view.hide();
+            self.raw_display.show();
+            self.raw_toggle.set_label(&tr("Show Rendered"));
+        } else {
+            self.raw_display.hide();
+            self.help_view.show();
+            self.raw_toggle.set_label(&tr("Show Raw"));
+        }
+
+        self.group.redraw();
+    }
+
+    /// Display an error message
+    fn show_error(&mut self, message: &str) {
+        self.help_view.hide();
+        self.raw_display.hide();
+        self.error_frame.set_label(message);
+        self.error_frame.show();
+
+        // Force redraw
+        self.group.redraw();
+    }
+
+    /// Get the current file path
+    pub fn get_current_file(&self) -> Option<PathBuf> {
+        let current = self.current_file.lock().unwrap();
+        current.clone()
+    }
+
+    /// Clear the Markdown display
+    pub fn clear(&mut self) {
+        self.raw_buffer.set_text("");
+        self.help_view.set_value("");
+        self.error_frame.hide();
+
+        // Clear the path reference
+        let mut current = self.current_file.lock().unwrap();
+        *current = None;
+
+        // Force a redraw
+        self.group.redraw();
+    }
+
+    /// Hide the component
+    pub fn hide(&mut self) {
+        self.group.hide();
+    }
+
+    /// Show the component
+    pub fn show(&mut self) {
+        self.group.show();
+    }
+}
@@ -1 +1,9 @@
-pub mod preview;
\ No newline at end of file
+pub mod preview_panel;
+pub mod image_preview;
+pub mod text_preview;
+pub mod document_preview;
+pub mod markdown_preview;
+pub mod table_preview;
+
+// Re-export the unified preview panel
+pub use preview_panel::PreviewPanel;
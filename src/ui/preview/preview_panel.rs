@@ -4,12 +4,20 @@ use fltk::{
     prelude::*,
 };
 
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::process::Child;
 
-use crate::core::file::{FileType, get_file_type_info};
+use arboard::{Clipboard, ImageData};
+
+use crate::core::file::{FileType, get_file_type_info, is_markdown_file, is_table_file, temp_download_path};
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::method::TransferMethod;
 use crate::ui::preview::image_preview::ImagePreviewComponent;
 use crate::ui::preview::text_preview::TextPreviewComponent;
+use crate::ui::preview::markdown_preview::MarkdownPreviewComponent;
+use crate::ui::preview::table_preview::TablePreviewComponent;
 
 /// A unified preview panel that can display various file types
 pub struct PreviewPanel {
@@ -19,6 +27,10 @@ pub struct PreviewPanel {
     image_preview: ImagePreviewComponent,
     /// Text preview component
     text_preview: TextPreviewComponent,
+    /// Markdown preview component
+    markdown_preview: MarkdownPreviewComponent,
+    /// CSV/TSV table preview component
+    table_preview: TablePreviewComponent,
     /// Currently active preview type
     current_type: Option<FileType>,
     /// Currently previewed file path
@@ -31,6 +43,8 @@ impl Clone for PreviewPanel {
             group: self.group.clone(),
             image_preview: self.image_preview.clone(),
             text_preview: self.text_preview.clone(),
+            markdown_preview: self.markdown_preview.clone(),
+            table_preview: self.table_preview.clone(),
             current_type: self.current_type,
             current_file: self.current_file.clone(),
         }
@@ -45,21 +59,31 @@ impl PreviewPanel {
         group.set_frame(FrameType::FlatBox);
         
         // Create image preview component (initially hidden)
-        let image_preview = ImagePreviewComponent::new(x, y, w, h);
+        let mut image_preview = ImagePreviewComponent::new(x, y, w, h);
         
         // Create text preview component (initially hidden)
-        let text_preview = TextPreviewComponent::new(x, y, w, h);
+        let mut text_preview = TextPreviewComponent::new(x, y, w, h);
+        
+        // Create Markdown preview component (initially hidden)
+        let mut markdown_preview = MarkdownPreviewComponent::new(x, y, w, h);
+        
+        // Create table preview component (initially hidden)
+        let mut table_preview = TablePreviewComponent::new(x, y, w, h);
         
         group.end();
         
         // Hide all preview components initially
         image_preview.hide();
         text_preview.hide();
+        markdown_preview.hide();
+        table_preview.hide();
         
         PreviewPanel {
             group,
             image_preview,
             text_preview,
+            markdown_preview,
+            table_preview,
             current_type: None,
             current_file: Arc::new(Mutex::new(None)),
         }
@@ -67,23 +91,28 @@ impl PreviewPanel {
     
     /// Preview a file
     pub fn preview_file(&mut self, path: &Path) -> bool {
+        // Keep the current preview if it has edits the user wants to keep
+        if !self.text_preview.confirm_discard() {
+            return false;
+        }
+        
         // Clear any existing preview
         self.clear();
         
         // Check if file exists
         if !path.exists() {
-            println!("Preview file doesn't exist: {}", path.display());
+            log::info!("Preview file doesn't exist: {}", path.display());
             return false;
         }
         
         // Get file type info
         let file_type_info = get_file_type_info(path);
         if !file_type_info.previewable {
-            println!("File type not supported for preview: {}", path.display());
+            log::info!("File type not supported for preview: {}", path.display());
             return false;
         }
         
-        println!("Previewing file: {} (type: {:?})", path.display(), file_type_info.file_type);
+        log::info!("Previewing file: {} (type: {:?})", path.display(), file_type_info.file_type);
         
         // Store current file and type
         self.current_type = Some(file_type_info.file_type);
@@ -98,6 +127,14 @@ impl PreviewPanel {
                 self.image_preview.show();
                 self.image_preview.load_image(path)
             },
+            FileType::Text if is_markdown_file(path) => {
+                self.markdown_preview.show();
+                self.markdown_preview.load_markdown(path)
+            },
+            FileType::Text if is_table_file(path) => {
+                self.table_preview.show();
+                self.table_preview.load_table(path)
+            },
             FileType::Text | FileType::Code => {
                 self.text_preview.show();
                 self.text_preview.load_text(path)
@@ -108,7 +145,7 @@ impl PreviewPanel {
                 self.text_preview.load_text(path)
             },
             _ => {
-                println!("Unsupported preview type: {:?}", file_type_info.file_type);
+                log::info!("Unsupported preview type: {:?}", file_type_info.file_type);
                 false
             }
         };
@@ -128,6 +165,12 @@ impl PreviewPanel {
         self.text_preview.clear();
         self.text_preview.hide();
         
+        self.markdown_preview.clear();
+        self.markdown_preview.hide();
+        
+        self.table_preview.clear();
+        self.table_preview.hide();
+        
         // Reset state
         self.current_type = None;
         {
@@ -150,8 +193,94 @@ impl PreviewPanel {
         self.current_type
     }
     
+    /// Preview `local_path`, a copy of `remote_path` on the Pi behind
+    /// `method`; edited text files can be saved back to the Pi
+    pub fn preview_remote_file(&mut self, local_path: &Path, method: Box<dyn TransferMethod>, remote_path: PathBuf) -> bool {
+        if !self.preview_file(local_path) {
+            return false;
+        }
+        self.text_preview.set_remote_source(method, remote_path);
+        true
+    }
+    
+    /// Preview a text file on the Pi that is too large to download, reading
+    /// it through `method` a chunk at a time
+    pub fn stream_remote_file(&mut self, method: Box<dyn TransferMethod>, remote_path: PathBuf) -> bool {
+        if !self.text_preview.confirm_discard() {
+            return false;
+        }
+        self.clear();
+        
+        self.current_type = Some(FileType::Text);
+        self.text_preview.show();
+        let result = self.text_preview.stream_remote(method, remote_path);
+        
+        self.group.redraw();
+        
+        result
+    }
+    
+    /// Stream the output of a running remote command into the text preview
+    pub fn follow_command(&mut self, child: Child, description: &str) -> bool {
+        if !self.text_preview.confirm_discard() {
+            return false;
+        }
+        self.clear();
+        
+        self.current_type = Some(FileType::Text);
+        self.text_preview.show();
+        let result = self.text_preview.follow(child, description);
+        
+        self.group.redraw();
+        
+        result
+    }
+    
+    /// Switch the Markdown preview between raw source and rendered output
+    pub fn set_markdown_raw_mode(&mut self, raw: bool) {
+        self.markdown_preview.set_raw_mode(raw);
+    }
+    
     /// Back-compatibility alias for ImageViewPanel
     pub fn load_image(&mut self, path: &Path) -> bool {
         self.preview_file(path)
     }
+    
+    /// Save the image on the system clipboard to a temporary PNG file and
+    /// preview it; returns the file so it can be uploaded
+    pub fn paste_clipboard_image(&mut self) -> AppResult<PathBuf> {
+        let image = Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_image())
+            .map_err(|e| AppError::FileError(format!("No image on the clipboard: {}", e)))?;
+        let buffer = ::image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned())
+            .ok_or_else(|| AppError::FileError("The clipboard image has an unexpected size".to_string()))?;
+        
+        let name = format!("clipboard-{}.png", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+        let path = temp_download_path(&name)?;
+        buffer.save(&path).map_err(|e| AppError::FileError(format!("Could not save the clipboard image: {}", e)))?;
+        log::info!("Saved clipboard image to {}", path.display());
+        
+        self.preview_file(&path);
+        Ok(path)
+    }
+    
+    /// Put the previewed image on the system clipboard
+    pub fn copy_image_to_clipboard(&self) -> AppResult<()> {
+        let path = match (self.current_type, self.get_current_file()) {
+            (Some(FileType::Image), Some(path)) => path,
+            _ => return Err(AppError::FileError("No image is being previewed".to_string())),
+        };
+        let image = ::image::open(&path)
+            .map_err(|e| AppError::FileError(format!("{}: {}", path.display(), e)))?
+            .to_rgba8();
+        
+        let data = ImageData {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            bytes: Cow::Owned(image.into_raw()),
+        };
+        Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_image(data))
+            .map_err(|e| AppError::FileError(format!("Could not copy to the clipboard: {}", e)))
+    }
 }
\ No newline at end of file
@@ -0,0 +1,266 @@
+use fltk::{
+    draw,
+    enums::{Color, FrameType, Font, Align},
+    group::Group,
+    table::{Table, TableContext},
+    frame::Frame,
+    prelude::*,
+};
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::core::file::{TableData, read_table_preview, MAX_TABLE_PREVIEW_ROWS};
+use crate::ui::theme::font_size;
+
+/// Height of the status line above the table
+const STATUS_HEIGHT: i32 = 20;
+
+/// Component for previewing CSV/TSV files as a table
+pub struct TablePreviewComponent {
+    /// Container group
+    group: Group,
+    /// Table widget
+    table: Table,
+    /// Row count / truncation status line
+    status_frame: Frame,
+    /// Error message frame
+    error_frame: Frame,
+    /// Parsed table contents shared with the draw callback
+    data: Arc<Mutex<TableData>>,
+    /// Currently loaded file path
+    current_file: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl Clone for TablePreviewComponent {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            table: self.table.clone(),
+            status_frame: self.status_frame.clone(),
+            error_frame: self.error_frame.clone(),
+            data: self.data.clone(),
+            current_file: self.current_file.clone(),
+        }
+    }
+}
+
+impl TablePreviewComponent {
+    /// Create a new table preview component
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::FlatBox);
+
+        let padding = 5;
+        let content_x = x + padding;
+        let content_w = w - 2 * padding;
+
+        // Status line
+        let mut status_frame = Frame::new(
+            content_x,
+            y + padding,
+            content_w,
+            STATUS_HEIGHT,
+            None
+        );
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        let table_y = y + padding + STATUS_HEIGHT + padding;
+        let table_h = h - (table_y - y) - padding;
+
+        let mut table = Table::new(content_x, table_y, content_w, table_h, None);
+        table.set_rows(0);
+        table.set_cols(0);
+        table.set_row_header(true);
+        table.set_row_header_width(50);
+        table.set_col_header(true);
+        table.set_col_resize(true);
+        table.set_row_height_all(22);
+        table.end();
+
+        // Error message frame (initially hidden)
+        let mut error_frame = Frame::new(
+            content_x,
+            table_y,
+            content_w,
+            table_h,
+            None
+        );
+        error_frame.set_frame(FrameType::BorderFrame);
+        error_frame.set_color(Color::from_rgb(250, 240, 240));
+        error_frame.set_label_size(font_size(12));
+        error_frame.set_align(Align::Center | Align::Inside);
+        error_frame.hide();
+
+        group.end();
+
+        let mut preview = TablePreviewComponent {
+            group,
+            table,
+            status_frame,
+            error_frame,
+            data: Arc::new(Mutex::new(TableData::default())),
+            current_file: Arc::new(Mutex::new(None)),
+        };
+
+        preview.setup_draw();
+
+        preview
+    }
+
+    fn setup_draw(&mut self) {
+        let data = self.data.clone();
+
+        self.table.draw_cell(move |t, ctx, row, col, x, y, w, h| {
+            match ctx {
+                TableContext::StartPage => draw::set_font(Font::Helvetica, 12),
+                TableContext::ColHeader => {
+                    let label = data.lock().unwrap().header(col as usize);
+                    Self::draw_header(&label, x, y, w, h);
+                },
+                TableContext::RowHeader => {
+                    Self::draw_header(&(row + 1).to_string(), x, y, w, h);
+                },
+                TableContext::Cell => {
+                    let data = data.lock().unwrap();
+                    let value = data.cell(row as usize, col as usize);
+                    Self::draw_data(value, x, y, w, h, t.is_selected(row, col));
+                },
+                _ => (),
+            }
+        });
+    }
+
+    fn draw_header(text: &str, x: i32, y: i32, w: i32, h: i32) {
+        draw::push_clip(x, y, w, h);
+        draw::draw_box(FrameType::ThinUpBox, x, y, w, h, Color::FrameDefault);
+        draw::set_draw_color(Color::Black);
+        draw::set_font(Font::HelveticaBold, 12);
+        draw::draw_text2(text, x + 2, y, w - 4, h, Align::Left);
+        draw::pop_clip();
+    }
+
+    fn draw_data(text: &str, x: i32, y: i32, w: i32, h: i32, selected: bool) {
+        draw::push_clip(x, y, w, h);
+        if selected {
+            draw::set_draw_color(Color::from_rgb(210, 225, 245));
+        } else {
+            draw::set_draw_color(Color::White);
+        }
+        draw::draw_rectf(x, y, w, h);
+        draw::set_draw_color(Color::Black);
+        draw::set_font(Font::Helvetica, 12);
+        draw::draw_text2(text, x + 3, y, w - 6, h, Align::Left);
+        draw::set_draw_color(Color::Light2);
+        draw::draw_rect(x, y, w, h);
+        draw::pop_clip();
+    }
+
+    /// Load and display a CSV/TSV file
+    pub fn load_table(&mut self, path: &Path) -> bool {
+        if !path.exists() {
+            return false;
+        }
+
+        // Clear any previous content
+        self.clear();
+
+        match read_table_preview(path, MAX_TABLE_PREVIEW_ROWS) {
+            Ok(table_data) => {
+                let rows = table_data.rows.len();
+                let cols = table_data.column_count();
+
+                let status = if table_data.truncated {
+                    format!("{} columns, showing first {} rows", cols, rows)
+                } else {
+                    format!("{} columns, {} rows", cols, rows)
+                };
+
+                // Size columns to their widest content, within reasonable bounds
+                let widths: Vec<i32> = (0..cols)
+                    .map(|col| {
+                        let widest = table_data.rows
+                            .iter()
+                            .map(|row| row.get(col).map(|c| c.chars().count()).unwrap_or(0))
+                            .chain(std::iter::once(table_data.header(col).chars().count()))
+                            .max()
+                            .unwrap_or(0) as i32;
+                        (widest * 7 + 12).clamp(60, 300)
+                    })
+                    .collect();
+
+                *self.data.lock().unwrap() = table_data;
+
+                self.table.set_rows(rows as i32);
+                self.table.set_cols(cols as i32);
+                for (col, width) in widths.into_iter().enumerate() {
+                    self.table.set_col_width(col as i32, width);
+                }
+                self.table.set_row_position(0);
+                self.table.set_col_position(0);
+
+                self.status_frame.set_label(&status);
+                self.table.show();
+                self.error_frame.hide();
+
+                // Store the current file path
+                let mut current = self.current_file.lock().unwrap();
+                *current = Some(path.to_path_buf());
+
+                self.group.redraw();
+
+                true
+            },
+            Err(e) => {
+                self.show_error(&e);
+                false
+            }
+        }
+    }
+
+    /// Display an error message
+    fn show_error(&mut self, message: &str) {
+        self.table.hide();
+        self.status_frame.set_label("");
+        self.error_frame.set_label(message);
+        self.error_frame.show();
+
+        // Force redraw
+        self.group.redraw();
+    }
+
+    /// Get the current file path
+    pub fn get_current_file(&self) -> Option<PathBuf> {
+        let current = self.current_file.lock().unwrap();
+        current.clone()
+    }
+
+    /// Clear the table display
+    pub fn clear(&mut self) {
+        *self.data.lock().unwrap() = TableData::default();
+
+        self.table.set_rows(0);
+        self.table.set_cols(0);
+        self.status_frame.set_label("");
+        self.error_frame.hide();
+        self.table.show();
+
+        // Clear the path reference
+        let mut current = self.current_file.lock().unwrap();
+        *current = None;
+
+        // Force a redraw
+        self.group.redraw();
+    }
+
+    /// Hide the component
+    pub fn hide(&mut self) {
+        self.group.hide();
+    }
+
+    /// Show the component
+    pub fn show(&mut self) {
+        self.group.show();
+    }
+}
@@ -1,19 +1,78 @@
 use fltk::{
     enums::{Color, FrameType, Font, Align},
     group::Group,
-    text::{TextDisplay, TextBuffer},
+    text::{TextDisplay, TextEditor, TextBuffer},
     frame::Frame,
+    button::{Button, ToggleButton},
     prelude::*,
+    app,
 };
 
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::process::Child;
+use std::io::{BufRead, BufReader};
 use std::fs;
+use std::thread::{self, JoinHandle};
 
-use crate::core::file::get_text_preview;
+use crate::core::file::{
+    get_text_preview,
+    last_lines_of,
+    lines_from_bytes,
+    needs_streamed_preview,
+    read_lines_from,
+    read_last_lines,
+    remote_file_size_command,
+    remote_range_command,
+    remote_tail_command,
+    temp_download_path,
+    TextChunk,
+    REMOTE_CHUNK_BYTES,
+};
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::method::TransferMethod;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::ui::theme::font_size;
+
+/// Number of lines loaded per chunk when streaming large files
+const STREAM_CHUNK_LINES: usize = 2000;
+
+/// Height of the toolbar above the text display
+const TOOLBAR_HEIGHT: i32 = 30;
+
+/// How often followed output is flushed into the display (seconds)
+const FOLLOW_POLL_INTERVAL: f64 = 0.25;
+
+/// Buffer size after which the oldest followed output is discarded
+const FOLLOW_BUFFER_LIMIT: i32 = 2 * 1024 * 1024;
+
+/// Progress through a file that is too large to load at once
+#[derive(Default)]
+struct StreamState {
+    /// File being streamed, if the current file is large
+    path: Option<PathBuf>,
+    /// Byte offset where the next head chunk starts
+    next_offset: u64,
+    /// Lines loaded so far in head mode
+    lines_loaded: usize,
+    /// Whether the head reader has reached the end of the file
+    eof: bool,
+    /// Whether the last lines are shown instead of the first
+    tail_mode: bool,
+    /// Connection to read through when the file is on the Pi
+    remote: Option<Arc<dyn TransferMethod>>,
+    /// Size of the file on the Pi when it was opened
+    remote_size: u64,
+}
 
-/// Maximum file size for text preview (5MB)
-const MAX_TEXT_SIZE: u64 = 5 * 1024 * 1024;
+/// The file on the Pi a previewed copy was downloaded from
+struct RemoteSource {
+    method: Box<dyn TransferMethod>,
+    path: PathBuf,
+}
 
 /// Component for previewing text files
 pub struct TextPreviewComponent {
@@ -21,28 +80,61 @@ pub struct TextPreviewComponent {
     group: Group,
     /// Text display widget
     text_display: TextDisplay,
+    /// Editor over the same buffer, shown instead of the display in edit mode
+    text_editor: TextEditor,
     /// Text buffer
     text_buffer: TextBuffer,
     /// Error message frame
     error_frame: Frame,
+    /// Status line describing what portion of the file is shown
+    status_frame: Frame,
+    /// Loads the next chunk of a streamed file
+    load_more_button: Button,
+    /// Switches a streamed file between head and tail view
+    tail_toggle: ToggleButton,
+    /// Stops following a remote command
+    stop_button: Button,
+    /// Switches a fully loaded file between viewing and editing
+    edit_toggle: ToggleButton,
+    /// Saves the edited text to a local file
+    save_button: Button,
+    /// Saves the edited text back to the file on the Pi
+    upload_button: Button,
+    /// Text as last loaded or saved, to tell whether there are unsaved edits
+    saved_text: Arc<Mutex<String>>,
+    /// Where the previewed copy came from, when it was downloaded from the Pi
+    remote_source: Arc<Mutex<Option<RemoteSource>>>,
+    /// Running command whose output is being followed
+    follow_child: Arc<Mutex<Option<Child>>>,
+    /// Incremented whenever a follow starts or is discarded, so stale timers stop
+    follow_generation: Arc<AtomicUsize>,
+    /// Streaming state for large files
+    stream_state: Arc<Mutex<StreamState>>,
     /// Currently loaded file path
     current_file: Arc<Mutex<Option<PathBuf>>>,
 }
 
 impl Clone for TextPreviewComponent {
     fn clone(&self) -> Self {
-        // Create a new text buffer when cloning
-        let text_buffer = TextBuffer::default();
-        
-        // We need to update the text display with the new buffer
-        let mut text_display = self.text_display.clone();
-        text_display.set_buffer(text_buffer.clone());
-        
+        // Share the buffer so callbacks wired at construction stay attached
         Self {
             group: self.group.clone(),
-            text_display,
-            text_buffer,
+            text_display: self.text_display.clone(),
+            text_editor: self.text_editor.clone(),
+            text_buffer: self.text_buffer.clone(),
             error_frame: self.error_frame.clone(),
+            status_frame: self.status_frame.clone(),
+            load_more_button: self.load_more_button.clone(),
+            tail_toggle: self.tail_toggle.clone(),
+            stop_button: self.stop_button.clone(),
+            edit_toggle: self.edit_toggle.clone(),
+            save_button: self.save_button.clone(),
+            upload_button: self.upload_button.clone(),
+            saved_text: self.saved_text.clone(),
+            remote_source: self.remote_source.clone(),
+            follow_child: self.follow_child.clone(),
+            follow_generation: self.follow_generation.clone(),
+            stream_state: self.stream_state.clone(),
             current_file: self.current_file.clone(),
         }
     }
@@ -53,17 +145,90 @@ impl TextPreviewComponent {
     pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
         let mut group = Group::new(x, y, w, h, None);
         group.set_frame(FrameType::FlatBox);
-        
+
         // Add text display area
         let padding = 5;
         let display_x = x + padding;
-        let display_y = y + padding;
+        let display_y = y + padding + TOOLBAR_HEIGHT;
         let display_w = w - 2 * padding;
-        let display_h = h - 2 * padding;
-        
+        let display_h = h - 2 * padding - TOOLBAR_HEIGHT;
+
+        // Toolbar: status on the left, editing and streaming controls on the right
+        let button_w = 90;
+        let mut status_frame = Frame::new(
+            display_x,
+            y + padding,
+            display_w - 5 * button_w - 5 * padding,
+            TOOLBAR_HEIGHT - padding,
+            None
+        );
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        let mut edit_toggle = ToggleButton::new(
+            x + w - 5 * (button_w + padding),
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Edit"
+        );
+        edit_toggle.set_tooltip("Edit this file");
+        edit_toggle.deactivate();
+
+        let mut save_button = Button::new(
+            x + w - 4 * (button_w + padding),
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Save"
+        );
+        save_button.set_tooltip("Save the edited text to this computer");
+        save_button.deactivate();
+
+        let mut upload_button = Button::new(
+            x + w - 3 * (button_w + padding),
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Save to Pi"
+        );
+        upload_button.set_tooltip("Save the edited text and upload it to the Pi");
+        upload_button.deactivate();
+
+        let mut load_more_button = Button::new(
+            x + w - padding - 2 * button_w - padding,
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Load More"
+        );
+        load_more_button.set_tooltip("Load the next chunk of this file");
+        load_more_button.deactivate();
+
+        let mut tail_toggle = ToggleButton::new(
+            x + w - padding - button_w,
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Tail"
+        );
+        tail_toggle.set_tooltip("Show the end of the file instead of the beginning");
+        tail_toggle.deactivate();
+
+        // Stop button replaces the streaming controls while following
+        let mut stop_button = Button::new(
+            x + w - padding - button_w,
+            y + padding,
+            button_w,
+            TOOLBAR_HEIGHT - padding,
+            "Stop"
+        );
+        stop_button.set_tooltip("Stop following this file");
+        stop_button.hide();
+
         // Create text buffer and display
         let text_buffer = TextBuffer::default();
-        
+
         let mut text_display = TextDisplay::new(
             display_x,
             display_y,
@@ -75,9 +240,24 @@ impl TextPreviewComponent {
         text_display.set_frame(FrameType::BorderFrame);
         text_display.set_color(Color::from_rgb(250, 250, 250));
         text_display.set_text_font(Font::Courier);
-        text_display.set_text_size(12);
-        text_display.wrap_mode(true, 0); // Enable word wrap
-        
+        text_display.set_text_size(font_size(12));
+        text_display.wrap_mode(fltk::text::WrapMode::AtBounds, 0); // Enable word wrap
+
+        // Editing is unwrapped so lines are saved exactly as typed
+        let mut text_editor = TextEditor::new(
+            display_x,
+            display_y,
+            display_w,
+            display_h,
+            None
+        );
+        text_editor.set_buffer(text_buffer.clone());
+        text_editor.set_frame(FrameType::BorderFrame);
+        text_editor.set_color(Color::White);
+        text_editor.set_text_font(Font::Courier);
+        text_editor.set_text_size(font_size(12));
+        text_editor.hide();
+
         // Add error message frame (initially hidden)
         let mut error_frame = Frame::new(
             display_x,
@@ -88,65 +268,591 @@ impl TextPreviewComponent {
         );
         error_frame.set_frame(FrameType::BorderFrame);
         error_frame.set_color(Color::from_rgb(250, 240, 240));
-        error_frame.set_label_size(12);
+        error_frame.set_label_size(font_size(12));
         error_frame.set_align(Align::Center | Align::Inside);
         error_frame.hide();
-        
+
         group.end();
-        
-        TextPreviewComponent {
+
+        let mut preview = TextPreviewComponent {
             group,
             text_display,
+            text_editor,
             text_buffer,
             error_frame,
+            status_frame,
+            load_more_button,
+            tail_toggle,
+            stop_button,
+            edit_toggle,
+            save_button,
+            upload_button,
+            saved_text: Arc::new(Mutex::new(String::new())),
+            remote_source: Arc::new(Mutex::new(None)),
+            follow_child: Arc::new(Mutex::new(None)),
+            follow_generation: Arc::new(AtomicUsize::new(0)),
+            stream_state: Arc::new(Mutex::new(StreamState::default())),
             current_file: Arc::new(Mutex::new(None)),
+        };
+
+        preview.setup_callbacks();
+
+        preview
+    }
+
+    fn setup_callbacks(&mut self) {
+        // Load more button appends the next chunk in head mode
+        let mut preview = self.clone();
+        self.load_more_button.set_callback(move |_| {
+            preview.load_next_chunk();
+        });
+
+        // Tail toggle switches between the start and the end of the file
+        let mut preview = self.clone();
+        self.tail_toggle.set_callback(move |b| {
+            let tail_mode = b.value();
+            preview.stream_state.lock().unwrap().tail_mode = tail_mode;
+
+            if tail_mode {
+                preview.load_tail();
+            } else {
+                preview.restart_head();
+            }
+        });
+
+        // Stop button terminates a followed command
+        let mut preview = self.clone();
+        self.stop_button.set_callback(move |_| {
+            preview.stop_following();
+        });
+
+        // Edit toggle swaps the display for the editor
+        let mut preview = self.clone();
+        self.edit_toggle.set_callback(move |b| {
+            if b.value() {
+                preview.set_edit_mode(true);
+            } else if preview.confirm_discard() {
+                preview.set_edit_mode(false);
+            } else {
+                b.set_value(true);
+            }
+        });
+
+        let mut preview = self.clone();
+        self.save_button.set_callback(move |_| {
+            if let Err(e) = preview.save_local() {
+                dialogs::error_dialog("Could not save the file", &e);
+            }
+        });
+
+        let mut preview = self.clone();
+        self.upload_button.set_callback(move |_| {
+            preview.save_to_remote();
+        });
+    }
+
+    /// Remember that the previewed file is a copy of `remote_path` on the Pi
+    /// behind `method`, so edits can be uploaded back to it
+    pub fn set_remote_source(&mut self, method: Box<dyn TransferMethod>, remote_path: PathBuf) {
+        *self.remote_source.lock().unwrap() = Some(RemoteSource { method, path: remote_path });
+        if self.edit_toggle.value() {
+            self.upload_button.activate();
+        }
+    }
+
+    /// Whether the text was edited since it was loaded or last saved
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.edit_toggle.value() && self.text_buffer.text() != *self.saved_text.lock().unwrap()
+    }
+
+    /// Ask before throwing away unsaved edits; true when there are none or
+    /// the user agreed
+    pub fn confirm_discard(&mut self) -> bool {
+        if !self.has_unsaved_changes() {
+            return true;
+        }
+        let discard = dialogs::choice_dialog(
+            "Unsaved Changes",
+            "The file has unsaved changes. Discard them?",
+            &["Keep Editing", "Discard"]
+        ) == 1;
+        if discard {
+            let saved = self.saved_text.lock().unwrap().clone();
+            self.text_buffer.set_text(&saved);
+        }
+        discard
+    }
+
+    /// Switch between the read-only display and the editor
+    fn set_edit_mode(&mut self, editing: bool) {
+        self.edit_toggle.set_value(editing);
+        if editing {
+            self.text_display.hide();
+            self.text_editor.show();
+            self.save_button.activate();
+            if self.remote_source.lock().unwrap().is_some() {
+                self.upload_button.activate();
+            }
+            let _ = self.text_editor.take_focus();
+        } else {
+            self.text_editor.hide();
+            self.text_display.show();
+            self.save_button.deactivate();
+            self.upload_button.deactivate();
+        }
+        self.group.redraw();
+    }
+
+    /// Write the edited text over the previewed file, or for copies of files
+    /// on the Pi, to a local file the user picks
+    fn save_local(&mut self) -> AppResult<()> {
+        let is_remote = self.remote_source.lock().unwrap().is_some();
+        let target = if is_remote {
+            match dialogs::save_file_dialog("Save Text As", "") {
+                Some(path) => path,
+                None => return Ok(()),
+            }
+        } else {
+            self.get_current_file().ok_or_else(|| AppError::FileError("No file is being previewed".to_string()))?
+        };
+
+        let text = self.text_buffer.text();
+        fs::write(&target, &text)?;
+        log::info!("Saved edited text to {}", target.display());
+
+        // A copy saved elsewhere doesn't make the Pi's file saved
+        if !is_remote {
+            *self.saved_text.lock().unwrap() = text;
+        }
+        self.status_frame.set_label(&format!("Saved to {}", target.display()));
+        Ok(())
+    }
+
+    /// Upload the edited text over the file on the Pi the preview came from
+    fn save_to_remote(&mut self) {
+        let (method, remote_path) = match self.remote_source.lock().unwrap().as_ref() {
+            Some(source) => (source.method.clone_box(), source.path.clone()),
+            None => return,
+        };
+        let name = remote_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let text = self.text_buffer.text();
+
+        self.upload_button.deactivate();
+        let worker_path = remote_path.clone();
+        let worker_text = text.clone();
+        let mut preview = self.clone();
+        run_in_background(
+            move || -> AppResult<()> {
+                let local = temp_download_path(&name)?;
+                fs::write(&local, &worker_text)?;
+                let uploaded = method.upload_file(&local, &worker_path);
+                let _ = fs::remove_file(&local);
+                uploaded?;
+                log::info!("Uploaded edited text to {}", worker_path.display());
+                Ok(())
+            },
+            move |result| {
+                if preview.edit_toggle.value() {
+                    preview.upload_button.activate();
+                }
+                match result {
+                    Ok(()) => {
+                        *preview.saved_text.lock().unwrap() = text;
+                        preview.status_frame.set_label(&format!("Saved to the Pi at {}", remote_path.display()));
+                        notify(ToastKind::Success, "Saved to Pi", &remote_path.display().to_string());
+                    },
+                    Err(e) => dialogs::error_dialog("Could not upload the file to the Pi", &e),
+                }
+            }
+        );
+    }
+
+    /// Stream the output of a running command (e.g. `tail -F` on the Pi) into the display
+    pub fn follow(&mut self, mut child: Child, description: &str) -> bool {
+        self.clear();
+
+        let stdout = match child.stdout.take() {
+            Some(stdout) => stdout,
+            None => {
+                let _ = child.kill();
+                self.show_error("Remote command has no output stream");
+                return false;
+            }
+        };
+
+        // Reader thread collects lines; a UI timer drains them into the buffer
+        let pending = Arc::new(Mutex::new(Vec::<String>::new()));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let pending_reader = pending.clone();
+        let finished_reader = finished.clone();
+        thread::spawn(move || {
+            let reader = BufReader::new(stdout);
+            for line in reader.split(b'\n') {
+                match line {
+                    Ok(bytes) => pending_reader.lock().unwrap()
+                        .push(String::from_utf8_lossy(&bytes).into_owned()),
+                    Err(_) => break,
+                }
+            }
+            finished_reader.store(true, Ordering::SeqCst);
+            app::awake();
+        });
+
+        // Drain stderr as it arrives so a chatty command can't fill the pipe
+        // and stall; only the first message is kept for the status line
+        let mut stderr_reader = child.stderr.take().map(|stderr| thread::spawn(move || {
+            let mut first_line = None;
+            for line in BufReader::new(stderr).split(b'\n') {
+                match line {
+                    Ok(bytes) => {
+                        let line = String::from_utf8_lossy(&bytes).trim().to_string();
+                        if first_line.is_none() && !line.is_empty() {
+                            first_line = Some(line);
+                        }
+                    },
+                    Err(_) => break,
+                }
+            }
+            first_line
+        }));
+
+        *self.follow_child.lock().unwrap() = Some(child);
+        let generation = self.follow_generation.fetch_add(1, Ordering::SeqCst) + 1;
+
+        self.status_frame.set_label(&format!("Following {}", description));
+        self.load_more_button.hide();
+        self.tail_toggle.hide();
+        self.edit_toggle.hide();
+        self.save_button.hide();
+        self.upload_button.hide();
+        self.stop_button.show();
+        self.stop_button.activate();
+        self.group.redraw();
+
+        let mut preview = self.clone();
+        let description = description.to_string();
+        app::add_timeout3(FOLLOW_POLL_INTERVAL, move |handle| {
+            // The display was cleared or reused for something else
+            if preview.follow_generation.load(Ordering::SeqCst) != generation {
+                return;
+            }
+
+            let lines = std::mem::take(&mut *pending.lock().unwrap());
+            if !lines.is_empty() {
+                for line in lines {
+                    preview.text_buffer.append(&line);
+                    preview.text_buffer.append("\n");
+                }
+
+                // Keep memory bounded for long-running follows
+                if preview.text_buffer.length() > FOLLOW_BUFFER_LIMIT {
+                    let quarter = preview.text_buffer.length() / 4;
+                    let cut = preview.text_buffer.find_char_forward(quarter, '\n').map_or(quarter, |end| end + 1);
+                    preview.text_buffer.remove(0, cut);
+                }
+
+                preview.text_display.set_insert_position(preview.text_buffer.length());
+                preview.text_display.show_insert_position();
+            }
+
+            if finished.load(Ordering::SeqCst) {
+                preview.finish_following(&description, stderr_reader.take());
+            } else {
+                app::repeat_timeout3(FOLLOW_POLL_INTERVAL, handle);
+            }
+        });
+
+        true
+    }
+
+    /// Stop following the current command
+    pub fn stop_following(&mut self) {
+        if let Some(child) = self.follow_child.lock().unwrap().as_mut() {
+            let _ = child.kill();
         }
+        // The UI timer notices the closed stream and finishes up
     }
-    
+
+    /// Reap a followed command once its output stream has closed
+    fn finish_following(&mut self, description: &str, stderr_reader: Option<JoinHandle<Option<String>>>) {
+        if let Some(mut child) = self.follow_child.lock().unwrap().take() {
+            let _ = child.wait();
+        }
+
+        let mut message = format!("Stopped following {}", description);
+        if let Some(first_line) = stderr_reader.and_then(|reader| reader.join().ok().flatten()) {
+            message = format!("{}: {}", message, first_line);
+        }
+
+        self.status_frame.set_label(&message);
+        self.stop_button.hide();
+        self.load_more_button.show();
+        self.tail_toggle.show();
+        self.edit_toggle.show();
+        self.save_button.show();
+        self.upload_button.show();
+        self.group.redraw();
+    }
+
     /// Load and display a text file
     pub fn load_text(&mut self, path: &Path) -> bool {
         if !path.exists() {
             return false;
         }
-        
+
         // Clear any previous content
         self.clear();
-        
+
         // Check file size
-        match fs::metadata(path) {
-            Ok(metadata) => {
-                if metadata.len() > MAX_TEXT_SIZE {
-                    self.show_error(&format!(
-                        "File too large to preview ({} bytes)\nMaximum size: {} bytes",
-                        metadata.len(),
-                        MAX_TEXT_SIZE
-                    ));
-                    return false;
-                }
-            },
-            Err(e) => {
-                self.show_error(&format!("Error accessing file: {}", e));
-                return false;
+        if let Err(e) = fs::metadata(path) {
+            self.show_error(&format!("Error accessing file: {}", e));
+            return false;
+        }
+
+        // Large files are streamed a chunk at a time instead of rejected
+        if needs_streamed_preview(path) {
+            {
+                let mut state = self.stream_state.lock().unwrap();
+                *state = StreamState {
+                    path: Some(path.to_path_buf()),
+                    ..StreamState::default()
+                };
             }
+
+            self.tail_toggle.set_value(false);
+            self.tail_toggle.activate();
+
+            let mut current = self.current_file.lock().unwrap();
+            *current = Some(path.to_path_buf());
+            drop(current);
+
+            return self.restart_head();
         }
-        
+
         // Try to read the file
         match get_text_preview(path) {
             Ok(content) => {
                 // Set the content to the text buffer
                 self.text_buffer.set_text(&content);
-                
+                self.status_frame.set_label(&format!("{} lines", content.lines().count()));
+                *self.saved_text.lock().unwrap() = content;
+
+                // Only whole files can be edited
+                self.edit_toggle.activate();
+
                 // Show the text display, hide the error frame
                 self.text_display.show();
                 self.error_frame.hide();
-                
+
                 // Store the current file path
                 let mut current = self.current_file.lock().unwrap();
                 *current = Some(path.to_path_buf());
-                
+
                 // Scroll to the top
                 self.text_display.scroll(0, 0);
-                
+
+                true
+            },
+            Err(e) => {
+                self.show_error(&format!("Error reading file: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Stream a large text file on the Pi a byte range at a time, so only
+    /// the part being shown is read over the connection
+    pub fn stream_remote(&mut self, method: Box<dyn TransferMethod>, path: PathBuf) -> bool {
+        self.clear();
+
+        let method: Arc<dyn TransferMethod> = Arc::from(method);
+        *self.stream_state.lock().unwrap() = StreamState {
+            path: Some(path.clone()),
+            remote: Some(method.clone()),
+            ..StreamState::default()
+        };
+        self.status_frame.set_label(&format!("Reading {} on the Pi...", path.display()));
+
+        let worker_path = path.clone();
+        let mut preview = self.clone();
+        run_in_background(
+            move || -> AppResult<u64> {
+                let output = remote_output(method.as_ref(), &remote_file_size_command(&worker_path))?;
+                String::from_utf8_lossy(&output).trim().parse()
+                    .map_err(|_| AppError::FileError(format!("Could not read the size of {}", worker_path.display())))
+            },
+            move |result| {
+                // Another file was opened meanwhile
+                if preview.stream_state.lock().unwrap().path.as_ref() != Some(&path) {
+                    return;
+                }
+                match result {
+                    Ok(size) => {
+                        preview.stream_state.lock().unwrap().remote_size = size;
+                        preview.tail_toggle.set_value(false);
+                        preview.tail_toggle.activate();
+                        preview.restart_head();
+                    },
+                    Err(e) => preview.show_error(&format!("Error reading file: {}", e)),
+                }
+            }
+        );
+
+        true
+    }
+
+    /// Reload a streamed file from its first line
+    fn restart_head(&mut self) -> bool {
+        {
+            let mut state = self.stream_state.lock().unwrap();
+            state.next_offset = 0;
+            state.lines_loaded = 0;
+            state.eof = false;
+        }
+
+        self.text_buffer.set_text("");
+        let loaded = self.load_next_chunk();
+        self.text_display.scroll(0, 0);
+        loaded
+    }
+
+    /// Append the next chunk of a streamed file
+    fn load_next_chunk(&mut self) -> bool {
+        let (path, offset, remote, remote_size) = {
+            let state = self.stream_state.lock().unwrap();
+            match state.path.clone() {
+                Some(path) if !state.eof && !state.tail_mode => (path, state.next_offset, state.remote.clone(), state.remote_size),
+                _ => return false,
+            }
+        };
+
+        let method = match remote {
+            Some(method) => method,
+            None => {
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                let chunk = read_lines_from(&path, offset, STREAM_CHUNK_LINES).map_err(AppError::from);
+                return self.show_head_chunk(chunk, size);
+            },
+        };
+
+        // Only the requested range comes over the connection
+        self.load_more_button.deactivate();
+        let worker_path = path.clone();
+        let mut preview = self.clone();
+        run_in_background(
+            move || -> AppResult<TextChunk> {
+                let command = remote_range_command(&worker_path, offset, REMOTE_CHUNK_BYTES);
+                let bytes = remote_output(method.as_ref(), &command)?;
+                Ok(lines_from_bytes(&bytes, offset, remote_size, STREAM_CHUNK_LINES))
+            },
+            move |chunk| {
+                // Drop the chunk if the file or mode changed while it was read
+                let current = {
+                    let state = preview.stream_state.lock().unwrap();
+                    state.path.as_ref() == Some(&path) && state.next_offset == offset && !state.tail_mode
+                };
+                if current {
+                    preview.show_head_chunk(chunk, remote_size);
+                }
+            }
+        );
+
+        true
+    }
+
+    /// Append a chunk read from the head of a streamed file of `size` bytes
+    fn show_head_chunk(&mut self, chunk: AppResult<TextChunk>, size: u64) -> bool {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                self.show_error(&format!("Error reading file: {}", e));
+                return false;
+            },
+        };
+
+        let mut state = self.stream_state.lock().unwrap();
+        self.text_buffer.append(&chunk.text);
+        state.next_offset = chunk.next_offset;
+        state.lines_loaded += chunk.lines;
+        state.eof = chunk.eof;
+
+        self.status_frame.set_label(&format!(
+            "Large file ({:.1} MB): first {} lines{}",
+            size as f64 / (1024.0 * 1024.0),
+            state.lines_loaded,
+            if state.eof { " (end of file)" } else { "" }
+        ));
+
+        if state.eof {
+            self.load_more_button.deactivate();
+        } else {
+            self.load_more_button.activate();
+        }
+
+        self.text_display.show();
+        self.error_frame.hide();
+        self.group.redraw();
+
+        true
+    }
+
+    /// Show the last lines of a streamed file
+    fn load_tail(&mut self) -> bool {
+        let (path, remote, remote_size) = {
+            let state = self.stream_state.lock().unwrap();
+            match state.path.clone() {
+                Some(path) => (path, state.remote.clone(), state.remote_size),
+                None => return false,
+            }
+        };
+
+        let method = match remote {
+            Some(method) => method,
+            None => {
+                let chunk = read_last_lines(&path, STREAM_CHUNK_LINES).map_err(AppError::from);
+                return self.show_tail_chunk(chunk);
+            },
+        };
+
+        self.load_more_button.deactivate();
+        let worker_path = path.clone();
+        let mut preview = self.clone();
+        run_in_background(
+            move || -> AppResult<TextChunk> {
+                let bytes = remote_output(method.as_ref(), &remote_tail_command(&worker_path, REMOTE_CHUNK_BYTES))?;
+                Ok(last_lines_of(&bytes, STREAM_CHUNK_LINES, remote_size))
+            },
+            move |chunk| {
+                let current = {
+                    let state = preview.stream_state.lock().unwrap();
+                    state.path.as_ref() == Some(&path) && state.tail_mode
+                };
+                if current {
+                    preview.show_tail_chunk(chunk);
+                }
+            }
+        );
+
+        true
+    }
+
+    /// Replace the display with the last lines of a streamed file
+    fn show_tail_chunk(&mut self, chunk: AppResult<TextChunk>) -> bool {
+        match chunk {
+            Ok(chunk) => {
+                self.text_buffer.set_text(&chunk.text);
+                self.status_frame.set_label(&format!("Last {} lines", chunk.lines));
+                self.load_more_button.deactivate();
+
+                self.text_display.show();
+                self.error_frame.hide();
+
+                // Jump to the end of the file
+                self.text_display.set_insert_position(self.text_buffer.length());
+                self.text_display.show_insert_position();
+                self.group.redraw();
+
                 true
             },
             Err(e) => {
@@ -155,48 +861,85 @@ impl TextPreviewComponent {
             }
         }
     }
-    
+
     /// Display an error message
     fn show_error(&mut self, message: &str) {
-        // Hide text display, show error frame
+        // Hide text display and editor, show error frame
         self.text_display.hide();
+        self.text_editor.hide();
         self.error_frame.set_label(message);
         self.error_frame.show();
-        
+
         // Force redraw
         self.group.redraw();
     }
-    
+
     /// Get the current file path
     pub fn get_current_file(&self) -> Option<PathBuf> {
         let current = self.current_file.lock().unwrap();
         current.clone()
     }
-    
+
     /// Clear the text display
     pub fn clear(&mut self) {
+        // Terminate any followed command and detach its timer
+        self.follow_generation.fetch_add(1, Ordering::SeqCst);
+        if let Some(mut child) = self.follow_child.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        self.stop_button.hide();
+        self.load_more_button.show();
+        self.tail_toggle.show();
+        self.edit_toggle.show();
+        self.save_button.show();
+        self.upload_button.show();
+
+        // Leave edit mode; unsaved edits are dropped
+        self.set_edit_mode(false);
+        self.edit_toggle.deactivate();
+        self.saved_text.lock().unwrap().clear();
+        *self.remote_source.lock().unwrap() = None;
+
         // Clear the text buffer
         self.text_buffer.set_text("");
-        
+        self.status_frame.set_label("");
+
+        // Reset streaming controls
+        *self.stream_state.lock().unwrap() = StreamState::default();
+        self.load_more_button.deactivate();
+        self.tail_toggle.set_value(false);
+        self.tail_toggle.deactivate();
+
         // Hide error frame, show text display
         self.error_frame.hide();
         self.text_display.show();
-        
+
         // Clear the path reference
         let mut current = self.current_file.lock().unwrap();
         *current = None;
-        
+
         // Force a redraw
         self.group.redraw();
     }
-    
+
     /// Hide the component
     pub fn hide(&mut self) {
         self.group.hide();
     }
-    
+
     /// Show the component
     pub fn show(&mut self) {
         self.group.show();
     }
-}
\ No newline at end of file
+}
+
+/// Stdout of `command` run on the Pi, or its stderr as an error
+fn remote_output(method: &dyn TransferMethod, command: &str) -> AppResult<Vec<u8>> {
+    let output = method.exec(command)?;
+    if output.success() {
+        Ok(output.stdout_bytes)
+    } else {
+        Err(AppError::FileError(output.stderr.trim().to_string()))
+    }
+}
@@ -0,0 +1,251 @@
+use fltk::{
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, FrameType},
+    frame::Frame,
+    group::Group,
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::core::image::{ImageProcessingService, BUILTIN_PRESETS};
+use crate::core::image_utils::find_images_in_dir;
+use crate::core::upload_target::preset_upload_target;
+use crate::ui::dialogs::dialogs;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::processing_queue::{JobState, JobStatus, Pipeline, ProcessingJob, ProcessingQueue};
+use crate::ui::theme::font_size;
+use crate::core::locale::trf;
+
+/// Panel listing image processing jobs with their progress
+pub struct ProcessingPanel {
+    group: Group,
+    add_button: Button,
+    cancel_button: Button,
+    errors_button: Button,
+    clear_button: Button,
+    summary_frame: Frame,
+    jobs_browser: HoldBrowser,
+    /// Job id of each browser line, in order
+    shown_ids: Arc<Mutex<Vec<u64>>>,
+    queue: ProcessingQueue,
+    image_service: Arc<Mutex<ImageProcessingService>>,
+    config: Arc<Mutex<Config>>,
+}
+
+impl Clone for ProcessingPanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            add_button: self.add_button.clone(),
+            cancel_button: self.cancel_button.clone(),
+            errors_button: self.errors_button.clone(),
+            clear_button: self.clear_button.clone(),
+            summary_frame: self.summary_frame.clone(),
+            jobs_browser: self.jobs_browser.clone(),
+            shown_ids: self.shown_ids.clone(),
+            queue: self.queue.clone(),
+            image_service: self.image_service.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+impl ProcessingPanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        queue: ProcessingQueue,
+        image_service: Arc<Mutex<ImageProcessingService>>,
+        config: Arc<Mutex<Config>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 110;
+
+        let mut add_button = Button::new(x + padding, y + padding, button_width + 20, row_height, "Process Folder...");
+        add_button.set_tooltip("Queue every image in a folder");
+
+        let cancel_button = Button::new(
+            x + w - padding - 3 * button_width - 10,
+            y + padding,
+            button_width,
+            row_height,
+            "Cancel Job"
+        );
+        let errors_button = Button::new(
+            x + w - padding - 2 * button_width - 5,
+            y + padding,
+            button_width,
+            row_height,
+            "Show Errors"
+        );
+        let clear_button = Button::new(
+            x + w - padding - button_width,
+            y + padding,
+            button_width,
+            row_height,
+            "Clear Finished"
+        );
+
+        let mut summary_frame = Frame::new(x + padding, y + 2 * padding + row_height, w - 2 * padding, 20, None);
+        summary_frame.set_label_size(font_size(12));
+        summary_frame.set_align(Align::Left | Align::Inside);
+
+        let list_y = y + 3 * padding + row_height + 20;
+        let mut jobs_browser = HoldBrowser::new(x + padding, list_y, w - 2 * padding, y + h - padding - list_y, None);
+        jobs_browser.set_column_char('\t');
+        jobs_browser.set_column_widths(&[w / 3, 100, 220, 120]);
+
+        group.end();
+
+        let mut panel = ProcessingPanel {
+            group,
+            add_button,
+            cancel_button,
+            errors_button,
+            clear_button,
+            summary_frame,
+            jobs_browser,
+            shown_ids: Arc::new(Mutex::new(Vec::new())),
+            queue,
+            image_service,
+            config,
+        };
+
+        panel.render();
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    /// Re-render whenever the queue changes
+    pub fn subscribe(&self, events: &EventBus) {
+        let mut panel = self.clone();
+        events.subscribe(move |event| {
+            if let AppEvent::ProcessingQueueChanged = event {
+                panel.render();
+            }
+        });
+    }
+
+    fn setup_callbacks(&mut self) {
+        let panel = self.clone();
+        self.add_button.set_callback(move |_| {
+            panel.add_folder_job();
+        });
+
+        let panel = self.clone();
+        self.cancel_button.set_callback(move |_| {
+            if let Some(job) = panel.selected_job() {
+                panel.queue.cancel(job.id);
+            }
+        });
+
+        let panel = self.clone();
+        self.errors_button.set_callback(move |_| {
+            let Some(job) = panel.selected_job() else { return };
+            if job.errors.is_empty() {
+                dialogs::message_dialog("Processing Errors", &trf("{} has no errors.", &[&job.name]));
+            } else {
+                dialogs::message_dialog("Processing Errors", &job.errors.join("\n"));
+            }
+        });
+
+        let panel = self.clone();
+        self.clear_button.set_callback(move |_| {
+            panel.queue.clear_finished();
+        });
+    }
+
+    /// Ask for a folder and a pipeline, then queue the folder's images
+    fn add_folder_job(&self) {
+        let Some(folder) = dialogs::open_directory_dialog("Folder to Process") else { return };
+        let inputs = find_images_in_dir(&folder);
+        if inputs.is_empty() {
+            dialogs::message_dialog("Process Folder", &trf("No images found in {}.", &[&folder.display()]));
+            return;
+        }
+
+        let mut pipelines = vec!["Operations in the Image Processing tab".to_string()];
+        pipelines.extend(BUILTIN_PRESETS.iter().map(|p| format!("Preset '{}': {}", p.name, p.description)));
+        let Some(choice) = dialogs::select_dialog(
+            "Process Folder",
+            &format!("Run on {} images:", inputs.len()),
+            &pipelines
+        ) else { return };
+        let pipeline = match choice {
+            0 => Pipeline::Current(self.image_service.clone()),
+            i => Pipeline::Preset(&BUILTIN_PRESETS[i - 1]),
+        };
+
+        let name = folder
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| folder.display().to_string());
+        let (name_template, upload) = {
+            let config = self.config.lock().unwrap();
+            let upload = match &pipeline {
+                Pipeline::Preset(preset) => preset_upload_target(&config, preset.name),
+                Pipeline::Current(_) => None,
+            };
+            (config.output_name_template.clone(), upload)
+        };
+        self.queue.enqueue(ProcessingJob {
+            name,
+            inputs,
+            output_dir: Some(folder.join("processed")),
+            pipeline,
+            name_template,
+            upload,
+        });
+    }
+
+    fn selected_job(&self) -> Option<JobStatus> {
+        let line = self.jobs_browser.value();
+        if line <= 0 {
+            return None;
+        }
+        let id = *self.shown_ids.lock().unwrap().get(line as usize - 1)?;
+        self.queue.jobs().into_iter().find(|job| job.id == id)
+    }
+
+    fn render(&mut self) {
+        let selected = self.selected_job().map(|job| job.id);
+        let jobs = self.queue.jobs();
+
+        self.jobs_browser.clear();
+        for job in &jobs {
+            let progress = match job.state {
+                JobState::Queued => format!("{} images", job.total),
+                _ => format!("{} of {} images", job.done, job.total),
+            };
+            let errors = match job.errors.len() {
+                0 => String::new(),
+                1 => "1 error".to_string(),
+                n => format!("{} errors", n),
+            };
+            self.jobs_browser.add(&format!("{}\t{}\t{}\t{}", job.name, job.state.label(), progress, errors));
+        }
+        *self.shown_ids.lock().unwrap() = jobs.iter().map(|job| job.id).collect();
+
+        if let Some(line) = selected.and_then(|id| jobs.iter().position(|job| job.id == id)) {
+            self.jobs_browser.select(line as i32 + 1);
+        }
+
+        let active = jobs.iter().filter(|job| matches!(job.state, JobState::Queued | JobState::Running)).count();
+        self.summary_frame.set_label(&match active {
+            0 => "No jobs running".to_string(),
+            1 => "1 job queued or running".to_string(),
+            n => format!("{} jobs queued or running", n),
+        });
+    }
+}
+
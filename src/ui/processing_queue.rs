@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::core::image::{ImageFormat, ImageProcessingService, ProcessingPreset};
+use crate::core::image_utils::{generate_output_filename, get_image_format, OutputNameContext};
+use crate::core::upload_target::UploadTarget;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::executor::run_on_own_thread;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::core::locale::trf;
+
+/// `{op}` of results made with the Image Processing tab's operations
+pub const PIPELINE_SUFFIX: &str = "processed";
+
+/// What a processing job does to each image
+#[derive(Clone)]
+pub enum Pipeline {
+    Preset(&'static ProcessingPreset),
+    /// The operations set up in the Image Processing tab
+    Current(Arc<Mutex<ImageProcessingService>>),
+}
+
+impl Pipeline {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Preset(preset) => format!("preset '{}'", preset.name),
+            Self::Current(_) => "current operations".to_string(),
+        }
+    }
+}
+
+/// Run `pipeline` on `input`, writing the result into `output_dir` or next
+/// to the input under a name made from `name_template`; returns the path
+/// written. `counter` is the input's position in its batch.
+pub fn process_image(
+    input: &Path,
+    output_dir: Option<&Path>,
+    pipeline: &Pipeline,
+    name_template: &str,
+    counter: usize
+) -> Result<PathBuf, String> {
+    if let Some(dir) = output_dir {
+        fs::create_dir_all(dir).map_err(|e| format!("Could not create {}: {}", dir.display(), e))?;
+    }
+    let place = |output: PathBuf| match output_dir {
+        Some(dir) => dir.join(output.file_name().unwrap_or_default()),
+        None => output,
+    };
+
+    match pipeline {
+        Pipeline::Preset(preset) => {
+            let context = OutputNameContext::new(input, preset.format.clone(), preset.name, counter)
+                .with_size(preset.resize);
+            let output = place(generate_output_filename(input, name_template, &context));
+            preset.service()
+                .process_image(input, &output, preset.factory_index())
+                .map_err(|e| e.to_string())?;
+            Ok(output)
+        },
+        Pipeline::Current(service) => {
+            // Keep PNGs lossless; everything else is written as JPEG
+            let (format, factory_index) = match get_image_format(input) {
+                Some(ImageFormat::PNG) => (ImageFormat::PNG, 1),
+                _ => (ImageFormat::JPEG, 0),
+            };
+            let context = OutputNameContext::new(input, format, PIPELINE_SUFFIX, counter);
+            let output = place(generate_output_filename(input, name_template, &context));
+            service
+                .lock()
+                .unwrap()
+                .process_image(input, &output, factory_index)
+                .map_err(|e| e.to_string())?;
+            Ok(output)
+        },
+    }
+}
+
+/// A batch of images to run through one pipeline
+pub struct ProcessingJob {
+    pub name: String,
+    pub inputs: Vec<PathBuf>,
+    /// Folder the results go to; None writes them next to each input
+    pub output_dir: Option<PathBuf>,
+    pub pipeline: Pipeline,
+    /// Output naming, see `OUTPUT_NAME_TOKENS`
+    pub name_template: String,
+    /// Where each result is also sent, such as the Pi or Dropbox
+    pub upload: Option<Box<dyn UploadTarget>>,
+}
+
+/// Work of a queued job other than running images through a pipeline,
+/// such as assembling a video
+///
+/// It is called on a thread of its own with a function taking the number of
+/// steps done so far, and should stop early once the flag is set.
+pub type TaskWork = Box<dyn FnOnce(&dyn Fn(usize), &AtomicBool) -> Result<(), String> + Send>;
+
+enum QueuedWork {
+    Images(ProcessingJob),
+    Task(TaskWork),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Queued => "Queued",
+            Self::Running => "Running",
+            Self::Finished => "Finished",
+            Self::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// Progress of a queued, running or finished job
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub id: u64,
+    pub name: String,
+    pub state: JobState,
+    /// Images processed or failed so far, or steps of a task done
+    pub done: usize,
+    pub total: usize,
+    /// One line per image that failed
+    pub errors: Vec<String>,
+}
+
+struct JobEntry {
+    status: JobStatus,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Image processing jobs run in the background one at a time, in the order
+/// queued
+///
+/// Every change of a job's progress is announced with
+/// `AppEvent::ProcessingQueueChanged`; `jobs` gives the current state.
+/// Cancelling stops a running job after the image it is on.
+#[derive(Clone)]
+pub struct ProcessingQueue {
+    pending: Arc<Mutex<VecDeque<(u64, QueuedWork)>>>,
+    jobs: Arc<Mutex<Vec<JobEntry>>>,
+    running: Arc<Mutex<bool>>,
+    next_id: Arc<Mutex<u64>>,
+    events: EventBus,
+}
+
+impl ProcessingQueue {
+    pub fn new(events: EventBus) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            jobs: Arc::new(Mutex::new(Vec::new())),
+            running: Arc::new(Mutex::new(false)),
+            next_id: Arc::new(Mutex::new(1)),
+            events,
+        }
+    }
+
+    /// Add a job to the end of the queue, starting it if nothing runs;
+    /// returns its id
+    pub fn enqueue(&self, job: ProcessingJob) -> u64 {
+        log::info!("Queued processing job '{}': {} images with {}", job.name, job.inputs.len(), job.pipeline.label());
+        self.add(job.name.clone(), job.inputs.len(), QueuedWork::Images(job))
+    }
+
+    /// Queue a task of `total` steps shown as `name`; returns its id
+    pub fn enqueue_task(&self, name: &str, total: usize, work: TaskWork) -> u64 {
+        log::info!("Queued task '{}' of {} steps", name, total);
+        self.add(name.to_string(), total, QueuedWork::Task(work))
+    }
+
+    fn add(&self, name: String, total: usize, work: QueuedWork) -> u64 {
+        let id = {
+            let mut next_id = self.next_id.lock().unwrap();
+            *next_id += 1;
+            *next_id - 1
+        };
+
+        self.jobs.lock().unwrap().push(JobEntry {
+            status: JobStatus {
+                id,
+                name,
+                state: JobState::Queued,
+                done: 0,
+                total,
+                errors: Vec::new(),
+            },
+            cancel: Arc::new(AtomicBool::new(false)),
+        });
+        self.pending.lock().unwrap().push_back((id, work));
+        self.changed();
+
+        self.start_next();
+        id
+    }
+
+    /// Every job still listed, oldest first
+    pub fn jobs(&self) -> Vec<JobStatus> {
+        self.jobs.lock().unwrap().iter().map(|entry| entry.status.clone()).collect()
+    }
+
+    /// Drop a queued job, or stop a running one after its current image
+    pub fn cancel(&self, id: u64) {
+        let was_queued = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(entry) = jobs.iter_mut().find(|entry| entry.status.id == id) else { return };
+            entry.cancel.store(true, Ordering::SeqCst);
+            if entry.status.state == JobState::Queued {
+                entry.status.state = JobState::Cancelled;
+                true
+            } else {
+                false
+            }
+        };
+
+        if was_queued {
+            self.pending.lock().unwrap().retain(|(pending_id, _)| *pending_id != id);
+        }
+        self.changed();
+    }
+
+    /// Forget finished and cancelled jobs
+    pub fn clear_finished(&self) {
+        self.jobs
+            .lock()
+            .unwrap()
+            .retain(|entry| matches!(entry.status.state, JobState::Queued | JobState::Running));
+        self.changed();
+    }
+
+    fn changed(&self) {
+        self.events.publish(AppEvent::ProcessingQueueChanged);
+    }
+
+    fn start_next(&self) {
+        let next = {
+            let mut running = self.running.lock().unwrap();
+            if *running {
+                return;
+            }
+            let next = self.pending.lock().unwrap().pop_front();
+            *running = next.is_some();
+            next
+        };
+        let Some((id, work)) = next else { return };
+
+        let cancel = {
+            let mut jobs = self.jobs.lock().unwrap();
+            match jobs.iter_mut().find(|entry| entry.status.id == id) {
+                Some(entry) => {
+                    entry.status.state = JobState::Running;
+                    entry.cancel.clone()
+                },
+                None => Arc::new(AtomicBool::new(false)),
+            }
+        };
+        self.changed();
+
+        // Jobs may take many minutes, so they get a thread instead of a pool worker
+        let queue = self.clone();
+        let worker = self.clone();
+        run_on_own_thread(
+            &format!("job-{}", id),
+            move || {
+                match work {
+                    QueuedWork::Images(mut job) => {
+                        for (index, input) in job.inputs.iter().enumerate() {
+                            if cancel.load(Ordering::SeqCst) {
+                                break;
+                            }
+                            let result = process_image(
+                                input,
+                                job.output_dir.as_deref(),
+                                &job.pipeline,
+                                &job.name_template,
+                                index + 1
+                            ).and_then(|output| match job.upload.as_mut() {
+                                Some(target) => target
+                                    .upload(&output)
+                                    .map_err(|e| format!("Sending to {} failed: {}", target.label(), e)),
+                                None => Ok(()),
+                            });
+                            worker.update(id, |status| {
+                                status.done += 1;
+                                if let Err(e) = result {
+                                    status.errors.push(format!("{}: {}", input.display(), e));
+                                }
+                            });
+                        }
+                    },
+                    QueuedWork::Task(task) => {
+                        let result = task(&|done| worker.update(id, |status| status.done = done.min(status.total)), &cancel);
+                        worker.update(id, |status| match result {
+                            Ok(()) if !cancel.load(Ordering::SeqCst) => status.done = status.total,
+                            Ok(()) => {},
+                            Err(e) => status.errors.push(e),
+                        });
+                    },
+                }
+                cancel.load(Ordering::SeqCst)
+            },
+            move |cancelled| {
+                let mut finished = None;
+                queue.update(id, |status| {
+                    status.state = if cancelled { JobState::Cancelled } else { JobState::Finished };
+                    finished = Some(status.clone());
+                });
+                *queue.running.lock().unwrap() = false;
+
+                if let Some(status) = finished {
+                    queue.events.publish(AppEvent::ProcessingJobFinished {
+                        name: status.name.clone(),
+                        done: status.done,
+                        failed: status.errors.len(),
+                        cancelled: status.state == JobState::Cancelled,
+                    });
+                    match (status.state, status.errors.len()) {
+                        (JobState::Cancelled, _) => {
+                            notify(ToastKind::Info, "Processing cancelled", &trf("{} after {} of {} images", &[&status.name, &status.done, &status.total]));
+                        },
+                        (_, 0) => {
+                            notify(ToastKind::Success, "Processing finished", &trf("{}: {} images", &[&status.name, &status.total]));
+                        },
+                        (_, failed) => {
+                            notify(ToastKind::Error, "Processing finished with errors", &trf("{}: {} of {} images failed", &[&status.name, &failed, &status.total]));
+                        },
+                    }
+                }
+                queue.start_next();
+            }
+        );
+    }
+
+    /// Change a job's status and announce it; called from the worker thread too
+    fn update<F: FnOnce(&mut JobStatus)>(&self, id: u64, change: F) {
+        if let Some(entry) = self.jobs.lock().unwrap().iter_mut().find(|entry| entry.status.id == id) {
+            change(&mut entry.status);
+        }
+        self.changed();
+    }
+}
@@ -0,0 +1,86 @@
+use fltk::app;
+
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::provision::{installed_tools, provision, CompanionTool, ProvisionPlan};
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::processing_queue::ProcessingQueue;
+
+/// Check which companion tools the Pi lacks, let the user pick what to
+/// install and install it as a job in the processing queue
+///
+/// The agent is uploaded from a build chosen here; rsync and ImageMagick
+/// come from apt. `on_installed` runs on the main thread after the job,
+/// successful or not, so views can pick up the new tools.
+pub fn install_companion_tools<F>(method: Box<dyn TransferMethod>, queue: ProcessingQueue, on_installed: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    run_in_background(
+        move || {
+            let installed = installed_tools(method.as_ref());
+            (method, installed)
+        },
+        move |(method, installed)| {
+            let installed = match installed {
+                Ok(installed) => installed,
+                Err(e) => {
+                    dialogs::error_dialog("Cannot check the Pi's tools", &e.into());
+                    return;
+                },
+            };
+
+            let missing: Vec<CompanionTool> = installed.iter().filter(|(_, present)| !present).map(|(tool, _)| *tool).collect();
+            let installed_names: Vec<&str> = installed.iter().filter(|(_, present)| *present).map(|(tool, _)| tool.name()).collect();
+            if missing.is_empty() {
+                dialogs::message_dialog("Companion Tools", "Every companion tool is already installed on the Pi.");
+                return;
+            }
+
+            let mut message = "Install on the Pi:".to_string();
+            if !installed_names.is_empty() {
+                message = format!("Already installed: {}. Install on the Pi:", installed_names.join(", "));
+            }
+            let options: Vec<String> = missing.iter().map(|tool| tool.label().to_string()).collect();
+            let Some(chosen) = dialogs::multi_select_dialog("Install Companion Tools", &message, &options) else { return };
+
+            let mut plan = ProvisionPlan {
+                agent_binary: None,
+                tools: chosen.into_iter().map(|index| missing[index]).collect(),
+            };
+            if plan.tools.contains(&CompanionTool::Agent) {
+                match dialogs::open_file_dialog("Build of pi_remote_manager for the Pi", "*") {
+                    Some(binary) => plan.agent_binary = Some(binary),
+                    None => plan.tools.retain(|tool| *tool != CompanionTool::Agent),
+                }
+            }
+            if plan.tools.is_empty() {
+                return;
+            }
+
+            let names: Vec<&str> = plan.tools.iter().map(|tool| tool.name()).collect();
+            let name = format!("Install {}", names.join(", "));
+            queue.enqueue_task(&name, plan.steps(), Box::new(move |progress, cancel| {
+                let result = provision(method.as_ref(), &plan, progress, cancel);
+
+                let report = result.as_ref().ok().map(|lines| lines.join("\n"));
+                let mut finished = Some(on_installed);
+                app::awake_callback(move || {
+                    if let Some(on_installed) = finished.take() {
+                        on_installed();
+                        if let Some(report) = &report {
+                            dialogs::message_dialog("Companion Tools", report);
+                        }
+                    }
+                });
+
+                match result {
+                    Ok(_) => Ok(()),
+                    // The queue reports the job as cancelled
+                    Err(TransferError::Cancelled) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }));
+        }
+    );
+}
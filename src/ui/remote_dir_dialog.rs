@@ -0,0 +1,208 @@
+use fltk::{
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, CallbackTrigger, Color},
+    frame::Frame,
+    input::Input,
+    prelude::*,
+};
+
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::core::locale::tr;
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::ui::executor::run_in_background;
+use crate::ui::modal::ModalDialog;
+use crate::ui::theme::font_size;
+
+/// Longest a directory listing in the chooser may take
+const LISTING_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Let the user pick a directory on the Pi, starting in `start`
+///
+/// Subdirectories are listed through `method`; double-click or Open enters
+/// one. Returns None when cancelled.
+pub fn choose_remote_directory(method: Box<dyn TransferMethod>, start: &Path) -> Option<PathBuf> {
+    let width = 460;
+    let height = 420;
+    let padding = 10;
+    let row_height = 25;
+    let button_width = 80;
+
+    let mut dialog = ModalDialog::new(width, height, "Choose Folder on Pi");
+
+    let mut up_button = Button::new(padding, padding, 40, row_height, "@8->");
+    up_button.set_tooltip("Parent folder");
+
+    let mut path_input = Input::new(
+        padding * 2 + 40,
+        padding,
+        width - padding * 3 - 40,
+        row_height,
+        None
+    );
+    path_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+    path_input.set_tooltip("Type a path and press Enter to go there");
+
+    let list_y = padding * 2 + row_height;
+    let list_h = height - list_y - padding * 3 - row_height * 2;
+    let mut folders = HoldBrowser::new(padding, list_y, width - padding * 2, list_h, None);
+
+    let mut status_frame = Frame::new(padding, list_y + list_h + padding, width - padding * 2, row_height, None);
+    status_frame.set_align(Align::Left | Align::Inside);
+    status_frame.set_label_size(font_size(12));
+
+    let buttons_y = height - padding - row_height;
+    let mut open_button = Button::new(padding, buttons_y, button_width, row_height, "Open");
+    let mut cancel_button = Button::new(
+        width - padding * 2 - button_width * 2,
+        buttons_y,
+        button_width,
+        row_height,
+        "Cancel"
+    );
+    let mut choose_button = Button::new(width - padding - button_width, buttons_y, button_width, row_height, "Choose");
+    choose_button.set_color(Color::from_rgb(0, 120, 255));
+    choose_button.set_label_color(Color::White);
+
+    let method: Arc<dyn TransferMethod> = Arc::from(method);
+    let current = Rc::new(RefCell::new(start.to_path_buf()));
+    // Listings that finish after the user moved on are dropped
+    let generation = Arc::new(AtomicUsize::new(0));
+    let chosen = Rc::new(RefCell::new(None::<PathBuf>));
+    // Names of the listed subfolders by line, as the Pi reported them
+    let subfolders = Arc::new(Mutex::new(Vec::<OsString>::new()));
+
+    // List `dir` and show its subdirectories once the listing arrives
+    let show = {
+        let current = current.clone();
+        let mut path_input = path_input.clone();
+        let folders = folders.clone();
+        let status_frame = status_frame.clone();
+        let subfolders = subfolders.clone();
+        move |dir: PathBuf| {
+            *current.borrow_mut() = dir.clone();
+            path_input.set_value(&dir.to_string_lossy());
+
+            let mut folders = folders.clone();
+            let mut status_frame = status_frame.clone();
+            folders.clear();
+            subfolders.lock().unwrap().clear();
+            status_frame.set_label(&tr("Listing..."));
+            status_frame.set_label_color(Color::Blue);
+
+            let this_listing = generation.fetch_add(1, Ordering::SeqCst) + 1;
+            let generation = generation.clone();
+            let method = method.clone();
+            let subfolders = subfolders.clone();
+            run_in_background(
+                move || method.list_files_with_timeout(&dir, LISTING_TIMEOUT, &AtomicBool::new(false)),
+                move |result: Result<Vec<(OsString, bool)>, TransferError>| {
+                    if generation.load(Ordering::SeqCst) != this_listing {
+                        return;
+                    }
+                    match result {
+                        Ok(entries) => {
+                            let mut names: Vec<OsString> = entries
+                                .into_iter()
+                                .filter(|(_, is_dir)| *is_dir)
+                                .map(|(name, _)| name)
+                                .collect();
+                            names.sort_by_key(|name| name.to_string_lossy().to_lowercase());
+                            for name in &names {
+                                folders.add(&format!("@.{}", name.to_string_lossy()));
+                            }
+                            status_frame.set_label(&match names.len() {
+                                0 => "No subfolders".to_string(),
+                                1 => "1 subfolder".to_string(),
+                                n => format!("{} subfolders", n),
+                            });
+                            *subfolders.lock().unwrap() = names;
+                            status_frame.set_label_color(Color::Black);
+                        },
+                        Err(e) => {
+                            status_frame.set_label(&e.to_string());
+                            status_frame.set_label_color(Color::Red);
+                        },
+                    }
+                }
+            );
+        }
+    };
+
+    let mut show_start = show.clone();
+    show_start(start.to_path_buf());
+
+    // Enter the selected subfolder
+    let enter_selected = {
+        let current = current.clone();
+        let folders = folders.clone();
+        let subfolders = subfolders.clone();
+        let show = show.clone();
+        move || {
+            let line = folders.value();
+            if line <= 0 {
+                return;
+            }
+            let name = subfolders.lock().unwrap().get(line as usize - 1).cloned();
+            if let Some(name) = name {
+                let dir = current.borrow().join(name);
+                let mut show = show.clone();
+                show(dir);
+            }
+        }
+    };
+
+    let enter_double_click = enter_selected.clone();
+    folders.set_callback(move |_| {
+        if fltk::app::event_clicks() {
+            enter_double_click();
+        }
+    });
+
+    let enter_open = enter_selected.clone();
+    open_button.set_callback(move |_| {
+        enter_open();
+    });
+
+    let current_up = current.clone();
+    let mut show_up = show.clone();
+    up_button.set_callback(move |_| {
+        let parent = current_up.borrow().parent().map(Path::to_path_buf);
+        if let Some(parent) = parent {
+            show_up(parent);
+        }
+    });
+
+    let mut show_typed = show.clone();
+    path_input.set_callback(move |input| {
+        let typed = input.value().trim().to_string();
+        if !typed.is_empty() {
+            show_typed(PathBuf::from(typed));
+        }
+    });
+
+    let mut dialog_cancel = dialog.clone();
+    cancel_button.set_callback(move |_| {
+        dialog_cancel.close();
+    });
+
+    let current_choose = current.clone();
+    let chosen_clone = chosen.clone();
+    let mut dialog_choose = dialog.clone();
+    choose_button.set_callback(move |_| {
+        *chosen_clone.borrow_mut() = Some(current_choose.borrow().clone());
+        dialog_choose.close();
+    });
+
+    dialog.run();
+
+    let result = chosen.borrow().clone();
+    result
+}
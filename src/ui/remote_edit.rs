@@ -0,0 +1,253 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use directories::ProjectDirs;
+use fltk::app;
+
+use crate::core::utils::{open_with_default_app, AppError, AppResult};
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::ssh::shell_quote_path;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::core::locale::trf;
+
+/// Seconds between checks of the local copies for saves
+const POLL_INTERVAL: f64 = 1.0;
+
+/// A remote file being edited through a local copy
+struct Edit {
+    method: Box<dyn TransferMethod>,
+    remote_path: PathBuf,
+    local_path: PathBuf,
+    // Modification time of the copy when it last matched the Pi's file
+    synced: Option<SystemTime>,
+    // Modification time seen at the previous check; a save is uploaded
+    // once the editor has stopped writing
+    pending: Option<SystemTime>,
+    // The Pi's modification time of the file when last synced, if known
+    remote_modified: Option<i64>,
+    uploading: bool,
+}
+
+/// Edits keyed by connection and remote path
+fn edits() -> &'static Mutex<HashMap<(String, PathBuf), Edit>> {
+    static EDITS: OnceLock<Mutex<HashMap<(String, PathBuf), Edit>>> = OnceLock::new();
+    EDITS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Whether the timer checking the copies is running
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// Folder holding the copies being edited, in the user's cache directory;
+/// the storage cleanup leaves it alone
+fn edit_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "PiImageProcessor", "piimgproc").map(|dirs| dirs.cache_dir().join("edits"))
+}
+
+/// Download `remote_path` from the Pi behind `method`, open it in the
+/// desktop's editor for its type and upload it back each time it is saved
+///
+/// The copy is watched until the app exits or `stop_editing` is called.
+/// Editing a file already being edited opens the same copy again. Before
+/// each upload the file on the Pi is checked, and the user is asked before
+/// a change made there since the download is overwritten.
+pub fn edit_remotely(method: Box<dyn TransferMethod>, remote_path: PathBuf) {
+    let key = (method.connection_key(), remote_path.clone());
+    let existing = edits().lock().unwrap().get(&key).map(|edit| edit.local_path.clone());
+    if let Some(local_path) = existing {
+        if let Err(e) = open_with_default_app(&local_path) {
+            dialogs::error_dialog("Failed to open remote file", &e);
+        }
+        return;
+    }
+
+    let worker_method = method.clone_box();
+    let worker_path = remote_path.clone();
+    run_in_background(
+        move || -> AppResult<(PathBuf, Option<i64>)> {
+            let local_path = edit_copy_path(&worker_path)?;
+            worker_method.download_file(&worker_path, &local_path)?;
+            log::info!("Downloaded {} to {} for editing", worker_path.display(), local_path.display());
+            Ok((local_path, remote_modified(worker_method.as_ref(), &worker_path)))
+        },
+        move |result| {
+            let (local_path, modified) = match result {
+                Ok(downloaded) => downloaded,
+                Err(e) => {
+                    dialogs::error_dialog("Failed to open remote file", &e);
+                    return;
+                },
+            };
+            edits().lock().unwrap().insert(key, Edit {
+                method,
+                remote_path,
+                local_path: local_path.clone(),
+                synced: local_modified(&local_path),
+                pending: None,
+                remote_modified: modified,
+                uploading: false,
+            });
+            watch();
+
+            if let Err(e) = open_with_default_app(&local_path) {
+                dialogs::error_dialog("Failed to open remote file", &e);
+            }
+        }
+    );
+}
+
+/// Whether `remote_path` on the connection `connection` is being edited
+pub fn is_editing(connection: &str, remote_path: &Path) -> bool {
+    edits().lock().unwrap().contains_key(&(connection.to_string(), remote_path.to_path_buf()))
+}
+
+/// Stop uploading saves of `remote_path` and delete its local copy; saves
+/// not yet uploaded are lost
+pub fn stop_editing(connection: &str, remote_path: &Path) {
+    let removed = edits().lock().unwrap().remove(&(connection.to_string(), remote_path.to_path_buf()));
+    if let Some(edit) = removed {
+        if let Some(dir) = edit.local_path.parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+        log::info!("Stopped editing {}", edit.remote_path.display());
+    }
+}
+
+/// A fresh folder for the copy of `remote_path`, so files of the same name
+/// from different folders don't collide
+fn edit_copy_path(remote_path: &Path) -> AppResult<PathBuf> {
+    let name = remote_path
+        .file_name()
+        .ok_or_else(|| AppError::FileError(format!("{} is not a file", remote_path.display())))?;
+    let root = edit_dir().ok_or_else(|| AppError::FileError("No cache directory for edited files".to_string()))?;
+    let timestamp = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let dir = root.join(timestamp.to_string());
+    fs::create_dir_all(&dir)?;
+    Ok(dir.join(name))
+}
+
+fn local_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+/// The Pi's modification time of `path` in seconds, when the connection
+/// can run commands
+fn remote_modified(method: &dyn TransferMethod, path: &Path) -> Option<i64> {
+    let output = method.exec(&format!("stat -c %Y -- {}", shell_quote_path(path))).ok()?;
+    output.stdout.trim().parse().ok()
+}
+
+/// Start checking the copies for saves, unless already checking
+fn watch() {
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    app::add_timeout3(POLL_INTERVAL, |handle| {
+        check_edits();
+        if edits().lock().unwrap().is_empty() {
+            WATCHING.store(false, Ordering::SeqCst);
+        } else {
+            app::repeat_timeout3(POLL_INTERVAL, handle);
+        }
+    });
+}
+
+/// Upload the copies saved since the previous check
+fn check_edits() {
+    let mut saved = Vec::new();
+    {
+        let mut edits = edits().lock().unwrap();
+        for (key, edit) in edits.iter_mut() {
+            // Editors that save by replacing the file leave it missing for
+            // a moment
+            let modified = match local_modified(&edit.local_path) {
+                Some(modified) if !edit.uploading => Some(modified),
+                _ => continue,
+            };
+            if modified == edit.synced {
+                edit.pending = None;
+            } else if modified == edit.pending {
+                // Unchanged for a whole interval, so the save is complete
+                edit.uploading = true;
+                saved.push((key.clone(), modified));
+            } else {
+                edit.pending = modified;
+            }
+        }
+    }
+
+    for (key, modified) in saved {
+        upload(key, modified, false);
+    }
+}
+
+enum UploadOutcome {
+    Uploaded(Option<i64>),
+    // The file on the Pi changed since it was downloaded or last uploaded
+    Conflict,
+}
+
+/// Upload the copy of the edit `key`, saved at `modified`; unless
+/// `overwrite`, first make sure the Pi's file is still the one downloaded
+fn upload(key: (String, PathBuf), modified: Option<SystemTime>, overwrite: bool) {
+    let (method, local_path, remote_path, expected) = {
+        let edits = edits().lock().unwrap();
+        let Some(edit) = edits.get(&key) else { return };
+        (edit.method.clone_box(), edit.local_path.clone(), edit.remote_path.clone(), edit.remote_modified)
+    };
+
+    run_in_background(
+        move || -> Result<UploadOutcome, TransferError> {
+            if !overwrite && expected.is_some() && remote_modified(method.as_ref(), &remote_path) != expected {
+                return Ok(UploadOutcome::Conflict);
+            }
+            method.upload_file(&local_path, &remote_path)?;
+            log::info!("Uploaded edited {} to {}", local_path.display(), remote_path.display());
+            Ok(UploadOutcome::Uploaded(remote_modified(method.as_ref(), &remote_path)))
+        },
+        move |result| {
+            let name = key.1.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+            if let Ok(UploadOutcome::Conflict) = result {
+                let message = format!(
+                    "{} was changed on the Pi since it was opened for editing. Overwrite it with your version?",
+                    key.1.display()
+                );
+                if dialogs::choice_dialog("File Changed on the Pi", &message, &["Keep Pi's Version", "Overwrite"]) == 1 {
+                    upload(key, modified, true);
+                    return;
+                }
+            }
+
+            let mut edits = edits().lock().unwrap();
+            let Some(edit) = edits.get_mut(&key) else { return };
+            // Failed or declined saves aren't retried until the next save
+            edit.synced = modified;
+            edit.pending = None;
+            edit.uploading = false;
+            match result {
+                Ok(UploadOutcome::Uploaded(remote)) => {
+                    edit.remote_modified = remote;
+                    drop(edits);
+                    notify(ToastKind::Success, "Remote Edit", &trf("Uploaded {}", &[&name]));
+                },
+                Ok(UploadOutcome::Conflict) => {
+                    drop(edits);
+                    notify(ToastKind::Info, "Remote Edit", &trf("Kept the Pi's version of {}", &[&name]));
+                },
+                Err(e) => {
+                    drop(edits);
+                    notify(ToastKind::Error, "Remote Edit", &trf("Could not upload {}: {}. Save again to retry.", &[&name, &e]));
+                },
+            }
+        }
+    );
+}
@@ -0,0 +1,179 @@
+use fltk::{
+    browser::MultiBrowser,
+    button::Button,
+    enums::{Align, Color},
+    frame::Frame,
+    prelude::*,
+};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::remote_trash::{
+    empty_remote_trash, list_remote_trash, purge_remote_trash, restore_from_remote_trash, TrashedItem,
+    REMOTE_TRASH_DIR,
+};
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::modal::ModalDialog;
+use crate::ui::notifications::{notify, ToastKind};
+
+/// What the user asked the trash view to do
+enum TrashAction {
+    Restore(Vec<TrashedItem>),
+    Purge(Vec<TrashedItem>),
+    Empty,
+}
+
+/// Show what has been moved to the Pi's trash, letting the user restore
+/// items or delete them for good
+///
+/// `on_restored` runs after items are put back so the remote pane can
+/// refresh; the view opens again after every action until it is closed.
+pub fn show_remote_trash<F>(method: Box<dyn TransferMethod>, on_restored: F)
+where
+    F: Fn() + Clone + Send + 'static,
+{
+    run_in_background(
+        move || {
+            let items = list_remote_trash(method.as_ref());
+            (items, method)
+        },
+        move |(items, method): (Result<Vec<TrashedItem>, TransferError>, Box<dyn TransferMethod>)| {
+            let items = match items {
+                Ok(items) => items,
+                Err(e) => {
+                    dialogs::error_dialog("Reading the Pi's trash failed", &e.into());
+                    return;
+                }
+            };
+
+            let Some(action) = trash_dialog(&items) else { return };
+            let (question, confirm) = match &action {
+                TrashAction::Restore(_) => (None, "Restore"),
+                TrashAction::Purge(chosen) => (Some(format!("Permanently delete {} items from the Pi's trash?", chosen.len())), "Delete"),
+                TrashAction::Empty => (Some(format!("Permanently delete all {} items in the Pi's trash?", items.len())), "Empty Trash"),
+            };
+            if let Some(question) = question {
+                if dialogs::choice_dialog("Remote Trash", &question, &[confirm, "Cancel"]) != 0 {
+                    show_remote_trash(method, on_restored);
+                    return;
+                }
+            }
+
+            run_in_background(
+                move || {
+                    let result = match &action {
+                        TrashAction::Restore(chosen) => restore_from_remote_trash(method.as_ref(), chosen)
+                            .map(|_| format!("Restored {} items", chosen.len())),
+                        TrashAction::Purge(chosen) => purge_remote_trash(method.as_ref(), chosen)
+                            .map(|_| format!("Deleted {} items for good", chosen.len())),
+                        TrashAction::Empty => empty_remote_trash(method.as_ref())
+                            .map(|_| "Emptied the trash".to_string()),
+                    };
+                    let restored = matches!(action, TrashAction::Restore(_));
+                    (result, restored, method)
+                },
+                move |(result, restored, method): (Result<String, TransferError>, bool, Box<dyn TransferMethod>)| {
+                    match result {
+                        Ok(message) => notify(ToastKind::Success, "Remote Trash", &message),
+                        Err(e) => dialogs::error_dialog("Remote Trash", &e.into()),
+                    }
+                    // Even a partial restore may have put files back
+                    if restored {
+                        on_restored();
+                    }
+                    show_remote_trash(method, on_restored);
+                }
+            );
+        }
+    );
+}
+
+/// List `items` with Restore, Delete and Empty Trash buttons; None when
+/// closed without an action
+fn trash_dialog(items: &[TrashedItem]) -> Option<TrashAction> {
+    let width = 640;
+    let height = 420;
+    let padding = 10;
+    let row_height = 25;
+    let button_width = 130;
+
+    let mut dialog = ModalDialog::new(width, height, "Remote Trash");
+
+    let mut info_frame = Frame::new(padding, padding, width - padding * 2, row_height, None);
+    info_frame.set_label(&match items.len() {
+        0 => format!("The trash (~/{}) is empty.", REMOTE_TRASH_DIR),
+        n => format!("{} items in ~/{}; select items to restore or delete.", n, REMOTE_TRASH_DIR),
+    });
+    info_frame.set_align(Align::Left | Align::Inside);
+
+    let list_y = padding * 2 + row_height;
+    let mut list = MultiBrowser::new(padding, list_y, width - padding * 2, height - list_y - padding * 2 - row_height, None);
+    list.set_column_char('\t');
+    list.set_column_widths(&[180, 300, 140]);
+    for item in items {
+        let folder = item.original.parent().map(|p| p.display().to_string()).unwrap_or_default();
+        let deleted = item.deleted_at().map(|time| time.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default();
+        list.add(&format!("@.{}\t@.{}\t@.{}", item.name(), folder, deleted));
+    }
+
+    let buttons_y = height - padding - row_height;
+    let mut restore_button = Button::new(padding, buttons_y, button_width, row_height, "Restore");
+    restore_button.set_color(Color::from_rgb(0, 120, 255));
+    restore_button.set_label_color(Color::White);
+    let mut purge_button = Button::new(padding * 2 + button_width, buttons_y, button_width, row_height, "Delete Permanently");
+    let mut empty_button = Button::new(padding * 3 + button_width * 2, buttons_y, button_width, row_height, "Empty Trash");
+    let mut close_button = Button::new(width - padding - 80, buttons_y, 80, row_height, "Close");
+    if items.is_empty() {
+        restore_button.deactivate();
+        purge_button.deactivate();
+        empty_button.deactivate();
+    }
+
+    let action = Rc::new(RefCell::new(None::<TrashAction>));
+    let selected = {
+        let list = list.clone();
+        let items = items.to_vec();
+        move || -> Vec<TrashedItem> {
+            (0..items.len())
+                .filter(|i| list.selected(*i as i32 + 1))
+                .map(|i| items[i].clone())
+                .collect()
+        }
+    };
+
+    let mut dialog_close = dialog.clone();
+    close_button.set_callback(move |_| dialog_close.close());
+
+    for (button, make) in [
+        (&mut restore_button, TrashAction::Restore as fn(Vec<TrashedItem>) -> TrashAction),
+        (&mut purge_button, TrashAction::Purge as fn(Vec<TrashedItem>) -> TrashAction),
+    ] {
+        let action = action.clone();
+        let selected = selected.clone();
+        let mut dialog = dialog.clone();
+        button.set_callback(move |_| {
+            let chosen = selected();
+            if chosen.is_empty() {
+                dialogs::message_dialog("Remote Trash", "Select the items first.");
+                return;
+            }
+            *action.borrow_mut() = Some(make(chosen));
+            dialog.close();
+        });
+    }
+
+    let action_empty = action.clone();
+    let mut dialog_empty = dialog.clone();
+    empty_button.set_callback(move |_| {
+        *action_empty.borrow_mut() = Some(TrashAction::Empty);
+        dialog_empty.close();
+    });
+
+    dialog.run();
+
+    let chosen = action.borrow_mut().take();
+    chosen
+}
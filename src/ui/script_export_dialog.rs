@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::core::file::temp_download_path;
+use crate::core::image::{cron_line, install_cron_command, ImageFormat, ImageProcessingService, PiScript, CRON_SCHEDULES};
+use crate::core::locale::tr;
+use crate::core::utils::{AppError, AppResult};
+use crate::transfer::method::TransferMethod;
+use crate::transfer::ssh::shell_quote;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+
+/// Turn the operations set up in the Image Processing tab into an
+/// ImageMagick shell script, then save it or upload it to the Pi and
+/// optionally schedule it with cron there
+///
+/// `remote` is the connection and current directory of the remote pane;
+/// without one only saving is offered.
+pub fn export_pipeline_script(
+    image_service: Arc<Mutex<ImageProcessingService>>,
+    remote: Option<(Box<dyn TransferMethod>, PathBuf)>
+) {
+    let format = match dialogs::choice_dialog("Export as Script", "Write the results as:", &["JPEG", "PNG", "Cancel"]) {
+        0 => ImageFormat::JPEG,
+        1 => ImageFormat::PNG,
+        _ => return,
+    };
+
+    let initial_input = remote
+        .as_ref()
+        .map(|(_, dir)| dir.display().to_string())
+        .unwrap_or_else(|| "/home/pi/Pictures".to_string());
+    let Some(input_dir) = dialogs::input_dialog("Export as Script", "Folder of images on the Pi:", &initial_input) else { return };
+    let input_dir = input_dir.trim().trim_end_matches('/').to_string();
+    let Some(output_dir) = dialogs::input_dialog(
+        "Export as Script",
+        "Folder for the results:",
+        &format!("{}/processed", input_dir)
+    ) else { return };
+
+    let settings = PiScript { input_dir, output_dir: output_dir.trim().to_string(), format };
+    let script = match settings.render(image_service.lock().unwrap().get_operations()) {
+        Ok(script) => script,
+        Err(e) => {
+            dialogs::error_dialog("Cannot export the operations", &e.into());
+            return;
+        },
+    };
+
+    let mut places = vec!["Save...", "Cancel"];
+    if remote.is_some() {
+        places.insert(1, "Upload to the Pi");
+    }
+    match places.get(dialogs::choice_dialog("Export as Script", "Send the script to:", &places) as usize) {
+        Some(&"Save...") => save_script(&script),
+        Some(&"Upload to the Pi") => {
+            if let Some((method, remote_dir)) = remote {
+                upload_script(script, method, remote_dir);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn save_script(script: &str) {
+    let Some(path) = dialogs::save_file_dialog("Save Script", "*.sh") else { return };
+    if let Err(e) = fs::write(&path, script) {
+        dialogs::error_dialog("Could not save the script", &e.into());
+        return;
+    }
+    log::info!("Saved the operations as {}", path.display());
+
+    // The script's place on the Pi is unknown here; the user fills it in
+    if let Some(schedule) = ask_schedule() {
+        let line = cron_line(&schedule, &format!("/home/pi/{}", path.file_name().unwrap_or_default().to_string_lossy()));
+        dialogs::report_dialog(
+            "Cron Line",
+            &format!("Copy the script to the Pi, make it executable and add this line with `crontab -e`, changing the path if needed:\n\n{}", line)
+        );
+    } else {
+        notify(ToastKind::Success, "Script saved", &path.display().to_string());
+    }
+}
+
+fn upload_script(script: String, method: Box<dyn TransferMethod>, remote_dir: PathBuf) {
+    let Some(name) = dialogs::input_dialog("Export as Script", "Name of the script on the Pi:", "process_images.sh") else { return };
+    let remote_path = remote_dir.join(name.trim());
+    let schedule = ask_schedule();
+
+    run_in_background(
+        move || -> AppResult<String> {
+            let file_name = remote_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let local = temp_download_path(&file_name)?;
+            fs::write(&local, script)?;
+            method.upload_file(&local, &remote_path)?;
+            let _ = fs::remove_file(&local);
+
+            let script_path = remote_path.display().to_string();
+            let mut command = format!("chmod +x {}", shell_quote(&script_path));
+            if let Some(schedule) = &schedule {
+                command = format!("{} && {}", command, install_cron_command(&cron_line(schedule, &script_path), &script_path));
+            }
+            let output = method.exec(&command)?;
+            if !output.success() {
+                return Err(AppError::NetworkError(format!("{}: {}", command, output.stderr.trim())));
+            }
+
+            Ok(match schedule {
+                Some(schedule) => format!("{} runs on the Pi at \"{}\"; its output goes to {}.log", script_path, schedule, script_path),
+                None => format!("Run {} on the Pi to process the images", script_path),
+            })
+        },
+        |result| match result {
+            Ok(message) => {
+                log::info!("{}", message);
+                dialogs::message_dialog("Export as Script", &message);
+            },
+            Err(e) => dialogs::error_dialog("Could not install the script", &e),
+        }
+    );
+}
+
+/// Cron schedule to run the script on, or None to run it by hand
+fn ask_schedule() -> Option<String> {
+    let mut options = vec!["Don't schedule".to_string()];
+    options.extend(CRON_SCHEDULES.iter().map(|(label, schedule)| format!("{} ({})", tr(label), schedule)));
+    options.push("Custom...".to_string());
+
+    match dialogs::select_dialog("Export as Script", "Run the script on the Pi:", &options)? {
+        0 => None,
+        i if i <= CRON_SCHEDULES.len() => Some(CRON_SCHEDULES[i - 1].1.to_string()),
+        _ => dialogs::input_dialog("Export as Script", "Cron schedule (minute hour day month weekday):", "0 * * * *")
+            .map(|schedule| schedule.trim().to_string())
+            .filter(|schedule| schedule.split_whitespace().count() == 5),
+    }
+}
@@ -0,0 +1,316 @@
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, CallbackTrigger, Color},
+    frame::Frame,
+    input::{Input, IntInput},
+    prelude::*,
+};
+
+use chrono::{Local, TimeZone};
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::core::device::{
+    SearchCriteria, SearchMatch, MAX_SEARCH_RESULTS, search_command, parse_search_output,
+};
+use crate::core::utils::AppError;
+use crate::core::locale::tr;
+use crate::transfer::method::{TransferError, wait_with_deadline};
+use crate::ui::dialogs::dialogs;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::executor::run_in_background;
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::modal::ModalDialog;
+use crate::ui::theme::font_size;
+
+/// Longest a search may run before it is abandoned
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Find files on the Pi by name, size or age, then open their folder in the
+/// remote browser or download them
+pub fn remote_search_dialog(remote_browser: Arc<Mutex<FileBrowserPanel>>, events: EventBus) {
+    let start_dir = match remote_browser.lock() {
+        Ok(browser) if browser.has_transfer_method() => browser.get_current_directory(),
+        _ => {
+            dialogs::error_dialog("Cannot search the Pi", &AppError::NotConnected);
+            return;
+        }
+    };
+
+    let width = 600;
+    let height = 480;
+    let padding = 10;
+    let row_height = 25;
+    let label_width = 110;
+
+    let mut dialog = ModalDialog::new(width, height, "Search on Pi");
+
+    let mut root_input = Input::new(
+        padding + label_width,
+        padding,
+        width - 2 * padding - label_width,
+        row_height,
+        "Look in:"
+    );
+    root_input.set_align(Align::Left);
+    root_input.set_value(&start_dir.to_string_lossy());
+
+    let row2_y = padding * 2 + row_height;
+    let mut name_input = Input::new(
+        padding + label_width,
+        row2_y,
+        width - 2 * padding - label_width,
+        row_height,
+        "Name:"
+    );
+    name_input.set_align(Align::Left);
+    name_input.set_tooltip("Pattern such as *.jpg or frame_2024*; leave empty to match everything");
+    name_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+
+    let row3_y = padding * 3 + row_height * 2;
+    let small_width = 70;
+    let mut min_input = IntInput::new(padding + label_width, row3_y, small_width, row_height, "Min size (KB):");
+    min_input.set_align(Align::Left);
+    let mut max_input = IntInput::new(
+        padding + label_width * 2 + small_width,
+        row3_y,
+        small_width,
+        row_height,
+        "Max size (KB):"
+    );
+    max_input.set_align(Align::Left);
+    let mut days_input = IntInput::new(
+        width - padding - small_width,
+        row3_y,
+        small_width,
+        row_height,
+        "Changed in last days:"
+    );
+    days_input.set_align(Align::Left);
+
+    let row4_y = padding * 4 + row_height * 3;
+    let mut status_frame = Frame::new(padding, row4_y, width - 3 * padding - 100, row_height, None);
+    status_frame.set_align(Align::Left | Align::Inside);
+    status_frame.set_label_size(font_size(12));
+
+    let mut search_button = Button::new(width - padding - 100, row4_y, 100, row_height, "Search");
+    search_button.set_color(Color::from_rgb(0, 120, 255));
+    search_button.set_label_color(Color::White);
+
+    let results_y = padding * 5 + row_height * 4;
+    let mut results = HoldBrowser::new(
+        padding,
+        results_y,
+        width - 2 * padding,
+        height - results_y - 2 * padding - row_height,
+        None
+    );
+    results.set_column_widths(&[width - 2 * padding - 240, 90, 130]);
+    results.set_column_char('\t');
+
+    let buttons_y = height - padding - row_height;
+    let mut goto_button = Button::new(padding, buttons_y, 110, row_height, "Go to Folder");
+    let mut download_button = Button::new(padding * 2 + 110, buttons_y, 110, row_height, "Download...");
+    let mut close_button = Button::new(width - padding - 80, buttons_y, 80, row_height, "Close");
+
+    let matches: Arc<Mutex<Vec<SearchMatch>>> = Arc::new(Mutex::new(Vec::new()));
+    let running: Arc<Mutex<Option<Arc<AtomicBool>>>> = Arc::new(Mutex::new(None));
+
+    // Start a search, or stop the one running
+    let remote_browser_search = remote_browser.clone();
+    let matches_search = matches.clone();
+    let running_search = running.clone();
+    let mut results_search = results.clone();
+    let mut status_search = status_frame.clone();
+    let root_search = root_input.clone();
+    let name_search = name_input.clone();
+    let min_search = min_input.clone();
+    let max_search = max_input.clone();
+    let days_search = days_input.clone();
+    search_button.set_callback(move |button| {
+        if let Some(cancel) = running_search.lock().unwrap().as_ref() {
+            cancel.store(true, Ordering::Relaxed);
+            status_search.set_label(&tr("Stopping..."));
+            return;
+        }
+
+        let criteria = SearchCriteria {
+            root: PathBuf::from(root_search.value().trim()),
+            name_pattern: name_search.value().trim().to_string(),
+            min_size_kb: min_search.value().trim().parse().ok(),
+            max_size_kb: max_search.value().trim().parse().ok(),
+            modified_within_days: days_search.value().trim().parse().ok(),
+        };
+        if criteria.root.as_os_str().is_empty() {
+            dialogs::message_dialog("Search", "Enter a directory to search in.");
+            return;
+        }
+
+        let method = match remote_browser_search.lock().ok().and_then(|b| b.get_transfer_method()) {
+            Some(method) => method,
+            None => {
+                dialogs::error_dialog("Cannot search the Pi", &AppError::NotConnected);
+                return;
+            }
+        };
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        *running_search.lock().unwrap() = Some(cancel.clone());
+        results_search.clear();
+        matches_search.lock().unwrap().clear();
+        button.set_label(&tr("Stop"));
+        status_search.set_label(&format!("Searching {}...", criteria.root.display()));
+
+        let mut button = button.clone();
+        let mut results_done = results_search.clone();
+        let mut status_done = status_search.clone();
+        let matches_done = matches_search.clone();
+        let running_done = running_search.clone();
+        run_in_background(
+            move || {
+                let child = method.spawn_remote(&search_command(&criteria))?;
+                let output = wait_with_deadline(child, SEARCH_TIMEOUT, &cancel)?;
+                Ok::<_, TransferError>(parse_search_output(&output.stdout))
+            },
+            move |result| {
+                *running_done.lock().unwrap() = None;
+                button.set_label(&tr("Search"));
+
+                match result {
+                    Ok(found) => {
+                        for found in &found {
+                            results_done.add(&result_line(found));
+                        }
+                        let label = if found.len() >= MAX_SEARCH_RESULTS {
+                            format!("Showing the first {} matches; narrow the search to see the rest", found.len())
+                        } else {
+                            format!("{} matches", found.len())
+                        };
+                        status_done.set_label(&label);
+                        *matches_done.lock().unwrap() = found;
+                    },
+                    Err(TransferError::Cancelled) => status_done.set_label(&tr("Search stopped")),
+                    Err(e) => {
+                        status_done.set_label(&tr("Search failed"));
+                        dialogs::error_dialog("Search failed", &e.into());
+                    }
+                }
+                results_done.redraw();
+            }
+        );
+    });
+
+    let mut search_enter = search_button.clone();
+    name_input.set_callback(move |_| search_enter.do_callback());
+
+    // Show the folder holding the selected match in the remote browser
+    let matches_goto = matches.clone();
+    let results_goto = results.clone();
+    let remote_browser_goto = remote_browser.clone();
+    goto_button.set_callback(move |_| {
+        let target = match selected_match(&results_goto, &matches_goto) {
+            Some(found) if found.is_dir => found.path,
+            Some(found) => found.path.parent().map(Path::to_path_buf).unwrap_or(found.path),
+            None => return,
+        };
+
+        if let Ok(mut browser) = remote_browser_goto.lock() {
+            browser.set_current_remote_directory(&target);
+        }
+    });
+
+    // Double-clicking a match does the same
+    let mut goto_double = goto_button.clone();
+    results.set_callback(move |_| {
+        if app::event_clicks() {
+            goto_double.do_callback();
+        }
+    });
+
+    // Copy the selected file into a local folder
+    let matches_download = matches.clone();
+    let results_download = results.clone();
+    let remote_browser_download = remote_browser.clone();
+    download_button.set_callback(move |_| {
+        let found = match selected_match(&results_download, &matches_download) {
+            Some(found) if !found.is_dir => found,
+            Some(_) => {
+                dialogs::message_dialog("Download", "Only files can be downloaded; use Go to Folder for directories.");
+                return;
+            },
+            None => return,
+        };
+
+        let local_dir = match dialogs::open_directory_dialog("Download To") {
+            Some(dir) => dir,
+            None => return,
+        };
+        let file_name = found.path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+        let destination = local_dir.join(file_name);
+
+        let method = match remote_browser_download.lock().ok().and_then(|b| b.get_transfer_method()) {
+            Some(method) => method,
+            None => {
+                dialogs::error_dialog("Cannot download", &AppError::NotConnected);
+                return;
+            }
+        };
+
+        let events = events.clone();
+        run_in_background(
+            move || method.download_file(&found.path, &destination).map(|_| (found.path, destination)),
+            move |result| match result {
+                Ok((source, destination)) => {
+                    events.publish(AppEvent::TransferFinished { source, destination, upload: false });
+                },
+                Err(e) => dialogs::error_dialog("Download failed", &e.into()),
+            }
+        );
+    });
+
+    let mut dialog_close = dialog.clone();
+    let running_close = running.clone();
+    close_button.set_callback(move |_| {
+        if let Some(cancel) = running_close.lock().unwrap().as_ref() {
+            cancel.store(true, Ordering::Relaxed);
+        }
+        dialog_close.close();
+    });
+
+    dialog.run();
+
+    // Closing the window any other way still stops the search
+    if let Some(cancel) = running.lock().unwrap().as_ref() {
+        cancel.store(true, Ordering::Relaxed);
+    };
+}
+
+/// Line of the results list: path, size and modification time
+fn result_line(found: &SearchMatch) -> String {
+    let size = if found.is_dir {
+        "folder".to_string()
+    } else {
+        format!("{} KB", found.size.div_ceil(1024))
+    };
+    let modified = found.modified
+        .and_then(|secs| Local.timestamp_opt(secs, 0).single())
+        .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default();
+
+    // "@." stops FLTK from reading formatting codes out of the path
+    format!("@.{}\t@.{}\t@.{}", found.path.display(), size, modified)
+}
+
+/// Match behind the selected line of the results list
+fn selected_match(results: &HoldBrowser, matches: &Arc<Mutex<Vec<SearchMatch>>>) -> Option<SearchMatch> {
+    let line = results.value();
+    if line <= 0 {
+        return None;
+    }
+    matches.lock().unwrap().get(line as usize - 1).cloned()
+}
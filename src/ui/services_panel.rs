@@ -0,0 +1,397 @@
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Color, FrameType, Font, Align, CallbackTrigger},
+    frame::Frame,
+    group::Group,
+    input::Input,
+    text::{TextDisplay, TextBuffer},
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::core::device::{
+    ServiceUnit, ServiceAction, LIST_SERVICES_COMMAND,
+    status_command, journal_command, parse_service_list,
+};
+use crate::core::locale::{tr, trf};
+use crate::transfer::CommandOutput;
+use crate::ui::dialogs::dialogs;
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::theme::font_size;
+
+/// How often a running command is checked for completion (seconds)
+const POLL_INTERVAL: f64 = 0.1;
+
+/// What to do with the output of a finished command
+#[derive(Clone, Copy)]
+enum Request {
+    List,
+    Show,
+    Action(ServiceAction),
+}
+
+/// Panel for inspecting and controlling systemd services on the Pi
+pub struct ServicesPanel {
+    group: Group,
+    filter_input: Input,
+    service_list: HoldBrowser,
+    refresh_button: Button,
+    start_button: Button,
+    stop_button: Button,
+    restart_button: Button,
+    status_button: Button,
+    journal_button: Button,
+    status_frame: Frame,
+    output_buffer: TextBuffer,
+    output_display: TextDisplay,
+    units: Arc<Mutex<Vec<ServiceUnit>>>,
+    /// Units currently shown in the list after filtering
+    visible_units: Arc<Mutex<Vec<ServiceUnit>>>,
+    busy: Arc<Mutex<bool>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+}
+
+impl Clone for ServicesPanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            filter_input: self.filter_input.clone(),
+            service_list: self.service_list.clone(),
+            refresh_button: self.refresh_button.clone(),
+            start_button: self.start_button.clone(),
+            stop_button: self.stop_button.clone(),
+            restart_button: self.restart_button.clone(),
+            status_button: self.status_button.clone(),
+            journal_button: self.journal_button.clone(),
+            status_frame: self.status_frame.clone(),
+            output_buffer: self.output_buffer.clone(),
+            output_display: self.output_display.clone(),
+            units: self.units.clone(),
+            visible_units: self.visible_units.clone(),
+            busy: self.busy.clone(),
+            remote_browser: self.remote_browser.clone(),
+        }
+    }
+}
+
+impl ServicesPanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 80;
+
+        // Filter and refresh
+        let mut filter_input = Input::new(x + padding + 50, y + padding, 250, row_height, "Filter:");
+        filter_input.set_trigger(CallbackTrigger::Changed);
+        filter_input.set_tooltip("Show only services whose name or description contains this text");
+
+        let refresh_button = Button::new(
+            x + padding + 310,
+            y + padding,
+            button_width,
+            row_height,
+            "Refresh"
+        );
+
+        let mut status_frame = Frame::new(
+            x + padding + 400,
+            y + padding,
+            w - 2 * padding - 400,
+            row_height,
+            "Not connected"
+        );
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        // Service list
+        let list_y = y + 2 * padding + row_height;
+        let list_h = (h - 5 * padding - 2 * row_height) / 2;
+
+        let mut service_list = HoldBrowser::new(x + padding, list_y, w - 2 * padding, list_h, None);
+        service_list.set_column_char('\t');
+        service_list.set_column_widths(&[260, 80, 80, 90]);
+        service_list.set_text_size(font_size(12));
+
+        // Actions
+        let buttons_y = list_y + list_h + padding;
+        let make_button = |index: i32, label: &str| {
+            Button::new(
+                x + padding + index * (button_width + 5),
+                buttons_y,
+                button_width,
+                row_height,
+                None
+            ).with_label(label)
+        };
+
+        let start_button = make_button(0, "Start");
+        let stop_button = make_button(1, "Stop");
+        let restart_button = make_button(2, "Restart");
+        let status_button = make_button(3, "Status");
+        let journal_button = make_button(4, "Journal");
+
+        // Command output
+        let output_y = buttons_y + row_height + padding;
+        let output_buffer = TextBuffer::default();
+        let mut output_display = TextDisplay::new(
+            x + padding,
+            output_y,
+            w - 2 * padding,
+            y + h - padding - output_y,
+            None
+        );
+        output_display.set_buffer(output_buffer.clone());
+        output_display.set_frame(FrameType::BorderFrame);
+        output_display.set_text_font(Font::Courier);
+        output_display.set_text_size(font_size(12));
+
+        group.end();
+
+        let mut panel = ServicesPanel {
+            group,
+            filter_input,
+            service_list,
+            refresh_button,
+            start_button,
+            stop_button,
+            restart_button,
+            status_button,
+            journal_button,
+            status_frame,
+            output_buffer,
+            output_display,
+            units: Arc::new(Mutex::new(Vec::new())),
+            visible_units: Arc::new(Mutex::new(Vec::new())),
+            busy: Arc::new(Mutex::new(false)),
+            remote_browser,
+        };
+
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        let mut panel = self.clone();
+        self.refresh_button.set_callback(move |_| {
+            panel.refresh();
+        });
+
+        let mut panel = self.clone();
+        self.filter_input.set_callback(move |_| {
+            panel.populate_list();
+        });
+
+        let mut panel = self.clone();
+        self.start_button.set_callback(move |_| {
+            panel.run_action(ServiceAction::Start);
+        });
+
+        let mut panel = self.clone();
+        self.stop_button.set_callback(move |_| {
+            panel.run_action(ServiceAction::Stop);
+        });
+
+        let mut panel = self.clone();
+        self.restart_button.set_callback(move |_| {
+            panel.run_action(ServiceAction::Restart);
+        });
+
+        let mut panel = self.clone();
+        self.status_button.set_callback(move |_| {
+            if let Some(unit) = panel.selected_unit() {
+                panel.run(status_command(&unit.name), Request::Show);
+            }
+        });
+
+        let mut panel = self.clone();
+        self.journal_button.set_callback(move |_| {
+            if let Some(unit) = panel.selected_unit() {
+                panel.run(journal_command(&unit.name), Request::Show);
+            }
+        });
+
+        // Double-click shows the status of a service
+        let mut panel = self.clone();
+        self.service_list.set_callback(move |_| {
+            if app::event_clicks() {
+                if let Some(unit) = panel.selected_unit() {
+                    panel.run(status_command(&unit.name), Request::Show);
+                }
+            }
+        });
+    }
+
+    /// Reload the service list from the Pi
+    pub fn refresh(&mut self) {
+        self.run(LIST_SERVICES_COMMAND.to_string(), Request::List);
+    }
+
+    fn selected_unit(&mut self) -> Option<ServiceUnit> {
+        let line = self.service_list.value();
+        let unit = if line > 0 {
+            self.visible_units.lock().unwrap().get(line as usize - 1).cloned()
+        } else {
+            None
+        };
+
+        if unit.is_none() {
+            self.set_status("Select a service first", true);
+        }
+        unit
+    }
+
+    fn run_action(&mut self, action: ServiceAction) {
+        let unit = match self.selected_unit() {
+            Some(unit) => unit,
+            None => return,
+        };
+
+        if action.is_destructive() {
+            let result = dialogs::choice_dialog(
+                "Confirm",
+                &trf("Really {} {}?\nDependent services will be interrupted.", &[&tr(action.verb()), &unit.name]),
+                &["Yes", "No"]
+            );
+
+            if result != 0 { // User did not click "Yes"
+                return;
+            }
+        }
+
+        self.run(action.command(&unit.name), Request::Action(action));
+    }
+
+    /// Run a command on the Pi off the UI thread and handle its output
+    fn run(&mut self, command: String, request: Request) {
+        if *self.busy.lock().unwrap() {
+            return;
+        }
+
+        let method = self.remote_browser.lock().ok().and_then(|b| b.get_transfer_method());
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.set_status("Not connected", true);
+                return;
+            }
+        };
+
+        *self.busy.lock().unwrap() = true;
+        self.set_status(&format!("Running on {}...", method.get_description()), false);
+
+        let result: Arc<Mutex<Option<Result<CommandOutput, String>>>> = Arc::new(Mutex::new(None));
+        let result_worker = result.clone();
+
+        thread::spawn(move || {
+            let output = method.exec(&command).map_err(|e| e.to_string());
+            *result_worker.lock().unwrap() = Some(output);
+            app::awake();
+        });
+
+        let mut panel = self.clone();
+        app::add_timeout3(POLL_INTERVAL, move |handle| {
+            let finished = result.lock().unwrap().take();
+
+            match finished {
+                Some(output) => {
+                    *panel.busy.lock().unwrap() = false;
+                    panel.handle_result(request, output);
+                },
+                None => app::repeat_timeout3(POLL_INTERVAL, handle),
+            }
+        });
+    }
+
+    fn handle_result(&mut self, request: Request, output: Result<CommandOutput, String>) {
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                self.set_status(&format!("Error: {}", e), true);
+                return;
+            }
+        };
+
+        match request {
+            Request::List => {
+                let units = parse_service_list(&output.stdout);
+                if units.is_empty() && !output.success() {
+                    self.set_status(&format!("Could not list services: {}", output.stderr.trim()), true);
+                    return;
+                }
+
+                self.set_status(&format!("{} services", units.len()), false);
+                *self.units.lock().unwrap() = units;
+                self.populate_list();
+            },
+            Request::Show => {
+                // `systemctl status` exits non-zero for stopped units, so always show output
+                let mut text = output.stdout;
+                text.push_str(&output.stderr);
+                self.output_buffer.set_text(&text);
+                self.output_display.scroll(0, 0);
+                self.set_status("", false);
+            },
+            Request::Action(action) => {
+                if output.success() {
+                    self.set_status(&format!("{} succeeded", action.verb()), false);
+                    self.output_buffer.set_text(&output.stdout);
+                    self.refresh();
+                } else {
+                    let message = output.stderr.trim().to_string();
+                    self.set_status(&format!("{} failed", action.verb()), true);
+                    self.output_buffer.set_text(&message);
+                }
+            },
+        }
+    }
+
+    /// Fill the list with the units matching the filter
+    fn populate_list(&mut self) {
+        let filter = self.filter_input.value().to_lowercase();
+        let units = self.units.lock().unwrap();
+
+        let visible: Vec<ServiceUnit> = units
+            .iter()
+            .filter(|u| {
+                filter.is_empty()
+                    || u.name.to_lowercase().contains(&filter)
+                    || u.description.to_lowercase().contains(&filter)
+            })
+            .cloned()
+            .collect();
+
+        self.service_list.clear();
+        for unit in &visible {
+            // Highlight failed units in red
+            let prefix = if unit.is_failed() { "@C1" } else if unit.is_active() { "" } else { "@C8" };
+            self.service_list.add(&format!(
+                "{p}@.{}\t{p}@.{}\t{p}@.{}\t{p}@.{}\t{p}@.{}",
+                unit.name, unit.load, unit.active, unit.sub, unit.description,
+                p = prefix
+            ));
+        }
+
+        *self.visible_units.lock().unwrap() = visible;
+        self.service_list.redraw();
+    }
+
+    fn set_status(&mut self, message: &str, is_error: bool) {
+        self.status_frame.set_label(message);
+        self.status_frame.set_label_color(if is_error { Color::Red } else { Color::Black });
+        self.group.redraw();
+    }
+}
@@ -0,0 +1,118 @@
+use fltk::{
+    app,
+    enums::{Key, Shortcut},
+};
+
+use crate::config::KeyBindings;
+
+/// Browser actions bound to configurable keys
+///
+/// Built from [`KeyBindings`]; a binding that cannot be parsed falls back to
+/// the default key and is logged.
+#[derive(Debug, Clone, Copy)]
+pub struct ShortcutMap {
+    pub refresh: Shortcut,
+    pub transfer: Shortcut,
+    pub delete: Shortcut,
+    pub rename: Shortcut,
+    pub switch_pane: Shortcut,
+    pub quick_connect: Shortcut,
+}
+
+impl ShortcutMap {
+    pub fn from_bindings(bindings: &KeyBindings) -> Self {
+        let defaults = KeyBindings::default();
+        let pick = |name: &str, text: &str, default: &str| {
+            parse_shortcut(text).unwrap_or_else(|| {
+                log::warn!("Unknown key \"{}\" for {}, using {}", text, name, default);
+                parse_shortcut(default).unwrap_or(Shortcut::None)
+            })
+        };
+
+        Self {
+            refresh: pick("refresh", &bindings.refresh, &defaults.refresh),
+            transfer: pick("transfer", &bindings.transfer, &defaults.transfer),
+            delete: pick("delete", &bindings.delete, &defaults.delete),
+            rename: pick("rename", &bindings.rename, &defaults.rename),
+            switch_pane: pick("switch_pane", &bindings.switch_pane, &defaults.switch_pane),
+            quick_connect: pick("quick_connect", &bindings.quick_connect, &defaults.quick_connect),
+        }
+    }
+}
+
+/// Whether the key event being handled is `shortcut`
+///
+/// The key must match and so must Ctrl, Shift, Alt and Meta; letters match
+/// in either case, as FLTK reports them in lower case.
+pub fn is_pressed(shortcut: Shortcut) -> bool {
+    if shortcut == Shortcut::None {
+        return false;
+    }
+    let modifiers = Shortcut::Ctrl | Shortcut::Shift | Shortcut::Alt | Shortcut::Meta;
+    let lower = |key: Key| match u8::try_from(key.bits()) {
+        Ok(byte) => byte.to_ascii_lowercase() as i32,
+        Err(_) => key.bits(),
+    };
+    lower(app::event_key()) == lower(shortcut.key())
+        && app::event_state() & modifiers == shortcut & modifiers
+}
+
+/// Parse a key description such as "F5", "Delete" or "Ctrl+Shift+K"
+///
+/// Modifiers are Ctrl, Shift, Alt and Meta (or Cmd); names are matched
+/// without regard to case. Returns None for an unknown key.
+pub fn parse_shortcut(text: &str) -> Option<Shortcut> {
+    let mut modifiers = Shortcut::None;
+    let mut key = None;
+
+    for part in text.split('+').map(str::trim) {
+        if key.is_some() {
+            // Only the last part may name a key
+            return None;
+        }
+        match part.to_lowercase().as_str() {
+            "ctrl" | "control" => modifiers |= Shortcut::Ctrl,
+            "shift" => modifiers |= Shortcut::Shift,
+            "alt" => modifiers |= Shortcut::Alt,
+            "meta" | "cmd" | "command" => modifiers |= Shortcut::Meta,
+            name => key = Some(parse_key(name)?),
+        }
+    }
+
+    key.map(|key| modifiers | key)
+}
+
+fn parse_key(name: &str) -> Option<Shortcut> {
+    let key = match name {
+        "delete" | "del" => Key::Delete,
+        "insert" | "ins" => Key::Insert,
+        "backspace" => Key::BackSpace,
+        "tab" => Key::Tab,
+        "enter" | "return" => Key::Enter,
+        "escape" | "esc" => Key::Escape,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "space" => return Some(Shortcut::from_char(' ')),
+        _ => {
+            if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<i32>().ok()) {
+                if (1..=12).contains(&number) {
+                    return Some(Shortcut::from_key(Key::from_i32(Key::F1.bits() + number - 1)));
+                }
+            }
+
+            let mut chars = name.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_ascii_graphic() => Some(Shortcut::from_char(c)),
+                _ => None,
+            };
+        }
+    };
+
+    Some(Shortcut::from_key(key))
+}
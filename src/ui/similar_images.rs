@@ -0,0 +1,282 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::core::file::move_to_trash;
+use crate::core::image::{difference_hash, group_similar};
+use crate::core::image_utils::{find_images_in_dir, is_image_file};
+use crate::core::utils::AppError;
+use crate::transfer::agent::{agent_available, run_agent, AgentMessage, AgentRequest};
+use crate::transfer::method::{TransferError, TransferMethod};
+use crate::transfer::remote_trash::move_to_remote_trash;
+use crate::transfer::ssh::shell_quote_path;
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::core::locale::trf;
+
+/// Longest side of the thumbnails the Pi's agent makes for hashing; far
+/// more than the hash looks at, yet quick to download
+const THUMBNAIL_SIZE: u32 = 256;
+
+/// Longest the agent may take over one thumbnail
+const THUMBNAIL_STALL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Near-duplicates found in a folder
+struct Analysis {
+    /// Groups of similar images, as paths in the analysed folder
+    groups: Vec<Vec<PathBuf>>,
+    compared: usize,
+    /// Images that could not be read or downloaded
+    errors: Vec<String>,
+}
+
+/// Group the near-duplicate images in `folder` and offer to delete all but
+/// one of each group
+///
+/// With a `method` the folder is on the Pi: its images are downloaded to a
+/// temporary folder for hashing and the copies chosen are deleted there,
+/// into the Pi's trash with `remote_trash`, after which `on_remote_deleted`
+/// runs. Local copies go to the trash. Images whose hashes differ by at
+/// most `threshold` bits are grouped.
+pub fn find_similar_images<F>(
+    folder: PathBuf,
+    method: Option<Box<dyn TransferMethod>>,
+    threshold: u32,
+    remote_trash: bool,
+    on_remote_deleted: F
+) where
+    F: FnOnce() + Send + 'static,
+{
+    notify(ToastKind::Info, "Finding similar images", &folder.display().to_string());
+
+    run_in_background(
+        move || {
+            let analysis = match &method {
+                Some(method) => analyse_remote(method.as_ref(), &folder, threshold),
+                None => Ok(analyse(sorted(find_images_in_dir(&folder)), threshold, |path| path.to_path_buf())),
+            };
+            (analysis, method)
+        },
+        move |(analysis, method): (Result<Analysis, AppError>, Option<Box<dyn TransferMethod>>)| {
+            let analysis = match analysis {
+                Ok(analysis) => analysis,
+                Err(e) => {
+                    dialogs::error_dialog("Finding similar images failed", &e);
+                    return;
+                }
+            };
+            for error in &analysis.errors {
+                log::warn!("Not compared: {}", error);
+            }
+
+            if analysis.groups.is_empty() {
+                dialogs::message_dialog(
+                    "Similar Images",
+                    &trf("No near-duplicates among {} images.", &[&analysis.compared])
+                );
+                return;
+            }
+
+            let Some(chosen) = choose_copies(&analysis) else { return };
+            if chosen.is_empty() {
+                return;
+            }
+            match method {
+                Some(method) => delete_remote(method, chosen, remote_trash, on_remote_deleted),
+                None => match move_to_trash(&chosen) {
+                    Ok(()) => notify(ToastKind::Success, "Similar images", &trf("Moved {} images to the trash", &[&chosen.len()])),
+                    Err(e) => dialogs::error_dialog("Move to Trash failed", &e),
+                },
+            }
+        }
+    );
+}
+
+/// Let the user pick which images to delete; all but the first of each
+/// group are picked to begin with
+fn choose_copies(analysis: &Analysis) -> Option<Vec<PathBuf>> {
+    let mut copies = Vec::new();
+    let mut options = Vec::new();
+    for (index, group) in analysis.groups.iter().enumerate() {
+        let kept = file_name(&group[0]);
+        for copy in &group[1..] {
+            options.push(format!("Group {}: {} (keeping {})", index + 1, file_name(copy), kept));
+            copies.push(copy.clone());
+        }
+    }
+
+    let message = format!(
+        "{} groups of near-duplicates among {} images{}. Delete the selected copies?",
+        analysis.groups.len(),
+        analysis.compared,
+        match analysis.errors.len() {
+            0 => String::new(),
+            n => format!(" ({} could not be read)", n),
+        }
+    );
+    let selected = dialogs::multi_select_dialog("Similar Images", &message, &options)?;
+    Some(selected.into_iter().map(|i| copies[i].clone()).collect())
+}
+
+fn delete_remote<F>(method: Box<dyn TransferMethod>, paths: Vec<PathBuf>, to_trash: bool, on_deleted: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    let (title, question, done) = if to_trash {
+        (
+            "Move to Trash",
+            format!("Move {} images to the Pi's trash?", paths.len()),
+            format!("Moved {} images to the Pi's trash", paths.len()),
+        )
+    } else {
+        (
+            "Delete",
+            format!("Permanently delete {} images on the Pi?", paths.len()),
+            format!("Deleted {} images on the Pi", paths.len()),
+        )
+    };
+    if dialogs::choice_dialog(title, &question, &[title, "Cancel"]) != 0 {
+        return;
+    }
+
+    run_in_background(
+        move || {
+            if to_trash {
+                return move_to_remote_trash(method.as_ref(), &paths);
+            }
+            let quoted: Vec<String> = paths.iter().map(|p| shell_quote_path(p)).collect();
+            let output = method.exec(&format!("rm -f -- {}", quoted.join(" ")))?;
+            if output.success() {
+                Ok(())
+            } else {
+                Err(TransferError::PermissionDenied(output.stderr.trim().to_string()))
+            }
+        },
+        move |result| {
+            match result {
+                Ok(()) => notify(ToastKind::Success, "Similar images", &done),
+                Err(e) => dialogs::error_dialog("Delete failed", &e.into()),
+            }
+            on_deleted();
+        }
+    );
+}
+
+/// Download the images of a Pi folder and analyse the copies, reporting
+/// the Pi's paths
+fn analyse_remote(method: &dyn TransferMethod, folder: &Path, threshold: u32) -> Result<Analysis, AppError> {
+    let temp_dir = std::env::temp_dir()
+        .join("pi_remote_manager")
+        .join(format!("similar-{}", std::process::id()));
+    fs::create_dir_all(&temp_dir)?;
+
+    // Thumbnails hash the same and are much quicker to download
+    let thumbnails = if agent_available(method) {
+        download_thumbnails(method, folder, &temp_dir)
+            .map_err(|e| log::warn!("Agent thumbnails of {} failed, downloading the images: {}", folder.display(), e))
+            .ok()
+    } else {
+        None
+    };
+
+    let (copies, errors) = match thumbnails {
+        Some(thumbnails) => thumbnails,
+        None => {
+            let names: Vec<OsString> = method
+                .list_files(folder)?
+                .into_iter()
+                .filter(|(name, is_dir)| !*is_dir && is_image_file(Path::new(name)))
+                .map(|(name, _)| name)
+                .collect();
+
+            let mut errors = Vec::new();
+            let mut copies = Vec::new();
+            for name in sorted(names) {
+                let copy = temp_dir.join(&name);
+                match method.download_file(&folder.join(&name), &copy) {
+                    Ok(()) => copies.push(copy),
+                    Err(e) => errors.push(format!("{}: {}", name.to_string_lossy(), e)),
+                }
+            }
+            (copies, errors)
+        },
+    };
+
+    let mut analysis = analyse(copies, threshold, |copy| folder.join(copy.file_name().unwrap_or_default()));
+    analysis.compared += errors.len();
+    analysis.errors.extend(errors);
+    let _ = fs::remove_dir_all(&temp_dir);
+    Ok(analysis)
+}
+
+/// Have the Pi's companion agent shrink the folder's images, then download
+/// the thumbnails into `temp_dir` under the images' names
+fn download_thumbnails(
+    method: &dyn TransferMethod,
+    folder: &Path,
+    temp_dir: &Path
+) -> Result<(Vec<PathBuf>, Vec<String>), TransferError> {
+    let remote_dir = PathBuf::from(format!("/tmp/pi_remote_manager-thumbnails-{}", std::process::id()));
+    let request = AgentRequest::Thumbnails {
+        dir: folder.to_path_buf(),
+        size: THUMBNAIL_SIZE,
+        output_dir: remote_dir.clone(),
+    };
+
+    let mut thumbnails = Vec::new();
+    let mut errors = Vec::new();
+    let result = run_agent(method, &request, THUMBNAIL_STALL_TIMEOUT, &AtomicBool::new(false), &mut |message| match message {
+        AgentMessage::Thumbnail { output, .. } => thumbnails.push(output),
+        AgentMessage::Error { message, path: Some(path) } => errors.push(format!("{}: {}", file_name(&path), message)),
+        _ => {},
+    });
+
+    let copies = result.map(|()| {
+        let mut copies = Vec::new();
+        for thumbnail in sorted(thumbnails) {
+            let copy = temp_dir.join(file_name(&thumbnail));
+            match method.download_file(&thumbnail, &copy) {
+                Ok(()) => copies.push(copy),
+                Err(e) => errors.push(format!("{}: {}", file_name(&thumbnail), e)),
+            }
+        }
+        copies
+    });
+    let _ = method.exec(&format!("rm -rf {}", shell_quote_path(&remote_dir)));
+    copies.map(|copies| (copies, errors))
+}
+
+/// Hash and group `images`, naming each by `original`
+fn analyse<F>(images: Vec<PathBuf>, threshold: u32, original: F) -> Analysis
+where
+    F: Fn(&Path) -> PathBuf,
+{
+    let compared = images.len();
+    let mut hashes = Vec::new();
+    let mut errors = Vec::new();
+    for image in images {
+        match difference_hash(&image) {
+            Ok(hash) => hashes.push((original(&image), hash)),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+
+    Analysis {
+        groups: group_similar(&hashes, threshold),
+        compared,
+        errors,
+    }
+}
+
+/// Sort by name, which for camera and time-lapse files is capture order
+fn sorted<T: Ord>(mut items: Vec<T>) -> Vec<T> {
+    items.sort();
+    items
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+
+use fltk::{
+    enums::{Align, FrameType},
+    frame::Frame,
+    group::Group,
+    prelude::*,
+};
+
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::theme::font_size;
+
+/// Height of the bar along the bottom of the main window
+pub const STATUS_BAR_HEIGHT: i32 = 22;
+
+/// How many of the latest copies the transfer time estimate is based on
+const RECENT_TRANSFERS: usize = 10;
+
+/// Bottom line of the main window: the connected Pi, what the transfer
+/// queue is doing and what is selected in the browsers, with an estimate
+/// of how long copying the selection would take
+///
+/// Everything shown arrives through the event bus.
+#[derive(Clone)]
+pub struct StatusBar {
+    group: Group,
+    connection: Frame,
+    transfers: Frame,
+    selection: Frame,
+}
+
+impl StatusBar {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::ThinUpBox);
+
+        let section = w / 3;
+        let mut connection = Frame::new(x + 5, y, section - 5, h, "Not connected");
+        let mut transfers = Frame::new(x + section, y, section, h, "No transfers");
+        let mut selection = Frame::new(x + 2 * section, y, w - 2 * section - 5, h, None);
+        for frame in [&mut connection, &mut transfers, &mut selection] {
+            frame.set_label_size(font_size(12));
+            frame.set_align(Align::Left | Align::Inside | Align::Clip);
+        }
+        selection.set_align(Align::Right | Align::Inside | Align::Clip);
+
+        group.end();
+
+        StatusBar { group, connection, transfers, selection }
+    }
+
+    /// Keep the bar up to date from now on
+    pub fn subscribe(&self, events: &EventBus) {
+        let mut bar = self.clone();
+        // Speed of the last finished copy, kept while the next one runs
+        let mut last_speed: Option<String> = None;
+        // Bytes and seconds of the latest copies
+        let mut recent: VecDeque<(u64, f64)> = VecDeque::new();
+        // Pane and item count of the selection shown, so a late count of
+        // folders doesn't replace the other pane's selection
+        let mut shown: Option<(bool, usize)> = None;
+
+        events.subscribe(move |event| match event {
+            AppEvent::ConnectionChanged { hostname } => {
+                let label = match hostname {
+                    Some(hostname) => format!("Connected to {}", hostname),
+                    None => "Not connected".to_string(),
+                };
+                bar.connection.set_label(&label);
+            },
+            AppEvent::TransferMeasured { bytes, seconds } => {
+                last_speed = Some(format_speed(*bytes, *seconds));
+                if recent.len() == RECENT_TRANSFERS {
+                    recent.pop_front();
+                }
+                recent.push_back((*bytes, *seconds));
+            },
+            AppEvent::TransferQueueChanged { running, queued } => {
+                let mut label = match running {
+                    Some(source) => format!(
+                        "Copying {}",
+                        source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+                    ),
+                    None => "No transfers".to_string(),
+                };
+                if *queued > 0 {
+                    label.push_str(&format!(", {} queued", queued));
+                }
+                if let Some(speed) = &last_speed {
+                    label.push_str(&format!(" (last {})", speed));
+                }
+                bar.transfers.set_label(&label);
+            },
+            AppEvent::SelectionChanged { is_remote, count, bytes, scanning } => {
+                shown = Some((*is_remote, *count));
+                let label = match count {
+                    0 => String::new(),
+                    _ if *scanning => format!("{}, counting folders...", items_label(*is_remote, *count)),
+                    _ => format!("{}, {}{}", items_label(*is_remote, *count), format_bytes(*bytes), estimate(&recent, *bytes)),
+                };
+                bar.selection.set_label(&label);
+            },
+            AppEvent::SelectionMeasured { is_remote, files, bytes } => {
+                let count = match shown {
+                    Some((pane, count)) if pane == *is_remote => count,
+                    _ => return,
+                };
+                let files = if *files == 1 { "1 file".to_string() } else { format!("{} files", files) };
+                bar.selection.set_label(&format!(
+                    "{}, {}, {}{}",
+                    items_label(*is_remote, count),
+                    files,
+                    format_bytes(*bytes),
+                    estimate(&recent, *bytes)
+                ));
+            },
+            _ => {},
+        });
+    }
+
+    /// Place the bar along the bottom of a window of the given size
+    pub fn resize_to_window(&mut self, w: i32, h: i32) {
+        self.group.resize(0, h - STATUS_BAR_HEIGHT, w, STATUS_BAR_HEIGHT);
+    }
+}
+
+/// Pane and number of selected items, e.g. "Pi: 3 items"
+fn items_label(is_remote: bool, count: usize) -> String {
+    let pane = if is_remote { "Pi" } else { "Local" };
+    if count == 1 {
+        format!("{}: 1 item", pane)
+    } else {
+        format!("{}: {} items", pane, count)
+    }
+}
+
+/// How long copying `bytes` would take at the speed of the recent copies,
+/// e.g. ", about 3 min at 4.2 MB/s"; empty before anything was copied
+fn estimate(recent: &VecDeque<(u64, f64)>, bytes: u64) -> String {
+    let (copied, seconds) = recent
+        .iter()
+        .fold((0u64, 0.0f64), |(copied, seconds), (b, s)| (copied + b, seconds + s));
+    if bytes == 0 || copied == 0 || seconds <= 0.0 {
+        return String::new();
+    }
+    let speed = copied as f64 / seconds;
+    format!(", about {} at {}", format_duration(bytes as f64 / speed), format_speed(copied, seconds))
+}
+
+/// Rough duration such as "40 s", "3 min" or "1 h 20 min"
+pub fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.ceil().max(1.0) as u64;
+    let minutes = (seconds + 30) / 60;
+    if seconds < 60 {
+        format!("{} s", seconds)
+    } else if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    }
+}
+
+/// Size in B, KB, MB or GB with one decimal
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes >= 1024 * 1024 * 1024 {
+        format!("{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    } else if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// Speed of moving `bytes` in `seconds`, e.g. "1.2 MB/s"
+pub fn format_speed(bytes: u64, seconds: f64) -> String {
+    if seconds <= 0.0 {
+        return format!("{} in no time", format_bytes(bytes));
+    }
+    format!("{}/s", format_bytes((bytes as f64 / seconds) as u64))
+}
@@ -0,0 +1,120 @@
+use fltk::{
+    button::Button,
+    enums::{Align, Color},
+    frame::Frame,
+    input::IntInput,
+    prelude::*,
+};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::config::StorageSettings;
+use crate::core::storage::{self, Store, StoreUsage};
+use crate::ui::executor::run_in_background;
+use crate::ui::modal::ModalDialog;
+use crate::ui::notifications::{notify, ToastKind};
+use crate::ui::status_bar::format_bytes;
+use crate::core::locale::trf;
+
+/// Show how much each cache, the temporary files and the logs take, with a
+/// button to clear each, and let the user change the retention limits;
+/// returns the new limits, or None when cancelled
+pub fn storage_dialog(current: &StorageSettings) -> Option<StorageSettings> {
+    let width = 480;
+    let padding = 10;
+    let row_height = 25;
+    let label_width = 150;
+    let usage_width = 200;
+    let limits_y = padding * 3 + row_height + (row_height + padding) * Store::ALL.len() as i32;
+    let height = limits_y + (row_height + padding) * 4 + padding + row_height;
+
+    let mut dialog = ModalDialog::new(width, height, "Storage and Caches");
+
+    let mut heading = Frame::new(padding, padding, width - padding * 2, row_height, "Space used:");
+    heading.set_align(Align::Left | Align::Inside);
+
+    for (index, store) in Store::ALL.into_iter().enumerate() {
+        let row_y = padding * 2 + row_height + (row_height + padding) * index as i32;
+        let mut label = Frame::new(padding, row_y, label_width, row_height, store.label());
+        label.set_align(Align::Left | Align::Inside);
+
+        let mut usage_frame = Frame::new(padding * 2 + label_width, row_y, usage_width, row_height, "Counting...");
+        usage_frame.set_align(Align::Left | Align::Inside);
+        show_usage(store, &usage_frame);
+
+        let mut clear_button = Button::new(width - padding - 80, row_y, 80, row_height, "Clear");
+        clear_button.set_callback(move |button| {
+            let mut button = button.clone();
+            let usage_frame = usage_frame.clone();
+            button.deactivate();
+            run_in_background(
+                move || storage::clear(store),
+                move |freed| {
+                    notify(ToastKind::Info, store.label(), &trf("Freed {}", &[&format_bytes(freed)]));
+                    button.activate();
+                    show_usage(store, &usage_frame);
+                }
+            );
+        });
+    }
+
+    let limits: [(&str, u64); 4] = [
+        ("Preview cache limit (MB):", current.preview_cache_mb),
+        ("Thumbnail cache limit (MB):", current.thumbnail_cache_mb),
+        ("Delete temporary files after (hours):", current.temp_file_hours),
+        ("Check the limits every (minutes):", current.cleanup_minutes),
+    ];
+    let mut inputs = Vec::new();
+    for (index, (text, value)) in limits.into_iter().enumerate() {
+        let row_y = limits_y + (row_height + padding) * index as i32;
+        let mut label = Frame::new(padding, row_y, width - padding * 3 - 100, row_height, text);
+        label.set_align(Align::Left | Align::Inside);
+        let mut input = IntInput::new(width - padding - 100, row_y, 100, row_height, None);
+        input.set_value(&value.to_string());
+        inputs.push(input);
+    }
+
+    let buttons_y = height - padding - row_height;
+    let mut cancel_button = Button::new(width - padding * 2 - 160, buttons_y, 80, row_height, "Cancel");
+    let mut ok_button = Button::new(width - padding - 80, buttons_y, 80, row_height, "Save");
+    ok_button.set_color(Color::from_rgb(0, 120, 255));
+    ok_button.set_label_color(Color::White);
+
+    let mut dialog_cancel = dialog.clone();
+    cancel_button.set_callback(move |_| {
+        dialog_cancel.close();
+    });
+
+    let result = Rc::new(RefCell::new(None::<StorageSettings>));
+    let result_ok = result.clone();
+    let mut dialog_ok = dialog.clone();
+    let defaults = current.clone();
+    ok_button.set_callback(move |_| {
+        let value = |index: usize, default: u64| inputs[index].value().trim().parse::<u64>().unwrap_or(default);
+        *result_ok.borrow_mut() = Some(StorageSettings {
+            preview_cache_mb: value(0, defaults.preview_cache_mb),
+            thumbnail_cache_mb: value(1, defaults.thumbnail_cache_mb),
+            temp_file_hours: value(2, defaults.temp_file_hours).max(1),
+            cleanup_minutes: value(3, defaults.cleanup_minutes).max(1),
+        });
+        dialog_ok.close();
+    });
+
+    dialog.run();
+
+    let settings = result.borrow().clone();
+    settings
+}
+
+/// Count `store` in the background and show the result in `frame`
+fn show_usage(store: Store, frame: &Frame) {
+    let mut frame = frame.clone();
+    run_in_background(
+        move || storage::usage(store),
+        move |usage: StoreUsage| {
+            let items = if store == Store::Logs { "records" } else { "items" };
+            frame.set_label(&format!("{}, {} {}", format_bytes(usage.bytes), usage.items, items));
+        }
+    );
+}
@@ -0,0 +1,322 @@
+use fltk::{
+    app,
+    button::Button,
+    enums::{Color, FrameType, Font, Align, CallbackTrigger, Event, Key},
+    frame::Frame,
+    group::Group,
+    input::Input,
+    text::{TextDisplay, TextBuffer},
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::transfer::ssh::shell_quote_path;
+use crate::transfer::CommandOutput;
+use crate::ui::file_browser::file_browser::FileBrowserPanel;
+use crate::ui::theme::font_size;
+
+/// How often a running command is checked for completion (seconds)
+const POLL_INTERVAL: f64 = 0.1;
+
+/// Maximum number of commands kept in the history
+const MAX_HISTORY: usize = 200;
+
+/// Previously executed commands with a cursor for Up/Down recall
+#[derive(Default)]
+struct CommandHistory {
+    entries: Vec<String>,
+    /// Position while browsing; equal to entries.len() when not browsing
+    cursor: usize,
+}
+
+impl CommandHistory {
+    fn push(&mut self, command: &str) {
+        if self.entries.last().map(|c| c.as_str()) != Some(command) {
+            self.entries.push(command.to_string());
+            if self.entries.len() > MAX_HISTORY {
+                self.entries.remove(0);
+            }
+        }
+        self.cursor = self.entries.len();
+    }
+
+    fn previous(&mut self) -> Option<&str> {
+        if self.cursor == 0 {
+            return self.entries.first().map(|c| c.as_str());
+        }
+        self.cursor -= 1;
+        self.entries.get(self.cursor).map(|c| c.as_str())
+    }
+
+    fn next(&mut self) -> Option<&str> {
+        if self.cursor >= self.entries.len() {
+            return None;
+        }
+        self.cursor += 1;
+        // Moving past the newest entry yields an empty line
+        Some(self.entries.get(self.cursor).map(|c| c.as_str()).unwrap_or(""))
+    }
+}
+
+/// A minimal terminal that runs commands on the connected Pi
+pub struct TerminalPanel {
+    group: Group,
+    output_display: TextDisplay,
+    output_buffer: TextBuffer,
+    command_input: Input,
+    run_button: Button,
+    clear_button: Button,
+    status_frame: Frame,
+    history: Arc<Mutex<CommandHistory>>,
+    running: Arc<Mutex<bool>>,
+    remote_browser: Arc<Mutex<FileBrowserPanel>>,
+}
+
+impl Clone for TerminalPanel {
+    fn clone(&self) -> Self {
+        Self {
+            group: self.group.clone(),
+            output_display: self.output_display.clone(),
+            output_buffer: self.output_buffer.clone(),
+            command_input: self.command_input.clone(),
+            run_button: self.run_button.clone(),
+            clear_button: self.clear_button.clone(),
+            status_frame: self.status_frame.clone(),
+            history: self.history.clone(),
+            running: self.running.clone(),
+            remote_browser: self.remote_browser.clone(),
+        }
+    }
+}
+
+impl TerminalPanel {
+    pub fn new(
+        x: i32,
+        y: i32,
+        w: i32,
+        h: i32,
+        remote_browser: Arc<Mutex<FileBrowserPanel>>
+    ) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 80;
+
+        // Status line showing where commands run
+        let mut status_frame = Frame::new(
+            x + padding,
+            y + padding,
+            w - 2 * padding,
+            20,
+            "Not connected"
+        );
+        status_frame.set_label_size(font_size(12));
+        status_frame.set_align(Align::Left | Align::Inside);
+
+        // Command output
+        let output_buffer = TextBuffer::default();
+        let output_y = y + padding + 25;
+        let output_h = h - 3 * padding - 25 - row_height;
+
+        let mut output_display = TextDisplay::new(
+            x + padding,
+            output_y,
+            w - 2 * padding,
+            output_h,
+            None
+        );
+        output_display.set_buffer(output_buffer.clone());
+        output_display.set_frame(FrameType::BorderFrame);
+        output_display.set_color(Color::from_rgb(30, 30, 30));
+        output_display.set_text_color(Color::from_rgb(220, 220, 220));
+        output_display.set_text_font(Font::Courier);
+        output_display.set_text_size(font_size(12));
+
+        // Command line
+        let input_y = output_y + output_h + padding;
+        let mut command_input = Input::new(
+            x + padding + 20,
+            input_y,
+            w - 2 * padding - 20 - 2 * (button_width + 5),
+            row_height,
+            "$"
+        );
+        command_input.set_text_font(Font::Courier);
+        command_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+        command_input.set_tooltip("Command to run on the Pi (Up/Down for history)");
+
+        let mut run_button = Button::new(
+            x + w - padding - 2 * button_width - 5,
+            input_y,
+            button_width,
+            row_height,
+            "Run"
+        );
+        run_button.set_color(Color::from_rgb(0, 120, 255));
+        run_button.set_label_color(Color::White);
+
+        let clear_button = Button::new(
+            x + w - padding - button_width,
+            input_y,
+            button_width,
+            row_height,
+            "Clear"
+        );
+
+        group.end();
+
+        let mut panel = TerminalPanel {
+            group,
+            output_display,
+            output_buffer,
+            command_input,
+            run_button,
+            clear_button,
+            status_frame,
+            history: Arc::new(Mutex::new(CommandHistory::default())),
+            running: Arc::new(Mutex::new(false)),
+            remote_browser,
+        };
+
+        panel.setup_callbacks();
+
+        panel
+    }
+
+    fn setup_callbacks(&mut self) {
+        // Enter in the command line runs the command
+        let mut panel = self.clone();
+        self.command_input.set_callback(move |_| {
+            panel.run_current_command();
+        });
+
+        let mut panel = self.clone();
+        self.run_button.set_callback(move |_| {
+            panel.run_current_command();
+        });
+
+        let mut output_buffer = self.output_buffer.clone();
+        self.clear_button.set_callback(move |_| {
+            output_buffer.set_text("");
+        });
+
+        // Up/Down browse the command history
+        let history = self.history.clone();
+        self.command_input.handle(move |input, ev| {
+            if ev != Event::KeyDown {
+                return false;
+            }
+
+            let recalled = match app::event_key() {
+                Key::Up => history.lock().unwrap().previous().map(|c| c.to_string()),
+                Key::Down => history.lock().unwrap().next().map(|c| c.to_string()),
+                _ => return false,
+            };
+
+            if let Some(command) = recalled {
+                input.set_value(&command);
+                input.set_position(command.len() as i32).ok();
+            }
+            true
+        });
+    }
+
+    fn run_current_command(&mut self) {
+        let command = self.command_input.value().trim().to_string();
+        if command.is_empty() {
+            return;
+        }
+
+        if *self.running.lock().unwrap() {
+            return;
+        }
+
+        // Take a copy of the connection so the command can run off the UI thread
+        let (method, remote_dir) = match self.remote_browser.lock() {
+            Ok(browser) => (browser.get_transfer_method(), browser.get_current_directory()),
+            Err(_) => (None, Default::default()),
+        };
+
+        let method = match method {
+            Some(method) => method,
+            None => {
+                self.append_output("Not connected. Use Connection > Connect to Raspberry Pi first.\n");
+                return;
+            }
+        };
+
+        self.history.lock().unwrap().push(&command);
+        self.command_input.set_value("");
+
+        // Run commands from the directory shown in the remote browser
+        let remote_command = if remote_dir.as_os_str().is_empty() {
+            command.clone()
+        } else {
+            format!("cd {} && {}", shell_quote_path(&remote_dir), command)
+        };
+
+        self.status_frame.set_label(&format!("{} — {}", method.get_description(), remote_dir.display()));
+        self.append_output(&format!("$ {}\n", command));
+
+        *self.running.lock().unwrap() = true;
+        self.run_button.deactivate();
+
+        let result: Arc<Mutex<Option<Result<CommandOutput, String>>>> = Arc::new(Mutex::new(None));
+        let result_worker = result.clone();
+
+        thread::spawn(move || {
+            let output = method.exec(&remote_command).map_err(|e| e.to_string());
+            *result_worker.lock().unwrap() = Some(output);
+            app::awake();
+        });
+
+        let mut panel = self.clone();
+        app::add_timeout3(POLL_INTERVAL, move |handle| {
+            let finished = result.lock().unwrap().take();
+
+            match finished {
+                Some(Ok(output)) => {
+                    panel.append_output(&output.stdout);
+                    panel.append_output(&output.stderr);
+                    if !output.success() {
+                        let code = output.exit_code
+                            .map(|c| c.to_string())
+                            .unwrap_or_else(|| "signal".to_string());
+                        panel.append_output(&format!("[exit status: {}]\n", code));
+                    }
+                    panel.finish_command();
+                },
+                Some(Err(e)) => {
+                    panel.append_output(&format!("Error: {}\n", e));
+                    panel.finish_command();
+                },
+                None => app::repeat_timeout3(POLL_INTERVAL, handle),
+            }
+        });
+    }
+
+    fn finish_command(&mut self) {
+        *self.running.lock().unwrap() = false;
+        self.run_button.activate();
+        let _ = self.command_input.take_focus();
+    }
+
+    fn append_output(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.output_buffer.append(text);
+        if !text.ends_with('\n') {
+            self.output_buffer.append("\n");
+        }
+
+        // Keep the newest output in view
+        self.output_display.set_insert_position(self.output_buffer.length());
+        self.output_display.show_insert_position();
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::OnceLock;
+
+use fltk::{app, enums::Color, group::Group, prelude::*};
+
+use crate::config::Appearance;
+
+/// FLTK's own size of ordinary text; the sizes written in the panels are
+/// relative to it
+pub const DEFAULT_FONT_SIZE: i32 = 14;
+
+/// Scale factors offered in the Appearance dialog
+pub const UI_SCALES: &[f32] = &[1.0, 1.25, 1.5, 1.75, 2.0, 2.5];
+
+/// Font sizes offered in the Appearance dialog
+pub const FONT_SIZES: &[i32] = &[12, 14, 16, 18, 20, 24];
+
+static FONT_SIZE: AtomicI32 = AtomicI32::new(DEFAULT_FONT_SIZE);
+static HIGH_CONTRAST: AtomicBool = AtomicBool::new(false);
+
+/// Scale FLTK chose for each screen before the setting was applied
+fn screen_scales() -> &'static Vec<f32> {
+    static SCALES: OnceLock<Vec<f32>> = OnceLock::new();
+    SCALES.get_or_init(|| (0..app::screen_count()).map(app::screen_scale).collect())
+}
+
+/// Apply the font size and colours of `appearance` to widgets created
+/// from now on, and its scale to every window
+///
+/// Call before the main window is built; widgets that already exist keep
+/// their font size.
+pub fn apply_appearance(appearance: &Appearance) {
+    FONT_SIZE.store(appearance.font_size.clamp(8, 48), Ordering::Relaxed);
+    app::set_font_size(font_size(DEFAULT_FONT_SIZE));
+    apply_scale(appearance.ui_scale);
+
+    HIGH_CONTRAST.store(appearance.high_contrast, Ordering::Relaxed);
+    if appearance.high_contrast {
+        app::background(0, 0, 0);
+        app::background2(0, 0, 0);
+        app::foreground(255, 255, 255);
+        app::set_selection_color(255, 221, 0);
+        app::set_inactive_color(160, 160, 160);
+        app::set_visible_focus(true);
+    }
+}
+
+/// Scale every screen by `factor` on top of FLTK's own scaling
+pub fn apply_scale(factor: f32) {
+    let factor = factor.clamp(0.5, 4.0);
+    for (screen, base) in screen_scales().iter().enumerate() {
+        app::set_screen_scale(screen as i32, base * factor);
+    }
+}
+
+/// Size to use for text written as `size` points at FLTK's default size
+pub fn font_size(size: i32) -> i32 {
+    let scaled = size * FONT_SIZE.load(Ordering::Relaxed);
+    ((scaled + DEFAULT_FONT_SIZE / 2) / DEFAULT_FONT_SIZE).max(6)
+}
+
+/// Keep the colours panels chose themselves readable in high-contrast
+/// mode: dark text becomes white and light backgrounds black
+///
+/// Does nothing in the normal theme. Coloured indicators, such as the
+/// connection light, are mostly kept.
+pub fn apply_contrast<W: WidgetExt>(root: &mut W) {
+    if !HIGH_CONTRAST.load(Ordering::Relaxed) {
+        return;
+    }
+
+    if luminance(root.label_color()) < 128 {
+        root.set_label_color(Color::Foreground);
+    }
+    if luminance(root.color()) > 128 {
+        root.set_color(Color::Background);
+    }
+
+    if let Some(group) = Group::from_dyn_widget(&*root) {
+        for i in 0..group.children() {
+            if let Some(mut child) = group.child(i) {
+                apply_contrast(&mut child);
+            }
+        }
+    }
+}
+
+fn luminance(color: Color) -> u32 {
+    let (r, g, b) = color.to_rgb();
+    (299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000
+}
@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::core::image::{ffmpeg_args, frame_list, parse_progress_frame, TimeLapseSettings, VideoCodec, TIMELAPSE_WIDTHS};
+use crate::core::image_utils::{find_images_in_dir, is_image_file};
+use crate::core::utils::AppResult;
+use crate::transfer::method::{stream_lines_with_deadline, TransferError, TransferMethod};
+use crate::transfer::ssh::{shell_quote, shell_quote_path};
+use crate::ui::dialogs::dialogs;
+use crate::ui::executor::run_in_background;
+use crate::ui::processing_queue::ProcessingQueue;
+use crate::core::locale::trf;
+
+/// Longest ffmpeg may go without reporting progress before it is stopped;
+/// H.265 on a Pi can be slow to get going
+const FFMPEG_STALL_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Make an mp4 from the images in `folder`, in name order, as a job in the
+/// processing queue
+///
+/// With a `method` the frames are on the Pi and ffmpeg runs there, leaving
+/// the video next to them; otherwise the local ffmpeg is used.
+pub fn create_timelapse(folder: PathBuf, method: Option<Box<dyn TransferMethod>>, queue: ProcessingQueue) {
+    run_in_background(
+        move || {
+            let frames = match &method {
+                Some(method) => remote_frames(method.as_ref(), &folder),
+                None => Ok(find_images_in_dir(&folder)),
+            };
+            (frames, folder, method)
+        },
+        move |(frames, folder, method): (AppResult<Vec<PathBuf>>, PathBuf, Option<Box<dyn TransferMethod>>)| {
+            let mut frames = match frames {
+                Ok(frames) => frames,
+                Err(e) => {
+                    dialogs::error_dialog("Reading the frames failed", &e);
+                    return;
+                }
+            };
+            if frames.len() < 2 {
+                dialogs::message_dialog("Create Time-lapse", &trf("{} has fewer than two images.", &[&folder.display()]));
+                return;
+            }
+            frames.sort();
+
+            let Some(settings) = settings_dialog(frames.len()) else { return };
+            let default_name = format!(
+                "{}_timelapse.mp4",
+                folder.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| "frames".to_string())
+            );
+            let output = match &method {
+                Some(_) => match dialogs::input_dialog("Create Time-lapse", "Video name, saved next to the frames on the Pi:", &default_name) {
+                    Some(name) if !name.trim().is_empty() => folder.join(name.trim()),
+                    _ => return,
+                },
+                None => match dialogs::save_file_dialog("Save Time-lapse", "*.mp4") {
+                    Some(path) if path.extension().is_none() => path.with_extension("mp4"),
+                    Some(path) => path,
+                    None => return,
+                },
+            };
+
+            let name = format!("Time-lapse {}", output.file_name().unwrap_or_default().to_string_lossy());
+            let total = frames.len();
+            queue.enqueue_task(&name, total, Box::new(move |progress, cancel| {
+                let result = match method {
+                    Some(method) => encode_remote(method.as_ref(), &frames, &output, &settings, progress, cancel),
+                    None => encode_local(&frames, &output, &settings, progress, cancel),
+                };
+                match result {
+                    Ok(()) => {
+                        log::info!("Wrote time-lapse {} from {} frames", output.display(), total);
+                        Ok(())
+                    },
+                    // The queue reports the job as cancelled
+                    Err(TransferError::Cancelled) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }));
+        }
+    );
+}
+
+/// Ask for the frame rate, size and codec of a video of `frames` frames
+fn settings_dialog(frames: usize) -> Option<TimeLapseSettings> {
+    let defaults = TimeLapseSettings::default();
+
+    let fps = dialogs::input_dialog(
+        "Create Time-lapse",
+        &format!("Frames per second ({} frames):", frames),
+        &defaults.fps.to_string()
+    )?;
+    let Some(fps) = fps.trim().parse::<u32>().ok().filter(|fps| (1..=120).contains(fps)) else {
+        dialogs::message_dialog("Error", "The frame rate must be between 1 and 120.");
+        return None;
+    };
+
+    let sizes: Vec<String> = TIMELAPSE_WIDTHS.iter().map(|(name, _)| name.to_string()).collect();
+    let width = TIMELAPSE_WIDTHS[dialogs::select_dialog("Create Time-lapse", "Video size:", &sizes)?].1;
+
+    let codecs: Vec<String> = VideoCodec::ALL.iter().map(|codec| codec.label().to_string()).collect();
+    let codec = VideoCodec::ALL[dialogs::select_dialog("Create Time-lapse", "Codec:", &codecs)?];
+
+    Some(TimeLapseSettings { fps, width, codec })
+}
+
+/// Images in a Pi folder, as paths on the Pi
+fn remote_frames(method: &dyn TransferMethod, folder: &Path) -> AppResult<Vec<PathBuf>> {
+    Ok(method
+        .list_files(folder)?
+        .into_iter()
+        .filter(|(name, is_dir)| !*is_dir && is_image_file(Path::new(name)))
+        .map(|(name, _)| folder.join(name))
+        .collect())
+}
+
+/// Write the concat list for `frames` to a local temporary file
+fn write_frame_list(frames: &[PathBuf], fps: u32) -> Result<PathBuf, TransferError> {
+    let names: Vec<String> = frames.iter().map(|frame| frame.to_string_lossy().to_string()).collect();
+    let list = std::env::temp_dir().join(format!("pi_remote_manager-timelapse-{}.txt", std::process::id()));
+    fs::write(&list, frame_list(&names, fps))
+        .map_err(|e| TransferError::TransferFailed(format!("Could not write {}: {}", list.display(), e)))?;
+    Ok(list)
+}
+
+fn encode_local(
+    frames: &[PathBuf],
+    output: &Path,
+    settings: &TimeLapseSettings,
+    progress: &dyn Fn(usize),
+    cancel: &AtomicBool
+) -> Result<(), TransferError> {
+    let list = write_frame_list(frames, settings.fps)?;
+    let child = Command::new("ffmpeg")
+        .args(ffmpeg_args(&list, output, settings))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| TransferError::TransferFailed(format!("Could not run ffmpeg; is it installed? {}", e)));
+
+    let result = child.and_then(|child| run_ffmpeg(child, progress, cancel));
+    let _ = fs::remove_file(&list);
+    if result.is_err() {
+        let _ = fs::remove_file(output);
+    }
+    result
+}
+
+fn encode_remote(
+    method: &dyn TransferMethod,
+    frames: &[PathBuf],
+    output: &Path,
+    settings: &TimeLapseSettings,
+    progress: &dyn Fn(usize),
+    cancel: &AtomicBool
+) -> Result<(), TransferError> {
+    let local_list = write_frame_list(frames, settings.fps)?;
+    let remote_list = PathBuf::from("/tmp").join(local_list.file_name().unwrap_or_default());
+    let uploaded = method.upload_file(&local_list, &remote_list);
+    let _ = fs::remove_file(&local_list);
+    uploaded?;
+
+    let command: Vec<String> = std::iter::once("ffmpeg".to_string())
+        .chain(ffmpeg_args(&remote_list, output, settings))
+        .map(|arg| shell_quote(&arg))
+        .collect();
+    let result = method
+        .spawn_remote(&command.join(" "))
+        .and_then(|child| run_ffmpeg(child, progress, cancel));
+
+    let mut cleanup = format!("rm -f -- {}", shell_quote_path(&remote_list));
+    if result.is_err() {
+        cleanup.push_str(&format!(" {}", shell_quote_path(output)));
+    }
+    let _ = method.exec(&cleanup);
+    result
+}
+
+/// Follow ffmpeg's progress output until it exits
+fn run_ffmpeg(child: std::process::Child, progress: &dyn Fn(usize), cancel: &AtomicBool) -> Result<(), TransferError> {
+    let output = stream_lines_with_deadline(child, FFMPEG_STALL_TIMEOUT, cancel, &mut |line| {
+        if let Some(frame) = parse_progress_frame(line) {
+            progress(frame);
+        }
+    })?;
+
+    if output.success() {
+        Ok(())
+    } else {
+        Err(TransferError::TransferFailed(format!("ffmpeg failed: {}", output.stderr.trim())))
+    }
+}
+
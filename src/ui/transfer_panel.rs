@@ -1,26 +1,36 @@
 // ui/transfer_panel.rs - File transfer panel
 pub mod transfer_panel {
     use fltk::{
-        button::Button,
-        enums::{Color, FrameType},
+        button::{Button, CheckButton},
+        enums::{Align, Color, Event, FrameType},
         group::Group,
         input::Input,
+        menu::Choice,
         prelude::*,
     };
     
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::AtomicBool;
     use std::sync::{Arc, Mutex};
     
-    use crate::config::Config;
+    use crate::config::{Config, Host, TransferProtocol, TransferTemplate, UploadOptimization};
+    use crate::core::device::Capabilities;
 
     // Updated imports to use the new module structure
-    use crate::transfer::ssh::SSHTransferFactory;
-    use crate::transfer::method::{
-        TransferMethod,
-        TransferMethodFactory,
-    };
+    use crate::core::image::optimized_copy;
+    use crate::core::locale::{tr, trf};
+    use crate::core::image_utils::is_image_file;
+    use crate::transfer::factory_for_reachable_host;
+    use crate::transfer::method::{TransferError, TransferMethod};
+    use crate::transfer::filter::parse_patterns;
+    use crate::transfer::{Mirror, MirrorPlan, PathFilter, ProtocolChoice};
     
     use crate::ui::dialogs::dialogs;
+    use crate::ui::events::{AppEvent, EventBus};
+    use crate::ui::executor::run_in_background;
+    use crate::ui::notifications::{self, ToastKind};
+    use crate::ui::remote_dir_dialog::choose_remote_directory;
+    use crate::ui::theme::font_size;
     
     pub struct TransferPanel {
         group: Group,
@@ -28,10 +38,21 @@ pub mod transfer_panel {
         dest_input: Input,
         transfer_button: Button,
         direction_button: Button,
-        source_is_local: bool,
+        /// Protocol of the active host, saved when changed
+        method_choice: Choice,
+        template_choice: Choice,
+        run_template_button: Button,
+        save_template_button: Button,
+        delete_template_button: Button,
+        browse_button: Button,
+        optimize_check: CheckButton,
+        optimize_settings_button: Button,
+        /// Connection of the remote pane, used to browse the Pi for a destination
+        remote_method: Arc<Mutex<Option<Box<dyn TransferMethod>>>>,
+        /// Shared with the callbacks so every clone sees direction changes
+        source_is_local: Arc<Mutex<bool>>,
         config: Arc<Mutex<Config>>,
-        // Changed from Fn to FnMut
-        callback: Option<Box<dyn FnMut(bool, PathBuf, PathBuf) + Send + Sync>>,
+        events: EventBus,
     }
     
     impl Clone for TransferPanel {
@@ -42,9 +63,18 @@ pub mod transfer_panel {
                 dest_input: self.dest_input.clone(),
                 transfer_button: self.transfer_button.clone(),
                 direction_button: self.direction_button.clone(),
-                source_is_local: self.source_is_local,
+                method_choice: self.method_choice.clone(),
+                template_choice: self.template_choice.clone(),
+                run_template_button: self.run_template_button.clone(),
+                save_template_button: self.save_template_button.clone(),
+                delete_template_button: self.delete_template_button.clone(),
+                browse_button: self.browse_button.clone(),
+                optimize_check: self.optimize_check.clone(),
+                optimize_settings_button: self.optimize_settings_button.clone(),
+                remote_method: self.remote_method.clone(),
+                source_is_local: self.source_is_local.clone(),
                 config: self.config.clone(),
-                callback: None, // Cannot clone the callback
+                events: self.events.clone(),
             }
         }
     }
@@ -55,7 +85,8 @@ pub mod transfer_panel {
             y: i32, 
             w: i32, 
             h: i32,
-            config: Arc<Mutex<Config>>
+            config: Arc<Mutex<Config>>,
+            events: EventBus
         ) -> Self {
             let mut group = Group::new(x, y, w, h, None);
             group.set_frame(FrameType::EngravedBox);
@@ -75,9 +106,48 @@ pub mod transfer_panel {
                 20,
                 "File Transfer"
             );
-            title.set_label_size(14);
+            title.set_label_size(font_size(14));
             title.set_align(fltk::enums::Align::Center);
             
+            // Saved transfer jobs
+            let mut template_choice = Choice::new(
+                x + padding + 65,
+                y + padding - 2,
+                150,
+                22,
+                "Template:"
+            );
+            template_choice.set_align(Align::Left);
+            template_choice.set_tooltip("Saved transfers");
+            
+            let run_template_button = Button::new(x + padding + 220, y + padding - 2, 45, 22, "Run");
+            let mut save_template_button = Button::new(x + padding + 270, y + padding - 2, 55, 22, "Save...");
+            save_template_button.set_tooltip("Save the source, destination, direction and host as a template");
+            let mut delete_template_button = Button::new(x + padding + 330, y + padding - 2, 25, 22, "✕");
+            delete_template_button.set_tooltip("Delete the selected template");
+            
+            // Transfer method of the active host
+            let mut method_choice = Choice::new(
+                x + w - padding - button_width,
+                y + padding - 2,
+                button_width,
+                22,
+                "Method:"
+            );
+            method_choice.set_align(Align::Left);
+            for choice in ProtocolChoice::all() {
+                method_choice.add_choice(choice.label());
+            }
+            method_choice.set_tooltip("How files are copied to and from the active host");
+            
+            // Shrink images before uploading them
+            let optimize_x = x + w - padding - button_width - 60 - 160;
+            let mut optimize_check = CheckButton::new(optimize_x, y + padding - 2, 130, 22, "Optimize images");
+            optimize_check.set_checked(config.lock().unwrap().upload_optimization.enabled);
+            optimize_check.set_tooltip("Resize and recompress images before uploading them");
+            let mut optimize_settings_button = Button::new(optimize_x + 130, y + padding - 2, 25, 22, "...");
+            optimize_settings_button.set_tooltip("Maximum size and JPEG quality of optimized uploads");
+            
             // Source path
             let row1_y = y + padding + 25;
             let mut source_label = fltk::frame::Frame::new(
@@ -119,11 +189,20 @@ pub mod transfer_panel {
             let dest_input = Input::new(
                 x + padding + label_width,
                 row2_y,
-                input_width,
+                input_width - 85,
                 row_height,
                 None
             );
             
+            let mut browse_button = Button::new(
+                x + padding + label_width + input_width - 80,
+                row2_y,
+                80,
+                row_height,
+                "Browse..."
+            );
+            browse_button.set_tooltip("Choose the destination folder");
+            
             let mut transfer_button = Button::new(
                 x + padding + label_width + input_width + padding,
                 row2_y,
@@ -142,47 +221,101 @@ pub mod transfer_panel {
                 dest_input,
                 transfer_button,
                 direction_button,
-                source_is_local: true,
+                method_choice,
+                template_choice,
+                run_template_button,
+                save_template_button,
+                delete_template_button,
+                browse_button,
+                optimize_check,
+                optimize_settings_button,
+                remote_method: Arc::new(Mutex::new(None)),
+                source_is_local: Arc::new(Mutex::new(true)),
                 config,
-                callback: None,
+                events,
             };
             
+            panel.sync_method_choice();
+            panel.reload_templates();
             panel.setup_callbacks();
             
             panel
         }
         
         fn setup_callbacks(&mut self) {
-            // Create a shared state for source_is_local
-            let source_is_local_state = Arc::new(Mutex::new(self.source_is_local));
-            
-            // Direction button callback
-            let mut direction_button = self.direction_button.clone();
-            let source_is_local_clone = source_is_local_state.clone();
+            // The active host may have changed since the choice was shown
+            let mut panel = self.clone();
+            self.method_choice.handle(move |_, event| {
+                if event == Event::Push {
+                    panel.sync_method_choice();
+                }
+                false
+            });
             
-            direction_button.set_callback(move |b| {
-                let mut source_is_local = source_is_local_clone.lock().unwrap();
-                *source_is_local = !*source_is_local;
+            let config_method = self.config.clone();
+            self.method_choice.set_callback(move |c| {
+                let Some(choice) = ProtocolChoice::all().get(c.value().max(0) as usize).copied() else { return };
+                let mut config = config_method.lock().unwrap();
+                let Some(index) = active_host_index(&config) else { return };
                 
-                if *source_is_local {
-                    b.set_label("Local → Remote");
-                } else {
-                    b.set_label("Remote → Local");
+                choice.apply(&mut config.hosts[index].transfer);
+                log::info!("Transfers to {} now use {}", config.hosts[index].name, choice.label());
+                if let Err(e) = config.save() {
+                    log::warn!("Failed to save the transfer method: {}", e);
                 }
             });
             
+            let config_optimize = self.config.clone();
+            self.optimize_check.set_callback(move |c| {
+                let enabled = c.is_checked();
+                config_optimize.lock().unwrap().upload_optimization.enabled = enabled;
+                log::info!("Upload optimization {}", if enabled { "enabled" } else { "disabled" });
+            });
+            
+            let config_optimize = self.config.clone();
+            self.optimize_settings_button.set_callback(move |_| {
+                let current = config_optimize.lock().unwrap().upload_optimization.clone();
+                
+                let Some(size) = dialogs::input_dialog(
+                    "Optimize Uploads",
+                    "Longest side of uploaded images (pixels):",
+                    &current.max_dimension.to_string()
+                ) else { return };
+                let Some(max_dimension) = size.trim().parse::<u32>().ok().filter(|d| *d > 0) else {
+                    dialogs::message_dialog("Error", "The size must be a whole number of pixels.");
+                    return;
+                };
+                
+                let Some(quality) = dialogs::input_dialog(
+                    "Optimize Uploads",
+                    "JPEG quality (1-100):",
+                    &current.jpeg_quality.to_string()
+                ) else { return };
+                let Some(jpeg_quality) = quality.trim().parse::<u8>().ok().filter(|q| (1..=100).contains(q)) else {
+                    dialogs::message_dialog("Error", "The quality must be between 1 and 100.");
+                    return;
+                };
+                
+                let mut config = config_optimize.lock().unwrap();
+                config.upload_optimization.max_dimension = max_dimension;
+                config.upload_optimization.jpeg_quality = jpeg_quality;
+            });
+            
+            // Direction button callback
+            let source_is_local_state = self.source_is_local.clone();
+            self.direction_button.set_callback(move |b| {
+                let mut source_is_local = source_is_local_state.lock().unwrap();
+                *source_is_local = !*source_is_local;
+                b.set_label(direction_label(*source_is_local));
+            });
+            
             // Transfer button callback
             let source_input = self.source_input.clone();
             let dest_input = self.dest_input.clone();
             let config = self.config.clone();
-            let source_is_local_clone = source_is_local_state.clone();
-            
-            // Changed from Fn to FnMut
-            let callback_ref = Arc::new(Mutex::new(None::<Box<dyn FnMut(bool, PathBuf, PathBuf) + Send + Sync>>));
-            let callback_clone = callback_ref.clone();
-            
-            let mut transfer_button = self.transfer_button.clone();
-            transfer_button.set_callback(move |_| {
+            let source_is_local_state = self.source_is_local.clone();
+            let events = self.events.clone();
+            self.transfer_button.set_callback(move |button| {
                 let source_path = source_input.value();
                 let dest_path = dest_input.value();
                 
@@ -191,91 +324,314 @@ pub mod transfer_panel {
                     return;
                 }
                 
-                let source = PathBuf::from(&source_path);
-                let dest = PathBuf::from(&dest_path);
-                
                 // Get the current transfer direction from the shared state
-                let source_is_local = *source_is_local_clone.lock().unwrap();
-                println!("Transfer with source_is_local = {}", source_is_local);
+                let source_is_local = *source_is_local_state.lock().unwrap();
+                log::info!("Transfer with source_is_local = {}", source_is_local);
                 
                 // Get the currently selected host
                 let host = {
                     let config_guard = config.lock().unwrap();
-                    if config_guard.hosts.is_empty() {
-                        dialogs::message_dialog("Error", "No host configured. Please add a host first.");
-                        return;
+                    match active_host_index(&config_guard) {
+                        Some(index) => config_guard.hosts[index].clone(),
+                        None => {
+                            dialogs::message_dialog("Error", "No host configured. Please add a host first.");
+                            return;
+                        }
                     }
-                    
-                    // Use the last selected host
-                    let index = config_guard.last_used_host_index.min(config_guard.hosts.len() - 1);
-                    config_guard.hosts[index].clone()
                 };
                 
-                // Create a transfer method
-                let factory = SSHTransferFactory::new(
-                    host.hostname.clone(),
-                    host.username.clone(),
-                    host.port,
-                    host.use_key_auth,
-                    host.key_path.clone(),
+                let optimization = config.lock().unwrap().upload_optimization.clone();
+                start_transfer(
+                    host,
+                    PathBuf::from(&source_path),
+                    PathBuf::from(&dest_path),
+                    source_is_local,
+                    &optimization,
+                    button,
+                    &events
                 );
+            });
+            
+            // Pick the destination folder on the Pi or on this computer,
+            // depending on the direction
+            let panel = self.clone();
+            self.browse_button.set_callback(move |_| {
+                let mut panel = panel.clone();
+                let source = PathBuf::from(panel.source_input.value().trim());
+                let current = panel.dest_input.value().trim().to_string();
+                let source_is_local = *panel.source_is_local.lock().unwrap();
+                
+                let folder = if source_is_local {
+                    let Some(method) = panel.remote_method_for_browsing() else { return };
+                    let start = match Path::new(&current).parent() {
+                        Some(dir) if dir.is_absolute() => dir.to_path_buf(),
+                        _ => panel.default_remote_dir(),
+                    };
+                    choose_remote_directory(method, &start)
+                } else {
+                    dialogs::open_directory_dialog("Download To")
+                };
                 
-                let mut method = factory.create_method();
+                if let Some(folder) = folder {
+                    let dest = match source.file_name() {
+                        Some(name) => folder.join(name),
+                        None => folder,
+                    };
+                    panel.dest_input.set_value(&dest.to_string_lossy());
+                }
+            });
+            
+            // Pick up templates saved since the list was filled
+            let mut panel = self.clone();
+            self.template_choice.handle(move |_, event| {
+                if event == Event::Push {
+                    panel.reload_templates();
+                }
+                false
+            });
+            
+            // Fill in the selected template and run it against its own host
+            let mut panel = self.clone();
+            self.run_template_button.set_callback(move |_| {
+                let Some(template) = panel.selected_template() else { return };
                 
-                // Ask for password if needed
-                if !host.use_key_auth {
-                    if let Some(password) = dialogs::password_dialog(
-                        "SSH Password", 
-                        &format!("Enter password for {}@{}", host.username, host.hostname)
-                    ) {
-                        if let Some(method_mut) = method.as_any().downcast_mut::<crate::transfer::ssh::SSHTransfer>() {
-                            method_mut.set_password(password.clone());
-                        }
-                    } else {
-                        // User canceled password dialog
+                let host = panel.config.lock().unwrap().template_host(&template);
+                let host = match host {
+                    Ok(host) => host,
+                    Err(e) => {
+                        dialogs::error_dialog("Cannot run template", &e);
                         return;
                     }
+                };
+                
+                panel.source_input.set_value(&template.source);
+                panel.dest_input.set_value(&template.destination);
+                panel.set_direction(template.upload);
+                
+                log::info!("Running transfer template '{}'", template.name);
+                if panel.config.lock().unwrap().template_walks(&template) {
+                    let mut button = panel.transfer_button.clone();
+                    let to_trash = panel.config.lock().unwrap().remote_trash;
+                    start_mirror(host, &template, to_trash, &mut button);
+                    return;
+                }
+                let optimization = panel.config.lock().unwrap().upload_optimization.clone();
+                let mut button = panel.transfer_button.clone();
+                start_transfer(
+                    host,
+                    PathBuf::from(&template.source),
+                    PathBuf::from(&template.destination),
+                    template.upload,
+                    &optimization,
+                    &mut button,
+                    &panel.events
+                );
+            });
+            
+            // Save the current transfer under a name
+            let mut panel = self.clone();
+            self.save_template_button.set_callback(move |_| {
+                let source = panel.source_input.value().trim().to_string();
+                let destination = panel.dest_input.value().trim().to_string();
+                if source.is_empty() || destination.is_empty() {
+                    dialogs::message_dialog("Error", "Fill in the source and destination first.");
+                    return;
                 }
                 
-                // Perform the transfer 
-                println!("Transferring file:");
-                println!("  Source: {}", source.display());
-                println!("  Destination: {}", dest.display());
-                println!("  Direction: {}", if source_is_local { "Local → Remote" } else { "Remote → Local" });
+                let host = {
+                    let config = panel.config.lock().unwrap();
+                    active_host_index(&config).map(|index| config.hosts[index].clone())
+                };
+                let Some(host) = host else {
+                    dialogs::message_dialog("Error", "No host configured. Please add a host first.");
+                    return;
+                };
                 
-                let result = if source_is_local {
-                    println!("Uploading local file to remote...");
-                    method.upload_file(&source, &dest)
-                } else {
-                    println!("Downloading remote file to local...");
-                    method.download_file(&source, &dest)
+                let suggested = panel.selected_template().map(|t| t.name).unwrap_or_default();
+                let name = match dialogs::input_dialog("Save Template", "Template name:", &suggested) {
+                    Some(name) if !name.trim().is_empty() => name.trim().to_string(),
+                    _ => return,
                 };
                 
-                match result {
-                    Ok(_) => {
-                        dialogs::message_dialog("Success", "File transfer completed successfully.");
-                        
-                        // Call the callback if set
-                        if let Ok(mut callback_guard) = callback_clone.lock() {
-                            if let Some(ref mut callback) = *callback_guard {
-                                callback(source_is_local, source, dest);
-                            }
-                        }
-                    },
-                    Err(e) => {
-                        dialogs::message_dialog("Error", &format!("File transfer failed: {}", e));
+                let existing = panel.config.lock().unwrap()
+                    .transfer_templates
+                    .iter()
+                    .find(|t| t.name == name)
+                    .cloned();
+                let Some((mirror, delete, filter)) = template_settings_dialog(existing.as_ref()) else { return };
+                
+                let template = TransferTemplate {
+                    name: name.clone(),
+                    host: host.name.clone(),
+                    source,
+                    destination,
+                    upload: *panel.source_is_local.lock().unwrap(),
+                    options: Some(host.transfer.clone()),
+                    mirror,
+                    delete,
+                    include: filter.include,
+                    exclude: filter.exclude,
+                };
+                
+                {
+                    let mut config = panel.config.lock().unwrap();
+                    config.save_template(template);
+                    if let Err(e) = config.save() {
+                        dialogs::error_dialog("Failed to save settings", &e);
                     }
                 }
+                log::info!("Saved transfer template '{}'", name);
+                panel.reload_templates();
+                panel.select_template(&name);
             });
             
-            // Store callback reference for later use
-            self.callback = {
-                let mut callback_guard = callback_ref.lock().unwrap();
-                std::mem::take(&mut *callback_guard)
+            let mut panel = self.clone();
+            self.delete_template_button.set_callback(move |_| {
+                let Some(template) = panel.selected_template() else { return };
+                
+                let confirmed = dialogs::choice_dialog(
+                    "Delete Template",
+                    &trf("Delete the template '{}'?", &[&template.name]),
+                    &["Delete", "Cancel"]
+                ) == 0;
+                if !confirmed {
+                    return;
+                }
+                
+                {
+                    let mut config = panel.config.lock().unwrap();
+                    config.transfer_templates.retain(|t| t.name != template.name);
+                    if let Err(e) = config.save() {
+                        dialogs::error_dialog("Failed to save settings", &e);
+                    }
+                }
+                panel.reload_templates();
+            });
+        }
+        
+        fn set_direction(&mut self, source_is_local: bool) {
+            *self.source_is_local.lock().unwrap() = source_is_local;
+            self.direction_button.set_label(direction_label(source_is_local));
+        }
+        
+        fn selected_template(&self) -> Option<TransferTemplate> {
+            let index = self.template_choice.value();
+            if index < 0 {
+                return None;
+            }
+            self.config.lock().unwrap().transfer_templates.get(index as usize).cloned()
+        }
+        
+        fn select_template(&mut self, name: &str) {
+            let index = self.config.lock().unwrap()
+                .transfer_templates
+                .iter()
+                .position(|t| t.name == name);
+            if let Some(index) = index {
+                self.template_choice.set_value(index as i32);
+            }
+        }
+        
+        /// Fill the template dropdown from the config, keeping the selection
+        fn reload_templates(&mut self) {
+            let previous = self.selected_template().map(|t| t.name);
+            let names: Vec<String> = self.config.lock().unwrap()
+                .transfer_templates
+                .iter()
+                .map(|t| t.name.clone())
+                .collect();
+            
+            self.template_choice.clear();
+            for name in &names {
+                // Escape characters the menu would interpret
+                self.template_choice.add_choice(&name.replace('/', "\\/").replace('|', "\\|"));
+            }
+            let selected = previous
+                .and_then(|name| names.iter().position(|n| *n == name))
+                .or(if names.is_empty() { None } else { Some(0) });
+            self.template_choice.set_value(selected.map_or(-1, |index| index as i32));
+            
+            if names.is_empty() {
+                self.template_choice.deactivate();
+                self.run_template_button.deactivate();
+                self.delete_template_button.deactivate();
+            } else {
+                self.template_choice.activate();
+                self.run_template_button.activate();
+                self.delete_template_button.activate();
+            }
+        }
+        
+        /// Show the active host's protocol, or disable the choice without hosts
+        fn sync_method_choice(&mut self) {
+            let choice_index = {
+                let config = self.config.lock().unwrap();
+                active_host_index(&config).map(|index| ProtocolChoice::index_of(&config.hosts[index].transfer))
             };
             
-            // Store the reference to the shared state
-            self.source_is_local = *source_is_local_state.lock().unwrap();
+            match choice_index {
+                Some(choice_index) => {
+                    self.method_choice.set_value(choice_index as i32);
+                    self.method_choice.activate();
+                },
+                None => self.method_choice.deactivate(),
+            }
+        }
+        
+        /// Grey out rsync in the method list unless both ends have it
+        pub fn apply_capabilities(&mut self, capabilities: &Capabilities) {
+            let rsync = ProtocolChoice::all()
+                .iter()
+                .position(|choice| matches!(choice, ProtocolChoice::Builtin(TransferProtocol::Rsync)));
+            let Some(mut item) = rsync.and_then(|index| self.method_choice.at(index as i32)) else { return };
+            
+            if capabilities.has_rsync() {
+                item.activate();
+                self.method_choice.set_tooltip("How files are copied to and from the active host");
+            } else {
+                item.deactivate();
+                self.method_choice.set_tooltip("How files are copied to and from the active host (rsync must be installed here and on the Pi)");
+            }
+        }
+        
+        /// Use `method` (the remote pane's connection) when browsing the Pi;
+        /// None when disconnected
+        pub fn set_remote_method(&mut self, method: Option<Box<dyn TransferMethod>>) {
+            *self.remote_method.lock().unwrap() = method;
+        }
+        
+        /// The remote pane's connection, or a new one to the active host
+        fn remote_method_for_browsing(&self) -> Option<Box<dyn TransferMethod>> {
+            if let Some(method) = self.remote_method.lock().unwrap().as_ref() {
+                return Some(method.clone_box());
+            }
+            
+            let host = {
+                let config = self.config.lock().unwrap();
+                active_host_index(&config).map(|index| config.hosts[index].clone())
+            };
+            let Some(host) = host else {
+                dialogs::message_dialog("Error", "No host configured. Please add a host first.");
+                return None;
+            };
+            
+            let mut method = factory_for_reachable_host(&host).create_method();
+            if !host.use_key_auth {
+                let password = dialogs::password_dialog(
+                    "SSH Password",
+                    &format!("Enter password for {}@{}", host.username, host.hostname)
+                )?;
+                method.set_password(&password);
+            }
+            Some(method)
+        }
+        
+        /// Where uploads go by default: the active host's start directory
+        fn default_remote_dir(&self) -> PathBuf {
+            let config = self.config.lock().unwrap();
+            active_host_index(&config)
+                .map(|index| config.hosts[index].start_directory())
+                .unwrap_or_else(|| PathBuf::from("."))
         }
         
         pub fn set_source_path(&mut self, path: PathBuf, is_local: bool) {
@@ -283,14 +639,8 @@ pub mod transfer_panel {
             self.source_input.set_value(&path.to_string_lossy());
             
             // Update direction if needed
-            if self.source_is_local != is_local {
-                self.source_is_local = is_local;
-                
-                if is_local {
-                    self.direction_button.set_label("Local → Remote");
-                } else {
-                    self.direction_button.set_label("Remote → Local");
-                }
+            if *self.source_is_local.lock().unwrap() != is_local {
+                self.set_direction(is_local);
             }
             
             // Generate a reasonable destination path
@@ -299,26 +649,254 @@ pub mod transfer_panel {
                 .unwrap_or("file");
                 
             let dest_path = if is_local {
-                // Local to remote, use remote home directory
-                format!("/home/{}/{}", 
-                    self.config.lock().unwrap().hosts[0].username,
-                    filename
-                )
+                // Local to remote, into the active host's start directory
+                self.default_remote_dir().join(filename)
             } else {
                 // Remote to local, use local downloads directory
-                let local_dir = dirs::download_dir()
-                    .unwrap_or_else(|| PathBuf::from("."));
-                format!("{}/{}", local_dir.to_string_lossy(), filename)
+                dirs::download_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(filename)
             };
             
-            self.dest_input.set_value(&dest_path);
+            self.dest_input.set_value(&dest_path.to_string_lossy());
+        }
+    }
+    
+    fn direction_label(source_is_local: bool) -> &'static str {
+        if source_is_local {
+            "Local → Remote"
+        } else {
+            "Remote → Local"
+        }
+    }
+    
+    // Copy `source` to `dest` through `host` on a worker thread, asking for
+    // the password first when the host needs one
+    fn start_transfer(
+        host: Host,
+        source: PathBuf,
+        dest: PathBuf,
+        source_is_local: bool,
+        optimization: &UploadOptimization,
+        button: &mut Button,
+        events: &EventBus
+    ) {
+        // Create a transfer method
+        let factory = factory_for_reachable_host(&host);
+        
+        let mut method = factory.create_method();
+        
+        // Ask for password if needed
+        if !host.use_key_auth {
+            if let Some(password) = dialogs::password_dialog(
+                "SSH Password", 
+                &format!("Enter password for {}@{}", host.username, host.hostname)
+            ) {
+                method.set_password(&password);
+            } else {
+                // User canceled password dialog
+                return;
+            }
         }
         
-        pub fn set_callback<F>(&mut self, callback: F)
-        where
-            F: FnMut(bool, PathBuf, PathBuf) + 'static + Send + Sync,
-        {
-            self.callback = Some(Box::new(callback));
+        // Perform the transfer 
+        log::info!("Transferring file:");
+        log::info!("  Source: {}", source.display());
+        log::info!("  Destination: {}", dest.display());
+        log::info!("  Direction: {}", direction_label(source_is_local));
+        
+        // Copy on a worker thread so the window stays responsive
+        button.deactivate();
+        button.set_label(&tr("Transferring..."));
+        
+        let mut button = button.clone();
+        let events = events.clone();
+        let optimization = optimization.clone();
+        run_in_background(
+            move || {
+                let result = if source_is_local {
+                    // Send a smaller copy of images when asked to
+                    let optimized = if optimization.enabled && is_image_file(&source) {
+                        optimized_copy(&source, optimization.max_dimension, optimization.jpeg_quality)
+                            .unwrap_or_else(|e| {
+                                log::warn!("Uploading the original, optimizing failed: {}", e);
+                                None
+                            })
+                    } else {
+                        None
+                    };
+                    
+                    log::info!("Uploading local file to remote...");
+                    let result = method.upload_file(optimized.as_deref().unwrap_or(&source), &dest);
+                    if let Some(copy) = optimized {
+                        let _ = std::fs::remove_file(copy);
+                    }
+                    result
+                } else {
+                    log::info!("Downloading remote file to local...");
+                    method.download_file(&source, &dest)
+                };
+                result.map(|_| (source, dest))
+            },
+            move |result| {
+                button.activate();
+                button.set_label(&tr("Transfer"));
+                
+                match result {
+                    Ok((source, dest)) => {
+                        // The main window announces finished transfers
+                        events.publish(AppEvent::TransferFinished {
+                            source,
+                            destination: dest,
+                            upload: source_is_local,
+                        });
+                    },
+                    Err(e) => {
+                        dialogs::error_dialog("File transfer failed", &e.into());
+                    }
+                }
+            }
+        );
+    }
+    
+    // Ask whether a template copies or mirrors, for a mirror's deletion
+    // setting and for the template's filters; None when cancelled
+    fn template_settings_dialog(existing: Option<&TransferTemplate>) -> Option<(bool, bool, PathFilter)> {
+        let (mirror, delete) = match dialogs::choice_dialog(
+            "Save Template",
+            "Run this template as:",
+            &["Copy", "Mirror folder", "Cancel"]
+        ) {
+            0 => (false, false),
+            1 => match dialogs::choice_dialog(
+                "Save Template",
+                "Remove destination files the source no longer has?\nYou review the list before anything is removed.",
+                &["Keep Them", "Remove", "Cancel"]
+            ) {
+                0 => (true, false),
+                1 => (true, true),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        
+        let include = dialogs::input_dialog(
+            "Save Template",
+            "Only transfer (comma-separated globs, e.g. *.jpg; empty for all):",
+            &existing.map(|t| t.include.join(", ")).unwrap_or_default()
+        )?;
+        let exclude = dialogs::input_dialog(
+            "Save Template",
+            "Skip (comma-separated globs, e.g. *.tmp, lost+found/):",
+            &existing.map(|t| t.exclude.join(", ")).unwrap_or_default()
+        )?;
+        Some((mirror, delete, PathFilter { include: parse_patterns(&include), exclude: parse_patterns(&exclude) }))
+    }
+    
+    // Mirror a template's source folder into its destination: compare both
+    // sides, review deletions, then copy what is new or changed. Filtered
+    // copies come here too and never delete.
+    fn start_mirror(host: Host, template: &TransferTemplate, to_trash: bool, button: &mut Button) {
+        let mut method = factory_for_reachable_host(&host).create_method();
+        if !host.use_key_auth {
+            match dialogs::password_dialog(
+                "SSH Password",
+                &format!("Enter password for {}@{}", host.username, host.hostname)
+            ) {
+                Some(password) => method.set_password(&password),
+                None => return,
+            }
+        }
+        
+        button.deactivate();
+        button.set_label(&tr("Comparing..."));
+        
+        let mirror = Mirror::from_template(template);
+        let delete = template.mirror && template.delete;
+        let mut button = button.clone();
+        run_in_background(
+            move || {
+                let plan = mirror.plan(method.as_ref());
+                (plan, mirror, method)
+            },
+            move |(plan, mirror, method): (Result<MirrorPlan, _>, Mirror, Box<dyn TransferMethod>)| {
+                let plan = match plan {
+                    Ok(plan) => plan,
+                    Err(e) => {
+                        button.activate();
+                        button.set_label(&tr("Transfer"));
+                        dialogs::error_dialog("Comparing the folders failed", &e.into());
+                        return;
+                    }
+                };
+                
+                let deletions = if delete && !plan.deletions.is_empty() {
+                    let names: Vec<String> = plan.deletions.iter().map(|path| path.display().to_string()).collect();
+                    let chosen = dialogs::multi_select_dialog(
+                        "Review Deletions",
+                        &format!(
+                            "{} files in {} are not in the source. Selected files will be removed:",
+                            names.len(),
+                            mirror.target.display()
+                        ),
+                        &names
+                    );
+                    let Some(chosen) = chosen else {
+                        button.activate();
+                        button.set_label(&tr("Transfer"));
+                        return;
+                    };
+                    chosen.into_iter().map(|index| plan.deletions[index].clone()).collect()
+                } else {
+                    Vec::new()
+                };
+                
+                if plan.copies.is_empty() && deletions.is_empty() {
+                    button.activate();
+                    button.set_label(&tr("Transfer"));
+                    dialogs::message_dialog(
+                        "Mirror",
+                        &trf("{} is up to date ({} files).", &[&mirror.target.display(), &plan.unchanged])
+                    );
+                    return;
+                }
+                
+                button.set_label(&tr("Mirroring..."));
+                run_in_background(
+                    move || {
+                        let copied = mirror.copy(method.as_ref(), &plan.copies, &|_| {}, &AtomicBool::new(false))?;
+                        let removed = mirror.delete(method.as_ref(), &deletions, to_trash)?;
+                        Ok::<_, TransferError>((copied, removed, plan.unchanged, mirror))
+                    },
+                    move |result| {
+                        button.activate();
+                        button.set_label(&tr("Transfer"));
+                        match result {
+                            Ok((copied, removed, unchanged, mirror)) => {
+                                log::info!(
+                                    "Mirrored {} to {}: {} copied, {} unchanged, {} removed",
+                                    mirror.source.display(), mirror.target.display(), copied, unchanged, removed
+                                );
+                                notifications::notify(
+                                    ToastKind::Success,
+                                    "Mirror finished",
+                                    &trf("{}: {} copied, {} unchanged, {} removed", &[&mirror.target.display(), &copied, &unchanged, &removed])
+                                );
+                            },
+                            Err(e) => dialogs::error_dialog("Mirroring failed", &e.into()),
+                        }
+                    }
+                );
+            }
+        );
+    }
+    
+    // Host that transfers go to: the one connected to last
+    fn active_host_index(config: &Config) -> Option<usize> {
+        if config.hosts.is_empty() {
+            None
+        } else {
+            Some(config.last_used_host_index.min(config.hosts.len() - 1))
         }
     }
 }
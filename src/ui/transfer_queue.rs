@@ -0,0 +1,155 @@
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::core::utils::AppError;
+use crate::transfer::method::{with_retries, TransferError, TransferMethod};
+use crate::ui::dialogs::dialogs;
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::executor::run_in_background;
+
+/// One file copy waiting to run
+pub struct QueuedTransfer {
+    /// Connection to the Pi the file is copied to or from
+    pub method: Box<dyn TransferMethod>,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// True for local to Pi, false for Pi to local
+    pub upload: bool,
+}
+
+/// Uploads and downloads run in the background in the order queued
+///
+/// Copies run one at a time unless the method of the next one allows more
+/// at once (a host's transfer concurrency). Each finished copy is announced
+/// with `AppEvent::TransferFinished` so the pane it wrote into refreshes.
+/// Copies that fail for a retryable reason are tried again as the host's
+/// retry policy says; other failures are reported and the queue moves on.
+/// Downloads the Pi refused can be queued again with sudo when the host
+/// allows it.
+#[derive(Clone)]
+pub struct TransferQueue {
+    pending: Arc<Mutex<VecDeque<QueuedTransfer>>>,
+    /// Copies running now
+    running: Arc<Mutex<usize>>,
+    events: EventBus,
+}
+
+impl TransferQueue {
+    pub fn new(events: EventBus) -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(VecDeque::new())),
+            running: Arc::new(Mutex::new(0)),
+            events,
+        }
+    }
+
+    /// Add a copy to the end of the queue, starting it if there is room
+    pub fn enqueue(&self, transfer: QueuedTransfer) {
+        log::info!(
+            "Queued {}: {} -> {}",
+            if transfer.upload { "upload" } else { "download" },
+            transfer.source.display(),
+            transfer.destination.display()
+        );
+        self.pending.lock().unwrap().push_back(transfer);
+        self.start_ready();
+    }
+
+    /// Copies waiting behind the ones running
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Whether a copy is running now
+    pub fn is_busy(&self) -> bool {
+        *self.running.lock().unwrap() > 0
+    }
+
+    /// Start queued copies while the next one's method allows another to run
+    fn start_ready(&self) {
+        loop {
+            let next = {
+                let mut pending = self.pending.lock().unwrap();
+                let mut running = self.running.lock().unwrap();
+                match pending.front() {
+                    Some(transfer) if *running < transfer.method.max_concurrent() => {
+                        *running += 1;
+                        pending.pop_front()
+                    },
+                    _ => None,
+                }
+            };
+            match next {
+                Some(transfer) => self.start(transfer),
+                None => break,
+            }
+        }
+
+        if !self.is_busy() && self.pending_count() == 0 {
+            self.events.publish(AppEvent::TransferQueueChanged { running: None, queued: 0 });
+        }
+    }
+
+    fn start(&self, transfer: QueuedTransfer) {
+        self.events.publish(AppEvent::TransferQueueChanged {
+            running: Some(transfer.source.clone()),
+            queued: self.pending_count(),
+        });
+
+        let queue = self.clone();
+        let QueuedTransfer { method, source, destination, upload } = transfer;
+        let sudo_method = if upload { None } else { method.sudo_method() };
+        run_in_background(
+            move || {
+                // Only the successful try counts towards the speed
+                let mut started = Instant::now();
+                let what = format!("Copying {}", source.display());
+                let result = with_retries(&method.retry_policy(), &what, &AtomicBool::new(false), |_| {
+                    started = Instant::now();
+                    if upload {
+                        method.upload_file(&source, &destination)
+                    } else {
+                        method.download_file(&source, &destination)
+                    }
+                });
+                let seconds = started.elapsed().as_secs_f64();
+                match result {
+                    Ok(()) => Ok((source, destination, seconds)),
+                    Err(e) => Err((source, destination, e)),
+                }
+            },
+            move |result| {
+                *queue.running.lock().unwrap() -= 1;
+                match result {
+                    Ok((source, destination, seconds)) => {
+                        // Whichever end is local has the finished file
+                        let local = if upload { &source } else { &destination };
+                        if let Ok(metadata) = std::fs::metadata(local) {
+                            queue.events.publish(AppEvent::TransferMeasured { bytes: metadata.len(), seconds });
+                        }
+                        queue.events.publish(AppEvent::TransferFinished { source, destination, upload });
+                    },
+                    Err((source, destination, TransferError::PermissionDenied(reason))) if sudo_method.is_some() => {
+                        log::error!("Download of {} refused: {}", source.display(), reason);
+                        let message = format!("The Pi refused to read {}. Download it with sudo?", source.display());
+                        match sudo_method {
+                            Some(method) if dialogs::choice_dialog("Permission Denied", &message, &["Cancel", "Use sudo"]) == 1 => {
+                                queue.pending.lock().unwrap().push_front(QueuedTransfer { method, source, destination, upload });
+                            },
+                            _ => queue.events.publish(AppEvent::TransferFailed { source, upload }),
+                        }
+                    },
+                    Err((source, _, e)) => {
+                        queue.events.publish(AppEvent::TransferFailed { source, upload });
+                        let action = if upload { "Upload failed" } else { "Download failed" };
+                        dialogs::error_dialog(action, &AppError::from(e));
+                    }
+                }
+                queue.start_ready();
+            }
+        );
+    }
+}
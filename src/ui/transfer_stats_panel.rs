@@ -0,0 +1,230 @@
+use fltk::{
+    button::Button,
+    draw::{self, LineStyle},
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::Group,
+    prelude::*,
+};
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::ui::events::{AppEvent, EventBus};
+use crate::ui::status_bar::{format_bytes, format_speed};
+
+/// Samples kept for the graph; older ones are dropped
+const MAX_SAMPLES: usize = 500;
+
+/// Speed of one finished copy
+#[derive(Debug, Clone, Copy)]
+struct SpeedSample {
+    /// Seconds since the statistics were started or reset
+    at: f64,
+    bytes_per_second: f64,
+}
+
+/// Throughput of the transfer queue since the application started or the
+/// statistics were reset
+#[derive(Debug)]
+struct TransferStats {
+    started: Instant,
+    samples: Vec<SpeedSample>,
+    transfers: usize,
+    failures: usize,
+    total_bytes: u64,
+    /// Time spent copying, which excludes the gaps between copies
+    total_seconds: f64,
+    fastest: f64,
+    slowest: Option<f64>,
+}
+
+impl TransferStats {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            samples: Vec::new(),
+            transfers: 0,
+            failures: 0,
+            total_bytes: 0,
+            total_seconds: 0.0,
+            fastest: 0.0,
+            slowest: None,
+        }
+    }
+
+    fn record(&mut self, bytes: u64, seconds: f64) {
+        self.transfers += 1;
+        self.total_bytes += bytes;
+        self.total_seconds += seconds;
+        if seconds <= 0.0 {
+            return;
+        }
+
+        let speed = bytes as f64 / seconds;
+        self.fastest = self.fastest.max(speed);
+        self.slowest = Some(self.slowest.map_or(speed, |slowest| slowest.min(speed)));
+        self.samples.push(SpeedSample { at: self.started.elapsed().as_secs_f64(), bytes_per_second: speed });
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.remove(0);
+        }
+    }
+
+    fn summary(&self) -> String {
+        let average = if self.total_seconds > 0.0 {
+            format_speed(self.total_bytes, self.total_seconds)
+        } else {
+            "-".to_string()
+        };
+        let range = match self.slowest {
+            Some(slowest) => format!(
+                "slowest {}, fastest {}",
+                format_speed(slowest as u64, 1.0),
+                format_speed(self.fastest as u64, 1.0)
+            ),
+            None => "no speeds measured yet".to_string(),
+        };
+        format!(
+            "{} copies, {} failed, {} moved, average {} ({})",
+            self.transfers,
+            self.failures,
+            format_bytes(self.total_bytes),
+            average,
+            range
+        )
+    }
+}
+
+/// Graph of the speed of each queued copy plus totals for the session,
+/// for telling a slow Wi-Fi link from a slow Pi
+///
+/// Everything shown arrives through the event bus.
+#[derive(Clone)]
+pub struct TransferStatsPanel {
+    summary_frame: Frame,
+    graph: Frame,
+    reset_button: Button,
+    stats: Arc<Mutex<TransferStats>>,
+}
+
+impl TransferStatsPanel {
+    pub fn new(x: i32, y: i32, w: i32, h: i32) -> Self {
+        let mut group = Group::new(x, y, w, h, None);
+        group.set_frame(FrameType::EngravedBox);
+
+        let padding = 10;
+        let row_height = 25;
+        let button_width = 110;
+
+        let mut summary_frame = Frame::new(x + padding, y + padding, w - button_width - padding * 3, row_height, None);
+        summary_frame.set_align(Align::Left | Align::Inside | Align::Clip);
+
+        let mut reset_button = Button::new(x + w - padding - button_width, y + padding, button_width, row_height, "Reset");
+        reset_button.set_tooltip("Start the statistics afresh");
+
+        let graph_y = y + padding * 2 + row_height;
+        let mut graph = Frame::new(x + padding, graph_y, w - padding * 2, h - (graph_y - y) - padding, None);
+        graph.set_frame(FrameType::DownBox);
+        graph.set_color(Color::White);
+
+        group.resizable(&graph);
+        group.end();
+
+        let stats = Arc::new(Mutex::new(TransferStats::new()));
+        let stats_draw = stats.clone();
+        graph.draw(move |frame| draw_graph(frame, &stats_draw.lock().unwrap()));
+
+        let mut panel = TransferStatsPanel { summary_frame, graph, reset_button, stats };
+        panel.update();
+
+        let mut panel_reset = panel.clone();
+        panel.reset_button.set_callback(move |_| {
+            *panel_reset.stats.lock().unwrap() = TransferStats::new();
+            panel_reset.update();
+        });
+
+        panel
+    }
+
+    /// Record copies from now on
+    pub fn subscribe(&self, events: &EventBus) {
+        let mut panel = self.clone();
+        events.subscribe(move |event| match event {
+            AppEvent::TransferMeasured { bytes, seconds } => {
+                panel.stats.lock().unwrap().record(*bytes, *seconds);
+                panel.update();
+            },
+            AppEvent::TransferFailed { .. } => {
+                panel.stats.lock().unwrap().failures += 1;
+                panel.update();
+            },
+            _ => {},
+        });
+    }
+
+    fn update(&mut self) {
+        let summary = self.stats.lock().unwrap().summary();
+        self.summary_frame.set_label(&summary);
+        self.graph.redraw();
+    }
+}
+
+/// Plot each copy's speed against the time it finished, with the average
+/// as a dashed line
+fn draw_graph(frame: &mut Frame, stats: &TransferStats) {
+    let (x, y, w, h) = (frame.x() + 50, frame.y() + 10, frame.w() - 60, frame.h() - 30);
+    if w <= 0 || h <= 0 {
+        return;
+    }
+
+    draw::set_font(Font::Helvetica, 11);
+    if stats.samples.is_empty() {
+        draw::set_draw_color(Color::Dark3);
+        draw::draw_text2("Speeds of queued copies appear here", frame.x(), frame.y(), frame.w(), frame.h(), Align::Center);
+        return;
+    }
+
+    // Leave headroom above the fastest copy
+    let top = stats.fastest * 1.1;
+    let first = stats.samples[0].at;
+    let span = (stats.samples[stats.samples.len() - 1].at - first).max(1.0);
+    let point = |sample: &SpeedSample| {
+        let px = x + ((sample.at - first) / span * w as f64) as i32;
+        let py = y + h - (sample.bytes_per_second / top * h as f64) as i32;
+        (px, py)
+    };
+
+    draw::push_clip(frame.x(), frame.y(), frame.w(), frame.h());
+
+    // Axes with the top speed and the time covered
+    draw::set_draw_color(Color::Dark3);
+    draw::draw_line(x, y, x, y + h);
+    draw::draw_line(x, y + h, x + w, y + h);
+    draw::draw_text2(&format_speed(top as u64, 1.0), frame.x() + 2, y - 6, 46, 12, Align::Right);
+    draw::draw_text2("0", frame.x() + 2, y + h - 6, 46, 12, Align::Right);
+    draw::draw_text2(&format!("{:.0} s", span), x + w - 60, y + h + 4, 60, 12, Align::Right);
+
+    if stats.total_seconds > 0.0 {
+        let average = stats.total_bytes as f64 / stats.total_seconds;
+        let ay = y + h - (average / top * h as f64) as i32;
+        draw::set_draw_color(Color::from_rgb(200, 120, 0));
+        draw::set_line_style(LineStyle::Dash, 1);
+        draw::draw_line(x, ay, x + w, ay);
+        draw::set_line_style(LineStyle::Solid, 0);
+    }
+
+    draw::set_draw_color(Color::from_rgb(0, 120, 255));
+    draw::set_line_style(LineStyle::Solid, 2);
+    for pair in stats.samples.windows(2) {
+        let (x1, y1) = point(&pair[0]);
+        let (x2, y2) = point(&pair[1]);
+        draw::draw_line(x1, y1, x2, y2);
+    }
+    for sample in &stats.samples {
+        let (px, py) = point(sample);
+        draw::draw_rectf(px - 2, py - 2, 5, 5);
+    }
+    draw::set_line_style(LineStyle::Solid, 0);
+
+    draw::pop_clip();
+}
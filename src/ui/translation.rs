@@ -0,0 +1,73 @@
+use fltk::{
+    group::Group,
+    menu::{Choice, MenuBar},
+    prelude::*,
+    window::Window,
+};
+
+use crate::core::locale::{retranslate, Language};
+
+/// Translate the labels, tooltips and menu items of `root` and everything
+/// inside it, which are currently shown in `from`, to the current language
+///
+/// Widgets built from English literals are translated with `from` set to
+/// English; after switching languages every open window is translated
+/// from the previous one.
+pub fn translate_widgets<W: WidgetExt>(root: &mut W, from: Language) {
+    translate_widget(root, from);
+
+    if let Some(mut menu) = MenuBar::from_dyn_widget(&*root) {
+        translate_menu(&mut menu, from);
+    } else if let Some(mut menu) = Choice::from_dyn_widget(&*root) {
+        translate_menu(&mut menu, from);
+    }
+
+    if let Some(group) = Group::from_dyn_widget(&*root) {
+        for i in 0..group.children() {
+            if let Some(mut child) = group.child(i) {
+                translate_widgets(&mut child, from);
+            }
+        }
+    }
+}
+
+/// Translate every open window, e.g. after the language was switched
+pub fn translate_windows(from: Language) {
+    for mut window in fltk::app::windows().unwrap_or_default() {
+        translate_widgets(&mut window, from);
+    }
+}
+
+fn translate_widget<W: WidgetExt>(widget: &mut W, from: Language) {
+    let label = widget.label();
+    if !label.is_empty() {
+        let translated = retranslate(&label, from);
+        if translated != label {
+            widget.set_label(&translated);
+        }
+    }
+
+    let tooltip = widget.tooltip().unwrap_or_default();
+    if !tooltip.is_empty() {
+        let translated = retranslate(&tooltip, from);
+        if translated != tooltip {
+            widget.set_tooltip(&translated);
+        }
+    }
+}
+
+fn translate_menu<M: MenuExt>(menu: &mut M, from: Language) {
+    for i in 0..menu.size() {
+        let Some(mut item) = menu.at(i) else { continue };
+        let Some(label) = item.label() else { continue };
+        let translated = retranslate(&label, from);
+        if translated != label {
+            item.set_label(&translated);
+        }
+    }
+}
+
+/// Translate a dialog window built from English literals before it is shown
+pub fn translate_window(window: &mut Window) {
+    translate_widgets(window, Language::English);
+}